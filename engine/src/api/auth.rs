@@ -1,39 +1,196 @@
-use actix_web::{error::ErrorUnauthorized, http::StatusCode, web, Error, Responder};
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{LazyLock, Mutex},
+};
+
+use actix_web::{
+    error::ErrorUnauthorized,
+    http::{header, Method, StatusCode},
+    web, Error, Responder,
+};
 use argon2::{password_hash::PasswordHash, Argon2, PasswordVerifier};
-use chrono::{TimeDelta, Utc};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
 use jsonwebtoken::{self, DecodingKey, EncodingKey, Header, Validation};
 use log::*;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use rand::{distr::Alphanumeric, Rng};
 use serde::{Deserialize, Serialize};
-use sqlx::{Pool, Sqlite};
+use sha2::{Digest, Sha256};
 
 use crate::{
-    db::{handles, models::Role, models::User, GLOBAL_SETTINGS},
+    db::{
+        handles,
+        models::{AuthAudit, ChannelPermission, Role, Session, TokenScope, User},
+        DbPool, GLOBAL_SETTINGS,
+    },
     utils::errors::ServiceError,
 };
 
-// Token lifetime
-const ACCESS_LIFETIME: i64 = 3;
-const REFRESH_LIFETIME: i64 = 30;
+// Token lifetime, in seconds
+const ACCESS_LIFETIME: i64 = 15 * 60;
+const REFRESH_LIFETIME: i64 = 30 * 86400;
+
+// Failed logins in the window lock an account or an address, times in seconds
+const LOGIN_WINDOW: i64 = 15 * 60;
+const LOGIN_LOCK: i64 = 15 * 60;
+const ACCOUNT_FAILURES: usize = 5;
+const IP_FAILURES: usize = 20;
+
+// Days to keep the audit of logins
+const AUTH_AUDIT_DAYS: i64 = 90;
+
+// Signed media URL lifetime, in seconds
+pub const MEDIA_URL_LIFETIME: i64 = 3600;
+pub const MEDIA_URL_MAX_LIFETIME: i64 = 7 * 86400;
+
+/// Characters which are escaped in the path of signed media URLs.
+const PATH_ESCAPE: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'&')
+    .add(b'+')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Beginning of API tokens, to tell them apart from a JWT.
+pub const API_TOKEN_PREFIX: &str = "ffp_";
+
+type HmacSha256 = Hmac<Sha256>;
+
+static LOGIN_LIMITER: LazyLock<LoginLimiter> = LazyLock::new(LoginLimiter::default);
+
+#[derive(Debug, Default)]
+struct LoginAttempts {
+    failures: Vec<i64>,
+    locked_until: i64,
+}
+
+/// Count failed logins per account and per address, in memory.
+#[derive(Debug, Default)]
+struct LoginLimiter {
+    attempts: Mutex<HashMap<String, LoginAttempts>>,
+}
+
+impl LoginLimiter {
+    /// Seconds until the key is unlocked, `None` when it is not locked.
+    fn locked(&self, key: &str, now: i64) -> Option<i64> {
+        let attempts = self.attempts.lock().unwrap();
+
+        attempts
+            .get(key)
+            .map(|a| a.locked_until - now)
+            .filter(|wait| *wait > 0)
+    }
+
+    /// Count a failed login, gives `true` when the key is locked by it.
+    fn failure(&self, key: &str, limit: usize, now: i64) -> bool {
+        let mut attempts = self.attempts.lock().unwrap();
+
+        attempts.retain(|_, a| {
+            a.locked_until > now || a.failures.last().is_some_and(|t| now - t < LOGIN_WINDOW)
+        });
+
+        let entry = attempts.entry(key.to_string()).or_default();
+        entry.failures.retain(|t| now - t < LOGIN_WINDOW);
+        entry.failures.push(now);
+
+        if entry.failures.len() >= limit {
+            entry.failures.clear();
+            entry.locked_until = now + LOGIN_LOCK;
+
+            return true;
+        }
+
+        false
+    }
+
+    fn success(&self, key: &str) {
+        self.attempts.lock().unwrap().remove(key);
+    }
+}
+
+fn limiter_keys(username: &str, ip: &str) -> [(String, usize); 2] {
+    [
+        (
+            format!("user:{}", username.to_lowercase()),
+            ACCOUNT_FAILURES,
+        ),
+        (format!("ip:{ip}"), IP_FAILURES),
+    ]
+}
+
+async fn audit_login(pool: &DbPool, username: &str, ip: &str, event: &str, now: i64) {
+    let entry = AuthAudit {
+        username: username.to_string(),
+        ip: ip.to_string(),
+        event: event.to_string(),
+        timestamp: now,
+        ..Default::default()
+    };
+
+    if let Err(e) = handles::insert_auth_audit(pool, &entry).await {
+        error!("Save login audit failed: {e}");
+    }
+}
+
+/// Count the failed login, lock the account or the address when there are too many.
+async fn login_failed(pool: &DbPool, username: &str, ip: &str, now: i64) {
+    audit_login(pool, username, ip, "failure", now).await;
+
+    for (key, limit) in limiter_keys(username, ip) {
+        if LOGIN_LIMITER.failure(&key, limit, now) {
+            warn!("Too many failed logins, lock <yellow>{key}</> for {LOGIN_LOCK} seconds");
+
+            audit_login(pool, username, ip, "lockout", now).await;
+        }
+    }
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub struct Claims {
     pub id: i32,
     pub channels: Vec<i32>,
+    /// Permissions per channel, only for the role `user`.
+    #[serde(default)]
+    pub permissions: HashMap<i32, ChannelPermission>,
     pub username: String,
     pub role: Role,
     exp: i64,
+    /// Random ID of a refresh token, so that every session has its own token.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    jti: String,
 }
 
 impl Claims {
     pub fn new(user: User, role: Role, lifetime: i64) -> Self {
+        let permissions = match role {
+            Role::User => user.permissions.unwrap_or_default(),
+            _ => HashMap::new(),
+        };
+
         Self {
             id: user.id,
             channels: user.channel_ids.unwrap_or_default(),
+            permissions,
             username: user.username,
             role,
-            exp: (Utc::now() + TimeDelta::try_days(lifetime).unwrap()).timestamp(),
+            exp: Utc::now().timestamp() + lifetime,
+            jti: String::new(),
         }
     }
+
+    /// Refresh tokens carry a session ID, they are not valid for the API.
+    pub fn is_refresh(&self) -> bool {
+        !self.jti.is_empty()
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -47,6 +204,169 @@ pub struct TokenRefreshRequest {
     pub refresh: String,
 }
 
+/// Address and browser of a login, to recognize the session in the list.
+#[derive(Clone, Debug, Default)]
+pub struct LoginClient {
+    pub ip: String,
+    pub user_agent: String,
+}
+
+/// Address of the client. The `X-Forwarded-For` header counts only when the peer is a trusted proxy,
+/// then the last address in it, which is not a trusted proxy, is the client.
+pub fn client_ip(peer: Option<IpAddr>, forwarded_for: &str, trusted: &[IpAddr]) -> String {
+    let Some(peer) = peer else {
+        return String::new();
+    };
+
+    if !trusted.contains(&peer) {
+        return peer.to_string();
+    }
+
+    forwarded_for
+        .rsplit(',')
+        .map_while(|addr| addr.trim().parse::<IpAddr>().ok())
+        .find(|addr| !trusted.contains(addr))
+        .unwrap_or(peer)
+        .to_string()
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MediaSignature {
+    pub expires: i64,
+    pub signature: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SignedUrl {
+    pub url: String,
+    pub expires: i64,
+}
+
+fn media_mac(secret: &str, channel_id: i32, path: &str, expires: i64) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+    mac.update(format!("{channel_id}:{}:{expires}", path.trim_start_matches('/')).as_bytes());
+
+    mac
+}
+
+fn media_signature(secret: &str, channel_id: i32, path: &str, expires: i64) -> String {
+    media_mac(secret, channel_id, path, expires)
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Create an expiring URL for a media file, which can be fetched without a JWT.
+pub fn sign_media_url(channel_id: i32, path: &str, lifetime: i64) -> SignedUrl {
+    let config = GLOBAL_SETTINGS.get().unwrap();
+    let expires = Utc::now().timestamp() + lifetime.clamp(1, MEDIA_URL_MAX_LIFETIME);
+    let signature = media_signature(
+        config.secret.as_deref().unwrap_or_default(),
+        channel_id,
+        path,
+        expires,
+    );
+    let path = utf8_percent_encode(path.trim_start_matches('/'), PATH_ESCAPE);
+
+    SignedUrl {
+        url: format!("/file/{channel_id}/{path}?expires={expires}&signature={signature}"),
+        expires,
+    }
+}
+
+/// File path of a signed URL, as it was signed. The router keeps characters like `+`, `%` and `/` encoded.
+pub fn media_path(matched: &str) -> String {
+    percent_decode_str(matched).decode_utf8_lossy().to_string()
+}
+
+fn check_media_signature(
+    secret: &str,
+    channel_id: i32,
+    path: &str,
+    sign: &MediaSignature,
+    now: i64,
+) -> bool {
+    let Some(signature) = (0..sign.signature.len())
+        .step_by(2)
+        .map(|i| {
+            sign.signature
+                .get(i..i + 2)
+                .and_then(|b| u8::from_str_radix(b, 16).ok())
+        })
+        .collect::<Option<Vec<u8>>>()
+    else {
+        return false;
+    };
+
+    sign.expires >= now
+        && media_mac(secret, channel_id, path, sign.expires)
+            .verify_slice(&signature)
+            .is_ok()
+}
+
+/// Validate the signature and expiration time of a media URL.
+pub fn verify_media_url(channel_id: i32, path: &str, sign: &MediaSignature) -> bool {
+    let config = GLOBAL_SETTINGS.get().unwrap();
+
+    check_media_signature(
+        config.secret.as_deref().unwrap_or_default(),
+        channel_id,
+        path,
+        sign,
+        Utc::now().timestamp(),
+    )
+}
+
+fn random_key(length: usize) -> String {
+    rand::rng()
+        .sample_iter(&Alphanumeric)
+        .take(length)
+        .map(char::from)
+        .collect()
+}
+
+/// Create a new random API token.
+pub fn generate_api_token() -> String {
+    format!("{API_TOKEN_PREFIX}{}", random_key(40))
+}
+
+/// Hash of an API or refresh token, as it is stored in the database.
+pub fn token_hash(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Check if the scope of an API token allows the request.
+///
+/// Reading is allowed with every scope, the file browser is read with POST.
+/// The other scopes can change playlists or files in addition.
+pub fn api_token_allows(scope: &TokenScope, method: &Method, path: &str) -> bool {
+    let path = path.trim_start_matches("/api");
+    let read = method == Method::GET
+        || method == Method::HEAD
+        || (method == Method::POST && path.starts_with("/file/") && path.ends_with("/browse/"));
+
+    read || match scope {
+        TokenScope::ReadOnly => false,
+        TokenScope::PlaylistEdit => path.starts_with("/playlist/"),
+        TokenScope::FileManage => path.starts_with("/file/"),
+    }
+}
+
+/// Permission of an API token on a channel, limited by the permission of its creator.
+pub fn api_token_permission(scope: &TokenScope, creator: &ChannelPermission) -> ChannelPermission {
+    match scope {
+        TokenScope::PlaylistEdit if creator.playlists() => ChannelPermission::PlaylistEditor,
+        TokenScope::FileManage if creator.files() => ChannelPermission::FileManager,
+        _ => ChannelPermission::Viewer,
+    }
+}
+
 /// Create a json web token (JWT)
 pub async fn encode_jwt(claims: Claims) -> Result<String, ServiceError> {
     let config = GLOBAL_SETTINGS.get().unwrap();
@@ -68,11 +388,28 @@ pub async fn decode_jwt(token: &str) -> Result<Claims, Error> {
 }
 
 pub async fn authorize(
-    pool: &Pool<Sqlite>,
+    pool: &DbPool,
     credentials: Credentials,
+    client: LoginClient,
 ) -> Result<impl Responder, ServiceError> {
     let username = credentials.username.clone();
     let password = credentials.password.clone();
+    let now = Utc::now().timestamp();
+
+    if let Some(wait) = limiter_keys(&username, &client.ip)
+        .iter()
+        .filter_map(|(key, _)| LOGIN_LIMITER.locked(key, now))
+        .max()
+    {
+        warn!("Login {username} from {} is locked", client.ip);
+
+        return Ok(web::Json(serde_json::json!({
+            "detail": "Too many failed logins, try again later!",
+        }))
+        .customize()
+        .insert_header((header::RETRY_AFTER, wait.to_string()))
+        .with_status(StatusCode::TOO_MANY_REQUESTS));
+    }
 
     match handles::select_login(pool, &username).await {
         Ok(mut user) => {
@@ -92,9 +429,28 @@ pub async fn authorize(
             if verified_password.is_ok() {
                 let access_claims = Claims::new(user.clone(), role.clone(), ACCESS_LIFETIME);
                 let access_token = encode_jwt(access_claims).await?;
-                let refresh_claims = Claims::new(user, role.clone(), REFRESH_LIFETIME);
+                let mut refresh_claims = Claims::new(user.clone(), role.clone(), REFRESH_LIFETIME);
+                refresh_claims.jti = random_key(32);
+                let expires = refresh_claims.exp;
                 let refresh_token = encode_jwt(refresh_claims).await?;
 
+                let session = Session {
+                    user_id: user.id,
+                    ip: client.ip,
+                    user_agent: client.user_agent,
+                    created: now,
+                    last_used: now,
+                    expires,
+                    ..Default::default()
+                };
+
+                handles::delete_expired_sessions(pool, now).await?;
+                handles::delete_auth_audit(pool, now - AUTH_AUDIT_DAYS * 86400).await?;
+                handles::insert_session(pool, &session, &token_hash(&refresh_token)).await?;
+
+                LOGIN_LIMITER.success(&limiter_keys(&username, &session.ip)[0].0);
+                audit_login(pool, &username, &session.ip, "login", now).await;
+
                 info!("user {} login, with role: {role}", username);
 
                 Ok(web::Json(serde_json::json!({
@@ -105,6 +461,7 @@ pub async fn authorize(
                 .with_status(StatusCode::OK))
             } else {
                 error!("Wrong password for {username}!");
+                login_failed(pool, &username, &client.ip, now).await;
 
                 Ok(web::Json(serde_json::json!({
                     "detail": "Incorrect credentials!",
@@ -115,6 +472,7 @@ pub async fn authorize(
         }
         Err(e) => {
             error!("Login {username} failed! {e}");
+            login_failed(pool, &username, &client.ip, now).await;
 
             Ok(web::Json(serde_json::json!({
                 "detail": format!("Login {username} failed!"),
@@ -125,21 +483,34 @@ pub async fn authorize(
     }
 }
 
+/// Create a new access token, as long as the session of the refresh token exists.
+/// The role and permissions are read again, so changes of them apply here.
 pub async fn refresh(
-    pool: &Pool<Sqlite>,
+    pool: &DbPool,
     data: TokenRefreshRequest,
 ) -> Result<impl Responder, ServiceError> {
     let refresh_token = &data.refresh;
 
     match decode_jwt(refresh_token).await {
         Ok(claims) => {
-            let user_id = claims.id;
-            let role = claims.role;
+            let Some(session) = handles::select_session_by_hash(pool, &token_hash(refresh_token))
+                .await?
+                .filter(|s| s.user_id == claims.id)
+            else {
+                return Ok(web::Json(serde_json::json!({
+                    "detail": "Session is revoked",
+                }))
+                .customize()
+                .with_status(StatusCode::UNAUTHORIZED));
+            };
 
-            if let Ok(user) = handles::select_user(pool, user_id).await {
+            if let Ok(user) = handles::select_user(pool, claims.id).await {
+                let role = handles::select_role(pool, &user.role_id.unwrap_or_default()).await?;
                 let access_claims = Claims::new(user.clone(), role.clone(), ACCESS_LIFETIME);
                 let access_token = encode_jwt(access_claims).await?;
 
+                handles::update_session_used(pool, session.id, Utc::now().timestamp()).await?;
+
                 info!("user {} refresh, with role: {role}", user.username);
 
                 Ok(web::Json(serde_json::json!({
@@ -162,3 +533,189 @@ pub async fn refresh(
         .with_status(StatusCode::BAD_REQUEST)),
     }
 }
+
+/// End the session of the refresh token.
+pub async fn logout(pool: &DbPool, data: TokenRefreshRequest) -> Result<(), ServiceError> {
+    handles::delete_session_by_hash(pool, &token_hash(&data.refresh)).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::UserMeta;
+
+    #[test]
+    fn login_lockout() {
+        let limiter = LoginLimiter::default();
+
+        for i in 0..4 {
+            assert!(!limiter.failure("user:admin", ACCOUNT_FAILURES, 1000 + i));
+        }
+
+        assert_eq!(limiter.locked("user:admin", 1004), None);
+        assert!(limiter.failure("user:admin", ACCOUNT_FAILURES, 1004));
+        assert_eq!(limiter.locked("user:admin", 1004), Some(LOGIN_LOCK));
+        assert_eq!(limiter.locked("user:admin", 1004 + LOGIN_LOCK), None);
+
+        // old failures are out of the window
+        assert!(!limiter.failure("user:guest", ACCOUNT_FAILURES, 0));
+        for i in 0..3 {
+            assert!(!limiter.failure("user:guest", ACCOUNT_FAILURES, LOGIN_WINDOW + i));
+        }
+        assert!(!limiter.failure("user:guest", ACCOUNT_FAILURES, LOGIN_WINDOW + 3));
+
+        limiter.success("user:guest");
+        assert!(!limiter.attempts.lock().unwrap().contains_key("user:guest"));
+    }
+
+    #[test]
+    fn login_client_ip() {
+        let proxy: IpAddr = "127.0.0.1".parse().unwrap();
+        let peer: IpAddr = "203.0.113.7".parse().unwrap();
+
+        // headers from untrusted peers are ignored
+        assert_eq!(client_ip(Some(peer), "10.0.0.1", &[]), "203.0.113.7");
+        assert_eq!(client_ip(Some(proxy), "10.0.0.1", &[]), "127.0.0.1");
+
+        // behind a proxy, spoofed addresses on the left are skipped
+        assert_eq!(
+            client_ip(Some(proxy), "10.0.0.1, 203.0.113.7", &[proxy]),
+            "203.0.113.7"
+        );
+        assert_eq!(
+            client_ip(Some(proxy), "10.0.0.1, 203.0.113.7, 127.0.0.1", &[proxy]),
+            "203.0.113.7"
+        );
+        assert_eq!(client_ip(Some(proxy), "", &[proxy]), "127.0.0.1");
+        assert_eq!(client_ip(None, "10.0.0.1", &[proxy]), "");
+    }
+
+    #[test]
+    fn channel_permissions() {
+        let user = UserMeta::new(
+            2,
+            vec![2, 3],
+            HashMap::from([
+                (2, ChannelPermission::PlaylistEditor),
+                (3, ChannelPermission::Viewer),
+            ]),
+        );
+
+        assert!(user.playlists(2));
+        assert!(!user.files(2));
+        assert!(!user.control(2));
+        assert!(!user.playlists(3));
+        assert!(!user.playlists(4));
+        assert_eq!(user.permission(4), None);
+
+        // without a permission for the channel there is only read access
+        let claims: Claims = serde_json::from_str(
+            r#"{"id": 2, "channels": [1], "username": "user", "role": "user", "exp": 0}"#,
+        )
+        .unwrap();
+        let user = UserMeta::new(claims.id, claims.channels, claims.permissions);
+
+        assert_eq!(user.permission(1), Some(ChannelPermission::Viewer));
+        assert!(!user.control(1));
+        assert!(!user.playlists(1));
+    }
+
+    #[test]
+    fn api_token_scope() {
+        let token = generate_api_token();
+
+        assert!(token.starts_with(API_TOKEN_PREFIX));
+        assert_eq!(token_hash(&token).len(), 64);
+        assert_ne!(token_hash(&token), token_hash(&generate_api_token()));
+
+        let read = TokenScope::ReadOnly;
+        let playlist = TokenScope::PlaylistEdit;
+        let file = TokenScope::FileManage;
+
+        assert!(api_token_allows(&read, &Method::GET, "/api/playlist/1"));
+        assert!(api_token_allows(
+            &read,
+            &Method::POST,
+            "/api/file/1/browse/"
+        ));
+        assert!(!api_token_allows(&read, &Method::POST, "/api/playlist/1/"));
+        assert!(api_token_allows(
+            &playlist,
+            &Method::POST,
+            "/api/playlist/1/"
+        ));
+        assert!(api_token_allows(
+            &playlist,
+            &Method::DELETE,
+            "/api/playlist/1/2024-01-01"
+        ));
+        assert!(!api_token_allows(
+            &playlist,
+            &Method::PUT,
+            "/api/file/1/upload/"
+        ));
+        assert!(api_token_allows(&file, &Method::PUT, "/api/file/1/upload/"));
+        assert!(!api_token_allows(
+            &file,
+            &Method::POST,
+            "/api/control/1/playout/"
+        ));
+    }
+
+    #[test]
+    fn media_url_signature() {
+        let sign = MediaSignature {
+            expires: 1000,
+            signature: media_signature("secret", 1, "/folder/clip.mp4", 1000),
+        };
+
+        assert!(check_media_signature(
+            "secret",
+            1,
+            "folder/clip.mp4",
+            &sign,
+            900
+        ));
+        assert!(!check_media_signature(
+            "secret",
+            1,
+            "folder/clip.mp4",
+            &sign,
+            1001
+        ));
+        assert!(!check_media_signature(
+            "secret",
+            2,
+            "folder/clip.mp4",
+            &sign,
+            900
+        ));
+        assert!(!check_media_signature(
+            "other",
+            1,
+            "folder/clip.mp4",
+            &sign,
+            900
+        ));
+
+        // the router decodes spaces, but not `+` and `%`
+        let path = "folder/A+B 100%.mp4";
+        let sign = MediaSignature {
+            expires: 1000,
+            signature: media_signature("secret", 1, path, 1000),
+        };
+        let encoded = utf8_percent_encode(path, PATH_ESCAPE).to_string();
+
+        assert_eq!(encoded, "folder/A%2BB%20100%25.mp4");
+        assert_eq!(media_path("folder/A%2BB 100%25.mp4"), path);
+        assert!(check_media_signature(
+            "secret",
+            1,
+            &media_path("folder/A%2BB 100%25.mp4"),
+            &sign,
+            900
+        ));
+    }
+}
@@ -18,8 +18,8 @@ use actix_files;
 use actix_multipart::Multipart;
 use actix_web::{
     delete, get,
-    http::header::{ContentDisposition, DispositionType},
-    patch, post, put, web, HttpRequest, HttpResponse, Responder,
+    http::header::{self, ContentDisposition, DispositionType},
+    patch, post, put, web, Either, HttpRequest, HttpResponse, Responder,
 };
 use actix_web_grants::{authorities::AuthDetails, proc_macro::protect};
 
@@ -27,44 +27,87 @@ use argon2::{
     password_hash::{rand_core::OsRng, SaltString},
     Argon2, PasswordHasher,
 };
-use chrono::{DateTime, Datelike, Local, NaiveDateTime, TimeDelta, TimeZone, Utc};
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, TimeDelta, TimeZone, Utc};
 use log::*;
 use path_clean::PathClean;
+use rand::{distr::Alphanumeric, Rng};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use sqlx::{Pool, Sqlite};
 use tokio::{fs, sync::Mutex};
 
 use crate::{
-    api::auth::{self, Credentials, TokenRefreshRequest},
+    api::auth::{self, Credentials, LoginClient, TokenRefreshRequest},
     db::{
         handles,
-        models::{Channel, Role, TextPreset, User, UserMeta},
+        models::{
+            AlertRule, ApiToken, BumperRule, Channel, ExtraOutput, FillerSource, FilterTemplate,
+            IngestKey, InsertRule, LiveEvent, Notifier, Role, TextPreset, UploadLimit, User,
+            UserMeta, Webhook,
+        },
+        DbPool,
     },
     file::{
         norm_abs_path, resolve_path,
-        utils::{media_map::SharedMediaMap, ABS_PATH_INDICATOR},
-        MoveObject, PathObject,
+        utils::{
+            audit::{record, select_audit, AuditAction, AuditFilter},
+            filler::probe_fillers,
+            hash::{dedupe, group_duplicates, hash_uploads, scan_hashes, DedupeObj},
+            media_map::SharedMediaMap,
+            migrate::{migrate_storage, migration_status, MigrateObj, MigrationStatus},
+            validation::{upload_folder_allowed, validate_uploads},
+            ABS_PATH_INDICATOR,
+        },
+        MoveObject, PathObject, StorageBackend,
     },
     player::{
-        controller::ChannelController,
+        controller::{ChannelController, ChannelManager, ProcessUnit},
+        filter::{layout, logo::parse_rules},
+        input::{
+            ingest::authorize_key,
+            voice::{play_file, VoiceControl},
+            whip,
+            window::parse_windows,
+        },
+        output::{
+            archive,
+            delay::MAX_DELAY,
+            html::HtmlControl,
+            published,
+            reload::{config_reload, ConfigReload},
+            snapshot,
+        },
         utils::{
-            get_data_map, get_date_range, import::import_file, sec_to_time, time_to_sec,
-            JsonPlaylist,
+            get_data_map, get_date_range, import::import_file, json_validate::playlist_report,
+            playlist_clock, sec_to_time, secondary::parse_schedule, time_to_sec, JsonPlaylist,
         },
     },
     utils::{
         advanced_config::AdvancedConfig,
+        alerts,
         channels::{create_channel, delete_channel},
-        config::{get_config, PlayoutConfig, Template},
-        control::{control_state, send_message, ControlParams, Process, ProcessCtl},
+        config::{get_config, OutputMode, PlayoutConfig, Template, Transition},
+        control::{
+            control_state, emergency_control, send_message, ControlParams, Emergency, Process,
+            ProcessCtl,
+        },
+        epg,
         errors::ServiceError,
+        log_tail::tail_log,
+        logging::Target,
         mail::MailQueue,
-        naive_date_time_from_str,
-        playlist::{delete_playlist, generate_playlist, read_playlist, write_playlist},
-        public_path, read_log_file, system, TextFilter,
+        naive_date_time_from_str, notifier,
+        playlist::{
+            convert_playlists, delete_playlist, export_json_playlists, generate_playlist,
+            import_json_playlists, playlist_references, read_playlist, trim_item, write_playlist,
+            TrimObj,
+        },
+        public_path, read_log_file, system,
+        time_machine::time_now,
+        validity::library_validity,
+        webhook::parse_events,
+        TextFilter,
     },
-    vec_strings,
+    vec_strings, ARGS,
 };
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -73,6 +116,53 @@ pub struct DateObj {
     date: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct EpgObj {
+    #[serde(default = "default_epg_days")]
+    days: u32,
+}
+
+fn default_epg_days() -> u32 {
+    2
+}
+
+#[derive(Debug, Deserialize)]
+struct NowPlayingObj {
+    #[serde(default = "default_now_playing_next")]
+    next: usize,
+}
+
+fn default_now_playing_next() -> usize {
+    3
+}
+
+#[derive(Debug, Serialize)]
+struct ScreenshotObj {
+    time: String,
+    path: String,
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LogTailObj {
+    #[serde(default)]
+    date: String,
+    #[serde(default)]
+    level: String,
+    #[serde(default = "default_log_page")]
+    page: usize,
+    #[serde(default = "default_log_limit")]
+    limit: usize,
+}
+
+fn default_log_page() -> usize {
+    1
+}
+
+fn default_log_limit() -> usize {
+    100
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 struct FileObj {
     #[serde(default)]
@@ -94,6 +184,53 @@ pub struct ImportObj {
     date: String,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SignObj {
+    path: String,
+    #[serde(default = "media_url_lifetime")]
+    lifetime: i64,
+}
+
+fn media_url_lifetime() -> i64 {
+    auth::MEDIA_URL_LIFETIME
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ConvertObj {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScheduledObj {
+    search: String,
+    #[serde(default)]
+    from: String,
+    #[serde(default = "default_scheduled_limit")]
+    limit: i64,
+}
+
+fn default_scheduled_limit() -> i64 {
+    100
+}
+
+#[derive(Debug, Deserialize)]
+struct AuthAuditFilter {
+    user: Option<String>,
+    event: Option<String>,
+    #[serde(default = "default_scheduled_limit")]
+    limit: i64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ValidityObj {
+    path: String,
+    #[serde(default)]
+    valid_from: Option<String>,
+    #[serde(default)]
+    valid_to: Option<String>,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct ProgramObj {
     #[serde(default = "time_after", deserialize_with = "naive_date_time_from_str")]
@@ -147,12 +284,35 @@ struct ProgramItem {
 ///     "refresh": "<REFRESH TOKEN>"
 /// }
 /// ```
+///
+/// The access token is valid for 15 minutes, the refresh token for 30 days, as long as its session exists.
 #[post("/login/")]
 pub async fn login(
-    pool: web::Data<Pool<Sqlite>>,
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
     credentials: web::Json<Credentials>,
 ) -> Result<impl Responder, ServiceError> {
-    auth::authorize(&pool.into_inner(), credentials.into_inner()).await
+    let forwarded_for = req
+        .headers()
+        .get_all("x-forwarded-for")
+        .filter_map(|h| h.to_str().ok())
+        .collect::<Vec<_>>()
+        .join(",");
+    let client = LoginClient {
+        ip: auth::client_ip(
+            req.peer_addr().map(|a| a.ip()),
+            &forwarded_for,
+            &ARGS.trusted_proxies,
+        ),
+        user_agent: req
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|a| a.to_str().ok())
+            .unwrap_or_default()
+            .to_string(),
+    };
+
+    auth::authorize(&pool.into_inner(), credentials.into_inner(), client).await
 }
 
 /// **Refresh token**
@@ -170,12 +330,30 @@ pub async fn login(
 /// ```
 #[post("/refresh/")]
 pub async fn refresh(
-    pool: web::Data<Pool<Sqlite>>,
+    pool: web::Data<DbPool>,
     data: web::Json<TokenRefreshRequest>,
 ) -> Result<impl Responder, ServiceError> {
     auth::refresh(&pool.into_inner(), data.into_inner()).await
 }
 
+/// **Logout**
+///
+/// End the session of the refresh token, it can't be used anymore.
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/auth/logout/ -H "Content-Type: application/json" \
+/// -d '{ "refresh": "<REFRESH TOKEN>" }'
+/// ```
+#[post("/logout/")]
+pub async fn logout(
+    pool: web::Data<DbPool>,
+    data: web::Json<TokenRefreshRequest>,
+) -> Result<impl Responder, ServiceError> {
+    auth::logout(&pool, data.into_inner()).await?;
+
+    Ok("Logout Success")
+}
+
 /// From here on all request **must** contain the authorization header:\
 /// `"Authorization: Bearer <TOKEN>"`
 /// **Get current User**
@@ -190,7 +368,7 @@ pub async fn refresh(
     ty = "Role"
 )]
 async fn get_user(
-    pool: web::Data<Pool<Sqlite>>,
+    pool: web::Data<DbPool>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
     match handles::select_user(&pool, user.id).await {
@@ -211,7 +389,7 @@ async fn get_user(
 #[get("/user/{id}")]
 #[protect("Role::GlobalAdmin", ty = "Role")]
 async fn get_by_name(
-    pool: web::Data<Pool<Sqlite>>,
+    pool: web::Data<DbPool>,
     id: web::Path<i32>,
 ) -> Result<impl Responder, ServiceError> {
     match handles::select_user(&pool, *id).await {
@@ -231,7 +409,7 @@ async fn get_by_name(
 /// ```
 #[get("/users")]
 #[protect("Role::GlobalAdmin", ty = "Role")]
-async fn get_users(pool: web::Data<Pool<Sqlite>>) -> Result<impl Responder, ServiceError> {
+async fn get_users(pool: web::Data<DbPool>) -> Result<impl Responder, ServiceError> {
     match handles::select_users(&pool).await {
         Ok(users) => Ok(web::Json(users)),
         Err(e) => {
@@ -247,6 +425,15 @@ async fn get_users(pool: web::Data<Pool<Sqlite>>) -> Result<impl Responder, Serv
 /// curl -X PUT http://127.0.0.1:8787/api/user/1 -H 'Content-Type: application/json' \
 /// -d '{"mail": "<MAIL>", "password": "<PASS>"}' -H 'Authorization: Bearer <TOKEN>'
 /// ```
+///
+/// Global admins can set the permission of a user per channel:
+/// `viewer`, `playlist_editor`, `file_manager` or `editor`.
+///
+/// ```BASH
+/// curl -X PUT http://127.0.0.1:8787/api/user/2 -H 'Content-Type: application/json' \
+/// -d '{"channel_ids": [2, 3], "permissions": {"2": "playlist_editor", "3": "viewer"}}' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
 #[put("/user/{id}")]
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
@@ -254,13 +441,14 @@ async fn get_users(pool: web::Data<Pool<Sqlite>>) -> Result<impl Responder, Serv
     expr = "*id == user.id || role.has_authority(&Role::GlobalAdmin)"
 )]
 async fn update_user(
-    pool: web::Data<Pool<Sqlite>>,
+    pool: web::Data<DbPool>,
     id: web::Path<i32>,
     data: web::Json<User>,
     role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
-    let channel_ids = data.channel_ids.clone().unwrap_or_default();
+    let channel_ids = data.channel_ids.clone();
+    let permissions = data.permissions.clone();
     let mut fields = String::new();
 
     if let Some(mail) = data.mail.clone() {
@@ -291,21 +479,109 @@ async fn update_user(
         fields.push_str(&format!("password = '{password_hash}'"));
     }
 
-    handles::update_user(&pool, *id, fields).await?;
+    if !fields.is_empty() {
+        handles::update_user(&pool, *id, fields).await?;
+    }
+
+    // users can't change their own channels and permissions
+    if !role.has_authority(&Role::GlobalAdmin) {
+        return Ok("Update Success");
+    }
 
-    let related_channels = handles::select_related_channels(&pool, Some(*id)).await?;
+    if let Some(channel_ids) = channel_ids {
+        let related_channels = handles::select_related_channels(&pool, Some(*id)).await?;
 
-    for channel in related_channels {
-        if !channel_ids.contains(&channel.id) {
-            handles::delete_user_channel(&pool, *id, channel.id).await?;
+        for channel in related_channels {
+            if !channel_ids.contains(&channel.id) {
+                handles::delete_user_channel(&pool, *id, channel.id).await?;
+            }
         }
+
+        handles::insert_user_channel(&pool, *id, channel_ids).await?;
     }
 
-    handles::insert_user_channel(&pool, *id, channel_ids).await?;
+    if let Some(permissions) = permissions {
+        handles::update_user_permissions(&pool, *id, &permissions).await?;
+    }
 
     Ok("Update Success")
 }
 
+/// **Get Sessions of User**
+///
+/// Logins of the user, which can still refresh their access token.
+///
+/// ```BASH
+/// curl -X GET 'http://127.0.0.1:8787/api/user/1/sessions' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/user/{id}/sessions")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "*id == user.id || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn get_sessions(
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let sessions = handles::select_sessions(&pool, *id).await?;
+
+    Ok(web::Json(sessions))
+}
+
+/// **Revoke Sessions of User**
+///
+/// Revoke all sessions of the user, or only one with its ID.
+/// Access tokens, which are already given out, are valid until they expire.
+///
+/// ```BASH
+/// curl -X DELETE 'http://127.0.0.1:8787/api/user/1/sessions' -H 'Authorization: Bearer <TOKEN>'
+/// curl -X DELETE 'http://127.0.0.1:8787/api/user/1/sessions/3' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[delete("/user/{id}/sessions")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "*id == user.id || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn revoke_sessions(
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    handles::delete_sessions(&pool, *id, None).await?;
+
+    Ok("Revoke sessions Success")
+}
+
+#[delete("/user/{id}/sessions/{session}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "path.0 == user.id || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn revoke_session(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i32, i32)>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (id, session) = path.into_inner();
+
+    if handles::delete_sessions(&pool, id, Some(session))
+        .await?
+        .rows_affected()
+        == 0
+    {
+        return Err(ServiceError::BadRequest("Session not found".to_string()));
+    }
+
+    Ok("Revoke session Success")
+}
+
 /// **Add User**
 ///
 /// ```BASH
@@ -316,7 +592,7 @@ async fn update_user(
 #[post("/user/")]
 #[protect("Role::GlobalAdmin", ty = "Role")]
 async fn add_user(
-    pool: web::Data<Pool<Sqlite>>,
+    pool: web::Data<DbPool>,
     data: web::Json<User>,
 ) -> Result<impl Responder, ServiceError> {
     match handles::insert_user(&pool, data.into_inner()).await {
@@ -337,7 +613,7 @@ async fn add_user(
 #[delete("/user/{id}")]
 #[protect("Role::GlobalAdmin", ty = "Role")]
 async fn remove_user(
-    pool: web::Data<Pool<Sqlite>>,
+    pool: web::Data<DbPool>,
     id: web::Path<i32>,
 ) -> Result<impl Responder, ServiceError> {
     match handles::delete_user(&pool, *id).await {
@@ -349,6 +625,79 @@ async fn remove_user(
     }
 }
 
+/// **Get Login Audit**
+///
+/// Logins, failed logins and lockouts, newest first. `event` is `login`, `failure` or `lockout`.
+/// After 5 failed logins of an account, or 20 from one address, in 15 minutes,
+/// the login is locked for 15 minutes.
+///
+/// ```BASH
+/// curl -X GET 'http://127.0.0.1:8787/api/auth-audit?user=admin&event=failure&limit=100' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/auth-audit")]
+#[protect("Role::GlobalAdmin", ty = "Role")]
+async fn get_auth_audit(
+    pool: web::Data<DbPool>,
+    filter: web::Query<AuthAuditFilter>,
+) -> Result<impl Responder, ServiceError> {
+    let entries = handles::select_auth_audit(
+        &pool,
+        filter.user.as_deref(),
+        filter.event.as_deref(),
+        filter.limit.clamp(1, 1000),
+    )
+    .await?;
+
+    Ok(web::Json(entries))
+}
+
+/// **Get Upload Limits**
+///
+/// List the upload restrictions of all roles.
+///
+/// ```BASH
+/// curl -X GET 'http://127.0.0.1:8787/api/upload-limits' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/upload-limits")]
+#[protect("Role::GlobalAdmin", ty = "Role")]
+async fn get_upload_limits(pool: web::Data<DbPool>) -> Result<impl Responder, ServiceError> {
+    let limits = handles::select_upload_limits(&pool).await?;
+
+    Ok(web::Json(limits))
+}
+
+/// **Update Upload Limit**
+///
+/// Set max file size (MB), additional extensions and allowed target folders of a role.
+/// Zero and empty values disable the restriction, `{username}` in folders is replaced
+/// with the name of the uploading user.
+///
+/// ```BASH
+/// curl -X PUT 'http://127.0.0.1:8787/api/upload-limits/user' -H 'Content-Type: application/json' \
+/// -d '{"max_size": 2048, "extensions": "wav;mov", "folders": "submissions/{username}"}' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[put("/upload-limits/{role}")]
+#[protect("Role::GlobalAdmin", ty = "Role")]
+async fn update_upload_limit(
+    pool: web::Data<DbPool>,
+    role: web::Path<String>,
+    data: web::Json<UploadLimit>,
+) -> Result<impl Responder, ServiceError> {
+    let role = Role::set_role(&role);
+
+    if handles::update_upload_limit(&pool, &role, &data)
+        .await?
+        .rows_affected()
+        == 0
+    {
+        return Err(ServiceError::BadRequest("Role not found".to_string()));
+    }
+
+    Ok("Update upload limit success")
+}
+
 /// #### Settings
 ///
 /// **Get Settings from Channel**
@@ -375,7 +724,7 @@ async fn remove_user(
     expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
 )]
 async fn get_channel(
-    pool: web::Data<Pool<Sqlite>>,
+    pool: web::Data<DbPool>,
     id: web::Path<i32>,
     role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
@@ -398,7 +747,7 @@ async fn get_channel(
     ty = "Role"
 )]
 async fn get_all_channels(
-    pool: web::Data<Pool<Sqlite>>,
+    pool: web::Data<DbPool>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
     if let Ok(channel) = handles::select_related_channels(&pool, Some(user.id)).await {
@@ -422,7 +771,7 @@ async fn get_all_channels(
     expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
 )]
 async fn patch_channel(
-    pool: web::Data<Pool<Sqlite>>,
+    pool: web::Data<DbPool>,
     id: web::Path<i32>,
     data: web::Json<Channel>,
     controllers: web::Data<Mutex<ChannelController>>,
@@ -448,10 +797,15 @@ async fn patch_channel(
 
     handles::update_channel(&pool, *id, data.clone()).await?;
     let new_config = get_config(&pool, *id).await?;
+    let restart_mirror = manager.config.lock().await.channel.storage != new_config.channel.storage;
 
     manager.update_config(new_config).await;
     manager.clone().update_channel(&data).await;
 
+    if restart_mirror {
+        manager.start_mirror().await;
+    }
+
     Ok("Update Success")
 }
 
@@ -465,7 +819,7 @@ async fn patch_channel(
 #[post("/channel/")]
 #[protect("Role::GlobalAdmin", ty = "Role")]
 async fn add_channel(
-    pool: web::Data<Pool<Sqlite>>,
+    pool: web::Data<DbPool>,
     data: web::Json<Channel>,
     controllers: web::Data<Mutex<ChannelController>>,
     queue: web::Data<Mutex<Vec<Arc<Mutex<MailQueue>>>>>,
@@ -491,7 +845,7 @@ async fn add_channel(
 #[delete("/channel/{id}")]
 #[protect("Role::GlobalAdmin", ty = "Role")]
 async fn remove_channel(
-    pool: web::Data<Pool<Sqlite>>,
+    pool: web::Data<DbPool>,
     id: web::Path<i32>,
     controllers: web::Data<Mutex<ChannelController>>,
     queue: web::Data<Mutex<Vec<Arc<Mutex<MailQueue>>>>>,
@@ -547,7 +901,7 @@ async fn get_advanced_config(
     expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
 )]
 async fn get_related_advanced_config(
-    pool: web::Data<Pool<Sqlite>>,
+    pool: web::Data<DbPool>,
     id: web::Path<i32>,
     role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
@@ -581,7 +935,7 @@ async fn get_related_advanced_config(
     expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
 )]
 async fn remove_related_advanced_config(
-    pool: web::Data<Pool<Sqlite>>,
+    pool: web::Data<DbPool>,
     path: web::Path<(i32, i32)>,
     controllers: web::Data<Mutex<ChannelController>>,
     role: AuthDetails<Role>,
@@ -623,7 +977,7 @@ async fn remove_related_advanced_config(
     expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
 )]
 async fn update_advanced_config(
-    pool: web::Data<Pool<Sqlite>>,
+    pool: web::Data<DbPool>,
     id: web::Path<i32>,
     data: web::Json<AdvancedConfig>,
     controllers: web::Data<Mutex<ChannelController>>,
@@ -640,8 +994,14 @@ async fn update_advanced_config(
     handles::update_advanced_configuration(&pool, *id, data.into_inner()).await?;
     let new_config = get_config(&pool, *id).await?;
 
+    let restart_mirror = manager.config.lock().await.storage.mirror != new_config.storage.mirror;
+
     manager.update_config(new_config).await;
 
+    if restart_mirror {
+        manager.start_mirror().await;
+    }
+
     Ok(web::Json("Update success"))
 }
 
@@ -659,7 +1019,7 @@ async fn update_advanced_config(
     expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
 )]
 async fn add_advanced_config(
-    pool: web::Data<Pool<Sqlite>>,
+    pool: web::Data<DbPool>,
     id: web::Path<i32>,
     data: web::Json<AdvancedConfig>,
     controllers: web::Data<Mutex<ChannelController>>,
@@ -706,7 +1066,11 @@ async fn get_playout_config(
         .get(*id)
         .await
         .ok_or_else(|| ServiceError::BadRequest(format!("Channel ({id}) not exists!")))?;
-    let config = manager.config.lock().await.clone();
+    let mut config = manager.config.lock().await.clone();
+
+    if !role.has_authority(&Role::GlobalAdmin) {
+        config.storage.mirror.clear();
+    }
 
     Ok(web::Json(config))
 }
@@ -717,6 +1081,9 @@ async fn get_playout_config(
 /// curl -X PUT http://127.0.0.1:8787/api/playout/config/1 -H "Content-Type: application/json" \
 /// -d { <CONFIG DATA> } -H 'Authorization: Bearer <TOKEN>'
 /// ```
+///
+/// Response is how the changes get applied to the running playout: `"none"`, `"encoder"`
+/// for a new encoder at the next clip, or `"restart"`.
 #[allow(clippy::too_many_arguments)]
 #[put("/playout/config/{id}")]
 #[protect(
@@ -725,7 +1092,7 @@ async fn get_playout_config(
     expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
 )]
 async fn update_playout_config(
-    pool: web::Data<Pool<Sqlite>>,
+    pool: web::Data<DbPool>,
     id: web::Path<i32>,
     mut data: web::Json<PlayoutConfig>,
     controllers: web::Data<Mutex<ChannelController>>,
@@ -752,8 +1119,190 @@ async fn update_playout_config(
     data.storage.filler = filler;
     data.text.font = font;
 
+    for passphrase in [&data.output.srt_passphrase, &data.ingest.srt_passphrase] {
+        let length = passphrase.chars().count();
+
+        if length > 0 && !(10..=79).contains(&length) {
+            return Err(ServiceError::BadRequest(
+                "SRT passphrase must have 10 to 79 characters".to_string(),
+            ));
+        }
+    }
+
+    if let Err(e) = parse_windows(&data.ingest.windows) {
+        return Err(ServiceError::BadRequest(e));
+    }
+
+    if let Err(e) = parse_rules(&data.processing.logo_rules) {
+        return Err(ServiceError::BadRequest(e));
+    }
+
+    if let Err(e) = layout::parse_rules(&data.processing.audio_layout) {
+        return Err(ServiceError::BadRequest(e));
+    }
+
+    if !data.processing.filter_template.is_empty()
+        && !handles::select_filter_templates(&pool, *id)
+            .await?
+            .iter()
+            .any(|t| t.name == data.processing.filter_template)
+    {
+        return Err(ServiceError::BadRequest(format!(
+            "Filter template {} not found",
+            data.processing.filter_template
+        )));
+    }
+
+    if data.processing.black_alert < 0.0 || data.processing.silence_alert < 0.0 {
+        return Err(ServiceError::BadRequest(
+            "Black and silence alert can't be negative".to_string(),
+        ));
+    }
+
+    if !(0..=MAX_DELAY).contains(&data.ingest.delay) {
+        return Err(ServiceError::BadRequest(format!(
+            "Ingest delay must be between 0 and {MAX_DELAY} seconds"
+        )));
+    }
+
+    if data.ingest.transition == Transition::Crossfade {
+        return Err(ServiceError::BadRequest(
+            "Ingest transition can only be cut or fade_black".to_string(),
+        ));
+    }
+
+    if data.text.ticker
+        && !(data.text.ticker_url.starts_with("http://")
+            || data.text.ticker_url.starts_with("https://"))
+    {
+        return Err(ServiceError::BadRequest(
+            "Ticker needs the http(s) URL of a RSS or JSON feed".to_string(),
+        ));
+    }
+
+    if data.processing.html && data.processing.html_browser.trim().is_empty() {
+        return Err(ServiceError::BadRequest(
+            "HTML overlay needs a browser".to_string(),
+        ));
+    }
+
+    if data.ingest.voice
+        && (!(0.0..=60.0).contains(&data.ingest.voice_duck)
+            || !(0.01..=2000.0).contains(&data.ingest.voice_attack)
+            || !(0.01..=9000.0).contains(&data.ingest.voice_release))
+    {
+        return Err(ServiceError::BadRequest(
+            "Voice-over ducking must be 0 to 60 dB, attack 0.01 to 2000 ms and release 0.01 to 9000 ms".to_string(),
+        ));
+    }
+
+    if data.text.clock && data.text.clock_format.trim().is_empty() {
+        return Err(ServiceError::BadRequest(
+            "Clock format must not be empty".to_string(),
+        ));
+    }
+
+    if data.text.now_next_duration < 0 {
+        return Err(ServiceError::BadRequest(
+            "Now/next duration must not be negative".to_string(),
+        ));
+    }
+
+    if data.text.ticker_speed <= 0 || data.text.ticker_interval <= 0 {
+        return Err(ServiceError::BadRequest(
+            "Ticker speed and interval must be positive".to_string(),
+        ));
+    }
+
+    if data.ingest.whip_enable
+        && (data.ingest.whip_url.trim().is_empty() || data.ingest.whip_source.trim().is_empty())
+    {
+        return Err(ServiceError::BadRequest(
+            "WHIP ingest needs the URL and the source of the media server".to_string(),
+        ));
+    }
+
+    if !data.output.preview_whep_url.trim().is_empty()
+        && data.output.preview_publish.trim().is_empty()
+    {
+        return Err(ServiceError::BadRequest(
+            "WebRTC preview needs the publish address of the media server".to_string(),
+        ));
+    }
+
+    if data.archive.enable
+        && (data.archive.path.trim_matches('/').is_empty() || data.archive.path.contains(".."))
+    {
+        return Err(ServiceError::BadRequest(
+            "Archive path must be a folder in the storage".to_string(),
+        ));
+    }
+
+    if !data.output.monitor_url.trim().is_empty()
+        && data.output.monitor_interval < published::MIN_INTERVAL
+    {
+        return Err(ServiceError::BadRequest(format!(
+            "Monitor interval must be at least {} seconds",
+            published::MIN_INTERVAL
+        )));
+    }
+
+    if data.archive.screenshots
+        && (data.archive.screenshot_path.trim_matches('/').is_empty()
+            || data.archive.screenshot_path.contains("..")
+            || data.archive.screenshot_interval < 1)
+    {
+        return Err(ServiceError::BadRequest(
+            "Screenshots need a folder in the storage and an interval of at least one minute"
+                .to_string(),
+        ));
+    }
+
+    if data.ingest.record
+        && (data.ingest.record_path.trim_matches('/').is_empty()
+            || data.ingest.record_path.contains(".."))
+    {
+        return Err(ServiceError::BadRequest(
+            "Live recording path must be a folder in the storage".to_string(),
+        ));
+    }
+
+    if data.output.mode == OutputMode::Multicast {
+        let pids = [
+            data.output.multicast_pmt_pid,
+            data.output.multicast_video_pid,
+            data.output.multicast_audio_pid,
+        ];
+
+        if pids.iter().any(|p| !(16..=8190).contains(p))
+            || pids[0] == pids[1]
+            || pids[0] == pids[2]
+            || pids[1] == pids[2]
+        {
+            return Err(ServiceError::BadRequest(
+                "Multicast PIDs must be different and between 16 and 8190".to_string(),
+            ));
+        }
+
+        if !(1..=255).contains(&data.output.multicast_ttl) {
+            return Err(ServiceError::BadRequest(
+                "Multicast TTL must be between 1 and 255".to_string(),
+            ));
+        }
+    }
+
+    if !role.has_authority(&Role::GlobalAdmin) {
+        // mirror target can contain credentials, like the channel storage
+        data.storage.mirror = manager.config.lock().await.storage.mirror.clone();
+    }
+
     handles::update_configuration(&pool, config_id, data.into_inner()).await?;
     let new_config = get_config(&pool, *id).await?;
+    let reload = if manager.is_alive.load(Ordering::SeqCst) {
+        config_reload(&*manager.config.lock().await, &new_config)
+    } else {
+        ConfigReload::None
+    };
     let mut queues = mail_queues.lock().await;
 
     for queue in queues.iter_mut() {
@@ -771,12 +1320,18 @@ async fn update_playout_config(
 
     manager.update_config(new_config).await;
 
-    Ok(web::Json("Update success"))
+    if reload == ConfigReload::Encoder {
+        manager.reload.store(true, Ordering::SeqCst);
+    }
+
+    Ok(web::Json(reload))
 }
 
 /// #### Text Presets
 ///
 /// Text presets are made for sending text messages to the ffplayout engine, to overlay them as a lower third.
+/// The text can have the placeholders `{title}`, `{next_title}`, `{time}` and `{channel}`, which are replaced
+/// with the playing clip, the time and the name of the channel, when the text is sent. So one preset can be used as lower third for all shows.
 ///
 /// **Get all Presets**
 ///
@@ -791,7 +1346,7 @@ async fn update_playout_config(
     expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
 )]
 async fn get_presets(
-    pool: web::Data<Pool<Sqlite>>,
+    pool: web::Data<DbPool>,
     id: web::Path<i32>,
     role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
@@ -803,21 +1358,33 @@ async fn get_presets(
     Err(ServiceError::InternalServerError)
 }
 
+fn check_preset(preset: &TextPreset) -> Result<(), ServiceError> {
+    parse_schedule(&preset.schedule).map_err(ServiceError::BadRequest)?;
+
+    if preset.duration < 0.0 {
+        return Err(ServiceError::BadRequest(
+            "Duration can not be negative".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
 /// **Update Preset**
 ///
 /// ```BASH
 /// curl -X PUT http://127.0.0.1:8787/api/presets/1 -H 'Content-Type: application/json' \
-/// -d '{ "name": "<PRESET NAME>", "text": "<TEXT>", "x": "<X>", "y": "<Y>", "fontsize": 24, "line_spacing": 4, "fontcolor": "#ffffff", "box": 1, "boxcolor": "#000000", "boxborderw": 4, "alpha": 1.0, "channel_id": 1 }' \
+/// -d '{ "name": "<PRESET NAME>", "text": "<TEXT>", "x": "<X>", "y": "<Y>", "fontsize": 24, "line_spacing": 4, "fontcolor": "#ffffff", "box": 1, "boxcolor": "#000000", "boxborderw": 4, "alpha": 1.0, "schedule": "19:59:50", "duration": 10.0, "channel_id": 1 }' \
 /// -H 'Authorization: Bearer <TOKEN>'
 /// ```
 #[put("/presets/{channel}/{id}")]
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
-    expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.control(path.0) || role.has_authority(&Role::GlobalAdmin)"
 )]
 async fn update_preset(
-    pool: web::Data<Pool<Sqlite>>,
+    pool: web::Data<DbPool>,
     path: web::Path<(i32, i32)>,
     data: web::Json<TextPreset>,
     role: AuthDetails<Role>,
@@ -825,6 +1392,8 @@ async fn update_preset(
 ) -> Result<impl Responder, ServiceError> {
     let (_, id) = path.into_inner();
 
+    check_preset(&data)?;
+
     if handles::update_preset(&pool, &id, data.into_inner())
         .await
         .is_ok()
@@ -839,22 +1408,24 @@ async fn update_preset(
 ///
 /// ```BASH
 /// curl -X POST http://127.0.0.1:8787/api/presets/1/ -H 'Content-Type: application/json' \
-/// -d '{ "name": "<PRESET NAME>", "text": "TEXT>", "x": "<X>", "y": "<Y>", "fontsize": 24, "line_spacing": 4, "fontcolor": "#ffffff", "box": 1, "boxcolor": "#000000", "boxborderw": 4, "alpha": 1.0, "channel_id": 1 }' \
+/// -d '{ "name": "<PRESET NAME>", "text": "TEXT>", "x": "<X>", "y": "<Y>", "fontsize": 24, "line_spacing": 4, "fontcolor": "#ffffff", "box": 1, "boxcolor": "#000000", "boxborderw": 4, "alpha": 1.0, "schedule": "19:59:50", "duration": 10.0, "channel_id": 1 }' \
 /// -H 'Authorization: Bearer <TOKEN>'
 /// ```
 #[post("/presets/{id}/")]
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.control(*id) || role.has_authority(&Role::GlobalAdmin)"
 )]
 async fn add_preset(
-    pool: web::Data<Pool<Sqlite>>,
+    pool: web::Data<DbPool>,
     id: web::Path<i32>,
     data: web::Json<TextPreset>,
     role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
+    check_preset(&data)?;
+
     if handles::insert_preset(&pool, data.into_inner())
         .await
         .is_ok()
@@ -875,10 +1446,10 @@ async fn add_preset(
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
-    expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.control(path.0) || role.has_authority(&Role::GlobalAdmin)"
 )]
 async fn delete_preset(
-    pool: web::Data<Pool<Sqlite>>,
+    pool: web::Data<DbPool>,
     path: web::Path<(i32, i32)>,
     role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
@@ -892,33 +1463,2890 @@ async fn delete_preset(
     Err(ServiceError::InternalServerError)
 }
 
-/// ### ffplayout controlling
+/// #### Insert Rules
 ///
-/// here we communicate with the engine for:
-/// - jump to last or next clip
+/// Rules insert items from a file or folder every `interval` minutes, at `minute` inside the interval.
+/// They are used in folder mode and from the playlist generator.
+///
+/// **Get all Rules**
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/rules/1 -H 'Content-Type: application/json' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/rules/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn get_rules(
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let rules = handles::select_insert_rules(&pool, *id).await?;
+
+    Ok(web::Json(rules))
+}
+
+/// Reload the channel config, so the running playout gets the changed rules.
+async fn apply_rules(
+    pool: &DbPool,
+    controllers: &Mutex<ChannelController>,
+    id: i32,
+) -> Result<(), ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+
+    manager.update_config(get_config(pool, id).await?).await;
+
+    Ok(())
+}
+
+fn check_rule(rule: &InsertRule) -> Result<(), ServiceError> {
+    if rule.interval < 1 || rule.interval > 1440 {
+        return Err(ServiceError::BadRequest(
+            "Interval must be between 1 and 1440 minutes".to_string(),
+        ));
+    }
+
+    if rule.minute < 0 || rule.minute >= rule.interval {
+        return Err(ServiceError::BadRequest(
+            "Minute must be inside the interval".to_string(),
+        ));
+    }
+
+    if rule.path.trim().is_empty() {
+        return Err(ServiceError::BadRequest("Path is missing".to_string()));
+    }
+
+    Ok(())
+}
+
+/// **Add new Rule**
+///
+/// Insert one station ID at the top of every hour:
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/rules/1/ -H 'Content-Type: application/json' \
+/// -d '{ "name": "Station ID", "interval": 60, "minute": 0, "path": "/ids", "count": 1, "shuffle": false, "active": true }' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[post("/rules/{id}/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn add_rule(
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    data: web::Json<InsertRule>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let mut rule = data.into_inner();
+    rule.channel_id = *id;
+
+    check_rule(&rule)?;
+    handles::insert_insert_rule(&pool, &rule).await?;
+    apply_rules(&pool, &controllers, *id).await?;
+
+    Ok("Add rule Success")
+}
+
+/// **Update Rule**
+///
+/// ```BASH
+/// curl -X PUT http://127.0.0.1:8787/api/rules/1/1 -H 'Content-Type: application/json' \
+/// -d '{ "name": "Jingles", "interval": 30, "minute": 0, "path": "/jingles", "count": 1, "shuffle": true, "active": true }' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[put("/rules/{channel}/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn update_rule(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i32, i32)>,
+    data: web::Json<InsertRule>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (channel, id) = path.into_inner();
+    let mut rule = data.into_inner();
+    rule.channel_id = channel;
+
+    check_rule(&rule)?;
+
+    if handles::update_insert_rule(&pool, id, &rule)
+        .await?
+        .rows_affected()
+        == 0
+    {
+        return Err(ServiceError::BadRequest("Rule not found".to_string()));
+    }
+
+    apply_rules(&pool, &controllers, channel).await?;
+
+    Ok("Update Success")
+}
+
+/// **Delete Rule**
+///
+/// ```BASH
+/// curl -X DELETE http://127.0.0.1:8787/api/rules/1/1 -H 'Content-Type: application/json' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[delete("/rules/{channel}/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn delete_rule(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i32, i32)>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (channel, id) = path.into_inner();
+
+    handles::delete_insert_rule(&pool, channel, id).await?;
+    apply_rules(&pool, &controllers, channel).await?;
+
+    Ok("Delete rule Success")
+}
+
+/// #### Bumper Rules
+///
+/// Bumpers are played before (`intro`) and after (`outro`) every playlist item of `category`.
+/// They are added when the playlist gets loaded.
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/bumpers/1 -H 'Content-Type: application/json' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/bumpers/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn get_bumpers(
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let bumpers = handles::select_bumper_rules(&pool, *id).await?;
+
+    Ok(web::Json(bumpers))
+}
+
+fn check_bumper(rule: &BumperRule) -> Result<(), ServiceError> {
+    if rule.category.trim().is_empty() {
+        return Err(ServiceError::BadRequest("Category is missing".to_string()));
+    }
+
+    if rule.intro.trim().is_empty() && rule.outro.trim().is_empty() {
+        return Err(ServiceError::BadRequest(
+            "Intro or outro is needed".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// **Add new Bumper Rule**
+///
+/// Play an intro before every movie:
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/bumpers/1/ -H 'Content-Type: application/json' \
+/// -d '{ "category": "movie", "intro": "bumpers/movie_intro.mp4", "outro": "", "active": true }' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[post("/bumpers/{id}/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn add_bumper(
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    data: web::Json<BumperRule>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let mut rule = data.into_inner();
+    rule.channel_id = *id;
+
+    check_bumper(&rule)?;
+    handles::insert_bumper_rule(&pool, &rule).await?;
+    apply_rules(&pool, &controllers, *id).await?;
+
+    Ok("Add bumper Success")
+}
+
+/// **Update Bumper Rule**
+///
+/// ```BASH
+/// curl -X PUT http://127.0.0.1:8787/api/bumpers/1/1 -H 'Content-Type: application/json' \
+/// -d '{ "category": "movie", "intro": "bumpers/movie_intro.mp4", "outro": "bumpers/movie_outro.mp4", "active": true }' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[put("/bumpers/{channel}/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn update_bumper(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i32, i32)>,
+    data: web::Json<BumperRule>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (channel, id) = path.into_inner();
+    let mut rule = data.into_inner();
+    rule.channel_id = channel;
+
+    check_bumper(&rule)?;
+
+    if handles::update_bumper_rule(&pool, id, &rule)
+        .await?
+        .rows_affected()
+        == 0
+    {
+        return Err(ServiceError::BadRequest("Bumper not found".to_string()));
+    }
+
+    apply_rules(&pool, &controllers, channel).await?;
+
+    Ok("Update Success")
+}
+
+/// **Delete Bumper Rule**
+///
+/// ```BASH
+/// curl -X DELETE http://127.0.0.1:8787/api/bumpers/1/1 -H 'Content-Type: application/json' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[delete("/bumpers/{channel}/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn delete_bumper(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i32, i32)>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (channel, id) = path.into_inner();
+
+    handles::delete_bumper_rule(&pool, channel, id).await?;
+    apply_rules(&pool, &controllers, channel).await?;
+
+    Ok("Delete bumper Success")
+}
+
+/// #### Additional Outputs
+///
+/// Every active output gets the program with its own encoder and output parameters,
+/// besides the main output from the playout config. Changes are used on the next start of the playout.
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/outputs/1 -H 'Content-Type: application/json' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/outputs/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn get_outputs(
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let outputs = handles::select_outputs(&pool, *id).await?;
+
+    Ok(web::Json(outputs))
+}
+
+fn check_output(output: &ExtraOutput) -> Result<(), ServiceError> {
+    if output.name.trim().is_empty() {
+        return Err(ServiceError::BadRequest("Name is missing".to_string()));
+    }
+
+    if shlex::split(&output.param).is_none_or(|p| p.is_empty()) {
+        return Err(ServiceError::BadRequest(
+            "Output parameters are missing or invalid".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// **Add new Output**
+///
+/// Stream the program to YouTube, besides the main output:
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/outputs/1/ -H 'Content-Type: application/json' \
+/// -d '{ "name": "YouTube", "param": "-c:v libx264 -b:v 4500k -c:a aac -b:a 128k -f flv rtmp://a.rtmp.youtube.com/live2/<KEY>", "active": true }' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[post("/outputs/{id}/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn add_output(
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    data: web::Json<ExtraOutput>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let mut output = data.into_inner();
+    output.channel_id = *id;
+
+    check_output(&output)?;
+    handles::insert_output(&pool, &output).await?;
+
+    Ok("Add output Success")
+}
+
+/// **Update Output**
+///
+/// ```BASH
+/// curl -X PUT http://127.0.0.1:8787/api/outputs/1/1 -H 'Content-Type: application/json' \
+/// -d '{ "name": "YouTube", "param": "-c:v libx264 -b:v 4500k -c:a aac -b:a 128k -f flv rtmp://a.rtmp.youtube.com/live2/<KEY>", "active": false }' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[put("/outputs/{channel}/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn update_output(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i32, i32)>,
+    data: web::Json<ExtraOutput>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (channel, id) = path.into_inner();
+    let mut output = data.into_inner();
+    output.channel_id = channel;
+
+    check_output(&output)?;
+
+    if handles::update_output(&pool, id, &output)
+        .await?
+        .rows_affected()
+        == 0
+    {
+        return Err(ServiceError::BadRequest("Output not found".to_string()));
+    }
+
+    Ok("Update Success")
+}
+
+/// **Delete Output**
+///
+/// ```BASH
+/// curl -X DELETE http://127.0.0.1:8787/api/outputs/1/1 -H 'Content-Type: application/json' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[delete("/outputs/{channel}/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn delete_output(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i32, i32)>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (channel, id) = path.into_inner();
+
+    handles::delete_output(&pool, channel, id).await?;
+
+    Ok("Delete output Success")
+}
+
+/// #### Filler Sources
+///
+/// Gaps are filled from all sources, ordered by `priority`. Clips are picked by the `weight` of their source
+/// and not repeated within `no_repeat` minutes. Without sources the filler from the storage settings is used.
+///
+/// **Get all Filler Sources**
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/fillers/1 -H 'Content-Type: application/json' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/fillers/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn get_fillers(
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let fillers = handles::select_filler_sources(&pool, *id).await?;
+
+    Ok(web::Json(fillers))
+}
+
+/// Reload the channel config and collect the filler clips again.
+async fn apply_fillers(
+    pool: &DbPool,
+    controllers: &Mutex<ChannelController>,
+    id: i32,
+) -> Result<(), ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let config = get_config(pool, id).await?;
+
+    manager
+        .storage
+        .lock()
+        .await
+        .fill_filler_list(&config, Some(manager.filler_list.clone()))
+        .await;
+    manager.update_config(config).await;
+
+    tokio::spawn(probe_fillers(manager.filler_list.clone()));
+
+    Ok(())
+}
+
+fn check_filler(filler: &FillerSource) -> Result<(), ServiceError> {
+    if filler.path.trim().is_empty() {
+        return Err(ServiceError::BadRequest("Path is missing".to_string()));
+    }
+
+    if filler.weight < 0 || filler.no_repeat < 0 {
+        return Err(ServiceError::BadRequest(
+            "Weight and no repeat can not be negative".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// **Add new Filler Source**
+///
+/// Take two of three fillers from `/fillers/trailer`, no trailer repeats within two hours:
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/fillers/1/ -H 'Content-Type: application/json' \
+/// -d '{ "path": "/fillers/trailer", "priority": 0, "weight": 2, "no_repeat": 120 }' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[post("/fillers/{id}/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn add_filler(
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    data: web::Json<FillerSource>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let mut filler = data.into_inner();
+    filler.channel_id = *id;
+
+    check_filler(&filler)?;
+    handles::insert_filler_source(&pool, &filler).await?;
+    apply_fillers(&pool, &controllers, *id).await?;
+
+    Ok("Add filler Success")
+}
+
+/// **Update Filler Source**
+///
+/// ```BASH
+/// curl -X PUT http://127.0.0.1:8787/api/fillers/1/1 -H 'Content-Type: application/json' \
+/// -d '{ "path": "/fillers/station", "priority": 1, "weight": 1, "no_repeat": 30 }' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[put("/fillers/{channel}/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn update_filler(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i32, i32)>,
+    data: web::Json<FillerSource>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (channel, id) = path.into_inner();
+    let mut filler = data.into_inner();
+    filler.channel_id = channel;
+
+    check_filler(&filler)?;
+
+    if handles::update_filler_source(&pool, id, &filler)
+        .await?
+        .rows_affected()
+        == 0
+    {
+        return Err(ServiceError::BadRequest("Filler not found".to_string()));
+    }
+
+    apply_fillers(&pool, &controllers, channel).await?;
+
+    Ok("Update Success")
+}
+
+/// **Delete Filler Source**
+///
+/// ```BASH
+/// curl -X DELETE http://127.0.0.1:8787/api/fillers/1/1 -H 'Content-Type: application/json' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[delete("/fillers/{channel}/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn delete_filler(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i32, i32)>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (channel, id) = path.into_inner();
+
+    handles::delete_filler_source(&pool, channel, id).await?;
+    apply_fillers(&pool, &controllers, channel).await?;
+
+    Ok("Delete filler Success")
+}
+
+/// #### Filter Templates
+///
+/// Named video and audio filter chains. The channel uses its `filter_template` for all clips,
+/// playlist items can use another template with their own `filter_template`.
+///
+/// **Get all Filter Templates**
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/filter-templates/1 -H 'Content-Type: application/json' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/filter-templates/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn get_filter_templates(
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let templates = handles::select_filter_templates(&pool, *id).await?;
+
+    Ok(web::Json(templates))
+}
+
+/// Reload the channel config, the next clips get the changed templates.
+async fn apply_templates(
+    pool: &DbPool,
+    controllers: &Mutex<ChannelController>,
+    id: i32,
+) -> Result<(), ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let config = get_config(pool, id).await?;
+
+    manager.update_config(config).await;
+
+    Ok(())
+}
+
+fn check_template(template: &FilterTemplate) -> Result<(), ServiceError> {
+    if template.name.trim().is_empty() {
+        return Err(ServiceError::BadRequest("Name is missing".to_string()));
+    }
+
+    // the templates are single chains, the links are set by the engine
+    if [&template.video_filter, &template.audio_filter]
+        .iter()
+        .any(|f| f.contains([';', '[', ']']))
+    {
+        return Err(ServiceError::BadRequest(
+            "Filter templates can not have links or more chains".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// **Add new Filter Template**
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/filter-templates/1/ -H 'Content-Type: application/json' \
+/// -d '{ "name": "cinema", "video_filter": "eq=contrast=1.1:saturation=0.9,vignette", "audio_filter": "highpass=f=60" }' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[post("/filter-templates/{id}/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn add_filter_template(
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    data: web::Json<FilterTemplate>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let mut template = data.into_inner();
+    template.channel_id = *id;
+
+    check_template(&template)?;
+    handles::insert_filter_template(&pool, &template).await?;
+    apply_templates(&pool, &controllers, *id).await?;
+
+    Ok("Add filter template Success")
+}
+
+/// **Update Filter Template**
+///
+/// ```BASH
+/// curl -X PUT http://127.0.0.1:8787/api/filter-templates/1/1 -H 'Content-Type: application/json' \
+/// -d '{ "name": "cinema", "video_filter": "eq=contrast=1.2", "audio_filter": "" }' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[put("/filter-templates/{channel}/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn update_filter_template(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i32, i32)>,
+    data: web::Json<FilterTemplate>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (channel, id) = path.into_inner();
+    let mut template = data.into_inner();
+    template.channel_id = channel;
+
+    check_template(&template)?;
+
+    if handles::update_filter_template(&pool, id, &template)
+        .await?
+        .rows_affected()
+        == 0
+    {
+        return Err(ServiceError::BadRequest(
+            "Filter template not found".to_string(),
+        ));
+    }
+
+    apply_templates(&pool, &controllers, channel).await?;
+
+    Ok("Update Success")
+}
+
+/// **Delete Filter Template**
+///
+/// ```BASH
+/// curl -X DELETE http://127.0.0.1:8787/api/filter-templates/1/1 -H 'Content-Type: application/json' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[delete("/filter-templates/{channel}/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn delete_filter_template(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i32, i32)>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (channel, id) = path.into_inner();
+
+    handles::delete_filter_template(&pool, channel, id).await?;
+    apply_templates(&pool, &controllers, channel).await?;
+
+    Ok("Delete filter template Success")
+}
+
+/// #### Webhooks
+///
+/// Webhooks get the events of the channel as JSON `POST` request. `events` is a comma separated list,
+/// empty for all events: `clip_start`, `clip_end`, `playlist_missing`, `ffmpeg_crash`, `ingest_connect`,
+/// `ingest_disconnect`, `storage_error`, `black` and `silence`. With a `secret` the body is signed.
+///
+/// **Get all Webhooks**
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/webhooks/1 -H 'Content-Type: application/json' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/webhooks/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn get_webhooks(
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let webhooks = handles::select_webhooks(&pool, *id).await?;
+
+    Ok(web::Json(webhooks))
+}
+
+/// Reload the channel config, the next events go to the changed webhooks.
+async fn apply_webhooks(
+    pool: &DbPool,
+    controllers: &Mutex<ChannelController>,
+    id: i32,
+) -> Result<(), ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+
+    manager.update_config(get_config(pool, id).await?).await;
+
+    Ok(())
+}
+
+fn check_webhook(webhook: &Webhook) -> Result<(), ServiceError> {
+    if !webhook.url.starts_with("http://") && !webhook.url.starts_with("https://") {
+        return Err(ServiceError::BadRequest(
+            "Webhook needs a http or https URL".to_string(),
+        ));
+    }
+
+    parse_events(&webhook.events).map_err(ServiceError::BadRequest)?;
+
+    Ok(())
+}
+
+/// **Add new Webhook**
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/webhooks/1/ -H 'Content-Type: application/json' \
+/// -d '{ "url": "https://example.org/hook", "secret": "<SECRET>", "events": "clip_start,ffmpeg_crash", "active": true }' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[post("/webhooks/{id}/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn add_webhook(
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    data: web::Json<Webhook>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let mut webhook = data.into_inner();
+    webhook.channel_id = *id;
+
+    check_webhook(&webhook)?;
+    handles::insert_webhook(&pool, &webhook).await?;
+    apply_webhooks(&pool, &controllers, *id).await?;
+
+    Ok("Add webhook Success")
+}
+
+/// **Update Webhook**
+///
+/// ```BASH
+/// curl -X PUT http://127.0.0.1:8787/api/webhooks/1/1 -H 'Content-Type: application/json' \
+/// -d '{ "url": "https://example.org/hook", "secret": "<SECRET>", "events": "", "active": false }' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[put("/webhooks/{channel}/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn update_webhook(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i32, i32)>,
+    data: web::Json<Webhook>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (channel, id) = path.into_inner();
+    let mut webhook = data.into_inner();
+    webhook.channel_id = channel;
+
+    check_webhook(&webhook)?;
+
+    if handles::update_webhook(&pool, id, &webhook)
+        .await?
+        .rows_affected()
+        == 0
+    {
+        return Err(ServiceError::BadRequest("Webhook not found".to_string()));
+    }
+
+    apply_webhooks(&pool, &controllers, channel).await?;
+
+    Ok("Update Success")
+}
+
+/// **Delete Webhook**
+///
+/// ```BASH
+/// curl -X DELETE http://127.0.0.1:8787/api/webhooks/1/1 -H 'Content-Type: application/json' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[delete("/webhooks/{channel}/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn delete_webhook(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i32, i32)>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (channel, id) = path.into_inner();
+
+    handles::delete_webhook(&pool, channel, id).await?;
+    apply_webhooks(&pool, &controllers, channel).await?;
+
+    Ok("Delete webhook Success")
+}
+
+/// #### Notifiers
+///
+/// Notifiers get the same log messages as the mail recipient, with the level and interval of the mail settings.
+/// `kind` is `telegram`, `slack`, `discord` or `matrix`:
+///
+/// - **telegram:** bot `token` and chat id as `target`, `url` is optional for an own bot API server
+/// - **slack**, **discord:** webhook `url`
+/// - **matrix:** homeserver `url`, access `token` and room id as `target`
+///
+/// **Get all Notifiers**
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/notifiers/1 -H 'Content-Type: application/json' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/notifiers/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn get_notifiers(
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let notifiers = handles::select_notifiers(&pool, *id).await?;
+
+    Ok(web::Json(notifiers))
+}
+
+/// Reload the channel config and hand the notifiers to the mail queue of the channel.
+async fn apply_notifiers(
+    pool: &DbPool,
+    controllers: &Mutex<ChannelController>,
+    mail_queues: &Mutex<Vec<Arc<Mutex<MailQueue>>>>,
+    id: i32,
+) -> Result<(), ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let config = get_config(pool, id).await?;
+
+    for queue in mail_queues.lock().await.iter() {
+        let mut queue_lock = queue.lock().await;
+
+        if queue_lock.id == id {
+            queue_lock.update(config.mail.clone());
+            break;
+        }
+    }
+
+    manager.update_config(config).await;
+
+    Ok(())
+}
+
+/// **Add new Notifier**
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/notifiers/1/ -H 'Content-Type: application/json' \
+/// -d '{ "kind": "telegram", "token": "<BOT_TOKEN>", "target": "<CHAT_ID>", "active": true }' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[allow(clippy::too_many_arguments)]
+#[post("/notifiers/{id}/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn add_notifier(
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    data: web::Json<Notifier>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    mail_queues: web::Data<Mutex<Vec<Arc<Mutex<MailQueue>>>>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let mut notifier = data.into_inner();
+    notifier.channel_id = *id;
+    notifier.kind = notifier::validate(&notifier)?.to_string();
+
+    handles::insert_notifier(&pool, &notifier).await?;
+    apply_notifiers(&pool, &controllers, &mail_queues, *id).await?;
+
+    Ok("Add notifier Success")
+}
+
+/// **Update Notifier**
+///
+/// ```BASH
+/// curl -X PUT http://127.0.0.1:8787/api/notifiers/1/1 -H 'Content-Type: application/json' \
+/// -d '{ "kind": "discord", "url": "https://discord.com/api/webhooks/<ID>/<TOKEN>", "active": false }' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[allow(clippy::too_many_arguments)]
+#[put("/notifiers/{channel}/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn update_notifier(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i32, i32)>,
+    data: web::Json<Notifier>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    mail_queues: web::Data<Mutex<Vec<Arc<Mutex<MailQueue>>>>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (channel, id) = path.into_inner();
+    let mut notifier = data.into_inner();
+    notifier.channel_id = channel;
+    notifier.kind = notifier::validate(&notifier)?.to_string();
+
+    if handles::update_notifier(&pool, id, &notifier)
+        .await?
+        .rows_affected()
+        == 0
+    {
+        return Err(ServiceError::BadRequest("Notifier not found".to_string()));
+    }
+
+    apply_notifiers(&pool, &controllers, &mail_queues, channel).await?;
+
+    Ok("Update Success")
+}
+
+/// **Delete Notifier**
+///
+/// ```BASH
+/// curl -X DELETE http://127.0.0.1:8787/api/notifiers/1/1 -H 'Content-Type: application/json' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[delete("/notifiers/{channel}/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn delete_notifier(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i32, i32)>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    mail_queues: web::Data<Mutex<Vec<Arc<Mutex<MailQueue>>>>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (channel, id) = path.into_inner();
+
+    handles::delete_notifier(&pool, channel, id).await?;
+    apply_notifiers(&pool, &controllers, &mail_queues, channel).await?;
+
+    Ok("Delete notifier Success")
+}
+
+/// #### Alert Rules
+///
+/// Alert rules are checked every minute, while the channel runs. An alert is sent once, when the condition becomes true.
+/// `condition` is one of:
+///
+/// - **filler_ratio:** filler time is over `threshold` percent, in the last `minutes`
+/// - **playlist_missing:** no playlist for the next day at `time` (`HH:MM`)
+/// - **encoder_restarts:** the encoder restarted `threshold` times, in the last `minutes`
+///
+/// `severity` is `info`, `warning` or `error`. `targets` is a comma separated list of `mail`, `telegram`, `slack`,
+/// `discord` and `matrix`, empty for all.
+///
+/// **Get all Alert Rules**
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/alert-rules/1 -H 'Content-Type: application/json' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/alert-rules/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn get_alert_rules(
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let rules = handles::select_alert_rules(&pool, *id).await?;
+
+    Ok(web::Json(rules))
+}
+
+/// **Add new Alert Rule**
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/alert-rules/1/ -H 'Content-Type: application/json' \
+/// -d '{ "name": "Filler", "condition": "filler_ratio", "threshold": 20, "minutes": 60, "severity": "warning", "targets": "mail,telegram" }' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[post("/alert-rules/{id}/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn add_alert_rule(
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    data: web::Json<AlertRule>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let mut rule = data.into_inner();
+    rule.channel_id = *id;
+
+    alerts::validate(&rule)?;
+    handles::insert_alert_rule(&pool, &rule).await?;
+
+    Ok("Add alert rule Success")
+}
+
+/// **Update Alert Rule**
+///
+/// ```BASH
+/// curl -X PUT http://127.0.0.1:8787/api/alert-rules/1/1 -H 'Content-Type: application/json' \
+/// -d '{ "name": "Playlist", "condition": "playlist_missing", "time": "18:00", "severity": "error", "targets": "" }' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[put("/alert-rules/{channel}/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn update_alert_rule(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i32, i32)>,
+    data: web::Json<AlertRule>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (channel, id) = path.into_inner();
+    let mut rule = data.into_inner();
+    rule.channel_id = channel;
+
+    alerts::validate(&rule)?;
+
+    if handles::update_alert_rule(&pool, id, &rule)
+        .await?
+        .rows_affected()
+        == 0
+    {
+        return Err(ServiceError::BadRequest("Alert rule not found".to_string()));
+    }
+
+    Ok("Update Success")
+}
+
+/// **Delete Alert Rule**
+///
+/// ```BASH
+/// curl -X DELETE http://127.0.0.1:8787/api/alert-rules/1/1 -H 'Content-Type: application/json' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[delete("/alert-rules/{channel}/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn delete_alert_rule(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i32, i32)>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (channel, id) = path.into_inner();
+
+    handles::delete_alert_rule(&pool, channel, id).await?;
+
+    Ok("Delete alert rule Success")
+}
+
+/// #### Live Events
+///
+/// Live events replace the playout with a remote source, between `start_time` and `end_time` (unix timestamps).
+/// At the end the playout goes back to the playlist, or folder.
+///
+/// **Get all Live Events**
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/live-events/1 -H 'Content-Type: application/json' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/live-events/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn get_live_events(
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let events = handles::select_live_events(&pool, *id).await?;
+
+    Ok(web::Json(events))
+}
+
+async fn check_live_event(
+    pool: &DbPool,
+    event: &LiveEvent,
+    id: Option<i32>,
+) -> Result<(), ServiceError> {
+    if event.source.trim().is_empty() {
+        return Err(ServiceError::BadRequest("Source is missing".to_string()));
+    }
+
+    if event.end_time <= event.start_time {
+        return Err(ServiceError::BadRequest(
+            "End time must be after start time".to_string(),
+        ));
+    }
+
+    let overlapping = handles::select_overlapping_live_events(
+        pool,
+        event.channel_id,
+        event.start_time,
+        event.end_time,
+    )
+    .await?;
+
+    if let Some(other) = overlapping.iter().find(|e| Some(e.id) != id) {
+        return Err(ServiceError::Conflict(format!(
+            "Overlaps with live event \"{}\"",
+            other.name
+        )));
+    }
+
+    Ok(())
+}
+
+/// Stop the event source when the event is running, the scheduler picks up the changes.
+async fn restart_live_event(
+    controllers: &Mutex<ChannelController>,
+    event: &LiveEvent,
+) -> Result<(), ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(event.channel_id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let now = time_now(&None).timestamp();
+
+    if manager.event_is_alive.load(Ordering::SeqCst)
+        && event.start_time <= now
+        && event.end_time > now
+    {
+        manager.stop(ProcessUnit::Event).await;
+    }
+
+    Ok(())
+}
+
+/// **Add new Live Event**
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/live-events/1/ -H 'Content-Type: application/json' \
+/// -d '{ "name": "Concert", "source": "srt://127.0.0.1:9000?mode=caller", "start_time": 1767294000, "end_time": 1767299400 }' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[post("/live-events/{id}/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn add_live_event(
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    data: web::Json<LiveEvent>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let mut event = data.into_inner();
+    event.channel_id = *id;
+
+    check_live_event(&pool, &event, None).await?;
+    handles::insert_live_event(&pool, &event).await?;
+
+    Ok("Add live event Success")
+}
+
+/// **Update Live Event**
+///
+/// A running event switches to the new source, or ends when it is moved out of the current time.
+///
+/// ```BASH
+/// curl -X PUT http://127.0.0.1:8787/api/live-events/1/1 -H 'Content-Type: application/json' \
+/// -d '{ "name": "Concert", "source": "srt://127.0.0.1:9000?mode=caller", "start_time": 1767294000, "end_time": 1767301200 }' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[put("/live-events/{channel}/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn update_live_event(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i32, i32)>,
+    data: web::Json<LiveEvent>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (channel, id) = path.into_inner();
+    let mut event = data.into_inner();
+    event.channel_id = channel;
+
+    let Some(old) = handles::select_live_event(&pool, channel, id).await? else {
+        return Err(ServiceError::BadRequest("Live event not found".to_string()));
+    };
+
+    check_live_event(&pool, &event, Some(id)).await?;
+    handles::update_live_event(&pool, id, &event).await?;
+    restart_live_event(&controllers, &old).await?;
+
+    Ok("Update Success")
+}
+
+/// **Delete Live Event**
+///
+/// ```BASH
+/// curl -X DELETE http://127.0.0.1:8787/api/live-events/1/1 -H 'Content-Type: application/json' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[delete("/live-events/{channel}/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn delete_live_event(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i32, i32)>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (channel, id) = path.into_inner();
+
+    if let Some(event) = handles::select_live_event(&pool, channel, id).await? {
+        handles::delete_live_event(&pool, channel, id).await?;
+        restart_live_event(&controllers, &event).await?;
+    }
+
+    Ok("Delete live event Success")
+}
+
+/// #### Ingest Keys
+///
+/// **Get all Ingest Keys**
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/ingest-keys/1 -H 'Content-Type: application/json' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/ingest-keys/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn get_ingest_keys(
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let keys = handles::select_ingest_keys(&pool, *id).await?;
+
+    Ok(web::Json(keys))
+}
+
+/// Keys are part of the stream URL, so only URL safe characters are allowed.
+async fn check_ingest_key(
+    pool: &DbPool,
+    key: &IngestKey,
+    id: Option<i32>,
+) -> Result<(), ServiceError> {
+    if key.name.trim().is_empty() {
+        return Err(ServiceError::BadRequest("Name is missing".to_string()));
+    }
+
+    if key.stream_key.chars().count() < 8
+        || !key
+            .stream_key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(ServiceError::BadRequest(
+            "Stream key needs at least 8 characters, only letters, numbers, - and _ are allowed"
+                .to_string(),
+        ));
+    }
+
+    if let Some(other) =
+        handles::select_ingest_key_by_key(pool, key.channel_id, &key.stream_key).await?
+    {
+        if Some(other.id) != id {
+            return Err(ServiceError::Conflict(format!(
+                "Stream key is used by \"{}\"",
+                other.name
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// **Add new Ingest Key**
+///
+/// Without `stream_key` a random key is generated, the new key is in the response.
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/ingest-keys/1/ -H 'Content-Type: application/json' \
+/// -d '{ "name": "Studio A" }' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[post("/ingest-keys/{id}/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn add_ingest_key(
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    data: web::Json<IngestKey>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let mut key = data.into_inner();
+    key.channel_id = *id;
+
+    if key.stream_key.is_empty() {
+        key.stream_key = rand::rng()
+            .sample_iter(&Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect();
+    }
+
+    check_ingest_key(&pool, &key, None).await?;
+    handles::insert_ingest_key(&pool, &key).await?;
+
+    let key = handles::select_ingest_key_by_key(&pool, key.channel_id, &key.stream_key).await?;
+
+    Ok(web::Json(key))
+}
+
+/// **Update Ingest Key**
+///
+/// Without `stream_key` the key stays the same. Changes apply to the next connect, a running stream continues.
+///
+/// ```BASH
+/// curl -X PUT http://127.0.0.1:8787/api/ingest-keys/1/1 -H 'Content-Type: application/json' \
+/// -d '{ "name": "Studio A", "active": false }' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[put("/ingest-keys/{channel}/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn update_ingest_key(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i32, i32)>,
+    data: web::Json<IngestKey>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (channel, id) = path.into_inner();
+    let mut key = data.into_inner();
+    key.channel_id = channel;
+
+    let Some(old) = handles::select_ingest_key(&pool, channel, id).await? else {
+        return Err(ServiceError::BadRequest("Ingest key not found".to_string()));
+    };
+
+    if key.stream_key.is_empty() {
+        key.stream_key = old.stream_key;
+    }
+
+    check_ingest_key(&pool, &key, Some(id)).await?;
+    handles::update_ingest_key(&pool, id, &key).await?;
+
+    Ok("Update Success")
+}
+
+/// **Delete Ingest Key**
+///
+/// ```BASH
+/// curl -X DELETE http://127.0.0.1:8787/api/ingest-keys/1/1 -H 'Content-Type: application/json' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[delete("/ingest-keys/{channel}/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
+)]
+async fn delete_ingest_key(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i32, i32)>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (channel, id) = path.into_inner();
+
+    handles::delete_ingest_key(&pool, channel, id).await?;
+
+    Ok("Delete ingest key Success")
+}
+
+/// #### API Tokens
+///
+/// Long lived tokens for scripts and other systems, they are used like the access token:\
+/// `"Authorization: Bearer <API TOKEN>"`
+///
+/// The scope is `read_only`, `playlist_edit` or `file_manage`, every scope can read.
+/// Requests with a token have the rights of a user, for the channels of the token.
+///
+/// **Get API Tokens**
+///
+/// Global admins get all tokens, channel admins the tokens, which they have created.
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/api-tokens -H 'Content-Type: application/json' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/api-tokens")]
+#[protect(any("Role::GlobalAdmin", "Role::ChannelAdmin"), ty = "Role")]
+async fn get_api_tokens(
+    pool: web::Data<DbPool>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let owner = (!role.has_authority(&Role::GlobalAdmin)).then_some(user.id);
+    let tokens = handles::select_api_tokens(&pool, owner).await?;
+
+    Ok(web::Json(tokens))
+}
+
+/// **Add API Token**
+///
+/// `expires` is an optional unix timestamp. The token is only in this response, it can't be shown again.
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/api-tokens/ -H 'Content-Type: application/json' \
+/// -d '{ "name": "Traffic", "scope": "playlist_edit", "channel_ids": [1] }' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[post("/api-tokens/")]
+#[protect(any("Role::GlobalAdmin", "Role::ChannelAdmin"), ty = "Role")]
+async fn add_api_token(
+    pool: web::Data<DbPool>,
+    data: web::Json<ApiToken>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let mut token = data.into_inner();
+    let now = Utc::now().timestamp();
+
+    if token.name.trim().is_empty() {
+        return Err(ServiceError::BadRequest("Name is missing".to_string()));
+    }
+
+    if token.channel_ids.is_empty() {
+        return Err(ServiceError::BadRequest(
+            "Token needs at least one channel".to_string(),
+        ));
+    }
+
+    if !role.has_authority(&Role::GlobalAdmin)
+        && !token.channel_ids.iter().all(|c| user.channels.contains(c))
+    {
+        return Err(ServiceError::Forbidden(
+            "No access to the channels of the token".to_string(),
+        ));
+    }
+
+    if token.expires.is_some_and(|e| e <= now) {
+        return Err(ServiceError::BadRequest(
+            "Expiration is in the past".to_string(),
+        ));
+    }
+
+    let secret = auth::generate_api_token();
+
+    token.user_id = user.id;
+    token.prefix = secret
+        .chars()
+        .take(auth::API_TOKEN_PREFIX.len() + 6)
+        .collect();
+    token.created = now;
+    token.id = handles::insert_api_token(&pool, &token, &auth::token_hash(&secret)).await?;
+    token.token = Some(secret);
+
+    info!(
+        "API token \"{}\" with scope {} created, for channels {:?}",
+        token.name, token.scope, token.channel_ids
+    );
+
+    Ok(web::Json(token))
+}
+
+/// **Delete API Token**
+///
+/// The token is invalid immediately.
+///
+/// ```BASH
+/// curl -X DELETE http://127.0.0.1:8787/api/api-tokens/1 -H 'Content-Type: application/json' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[delete("/api-tokens/{id}")]
+#[protect(any("Role::GlobalAdmin", "Role::ChannelAdmin"), ty = "Role")]
+async fn delete_api_token(
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let owner = (!role.has_authority(&Role::GlobalAdmin)).then_some(user.id);
+    let result = handles::delete_api_token(&pool, *id, owner).await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ServiceError::BadRequest("API token not found".to_string()));
+    }
+
+    Ok("Delete API token Success")
+}
+
+/// ### ffplayout controlling
+///
+/// here we communicate with the engine for:
+/// - jump to last or next clip
 /// - reset playlist state
 /// - get infos about current, next, last clip
 /// - send text to the engine, for overlaying it (as lower third etc.)
 ///
-/// **Send Text to ffplayout**
+/// **Send Text to ffplayout**
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/control/1/text/ \
+/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>' \
+/// -d '{"text": "Hello from ffplayout", "x": "(w-text_w)/2", "y": "(h-text_h)/2", fontsize": "24", "line_spacing": "4", "fontcolor": "#ffffff", "box": "1", "boxcolor": "#000000", "boxborderw": "4", "alpha": "1.0"}'
+/// ```
+#[post("/control/{id}/text/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.control(*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn send_text_message(
+    id: web::Path<i32>,
+    data: web::Json<TextFilter>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+
+    match send_message(manager, data.into_inner()).await {
+        Ok(res) => Ok(web::Json(res)),
+        Err(e) => Err(e),
+    }
+}
+
+/// **Control HTML Overlay**
+///
+/// Load a page in the browser of the HTML overlay, or run a script in the loaded page,
+/// like to start an animation. Both can be sent together, the page is loaded first.
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/control/1/html/ \
+/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>' \
+/// -d '{"url": "https://example.org/lower-third.html", "script": "play()"}'
+/// ```
+#[post("/control/{id}/html/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.control(*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn control_html(
+    id: web::Path<i32>,
+    data: web::Json<HtmlControl>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let control = data.into_inner();
+
+    if control.url.is_none() && control.script.is_none() {
+        return Err(ServiceError::BadRequest(
+            "Send an url or a script".to_string(),
+        ));
+    }
+
+    if !manager.html.control(control) {
+        return Err(ServiceError::Conflict(
+            "HTML overlay is not running".to_string(),
+        ));
+    }
+
+    Ok("Send HTML control Success")
+}
+
+/// **Emergency Override**
+///
+/// Preempt the program, also a live ingest or event, with the emergency slate from the config,
+/// or release it. The playlist continues at the current time. Start and release are written
+/// to the channel log, with the optional reason.
+///
+/// - start
+/// - release
+/// - status
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/control/1/emergency/ \
+/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>' \
+/// -d '{"command": "start", "reason": "Technical difficulties"}'
+/// ```
+#[post("/control/{id}/emergency/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.control(*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn control_emergency(
+    id: web::Path<i32>,
+    data: web::Json<Emergency>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+
+    if emergency_control(&manager, &data.into_inner()).await? {
+        Ok(web::Json("active"))
+    } else {
+        Ok(web::Json("inactive"))
+    }
+}
+
+/// **Control Voice-Over**
+///
+/// Play a file from the storage as voice-over, which ducks the program audio,
+/// or stop the playing file. A new file replaces the playing one.
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/control/1/voice/ \
+/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>' \
+/// -d '{"file": "announcements/next.mp3"}'
+/// ```
+#[post("/control/{id}/voice/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.control(*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn control_voice(
+    id: web::Path<i32>,
+    data: web::Json<VoiceControl>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let control = data.into_inner();
+    let (socket, storage) = {
+        let config = manager.config.lock().await;
+
+        (
+            config.ingest.voice_socket.clone(),
+            config.channel.storage.clone(),
+        )
+    };
+
+    if socket.is_none() || !manager.is_alive.load(Ordering::SeqCst) {
+        return Err(ServiceError::Conflict(
+            "Voice-over is not running".to_string(),
+        ));
+    }
+
+    if control.stop {
+        manager.voice.stop_file();
+
+        return Ok("Stop voice-over Success");
+    }
+
+    let Some(file) = control.file else {
+        return Err(ServiceError::BadRequest("Send a file or stop".to_string()));
+    };
+    let (path, _, _) = norm_abs_path(&storage, &file)?;
+
+    if !path.is_file() {
+        return Err(ServiceError::BadRequest(format!("File not found: {file}")));
+    }
+
+    tokio::spawn(play_file(
+        manager.clone(),
+        path.to_string_lossy().to_string(),
+    ));
+
+    Ok("Play voice-over Success")
+}
+
+/// **Control Playout**
+///
+/// - next
+/// - back
+/// - reset
+/// - skip: abort the running clip and continue with the next one, in playlist mode
+///   the time shift moves the rest of the playlist forward
+/// - seek: play the running clip from `position` (seconds, like the `in` point),
+///   the encoder keeps running
+/// - dump: drop the held part of the delayed live ingest, the filler covers
+///   the gap until the delay buffer is full again
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/control/1/playout/ -H 'Content-Type: application/json'
+/// -d '{ "command": "reset" }' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/control/1/playout/ -H 'Content-Type: application/json'
+/// -d '{ "control": "seek", "position": 120.5 }' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[post("/control/{id}/playout/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.control(*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn control_playout(
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    control: web::Json<ControlParams>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+
+    if manager.is_processing.load(Ordering::SeqCst) {
+        return Err(ServiceError::Conflict(
+            "A command is already being processed, please wait".to_string(),
+        ));
+    }
+
+    manager.is_processing.store(true, Ordering::SeqCst);
+
+    let resp = match control_state(&pool, &manager, &control).await {
+        Ok(res) => Ok(web::Json(res)),
+        Err(e) => Err(e),
+    };
+
+    manager.is_processing.store(false, Ordering::SeqCst);
+
+    resp
+}
+
+/// **Get current Clip**
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/control/1/media/current
+/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+///
+/// **Response:**
+///
+/// ```JSON
+///     {
+///       "media": {
+///         "category": "",
+///         "duration": 154.2,
+///         "out": 154.2,
+///         "in": 0.0,
+///         "source": "/opt/tv-media/clip.mp4"
+///       },
+///       "index": 39,
+///       "ingest": false,
+///       "mode": "playlist",
+///       "played": 67.808
+///     }
+/// ```
+#[get("/control/{id}/media/current")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn media_current(
+    id: web::Path<i32>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+
+    let media_map = get_data_map(&manager).await;
+
+    Ok(web::Json(media_map))
+}
+
+/// **Status of the Additional Outputs**
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/control/1/outputs -H 'Content-Type: application/json' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+///
+/// **Response:**
+///
+/// ```JSON
+///     [
+///       {
+///         "id": 1,
+///         "name": "YouTube",
+///         "running": true,
+///         "restarts": 0,
+///         "written": 104857600,
+///         "dropped": 0
+///       }
+///     ]
+/// ```
+#[get("/control/{id}/outputs")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn outputs_status(
+    id: web::Path<i32>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+
+    let status = manager
+        .outputs
+        .lock()
+        .await
+        .iter()
+        .map(|o| o.status())
+        .collect::<Vec<_>>();
+
+    Ok(web::Json(status))
+}
+
+/// **Encoder Statistics**
+///
+/// Statistics of the main encoder, from its progress report. `behind` is true, when the encoder
+/// runs slower than real time. Without a running encoder, the response is `null`.
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/control/1/encoder -H 'Authorization: Bearer <TOKEN>'
+/// ```
+///
+/// **Response:**
+///
+/// ```JSON
+///     {
+///       "frame": 45000,
+///       "fps": 25.0,
+///       "bitrate": 4012.3,
+///       "speed": 1.0,
+///       "dup_frames": 0,
+///       "drop_frames": 0,
+///       "behind": false,
+///       "black": false,
+///       "silent": false,
+///       "loudness": {
+///         "momentary": -22.8,
+///         "short_term": -23.4,
+///         "integrated": -23.1,
+///         "item": -23.0
+///       }
+///     }
+/// ```
+#[get("/control/{id}/encoder")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn encoder_stats(
+    id: web::Path<i32>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+
+    let stats = manager.progress.lock().await.as_ref().map(|p| p.stats());
+
+    Ok(web::Json(stats))
+}
+
+/// **Program Snapshot**
+///
+/// Latest frame of the program as JPEG, it is refreshed every few seconds.
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/player/1/snapshot.jpg -H 'Authorization: Bearer <TOKEN>' -o snapshot.jpg
+/// ```
+#[get("/player/{id}/snapshot.jpg")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn get_snapshot(
+    id: web::Path<i32>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let config = manager.config.lock().await.clone();
+
+    if !config.output.snapshot {
+        return Err(ServiceError::BadRequest(
+            "Snapshot is not enabled".to_string(),
+        ));
+    }
+
+    let path = snapshot::path(&config);
+    let fresh = fs::metadata(&path)
+        .await
+        .and_then(|m| m.modified())
+        .is_ok_and(|t| {
+            t.elapsed()
+                .is_ok_and(|age| age < snapshot::SNAPSHOT_MAX_AGE)
+        });
+
+    if !fresh || !manager.is_alive.load(Ordering::SeqCst) {
+        return Err(ServiceError::ServiceUnavailable(
+            "No current snapshot".to_string(),
+        ));
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type("image/jpeg")
+        .insert_header(header::CacheControl(vec![header::CacheDirective::NoCache]))
+        .body(fs::read(path).await?))
+}
+
+/// **Screenshot Archive**
+///
+/// Screenshots of the day, the date defaults to today. The URLs are signed for one hour.
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/archive/1/screenshots?date=2026-10-17 -H 'Authorization: Bearer <TOKEN>'
+/// ```
+///
+/// **Response:**
+///
+/// ```JSON
+/// [
+///     {
+///         "time": "14:10:00",
+///         "path": "screenshots/2026-10-17/14-10-00.jpg",
+///         "url": "/file/1/screenshots/2026-10-17/14-10-00.jpg?expires=1760710200&signature=<HMAC>"
+///     }
+/// ]
+/// ```
+#[get("/archive/{id}/screenshots")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn get_screenshots(
+    id: web::Path<i32>,
+    query: web::Query<DateObj>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let config = manager.config.lock().await.clone();
+    let storage = manager.storage.lock().await.clone();
+    let date = if query.date.is_empty() {
+        Local::now().date_naive()
+    } else {
+        NaiveDate::parse_from_str(&query.date, "%Y-%m-%d")?
+    };
+
+    let shots = archive::screenshots(&config, &storage, date)
+        .await?
+        .into_iter()
+        .map(|(time, path)| {
+            let url = auth::sign_media_url(*id, &path, 3600).url;
+
+            ScreenshotObj { time, path, url }
+        })
+        .collect::<Vec<_>>();
+
+    Ok(web::Json(shots))
+}
+
+/// #### ffplayout Process Control
+///
+/// Control ffplayout process, like:
+/// - start
+/// - stop
+/// - restart
+/// - status
+/// - standby: loop the standby slate, or stop the encoder when no slate is set
+/// - resume: continue the playlist at the current time
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/control/1/process/
+/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
+/// -d '{"command": "start"}'
+/// ```
+#[post("/control/{id}/process/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.control(*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn process_control(
+    id: web::Path<i32>,
+    proc: web::Json<Process>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    manager.list_init.store(true, Ordering::SeqCst);
+
+    if manager.is_processing.load(Ordering::SeqCst) {
+        return Err(ServiceError::Conflict(
+            "A command is already being processed, please wait".to_string(),
+        ));
+    }
+
+    manager.is_processing.store(true, Ordering::SeqCst);
+
+    match proc.into_inner().command {
+        ProcessCtl::Status => {
+            manager.is_processing.store(false, Ordering::SeqCst);
+
+            if manager.standby.load(Ordering::SeqCst) {
+                return Ok(web::Json("standby"));
+            }
+            if manager.is_alive.load(Ordering::SeqCst) {
+                return Ok(web::Json("active"));
+            }
+            return Ok(web::Json("not running"));
+        }
+        ProcessCtl::Start => {
+            if manager.standby.load(Ordering::SeqCst) {
+                manager.set_standby(false).await?;
+            }
+
+            if !manager.is_alive.load(Ordering::SeqCst) {
+                manager.channel.lock().await.active = true;
+                manager.start().await?;
+            }
+        }
+        ProcessCtl::Stop => {
+            manager.channel.lock().await.active = false;
+            manager.stop_all(true).await;
+        }
+        ProcessCtl::Restart => {
+            manager.channel.lock().await.active = false;
+            manager.stop_all(false).await;
+
+            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+            manager.channel.lock().await.active = true;
+            manager.start().await?;
+        }
+        ProcessCtl::Standby => {
+            let slate = manager.config.lock().await.storage.slate.clone();
+            manager.set_standby(true).await?;
+
+            if slate.is_empty() {
+                manager.channel.lock().await.active = false;
+                manager.stop_all(true).await;
+            } else {
+                // the player continues with the slate
+                manager.stop(ProcessUnit::Decoder).await;
+            }
+        }
+        ProcessCtl::Resume => {
+            manager.set_standby(false).await?;
+
+            if manager.is_alive.load(Ordering::SeqCst) {
+                // stop the slate, the playlist continues at the current time
+                manager.stop(ProcessUnit::Decoder).await;
+            } else {
+                manager.channel.lock().await.active = true;
+                manager.start().await?;
+            }
+        }
+    }
+
+    manager.is_processing.store(false, Ordering::SeqCst);
+
+    Ok(web::Json("Success"))
+}
+
+/// **Startup Queue**
+///
+/// Get the channels which are started on boot, with their stage and state.
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/control/startup -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/control/startup")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role"
+)]
+pub async fn startup_queue(
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let queue = controllers.lock().await.startup.clone();
+    let entries = queue
+        .entries
+        .lock()
+        .await
+        .iter()
+        .filter(|e| role.has_authority(&Role::GlobalAdmin) || user.channels.contains(&e.channel_id))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    Ok(web::Json(entries))
+}
+
+/// #### ffplayout Playlist Operations
+///
+/// **Get playlist**
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/playlist/1?date=2022-06-20
+/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/playlist/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn get_playlist(
+    id: web::Path<i32>,
+    obj: web::Query<DateObj>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let config = manager.config.lock().await.clone();
+
+    match read_playlist(&manager.db_pool, &config, obj.date.clone()).await {
+        Ok(playlist) => Ok(web::Json(playlist)),
+        Err(e) => Err(e),
+    }
+}
+
+/// **Validate playlist**
+///
+/// Check the playlist from the given date, without playing it. The report lists missing
+/// and unreadable sources, durations which differs from the probed files,
+/// items outside of their validity dates and gaps or overlaps at the day end.
+/// Soon expiring items are listed under `warnings`.
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/playlist/1/validate?date=2022-06-20
+/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/playlist/{id}/validate")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn get_playlist_report(
+    id: web::Path<i32>,
+    obj: web::Query<DateObj>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let config = manager.config.lock().await.clone();
+    let storage = manager.storage.lock().await.clone();
+    let library = library_validity(&manager.db_pool, &config).await;
+
+    let playlist = read_playlist(&manager.db_pool, &config, obj.date.clone()).await?;
+    let report = playlist_report(&config, &storage, &playlist, &library).await;
+
+    Ok(web::Json(report))
+}
+
+/// **Save playlist**
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/playlist/1/
+/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
+/// --data "{<JSON playlist data>}"
+/// ```
+#[post("/playlist/{id}/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.playlists(*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn save_playlist(
+    id: web::Path<i32>,
+    data: web::Json<JsonPlaylist>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let config = manager.config.lock().await.clone();
+    let storage = manager.storage.lock().await.clone();
+
+    let mut data = data.into_inner();
+    for media in &mut data.program {
+        let cloned_media_source = media.source.clone();
+        media.source = storage.sanitized_file_path(&cloned_media_source);
+    }
+
+    match write_playlist(&manager.db_pool, &config, data, user.id).await {
+        Ok(res) => Ok(web::Json(res)),
+        Err(e) => Err(e),
+    }
+}
+
+/// **Trim Playlist Item**
+///
+/// Set frame accurate in/out points of one playlist item, in seconds of the media file.
+/// They are independent of the `in`/`out` values, which are used for fitting the clip in time,
+/// `null` removes a trim point. Response is the updated item.
+///
+/// ```BASH
+/// curl -X PUT http://127.0.0.1:8787/api/playlist/1/trim/
+/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
+/// --data '{"date": "2022-06-20", "index": 3, "trim_in": 4.2, "trim_out": 118.04}'
+/// ```
+#[put("/playlist/{id}/trim/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.playlists(*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn trim_playlist_item(
+    id: web::Path<i32>,
+    data: web::Json<TrimObj>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let config = manager.config.lock().await.clone();
+    let storage = manager.storage.lock().await.clone();
+
+    let item = trim_item(&manager.db_pool, &config, &storage, &data, user.id).await?;
+
+    Ok(web::Json(item))
+}
+
+/// **Convert Legacy Playlists**
+///
+/// Convert all playlists from older ffplayout formats to the current schema.
+/// The response lists every converted or unreadable playlist, with notes about renamed fields
+/// and program items which could not be converted. Use `dry_run=true` to only get the report.
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/playlist/1/convert/?dry_run=true
+/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[post("/playlist/{id}/convert/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn convert_playlist(
+    id: web::Path<i32>,
+    obj: web::Query<ConvertObj>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let config = manager.config.lock().await.clone();
+
+    let reports = convert_playlists(&config, obj.dry_run).await?;
+
+    Ok(web::Json(reports))
+}
+
+/// **Playlist History**
+///
+/// Changes of the playlist items from the given date, newest revision first.
+/// Every entry has the `action` (`add`, `change` or `remove`), the user and the item before and after
+/// the change. Only for channels, which store their playlists in the database.
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/playlist/1/history?date=2022-06-20
+/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/playlist/{id}/history")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn get_playlist_history(
+    id: web::Path<i32>,
+    obj: web::Query<DateObj>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+
+    if !manager.config.lock().await.playlist.database {
+        return Err(ServiceError::Conflict(
+            "Playlists of this channel are not stored in the database".to_string(),
+        ));
+    }
+
+    let history = handles::select_playlist_history(&manager.db_pool, *id, &obj.date).await?;
+
+    Ok(web::Json(history))
+}
+
+/// **Find Scheduled Items**
+///
+/// Items from `from` (default today) on, which source or title contains the `search` text.
+/// `start` is the begin of the item in seconds of the day. Only for channels, which store
+/// their playlists in the database.
+///
+/// ```BASH
+/// curl -X GET 'http://127.0.0.1:8787/api/playlist/1/scheduled?search=promo.mp4&limit=50'
+/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/playlist/{id}/scheduled")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn get_scheduled_items(
+    id: web::Path<i32>,
+    obj: web::Query<ScheduledObj>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let config = manager.config.lock().await.clone();
+
+    if !config.playlist.database {
+        return Err(ServiceError::Conflict(
+            "Playlists of this channel are not stored in the database".to_string(),
+        ));
+    }
+
+    if obj.search.trim().is_empty() {
+        return Err(ServiceError::BadRequest(
+            "Search text is missing".to_string(),
+        ));
+    }
+
+    let start_sec = config.playlist.start_sec.unwrap_or_default();
+    let from = if obj.from.is_empty() {
+        playlist_clock(&config.channel.timezone, start_sec)
+            .0
+            .format("%Y-%m-%d")
+            .to_string()
+    } else {
+        obj.from.clone()
+    };
+    let mut items = handles::select_scheduled_items(
+        &manager.db_pool,
+        *id,
+        &from,
+        obj.search.trim(),
+        obj.limit.clamp(1, 1000),
+    )
+    .await?;
+
+    for item in &mut items {
+        item.start += start_sec;
+    }
+
+    Ok(web::Json(items))
+}
+
+/// **Import JSON Playlists**
+///
+/// Copy the JSON playlists from the playlist folder into the database, the response lists the
+/// dates of the new or changed playlists. Run it before the channel is switched to playlists
+/// in the database.
 ///
 /// ```BASH
-/// curl -X POST http://127.0.0.1:8787/api/control/1/text/ \
-/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>' \
-/// -d '{"text": "Hello from ffplayout", "x": "(w-text_w)/2", "y": "(h-text_h)/2", fontsize": "24", "line_spacing": "4", "fontcolor": "#ffffff", "box": "1", "boxcolor": "#000000", "boxborderw": "4", "alpha": "1.0"}'
+/// curl -X POST http://127.0.0.1:8787/api/playlist/1/import-json/
+/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
 /// ```
-#[post("/control/{id}/text/")]
+#[post("/playlist/{id}/import-json/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn import_json(
+    id: web::Path<i32>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let config = manager.config.lock().await.clone();
+
+    let dates = import_json_playlists(&manager.db_pool, &config, user.id).await?;
+
+    Ok(web::Json(dates))
+}
+
+/// **Export JSON Playlists**
+///
+/// Write all playlists from the database as JSON files into the playlist folder,
+/// existing files are overwritten. The response lists the dates of the written playlists.
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/playlist/1/export-json/
+/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[post("/playlist/{id}/export-json/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn export_json(
+    id: web::Path<i32>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let config = manager.config.lock().await.clone();
+
+    let dates = export_json_playlists(&manager.db_pool, &config).await?;
+
+    Ok(web::Json(dates))
+}
+
+/// **Generate Playlist**
+///
+/// A new playlist will be generated and response.
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/playlist/1/generate/2022-06-20
+/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
+/// /// --data '{ "paths": [<list of paths>] }' # <- data is optional
+/// ```
+///
+/// Or with template:
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/playlist/1/generate/2023-00-05
+/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
+/// --data '{"template": {"sources": [\
+///            {"start": "00:00:00", "duration": "10:00:00", "shuffle": true, "paths": ["path/1", "path/2"]}, \
+///            {"start": "10:00:00", "duration": "14:00:00", "shuffle": false, "paths": ["path/3", "path/4"]}]}}'
+/// ```
+#[post("/playlist/{id}/generate/{date}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.playlists(params.0) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn gen_playlist(
+    params: web::Path<(i32, String)>,
+    data: Option<web::Json<PathsObj>>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (id, date) = params.into_inner();
+    let manager = controllers
+        .lock()
+        .await
+        .get(id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    manager.config.lock().await.general.generate = Some(vec![date.clone()]);
+    let storage = manager.config.lock().await.channel.storage.clone();
+
+    if let Some(obj) = data {
+        if let Some(paths) = &obj.paths {
+            let mut path_list = vec![];
+
+            for path in paths {
+                let (p, _, _) = norm_abs_path(&storage, path)?;
+
+                path_list.push(p);
+            }
+
+            manager.config.lock().await.storage.paths = path_list;
+        }
+
+        manager
+            .config
+            .lock()
+            .await
+            .general
+            .template
+            .clone_from(&obj.template);
+    }
+
+    match generate_playlist(manager).await {
+        Ok(playlist) => Ok(web::Json(playlist)),
+        Err(e) => Err(e),
+    }
+}
+
+/// **Delete Playlist**
+///
+/// ```BASH
+/// curl -X DELETE http://127.0.0.1:8787/api/playlist/1/2022-06-20
+/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[delete("/playlist/{id}/{date}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.playlists(params.0) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn del_playlist(
+    params: web::Path<(i32, String)>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (id, date) = params.into_inner();
+    let manager = controllers
+        .lock()
+        .await
+        .get(id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let config = manager.config.lock().await.clone();
+
+    match delete_playlist(&manager.db_pool, &config, &date, user.id).await {
+        Ok(m) => Ok(web::Json(m)),
+        Err(e) => Err(e),
+    }
+}
+
+/// ### Log file
+///
+/// **Read Log File**
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/log/1?date=2022-06-20
+/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/log/{id}")]
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
     expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
 )]
-pub async fn send_text_message(
+pub async fn get_log(
     id: web::Path<i32>,
-    data: web::Json<TextFilter>,
+    log: web::Query<DateObj>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    read_log_file(&id, &log.date).await
+}
+
+/// **Tail Log File**
+///
+/// Newest lines first, `level` gives only lines with this level or higher,
+/// `page` and `limit` (max 1000) split the lines in pages.
+///
+/// ```BASH
+/// curl -X GET 'http://127.0.0.1:8787/api/log/1/tail?level=warning&page=1&limit=100'
+/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/log/{id}/tail")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn get_log_tail(
+    id: web::Path<i32>,
+    query: web::Query<LogTailObj>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let page = tail_log(*id, &query.date, &query.level, query.page, query.limit).await?;
+
+    Ok(web::Json(page))
+}
+
+/// ### File Operations
+///
+/// **Get File/Folder List**
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/file/1/browse/ -H 'Content-Type: application/json'
+/// -d '{ "source": "/" }' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[post("/file/{id}/browse/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn file_browser(
+    id: web::Path<i32>,
+    data: web::Json<PathObject>,
     controllers: web::Data<Mutex<ChannelController>>,
     role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
+    duration: web::Data<SharedMediaMap>,
 ) -> Result<impl Responder, ServiceError> {
     let manager = controllers
         .lock()
@@ -926,36 +4354,100 @@ pub async fn send_text_message(
         .get(*id)
         .await
         .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let storage = manager.storage.lock().await.clone();
+
+    match storage.browser(&data.into_inner(), duration.clone()).await {
+        Ok(obj) => Ok(web::Json(obj)),
+        Err(e) => Err(e),
+    }
+}
+
+/// **Create Folder**
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/file/1/create-folder/ -H 'Content-Type: application/json'
+/// -d '{"source": "<FOLDER PATH>"}' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[post("/file/{id}/create-folder/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.files(*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn add_dir(
+    id: web::Path<i32>,
+    data: web::Json<PathObject>,
+    pool: web::Data<DbPool>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<HttpResponse, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let storage = manager.storage.lock().await;
+    let result = storage.mkdir(&data).await;
+
+    record(
+        &pool,
+        *id,
+        user.id,
+        AuditAction::Mkdir,
+        &data.source,
+        None,
+        &result,
+    )
+    .await;
+    result?;
 
-    match send_message(manager, data.into_inner()).await {
-        Ok(res) => Ok(web::Json(res)),
-        Err(e) => Err(e),
+    Ok(HttpResponse::Ok().into())
+}
+
+async fn protect_referenced(
+    conn: &DbPool,
+    config: &PlayoutConfig,
+    storage: &StorageBackend,
+    source: &str,
+) -> Result<(), ServiceError> {
+    let dates = playlist_references(conn, config, storage, source).await?;
+
+    if dates.is_empty() {
+        Ok(())
+    } else {
+        Err(ServiceError::Conflict(format!(
+            "Source is used in playlists from: {}",
+            dates.join(", ")
+        )))
     }
 }
 
-/// **Control Playout**
+/// **Rename File**
 ///
-/// - next
-/// - back
-/// - reset
+/// Files which are used in the playlists of the next days are protected,
+/// set `"force": true` to rename them anyway.
 ///
 /// ```BASH
-/// curl -X POST http://127.0.0.1:8787/api/control/1/playout/ -H 'Content-Type: application/json'
-/// -d '{ "command": "reset" }' -H 'Authorization: Bearer <TOKEN>'
+/// curl -X POST http://127.0.0.1:8787/api/file/1/rename/ -H 'Content-Type: application/json'
+/// -d '{"source": "<SOURCE>", "target": "<TARGET>"}' -H 'Authorization: Bearer <TOKEN>'
 /// ```
-#[post("/control/{id}/playout/")]
+#[allow(clippy::too_many_arguments)]
+#[post("/file/{id}/rename/")]
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.files(*id) || role.has_authority(&Role::GlobalAdmin)"
 )]
-pub async fn control_playout(
-    pool: web::Data<Pool<Sqlite>>,
+pub async fn move_rename(
     id: web::Path<i32>,
-    control: web::Json<ControlParams>,
+    data: web::Json<MoveObject>,
+    pool: web::Data<DbPool>,
     controllers: web::Data<Mutex<ChannelController>>,
     role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
+    duration: web::Data<SharedMediaMap>,
 ) -> Result<impl Responder, ServiceError> {
     let manager = controllers
         .lock()
@@ -963,60 +4455,53 @@ pub async fn control_playout(
         .get(*id)
         .await
         .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let config = manager.config.lock().await.clone();
+    let storage = manager.storage.lock().await;
 
-    if manager.is_processing.load(Ordering::SeqCst) {
-        return Err(ServiceError::Conflict(
-            "A command is already being processed, please wait".to_string(),
-        ));
+    if !data.force {
+        protect_referenced(&pool, &config, &storage, &data.source).await?;
     }
 
-    manager.is_processing.store(true, Ordering::SeqCst);
-
-    let resp = match control_state(&pool, &manager, &control.control).await {
-        Ok(res) => Ok(web::Json(res)),
-        Err(e) => Err(e),
-    };
+    let result = storage.rename(&data, duration.clone()).await;
 
-    manager.is_processing.store(false, Ordering::SeqCst);
+    record(
+        &pool,
+        *id,
+        user.id,
+        AuditAction::Rename,
+        &data.source,
+        Some(&data.target),
+        &result,
+    )
+    .await;
 
-    resp
+    Ok(web::Json(result?))
 }
 
-/// **Get current Clip**
-///
-/// ```BASH
-/// curl -X GET http://127.0.0.1:8787/api/control/1/media/current
-/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
-/// ```
+/// **Remove File/Folder**
 ///
-/// **Response:**
+/// Files which are used in the playlists of the next days are protected,
+/// set `"force": true` to remove them anyway.
 ///
-/// ```JSON
-///     {
-///       "media": {
-///         "category": "",
-///         "duration": 154.2,
-///         "out": 154.2,
-///         "in": 0.0,
-///         "source": "/opt/tv-media/clip.mp4"
-///       },
-///       "index": 39,
-///       "ingest": false,
-///       "mode": "playlist",
-///       "played": 67.808
-///     }
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/file/1/remove/ -H 'Content-Type: application/json'
+/// -d '{"source": "<SOURCE>"}' -H 'Authorization: Bearer <TOKEN>'
 /// ```
-#[get("/control/{id}/media/current")]
+#[allow(clippy::too_many_arguments)]
+#[post("/file/{id}/remove/")]
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.files(*id) || role.has_authority(&Role::GlobalAdmin)"
 )]
-pub async fn media_current(
+pub async fn remove(
     id: web::Path<i32>,
+    data: web::Json<PathObject>,
+    pool: web::Data<DbPool>,
     controllers: web::Data<Mutex<ChannelController>>,
     role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
+    duration: web::Data<SharedMediaMap>,
 ) -> Result<impl Responder, ServiceError> {
     let manager = controllers
         .lock()
@@ -1024,106 +4509,164 @@ pub async fn media_current(
         .get(*id)
         .await
         .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let config = manager.config.lock().await.clone();
+    let storage = manager.storage.lock().await;
+    let recursive = data.recursive;
 
-    let media_map = get_data_map(&manager).await;
+    if !data.force {
+        protect_referenced(&pool, &config, &storage, &data.source).await?;
+    }
 
-    Ok(web::Json(media_map))
+    let result = storage
+        .remove(&data.source, duration.clone(), recursive)
+        .await;
+
+    record(
+        &pool,
+        *id,
+        user.id,
+        AuditAction::Delete,
+        &data.source,
+        None,
+        &result,
+    )
+    .await;
+
+    Ok(web::Json(result?))
 }
 
-/// #### ffplayout Process Control
+/// **Upload File**
 ///
-/// Control ffplayout process, like:
-/// - start
-/// - stop
-/// - restart
-/// - status
+/// Uploaded files are probed afterwards. Files which are corrupt, have no duration
+/// or are missing an audio/video stream get removed, or moved to the quarantine folder
+/// when `storage.quarantine` is enabled. The response contains a validation report
+/// for every file, the status is `422` when one of them is invalid.
 ///
 /// ```BASH
-/// curl -X POST http://127.0.0.1:8787/api/control/1/process/
-/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
-/// -d '{"command": "start"}'
+/// curl -X PUT http://127.0.0.1:8787/api/file/1/upload/ -H 'Authorization: Bearer <TOKEN>'
+/// -F "file=@file.mp4"
 /// ```
-#[post("/control/{id}/process/")]
+#[allow(clippy::too_many_arguments)]
+#[put("/file/{id}/upload/")]
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.files(*id) || role.has_authority(&Role::GlobalAdmin)"
 )]
-pub async fn process_control(
+async fn save_file(
     id: web::Path<i32>,
-    proc: web::Json<Process>,
+    req: HttpRequest,
+    payload: Multipart,
+    obj: web::Query<FileObj>,
     controllers: web::Data<Mutex<ChannelController>>,
     role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
-) -> Result<impl Responder, ServiceError> {
+    duration: web::Data<SharedMediaMap>,
+    pool: web::Data<DbPool>,
+) -> Result<HttpResponse, ServiceError> {
     let manager = controllers
         .lock()
         .await
         .get(*id)
         .await
         .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
-    manager.list_init.store(true, Ordering::SeqCst);
+    let config = manager.config.lock().await.clone();
+    let storage = manager.storage.lock().await.clone();
+    let account = handles::select_user(&pool, user.id).await?;
+    let limit = handles::select_upload_limit(&pool, account.role_id.unwrap_or_default())
+        .await
+        .unwrap_or_default();
 
-    if manager.is_processing.load(Ordering::SeqCst) {
-        return Err(ServiceError::Conflict(
-            "A command is already being processed, please wait".to_string(),
+    if !upload_folder_allowed(&limit, &account.username, &obj.path) {
+        return Err(ServiceError::Forbidden(
+            "Upload to this folder is not allowed".to_string(),
         ));
     }
 
-    manager.is_processing.store(true, Ordering::SeqCst);
+    let size: u64 = req
+        .headers()
+        .get("content-length")
+        .and_then(|cl| cl.to_str().ok())
+        .and_then(|cls| cls.parse().ok())
+        .unwrap_or(0);
+
+    // reject early, the size of every file is checked again after upload
+    if limit.max_size > 0 && size > limit.max_size as u64 * 1_048_576 {
+        return Err(ServiceError::Forbidden(format!(
+            "Upload is larger then {} MB",
+            limit.max_size
+        )));
+    }
 
-    match proc.into_inner().command {
-        ProcessCtl::Status => {
-            manager.is_processing.store(false, Ordering::SeqCst);
+    let upload = storage.upload(payload, &obj.path, false).await;
+
+    if upload.is_err() {
+        let folder = obj.path.to_string_lossy();
+        record(
+            &pool,
+            *id,
+            user.id,
+            AuditAction::Upload,
+            &folder,
+            None,
+            &upload,
+        )
+        .await;
+    }
 
-            if manager.is_alive.load(Ordering::SeqCst) {
-                return Ok(web::Json("active"));
-            }
-            return Ok(web::Json("not running"));
-        }
-        ProcessCtl::Start => {
-            if !manager.is_alive.load(Ordering::SeqCst) {
-                manager.channel.lock().await.active = true;
-                manager.start().await?;
-            }
-        }
-        ProcessCtl::Stop => {
-            manager.channel.lock().await.active = false;
-            manager.stop_all(true).await;
-        }
-        ProcessCtl::Restart => {
-            manager.channel.lock().await.active = false;
-            manager.stop_all(false).await;
+    let files = upload?;
+    let reports = validate_uploads(&storage, &config, duration, files, &limit).await?;
 
-            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+    for report in &reports {
+        let result = if report.valid {
+            Ok(())
+        } else {
+            Err(ServiceError::BadRequest(report.errors.join(", ")))
+        };
 
-            manager.channel.lock().await.active = true;
-            manager.start().await?;
-        }
+        record(
+            &pool,
+            *id,
+            user.id,
+            AuditAction::Upload,
+            &report.file,
+            None,
+            &result,
+        )
+        .await;
     }
 
-    manager.is_processing.store(false, Ordering::SeqCst);
+    let valid_files: Vec<String> = reports
+        .iter()
+        .filter(|r| r.valid)
+        .map(|r| r.file.clone())
+        .collect();
 
-    Ok(web::Json("Success"))
+    hash_uploads(&pool, &config, &storage, &valid_files).await?;
+
+    if reports.iter().all(|r| r.valid) {
+        Ok(HttpResponse::Ok().json(reports))
+    } else {
+        Ok(HttpResponse::UnprocessableEntity().json(reports))
+    }
 }
 
-/// #### ffplayout Playlist Operations
+/// **Scan File Hashes**
 ///
-/// **Get playlist**
+/// Hash all new and changed files of the channel storage in the background.
 ///
 /// ```BASH
-/// curl -X GET http://127.0.0.1:8787/api/playlist/1?date=2022-06-20
-/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
+/// curl -X POST http://127.0.0.1:8787/api/file/1/hash-scan/ -H 'Authorization: Bearer <TOKEN>'
 /// ```
-#[get("/playlist/{id}")]
+#[post("/file/{id}/hash-scan/")]
 #[protect(
-    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
     ty = "Role",
     expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
 )]
-pub async fn get_playlist(
+pub async fn hash_scan(
     id: web::Path<i32>,
-    obj: web::Query<DateObj>,
+    pool: web::Data<DbPool>,
     controllers: web::Data<Mutex<ChannelController>>,
     role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
@@ -1135,29 +4678,62 @@ pub async fn get_playlist(
         .await
         .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
     let config = manager.config.lock().await.clone();
+    let storage = manager.storage.lock().await.clone();
 
-    match read_playlist(&config, obj.date.clone()).await {
-        Ok(playlist) => Ok(web::Json(playlist)),
-        Err(e) => Err(e),
-    }
+    tokio::spawn(async move {
+        if let Err(e) = scan_hashes(&pool, &config, &storage).await {
+            let id = config.general.channel_id;
+            error!(target: Target::file_mail(), channel = id; "Hash scan failed: {e}");
+        }
+    });
+
+    Ok(web::Json("Hash scan started"))
 }
 
-/// **Save playlist**
+/// **Get Duplicates**
+///
+/// List files with identical content, grouped by their hash.
 ///
 /// ```BASH
-/// curl -X POST http://127.0.0.1:8787/api/playlist/1/
-/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
-/// --data "{<JSON playlist data>}"
+/// curl -X GET http://127.0.0.1:8787/api/file/1/duplicates/ -H 'Authorization: Bearer <TOKEN>'
 /// ```
-#[post("/playlist/{id}/")]
+#[get("/file/{id}/duplicates/")]
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
     expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
 )]
-pub async fn save_playlist(
+pub async fn get_duplicates(
     id: web::Path<i32>,
-    data: web::Json<JsonPlaylist>,
+    pool: web::Data<DbPool>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let media = handles::select_media_duplicates(&pool, *id).await?;
+
+    Ok(web::Json(group_duplicates(media)))
+}
+
+/// **Deduplicate Files**
+///
+/// Replace duplicate files of the local storage with hardlinks, based on the last hash scan.
+/// The response reports the linked files and the saved space in bytes,
+/// with `dry_run` nothing gets changed.
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/file/1/dedupe/ -H 'Content-Type: application/json'
+/// -d '{"dry_run": true}' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[post("/file/{id}/dedupe/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+)]
+pub async fn dedupe_files(
+    id: web::Path<i32>,
+    data: web::Json<DedupeObj>,
+    pool: web::Data<DbPool>,
     controllers: web::Data<Mutex<ChannelController>>,
     role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
@@ -1170,352 +4746,379 @@ pub async fn save_playlist(
         .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
     let config = manager.config.lock().await.clone();
     let storage = manager.storage.lock().await.clone();
+    let report = dedupe(&pool, &config, &storage, data.dry_run).await?;
 
-    let mut data = data.into_inner();
-    for media in &mut data.program {
-        let cloned_media_source = media.source.clone();
-        media.source = storage.sanitized_file_path(&cloned_media_source);
-    }
-
-    match write_playlist(&config, data).await {
-        Ok(res) => Ok(web::Json(res)),
-        Err(e) => Err(e),
-    }
+    Ok(web::Json(report))
 }
 
-/// **Generate Playlist**
-///
-/// A new playlist will be generated and response.
+/// **Migrate Storage**
 ///
-/// ```BASH
-/// curl -X POST http://127.0.0.1:8787/api/playlist/1/generate/2022-06-20
-/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
-/// /// --data '{ "paths": [<list of paths>] }' # <- data is optional
-/// ```
+/// Copy the whole channel storage to another backend, for example from local storage to S3.
+/// The copy runs in the background and continues with the missing files, when it is started again.
+/// With `switch` the playlists are rewritten and the channel uses the new storage, after all files are copied.
+/// `dry_run` returns only the number of files and playlists, which would be migrated.
 ///
-/// Or with template:
 /// ```BASH
-/// curl -X POST http://127.0.0.1:8787/api/playlist/1/generate/2023-00-05
-/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
-/// --data '{"template": {"sources": [\
-///            {"start": "00:00:00", "duration": "10:00:00", "shuffle": true, "paths": ["path/1", "path/2"]}, \
-///            {"start": "10:00:00", "duration": "14:00:00", "shuffle": false, "paths": ["path/3", "path/4"]}]}}'
+/// curl -X POST http://127.0.0.1:8787/api/storage/1/migrate/ -H 'Content-Type: application/json'
+/// -d '{"target": "s3://bucket/:http://127.0.0.1:9000/:access_key/:secret_key", "dry_run": false, "switch": true}'
+/// -H 'Authorization: Bearer <TOKEN>'
 /// ```
-#[post("/playlist/{id}/generate/{date}")]
-#[protect(
-    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
-    ty = "Role",
-    expr = "user.channels.contains(&params.0) || role.has_authority(&Role::GlobalAdmin)"
-)]
-pub async fn gen_playlist(
-    params: web::Path<(i32, String)>,
-    data: Option<web::Json<PathsObj>>,
+#[post("/storage/{id}/migrate/")]
+#[protect("Role::GlobalAdmin", ty = "Role")]
+pub async fn storage_migrate(
+    id: web::Path<i32>,
+    pool: web::Data<DbPool>,
+    data: web::Json<MigrateObj>,
     controllers: web::Data<Mutex<ChannelController>>,
-    role: AuthDetails<Role>,
-    user: web::ReqData<UserMeta>,
+    duration: web::Data<SharedMediaMap>,
 ) -> Result<impl Responder, ServiceError> {
-    let (id, date) = params.into_inner();
     let manager = controllers
         .lock()
         .await
-        .get(id)
+        .get(*id)
         .await
-        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
-    manager.config.lock().await.general.generate = Some(vec![date.clone()]);
-    let storage = manager.config.lock().await.channel.storage.clone();
-
-    if let Some(obj) = data {
-        if let Some(paths) = &obj.paths {
-            let mut path_list = vec![];
-
-            for path in paths {
-                let (p, _, _) = norm_abs_path(&storage, path)?;
-
-                path_list.push(p);
-            }
-
-            manager.config.lock().await.storage.paths = path_list;
-        }
-
-        manager
-            .config
-            .lock()
-            .await
-            .general
-            .template
-            .clone_from(&obj.template);
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let obj = data.into_inner();
+
+    if obj.dry_run {
+        let status = migrate_storage(&pool, &manager, &obj, &duration).await?;
+
+        return Ok(web::Json(status));
     }
 
-    match generate_playlist(manager).await {
-        Ok(playlist) => Ok(web::Json(playlist)),
-        Err(e) => Err(e),
+    if manager.is_migrating.load(Ordering::SeqCst) {
+        return Err(ServiceError::Conflict(
+            "Storage migration is already running".to_string(),
+        ));
     }
+
+    tokio::spawn(async move {
+        if let Err(e) = migrate_storage(&pool, &manager, &obj, &duration).await {
+            error!(target: Target::file_mail(), channel = manager.id; "Storage migration failed: {e}");
+        }
+    });
+
+    Ok(web::Json(MigrationStatus {
+        running: true,
+        ..Default::default()
+    }))
 }
 
-/// **Delete Playlist**
+/// **Get Migration Status**
+///
+/// Progress of the current or last storage migration.
 ///
 /// ```BASH
-/// curl -X DELETE http://127.0.0.1:8787/api/playlist/1/2022-06-20
-/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
+/// curl -X GET http://127.0.0.1:8787/api/storage/1/migrate/ -H 'Authorization: Bearer <TOKEN>'
 /// ```
-#[delete("/playlist/{id}/{date}")]
-#[protect(
-    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
-    ty = "Role",
-    expr = "user.channels.contains(&params.0) || role.has_authority(&Role::GlobalAdmin)"
-)]
-pub async fn del_playlist(
-    params: web::Path<(i32, String)>,
+#[get("/storage/{id}/migrate/")]
+#[protect("Role::GlobalAdmin", ty = "Role")]
+pub async fn get_storage_migration(
+    id: web::Path<i32>,
+    pool: web::Data<DbPool>,
     controllers: web::Data<Mutex<ChannelController>>,
-    role: AuthDetails<Role>,
-    user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
-    let (id, date) = params.into_inner();
     let manager = controllers
         .lock()
         .await
-        .get(id)
+        .get(*id)
         .await
         .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
-    let config = manager.config.lock().await.clone();
 
-    match delete_playlist(&config, &date).await {
-        Ok(m) => Ok(web::Json(m)),
-        Err(e) => Err(e),
-    }
+    Ok(web::Json(migration_status(&pool, &manager).await?))
 }
 
-/// ### Log file
+/// **Get Mirror Status**
 ///
-/// **Read Log File**
+/// Sync state of the storage mirror. `lag` is the age in seconds of the oldest change,
+/// which is not mirrored yet.
 ///
 /// ```BASH
-/// curl -X GET http://127.0.0.1:8787/api/log/1?date=2022-06-20
-/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
+/// curl -X GET http://127.0.0.1:8787/api/storage/1/mirror/ -H 'Authorization: Bearer <TOKEN>'
 /// ```
-#[get("/log/{id}")]
+#[get("/storage/{id}/mirror/")]
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
     expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
 )]
-pub async fn get_log(
+pub async fn get_mirror_status(
     id: web::Path<i32>,
-    log: web::Query<DateObj>,
+    controllers: web::Data<Mutex<ChannelController>>,
     role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
-    read_log_file(&id, &log.date).await
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let status = manager.mirror.lock().await.clone();
+
+    Ok(web::Json(status))
 }
 
-/// ### File Operations
+/// **Get Integrity Errors**
 ///
-/// **Get File/Folder List**
+/// List files which are flagged by the storage integrity scan.
 ///
 /// ```BASH
-/// curl -X POST http://127.0.0.1:8787/api/file/1/browse/ -H 'Content-Type: application/json'
-/// -d '{ "source": "/" }' -H 'Authorization: Bearer <TOKEN>'
+/// curl -X GET http://127.0.0.1:8787/api/file/1/integrity/ -H 'Authorization: Bearer <TOKEN>'
 /// ```
-#[post("/file/{id}/browse/")]
+#[get("/file/{id}/integrity/")]
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
     expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
 )]
-pub async fn file_browser(
+pub async fn get_integrity(
     id: web::Path<i32>,
-    data: web::Json<PathObject>,
-    controllers: web::Data<Mutex<ChannelController>>,
+    pool: web::Data<DbPool>,
     role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
-    duration: web::Data<SharedMediaMap>,
 ) -> Result<impl Responder, ServiceError> {
-    let manager = controllers
-        .lock()
-        .await
-        .get(*id)
-        .await
-        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
-    let storage = manager.storage.lock().await.clone();
+    let media = handles::select_media_errors(&pool, *id).await?;
 
-    match storage.browser(&data.into_inner(), duration.clone()).await {
-        Ok(obj) => Ok(web::Json(obj)),
-        Err(e) => Err(e),
+    Ok(web::Json(media))
+}
+
+fn validity_date(date: Option<String>) -> Result<Option<String>, ServiceError> {
+    match date.map(|d| d.trim().to_string()).filter(|d| !d.is_empty()) {
+        Some(d) if NaiveDate::parse_from_str(&d, "%Y-%m-%d").is_err() => Err(
+            ServiceError::BadRequest(format!("Date {d} is not in the format YYYY-MM-DD")),
+        ),
+        date => Ok(date),
     }
 }
 
-/// **Create Folder**
+/// **Set Validity Dates**
+///
+/// Set the first and last day, on which a file of the library can be scheduled.
+/// The path is relative to the channel storage and the file must be indexed by a hash scan,
+/// empty dates remove the limit.
 ///
 /// ```BASH
-/// curl -X POST http://127.0.0.1:8787/api/file/1/create-folder/ -H 'Content-Type: application/json'
-/// -d '{"source": "<FOLDER PATH>"}' -H 'Authorization: Bearer <TOKEN>'
+/// curl -X PUT http://127.0.0.1:8787/api/file/1/validity/ -H 'Content-Type: application/json'
+/// -d '{"path": "spots/christmas.mp4", "valid_from": "2025-11-24", "valid_to": "2025-12-26"}'
+/// -H 'Authorization: Bearer <TOKEN>'
 /// ```
-#[post("/file/{id}/create-folder/")]
+#[put("/file/{id}/validity/")]
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.files(*id) || role.has_authority(&Role::GlobalAdmin)"
 )]
-pub async fn add_dir(
+pub async fn set_media_validity(
     id: web::Path<i32>,
-    data: web::Json<PathObject>,
-    controllers: web::Data<Mutex<ChannelController>>,
+    data: web::Json<ValidityObj>,
+    pool: web::Data<DbPool>,
     role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
-) -> Result<HttpResponse, ServiceError> {
-    let manager = controllers
-        .lock()
-        .await
-        .get(*id)
-        .await
-        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
-    let storage = manager.storage.lock().await;
-
-    storage.mkdir(&data.into_inner()).await?;
+) -> Result<impl Responder, ServiceError> {
+    let obj = data.into_inner();
+    let path = obj.path.trim_start_matches('/');
+    let valid_from = validity_date(obj.valid_from)?;
+    let valid_to = validity_date(obj.valid_to)?;
+    let result = handles::update_media_validity(&pool, *id, path, valid_from, valid_to).await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ServiceError::BadRequest(
+            "File is not indexed, run a hash scan first".to_string(),
+        ));
+    }
 
-    Ok(HttpResponse::Ok().into())
+    Ok(web::Json("Validity saved"))
 }
 
-/// **Rename File**
+/// **Get Storage Audit**
+///
+/// List uploads, deletions, renames and created folders of a channel, newest first.
+/// All filters are optional, `path` matches also parts of a path, `from` and `to` are unix timestamps.
 ///
 /// ```BASH
-/// curl -X POST http://127.0.0.1:8787/api/file/1/rename/ -H 'Content-Type: application/json'
-/// -d '{"source": "<SOURCE>", "target": "<TARGET>"}' -H 'Authorization: Bearer <TOKEN>'
+/// curl -X GET 'http://127.0.0.1:8787/api/file/1/audit/?user=admin&action=delete&path=promo&from=1735689600&limit=100'
+/// -H 'Authorization: Bearer <TOKEN>'
 /// ```
-#[post("/file/{id}/rename/")]
+#[get("/file/{id}/audit/")]
 #[protect(
-    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
     ty = "Role",
     expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
 )]
-pub async fn move_rename(
+pub async fn get_storage_audit(
     id: web::Path<i32>,
-    data: web::Json<MoveObject>,
-    controllers: web::Data<Mutex<ChannelController>>,
+    filter: web::Query<AuditFilter>,
+    pool: web::Data<DbPool>,
     role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
-    duration: web::Data<SharedMediaMap>,
 ) -> Result<impl Responder, ServiceError> {
-    let manager = controllers
-        .lock()
-        .await
-        .get(*id)
-        .await
-        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
-    let storage = manager.storage.lock().await;
-    match storage.rename(&data.into_inner(), duration.clone()).await {
-        Ok(obj) => Ok(web::Json(obj)),
-        Err(e) => Err(e),
-    }
+    let entries = select_audit(&pool, *id, &filter).await?;
+
+    Ok(web::Json(entries))
 }
 
-/// **Remove File/Folder**
+/// **Sign File URL**
+///
+/// Create an expiring URL for a media file, which works without authorization header.
+/// Lifetime is in seconds, default is one hour, maximum are seven days.
 ///
 /// ```BASH
-/// curl -X POST http://127.0.0.1:8787/api/file/1/remove/ -H 'Content-Type: application/json'
-/// -d '{"source": "<SOURCE>"}' -H 'Authorization: Bearer <TOKEN>'
+/// curl -X POST http://127.0.0.1:8787/api/file/1/sign/ -H 'Content-Type: application/json'
+/// -d '{"path": "/path/to/file.mp4", "lifetime": 3600}' -H 'Authorization: Bearer <TOKEN>'
 /// ```
-#[post("/file/{id}/remove/")]
+/// **Response:**
+///
+/// ```JSON
+/// {
+///     "url": "/file/1/path/to/file.mp4?expires=1735689600&signature=<HMAC>",
+///     "expires": 1735689600
+/// }
+/// ```
+#[post("/file/{id}/sign/")]
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
     expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
 )]
-pub async fn remove(
+async fn sign_file(
     id: web::Path<i32>,
-    data: web::Json<PathObject>,
-    controllers: web::Data<Mutex<ChannelController>>,
+    data: web::Json<SignObj>,
     role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
-    duration: web::Data<SharedMediaMap>,
 ) -> Result<impl Responder, ServiceError> {
+    if data.path.split('/').any(|p| p == "..") {
+        return Err(ServiceError::BadRequest("Invalid file path".to_string()));
+    }
+
+    Ok(web::Json(auth::sign_media_url(
+        *id,
+        &data.path,
+        data.lifetime,
+    )))
+}
+
+/// **Get File**
+///
+/// Can be used for preview video files, the URL must be signed over `/api/file/{id}/sign/`.
+///
+/// ```BASH
+/// curl -X GET "http://127.0.0.1:8787/file/1/path/to/file.mp4?expires=<TIME>&signature=<HMAC>"
+/// ```
+#[get("/file/{id}/{filename:.*}")]
+async fn get_file(
+    req: HttpRequest,
+    controllers: web::Data<Mutex<ChannelController>>,
+) -> Result<HttpResponse, ServiceError> {
+    let id: i32 = req.match_info().query("id").parse()?;
+    let file_path = auth::media_path(req.match_info().query("filename"));
+    let sign = web::Query::<auth::MediaSignature>::from_query(req.query_string())
+        .map_err(|_| ServiceError::Unauthorized("Missing media signature".to_string()))?;
+
+    if !auth::verify_media_url(id, &file_path, &sign) {
+        return Err(ServiceError::Unauthorized(
+            "Invalid or expired media signature".to_string(),
+        ));
+    }
+
     let manager = controllers
         .lock()
         .await
-        .get(*id)
+        .get(id)
         .await
         .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    // let config = manager.config.lock().await;
+    // let storage = config.channel.storage.clone();
     let storage = manager.storage.lock().await;
-    let recursive = data.recursive;
 
-    match storage
-        .remove(&data.into_inner().source, duration.clone(), recursive)
-        .await
-    {
-        Ok(obj) => Ok(web::Json(obj)),
-        Err(e) => Err(e),
+    let opened_file = storage.open_media(&req, &file_path).await?;
+
+    Ok(opened_file)
+}
+
+/// Ingest key from the bearer token of a WHIP client.
+async fn whip_auth(req: &HttpRequest, manager: &ChannelManager) -> Result<(), ServiceError> {
+    let key = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .unwrap_or_default()
+        .trim();
+
+    if key.is_empty() || !authorize_key(manager, key, "WHIP").await {
+        return Err(ServiceError::Unauthorized("Invalid ingest key".to_string()));
     }
+
+    Ok(())
 }
 
-/// **Upload File**
+/// **WHIP Ingest**
+///
+/// WebRTC ingest for browsers and OBS, the bearer token is an ingest key of the channel.
 ///
 /// ```BASH
-/// curl -X PUT http://127.0.0.1:8787/api/file/1/upload/ -H 'Authorization: Bearer <TOKEN>'
-/// -F "file=@file.mp4"
+/// curl -X POST http://127.0.0.1:8787/whip/1 -H 'Content-Type: application/sdp' \
+/// -H 'Authorization: Bearer <INGEST_KEY>' --data-binary @offer.sdp
 /// ```
-#[allow(clippy::too_many_arguments)]
-#[put("/file/{id}/upload/")]
-#[protect(
-    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
-    ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
-)]
-async fn save_file(
+#[post("/whip/{id}")]
+async fn whip_publish(
+    req: HttpRequest,
     id: web::Path<i32>,
-    _req: HttpRequest,
-    payload: Multipart,
-    obj: web::Query<FileObj>,
+    offer: String,
     controllers: web::Data<Mutex<ChannelController>>,
-    role: AuthDetails<Role>,
-    user: web::ReqData<UserMeta>,
-) -> Result<HttpResponse, ServiceError> {
+) -> Result<impl Responder, ServiceError> {
     let manager = controllers
         .lock()
         .await
         .get(*id)
         .await
         .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
-    let storage = manager.storage.lock().await.clone();
 
-    // let size: u64 = req
-    //     .headers()
-    //     .get("content-length")
-    //     .and_then(|cl| cl.to_str().ok())
-    //     .and_then(|cls| cls.parse().ok())
-    //     .unwrap_or(0);
+    whip_auth(&req, &manager).await?;
 
-    storage.upload(payload, &obj.path, false).await?;
+    if !req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|h| h.starts_with("application/sdp"))
+    {
+        return Err(ServiceError::BadRequest(
+            "Offer must be application/sdp".to_string(),
+        ));
+    }
 
-    Ok(HttpResponse::Ok().into())
+    let (answer, session) = whip::publish(&manager, offer).await?;
+
+    Ok(HttpResponse::Created()
+        .content_type("application/sdp")
+        .insert_header((header::LOCATION, format!("/whip/{id}/{session}")))
+        .body(answer))
 }
 
-/// **Get File**
-///
-/// Can be used for preview video files
+/// **End WHIP Ingest**
 ///
 /// ```BASH
-/// curl -X GET http://127.0.0.1:8787/file/1/path/to/file.mp4
+/// curl -X DELETE http://127.0.0.1:8787/whip/1/<SESSION> -H 'Authorization: Bearer <INGEST_KEY>'
 /// ```
-#[get("/file/{id}/{filename:.*}")]
-async fn get_file(
+#[delete("/whip/{id}/{session}")]
+async fn whip_unpublish(
     req: HttpRequest,
+    path: web::Path<(i32, String)>,
     controllers: web::Data<Mutex<ChannelController>>,
-) -> Result<HttpResponse, ServiceError> {
-    let id: i32 = req.match_info().query("id").parse()?;
+) -> Result<impl Responder, ServiceError> {
+    let (id, session) = path.into_inner();
     let manager = controllers
         .lock()
         .await
         .get(id)
         .await
         .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
-    // let config = manager.config.lock().await;
-    // let storage = config.channel.storage.clone();
-    let storage = manager.storage.lock().await;
-    let file_path = req.match_info().query("filename");
 
-    let opened_file = storage.open_media(&req, file_path).await?;
+    whip_auth(&req, &manager).await?;
+    whip::unpublish(&manager, &session).await?;
 
-    Ok(opened_file)
+    Ok(HttpResponse::Ok().finish())
 }
 
 /// **Get Public**
@@ -1529,41 +5132,145 @@ async fn get_file(
 async fn get_public(
     path: web::Path<(i32, String, String)>,
     controllers: web::Data<Mutex<ChannelController>>,
-) -> Result<actix_files::NamedFile, ServiceError> {
+) -> Result<Either<actix_files::NamedFile, HttpResponse>, ServiceError> {
     let (id, public, file_stem) = path.into_inner();
+    let mut manager = None;
 
     let absolute_path = if file_stem.ends_with(".ts")
         || file_stem.ends_with(".m3u8")
         || file_stem.ends_with(".vtt")
     {
-        let manager = controllers
+        let channel_manager = controllers
             .lock()
             .await
             .get(id)
             .await
             .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
-        let config = manager.config.lock().await;
-        config.channel.public.join(public)
+        let public = channel_manager
+            .config
+            .lock()
+            .await
+            .channel
+            .public
+            .join(public);
+        manager = Some(channel_manager);
+
+        public
     } else {
         public_path()
     }
     .clean();
 
     let path = absolute_path.join(file_stem.as_str());
+
+    if let Some(manager) = manager.filter(|_| file_stem.ends_with(".m3u8")) {
+        if manager.config.lock().await.output.scte35 {
+            let playlist = fs::read_to_string(&path).await?;
+            let tagged = manager.splice_cues.lock().await.tag_playlist(&playlist);
+
+            return Ok(Either::Right(
+                HttpResponse::Ok()
+                    .content_type("application/vnd.apple.mpegurl")
+                    .body(tagged),
+            ));
+        }
+    }
+
     let file = actix_files::NamedFile::open(path)?; // to:do = handle for s3
 
-    Ok(file
-        .use_last_modified(true)
-        .set_content_disposition(ContentDisposition {
-            disposition: DispositionType::Attachment,
-            parameters: vec![],
-        }))
+    Ok(Either::Left(
+        file.use_last_modified(true)
+            .set_content_disposition(ContentDisposition {
+                disposition: DispositionType::Attachment,
+                parameters: vec![],
+            }),
+    ))
+}
+
+/// **Get EPG**
+///
+/// Programme guide from the upcoming playlists, as XMLTV or JSON. It needs no login,
+/// but the channel must publish its EPG in the playlist settings.
+/// With `days` more playlists can be included, default is 2, maximum 14.
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/epg/1.xml?days=7
+/// ```
+#[get("/epg/{id:\\d+}.{format:xml|json}")]
+pub async fn get_epg(
+    path: web::Path<(i32, String)>,
+    obj: web::Query<EpgObj>,
+    controllers: web::Data<Mutex<ChannelController>>,
+) -> Result<HttpResponse, ServiceError> {
+    let (id, format) = path.into_inner();
+    let manager = controllers
+        .lock()
+        .await
+        .get(id)
+        .await
+        .ok_or(ServiceError::NotFound("Channel not found".to_string()))?;
+    let config = manager.config.lock().await.clone();
+
+    if !config.playlist.epg {
+        return Err(ServiceError::Forbidden(
+            "EPG is not published for this channel".to_string(),
+        ));
+    }
+
+    let programmes = epg::programmes(&manager.db_pool, &config, obj.days.clamp(1, 14)).await;
+
+    if format == "json" {
+        return Ok(HttpResponse::Ok().json(programmes));
+    }
+
+    let name = manager.channel.lock().await.name.clone();
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/xml; charset=utf-8")
+        .body(epg::xmltv(id, &name, &programmes)))
+}
+
+/// **Get Now Playing**
+///
+/// Running clip with elapsed and remaining seconds, and the next clips. It needs no login,
+/// but the channel must publish it in the playlist settings. Only titles and times are included.
+/// With `next` the number of following clips can be set, default is 3, maximum 10.
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/now-playing/1?next=5
+/// ```
+#[get("/now-playing/{id:\\d+}")]
+async fn get_now_playing(
+    id: web::Path<i32>,
+    obj: web::Query<NowPlayingObj>,
+    controllers: web::Data<Mutex<ChannelController>>,
+) -> Result<HttpResponse, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::NotFound("Channel not found".to_string()))?;
+
+    if !manager.config.lock().await.playlist.now_playing {
+        return Err(ServiceError::Forbidden(
+            "Now playing is not published for this channel".to_string(),
+        ));
+    }
+
+    let now_playing = epg::now_playing(&manager, obj.next.clamp(1, 10)).await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(("Cache-Control", "no-cache"))
+        .json(now_playing))
 }
 
 /// **Import playlist**
 ///
-/// Import text/m3u file and convert it to a playlist
-/// lines with leading "#" will be ignore
+/// Import M3U/text, XSPF, CSV or BXF rundowns and convert them to a playlist, the format comes from the file extension.
+/// Sources are resolved against the channel storage, the response lists the entries which could not be imported.
+/// CSV files need a header with a `source` column, `title`, `in`, `out` and `duration` are optional.
+/// BXF schedules (`.bxf` or `.xml`) replace the playlist, without `date` the broadcast date from the file is used.
 ///
 /// ```BASH
 /// curl -X PUT http://127.0.0.1:8787/api/file/1/import/ -H 'Authorization: Bearer <TOKEN>'
@@ -1573,7 +5280,7 @@ async fn get_public(
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.playlists(*id) || role.has_authority(&Role::GlobalAdmin)"
 )]
 async fn import_playlist(
     // to-do : check this one!
@@ -1583,7 +5290,7 @@ async fn import_playlist(
     controllers: web::Data<Mutex<ChannelController>>,
     role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
-) -> Result<HttpResponse, ServiceError> {
+) -> Result<impl Responder, ServiceError> {
     let manager = controllers
         .lock()
         .await
@@ -1591,7 +5298,7 @@ async fn import_playlist(
         .await
         .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
     let channel_name = manager.channel.lock().await.name.clone();
-    let playlists = manager.config.lock().await.channel.playlists.clone();
+    let config = manager.config.lock().await.clone();
     let storage = manager.storage.lock().await;
     let file = obj.file.file_name().unwrap_or_default();
     let path = env::temp_dir().join(file);
@@ -1599,11 +5306,20 @@ async fn import_playlist(
 
     storage.upload(payload, &path, true).await?;
 
-    let response = import_file(&playlists, &obj.date, Some(channel_name), &path_clone).await?;
+    let report = import_file(
+        &manager.db_pool,
+        &config,
+        &storage,
+        &obj.date,
+        Some(channel_name),
+        &path_clone,
+        user.id,
+    )
+    .await;
 
     fs::remove_file(path).await?;
 
-    Ok(HttpResponse::Ok().body(response))
+    Ok(web::Json(report?))
 }
 
 /// **Program info**
@@ -1687,7 +5403,7 @@ async fn get_program(
         )
         .unwrap();
 
-        let playlist = match read_playlist(&config, date.clone()).await {
+        let playlist = match read_playlist(&manager.db_pool, &config, date.clone()).await {
             Ok(p) => p,
             Err(e) => {
                 error!("Error in Playlist from {date}: {e}");
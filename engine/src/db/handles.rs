@@ -1,22 +1,48 @@
+use std::collections::HashMap;
+
 use actix_web::web;
 use argon2::{
     password_hash::{rand_core::OsRng, SaltString},
     Argon2, PasswordHasher,
 };
 use rand::{distr::Alphanumeric, Rng};
-use sqlx::{sqlite::SqliteQueryResult, Pool, Row, Sqlite};
 
-use super::models::{AdvancedConfiguration, Configuration};
-use crate::db::models::{Channel, GlobalSettings, Role, TextPreset, User};
+use super::{
+    models::{AdvancedConfiguration, Configuration},
+    DbConnection, DbPool, DbQueryResult,
+};
+use crate::db::models::{
+    AlertRule, ApiToken, AuthAudit, BumperRule, Channel, ChannelPermission, ExtraOutput,
+    FillerSource, FilterTemplate, GlobalSettings, IngestKey, InsertRule, LiveEvent, MediaHash,
+    Notifier, PlaylistChange, PlaylistItem, Role, ScheduledItem, Session, StorageAudit,
+    StorageMigration, StoredPlaylist, TextPreset, UploadLimit, User, Webhook,
+};
 use crate::utils::{
     advanced_config::AdvancedConfig,
     config::PlayoutConfig,
     errors::{ProcessError, ServiceError},
     is_running_in_container,
+    playlist::item_changes,
+    time_machine::time_now,
 };
 
-pub async fn db_migrate(conn: &Pool<Sqlite>) -> Result<bool, ProcessError> {
+/// Id of the row, which was inserted by the query.
+#[cfg(not(feature = "mysql"))]
+fn last_insert_id(result: &DbQueryResult) -> i32 {
+    result.last_insert_rowid() as i32
+}
+
+#[cfg(feature = "mysql")]
+fn last_insert_id(result: &DbQueryResult) -> i32 {
+    result.last_insert_id() as i32
+}
+
+pub async fn db_migrate(conn: &DbPool) -> Result<bool, ProcessError> {
+    #[cfg(not(feature = "mysql"))]
     sqlx::migrate!("../migrations").run(conn).await?;
+    #[cfg(feature = "mysql")]
+    sqlx::migrate!("../migrations/mysql").run(conn).await?;
+
     let mut init = false;
 
     if select_global(conn).await.is_err() {
@@ -27,13 +53,17 @@ pub async fn db_migrate(conn: &Pool<Sqlite>) -> Result<bool, ProcessError> {
             .collect();
         let shared = is_running_in_container().await;
 
+        #[cfg(not(feature = "mysql"))]
         const QUERY: &str = "CREATE TRIGGER global_row_count
         BEFORE INSERT ON global
         WHEN (SELECT COUNT(*) FROM global) >= 1
         BEGIN
             SELECT RAISE(FAIL, 'Database is already initialized!');
         END;
-        INSERT INTO global(secret, shared) VALUES($1, $2);";
+        INSERT INTO global(secret, shared) VALUES(?, ?);";
+        // the fixed id takes the place of the trigger, a second row fails on the primary key
+        #[cfg(feature = "mysql")]
+        const QUERY: &str = "INSERT INTO global(id, secret, shared) VALUES(1, ?, ?);";
 
         sqlx::query(QUERY)
             .bind(secret)
@@ -47,7 +77,7 @@ pub async fn db_migrate(conn: &Pool<Sqlite>) -> Result<bool, ProcessError> {
     Ok(init)
 }
 
-pub async fn select_global(conn: &Pool<Sqlite>) -> Result<GlobalSettings, ProcessError> {
+pub async fn select_global(conn: &DbPool) -> Result<GlobalSettings, ProcessError> {
     const QUERY: &str =
         "SELECT id, secret, logs, playlists, public, storage, shared, smtp_server, smtp_user, smtp_password, smtp_starttls, smtp_port FROM global WHERE id = 1";
 
@@ -57,14 +87,13 @@ pub async fn select_global(conn: &Pool<Sqlite>) -> Result<GlobalSettings, Proces
 }
 
 pub async fn update_global(
-    conn: &Pool<Sqlite>,
+    conn: &DbPool,
     global: GlobalSettings,
-) -> Result<SqliteQueryResult, ProcessError> {
-    const QUERY: &str = "UPDATE global SET logs = $2, playlists = $3, public = $4, storage = $5,
-            smtp_server = $6, smtp_user = $7, smtp_password = $8, smtp_starttls = $9, smtp_port = $10  WHERE id = 1";
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "UPDATE global SET logs = ?, playlists = ?, public = ?, storage = ?,
+            smtp_server = ?, smtp_user = ?, smtp_password = ?, smtp_starttls = ?, smtp_port = ?  WHERE id = 1";
 
     let result = sqlx::query(QUERY)
-        .bind(global.id)
         .bind(global.logs)
         .bind(global.playlists)
         .bind(global.public)
@@ -80,8 +109,8 @@ pub async fn update_global(
     Ok(result)
 }
 
-pub async fn select_channel(conn: &Pool<Sqlite>, id: &i32) -> Result<Channel, ProcessError> {
-    const QUERY: &str = "SELECT * FROM channels WHERE id = $1";
+pub async fn select_channel(conn: &DbPool, id: &i32) -> Result<Channel, ProcessError> {
+    const QUERY: &str = "SELECT * FROM channels WHERE id = ?";
 
     let result = sqlx::query_as(QUERY).bind(id).fetch_one(conn).await?;
 
@@ -89,13 +118,13 @@ pub async fn select_channel(conn: &Pool<Sqlite>, id: &i32) -> Result<Channel, Pr
 }
 
 pub async fn select_related_channels(
-    conn: &Pool<Sqlite>,
+    conn: &DbPool,
     user_id: Option<i32>,
 ) -> Result<Vec<Channel>, ProcessError> {
     let query = match user_id {
         Some(id) => format!(
             "SELECT c.id, c.name, c.preview_url, c.extra_extensions, c.active, c.public, c.playlists,
-            c.storage, c.last_date, c.time_shift, c.timezone, c.advanced_id FROM channels c
+            c.storage, c.last_date, c.time_shift, c.timezone, c.advanced_id, c.priority, c.standby FROM channels c
                 left join user_channels uc on uc.channel_id = c.id
                 left join user u on u.id = uc.user_id
              WHERE u.id = {id} ORDER BY c.id ASC;"
@@ -109,11 +138,11 @@ pub async fn select_related_channels(
 }
 
 pub async fn delete_user_channel(
-    conn: &Pool<Sqlite>,
+    conn: &DbPool,
     user_id: i32,
     channel_id: i32,
-) -> Result<SqliteQueryResult, ProcessError> {
-    const QUERY: &str = "DELETE FROM user_channels WHERE user_id = $1 AND channel_id = $2";
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "DELETE FROM user_channels WHERE user_id = ? AND channel_id = ?";
 
     let result = sqlx::query(QUERY)
         .bind(user_id)
@@ -125,14 +154,13 @@ pub async fn delete_user_channel(
 }
 
 pub async fn update_channel(
-    conn: &Pool<Sqlite>,
+    conn: &DbPool,
     id: i32,
     channel: Channel,
-) -> Result<SqliteQueryResult, ProcessError> {
+) -> Result<DbQueryResult, ProcessError> {
     const QUERY: &str =
-        "UPDATE channels SET name = $2, preview_url = $3, extra_extensions = $4, public = $5, playlists = $6, storage = $7, timezone = $8 WHERE id = $1";
+        "UPDATE channels SET name = ?, preview_url = ?, extra_extensions = ?, public = ?, playlists = ?, storage = ?, timezone = ?, priority = ? WHERE id = ?";
     let result = sqlx::query(QUERY)
-        .bind(id)
         .bind(channel.name)
         .bind(channel.preview_url)
         .bind(channel.extra_extensions)
@@ -140,6 +168,8 @@ pub async fn update_channel(
         .bind(channel.playlists)
         .bind(channel.storage)
         .bind(channel.timezone.map(|tz| tz.to_string()))
+        .bind(channel.priority)
+        .bind(id)
         .execute(conn)
         .await?;
 
@@ -147,48 +177,61 @@ pub async fn update_channel(
 }
 
 pub async fn update_stat(
-    conn: &Pool<Sqlite>,
+    conn: &DbPool,
     id: i32,
     last_date: &Option<String>,
     time_shift: f64,
-) -> Result<SqliteQueryResult, ProcessError> {
+) -> Result<DbQueryResult, ProcessError> {
     let query = match last_date {
-        Some(_) => "UPDATE channels SET last_date = $2, time_shift = $3 WHERE id = $1",
-        None => "UPDATE channels SET time_shift = $2 WHERE id = $1",
+        Some(_) => "UPDATE channels SET last_date = ?, time_shift = ? WHERE id = ?",
+        None => "UPDATE channels SET time_shift = ? WHERE id = ?",
     };
 
-    let mut q = sqlx::query(query).bind(id);
+    let mut q = sqlx::query(query);
 
     if last_date.is_some() {
         q = q.bind(last_date);
     }
 
-    let result = q.bind(time_shift).execute(conn).await?;
+    let result = q.bind(time_shift).bind(id).execute(conn).await?;
+
+    Ok(result)
+}
+
+pub async fn update_standby(
+    conn: &DbPool,
+    id: i32,
+    standby: bool,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "UPDATE channels SET standby = ? WHERE id = ?";
+
+    let result = sqlx::query(QUERY)
+        .bind(standby)
+        .bind(id)
+        .execute(conn)
+        .await?;
 
     Ok(result)
 }
 
 pub async fn update_player(
-    conn: &Pool<Sqlite>,
+    conn: &DbPool,
     id: i32,
     active: bool,
-) -> Result<SqliteQueryResult, ProcessError> {
-    const QUERY: &str = "UPDATE channels SET active = $2 WHERE id = $1";
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "UPDATE channels SET active = ? WHERE id = ?";
 
     let result = sqlx::query(QUERY)
-        .bind(id)
         .bind(active)
+        .bind(id)
         .execute(conn)
         .await?;
 
     Ok(result)
 }
 
-pub async fn insert_channel(
-    conn: &Pool<Sqlite>,
-    channel: Channel,
-) -> Result<Channel, ProcessError> {
-    const QUERY: &str = "INSERT INTO channels (name, preview_url, extra_extensions, public, playlists, storage) VALUES($1, $2, $3, $4, $5, $6)";
+pub async fn insert_channel(conn: &DbPool, channel: Channel) -> Result<Channel, ProcessError> {
+    const QUERY: &str = "INSERT INTO channels (name, preview_url, extra_extensions, public, playlists, storage, priority) VALUES(?, ?, ?, ?, ?, ?, ?)";
     let result = sqlx::query(QUERY)
         .bind(channel.name)
         .bind(channel.preview_url)
@@ -196,30 +239,28 @@ pub async fn insert_channel(
         .bind(channel.public)
         .bind(channel.playlists)
         .bind(channel.storage)
+        .bind(channel.priority)
         .execute(conn)
         .await?;
 
-    let result = sqlx::query_as("SELECT * FROM channels WHERE id = $1")
-        .bind(result.last_insert_rowid())
+    let result = sqlx::query_as("SELECT * FROM channels WHERE id = ?")
+        .bind(last_insert_id(&result))
         .fetch_one(conn)
         .await?;
 
     Ok(result)
 }
 
-pub async fn delete_channel(
-    conn: &Pool<Sqlite>,
-    id: &i32,
-) -> Result<SqliteQueryResult, ProcessError> {
-    const QUERY: &str = "DELETE FROM channels WHERE id = $1";
+pub async fn delete_channel(conn: &DbPool, id: &i32) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "DELETE FROM channels WHERE id = ?";
 
     let result = sqlx::query(QUERY).bind(id).execute(conn).await?;
 
     Ok(result)
 }
 
-pub async fn select_last_channel(conn: &Pool<Sqlite>) -> Result<i32, ProcessError> {
-    const QUERY: &str = "select seq from sqlite_sequence WHERE name = 'channel';";
+pub async fn select_last_channel(conn: &DbPool) -> Result<i32, ProcessError> {
+    const QUERY: &str = "SELECT COALESCE(MAX(id), 0) FROM channels;";
 
     let result = sqlx::query_scalar(QUERY).fetch_one(conn).await?;
 
@@ -227,10 +268,10 @@ pub async fn select_last_channel(conn: &Pool<Sqlite>) -> Result<i32, ProcessErro
 }
 
 pub async fn select_configuration(
-    conn: &Pool<Sqlite>,
+    conn: &DbPool,
     channel: i32,
 ) -> Result<Configuration, ProcessError> {
-    const QUERY: &str = "SELECT * FROM configurations WHERE channel_id = $1";
+    const QUERY: &str = "SELECT * FROM configurations WHERE channel_id = ?";
 
     let result = sqlx::query_as(QUERY).bind(channel).fetch_one(conn).await?;
 
@@ -238,11 +279,11 @@ pub async fn select_configuration(
 }
 
 pub async fn insert_configuration(
-    conn: &Pool<Sqlite>,
+    conn: &DbPool,
     channel_id: i32,
     output_param: &str,
-) -> Result<SqliteQueryResult, ProcessError> {
-    const QUERY: &str = "INSERT INTO configurations (channel_id, output_param) VALUES($1, $2)";
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "INSERT INTO configurations (channel_id, output_param) VALUES(?, ?)";
 
     let result = sqlx::query(QUERY)
         .bind(channel_id)
@@ -254,14 +295,14 @@ pub async fn insert_configuration(
 }
 
 pub async fn update_configuration(
-    conn: &Pool<Sqlite>,
+    conn: &DbPool,
     id: i32,
     config: PlayoutConfig,
-) -> Result<SqliteQueryResult, ProcessError> {
-    const QUERY: &str = "UPDATE configurations SET general_stop_threshold = $2, mail_subject = $3, mail_recipient = $4, mail_level = $5, mail_interval = $6, logging_ffmpeg_level = $7, logging_ingest_level = $8, logging_detect_silence = $9, logging_ignore = $10, processing_mode = $11, processing_audio_only = $12, processing_copy_audio = $13, processing_copy_video = $14, processing_width = $15, processing_height = $16, processing_aspect = $17, processing_fps = $18, processing_add_logo = $19, processing_logo = $20, processing_logo_scale = $21, processing_logo_opacity = $22, processing_logo_position = $23, processing_audio_tracks = $24, processing_audio_track_index = $25, processing_audio_channels = $26, processing_volume = $27, processing_filter = $28, processing_override_filter = $29, processing_vtt_enable = $30, processing_vtt_dummy = $31, ingest_enable = $32, ingest_param = $33, ingest_filter = $34, playlist_day_start = $35, playlist_length = $36, playlist_infinit = $37, storage_filler = $38, storage_extensions = $39, storage_shuffle = $40, text_add = $41, text_from_filename = $42, text_font = $43, text_style = $44, text_regex = $45, task_enable = $46, task_path = $47, output_mode = $48, output_param = $49 WHERE id = $1";
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "UPDATE configurations SET general_stop_threshold = ?, mail_subject = ?, mail_recipient = ?, mail_level = ?, mail_interval = ?, logging_ffmpeg_level = ?, logging_ingest_level = ?, logging_detect_silence = ?, logging_ignore = ?, processing_mode = ?, processing_audio_only = ?, processing_copy_audio = ?, processing_copy_video = ?, processing_width = ?, processing_height = ?, processing_aspect = ?, processing_fps = ?, processing_add_logo = ?, processing_logo = ?, processing_logo_scale = ?, processing_logo_opacity = ?, processing_logo_position = ?, processing_audio_tracks = ?, processing_audio_track_index = ?, processing_audio_channels = ?, processing_volume = ?, processing_filter = ?, processing_override_filter = ?, processing_vtt_enable = ?, processing_vtt_dummy = ?, ingest_enable = ?, ingest_param = ?, ingest_filter = ?, playlist_day_start = ?, playlist_length = ?, playlist_infinit = ?, storage_filler = ?, storage_extensions = ?, storage_shuffle = ?, text_add = ?, text_from_filename = ?, text_font = ?, text_style = ?, text_regex = ?, task_enable = ?, task_path = ?, output_mode = ?, output_param = ?, storage_quarantine = ?, logging_decoder_level = ?, logging_encoder_level = ?, logging_decoder_rate = ?, logging_encoder_rate = ?, logging_ingest_rate = ?, logging_crash_dump = ?, storage_protect_days = ?, storage_mirror = ?, storage_exclude = ?, storage_depth = ?, processing_transition = ?, processing_transition_duration = ?, output_scte35 = ?, playlist_chain = ?, playlist_epg = ?, storage_slate = ?, storage_no_repeat_items = ?, storage_no_repeat_time = ?, storage_weights = ?, processing_loudnorm = ?, processing_loudnorm_i = ?, processing_loudnorm_tp = ?, processing_loudnorm_lra = ?, processing_subtitles = ?, processing_subtitle_style = ?, processing_captions = ?, processing_captions_dummy = ?, processing_audio_languages = ?, playlist_feed_url = ?, playlist_feed_auth = ?, playlist_feed_interval = ?, processing_failover_timeout = ?, output_hls_ladder = ?, output_hls_time = ?, output_hls_list_size = ?, output_srt_address = ?, output_srt_mode = ?, output_srt_latency = ?, output_srt_passphrase = ?, output_srt_streamid = ?, output_multicast_address = ?, output_multicast_rtp = ?, output_multicast_ttl = ?, output_multicast_muxrate = ?, output_multicast_service = ?, output_multicast_pmt_pid = ?, output_multicast_video_pid = ?, output_multicast_audio_pid = ?, output_icecast_url = ?, output_icecast_format = ?, output_icecast_bitrate = ?, output_icecast_name = ?, archive_enable = ?, archive_path = ?, archive_retention = ?, archive_param = ?, output_backup = ?, processing_hwaccel = ?, output_preview = ?, output_snapshot = ?, ingest_srt_enable = ?, ingest_srt_address = ?, ingest_srt_passphrase = ?, ingest_srt_streamid = ?, ingest_whip_enable = ?, ingest_whip_url = ?, ingest_whip_source = ?, ingest_windows = ?, ingest_countdown = ?, ingest_transition = ?, ingest_transition_duration = ?, ingest_record = ?, ingest_record_path = ?, ingest_delay = ?, ingest_monitor = ?, text_ticker = ?, text_ticker_url = ?, text_ticker_template = ?, text_ticker_interval = ?, text_ticker_speed = ?, text_ticker_style = ?, processing_logo_rules = ?, text_now_next = ?, text_now_next_duration = ?, text_now_next_now = ?, text_now_next_next = ?, text_now_next_style = ?, processing_html = ?, processing_html_url = ?, processing_html_browser = ?, ingest_voice = ?, ingest_voice_param = ?, ingest_voice_duck = ?, ingest_voice_attack = ?, ingest_voice_release = ?, storage_emergency = ?, storage_emergency_loop = ?, processing_aspect_mode = ?, processing_audio_layout = ?, text_clock = ?, text_clock_format = ?, text_clock_style = ?, processing_filter_template = ?, processing_black_alert = ?, processing_silence_alert = ?, processing_loudness_meter = ?, logging_max_size = ?, logging_max_files = ?, logging_compress = ?, logging_shared = ?, archive_screenshots = ?, archive_screenshot_path = ?, archive_screenshot_interval = ?, archive_screenshot_retention = ?, output_monitor_url = ?, output_monitor_interval = ?, playlist_database = ?, playlist_now_playing = ?, output_preview_whep_url = ?, output_preview_publish = ? WHERE id = ?";
 
+    let weights = config.storage.weights_string();
     let result = sqlx::query(QUERY)
-        .bind(id)
         .bind(config.general.stop_threshold)
         .bind(config.mail.subject)
         .bind(config.mail.recipient)
@@ -310,6 +351,128 @@ pub async fn update_configuration(
         .bind(config.task.path.to_string_lossy().to_string())
         .bind(config.output.mode.to_string())
         .bind(config.output.output_param)
+        .bind(config.storage.quarantine)
+        .bind(config.logging.decoder_level)
+        .bind(config.logging.encoder_level)
+        .bind(config.logging.decoder_rate)
+        .bind(config.logging.encoder_rate)
+        .bind(config.logging.ingest_rate)
+        .bind(config.logging.crash_dump)
+        .bind(config.storage.protect_days)
+        .bind(config.storage.mirror)
+        .bind(config.storage.exclude.join(";"))
+        .bind(config.storage.depth)
+        .bind(config.processing.transition.to_string())
+        .bind(config.processing.transition_duration)
+        .bind(config.output.scte35)
+        .bind(config.playlist.chain)
+        .bind(config.playlist.epg)
+        .bind(config.storage.slate)
+        .bind(config.storage.no_repeat_items)
+        .bind(config.storage.no_repeat_time)
+        .bind(weights)
+        .bind(config.processing.loudnorm)
+        .bind(config.processing.loudnorm_i)
+        .bind(config.processing.loudnorm_tp)
+        .bind(config.processing.loudnorm_lra)
+        .bind(config.processing.subtitles)
+        .bind(config.processing.subtitle_style)
+        .bind(config.processing.captions)
+        .bind(config.processing.captions_dummy)
+        .bind(config.processing.audio_languages.join(";"))
+        .bind(config.playlist.feed_url)
+        .bind(config.playlist.feed_auth)
+        .bind(config.playlist.feed_interval)
+        .bind(config.processing.failover_timeout)
+        .bind(config.output.hls_ladder.join(";"))
+        .bind(config.output.hls_time)
+        .bind(config.output.hls_list_size)
+        .bind(config.output.srt_address)
+        .bind(config.output.srt_mode.to_string())
+        .bind(config.output.srt_latency)
+        .bind(config.output.srt_passphrase)
+        .bind(config.output.srt_streamid)
+        .bind(config.output.multicast_address)
+        .bind(config.output.multicast_rtp)
+        .bind(config.output.multicast_ttl)
+        .bind(config.output.multicast_muxrate)
+        .bind(config.output.multicast_service)
+        .bind(config.output.multicast_pmt_pid)
+        .bind(config.output.multicast_video_pid)
+        .bind(config.output.multicast_audio_pid)
+        .bind(config.output.icecast_url)
+        .bind(config.output.icecast_format.to_string())
+        .bind(config.output.icecast_bitrate)
+        .bind(config.output.icecast_name)
+        .bind(config.archive.enable)
+        .bind(config.archive.path)
+        .bind(config.archive.retention)
+        .bind(config.archive.param)
+        .bind(config.output.backup.join("\n"))
+        .bind(config.processing.hwaccel.to_string())
+        .bind(config.output.preview)
+        .bind(config.output.snapshot)
+        .bind(config.ingest.srt_enable)
+        .bind(config.ingest.srt_address)
+        .bind(config.ingest.srt_passphrase)
+        .bind(config.ingest.srt_streamid)
+        .bind(config.ingest.whip_enable)
+        .bind(config.ingest.whip_url)
+        .bind(config.ingest.whip_source)
+        .bind(config.ingest.windows)
+        .bind(config.ingest.countdown)
+        .bind(config.ingest.transition.to_string())
+        .bind(config.ingest.transition_duration)
+        .bind(config.ingest.record)
+        .bind(config.ingest.record_path)
+        .bind(config.ingest.delay)
+        .bind(config.ingest.monitor)
+        .bind(config.text.ticker)
+        .bind(config.text.ticker_url)
+        .bind(config.text.ticker_template)
+        .bind(config.text.ticker_interval)
+        .bind(config.text.ticker_speed)
+        .bind(config.text.ticker_style)
+        .bind(config.processing.logo_rules)
+        .bind(config.text.now_next)
+        .bind(config.text.now_next_duration)
+        .bind(config.text.now_next_now)
+        .bind(config.text.now_next_next)
+        .bind(config.text.now_next_style)
+        .bind(config.processing.html)
+        .bind(config.processing.html_url)
+        .bind(config.processing.html_browser)
+        .bind(config.ingest.voice)
+        .bind(config.ingest.voice_param)
+        .bind(config.ingest.voice_duck)
+        .bind(config.ingest.voice_attack)
+        .bind(config.ingest.voice_release)
+        .bind(config.storage.emergency)
+        .bind(config.storage.emergency_loop)
+        .bind(config.processing.aspect_mode.to_string())
+        .bind(config.processing.audio_layout)
+        .bind(config.text.clock)
+        .bind(config.text.clock_format)
+        .bind(config.text.clock_style)
+        .bind(config.processing.filter_template)
+        .bind(config.processing.black_alert)
+        .bind(config.processing.silence_alert)
+        .bind(config.processing.loudness_meter)
+        .bind(config.logging.max_size)
+        .bind(config.logging.max_files)
+        .bind(config.logging.compress)
+        .bind(config.logging.shared)
+        .bind(config.archive.screenshots)
+        .bind(config.archive.screenshot_path)
+        .bind(config.archive.screenshot_interval)
+        .bind(config.archive.screenshot_retention)
+        .bind(config.output.monitor_url)
+        .bind(config.output.monitor_interval)
+        .bind(config.playlist.database)
+        .bind(config.playlist.now_playing)
+        .bind(config.output.preview_whep_url)
+        .bind(config.output.preview_publish)
+        .bind(id)
         .execute(conn)
         .await?;
 
@@ -317,7 +480,7 @@ pub async fn update_configuration(
 }
 
 pub async fn insert_advanced_configuration(
-    conn: &Pool<Sqlite>,
+    conn: &DbPool,
     channel_id: i32,
     adv_id: Option<i32>,
     config: AdvancedConfig,
@@ -329,11 +492,11 @@ pub async fn insert_advanced_configuration(
             filter_overlay_logo_fade_out, filter_overlay_logo, filter_tpad, filter_drawtext_from_file,
             filter_drawtext_from_zmq, filter_aevalsrc, filter_afade_in, filter_afade_out, filter_apad,
             filter_volume, filter_split, name)
-            VALUES($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26, $27) RETURNING id";
+            VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)";
 
-    const QUERY_UPDATE: &str = "UPDATE channels SET advanced_id = $2 WHERE id = $1";
+    const QUERY_UPDATE: &str = "UPDATE channels SET advanced_id = ? WHERE id = ?";
 
-    let advanced_id: i32 = sqlx::query(QUERY_INSERT)
+    let result = sqlx::query(QUERY_INSERT)
         .bind(channel_id)
         .bind(config.decoder.input_param)
         .bind(config.decoder.output_param)
@@ -361,15 +524,15 @@ pub async fn insert_advanced_configuration(
         .bind(config.filter.volume)
         .bind(config.filter.split)
         .bind(config.name)
-        .fetch_one(conn)
-        .await?
-        .get("id");
+        .execute(conn)
+        .await?;
 
+    let advanced_id = last_insert_id(&result);
     let a_id = adv_id.unwrap_or(advanced_id);
 
     sqlx::query(QUERY_UPDATE)
-        .bind(channel_id)
         .bind(a_id)
+        .bind(channel_id)
         .execute(conn)
         .await?;
 
@@ -377,21 +540,20 @@ pub async fn insert_advanced_configuration(
 }
 
 pub async fn update_advanced_configuration(
-    conn: &Pool<Sqlite>,
+    conn: &DbPool,
     id: i32,
     config: AdvancedConfig,
 ) -> Result<(), ProcessError> {
-    const QUERY_ADV: &str = "UPDATE advanced_configurations SET decoder_input_param = $2, decoder_output_param = $3,
-        encoder_input_param = $4, ingest_input_param = $5, filter_deinterlace = $6, filter_pad_video = $7, filter_fps = $8,
-        filter_scale = $9, filter_set_dar = $10, filter_fade_in = $11, filter_fade_out = $12, filter_logo = $13,
-        filter_overlay_logo_scale = $14, filter_overlay_logo_fade_in = $15, filter_overlay_logo_fade_out = $16,
-        filter_overlay_logo = $17, filter_tpad = $18, filter_drawtext_from_file = $19, filter_drawtext_from_zmq = $20,
-        filter_aevalsrc = $21, filter_afade_in = $22, filter_afade_out = $23, filter_apad = $24, filter_volume = $25, filter_split = $26, name = $27
-        WHERE id = $1";
-    const QUERY_CHL: &str = "UPDATE channels set advanced_id = $2 WHERE id = $1;";
+    const QUERY_ADV: &str = "UPDATE advanced_configurations SET decoder_input_param = ?, decoder_output_param = ?,
+        encoder_input_param = ?, ingest_input_param = ?, filter_deinterlace = ?, filter_pad_video = ?, filter_fps = ?,
+        filter_scale = ?, filter_set_dar = ?, filter_fade_in = ?, filter_fade_out = ?, filter_logo = ?,
+        filter_overlay_logo_scale = ?, filter_overlay_logo_fade_in = ?, filter_overlay_logo_fade_out = ?,
+        filter_overlay_logo = ?, filter_tpad = ?, filter_drawtext_from_file = ?, filter_drawtext_from_zmq = ?,
+        filter_aevalsrc = ?, filter_afade_in = ?, filter_afade_out = ?, filter_apad = ?, filter_volume = ?, filter_split = ?, name = ?
+        WHERE id = ?";
+    const QUERY_CHL: &str = "UPDATE channels set advanced_id = ? WHERE id = ?;";
 
     sqlx::query(QUERY_ADV)
-        .bind(config.id)
         .bind(config.decoder.input_param)
         .bind(config.decoder.output_param)
         .bind(config.encoder.input_param)
@@ -418,12 +580,13 @@ pub async fn update_advanced_configuration(
         .bind(config.filter.volume)
         .bind(config.filter.split)
         .bind(config.name)
+        .bind(config.id)
         .execute(conn)
         .await?;
 
     sqlx::query(QUERY_CHL)
-        .bind(id)
         .bind(config.id)
+        .bind(id)
         .execute(conn)
         .await?;
 
@@ -431,7 +594,7 @@ pub async fn update_advanced_configuration(
 }
 
 pub async fn select_advanced_configuration(
-    conn: &Pool<Sqlite>,
+    conn: &DbPool,
     channel: i32,
 ) -> Result<AdvancedConfiguration, ProcessError> {
     const QUERY: &str = "SELECT adv.id, adv.channel_id, adv.decoder_input_param, adv.decoder_output_param, adv.encoder_input_param,
@@ -439,7 +602,7 @@ pub async fn select_advanced_configuration(
         adv.filter_fade_in, adv.filter_fade_out, adv.filter_overlay_logo_scale, adv.filter_overlay_logo_fade_in, adv.filter_overlay_logo_fade_out,
         adv.filter_overlay_logo, adv.filter_tpad, adv.filter_drawtext_from_file, adv.filter_drawtext_from_zmq, adv.filter_aevalsrc,
         adv.filter_afade_in, adv.filter_afade_out, adv.filter_apad, adv.filter_volume, adv.filter_split, adv.filter_logo, adv.name
-        FROM advanced_configurations adv left join channels ch on ch.advanced_id = adv.id WHERE ch.id = $1";
+        FROM advanced_configurations adv left join channels ch on ch.advanced_id = adv.id WHERE ch.id = ?";
 
     let result = sqlx::query_as(QUERY)
         .bind(channel)
@@ -451,10 +614,10 @@ pub async fn select_advanced_configuration(
 }
 
 pub async fn select_related_advanced_configuration(
-    conn: &Pool<Sqlite>,
+    conn: &DbPool,
     channel: i32,
 ) -> Result<Vec<AdvancedConfiguration>, ProcessError> {
-    const QUERY: &str = "SELECT * FROM advanced_configurations WHERE channel_id = $1;";
+    const QUERY: &str = "SELECT * FROM advanced_configurations WHERE channel_id = ?;";
 
     let result = sqlx::query_as(QUERY).bind(channel).fetch_all(conn).await?;
 
@@ -462,55 +625,105 @@ pub async fn select_related_advanced_configuration(
 }
 
 pub async fn delete_advanced_configuration(
-    conn: &Pool<Sqlite>,
+    conn: &DbPool,
     id: i32,
-) -> Result<SqliteQueryResult, ProcessError> {
-    const QUERY: &str = "DELETE FROM advanced_configurations WHERE id = $1;";
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "DELETE FROM advanced_configurations WHERE id = ?;";
 
     let result = sqlx::query(QUERY).bind(id).execute(conn).await?;
 
     Ok(result)
 }
 
-pub async fn select_role(conn: &Pool<Sqlite>, id: &i32) -> Result<Role, ProcessError> {
-    const QUERY: &str = "SELECT name FROM roles WHERE id = $1";
+pub async fn select_role(conn: &DbPool, id: &i32) -> Result<Role, ProcessError> {
+    const QUERY: &str = "SELECT name FROM roles WHERE id = ?";
     let result: Role = sqlx::query_as(QUERY).bind(id).fetch_one(conn).await?;
 
     Ok(result)
 }
 
-pub async fn select_login(conn: &Pool<Sqlite>, user: &str) -> Result<User, ProcessError> {
+pub async fn select_upload_limits(conn: &DbPool) -> Result<Vec<UploadLimit>, ProcessError> {
+    const QUERY: &str =
+        "SELECT name, upload_max_size, upload_extensions, upload_folders FROM roles ORDER BY id";
+    let result = sqlx::query_as(QUERY).fetch_all(conn).await?;
+
+    Ok(result)
+}
+
+pub async fn select_upload_limit(conn: &DbPool, role_id: i32) -> Result<UploadLimit, ProcessError> {
+    const QUERY: &str =
+        "SELECT name, upload_max_size, upload_extensions, upload_folders FROM roles WHERE id = ?";
+    let result = sqlx::query_as(QUERY).bind(role_id).fetch_one(conn).await?;
+
+    Ok(result)
+}
+
+pub async fn update_upload_limit(
+    conn: &DbPool,
+    role: &Role,
+    limit: &UploadLimit,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "UPDATE roles SET upload_max_size = ?, upload_extensions = ?, upload_folders = ? WHERE name = ?";
+
+    let result = sqlx::query(QUERY)
+        .bind(limit.max_size)
+        .bind(&limit.extensions)
+        .bind(&limit.folders)
+        .bind(role.to_string())
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_login(conn: &DbPool, user: &str) -> Result<User, ProcessError> {
     const QUERY: &str =
-        "SELECT u.id, u.mail, u.username, u.password, u.role_id, group_concat(uc.channel_id, ',') as channel_ids FROM user u
+        "SELECT u.id, u.mail, u.username, u.password, u.role_id, group_concat(uc.channel_id) as channel_ids FROM user u
         left join user_channels uc on uc.user_id = u.id
-    WHERE u.username = $1";
+    WHERE u.username = ? GROUP BY u.id";
 
-    let result = sqlx::query_as(QUERY).bind(user).fetch_one(conn).await?;
+    let mut result: User = sqlx::query_as(QUERY).bind(user).fetch_one(conn).await?;
+    result.permissions = Some(select_user_permissions(conn, result.id).await?);
 
     Ok(result)
 }
 
-pub async fn select_user(conn: &Pool<Sqlite>, id: i32) -> Result<User, ProcessError> {
-    const QUERY: &str = "SELECT u.id, u.mail, u.username, u.role_id, group_concat(uc.channel_id, ',') as channel_ids FROM user u
+pub async fn select_user(conn: &DbPool, id: i32) -> Result<User, ProcessError> {
+    const QUERY: &str = "SELECT u.id, u.mail, u.username, u.role_id, group_concat(uc.channel_id) as channel_ids FROM user u
         left join user_channels uc on uc.user_id = u.id
-    WHERE u.id = $1";
+    WHERE u.id = ? GROUP BY u.id";
 
-    let result = sqlx::query_as(QUERY).bind(id).fetch_one(conn).await?;
+    let mut result: User = sqlx::query_as(QUERY).bind(id).fetch_one(conn).await?;
+    result.permissions = Some(select_user_permissions(conn, id).await?);
 
     Ok(result)
 }
 
-pub async fn select_global_admins(conn: &Pool<Sqlite>) -> Result<Vec<User>, ProcessError> {
-    const QUERY: &str = "SELECT u.id, u.mail, u.username, u.role_id, group_concat(uc.channel_id, ',') as channel_ids FROM user u
+pub async fn select_user_permissions(
+    conn: &DbPool,
+    user_id: i32,
+) -> Result<HashMap<i32, ChannelPermission>, ProcessError> {
+    const QUERY: &str = "SELECT channel_id, permission FROM user_channels WHERE user_id = ?";
+
+    let rows: Vec<(i32, String)> = sqlx::query_as(QUERY).bind(user_id).fetch_all(conn).await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(channel, permission)| (channel, permission.parse().unwrap_or_default()))
+        .collect())
+}
+
+pub async fn select_global_admins(conn: &DbPool) -> Result<Vec<User>, ProcessError> {
+    const QUERY: &str = "SELECT u.id, u.mail, u.username, u.role_id, group_concat(uc.channel_id) as channel_ids FROM user u
         left join user_channels uc on uc.user_id = u.id
-    WHERE u.role_id = 1";
+    WHERE u.role_id = 1 GROUP BY u.id";
 
     let result = sqlx::query_as(QUERY).fetch_all(conn).await?;
 
     Ok(result)
 }
 
-pub async fn select_users(conn: &Pool<Sqlite>) -> Result<Vec<User>, ProcessError> {
+pub async fn select_users(conn: &DbPool) -> Result<Vec<User>, ProcessError> {
     const QUERY: &str = "SELECT id, username FROM user";
 
     let result = sqlx::query_as(QUERY).fetch_all(conn).await?;
@@ -518,9 +731,8 @@ pub async fn select_users(conn: &Pool<Sqlite>) -> Result<Vec<User>, ProcessError
     Ok(result)
 }
 
-pub async fn insert_user(conn: &Pool<Sqlite>, user: User) -> Result<(), ServiceError> {
-    const QUERY: &str =
-        "INSERT INTO user (mail, username, password, role_id) VALUES($1, $2, $3, $4) RETURNING id";
+pub async fn insert_user(conn: &DbPool, user: User) -> Result<(), ServiceError> {
+    const QUERY: &str = "INSERT INTO user (mail, username, password, role_id) VALUES(?, ?, ?, ?)";
 
     let password_hash = web::block(move || {
         let salt = SaltString::generate(&mut OsRng);
@@ -532,23 +744,26 @@ pub async fn insert_user(conn: &Pool<Sqlite>, user: User) -> Result<(), ServiceE
     })
     .await?;
 
-    let user_id: i32 = sqlx::query(QUERY)
+    let result = sqlx::query(QUERY)
         .bind(user.mail)
         .bind(user.username)
         .bind(password_hash)
         .bind(user.role_id)
-        .fetch_one(conn)
-        .await?
-        .get("id");
+        .execute(conn)
+        .await?;
 
     if let Some(channel_ids) = user.channel_ids {
-        insert_user_channel(conn, user_id, channel_ids).await?;
+        insert_user_channel(conn, last_insert_id(&result), channel_ids).await?;
+    }
+
+    if let Some(permissions) = &user.permissions {
+        update_user_permissions(conn, last_insert_id(&result), permissions).await?;
     }
 
     Ok(())
 }
 
-pub async fn insert_or_update_user(conn: &Pool<Sqlite>, user: User) -> Result<(), ServiceError> {
+pub async fn insert_or_update_user(conn: &DbPool, user: User) -> Result<(), ServiceError> {
     let password_hash = web::block(move || {
         let salt = SaltString::generate(&mut OsRng);
         let hash = Argon2::default()
@@ -559,33 +774,47 @@ pub async fn insert_or_update_user(conn: &Pool<Sqlite>, user: User) -> Result<()
     })
     .await?;
 
-    const QUERY: &str = "INSERT INTO user (mail, username, password, role_id) VALUES($1, $2, $3, $4)
+    #[cfg(not(feature = "mysql"))]
+    const QUERY: &str = "INSERT INTO user (mail, username, password, role_id) VALUES(?, ?, ?, ?)
             ON CONFLICT(username) DO UPDATE SET
-                mail = excluded.mail, username = excluded.username, password = excluded.password, role_id = excluded.role_id
-        RETURNING id";
-
-    let user_id: i32 = sqlx::query(QUERY)
+                mail = excluded.mail, username = excluded.username, password = excluded.password, role_id = excluded.role_id";
+    #[cfg(feature = "mysql")]
+    const QUERY: &str = "INSERT INTO user (mail, username, password, role_id) VALUES(?, ?, ?, ?)
+            ON DUPLICATE KEY UPDATE
+                mail = VALUES(mail), password = VALUES(password), role_id = VALUES(role_id)";
+    const QUERY_ID: &str = "SELECT id FROM user WHERE username = ?";
+
+    sqlx::query(QUERY)
         .bind(user.mail)
-        .bind(user.username)
+        .bind(&user.username)
         .bind(password_hash)
         .bind(user.role_id)
+        .execute(conn)
+        .await?;
+
+    // the id of an updated row is not given back by both backends, so look it up
+    let user_id: i32 = sqlx::query_scalar(QUERY_ID)
+        .bind(user.username)
         .fetch_one(conn)
-        .await?
-        .get("id");
+        .await?;
 
     if let Some(channel_ids) = user.channel_ids {
         insert_user_channel(conn, user_id, channel_ids).await?;
     }
 
+    if let Some(permissions) = &user.permissions {
+        update_user_permissions(conn, user_id, permissions).await?;
+    }
+
     Ok(())
 }
 
 pub async fn update_user(
-    conn: &Pool<Sqlite>,
+    conn: &DbPool,
     id: i32,
     fields: String,
-) -> Result<SqliteQueryResult, ProcessError> {
-    let query = format!("UPDATE user SET {fields} WHERE id = $1");
+) -> Result<DbQueryResult, ProcessError> {
+    let query = format!("UPDATE user SET {fields} WHERE id = ?");
 
     let result = sqlx::query(&query).bind(id).execute(conn).await?;
 
@@ -593,17 +822,21 @@ pub async fn update_user(
 }
 
 pub async fn insert_user_channel(
-    conn: &Pool<Sqlite>,
+    conn: &DbPool,
     user_id: i32,
     channel_ids: Vec<i32>,
 ) -> Result<(), ProcessError> {
     for channel in &channel_ids {
+        #[cfg(not(feature = "mysql"))]
+        const QUERY: &str = "INSERT OR IGNORE INTO user_channels (channel_id, user_id, permission) VALUES (?, ?, ?);";
+        #[cfg(feature = "mysql")]
         const QUERY: &str =
-            "INSERT OR IGNORE INTO user_channels (channel_id, user_id) VALUES ($1, $2);";
+            "INSERT IGNORE INTO user_channels (channel_id, user_id, permission) VALUES (?, ?, ?);";
 
         sqlx::query(QUERY)
             .bind(channel)
             .bind(user_id)
+            .bind(ChannelPermission::default().to_string())
             .execute(conn)
             .await?;
     }
@@ -611,16 +844,186 @@ pub async fn insert_user_channel(
     Ok(())
 }
 
-pub async fn delete_user(conn: &Pool<Sqlite>, id: i32) -> Result<SqliteQueryResult, ProcessError> {
-    const QUERY: &str = "DELETE FROM user WHERE id = $1;";
+/// Set the permissions of a user, only for channels which the user has already.
+pub async fn update_user_permissions(
+    conn: &DbPool,
+    user_id: i32,
+    permissions: &HashMap<i32, ChannelPermission>,
+) -> Result<(), ProcessError> {
+    const QUERY: &str =
+        "UPDATE user_channels SET permission = ? WHERE user_id = ? AND channel_id = ?";
+
+    for (channel, permission) in permissions {
+        sqlx::query(QUERY)
+            .bind(permission.to_string())
+            .bind(user_id)
+            .bind(channel)
+            .execute(conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+pub async fn delete_user(conn: &DbPool, id: i32) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "DELETE FROM user WHERE id = ?;";
 
     let result = sqlx::query(QUERY).bind(id).execute(conn).await?;
 
     Ok(result)
 }
 
-pub async fn select_presets(conn: &Pool<Sqlite>, id: i32) -> Result<Vec<TextPreset>, ProcessError> {
-    const QUERY: &str = "SELECT * FROM presets WHERE channel_id = $1";
+pub async fn select_sessions(conn: &DbPool, user_id: i32) -> Result<Vec<Session>, ProcessError> {
+    const QUERY: &str =
+        "SELECT id, user_id, ip, user_agent, created, last_used, expires FROM sessions
+        WHERE user_id = ? ORDER BY last_used DESC";
+
+    let result = sqlx::query_as(QUERY).bind(user_id).fetch_all(conn).await?;
+
+    Ok(result)
+}
+
+pub async fn select_session_by_hash(
+    conn: &DbPool,
+    token_hash: &str,
+) -> Result<Option<Session>, ProcessError> {
+    const QUERY: &str =
+        "SELECT id, user_id, ip, user_agent, created, last_used, expires FROM sessions
+        WHERE token_hash = ?";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(token_hash)
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn insert_session(
+    conn: &DbPool,
+    session: &Session,
+    token_hash: &str,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str =
+        "INSERT INTO sessions (user_id, token_hash, ip, user_agent, created, last_used, expires)
+        VALUES(?, ?, ?, ?, ?, ?, ?)";
+
+    let result = sqlx::query(QUERY)
+        .bind(session.user_id)
+        .bind(token_hash)
+        .bind(&session.ip)
+        .bind(&session.user_agent)
+        .bind(session.created)
+        .bind(session.last_used)
+        .bind(session.expires)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn update_session_used(
+    conn: &DbPool,
+    id: i32,
+    time: i64,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "UPDATE sessions SET last_used = ? WHERE id = ?";
+
+    let result = sqlx::query(QUERY).bind(time).bind(id).execute(conn).await?;
+
+    Ok(result)
+}
+
+/// Delete a session of the user, without `id` all sessions.
+pub async fn delete_sessions(
+    conn: &DbPool,
+    user_id: i32,
+    id: Option<i32>,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "DELETE FROM sessions WHERE user_id = ? AND (? IS NULL OR id = ?)";
+
+    let result = sqlx::query(QUERY)
+        .bind(user_id)
+        .bind(id)
+        .bind(id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn delete_session_by_hash(
+    conn: &DbPool,
+    token_hash: &str,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "DELETE FROM sessions WHERE token_hash = ?";
+
+    let result = sqlx::query(QUERY).bind(token_hash).execute(conn).await?;
+
+    Ok(result)
+}
+
+pub async fn delete_expired_sessions(
+    conn: &DbPool,
+    time: i64,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "DELETE FROM sessions WHERE expires < ?";
+
+    let result = sqlx::query(QUERY).bind(time).execute(conn).await?;
+
+    Ok(result)
+}
+
+pub async fn insert_auth_audit(
+    conn: &DbPool,
+    entry: &AuthAudit,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str =
+        "INSERT INTO auth_audit (username, ip, event, timestamp) VALUES(?, ?, ?, ?)";
+
+    let result = sqlx::query(QUERY)
+        .bind(&entry.username)
+        .bind(&entry.ip)
+        .bind(&entry.event)
+        .bind(entry.timestamp)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_auth_audit(
+    conn: &DbPool,
+    username: Option<&str>,
+    event: Option<&str>,
+    limit: i64,
+) -> Result<Vec<AuthAudit>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM auth_audit
+        WHERE (? IS NULL OR username = ?) AND (? IS NULL OR event = ?)
+        ORDER BY timestamp DESC, id DESC LIMIT ?";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(username)
+        .bind(username)
+        .bind(event)
+        .bind(event)
+        .bind(limit)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn delete_auth_audit(conn: &DbPool, before: i64) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "DELETE FROM auth_audit WHERE timestamp < ?";
+
+    let result = sqlx::query(QUERY).bind(before).execute(conn).await?;
+
+    Ok(result)
+}
+
+pub async fn select_presets(conn: &DbPool, id: i32) -> Result<Vec<TextPreset>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM presets WHERE channel_id = ?";
 
     let result = sqlx::query_as(QUERY).bind(id).fetch_all(conn).await?;
 
@@ -628,13 +1031,14 @@ pub async fn select_presets(conn: &Pool<Sqlite>, id: i32) -> Result<Vec<TextPres
 }
 
 pub async fn update_preset(
-    conn: &Pool<Sqlite>,
+    conn: &DbPool,
     id: &i32,
     preset: TextPreset,
-) -> Result<SqliteQueryResult, ProcessError> {
+) -> Result<DbQueryResult, ProcessError> {
     const QUERY: &str =
-        "UPDATE presets SET name = $1, text = $2, x = $3, y = $4, fontsize = $5, line_spacing = $6,
-        fontcolor = $7, alpha = $8, box = $9, boxcolor = $10, boxborderw = $11 WHERE id = $12";
+        "UPDATE presets SET name = ?, text = ?, x = ?, y = ?, fontsize = ?, line_spacing = ?,
+        fontcolor = ?, alpha = ?, box = ?, boxcolor = ?, boxborderw = ?, schedule = ?, duration = ?
+        WHERE id = ?";
 
     let result = sqlx::query(QUERY)
         .bind(preset.name)
@@ -648,6 +1052,8 @@ pub async fn update_preset(
         .bind(preset.r#box)
         .bind(preset.boxcolor)
         .bind(preset.boxborderw)
+        .bind(preset.schedule)
+        .bind(preset.duration)
         .bind(id)
         .execute(conn)
         .await?;
@@ -656,12 +1062,12 @@ pub async fn update_preset(
 }
 
 pub async fn insert_preset(
-    conn: &Pool<Sqlite>,
+    conn: &DbPool,
     preset: TextPreset,
-) -> Result<SqliteQueryResult, ProcessError> {
+) -> Result<DbQueryResult, ProcessError> {
     const QUERY: &str =
-        "INSERT INTO presets (channel_id, name, text, x, y, fontsize, line_spacing, fontcolor, alpha, box, boxcolor, boxborderw)
-            VALUES($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)";
+        "INSERT INTO presets (channel_id, name, text, x, y, fontsize, line_spacing, fontcolor, alpha, box, boxcolor, boxborderw, schedule, duration)
+            VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)";
 
     let result = sqlx::query(QUERY)
         .bind(preset.channel_id)
@@ -676,6 +1082,8 @@ pub async fn insert_preset(
         .bind(preset.r#box)
         .bind(preset.boxcolor)
         .bind(preset.boxborderw)
+        .bind(preset.schedule)
+        .bind(preset.duration)
         .execute(conn)
         .await?;
 
@@ -683,27 +1091,1495 @@ pub async fn insert_preset(
 }
 
 pub async fn new_channel_presets(
-    conn: &Pool<Sqlite>,
+    conn: &DbPool,
     channel_id: i32,
-) -> Result<SqliteQueryResult, ProcessError> {
+) -> Result<DbQueryResult, ProcessError> {
     const QUERY: &str = "INSERT INTO presets (name, text, x, y, fontsize, line_spacing, fontcolor, box, boxcolor, boxborderw, alpha, channel_id)
-        VALUES ('Default', 'Welcome to ffplayout messenger!', '(w-text_w)/2', '(h-text_h)/2', '24', '4', '#ffffff@0xff', '0', '#000000@0x80', '4', '1.0', $1),
-        ('Empty Text', '', '0', '0', '24', '4', '#000000', '0', '#000000', '0', '0', $1),
-        ('Bottom Text fade in', 'The upcoming event will be delayed by a few minutes.', '(w-text_w)/2', '(h-line_h)*0.9', '24', '4', '#ffffff', '1', '#000000@0x80', '4', 'ifnot(ld(1),st(1,t));if(lt(t,ld(1)+1),0,if(lt(t,ld(1)+2),(t-(ld(1)+1))/1,if(lt(t,ld(1)+8),1,if(lt(t,ld(1)+9),(1-(t-(ld(1)+8)))/1,0))))', $1),
-        ('Scrolling Text', 'We have a very important announcement to make.', 'ifnot(ld(1),st(1,t));if(lt(t,ld(1)+1),w+4,w-w/12*mod(t-ld(1),12*(w+tw)/w))', '(h-line_h)*0.9', '24', '4', '#ffffff', '1', '#000000@0x80', '4', '1.0', $1);";
+        VALUES ('Default', 'Welcome to ffplayout messenger!', '(w-text_w)/2', '(h-text_h)/2', '24', '4', '#ffffff@0xff', '0', '#000000@0x80', '4', '1.0', ?),
+        ('Empty Text', '', '0', '0', '24', '4', '#000000', '0', '#000000', '0', '0', ?),
+        ('Bottom Text fade in', 'The upcoming event will be delayed by a few minutes.', '(w-text_w)/2', '(h-line_h)*0.9', '24', '4', '#ffffff', '1', '#000000@0x80', '4', 'ifnot(ld(1),st(1,t));if(lt(t,ld(1)+1),0,if(lt(t,ld(1)+2),(t-(ld(1)+1))/1,if(lt(t,ld(1)+8),1,if(lt(t,ld(1)+9),(1-(t-(ld(1)+8)))/1,0))))', ?),
+        ('Scrolling Text', 'We have a very important announcement to make.', 'ifnot(ld(1),st(1,t));if(lt(t,ld(1)+1),w+4,w-w/12*mod(t-ld(1),12*(w+tw)/w))', '(h-line_h)*0.9', '24', '4', '#ffffff', '1', '#000000@0x80', '4', '1.0', ?);";
 
-    let result = sqlx::query(QUERY).bind(channel_id).execute(conn).await?;
+    let result = sqlx::query(QUERY)
+        .bind(channel_id)
+        .bind(channel_id)
+        .bind(channel_id)
+        .bind(channel_id)
+        .execute(conn)
+        .await?;
 
     Ok(result)
 }
 
-pub async fn delete_preset(
-    conn: &Pool<Sqlite>,
-    id: &i32,
-) -> Result<SqliteQueryResult, ProcessError> {
-    const QUERY: &str = "DELETE FROM presets WHERE id = $1;";
+pub async fn delete_preset(conn: &DbPool, id: &i32) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "DELETE FROM presets WHERE id = ?;";
 
     let result = sqlx::query(QUERY).bind(id).execute(conn).await?;
 
     Ok(result)
 }
+
+pub async fn select_insert_rules(
+    conn: &DbPool,
+    channel_id: i32,
+) -> Result<Vec<InsertRule>, ProcessError> {
+    const QUERY: &str =
+        "SELECT * FROM insert_rules WHERE channel_id = ? ORDER BY `interval`, minute";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn insert_insert_rule(
+    conn: &DbPool,
+    rule: &InsertRule,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str =
+        "INSERT INTO insert_rules (channel_id, name, `interval`, minute, path, count, shuffle, active)
+            VALUES(?, ?, ?, ?, ?, ?, ?, ?)";
+
+    let result = sqlx::query(QUERY)
+        .bind(rule.channel_id)
+        .bind(&rule.name)
+        .bind(rule.interval)
+        .bind(rule.minute)
+        .bind(&rule.path)
+        .bind(rule.count)
+        .bind(rule.shuffle)
+        .bind(rule.active)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn update_insert_rule(
+    conn: &DbPool,
+    id: i32,
+    rule: &InsertRule,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str =
+        "UPDATE insert_rules SET name = ?, `interval` = ?, minute = ?, path = ?, count = ?,
+        shuffle = ?, active = ? WHERE id = ? AND channel_id = ?";
+
+    let result = sqlx::query(QUERY)
+        .bind(&rule.name)
+        .bind(rule.interval)
+        .bind(rule.minute)
+        .bind(&rule.path)
+        .bind(rule.count)
+        .bind(rule.shuffle)
+        .bind(rule.active)
+        .bind(id)
+        .bind(rule.channel_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn delete_insert_rule(
+    conn: &DbPool,
+    channel_id: i32,
+    id: i32,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "DELETE FROM insert_rules WHERE id = ? AND channel_id = ?;";
+
+    let result = sqlx::query(QUERY)
+        .bind(id)
+        .bind(channel_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_bumper_rules(
+    conn: &DbPool,
+    channel_id: i32,
+) -> Result<Vec<BumperRule>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM bumper_rules WHERE channel_id = ? ORDER BY category";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn insert_bumper_rule(
+    conn: &DbPool,
+    rule: &BumperRule,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str =
+        "INSERT INTO bumper_rules (channel_id, category, intro, outro, active) VALUES(?, ?, ?, ?, ?)";
+
+    let result = sqlx::query(QUERY)
+        .bind(rule.channel_id)
+        .bind(&rule.category)
+        .bind(&rule.intro)
+        .bind(&rule.outro)
+        .bind(rule.active)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn update_bumper_rule(
+    conn: &DbPool,
+    id: i32,
+    rule: &BumperRule,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "UPDATE bumper_rules SET category = ?, intro = ?, outro = ?, active = ?
+        WHERE id = ? AND channel_id = ?";
+
+    let result = sqlx::query(QUERY)
+        .bind(&rule.category)
+        .bind(&rule.intro)
+        .bind(&rule.outro)
+        .bind(rule.active)
+        .bind(id)
+        .bind(rule.channel_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn delete_bumper_rule(
+    conn: &DbPool,
+    channel_id: i32,
+    id: i32,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "DELETE FROM bumper_rules WHERE id = ? AND channel_id = ?;";
+
+    let result = sqlx::query(QUERY)
+        .bind(id)
+        .bind(channel_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_outputs(
+    conn: &DbPool,
+    channel_id: i32,
+) -> Result<Vec<ExtraOutput>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM outputs WHERE channel_id = ? ORDER BY id";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn insert_output(
+    conn: &DbPool,
+    output: &ExtraOutput,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "INSERT INTO outputs (channel_id, name, param, active) VALUES(?, ?, ?, ?)";
+
+    let result = sqlx::query(QUERY)
+        .bind(output.channel_id)
+        .bind(&output.name)
+        .bind(&output.param)
+        .bind(output.active)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn update_output(
+    conn: &DbPool,
+    id: i32,
+    output: &ExtraOutput,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str =
+        "UPDATE outputs SET name = ?, param = ?, active = ? WHERE id = ? AND channel_id = ?";
+
+    let result = sqlx::query(QUERY)
+        .bind(&output.name)
+        .bind(&output.param)
+        .bind(output.active)
+        .bind(id)
+        .bind(output.channel_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn delete_output(
+    conn: &DbPool,
+    channel_id: i32,
+    id: i32,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "DELETE FROM outputs WHERE id = ? AND channel_id = ?;";
+
+    let result = sqlx::query(QUERY)
+        .bind(id)
+        .bind(channel_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_filler_sources(
+    conn: &DbPool,
+    channel_id: i32,
+) -> Result<Vec<FillerSource>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM filler_sources WHERE channel_id = ? ORDER BY priority, id";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn insert_filler_source(
+    conn: &DbPool,
+    source: &FillerSource,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "INSERT INTO filler_sources (channel_id, path, priority, weight, no_repeat)
+            VALUES(?, ?, ?, ?, ?)";
+
+    let result = sqlx::query(QUERY)
+        .bind(source.channel_id)
+        .bind(&source.path)
+        .bind(source.priority)
+        .bind(source.weight)
+        .bind(source.no_repeat)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn update_filler_source(
+    conn: &DbPool,
+    id: i32,
+    source: &FillerSource,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str =
+        "UPDATE filler_sources SET path = ?, priority = ?, weight = ?, no_repeat = ?
+        WHERE id = ? AND channel_id = ?";
+
+    let result = sqlx::query(QUERY)
+        .bind(&source.path)
+        .bind(source.priority)
+        .bind(source.weight)
+        .bind(source.no_repeat)
+        .bind(id)
+        .bind(source.channel_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn delete_filler_source(
+    conn: &DbPool,
+    channel_id: i32,
+    id: i32,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "DELETE FROM filler_sources WHERE id = ? AND channel_id = ?;";
+
+    let result = sqlx::query(QUERY)
+        .bind(id)
+        .bind(channel_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_filter_templates(
+    conn: &DbPool,
+    channel_id: i32,
+) -> Result<Vec<FilterTemplate>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM filter_templates WHERE channel_id = ? ORDER BY name";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn insert_filter_template(
+    conn: &DbPool,
+    template: &FilterTemplate,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str =
+        "INSERT INTO filter_templates (channel_id, name, video_filter, audio_filter)
+            VALUES(?, ?, ?, ?)";
+
+    let result = sqlx::query(QUERY)
+        .bind(template.channel_id)
+        .bind(&template.name)
+        .bind(&template.video_filter)
+        .bind(&template.audio_filter)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn update_filter_template(
+    conn: &DbPool,
+    id: i32,
+    template: &FilterTemplate,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "UPDATE filter_templates SET name = ?, video_filter = ?, audio_filter = ?
+        WHERE id = ? AND channel_id = ?";
+
+    let result = sqlx::query(QUERY)
+        .bind(&template.name)
+        .bind(&template.video_filter)
+        .bind(&template.audio_filter)
+        .bind(id)
+        .bind(template.channel_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn delete_filter_template(
+    conn: &DbPool,
+    channel_id: i32,
+    id: i32,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "DELETE FROM filter_templates WHERE id = ? AND channel_id = ?;";
+
+    let result = sqlx::query(QUERY)
+        .bind(id)
+        .bind(channel_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_live_events(
+    conn: &DbPool,
+    channel_id: i32,
+) -> Result<Vec<LiveEvent>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM live_events WHERE channel_id = ? ORDER BY start_time";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_live_event(
+    conn: &DbPool,
+    channel_id: i32,
+    id: i32,
+) -> Result<Option<LiveEvent>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM live_events WHERE id = ? AND channel_id = ?";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(id)
+        .bind(channel_id)
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(result)
+}
+
+/// Running or next upcoming event.
+pub async fn select_next_live_event(
+    conn: &DbPool,
+    channel_id: i32,
+    now: i64,
+) -> Result<Option<LiveEvent>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM live_events WHERE channel_id = ? AND end_time > ?
+        ORDER BY start_time LIMIT 1";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .bind(now)
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(result)
+}
+
+/// Events of the channel, which overlap with the given time range.
+pub async fn select_overlapping_live_events(
+    conn: &DbPool,
+    channel_id: i32,
+    start_time: i64,
+    end_time: i64,
+) -> Result<Vec<LiveEvent>, ProcessError> {
+    const QUERY: &str =
+        "SELECT * FROM live_events WHERE channel_id = ? AND start_time < ? AND end_time > ?";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .bind(end_time)
+        .bind(start_time)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn insert_live_event(
+    conn: &DbPool,
+    event: &LiveEvent,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "INSERT INTO live_events (channel_id, name, source, start_time, end_time)
+        VALUES(?, ?, ?, ?, ?)";
+
+    let result = sqlx::query(QUERY)
+        .bind(event.channel_id)
+        .bind(&event.name)
+        .bind(&event.source)
+        .bind(event.start_time)
+        .bind(event.end_time)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn update_live_event(
+    conn: &DbPool,
+    id: i32,
+    event: &LiveEvent,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "UPDATE live_events SET name = ?, source = ?, start_time = ?, end_time = ?
+        WHERE id = ? AND channel_id = ?";
+
+    let result = sqlx::query(QUERY)
+        .bind(&event.name)
+        .bind(&event.source)
+        .bind(event.start_time)
+        .bind(event.end_time)
+        .bind(id)
+        .bind(event.channel_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn delete_live_event(
+    conn: &DbPool,
+    channel_id: i32,
+    id: i32,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "DELETE FROM live_events WHERE id = ? AND channel_id = ?;";
+
+    let result = sqlx::query(QUERY)
+        .bind(id)
+        .bind(channel_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_ingest_keys(
+    conn: &DbPool,
+    channel_id: i32,
+) -> Result<Vec<IngestKey>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM ingest_keys WHERE channel_id = ? ORDER BY name";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_ingest_key(
+    conn: &DbPool,
+    channel_id: i32,
+    id: i32,
+) -> Result<Option<IngestKey>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM ingest_keys WHERE id = ? AND channel_id = ?";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(id)
+        .bind(channel_id)
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_ingest_key_by_key(
+    conn: &DbPool,
+    channel_id: i32,
+    stream_key: &str,
+) -> Result<Option<IngestKey>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM ingest_keys WHERE channel_id = ? AND stream_key = ?";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .bind(stream_key)
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn insert_ingest_key(
+    conn: &DbPool,
+    key: &IngestKey,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str =
+        "INSERT INTO ingest_keys (channel_id, name, stream_key, active) VALUES(?, ?, ?, ?)";
+
+    let result = sqlx::query(QUERY)
+        .bind(key.channel_id)
+        .bind(&key.name)
+        .bind(&key.stream_key)
+        .bind(key.active)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn update_ingest_key(
+    conn: &DbPool,
+    id: i32,
+    key: &IngestKey,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "UPDATE ingest_keys SET name = ?, stream_key = ?, active = ?
+        WHERE id = ? AND channel_id = ?";
+
+    let result = sqlx::query(QUERY)
+        .bind(&key.name)
+        .bind(&key.stream_key)
+        .bind(key.active)
+        .bind(id)
+        .bind(key.channel_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn update_ingest_key_used(
+    conn: &DbPool,
+    id: i32,
+    time: i64,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "UPDATE ingest_keys SET last_used = ? WHERE id = ?";
+
+    let result = sqlx::query(QUERY).bind(time).bind(id).execute(conn).await?;
+
+    Ok(result)
+}
+
+pub async fn delete_ingest_key(
+    conn: &DbPool,
+    channel_id: i32,
+    id: i32,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "DELETE FROM ingest_keys WHERE id = ? AND channel_id = ?;";
+
+    let result = sqlx::query(QUERY)
+        .bind(id)
+        .bind(channel_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_api_tokens(
+    conn: &DbPool,
+    user_id: Option<i32>,
+) -> Result<Vec<ApiToken>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM api_tokens WHERE ? IS NULL OR user_id = ? ORDER BY name";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(user_id)
+        .bind(user_id)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_api_token_by_hash(
+    conn: &DbPool,
+    token_hash: &str,
+) -> Result<Option<ApiToken>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM api_tokens WHERE token_hash = ?";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(token_hash)
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn insert_api_token(
+    conn: &DbPool,
+    token: &ApiToken,
+    token_hash: &str,
+) -> Result<i32, ProcessError> {
+    const QUERY: &str = "INSERT INTO api_tokens (user_id, name, token_hash, prefix, scope, channel_ids, created, expires)
+        VALUES(?, ?, ?, ?, ?, ?, ?, ?)";
+
+    let channel_ids = token
+        .channel_ids
+        .iter()
+        .map(i32::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let result = sqlx::query(QUERY)
+        .bind(token.user_id)
+        .bind(&token.name)
+        .bind(token_hash)
+        .bind(&token.prefix)
+        .bind(token.scope.to_string())
+        .bind(channel_ids)
+        .bind(token.created)
+        .bind(token.expires)
+        .execute(conn)
+        .await?;
+
+    Ok(last_insert_id(&result))
+}
+
+pub async fn update_api_token_used(
+    conn: &DbPool,
+    id: i32,
+    time: i64,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "UPDATE api_tokens SET last_used = ? WHERE id = ?";
+
+    let result = sqlx::query(QUERY).bind(time).bind(id).execute(conn).await?;
+
+    Ok(result)
+}
+
+/// Delete a token, with `user_id` only one which this user has created.
+pub async fn delete_api_token(
+    conn: &DbPool,
+    id: i32,
+    user_id: Option<i32>,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "DELETE FROM api_tokens WHERE id = ? AND (? IS NULL OR user_id = ?)";
+
+    let result = sqlx::query(QUERY)
+        .bind(id)
+        .bind(user_id)
+        .bind(user_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_media_hashes(
+    conn: &DbPool,
+    channel_id: i32,
+) -> Result<Vec<MediaHash>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM media WHERE channel_id = ?";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_media_hash(
+    conn: &DbPool,
+    channel_id: i32,
+    path: &str,
+) -> Result<Option<MediaHash>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM media WHERE channel_id = ? AND path = ?";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .bind(path)
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_media_duplicates(
+    conn: &DbPool,
+    channel_id: i32,
+) -> Result<Vec<MediaHash>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM media WHERE channel_id = ? AND hash IN
+        (SELECT hash FROM media WHERE channel_id = ? GROUP BY hash HAVING COUNT(*) > 1)
+        ORDER BY hash, path";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .bind(channel_id)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_media_errors(
+    conn: &DbPool,
+    channel_id: i32,
+) -> Result<Vec<MediaHash>, ProcessError> {
+    const QUERY: &str =
+        "SELECT * FROM media WHERE channel_id = ? AND error IS NOT NULL ORDER BY path";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn upsert_media_hash(
+    conn: &DbPool,
+    media: &MediaHash,
+) -> Result<DbQueryResult, ProcessError> {
+    // a loudness measurement stays, as long as the file is not changed
+    #[cfg(not(feature = "mysql"))]
+    const QUERY: &str = "INSERT INTO media (channel_id, path, hash, size, modified, duration, error, checked, loudness_i, loudness_tp, loudness_lra, loudness_thresh)
+        VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(channel_id, path) DO UPDATE SET
+            loudness_i = CASE WHEN size = excluded.size AND modified = excluded.modified THEN COALESCE(excluded.loudness_i, loudness_i) ELSE excluded.loudness_i END,
+            loudness_tp = CASE WHEN size = excluded.size AND modified = excluded.modified THEN COALESCE(excluded.loudness_tp, loudness_tp) ELSE excluded.loudness_tp END,
+            loudness_lra = CASE WHEN size = excluded.size AND modified = excluded.modified THEN COALESCE(excluded.loudness_lra, loudness_lra) ELSE excluded.loudness_lra END,
+            loudness_thresh = CASE WHEN size = excluded.size AND modified = excluded.modified THEN COALESCE(excluded.loudness_thresh, loudness_thresh) ELSE excluded.loudness_thresh END,
+            hash = excluded.hash, size = excluded.size, modified = excluded.modified, duration = excluded.duration, error = excluded.error, checked = excluded.checked";
+    // MySQL sets the columns from left to right, so size and modified have to come last
+    #[cfg(feature = "mysql")]
+    const QUERY: &str = "INSERT INTO media (channel_id, path, hash, size, modified, duration, error, checked, loudness_i, loudness_tp, loudness_lra, loudness_thresh)
+        VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON DUPLICATE KEY UPDATE
+            loudness_i = CASE WHEN size = VALUES(size) AND modified = VALUES(modified) THEN COALESCE(VALUES(loudness_i), loudness_i) ELSE VALUES(loudness_i) END,
+            loudness_tp = CASE WHEN size = VALUES(size) AND modified = VALUES(modified) THEN COALESCE(VALUES(loudness_tp), loudness_tp) ELSE VALUES(loudness_tp) END,
+            loudness_lra = CASE WHEN size = VALUES(size) AND modified = VALUES(modified) THEN COALESCE(VALUES(loudness_lra), loudness_lra) ELSE VALUES(loudness_lra) END,
+            loudness_thresh = CASE WHEN size = VALUES(size) AND modified = VALUES(modified) THEN COALESCE(VALUES(loudness_thresh), loudness_thresh) ELSE VALUES(loudness_thresh) END,
+            hash = VALUES(hash), size = VALUES(size), modified = VALUES(modified), duration = VALUES(duration), error = VALUES(error), checked = VALUES(checked)";
+
+    let result = sqlx::query(QUERY)
+        .bind(media.channel_id)
+        .bind(&media.path)
+        .bind(&media.hash)
+        .bind(media.size)
+        .bind(media.modified)
+        .bind(media.duration)
+        .bind(&media.error)
+        .bind(media.checked)
+        .bind(media.loudness_i)
+        .bind(media.loudness_tp)
+        .bind(media.loudness_lra)
+        .bind(media.loudness_thresh)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_storage_migration(
+    conn: &DbPool,
+    channel_id: i32,
+) -> Result<Vec<StorageMigration>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM storage_migrations WHERE channel_id = ? ORDER BY path";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn upsert_storage_migration(
+    conn: &DbPool,
+    item: &StorageMigration,
+) -> Result<DbQueryResult, ProcessError> {
+    #[cfg(not(feature = "mysql"))]
+    const QUERY: &str =
+        "INSERT INTO storage_migrations (channel_id, target, path, size, done, error)
+        VALUES(?, ?, ?, ?, ?, ?)
+        ON CONFLICT(channel_id, target, path) DO UPDATE SET size = excluded.size, done = excluded.done, error = excluded.error";
+    #[cfg(feature = "mysql")]
+    const QUERY: &str =
+        "INSERT INTO storage_migrations (channel_id, target, path, size, done, error)
+        VALUES(?, ?, ?, ?, ?, ?)
+        ON DUPLICATE KEY UPDATE size = VALUES(size), done = VALUES(done), error = VALUES(error)";
+
+    let result = sqlx::query(QUERY)
+        .bind(item.channel_id)
+        .bind(&item.target)
+        .bind(&item.path)
+        .bind(item.size)
+        .bind(item.done)
+        .bind(&item.error)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+/// Remove the progress of migrations to other targets than the given one.
+pub async fn delete_storage_migration(
+    conn: &DbPool,
+    channel_id: i32,
+    target: &str,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "DELETE FROM storage_migrations WHERE channel_id = ? AND target != ?";
+
+    let result = sqlx::query(QUERY)
+        .bind(channel_id)
+        .bind(target)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn insert_storage_audit(
+    conn: &DbPool,
+    item: &StorageAudit,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "INSERT INTO storage_audit (channel_id, user_id, username, action, path, target, timestamp, error)
+        VALUES(?, ?, ?, ?, ?, ?, ?, ?)";
+
+    let result = sqlx::query(QUERY)
+        .bind(item.channel_id)
+        .bind(item.user_id)
+        .bind(&item.username)
+        .bind(&item.action)
+        .bind(&item.path)
+        .bind(&item.target)
+        .bind(item.timestamp)
+        .bind(&item.error)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+/// Audit entries of a channel, newest first. Empty filters match everything,
+/// `path` matches also parts of the source or target path.
+#[allow(clippy::too_many_arguments)]
+pub async fn select_storage_audit(
+    conn: &DbPool,
+    channel_id: i32,
+    username: Option<&str>,
+    action: Option<&str>,
+    path: Option<&str>,
+    from: Option<i64>,
+    to: Option<i64>,
+    limit: i64,
+) -> Result<Vec<StorageAudit>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM storage_audit WHERE channel_id = ?
+        AND (? IS NULL OR username = ?)
+        AND (? IS NULL OR action = ?)
+        AND (? IS NULL OR path LIKE ? OR target LIKE ?)
+        AND (? IS NULL OR timestamp >= ?)
+        AND (? IS NULL OR timestamp <= ?)
+        ORDER BY timestamp DESC, id DESC LIMIT ?";
+
+    let pattern = path.map(|p| format!("%{p}%"));
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .bind(username)
+        .bind(username)
+        .bind(action)
+        .bind(action)
+        .bind(&pattern)
+        .bind(&pattern)
+        .bind(&pattern)
+        .bind(from)
+        .bind(from)
+        .bind(to)
+        .bind(to)
+        .bind(limit)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn update_media_validity(
+    conn: &DbPool,
+    channel_id: i32,
+    path: &str,
+    valid_from: Option<String>,
+    valid_to: Option<String>,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str =
+        "UPDATE media SET valid_from = ?, valid_to = ? WHERE channel_id = ? AND path = ?";
+
+    let result = sqlx::query(QUERY)
+        .bind(valid_from)
+        .bind(valid_to)
+        .bind(channel_id)
+        .bind(path)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn delete_media_hash(
+    conn: &DbPool,
+    channel_id: i32,
+    path: &str,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "DELETE FROM media WHERE channel_id = ? AND path = ?";
+
+    let result = sqlx::query(QUERY)
+        .bind(channel_id)
+        .bind(path)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_webhooks(conn: &DbPool, channel_id: i32) -> Result<Vec<Webhook>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM webhooks WHERE channel_id = ? ORDER BY id";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn insert_webhook(
+    conn: &DbPool,
+    webhook: &Webhook,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "INSERT INTO webhooks (channel_id, url, secret, events, active)
+            VALUES(?, ?, ?, ?, ?)";
+
+    let result = sqlx::query(QUERY)
+        .bind(webhook.channel_id)
+        .bind(&webhook.url)
+        .bind(&webhook.secret)
+        .bind(&webhook.events)
+        .bind(webhook.active)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn update_webhook(
+    conn: &DbPool,
+    id: i32,
+    webhook: &Webhook,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "UPDATE webhooks SET url = ?, secret = ?, events = ?, active = ?
+        WHERE id = ? AND channel_id = ?";
+
+    let result = sqlx::query(QUERY)
+        .bind(&webhook.url)
+        .bind(&webhook.secret)
+        .bind(&webhook.events)
+        .bind(webhook.active)
+        .bind(id)
+        .bind(webhook.channel_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn delete_webhook(
+    conn: &DbPool,
+    channel_id: i32,
+    id: i32,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "DELETE FROM webhooks WHERE id = ? AND channel_id = ?;";
+
+    let result = sqlx::query(QUERY)
+        .bind(id)
+        .bind(channel_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_notifiers(
+    conn: &DbPool,
+    channel_id: i32,
+) -> Result<Vec<Notifier>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM notifiers WHERE channel_id = ? ORDER BY id";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn insert_notifier(
+    conn: &DbPool,
+    notifier: &Notifier,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "INSERT INTO notifiers (channel_id, kind, url, token, target, active)
+            VALUES(?, ?, ?, ?, ?, ?)";
+
+    let result = sqlx::query(QUERY)
+        .bind(notifier.channel_id)
+        .bind(&notifier.kind)
+        .bind(&notifier.url)
+        .bind(&notifier.token)
+        .bind(&notifier.target)
+        .bind(notifier.active)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn update_notifier(
+    conn: &DbPool,
+    id: i32,
+    notifier: &Notifier,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "UPDATE notifiers SET kind = ?, url = ?, token = ?, target = ?, active = ?
+        WHERE id = ? AND channel_id = ?";
+
+    let result = sqlx::query(QUERY)
+        .bind(&notifier.kind)
+        .bind(&notifier.url)
+        .bind(&notifier.token)
+        .bind(&notifier.target)
+        .bind(notifier.active)
+        .bind(id)
+        .bind(notifier.channel_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn delete_notifier(
+    conn: &DbPool,
+    channel_id: i32,
+    id: i32,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "DELETE FROM notifiers WHERE id = ? AND channel_id = ?;";
+
+    let result = sqlx::query(QUERY)
+        .bind(id)
+        .bind(channel_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_alert_rules(
+    conn: &DbPool,
+    channel_id: i32,
+) -> Result<Vec<AlertRule>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM alert_rules WHERE channel_id = ? ORDER BY id";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn insert_alert_rule(
+    conn: &DbPool,
+    rule: &AlertRule,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "INSERT INTO alert_rules (channel_id, name, `condition`, threshold, minutes, time, severity, targets, active)
+            VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?)";
+
+    let result = sqlx::query(QUERY)
+        .bind(rule.channel_id)
+        .bind(&rule.name)
+        .bind(&rule.condition)
+        .bind(rule.threshold)
+        .bind(rule.minutes)
+        .bind(&rule.time)
+        .bind(&rule.severity)
+        .bind(&rule.targets)
+        .bind(rule.active)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn update_alert_rule(
+    conn: &DbPool,
+    id: i32,
+    rule: &AlertRule,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str =
+        "UPDATE alert_rules SET name = ?, `condition` = ?, threshold = ?, minutes = ?, time = ?,
+        severity = ?, targets = ?, active = ? WHERE id = ? AND channel_id = ?";
+
+    let result = sqlx::query(QUERY)
+        .bind(&rule.name)
+        .bind(&rule.condition)
+        .bind(rule.threshold)
+        .bind(rule.minutes)
+        .bind(&rule.time)
+        .bind(&rule.severity)
+        .bind(&rule.targets)
+        .bind(rule.active)
+        .bind(id)
+        .bind(rule.channel_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn delete_alert_rule(
+    conn: &DbPool,
+    channel_id: i32,
+    id: i32,
+) -> Result<DbQueryResult, ProcessError> {
+    const QUERY: &str = "DELETE FROM alert_rules WHERE id = ? AND channel_id = ?;";
+
+    let result = sqlx::query(QUERY)
+        .bind(id)
+        .bind(channel_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_playlist(
+    conn: &DbPool,
+    channel_id: i32,
+    date: &str,
+) -> Result<StoredPlaylist, ProcessError> {
+    const QUERY: &str = "SELECT * FROM playlists WHERE channel_id = ? AND date = ?";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .bind(date)
+        .fetch_one(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_playlist_items(
+    conn: &DbPool,
+    playlist_id: i32,
+) -> Result<Vec<PlaylistItem>, ProcessError> {
+    const QUERY: &str =
+        "SELECT position, source, title, seek, `out`, duration, data FROM playlist_items
+        WHERE playlist_id = ? ORDER BY position";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(playlist_id)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_playlist_dates(
+    conn: &DbPool,
+    channel_id: i32,
+) -> Result<Vec<String>, ProcessError> {
+    const QUERY: &str = "SELECT date FROM playlists WHERE channel_id = ? ORDER BY date";
+
+    let result = sqlx::query_scalar(QUERY)
+        .bind(channel_id)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+/// Highest revision of the playlist, also of a deleted one, which has only history left.
+async fn last_playlist_revision(
+    conn: &mut DbConnection,
+    channel_id: i32,
+    date: &str,
+) -> Result<i32, ProcessError> {
+    const QUERY: &str = "SELECT COALESCE(MAX(revision), 0) FROM (
+            SELECT revision FROM playlists WHERE channel_id = ? AND date = ?
+            UNION ALL
+            SELECT revision FROM playlist_history WHERE channel_id = ? AND date = ?
+        ) AS revisions";
+
+    let result = sqlx::query_scalar(QUERY)
+        .bind(channel_id)
+        .bind(date)
+        .bind(channel_id)
+        .bind(date)
+        .fetch_one(conn)
+        .await?;
+
+    Ok(result)
+}
+
+async fn insert_playlist_changes(
+    conn: &mut DbConnection,
+    changes: &[PlaylistChange],
+) -> Result<(), ProcessError> {
+    const QUERY: &str = "INSERT INTO playlist_history (channel_id, date, revision, position, action, user_id, previous, item, timestamp)
+        VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?)";
+
+    for change in changes {
+        sqlx::query(QUERY)
+            .bind(change.channel_id)
+            .bind(&change.date)
+            .bind(change.revision)
+            .bind(change.position)
+            .bind(&change.action)
+            .bind(change.user_id)
+            .bind(&change.previous)
+            .bind(&change.item)
+            .bind(change.timestamp)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Replace the items of the playlist in one transaction and record the changed items.
+///
+/// Gives the new revision, or `None` when the items are the same as the stored ones.
+pub async fn save_playlist(
+    conn: &DbPool,
+    channel_id: i32,
+    user_id: i32,
+    date: &str,
+    channel: &str,
+    items: &[PlaylistItem],
+) -> Result<Option<i32>, ProcessError> {
+    const QUERY_INSERT: &str =
+        "INSERT INTO playlists (channel_id, date, channel, revision, modified) VALUES(?, ?, ?, ?, ?)";
+    const QUERY_UPDATE: &str =
+        "UPDATE playlists SET channel = ?, revision = ?, modified = ? WHERE id = ?";
+    const QUERY_CLEAR: &str = "DELETE FROM playlist_items WHERE playlist_id = ?";
+    const QUERY_ITEM: &str = "INSERT INTO playlist_items (playlist_id, position, source, title, seek, `out`, duration, data)
+        VALUES(?, ?, ?, ?, ?, ?, ?, ?)";
+
+    let mut tx = conn.begin().await?;
+    let stored: Option<StoredPlaylist> =
+        sqlx::query_as("SELECT * FROM playlists WHERE channel_id = ? AND date = ?")
+            .bind(channel_id)
+            .bind(date)
+            .fetch_optional(&mut *tx)
+            .await?;
+    let old_items: Vec<String> = match &stored {
+        Some(playlist) => {
+            sqlx::query_scalar(
+                "SELECT data FROM playlist_items WHERE playlist_id = ? ORDER BY position",
+            )
+            .bind(playlist.id)
+            .fetch_all(&mut *tx)
+            .await?
+        }
+        None => vec![],
+    };
+    let new_items = items.iter().map(|i| i.data.clone()).collect::<Vec<_>>();
+    let mut changes = item_changes(&old_items, &new_items);
+
+    if stored
+        .as_ref()
+        .is_some_and(|p| p.channel == channel && changes.is_empty())
+    {
+        return Ok(None);
+    }
+
+    let revision = last_playlist_revision(&mut tx, channel_id, date).await? + 1;
+    let timestamp = time_now(&None).timestamp();
+
+    let playlist_id = match &stored {
+        Some(playlist) => {
+            sqlx::query(QUERY_UPDATE)
+                .bind(channel)
+                .bind(revision)
+                .bind(timestamp)
+                .bind(playlist.id)
+                .execute(&mut *tx)
+                .await?;
+
+            sqlx::query(QUERY_CLEAR)
+                .bind(playlist.id)
+                .execute(&mut *tx)
+                .await?;
+
+            playlist.id
+        }
+        None => {
+            let result = sqlx::query(QUERY_INSERT)
+                .bind(channel_id)
+                .bind(date)
+                .bind(channel)
+                .bind(revision)
+                .bind(timestamp)
+                .execute(&mut *tx)
+                .await?;
+
+            last_insert_id(&result)
+        }
+    };
+
+    for item in items {
+        sqlx::query(QUERY_ITEM)
+            .bind(playlist_id)
+            .bind(item.position)
+            .bind(&item.source)
+            .bind(&item.title)
+            .bind(item.seek)
+            .bind(item.out)
+            .bind(item.duration)
+            .bind(&item.data)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    for change in &mut changes {
+        change.channel_id = channel_id;
+        change.date = date.to_string();
+        change.revision = revision;
+        change.user_id = user_id;
+        change.timestamp = timestamp;
+    }
+
+    insert_playlist_changes(&mut tx, &changes).await?;
+    tx.commit().await?;
+
+    Ok(Some(revision))
+}
+
+/// Delete the playlist and record all its items as removed. Gives `false` when no playlist exists.
+pub async fn delete_playlist(
+    conn: &DbPool,
+    channel_id: i32,
+    user_id: i32,
+    date: &str,
+) -> Result<bool, ProcessError> {
+    let mut tx = conn.begin().await?;
+    let stored: Option<StoredPlaylist> =
+        sqlx::query_as("SELECT * FROM playlists WHERE channel_id = ? AND date = ?")
+            .bind(channel_id)
+            .bind(date)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+    let Some(playlist) = stored else {
+        return Ok(false);
+    };
+
+    let old_items: Vec<String> = sqlx::query_scalar(
+        "SELECT data FROM playlist_items WHERE playlist_id = ? ORDER BY position",
+    )
+    .bind(playlist.id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let revision = last_playlist_revision(&mut tx, channel_id, date).await? + 1;
+    let timestamp = time_now(&None).timestamp();
+    let mut changes = item_changes(&old_items, &[]);
+
+    for change in &mut changes {
+        change.channel_id = channel_id;
+        change.date = date.to_string();
+        change.revision = revision;
+        change.user_id = user_id;
+        change.timestamp = timestamp;
+    }
+
+    insert_playlist_changes(&mut tx, &changes).await?;
+
+    sqlx::query("DELETE FROM playlists WHERE id = ?")
+        .bind(playlist.id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(true)
+}
+
+/// Changes of the playlist items from a date, newest first.
+pub async fn select_playlist_history(
+    conn: &DbPool,
+    channel_id: i32,
+    date: &str,
+) -> Result<Vec<PlaylistChange>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM playlist_history WHERE channel_id = ? AND date = ?
+        ORDER BY revision DESC, position";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .bind(date)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+/// Items from the given date on, which source or title contains the search text.
+pub async fn select_scheduled_items(
+    conn: &DbPool,
+    channel_id: i32,
+    from: &str,
+    search: &str,
+    limit: i64,
+) -> Result<Vec<ScheduledItem>, ProcessError> {
+    const QUERY: &str = "SELECT p.date, i.position, i.source, i.title, i.seek, i.`out`,
+            (SELECT COALESCE(SUM(b.`out` - b.seek), 0.0) FROM playlist_items b
+                WHERE b.playlist_id = i.playlist_id AND b.position < i.position) AS start
+        FROM playlist_items i JOIN playlists p ON p.id = i.playlist_id
+        WHERE p.channel_id = ? AND p.date >= ? AND (i.source LIKE ? OR i.title LIKE ?)
+        ORDER BY p.date, i.position LIMIT ?";
+
+    let pattern = format!("%{search}%");
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .bind(from)
+        .bind(&pattern)
+        .bind(&pattern)
+        .bind(limit)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
@@ -1,14 +1,13 @@
-use std::{
-    borrow::Cow,
-    io,
-    path::Path,
-    sync::{LazyLock, OnceLock},
-};
+use std::sync::OnceLock;
+#[cfg(not(feature = "mysql"))]
+use std::{borrow::Cow, io, path::Path, sync::LazyLock};
 
+#[cfg(not(feature = "mysql"))]
 use faccess::PathExt;
 use inquire::Confirm;
+#[cfg(not(feature = "mysql"))]
 use log::*;
-use sqlx::{migrate::MigrateDatabase, Pool, Sqlite, SqlitePool};
+use sqlx::{migrate::MigrateDatabase, Database, Pool};
 
 pub mod handles;
 pub mod models;
@@ -16,6 +15,18 @@ pub mod models;
 use crate::{utils::errors::ProcessError, ARGS};
 use models::GlobalSettings;
 
+/// Database backend, SQLite or, with the `mysql` feature, MySQL/MariaDB.
+#[cfg(not(feature = "mysql"))]
+pub type Db = sqlx::Sqlite;
+#[cfg(feature = "mysql")]
+pub type Db = sqlx::MySql;
+
+pub type DbPool = Pool<Db>;
+pub type DbConnection = <Db as Database>::Connection;
+pub type DbRow = <Db as Database>::Row;
+pub type DbQueryResult = <Db as Database>::QueryResult;
+
+#[cfg(not(feature = "mysql"))]
 pub static DB_PATH: LazyLock<Result<Cow<'static, Path>, io::Error>> = LazyLock::new(|| {
     const DEFAULT_DIR: &str = "/usr/share/ffplayout/db/";
     const DEFAULT_PATH: &str = "/usr/share/ffplayout/db/ffplayout.db";
@@ -63,17 +74,31 @@ pub static DB_PATH: LazyLock<Result<Cow<'static, Path>, io::Error>> = LazyLock::
 });
 
 pub static GLOBAL_SETTINGS: OnceLock<GlobalSettings> = OnceLock::new();
-pub async fn db_pool() -> Result<Pool<Sqlite>, ProcessError> {
-    let db_path = DB_PATH
+
+/// Connection URL of the database, for SQLite the path to the database file.
+fn db_url() -> Result<String, ProcessError> {
+    #[cfg(not(feature = "mysql"))]
+    let url = DB_PATH
         .as_ref()
-        .map_err(|e| ProcessError::IO(e.to_string()))?;
-    let db_path = db_path.to_string_lossy();
+        .map(|p| p.to_string_lossy().to_string())
+        .map_err(|e| ProcessError::IO(e.to_string()));
+
+    #[cfg(feature = "mysql")]
+    let url = ARGS.db_url.clone().ok_or_else(|| {
+        ProcessError::Custom("Database URL needed! Use `--db-url mysql://...`".to_string())
+    });
+
+    url
+}
+
+pub async fn db_pool() -> Result<DbPool, ProcessError> {
+    let db_url = db_url()?;
 
-    if !Sqlite::database_exists(&db_path).await? {
-        Sqlite::create_database(&db_path).await?;
+    if !Db::database_exists(&db_url).await? {
+        Db::create_database(&db_url).await?;
     }
 
-    let conn = SqlitePool::connect(&db_path).await?;
+    let conn = DbPool::connect(&db_url).await?;
 
     Ok(conn)
 }
@@ -85,15 +110,15 @@ pub async fn db_drop() {
         .unwrap_or(false);
 
     if drop {
-        let db_path = DB_PATH.as_ref().unwrap();
-        match Sqlite::drop_database(&db_path.to_string_lossy()).await {
+        let db_url = db_url().unwrap();
+        match Db::drop_database(&db_url).await {
             Ok(..) => println!("Successfully dropped DB"),
             Err(e) => eprintln!("{e}"),
         };
     };
 }
 
-pub async fn init_globales(conn: &Pool<Sqlite>) -> Result<(), ProcessError> {
+pub async fn init_globales(conn: &DbPool) -> Result<(), ProcessError> {
     let config = GlobalSettings::new(conn).await;
     GLOBAL_SETTINGS
         .set(config)
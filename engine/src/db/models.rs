@@ -1,4 +1,4 @@
-use std::{error::Error, fmt, str::FromStr};
+use std::{collections::HashMap, error::Error, fmt, str::FromStr};
 
 use chrono_tz::Tz;
 use regex::Regex;
@@ -6,9 +6,9 @@ use serde::{
     de::{self, Visitor},
     Deserialize, Serialize,
 };
-use sqlx::{sqlite::SqliteRow, FromRow, Pool, Row, Sqlite};
+use sqlx::{Database, FromRow, Row};
 
-use crate::db::handles;
+use crate::db::{handles, Db, DbPool, DbRow};
 use crate::utils::config::PlayoutConfig;
 
 #[derive(Clone, Default, Debug, Deserialize, Serialize, sqlx::FromRow)]
@@ -28,7 +28,7 @@ pub struct GlobalSettings {
 }
 
 impl GlobalSettings {
-    pub async fn new(conn: &Pool<Sqlite>) -> Self {
+    pub async fn new(conn: &DbPool) -> Self {
         let global_settings = handles::select_global(conn);
 
         match global_settings.await {
@@ -68,10 +68,14 @@ pub struct Channel {
     pub timezone: Option<Tz>,
     #[serde(default)]
     pub advanced_id: Option<i32>,
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default)]
+    pub standby: bool,
 }
 
-impl FromRow<'_, SqliteRow> for Channel {
-    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+impl FromRow<'_, DbRow> for Channel {
+    fn from_row(row: &DbRow) -> sqlx::Result<Self> {
         let mut timezone = None;
 
         if let Some(tz) = row
@@ -100,6 +104,8 @@ impl FromRow<'_, SqliteRow> for Channel {
             time_shift: row.try_get("time_shift").unwrap_or_default(),
             timezone,
             advanced_id: row.try_get("advanced_id").unwrap_or_default(),
+            priority: row.try_get("priority").unwrap_or_default(),
+            standby: row.try_get("standby").unwrap_or_default(),
         })
     }
 }
@@ -119,12 +125,15 @@ pub struct User {
     pub password: String,
     pub role_id: Option<i32>,
     pub channel_ids: Option<Vec<i32>>,
+    /// Permission per channel, channels without entry have the permission `editor`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<HashMap<i32, ChannelPermission>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub token: Option<String>,
 }
 
-impl FromRow<'_, SqliteRow> for User {
-    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+impl FromRow<'_, DbRow> for User {
+    fn from_row(row: &DbRow) -> sqlx::Result<Self> {
         Ok(Self {
             id: row.try_get("id").unwrap_or_default(),
             mail: row.try_get("mail").unwrap_or_default(),
@@ -138,20 +147,123 @@ impl FromRow<'_, SqliteRow> for User {
                     .map(|i| i.parse::<i32>().unwrap_or_default())
                     .collect(),
             ),
+            permissions: None,
             token: None,
         })
     }
 }
 
+/// Login of a user, the refresh token of it is only stored as hash.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, sqlx::FromRow)]
+pub struct Session {
+    pub id: i32,
+    pub user_id: i32,
+    pub ip: String,
+    pub user_agent: String,
+    pub created: i64,
+    /// Time of the last token refresh.
+    pub last_used: i64,
+    pub expires: i64,
+}
+
+/// Login, failed login or lockout, for the audit trail of the authentication.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, sqlx::FromRow)]
+pub struct AuthAudit {
+    #[sqlx(default)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    pub username: String,
+    pub ip: String,
+    pub event: String,
+    pub timestamp: i64,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct UserMeta {
     pub id: i32,
     pub channels: Vec<i32>,
+    pub permissions: HashMap<i32, ChannelPermission>,
 }
 
 impl UserMeta {
-    pub fn new(id: i32, channels: Vec<i32>) -> Self {
-        Self { id, channels }
+    pub fn new(id: i32, channels: Vec<i32>, permissions: HashMap<i32, ChannelPermission>) -> Self {
+        Self {
+            id,
+            channels,
+            permissions,
+        }
+    }
+
+    /// Permission on the channel, `None` without access.
+    pub fn permission(&self, channel: i32) -> Option<ChannelPermission> {
+        self.channels
+            .contains(&channel)
+            .then(|| self.permissions.get(&channel).cloned().unwrap_or_default())
+    }
+
+    pub fn playlists(&self, channel: i32) -> bool {
+        self.permission(channel).is_some_and(|p| p.playlists())
+    }
+
+    pub fn files(&self, channel: i32) -> bool {
+        self.permission(channel).is_some_and(|p| p.files())
+    }
+
+    pub fn control(&self, channel: i32) -> bool {
+        self.permission(channel).is_some_and(|p| p.control())
+    }
+}
+
+/// Permission of a user on one channel, it restricts users with the role `user`.
+/// Admins have always full access to their channels.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelPermission {
+    /// Can only read.
+    #[default]
+    Viewer,
+    PlaylistEditor,
+    FileManager,
+    /// Can edit playlists and files, and control the playout.
+    Editor,
+}
+
+impl ChannelPermission {
+    pub fn playlists(&self) -> bool {
+        matches!(self, Self::PlaylistEditor | Self::Editor)
+    }
+
+    pub fn files(&self) -> bool {
+        matches!(self, Self::FileManager | Self::Editor)
+    }
+
+    pub fn control(&self) -> bool {
+        *self == Self::Editor
+    }
+}
+
+impl FromStr for ChannelPermission {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "viewer" => Ok(Self::Viewer),
+            "playlist_editor" => Ok(Self::PlaylistEditor),
+            "file_manager" => Ok(Self::FileManager),
+            "editor" => Ok(Self::Editor),
+            _ => Err(format!("Unknown channel permission: {input}")),
+        }
+    }
+}
+
+impl fmt::Display for ChannelPermission {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::Viewer => write!(f, "viewer"),
+            Self::PlaylistEditor => write!(f, "playlist_editor"),
+            Self::FileManager => write!(f, "file_manager"),
+            Self::Editor => write!(f, "editor"),
+        }
     }
 }
 
@@ -195,21 +307,21 @@ impl fmt::Display for Role {
     }
 }
 
-impl<'r> sqlx::decode::Decode<'r, ::sqlx::Sqlite> for Role
+impl<'r> sqlx::decode::Decode<'r, Db> for Role
 where
-    &'r str: sqlx::decode::Decode<'r, sqlx::Sqlite>,
+    &'r str: sqlx::decode::Decode<'r, Db>,
 {
     fn decode(
-        value: sqlx::sqlite::SqliteValueRef<'r>,
+        value: <Db as Database>::ValueRef<'r>,
     ) -> Result<Self, Box<dyn Error + 'static + Send + Sync>> {
-        let value = <&str as sqlx::decode::Decode<sqlx::Sqlite>>::decode(value)?;
+        let value = <&str as sqlx::decode::Decode<Db>>::decode(value)?;
 
         Ok(value.parse()?)
     }
 }
 
-impl FromRow<'_, SqliteRow> for Role {
-    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+impl FromRow<'_, DbRow> for Role {
+    fn from_row(row: &DbRow) -> sqlx::Result<Self> {
         match row.get("name") {
             "global_admin" => Ok(Self::GlobalAdmin),
             "channel_admin" => Ok(Self::ChannelAdmin),
@@ -219,6 +331,241 @@ impl FromRow<'_, SqliteRow> for Role {
     }
 }
 
+/// What an API token is allowed to do, reading is always allowed.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenScope {
+    #[default]
+    ReadOnly,
+    PlaylistEdit,
+    FileManage,
+}
+
+impl FromStr for TokenScope {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "read_only" => Ok(Self::ReadOnly),
+            "playlist_edit" => Ok(Self::PlaylistEdit),
+            "file_manage" => Ok(Self::FileManage),
+            _ => Err(format!("Unknown token scope: {input}")),
+        }
+    }
+}
+
+impl fmt::Display for TokenScope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::ReadOnly => write!(f, "read_only"),
+            Self::PlaylistEdit => write!(f, "playlist_edit"),
+            Self::FileManage => write!(f, "file_manage"),
+        }
+    }
+}
+
+/// Long lived token for scripts and other systems, which works without a user login.
+/// Only the hash of the token is stored, the token itself is in the response of the creation.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ApiToken {
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    /// User who created the token, requests with the token act in the name of this user.
+    #[serde(skip_deserializing)]
+    pub user_id: i32,
+    pub name: String,
+    /// Beginning of the token, to recognize it in the list.
+    #[serde(skip_deserializing)]
+    pub prefix: String,
+    #[serde(default)]
+    pub scope: TokenScope,
+    #[serde(default)]
+    pub channel_ids: Vec<i32>,
+    #[serde(skip_deserializing)]
+    pub created: i64,
+    #[serde(default)]
+    pub expires: Option<i64>,
+    #[serde(skip_deserializing)]
+    pub last_used: Option<i64>,
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+impl FromRow<'_, DbRow> for ApiToken {
+    fn from_row(row: &DbRow) -> sqlx::Result<Self> {
+        Ok(Self {
+            id: row.try_get("id").unwrap_or_default(),
+            user_id: row.try_get("user_id").unwrap_or_default(),
+            name: row.try_get("name").unwrap_or_default(),
+            prefix: row.try_get("prefix").unwrap_or_default(),
+            scope: row
+                .try_get::<String, &str>("scope")
+                .unwrap_or_default()
+                .parse()
+                .unwrap_or_default(),
+            channel_ids: row
+                .try_get::<String, &str>("channel_ids")
+                .unwrap_or_default()
+                .split(',')
+                .filter_map(|i| i.trim().parse::<i32>().ok())
+                .collect(),
+            created: row.try_get("created").unwrap_or_default(),
+            expires: row.try_get("expires").unwrap_or_default(),
+            last_used: row.try_get("last_used").unwrap_or_default(),
+            token: None,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, sqlx::FromRow)]
+pub struct MediaHash {
+    #[sqlx(default)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    pub channel_id: i32,
+    pub path: String,
+    pub hash: String,
+    pub size: i64,
+    pub modified: i64,
+    #[sqlx(default)]
+    pub duration: f64,
+    #[sqlx(default)]
+    pub error: Option<String>,
+    #[sqlx(default)]
+    pub checked: i64,
+    /// Loudness measurement of the first loudnorm pass.
+    #[sqlx(default)]
+    pub loudness_i: Option<f64>,
+    #[sqlx(default)]
+    pub loudness_tp: Option<f64>,
+    #[sqlx(default)]
+    pub loudness_lra: Option<f64>,
+    #[sqlx(default)]
+    pub loudness_thresh: Option<f64>,
+    /// Days on which the file can be scheduled, as `YYYY-MM-DD`.
+    #[sqlx(default)]
+    pub valid_from: Option<String>,
+    #[sqlx(default)]
+    pub valid_to: Option<String>,
+}
+
+/// Copy state of one file, while the channel storage is migrated to another backend.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, sqlx::FromRow)]
+pub struct StorageMigration {
+    #[sqlx(default)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    pub channel_id: i32,
+    #[serde(skip_serializing)]
+    pub target: String,
+    pub path: String,
+    pub size: i64,
+    pub done: bool,
+    pub error: Option<String>,
+}
+
+/// Upload restrictions of a role, zero and empty values mean no restriction.
+/// Lists are separated by semicolon.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, sqlx::FromRow)]
+pub struct UploadLimit {
+    #[sqlx(rename = "name")]
+    #[serde(skip_deserializing)]
+    pub role: String,
+    /// Max file size in MB.
+    #[sqlx(rename = "upload_max_size")]
+    #[serde(default)]
+    pub max_size: i64,
+    /// Extensions which are allowed in addition to the storage extensions.
+    #[sqlx(rename = "upload_extensions")]
+    #[serde(default)]
+    pub extensions: String,
+    /// Allowed target folders, `{username}` is replaced with the name of the user.
+    #[sqlx(rename = "upload_folders")]
+    #[serde(default)]
+    pub folders: String,
+}
+
+/// Storage operation of a user, for the audit trail.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, sqlx::FromRow)]
+pub struct StorageAudit {
+    #[sqlx(default)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    pub channel_id: i32,
+    pub user_id: i32,
+    pub username: String,
+    pub action: String,
+    pub path: String,
+    pub target: Option<String>,
+    pub timestamp: i64,
+    pub error: Option<String>,
+}
+
+/// Playlist of a date in the database, the items are in `playlist_items`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, sqlx::FromRow)]
+pub struct StoredPlaylist {
+    pub id: i32,
+    pub channel_id: i32,
+    pub date: String,
+    pub channel: String,
+    pub revision: i32,
+    pub modified: i64,
+}
+
+/// Item of a stored playlist, `data` holds the complete item as JSON.
+#[derive(Clone, Debug, Default, PartialEq, sqlx::FromRow)]
+pub struct PlaylistItem {
+    pub position: i32,
+    pub source: String,
+    pub title: Option<String>,
+    pub seek: f64,
+    pub out: f64,
+    pub duration: f64,
+    pub data: String,
+}
+
+/// Added, changed or removed playlist item, with the item before and after the change.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, sqlx::FromRow)]
+pub struct PlaylistChange {
+    #[sqlx(default)]
+    pub id: i32,
+    pub channel_id: i32,
+    pub date: String,
+    pub revision: i32,
+    pub position: i32,
+    pub action: String,
+    pub user_id: i32,
+    #[serde(serialize_with = "json_text")]
+    pub previous: Option<String>,
+    #[serde(serialize_with = "json_text")]
+    pub item: Option<String>,
+    pub timestamp: i64,
+}
+
+/// Playlist item with the searched source or title.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, sqlx::FromRow)]
+pub struct ScheduledItem {
+    pub date: String,
+    pub position: i32,
+    pub source: String,
+    pub title: Option<String>,
+    /// Seconds from the playlist start to the begin of the item.
+    pub start: f64,
+    pub seek: f64,
+    pub out: f64,
+}
+
+/// Stored JSON text as JSON value.
+fn json_text<S>(value: &Option<String>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    value
+        .as_deref()
+        .and_then(|v| serde_json::from_str::<serde_json::Value>(v).ok())
+        .serialize(serializer)
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, sqlx::FromRow)]
 pub struct TextPreset {
     #[sqlx(default)]
@@ -240,6 +587,224 @@ pub struct TextPreset {
     pub boxborderw: String,
     #[serde(deserialize_with = "deserialize_number_or_string")]
     pub alpha: String,
+    /// Daily times for firing the preset, like `07:59:50;19:59:50`.
+    #[serde(default)]
+    pub schedule: String,
+    /// Seconds until a scheduled preset gets cleared, `0` keeps the text.
+    #[serde(default)]
+    pub duration: f64,
+}
+
+/// Insert items from `path` into folder playout and generated playlists,
+/// every `interval` minutes at `minute` inside the interval.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq, sqlx::FromRow)]
+pub struct InsertRule {
+    #[sqlx(default)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    #[serde(default)]
+    pub channel_id: i32,
+    pub name: String,
+    pub interval: i64,
+    #[serde(default)]
+    pub minute: i64,
+    pub path: String,
+    #[serde(default = "default_count")]
+    pub count: i64,
+    #[serde(default)]
+    pub shuffle: bool,
+    #[serde(default = "default_active")]
+    pub active: bool,
+}
+
+/// Clips which are played before (`intro`) and after (`outro`) every playlist item of `category`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq, sqlx::FromRow)]
+pub struct BumperRule {
+    #[sqlx(default)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    #[serde(default)]
+    pub channel_id: i32,
+    pub category: String,
+    #[serde(default)]
+    pub intro: String,
+    #[serde(default)]
+    pub outro: String,
+    #[serde(default = "default_active")]
+    pub active: bool,
+}
+
+/// Additional output of a channel, with its own encoder and output parameters.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq, sqlx::FromRow)]
+pub struct ExtraOutput {
+    #[sqlx(default)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    #[serde(default)]
+    pub channel_id: i32,
+    pub name: String,
+    pub param: String,
+    #[serde(default = "default_active")]
+    pub active: bool,
+}
+
+/// Folder or file for filling gaps. Sources are ordered by `priority`, clips are picked by `weight`
+/// and not repeated within `no_repeat` minutes.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq, sqlx::FromRow)]
+pub struct FillerSource {
+    #[sqlx(default)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    #[serde(default)]
+    pub channel_id: i32,
+    pub path: String,
+    #[serde(default)]
+    pub priority: i64,
+    #[serde(default = "default_count")]
+    pub weight: i64,
+    #[serde(default)]
+    pub no_repeat: i64,
+}
+
+/// Named video and audio filter chains, which the channel and the playlist items use by the name.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq, sqlx::FromRow)]
+pub struct FilterTemplate {
+    #[sqlx(default)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    #[serde(default)]
+    pub channel_id: i32,
+    pub name: String,
+    #[serde(default)]
+    pub video_filter: String,
+    #[serde(default)]
+    pub audio_filter: String,
+}
+
+/// Receiver of the channel events, `events` is a comma separated list, empty for all.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq, sqlx::FromRow)]
+pub struct Webhook {
+    #[sqlx(default)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    #[serde(default)]
+    pub channel_id: i32,
+    pub url: String,
+    /// Key for the HMAC signature of the body.
+    #[serde(default)]
+    pub secret: String,
+    #[serde(default)]
+    pub events: String,
+    #[serde(default = "default_active")]
+    pub active: bool,
+}
+
+/// Chat target for the log messages of the channel, next to the mail recipient.
+///
+/// `kind` is `telegram`, `slack`, `discord` or `matrix`. Slack and Discord only need the webhook `url`,
+/// Telegram the bot `token` and the chat id as `target`, Matrix the homeserver `url`,
+/// the access `token` and the room id as `target`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq, sqlx::FromRow)]
+pub struct Notifier {
+    #[sqlx(default)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    #[serde(default)]
+    pub channel_id: i32,
+    pub kind: String,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub token: String,
+    #[serde(default)]
+    pub target: String,
+    #[serde(default = "default_active")]
+    pub active: bool,
+}
+
+/// Condition of the channel, which sends an alert to the notification targets.
+///
+/// `condition` is `filler_ratio` (percent of filler time in the last `minutes` over `threshold`),
+/// `playlist_missing` (no playlist for the next day at `time`) or `encoder_restarts`
+/// (at least `threshold` encoder restarts in the last `minutes`). `targets` is a comma separated
+/// list of `mail` and notifier kinds, empty for all.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, sqlx::FromRow)]
+pub struct AlertRule {
+    #[sqlx(default)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    #[serde(default)]
+    pub channel_id: i32,
+    pub name: String,
+    pub condition: String,
+    #[serde(default)]
+    pub threshold: f64,
+    #[serde(default = "default_minutes")]
+    pub minutes: i64,
+    #[serde(default)]
+    pub time: String,
+    #[serde(default = "default_severity")]
+    pub severity: String,
+    #[serde(default)]
+    pub targets: String,
+    #[serde(default = "default_active")]
+    pub active: bool,
+}
+
+fn default_count() -> i64 {
+    1
+}
+
+fn default_active() -> bool {
+    true
+}
+
+fn default_log_files() -> i64 {
+    14
+}
+
+fn default_protect_days() -> i64 {
+    2
+}
+
+fn default_minutes() -> i64 {
+    60
+}
+
+fn default_severity() -> String {
+    "warning".to_string()
+}
+
+/// Remote source, which replaces the playout between `start_time` and `end_time` (unix timestamps).
+#[derive(Clone, Debug, Default, Deserialize, Serialize, sqlx::FromRow)]
+pub struct LiveEvent {
+    #[sqlx(default)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    #[serde(default)]
+    pub channel_id: i32,
+    pub name: String,
+    pub source: String,
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
+/// Key for the ingest server, the stream name of RTMP or the stream ID of SRT.
+/// `last_used` is the unix timestamp of the last stream with this key.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, sqlx::FromRow)]
+pub struct IngestKey {
+    #[sqlx(default)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    #[serde(default)]
+    pub channel_id: i32,
+    pub name: String,
+    #[serde(default)]
+    pub stream_key: String,
+    #[serde(default = "default_active")]
+    pub active: bool,
+    #[serde(skip_deserializing)]
+    pub last_used: Option<i64>,
 }
 
 /// Deserialize number or string
@@ -294,6 +859,26 @@ pub struct Configuration {
     pub logging_detect_silence: bool,
     #[serde(default)]
     pub logging_ignore: String,
+    #[serde(default)]
+    pub logging_decoder_level: String,
+    #[serde(default)]
+    pub logging_encoder_level: String,
+    #[serde(default)]
+    pub logging_decoder_rate: u32,
+    #[serde(default)]
+    pub logging_encoder_rate: u32,
+    #[serde(default)]
+    pub logging_ingest_rate: u32,
+    #[serde(default)]
+    pub logging_crash_dump: bool,
+    #[serde(default)]
+    pub logging_max_size: i64,
+    #[serde(default = "default_log_files")]
+    pub logging_max_files: i64,
+    #[serde(default)]
+    pub logging_compress: bool,
+    #[serde(default)]
+    pub logging_shared: bool,
 
     pub processing_mode: String,
     pub processing_audio_only: bool,
@@ -308,6 +893,8 @@ pub struct Configuration {
     pub processing_logo_scale: String,
     pub processing_logo_opacity: f64,
     pub processing_logo_position: String,
+    #[serde(default)]
+    pub processing_logo_rules: String,
     #[serde(default = "default_tracks")]
     pub processing_audio_tracks: i32,
     #[serde(default = "default_track_index")]
@@ -316,6 +903,46 @@ pub struct Configuration {
     pub processing_audio_channels: u8,
     pub processing_volume: f64,
     #[serde(default)]
+    pub processing_loudnorm: bool,
+    #[serde(default)]
+    pub processing_loudnorm_i: f64,
+    #[serde(default)]
+    pub processing_loudnorm_tp: f64,
+    #[serde(default)]
+    pub processing_loudnorm_lra: f64,
+    #[serde(default)]
+    pub processing_subtitles: bool,
+    #[serde(default)]
+    pub processing_subtitle_style: String,
+    #[serde(default)]
+    pub processing_captions: bool,
+    #[serde(default)]
+    pub processing_captions_dummy: String,
+    #[serde(default)]
+    pub processing_audio_languages: String,
+    #[serde(default)]
+    pub processing_failover_timeout: f64,
+    #[serde(default)]
+    pub processing_hwaccel: String,
+    #[serde(default)]
+    pub processing_aspect_mode: String,
+    #[serde(default)]
+    pub processing_audio_layout: String,
+    #[serde(default)]
+    pub processing_filter_template: String,
+    #[serde(default)]
+    pub processing_black_alert: f64,
+    #[serde(default)]
+    pub processing_silence_alert: f64,
+    #[serde(default)]
+    pub processing_loudness_meter: bool,
+    #[serde(default)]
+    pub processing_html: bool,
+    #[serde(default)]
+    pub processing_html_url: String,
+    #[serde(default)]
+    pub processing_html_browser: String,
+    #[serde(default)]
     pub processing_filter: String,
     #[serde(default)]
     pub processing_override_filter: bool,
@@ -323,11 +950,55 @@ pub struct Configuration {
     pub processing_vtt_enable: bool,
     #[serde(default)]
     pub processing_vtt_dummy: Option<String>,
+    #[serde(default)]
+    pub processing_transition: String,
+    #[serde(default)]
+    pub processing_transition_duration: i64,
 
     pub ingest_enable: bool,
     pub ingest_param: String,
     #[serde(default)]
     pub ingest_filter: String,
+    #[serde(default)]
+    pub ingest_srt_enable: bool,
+    #[serde(default)]
+    pub ingest_srt_address: String,
+    #[serde(default)]
+    pub ingest_srt_passphrase: String,
+    #[serde(default)]
+    pub ingest_srt_streamid: String,
+    #[serde(default)]
+    pub ingest_whip_enable: bool,
+    #[serde(default)]
+    pub ingest_whip_url: String,
+    #[serde(default)]
+    pub ingest_whip_source: String,
+    #[serde(default)]
+    pub ingest_windows: String,
+    #[serde(default)]
+    pub ingest_countdown: i64,
+    #[serde(default)]
+    pub ingest_transition: String,
+    #[serde(default)]
+    pub ingest_transition_duration: i64,
+    #[serde(default)]
+    pub ingest_record: bool,
+    #[serde(default)]
+    pub ingest_record_path: String,
+    #[serde(default)]
+    pub ingest_delay: i64,
+    #[serde(default)]
+    pub ingest_monitor: bool,
+    #[serde(default)]
+    pub ingest_voice: bool,
+    #[serde(default)]
+    pub ingest_voice_param: String,
+    #[serde(default)]
+    pub ingest_voice_duck: f64,
+    #[serde(default)]
+    pub ingest_voice_attack: f64,
+    #[serde(default)]
+    pub ingest_voice_release: f64,
 
     pub playlist_day_start: String,
     pub playlist_length: String,
@@ -336,18 +1007,154 @@ pub struct Configuration {
     pub storage_filler: String,
     pub storage_extensions: String,
     pub storage_shuffle: bool,
+    #[serde(default)]
+    pub storage_quarantine: bool,
+    #[serde(default = "default_protect_days")]
+    pub storage_protect_days: i64,
+    #[serde(default)]
+    pub storage_mirror: String,
+    #[serde(default)]
+    pub storage_exclude: String,
+    #[serde(default)]
+    pub storage_depth: i64,
 
     pub text_add: bool,
     pub text_from_filename: bool,
     pub text_font: String,
     pub text_style: String,
     pub text_regex: String,
+    #[serde(default)]
+    pub text_ticker: bool,
+    #[serde(default)]
+    pub text_ticker_url: String,
+    #[serde(default)]
+    pub text_ticker_template: String,
+    #[serde(default)]
+    pub text_ticker_interval: i64,
+    #[serde(default)]
+    pub text_ticker_speed: i64,
+    #[serde(default)]
+    pub text_ticker_style: String,
+    #[serde(default)]
+    pub text_now_next: bool,
+    #[serde(default)]
+    pub text_now_next_duration: i64,
+    #[serde(default)]
+    pub text_now_next_now: String,
+    #[serde(default)]
+    pub text_now_next_next: String,
+    #[serde(default)]
+    pub text_now_next_style: String,
+    #[serde(default)]
+    pub text_clock: bool,
+    #[serde(default)]
+    pub text_clock_format: String,
+    #[serde(default)]
+    pub text_clock_style: String,
 
     pub task_enable: bool,
     pub task_path: String,
 
     pub output_mode: String,
     pub output_param: String,
+    #[serde(default)]
+    pub output_scte35: bool,
+    #[serde(default)]
+    pub output_preview: bool,
+    #[serde(default)]
+    pub output_preview_whep_url: String,
+    #[serde(default)]
+    pub output_preview_publish: String,
+    #[serde(default)]
+    pub output_snapshot: bool,
+    #[serde(default)]
+    pub output_monitor_url: String,
+    #[serde(default)]
+    pub output_monitor_interval: i64,
+    #[serde(default)]
+    pub output_hls_ladder: String,
+    #[serde(default)]
+    pub output_hls_time: i64,
+    #[serde(default)]
+    pub output_hls_list_size: i64,
+    #[serde(default)]
+    pub output_srt_address: String,
+    #[serde(default)]
+    pub output_srt_mode: String,
+    #[serde(default)]
+    pub output_srt_latency: i64,
+    #[serde(default)]
+    pub output_srt_passphrase: String,
+    #[serde(default)]
+    pub output_srt_streamid: String,
+    #[serde(default)]
+    pub output_multicast_address: String,
+    #[serde(default)]
+    pub output_multicast_rtp: bool,
+    #[serde(default)]
+    pub output_multicast_ttl: i64,
+    #[serde(default)]
+    pub output_multicast_muxrate: i64,
+    #[serde(default)]
+    pub output_multicast_service: String,
+    #[serde(default)]
+    pub output_multicast_pmt_pid: i64,
+    #[serde(default)]
+    pub output_multicast_video_pid: i64,
+    #[serde(default)]
+    pub output_multicast_audio_pid: i64,
+    #[serde(default)]
+    pub output_icecast_url: String,
+    #[serde(default)]
+    pub output_icecast_format: String,
+    #[serde(default)]
+    pub output_icecast_bitrate: i64,
+    #[serde(default)]
+    pub output_icecast_name: String,
+    #[serde(default)]
+    pub output_backup: String,
+    #[serde(default)]
+    pub archive_enable: bool,
+    #[serde(default)]
+    pub archive_path: String,
+    #[serde(default)]
+    pub archive_retention: i64,
+    #[serde(default)]
+    pub archive_param: String,
+    #[serde(default)]
+    pub archive_screenshots: bool,
+    #[serde(default)]
+    pub archive_screenshot_path: String,
+    #[serde(default)]
+    pub archive_screenshot_interval: i64,
+    #[serde(default)]
+    pub archive_screenshot_retention: i64,
+    #[serde(default)]
+    pub playlist_chain: bool,
+    #[serde(default)]
+    pub playlist_epg: bool,
+    #[serde(default)]
+    pub playlist_database: bool,
+    #[serde(default)]
+    pub playlist_now_playing: bool,
+    #[serde(default)]
+    pub playlist_feed_url: String,
+    #[serde(default)]
+    pub playlist_feed_auth: String,
+    #[serde(default)]
+    pub playlist_feed_interval: i64,
+    #[serde(default)]
+    pub storage_slate: String,
+    #[serde(default)]
+    pub storage_no_repeat_items: i64,
+    #[serde(default)]
+    pub storage_no_repeat_time: f64,
+    #[serde(default)]
+    pub storage_weights: String,
+    #[serde(default)]
+    pub storage_emergency: String,
+    #[serde(default)]
+    pub storage_emergency_loop: bool,
 }
 
 impl Configuration {
@@ -364,6 +1171,16 @@ impl Configuration {
             logging_ingest_level: config.logging.ingest_level,
             logging_detect_silence: config.logging.detect_silence,
             logging_ignore: config.logging.ignore_lines.join(";"),
+            logging_decoder_level: config.logging.decoder_level,
+            logging_encoder_level: config.logging.encoder_level,
+            logging_decoder_rate: config.logging.decoder_rate,
+            logging_encoder_rate: config.logging.encoder_rate,
+            logging_ingest_rate: config.logging.ingest_rate,
+            logging_crash_dump: config.logging.crash_dump,
+            logging_max_size: config.logging.max_size,
+            logging_max_files: config.logging.max_files,
+            logging_compress: config.logging.compress,
+            logging_shared: config.logging.shared,
             processing_mode: config.processing.mode.to_string(),
             processing_audio_only: config.processing.audio_only,
             processing_audio_track_index: config.processing.audio_track_index,
@@ -378,31 +1195,142 @@ impl Configuration {
             processing_logo_scale: config.processing.logo_scale,
             processing_logo_opacity: config.processing.logo_opacity,
             processing_logo_position: config.processing.logo_position,
+            processing_logo_rules: config.processing.logo_rules,
             processing_audio_tracks: config.processing.audio_tracks,
             processing_audio_channels: config.processing.audio_channels,
             processing_volume: config.processing.volume,
+            processing_loudnorm: config.processing.loudnorm,
+            processing_loudnorm_i: config.processing.loudnorm_i,
+            processing_loudnorm_tp: config.processing.loudnorm_tp,
+            processing_loudnorm_lra: config.processing.loudnorm_lra,
+            processing_subtitles: config.processing.subtitles,
+            processing_subtitle_style: config.processing.subtitle_style,
+            processing_captions: config.processing.captions,
+            processing_captions_dummy: config.processing.captions_dummy,
+            processing_audio_languages: config.processing.audio_languages.join(";"),
+            processing_failover_timeout: config.processing.failover_timeout,
+            processing_hwaccel: config.processing.hwaccel.to_string(),
+            processing_aspect_mode: config.processing.aspect_mode.to_string(),
+            processing_audio_layout: config.processing.audio_layout,
+            processing_filter_template: config.processing.filter_template,
+            processing_black_alert: config.processing.black_alert,
+            processing_silence_alert: config.processing.silence_alert,
+            processing_loudness_meter: config.processing.loudness_meter,
+            processing_html: config.processing.html,
+            processing_html_url: config.processing.html_url,
+            processing_html_browser: config.processing.html_browser,
             processing_filter: config.processing.custom_filter,
             processing_override_filter: config.processing.override_filter,
             processing_vtt_enable: config.processing.vtt_enable,
             processing_vtt_dummy: config.processing.vtt_dummy,
+            processing_transition: config.processing.transition.to_string(),
+            processing_transition_duration: config.processing.transition_duration,
             ingest_enable: config.ingest.enable,
             ingest_param: config.ingest.input_param,
             ingest_filter: config.ingest.custom_filter,
+            ingest_srt_enable: config.ingest.srt_enable,
+            ingest_srt_address: config.ingest.srt_address,
+            ingest_srt_passphrase: config.ingest.srt_passphrase,
+            ingest_srt_streamid: config.ingest.srt_streamid,
+            ingest_whip_enable: config.ingest.whip_enable,
+            ingest_whip_url: config.ingest.whip_url,
+            ingest_whip_source: config.ingest.whip_source,
+            ingest_windows: config.ingest.windows,
+            ingest_countdown: config.ingest.countdown,
+            ingest_transition: config.ingest.transition.to_string(),
+            ingest_transition_duration: config.ingest.transition_duration,
+            ingest_record: config.ingest.record,
+            ingest_record_path: config.ingest.record_path,
+            ingest_delay: config.ingest.delay,
+            ingest_monitor: config.ingest.monitor,
+            ingest_voice: config.ingest.voice,
+            ingest_voice_param: config.ingest.voice_param,
+            ingest_voice_duck: config.ingest.voice_duck,
+            ingest_voice_attack: config.ingest.voice_attack,
+            ingest_voice_release: config.ingest.voice_release,
             playlist_day_start: config.playlist.day_start,
             playlist_length: config.playlist.length,
             playlist_infinit: config.playlist.infinit,
+            storage_weights: config.storage.weights_string(),
             storage_filler: config.storage.filler,
             storage_extensions: config.storage.extensions.join(";"),
             storage_shuffle: config.storage.shuffle,
+            storage_quarantine: config.storage.quarantine,
+            storage_protect_days: config.storage.protect_days,
+            storage_mirror: config.storage.mirror,
+            storage_exclude: config.storage.exclude.join(";"),
+            storage_depth: config.storage.depth,
             text_add: config.text.add_text,
             text_font: config.text.font,
             text_from_filename: config.text.text_from_filename,
             text_style: config.text.style,
             text_regex: config.text.regex,
+            text_ticker: config.text.ticker,
+            text_ticker_url: config.text.ticker_url,
+            text_ticker_template: config.text.ticker_template,
+            text_ticker_interval: config.text.ticker_interval,
+            text_ticker_speed: config.text.ticker_speed,
+            text_ticker_style: config.text.ticker_style,
+            text_now_next: config.text.now_next,
+            text_now_next_duration: config.text.now_next_duration,
+            text_now_next_now: config.text.now_next_now,
+            text_now_next_next: config.text.now_next_next,
+            text_now_next_style: config.text.now_next_style,
+            text_clock: config.text.clock,
+            text_clock_format: config.text.clock_format,
+            text_clock_style: config.text.clock_style,
             task_enable: config.task.enable,
             task_path: config.task.path.to_string_lossy().to_string(),
             output_mode: config.output.mode.to_string(),
             output_param: config.output.output_param,
+            output_scte35: config.output.scte35,
+            output_preview: config.output.preview,
+            output_preview_whep_url: config.output.preview_whep_url,
+            output_preview_publish: config.output.preview_publish,
+            output_snapshot: config.output.snapshot,
+            output_monitor_url: config.output.monitor_url,
+            output_monitor_interval: config.output.monitor_interval,
+            output_hls_ladder: config.output.hls_ladder.join(";"),
+            output_hls_time: config.output.hls_time,
+            output_hls_list_size: config.output.hls_list_size,
+            output_srt_address: config.output.srt_address,
+            output_srt_mode: config.output.srt_mode.to_string(),
+            output_srt_latency: config.output.srt_latency,
+            output_srt_passphrase: config.output.srt_passphrase,
+            output_srt_streamid: config.output.srt_streamid,
+            output_multicast_address: config.output.multicast_address,
+            output_multicast_rtp: config.output.multicast_rtp,
+            output_multicast_ttl: config.output.multicast_ttl,
+            output_multicast_muxrate: config.output.multicast_muxrate,
+            output_multicast_service: config.output.multicast_service,
+            output_multicast_pmt_pid: config.output.multicast_pmt_pid,
+            output_multicast_video_pid: config.output.multicast_video_pid,
+            output_multicast_audio_pid: config.output.multicast_audio_pid,
+            output_icecast_url: config.output.icecast_url,
+            output_icecast_format: config.output.icecast_format.to_string(),
+            output_icecast_bitrate: config.output.icecast_bitrate,
+            output_icecast_name: config.output.icecast_name,
+            output_backup: config.output.backup.join("\n"),
+            archive_enable: config.archive.enable,
+            archive_path: config.archive.path,
+            archive_retention: config.archive.retention,
+            archive_param: config.archive.param,
+            archive_screenshots: config.archive.screenshots,
+            archive_screenshot_path: config.archive.screenshot_path,
+            archive_screenshot_interval: config.archive.screenshot_interval,
+            archive_screenshot_retention: config.archive.screenshot_retention,
+            playlist_chain: config.playlist.chain,
+            playlist_epg: config.playlist.epg,
+            playlist_database: config.playlist.database,
+            playlist_now_playing: config.playlist.now_playing,
+            playlist_feed_url: config.playlist.feed_url,
+            playlist_feed_auth: config.playlist.feed_auth,
+            playlist_feed_interval: config.playlist.feed_interval,
+            storage_slate: config.storage.slate,
+            storage_emergency: config.storage.emergency,
+            storage_emergency_loop: config.storage.emergency_loop,
+            storage_no_repeat_items: config.storage.no_repeat_items,
+            storage_no_repeat_time: config.storage.no_repeat_time,
         }
     }
 }
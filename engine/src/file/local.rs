@@ -11,7 +11,7 @@ use actix_web::{
     http::header::{ContentDisposition, DispositionType},
     web, HttpRequest, HttpResponse,
 };
-use async_walkdir::WalkDir;
+use async_walkdir::{Filtering, WalkDir};
 
 // use futures_util::TryStreamExt as _;
 use tokio_stream::StreamExt;
@@ -23,7 +23,7 @@ use tokio::{fs, io::AsyncWriteExt, sync::Mutex, task::JoinHandle};
 
 use crate::file::{
     norm_abs_path, utils::media_map::SharedMediaMap, utils::watcher::watch, MoveObject, PathObject,
-    Storage, VideoFile,
+    Storage, VideoFile, WalkOptions,
 };
 use crate::player::utils::{file_extension, include_file_extension, probe::MediaProbe, Media};
 use crate::utils::{config::PlayoutConfig, errors::ServiceError, logging::Target};
@@ -317,7 +317,9 @@ impl Storage for LocalStorage {
         mut data: Multipart,
         path: &Path,
         is_abs: bool,
-    ) -> Result<(), ServiceError> {
+    ) -> Result<Vec<String>, ServiceError> {
+        let mut uploaded = vec![];
+
         while let Some(mut field) = data.try_next().await? {
             let content_disposition = field.content_disposition().ok_or("No content")?;
             debug!("{content_disposition}");
@@ -330,12 +332,15 @@ impl Storage for LocalStorage {
                 .get_filename()
                 .map_or_else(|| rand_string.to_string(), sanitize_filename::sanitize);
 
-            let filepath = if is_abs {
-                path.to_path_buf()
+            let (filepath, source) = if is_abs {
+                (path.to_path_buf(), path.to_string_lossy().to_string())
             } else {
                 let (target_path, _, _) = norm_abs_path(&self.root, &path.to_string_lossy())?;
 
-                target_path.join(filename)
+                (
+                    target_path.join(&filename),
+                    path.join(&filename).to_string_lossy().to_string(),
+                )
             };
 
             // INFO: File exist check should be enough because file size and content length are different.
@@ -366,8 +371,11 @@ impl Storage for LocalStorage {
                     }
                 }
             }
+
+            uploaded.push(source);
         }
-        Ok(())
+
+        Ok(uploaded)
     }
 
     async fn watchman(
@@ -408,11 +416,11 @@ impl Storage for LocalStorage {
     async fn fill_filler_list(
         &mut self,
         config: &PlayoutConfig,
-        fillers: Option<Arc<Mutex<Vec<Media>>>>,
+        filler_path: &Path,
+        probe: bool,
     ) -> Vec<Media> {
         let id = config.general.channel_id;
         let mut filler_list = vec![];
-        let filler_path = &config.storage.filler_path;
 
         if filler_path.is_dir() {
             let config_clone = config.clone();
@@ -423,7 +431,7 @@ impl Storage for LocalStorage {
                 if entry.path().is_file() && include_file_extension(config, &entry.path()) {
                     let mut media = Media::new(index, &entry.path().to_string_lossy(), false).await;
 
-                    if fillers.is_none() {
+                    if probe {
                         if let Err(e) = media.add_probe(false).await {
                             error!(target: Target::file_mail(), channel = id; "{e:?}");
                         };
@@ -445,25 +453,16 @@ impl Storage for LocalStorage {
             for (index, item) in filler_list.iter_mut().enumerate() {
                 item.index = Some(index);
             }
-
-            if let Some(f) = fillers.as_ref() {
-                f.lock().await.clone_from(&filler_list);
-            }
         } else if filler_path.is_file() {
-            let mut media =
-                Media::new(0, &config.storage.filler_path.to_string_lossy(), false).await;
+            let mut media = Media::new(0, &filler_path.to_string_lossy(), false).await;
 
-            if fillers.is_none() {
+            if probe {
                 if let Err(e) = media.add_probe(false).await {
                     error!(target: Target::file_mail(), channel = id; "{e:?}");
                 };
             }
 
             filler_list.push(media);
-
-            if let Some(f) = fillers.as_ref() {
-                f.lock().await.clone_from(&filler_list);
-            }
         }
 
         filler_list
@@ -543,9 +542,32 @@ impl Storage for LocalStorage {
     /// # Returns
     /// - `Ok(Vec<PathBuf>)`: Paths of all files and directories.
     /// - `Err(ServiceError)`: On traversal error.
-    async fn walk_dir<P: AsRef<Path>>(&self, input: P) -> Result<Vec<PathBuf>, ServiceError> {
+    async fn walk_dir<P: AsRef<Path>>(
+        &self,
+        input: P,
+        options: &WalkOptions,
+    ) -> Result<Vec<PathBuf>, ServiceError> {
         let mut contents = vec![];
-        let mut entries = WalkDir::new(input);
+        let root = input.as_ref().to_path_buf();
+        let options = options.clone();
+        let mut entries = WalkDir::new(&root).filter(move |entry| {
+            let root = root.clone();
+            let options = options.clone();
+
+            async move {
+                let path = entry.path();
+                let is_dir = entry.file_type().await.is_ok_and(|t| t.is_dir());
+
+                match (
+                    options.includes(path.strip_prefix(&root).unwrap_or(&path), is_dir),
+                    is_dir,
+                ) {
+                    (true, _) => Filtering::Continue,
+                    (false, true) => Filtering::IgnoreDir,
+                    (false, false) => Filtering::Ignore,
+                }
+            }
+        });
 
         while let Some(Ok(entry)) = entries.next().await {
             contents.push(entry.path());
@@ -553,6 +575,37 @@ impl Storage for LocalStorage {
 
         Ok(contents)
     }
+
+    async fn export_file(&self, file_path: &str, target: &Path) -> Result<(), ServiceError> {
+        let (source, _, _) = norm_abs_path(&self.root, file_path)?;
+        fs::copy(source, target).await?;
+
+        Ok(())
+    }
+
+    async fn import_file(&self, source: &Path, file_path: &str) -> Result<(), ServiceError> {
+        let (target, _, _) = norm_abs_path(&self.root, file_path)?;
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        fs::copy(source, target).await?;
+
+        Ok(())
+    }
+
+    async fn delete_file(&self, file_path: &str) -> Result<(), ServiceError> {
+        let (target, _, _) = norm_abs_path(&self.root, file_path)?;
+        fs::remove_file(&target).await?;
+
+        // remove the folder too, when it was the last file
+        if let Some(parent) = target.parent().filter(|p| *p != self.root) {
+            let _ = fs::remove_dir(parent).await;
+        }
+
+        Ok(())
+    }
 }
 
 async fn rename_only(source: &PathBuf, target: &PathBuf) -> Result<MoveObject, ServiceError> {
@@ -568,6 +621,7 @@ async fn rename_only(source: &PathBuf, target: &PathBuf) -> Result<MoveObject, S
                 .unwrap_or_default()
                 .to_string_lossy()
                 .to_string(),
+            force: false,
         }),
         Err(e) => {
             error!("{e}");
@@ -597,6 +651,7 @@ async fn copy_and_delete(source: &PathBuf, target: &PathBuf) -> Result<MoveObjec
                     .unwrap_or_default()
                     .to_string_lossy()
                     .to_string(),
+                force: false,
             })
         }
         Err(e) => {
@@ -5,6 +5,8 @@ use std::{
 
 use actix_multipart::Multipart;
 use actix_web::{web, HttpRequest, HttpResponse};
+use glob::Pattern;
+use log::*;
 use relative_path::RelativePath;
 use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
@@ -14,9 +16,12 @@ mod local;
 mod s3;
 pub mod utils;
 
-use crate::file::{utils::filler, utils::media_map::SharedMediaMap};
+use crate::file::{
+    utils::filler::{self, FillerPool},
+    utils::media_map::SharedMediaMap,
+};
 use crate::player::utils::Media;
-use crate::utils::{config::PlayoutConfig, errors::ServiceError};
+use crate::utils::{config::PlayoutConfig, errors::ServiceError, logging::Target};
 
 use s3::S3_INDICATOR;
 
@@ -31,6 +36,8 @@ pub struct PathObject {
     pub folders_only: bool,
     #[serde(default)]
     pub recursive: bool,
+    #[serde(default)]
+    pub force: bool,
 }
 
 impl PathObject {
@@ -43,14 +50,17 @@ impl PathObject {
             files: Some(vec![]),
             folders_only: false,
             recursive: false,
+            force: false,
         }
     }
 }
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct MoveObject {
-    source: String,
-    target: String,
+    pub source: String,
+    pub target: String,
+    #[serde(default, skip_serializing)]
+    pub force: bool,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -59,6 +69,70 @@ pub struct VideoFile {
     duration: f64,
 }
 
+/// Filter for `Storage::walk_dir`, the default lists everything.
+#[derive(Clone, Debug, Default)]
+pub struct WalkOptions {
+    /// Max folder depth below the input path, 1 lists only direct children.
+    pub depth: Option<usize>,
+    /// File extensions without dot, empty for all files.
+    pub extensions: Vec<String>,
+    /// Glob patterns, matched against the relative path and the name of an entry.
+    /// Matching folders are not traversed.
+    pub exclude: Vec<Pattern>,
+}
+
+impl WalkOptions {
+    /// Options for folder mode and playlist generation, from the channel storage settings.
+    pub fn from_config(config: &PlayoutConfig) -> Self {
+        let exclude = config
+            .storage
+            .exclude
+            .iter()
+            .filter(|p| !p.trim().is_empty())
+            .filter_map(|p| match Pattern::new(p.trim()) {
+                Ok(pattern) => Some(pattern),
+                Err(e) => {
+                    error!(target: Target::file_mail(), channel = config.general.channel_id; "Invalid exclude pattern <b><magenta>{p}</></b>: {e}");
+                    None
+                }
+            })
+            .collect();
+
+        Self {
+            depth: (config.storage.depth > 0).then_some(config.storage.depth as usize),
+            extensions: vec![],
+            exclude,
+        }
+    }
+
+    /// Check an entry by its path relative to the walked folder.
+    pub fn includes(&self, relative: &Path, is_dir: bool) -> bool {
+        if self
+            .depth
+            .is_some_and(|depth| relative.components().count() > depth)
+        {
+            return false;
+        }
+
+        let name = relative.file_name().unwrap_or_default();
+
+        if self
+            .exclude
+            .iter()
+            .any(|p| p.matches_path(relative) || p.matches_path(Path::new(name)))
+        {
+            return false;
+        }
+
+        is_dir
+            || self.extensions.is_empty()
+            || relative
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| self.extensions.iter().any(|x| x.eq_ignore_ascii_case(e)))
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum StorageType {
     Local,
@@ -86,6 +160,14 @@ impl StorageBackend {
         }
     }
 
+    /// Storage extensions, including the extra extensions of the channel.
+    pub fn extensions(&self) -> &[String] {
+        match self {
+            StorageBackend::Local(storage) => &storage.extensions,
+            StorageBackend::S3(storage) => &storage.extensions,
+        }
+    }
+
     pub fn echo_log(&self) {
         match self {
             StorageBackend::Local(storage) => storage.echo_log(),
@@ -148,7 +230,7 @@ impl StorageBackend {
         payload: Multipart,
         path: &Path,
         is_abs: bool,
-    ) -> Result<(), ServiceError> {
+    ) -> Result<Vec<String>, ServiceError> {
         match self {
             StorageBackend::Local(storage) => storage.upload(payload, path, is_abs).await,
             StorageBackend::S3(storage) => storage.upload(payload, path, is_abs).await,
@@ -174,20 +256,50 @@ impl StorageBackend {
         }
     }
 
+    /// Collect the clips from all filler sources. Without `fillers` all clips get probed,
+    /// otherwise the pool is shared with the player and probing happens on demand.
     pub async fn fill_filler_list(
         &mut self,
         config: &PlayoutConfig,
-        fillers: Option<Arc<Mutex<Vec<Media>>>>,
-    ) -> Vec<Media> {
-        let filler_path = &config.storage.filler;
-        if filler_path.starts_with(ABS_PATH_INDICATOR) {
-            filler::absolute_fill_filler_list(config, fillers).await
-        } else {
-            match self {
-                StorageBackend::Local(storage) => storage.fill_filler_list(config, fillers).await,
-                StorageBackend::S3(storage) => storage.fill_filler_list(config, fillers).await,
-            }
+        fillers: Option<Arc<Mutex<FillerPool>>>,
+    ) -> FillerPool {
+        let probe = fillers.is_none();
+        let mut pool = FillerPool::new();
+
+        for source in filler::filler_sources(config) {
+            let filler_path = match clean_raw_abs_path(
+                &config.channel.storage,
+                &source.path,
+                ABS_PATH_INDICATOR,
+            ) {
+                Ok((_, path)) => path,
+                Err(e) => {
+                    error!(target: Target::file_mail(), channel = config.general.channel_id; "Filler <b><magenta>{}</></b>: {e}", source.path);
+                    continue;
+                }
+            };
+
+            let media = if source.path.starts_with(ABS_PATH_INDICATOR) {
+                filler::absolute_fill_filler_list(config, &filler_path, probe).await
+            } else {
+                match self {
+                    StorageBackend::Local(storage) => {
+                        storage.fill_filler_list(config, &filler_path, probe).await
+                    }
+                    StorageBackend::S3(storage) => {
+                        storage.fill_filler_list(config, &filler_path, probe).await
+                    }
+                }
+            };
+
+            pool.add(source, media);
         }
+
+        if let Some(f) = fillers.as_ref() {
+            f.lock().await.clone_from(&pool);
+        }
+
+        pool
     }
 
     pub async fn copy_assets(&self) -> Result<(), std::io::Error> {
@@ -211,10 +323,35 @@ impl StorageBackend {
         }
     }
 
-    pub async fn walk_dir<P: AsRef<Path>>(&self, input: P) -> Result<Vec<PathBuf>, ServiceError> {
+    pub async fn walk_dir<P: AsRef<Path>>(
+        &self,
+        input: P,
+        options: &WalkOptions,
+    ) -> Result<Vec<PathBuf>, ServiceError> {
         match self {
-            StorageBackend::Local(storage) => storage.walk_dir(input).await,
-            StorageBackend::S3(storage) => storage.walk_dir(input).await,
+            StorageBackend::Local(storage) => storage.walk_dir(input, options).await,
+            StorageBackend::S3(storage) => storage.walk_dir(input, options).await,
+        }
+    }
+
+    pub async fn export_file(&self, file_path: &str, target: &Path) -> Result<(), ServiceError> {
+        match self {
+            StorageBackend::Local(storage) => storage.export_file(file_path, target).await,
+            StorageBackend::S3(storage) => storage.export_file(file_path, target).await,
+        }
+    }
+
+    pub async fn import_file(&self, source: &Path, file_path: &str) -> Result<(), ServiceError> {
+        match self {
+            StorageBackend::Local(storage) => storage.import_file(source, file_path).await,
+            StorageBackend::S3(storage) => storage.import_file(source, file_path).await,
+        }
+    }
+
+    pub async fn delete_file(&self, file_path: &str) -> Result<(), ServiceError> {
+        match self {
+            StorageBackend::Local(storage) => storage.delete_file(file_path).await,
+            StorageBackend::S3(storage) => storage.delete_file(file_path).await,
         }
     }
 
@@ -253,7 +390,12 @@ trait Storage {
         duration: web::Data<SharedMediaMap>,
         recursive: bool,
     ) -> Result<(), ServiceError>;
-    async fn upload(&self, data: Multipart, path: &Path, is_abs: bool) -> Result<(), ServiceError>;
+    async fn upload(
+        &self,
+        data: Multipart,
+        path: &Path,
+        is_abs: bool,
+    ) -> Result<Vec<String>, ServiceError>;
     async fn watchman(
         &mut self,
         config: PlayoutConfig,
@@ -264,12 +406,24 @@ trait Storage {
     async fn fill_filler_list(
         &mut self,
         config: &PlayoutConfig,
-        fillers: Option<Arc<Mutex<Vec<Media>>>>,
+        filler_path: &Path,
+        probe: bool,
     ) -> Vec<Media>;
     async fn copy_assets(&self) -> Result<(), std::io::Error>;
     async fn is_dir<P: AsRef<Path>>(&self, input: P) -> bool;
     async fn is_file<P: AsRef<Path>>(&self, input: P) -> bool;
-    async fn walk_dir<P: AsRef<Path>>(&self, input: P) -> Result<Vec<PathBuf>, ServiceError>;
+    /// List all entries below the input path, which are included by the options.
+    async fn walk_dir<P: AsRef<Path>>(
+        &self,
+        input: P,
+        options: &WalkOptions,
+    ) -> Result<Vec<PathBuf>, ServiceError>;
+    /// Copy a file from the storage to a local path.
+    async fn export_file(&self, file_path: &str, target: &Path) -> Result<(), ServiceError>;
+    /// Copy a local file into the storage, missing folders are created.
+    async fn import_file(&self, source: &Path, file_path: &str) -> Result<(), ServiceError>;
+    /// Delete a single file from the storage.
+    async fn delete_file(&self, file_path: &str) -> Result<(), ServiceError>;
     async fn open_media(
         &self,
         _req: &HttpRequest,
@@ -373,3 +527,27 @@ pub fn clean_raw_abs_path(
         Ok((filler, filler_path))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walk_options_filter() {
+        let options = WalkOptions {
+            depth: Some(2),
+            extensions: vec!["mp4".to_string()],
+            exclude: vec![
+                Pattern::new("_archive").unwrap(),
+                Pattern::new("proxy/*").unwrap(),
+            ],
+        };
+
+        assert!(options.includes(Path::new("news/clip.MP4"), false));
+        assert!(options.includes(Path::new("news"), true));
+        assert!(!options.includes(Path::new("news/clip.mov"), false));
+        assert!(!options.includes(Path::new("news/2024/clip.mp4"), false));
+        assert!(!options.includes(Path::new("news/_archive"), true));
+        assert!(!options.includes(Path::new("proxy/clip.mp4"), false));
+    }
+}
@@ -3,6 +3,7 @@ use std::{
     collections::HashSet,
     io::{Error, ErrorKind},
     path::{Path, PathBuf},
+    process,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
@@ -21,13 +22,19 @@ use rand::{distr::Alphanumeric, rngs::StdRng, seq::SliceRandom, Rng, SeedableRng
 use regex::Regex;
 use tokio::{sync::Mutex, task::JoinHandle};
 
-use crate::file::{utils::media_map::SharedMediaMap, MoveObject, PathObject, Storage, VideoFile};
+use crate::file::{
+    utils::media_map::SharedMediaMap, MoveObject, PathObject, Storage, VideoFile, WalkOptions,
+};
 use crate::player::utils::{include_file_extension, probe::MediaProbe, Media};
 use crate::utils::{config::PlayoutConfig, errors::ServiceError, logging::Target};
+use crate::ARGS;
 
 use aws_config::Region;
 use aws_sdk_s3::{
+    error::DisplayErrorContext,
+    operation::head_bucket::HeadBucketError,
     presigning::PresigningConfig,
+    primitives::ByteStream,
     types::{CompletedMultipartUpload, CompletedPart},
     Client,
 };
@@ -44,6 +51,8 @@ pub struct S3Storage {
     endpoint: String,
     bucket: String,
     client: Client,
+    /// Result of the startup self-check, `None` when the bucket is usable.
+    pub error: Option<String>,
     pub watch_handler: Arc<Mutex<Option<JoinHandle<()>>>>,
 }
 
@@ -53,32 +62,98 @@ impl S3Storage {
             .unwrap_or_else(|e| panic!("Invalid S3 schema!: {}", e));
         let credentials_cloned = credentials.clone();
         let endpoint_url_cloned = endpoint_url.clone();
+        let client = {
+            let shared_provider =
+                aws_sdk_s3::config::SharedCredentialsProvider::new(credentials_cloned);
+            let config = aws_config::from_env()
+                .region(Region::new("us-east-1")) // Dummy default region, will added if needed!
+                .credentials_provider(shared_provider)
+                .load()
+                .await;
+
+            let s3_config = aws_sdk_s3::config::Builder::from(&config)
+                .endpoint_url(&endpoint_url_cloned)
+                .force_path_style(true)
+                .build();
+
+            aws_sdk_s3::Client::from_conf(s3_config)
+        };
+        let error = Self::self_check(&client, &bucket, ARGS.s3_create_bucket)
+            .await
+            .err();
+
         Self {
             root: PathBuf::new(),
             original_root: root,
             extensions,
             endpoint: endpoint_url.clone(),
             bucket,
-            client: {
-                let shared_provider =
-                    aws_sdk_s3::config::SharedCredentialsProvider::new(credentials_cloned);
-                let config = aws_config::from_env()
-                    .region(Region::new("us-east-1")) // Dummy default region, will added if needed!
-                    .credentials_provider(shared_provider)
-                    .load()
-                    .await;
-
-                let s3_config = aws_sdk_s3::config::Builder::from(&config)
-                    .endpoint_url(&endpoint_url_cloned)
-                    .force_path_style(true)
-                    .build();
-
-                aws_sdk_s3::Client::from_conf(s3_config)
-            },
+            client,
+            error,
             watch_handler: Arc::new(Mutex::new(None)),
         }
     }
 
+    /// Check that the bucket exists and that objects can be written, read and deleted.
+    /// A missing bucket is created, when `create` is set.
+    async fn self_check(client: &Client, bucket: &str, create: bool) -> Result<(), String> {
+        if let Err(e) = client.head_bucket().bucket(bucket).send().await {
+            if !e
+                .as_service_error()
+                .is_some_and(HeadBucketError::is_not_found)
+            {
+                return Err(format!(
+                    "Bucket is not reachable: {}",
+                    DisplayErrorContext(&e)
+                ));
+            }
+
+            if !create {
+                return Err(format!(
+                    "Bucket '{bucket}' does not exist, create it or start with --s3-create-bucket"
+                ));
+            }
+
+            client
+                .create_bucket()
+                .bucket(bucket)
+                .send()
+                .await
+                .map_err(|e| format!("Create bucket failed: {}", DisplayErrorContext(&e)))?;
+
+            info!("Created S3 bucket <b><magenta>{bucket}</></b>");
+        }
+
+        let key = format!(".ffplayout_check_{}", process::id());
+
+        client
+            .put_object()
+            .bucket(bucket)
+            .key(&key)
+            .body(ByteStream::from_static(b"ffplayout"))
+            .send()
+            .await
+            .map_err(|e| format!("No write permission: {}", DisplayErrorContext(&e)))?;
+
+        let read = client
+            .get_object()
+            .bucket(bucket)
+            .key(&key)
+            .send()
+            .await
+            .map_err(|e| format!("No read permission: {}", DisplayErrorContext(&e)));
+
+        client
+            .delete_object()
+            .bucket(bucket)
+            .key(&key)
+            .send()
+            .await
+            .map_err(|e| format!("No delete permission: {}", DisplayErrorContext(&e)))?;
+
+        read.map(|_| ())
+    }
+
     /// Watches an S3 bucket for changes and updates the `sources` list accordingly.
     ///
     /// # Arguments
@@ -182,6 +257,7 @@ impl S3Storage {
         Ok(MoveObject {
             source: source_name.to_string(),
             target: target_name.to_string(),
+            force: false,
         })
     }
 
@@ -390,10 +466,16 @@ impl Storage for S3Storage {
         path.strip_prefix(path_prefix).unwrap_or(path).to_string()
     }
     fn echo_log(&self) {
-        info!(
-            "<blue>S3 Storage initialized at '{}/{}/'",
-            &self.endpoint, &self.bucket
-        );
+        match &self.error {
+            Some(e) => error!(
+                "S3 Storage at '{}/{}/' is not usable: {e}",
+                &self.endpoint, &self.bucket
+            ),
+            None => info!(
+                "<blue>S3 Storage initialized at '{}/{}/'",
+                &self.endpoint, &self.bucket
+            ),
+        }
     }
     async fn fetch_file_path(&self, file_path: &str) -> Result<String, ServiceError> {
         let (cleaned_root_prefix, _) = s3_path(&self.original_root.to_string_lossy())?;
@@ -408,6 +490,12 @@ impl Storage for S3Storage {
         path_obj: &PathObject,
         dur_data: web::Data<SharedMediaMap>,
     ) -> Result<PathObject, ServiceError> {
+        if let Some(e) = &self.error {
+            return Err(ServiceError::Conflict(format!(
+                "S3 storage is not usable: {e}"
+            )));
+        }
+
         // let s3_obj_dur = duration;
         let media_duration = dur_data;
         let mut parent_folders = vec![];
@@ -544,6 +632,7 @@ impl Storage for S3Storage {
         Ok(MoveObject {
             source: obj_names.source.to_string(),
             target: obj_names.target.to_string(),
+            force: false,
         })
     }
     async fn remove(
@@ -570,7 +659,7 @@ impl Storage for S3Storage {
         mut data: Multipart,
         path: &Path,
         _is_abs: bool,
-    ) -> Result<(), ServiceError> {
+    ) -> Result<Vec<String>, ServiceError> {
         let mut upload_id: Option<String> = None;
         let mut key: Option<String> = None;
         let mut completed_parts: Vec<CompletedPart> = Vec::new();
@@ -675,7 +764,8 @@ impl Storage for S3Storage {
                 .await
                 .map_err(|e| format!("Failed to complete multipart upload: {}", e))?;
         }
-        Ok(())
+
+        Ok(key.into_iter().filter(|_| s3_upload_permit).collect())
     }
 
     async fn watchman(
@@ -719,14 +809,14 @@ impl Storage for S3Storage {
     async fn fill_filler_list(
         &mut self,
         config: &PlayoutConfig,
-        fillers: Option<Arc<Mutex<Vec<Media>>>>,
+        filler_path: &Path,
+        probe: bool,
     ) -> Vec<Media> {
         let bucket = &self.bucket;
         let client = &self.client;
 
         let id = config.general.channel_id;
         let mut filler_list = vec![];
-        let filler_path = &config.storage.filler_path;
         let mut index = 0;
 
         if self.is_dir(filler_path.to_str().unwrap()).await {
@@ -749,7 +839,7 @@ impl Storage for S3Storage {
                                 .unwrap_or(obj_key.to_string());
                             if include_file_extension(config, Path::new(obj_key)) {
                                 let mut media = Media::new(index, &presigned_url, false).await;
-                                if fillers.is_none() {
+                                if probe {
                                     if let Err(e) = media.add_probe(false).await {
                                         error!(target: Target::file_mail(), channel = id; "{e:?}");
                                     };
@@ -774,10 +864,6 @@ impl Storage for S3Storage {
             for (index, item) in filler_list.iter_mut().enumerate() {
                 item.index = Some(index);
             }
-
-            if let Some(f) = fillers.as_ref() {
-                f.lock().await.clone_from(&filler_list);
-            }
         } else
         // if s3_utils::s3_is_object(filler_path.to_str().unwrap(), bucket, client)
         //     .await
@@ -792,20 +878,16 @@ impl Storage for S3Storage {
                 .unwrap_or(filler_path.to_string_lossy().to_string());
             let mut media = Media::new(0, &presigned_url, false).await;
 
-            if fillers.is_none() {
+            if probe {
                 if let Err(e) = media.add_probe(false).await {
                     error!(target: Target::file_mail(), channel = id; "{e:?}");
                 };
             }
 
             filler_list.push(media);
-
-            if let Some(f) = fillers.as_ref() {
-                f.lock().await.clone_from(&filler_list);
-            }
         }
 
-        vec![]
+        filler_list
     }
 
     async fn copy_assets(&self) -> Result<(), std::io::Error> {
@@ -831,7 +913,11 @@ impl Storage for S3Storage {
     /// # Returns
     /// - `Ok(Vec<PathBuf>)`: Paths of all objects and prefixes.
     /// - `Err(ServiceError)`: On S3 request error.
-    async fn walk_dir<P: AsRef<Path>>(&self, input: P) -> Result<Vec<PathBuf>, ServiceError> {
+    async fn walk_dir<P: AsRef<Path>>(
+        &self,
+        input: P,
+        options: &WalkOptions,
+    ) -> Result<Vec<PathBuf>, ServiceError> {
         let input = input.as_ref();
         let (cleaned_root_prefix, _) = s3_path(&self.original_root.to_string_lossy())?;
         let validated_file_path = input
@@ -845,7 +931,7 @@ impl Storage for S3Storage {
         let mut list_objs = client
             .list_objects_v2()
             .bucket(bucket)
-            .prefix(validated_file_path)
+            .prefix(validated_file_path.as_ref())
             // .max_keys(S3_MAX_KEYS)
             .into_paginator()
             .send();
@@ -853,14 +939,32 @@ impl Storage for S3Storage {
         while let Some(result) = list_objs.next().await {
             match result {
                 Ok(output) => {
-                    for object in output.contents() {
-                        if let Some(obj_key) = object.key() {
-                            objects.push(PathBuf::from(obj_key));
+                    let keys = output
+                        .contents()
+                        .iter()
+                        .filter_map(|o| o.key())
+                        .chain(output.common_prefixes().iter().filter_map(|p| p.prefix()));
+
+                    for obj_key in keys {
+                        let relative = obj_key
+                            .strip_prefix(validated_file_path.as_ref())
+                            .unwrap_or(obj_key)
+                            .trim_matches('/');
+
+                        // keys have no folder entries, so check every parent for exclusion
+                        let mut parent = Path::new(relative).parent();
+                        let mut included =
+                            options.includes(Path::new(relative), obj_key.ends_with('/'));
+
+                        while let Some(dir) =
+                            parent.filter(|p| included && !p.as_os_str().is_empty())
+                        {
+                            included = options.includes(dir, true);
+                            parent = dir.parent();
                         }
-                    }
-                    for prefix in output.common_prefixes() {
-                        if let Some(prefix_key) = prefix.prefix() {
-                            objects.push(PathBuf::from(prefix_key));
+
+                        if included {
+                            objects.push(PathBuf::from(obj_key));
                         }
                     }
                 }
@@ -873,6 +977,58 @@ impl Storage for S3Storage {
 
         Ok(objects)
     }
+
+    async fn export_file(&self, file_path: &str, target: &Path) -> Result<(), ServiceError> {
+        use tokio::io::AsyncWriteExt;
+
+        let (key, _) = s3_path(file_path)?;
+        let mut body = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(&key)
+            .send()
+            .await
+            .map_err(|e| ServiceError::Conflict(format!("Failed to get object!: {}", e)))?
+            .body;
+        let mut file = tokio::fs::File::create(target).await?;
+
+        while let Some(bytes) = body
+            .try_next()
+            .await
+            .map_err(|e| ServiceError::Conflict(format!("Failed to read object!: {}", e)))?
+        {
+            file.write_all(&bytes).await?;
+        }
+
+        file.flush().await?;
+
+        Ok(())
+    }
+
+    async fn import_file(&self, source: &Path, file_path: &str) -> Result<(), ServiceError> {
+        let (key, _) = s3_path(file_path)?;
+        let body = ByteStream::from_path(source)
+            .await
+            .map_err(|e| ServiceError::Conflict(format!("Failed to read file!: {}", e)))?;
+
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(&key)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| ServiceError::Conflict(format!("Failed to put object!: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn delete_file(&self, file_path: &str) -> Result<(), ServiceError> {
+        let (key, _) = s3_path(file_path)?;
+
+        Self::s3_delete_object(&key, &self.bucket, &self.client).await
+    }
 }
 
 /// **S3 String Parser**
@@ -0,0 +1,94 @@
+use std::fmt;
+
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use crate::db::{handles, models::StorageAudit, DbPool};
+use crate::utils::{errors::ServiceError, logging::Target, time_machine::time_now};
+
+/// Max entries of one audit request.
+const AUDIT_LIMIT: i64 = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuditAction {
+    Upload,
+    Delete,
+    Rename,
+    Mkdir,
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::Upload => write!(f, "upload"),
+            Self::Delete => write!(f, "delete"),
+            Self::Rename => write!(f, "rename"),
+            Self::Mkdir => write!(f, "mkdir"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct AuditFilter {
+    pub user: Option<String>,
+    pub action: Option<String>,
+    pub path: Option<String>,
+    /// Unix timestamps
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+/// Store a storage operation in the audit trail.
+///
+/// The operation itself is already done, so failing writes are only logged.
+pub async fn record<T>(
+    conn: &DbPool,
+    channel_id: i32,
+    user_id: i32,
+    action: AuditAction,
+    path: &str,
+    target: Option<&str>,
+    result: &Result<T, ServiceError>,
+) {
+    let username = handles::select_user(conn, user_id)
+        .await
+        .map(|u| u.username)
+        .unwrap_or_default();
+
+    let item = StorageAudit {
+        channel_id,
+        user_id,
+        username,
+        action: action.to_string(),
+        path: path.to_string(),
+        target: target.map(ToString::to_string),
+        timestamp: time_now(&None).timestamp(),
+        error: result.as_ref().err().map(ToString::to_string),
+        ..Default::default()
+    };
+
+    if let Err(e) = handles::insert_storage_audit(conn, &item).await {
+        error!(target: Target::file_mail(), channel = channel_id; "Write audit entry failed: {e}");
+    }
+}
+
+pub async fn select_audit(
+    conn: &DbPool,
+    channel_id: i32,
+    filter: &AuditFilter,
+) -> Result<Vec<StorageAudit>, ServiceError> {
+    let non_empty = |v: &Option<String>| v.clone().filter(|s| !s.trim().is_empty());
+
+    Ok(handles::select_storage_audit(
+        conn,
+        channel_id,
+        non_empty(&filter.user).as_deref(),
+        non_empty(&filter.action).as_deref(),
+        non_empty(&filter.path).as_deref(),
+        filter.from,
+        filter.to,
+        filter.limit.unwrap_or(AUDIT_LIMIT).clamp(1, AUDIT_LIMIT),
+    )
+    .await?)
+}
@@ -1,14 +1,16 @@
 use async_walkdir::WalkDir;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use tokio_stream::StreamExt;
 
 use lexical_sort::natural_lexical_cmp;
 use log::*;
-use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 use tokio::sync::Mutex;
 
+use crate::db::models::FillerSource;
 use crate::player::utils::{include_file_extension, Media};
 use crate::utils::{config::PlayoutConfig, logging::Target};
 
@@ -16,13 +18,13 @@ use super::ABS_PATH_INDICATOR;
 
 pub async fn absolute_fill_filler_list(
     config: &PlayoutConfig,
-    fillers: Option<Arc<Mutex<Vec<Media>>>>,
+    filler_path: &Path,
+    probe: bool,
 ) -> Vec<Media> {
     let id = config.general.channel_id;
     let mut filler_list = vec![];
-    // let filler_path = &config.storage.filler_path;
 
-    let raw_filler_path = &config.storage.filler_path.to_string_lossy();
+    let raw_filler_path = &filler_path.to_string_lossy();
     let filler_sanitized_path = raw_filler_path
         .strip_prefix(ABS_PATH_INDICATOR)
         .unwrap_or(raw_filler_path);
@@ -34,7 +36,6 @@ pub async fn absolute_fill_filler_list(
     let filler_path = PathBuf::from(filler_sanitized_path);
 
     if filler_path.is_dir() {
-        // let config_clone = config.clone();
         let mut index = 0;
         let mut entries = WalkDir::new(&filler_path);
 
@@ -42,7 +43,7 @@ pub async fn absolute_fill_filler_list(
             if entry.path().is_file() && include_file_extension(config, &entry.path()) {
                 let mut media = Media::new(index, &entry.path().to_string_lossy(), false).await;
 
-                if fillers.is_none() {
+                if probe {
                     if let Err(e) = media.add_probe(false).await {
                         error!(target: Target::file_mail(), channel = id; "{e:?}");
                     };
@@ -64,25 +65,382 @@ pub async fn absolute_fill_filler_list(
         for (index, item) in filler_list.iter_mut().enumerate() {
             item.index = Some(index);
         }
-
-        if let Some(f) = fillers.as_ref() {
-            f.lock().await.clone_from(&filler_list);
-        }
     } else if filler_path.is_file() {
-        let mut media = Media::new(0, &config.storage.filler_path.to_string_lossy(), false).await;
+        let mut media = Media::new(0, &filler_path.to_string_lossy(), false).await;
 
-        if fillers.is_none() {
+        if probe {
             if let Err(e) = media.add_probe(false).await {
                 error!(target: Target::file_mail(), channel = id; "{e:?}");
             };
         }
 
         filler_list.push(media);
+    }
+
+    filler_list
+}
+
+/// Configured filler sources, or the filler from the storage settings when no one is set.
+pub fn filler_sources(config: &PlayoutConfig) -> Vec<FillerSource> {
+    if config.fillers.is_empty() {
+        vec![FillerSource {
+            path: config.storage.filler.clone(),
+            weight: 1,
+            ..Default::default()
+        }]
+    } else {
+        config.fillers.clone()
+    }
+}
+
+#[derive(Clone, Debug)]
+struct FillerGroup {
+    source: FillerSource,
+    media: Vec<Media>,
+    cursor: usize,
+}
+
+/// Fillers from all sources.
+///
+/// Clips are taken in rotation from each source, the source is picked by its weight.
+/// A clip which was played within `no_repeat` minutes is skipped, as long as there are other clips.
+#[derive(Clone, Debug, Default)]
+pub struct FillerPool {
+    groups: Vec<FillerGroup>,
+    played: HashMap<String, f64>,
+}
+
+impl FillerPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, source: FillerSource, media: Vec<Media>) {
+        self.groups.push(FillerGroup {
+            source,
+            media,
+            cursor: 0,
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.iter().all(|g| g.media.is_empty())
+    }
+
+    fn is_fresh(&self, group: &FillerGroup, media: &Media, time: f64) -> bool {
+        let no_repeat = group.source.no_repeat as f64 * 60.0;
+
+        self.played
+            .get(&media.source)
+            .is_none_or(|last| time < *last || time - last >= no_repeat)
+    }
+
+    /// Next clip of the group in rotation order, which is not played recently.
+    fn next_fresh(&self, index: usize, time: f64) -> Option<usize> {
+        let group = &self.groups[index];
+        let len = group.media.len();
+
+        (0..len)
+            .map(|i| (group.cursor + i) % len)
+            .find(|i| self.is_fresh(group, &group.media[*i], time))
+    }
+
+    /// Clip of the first source, which was not played for the longest time.
+    fn least_recent(&self) -> Option<(usize, usize)> {
+        let (index, group) = self
+            .groups
+            .iter()
+            .enumerate()
+            .find(|(_, g)| !g.media.is_empty())?;
 
-        if let Some(f) = fillers.as_ref() {
-            f.lock().await.clone_from(&filler_list);
+        let media = (0..group.media.len()).min_by(|a, b| {
+            let last_a = self
+                .played
+                .get(&group.media[*a].source)
+                .unwrap_or(&f64::MIN);
+            let last_b = self
+                .played
+                .get(&group.media[*b].source)
+                .unwrap_or(&f64::MIN);
+
+            last_a.total_cmp(last_b)
+        })?;
+
+        Some((index, media))
+    }
+
+    /// Pick a group by its weight and take its first candidate.
+    fn weighted_pick(&self, candidates: &[(usize, usize)]) -> Option<(usize, usize)> {
+        let mut groups: Vec<usize> = candidates.iter().map(|(g, _)| *g).collect();
+        groups.dedup();
+
+        let total: i64 = groups
+            .iter()
+            .map(|g| self.groups[*g].source.weight.max(0))
+            .sum();
+
+        if total > 0 {
+            let mut value = rand::rng().random_range(0..total);
+
+            for group in groups {
+                let weight = self.groups[group].source.weight.max(0);
+
+                if value < weight {
+                    return candidates.iter().find(|(g, _)| *g == group).copied();
+                }
+
+                value -= weight;
+            }
         }
+
+        // sources without weight are only used when no other clip is allowed
+        candidates.first().copied()
     }
 
-    filler_list
+    fn pick(&self, time: f64) -> Option<(usize, usize)> {
+        let candidates: Vec<(usize, usize)> = (0..self.groups.len())
+            .filter_map(|g| self.next_fresh(g, time).map(|m| (g, m)))
+            .collect();
+
+        self.weighted_pick(&candidates)
+            .or_else(|| self.least_recent())
+    }
+
+    fn take(&mut self, group: usize, index: usize, time: f64) -> Media {
+        let group = &mut self.groups[group];
+        let media = group.media[index].clone();
+
+        group.cursor = index + 1;
+        self.played.insert(media.source.clone(), time);
+
+        media
+    }
+
+    /// Take the next filler clip at `time`, in seconds.
+    pub fn next(&mut self, time: f64) -> Option<Media> {
+        let (group, index) = self.pick(time)?;
+
+        Some(self.take(group, index, time))
+    }
+
+    /// Clips with known length, which fit in `gap`, in rotation order of each group.
+    fn fitting(&self, time: f64, gap: f64, fresh: bool) -> Vec<(usize, usize)> {
+        let mut candidates = vec![];
+
+        for (g, group) in self.groups.iter().enumerate() {
+            let len = group.media.len();
+
+            for i in (0..len).map(|i| (group.cursor + i) % len) {
+                let media = &group.media[i];
+
+                if media.duration >= MIN_FIT_LENGTH
+                    && media.duration <= gap
+                    && (!fresh || self.is_fresh(group, media, time))
+                {
+                    candidates.push((g, i));
+                }
+            }
+        }
+
+        candidates
+    }
+
+    /// Take a filler clip, which fits in the `gap` of seconds.
+    ///
+    /// When the gap is short, the clips are combined to fill it as good as possible
+    /// and the first clip of the best combination is taken. The next call, with the remaining gap,
+    /// continues the combination. Returns `None` when no clip fits.
+    pub fn next_fitting(&mut self, time: f64, gap: f64) -> Option<Media> {
+        let mut candidates = self.fitting(time, gap, true);
+
+        if candidates.is_empty() {
+            candidates = self.fitting(time, gap, false);
+        }
+
+        let durations: Vec<f64> = candidates
+            .iter()
+            .map(|(g, i)| self.groups[*g].media[*i].duration)
+            .collect();
+        let longest = durations.iter().copied().fold(0.0, f64::max);
+
+        let (group, index) = if gap >= longest * 2.0 {
+            // enough room left, keep weights and rotation
+            self.weighted_pick(&candidates)?
+        } else {
+            let combination = best_combination(&durations, gap);
+
+            *combination.first().and_then(|c| candidates.get(*c))?
+        };
+
+        Some(self.take(group, index, time))
+    }
+
+    /// Sources of the clips, which are not probed yet.
+    pub fn unprobed(&self) -> Vec<String> {
+        self.groups
+            .iter()
+            .flat_map(|g| g.media.iter())
+            .filter(|m| m.probe.is_none())
+            .map(|m| m.source.clone())
+            .collect()
+    }
+
+    /// Take over the probe of a clip.
+    pub fn update(&mut self, media: &Media) {
+        for item in self
+            .groups
+            .iter_mut()
+            .flat_map(|g| g.media.iter_mut())
+            .filter(|m| m.source == media.source)
+        {
+            item.duration = media.duration;
+            item.out = media.out;
+            item.probe.clone_from(&media.probe);
+        }
+    }
+}
+
+/// Clips shorter then this are not used for filling gaps, like images.
+const MIN_FIT_LENGTH: f64 = 1.0;
+
+/// Indexes of the durations, which sum is the closest to `gap`, without exceeding it.
+///
+/// Subset sum in tenth of seconds, durations are rounded up so the combination never overruns.
+fn best_combination(durations: &[f64], gap: f64) -> Vec<usize> {
+    let target = (gap * 10.0).floor() as usize;
+    let steps: Vec<usize> = durations
+        .iter()
+        .map(|d| (d * 10.0).ceil() as usize)
+        .collect();
+    // reached sum -> (previous sum, index of the clip)
+    let mut reached: Vec<Option<(usize, usize)>> = vec![None; target + 1];
+    let mut best = 0;
+
+    for (index, step) in steps.iter().enumerate() {
+        if *step == 0 || *step > target {
+            continue;
+        }
+
+        for sum in (*step..=target).rev() {
+            if reached[sum].is_none() && (sum == *step || reached[sum - step].is_some()) {
+                reached[sum] = Some((sum - step, index));
+                best = best.max(sum);
+            }
+        }
+    }
+
+    let mut combination = vec![];
+    let mut sum = best;
+
+    while let Some((previous, index)) = reached[sum] {
+        combination.push(index);
+        sum = previous;
+
+        if sum == 0 {
+            break;
+        }
+    }
+
+    combination.sort_unstable();
+    combination
+}
+
+/// Probe the filler clips in the background, their length is needed for filling gaps.
+pub async fn probe_fillers(fillers: Arc<Mutex<FillerPool>>) {
+    let sources = fillers.lock().await.unprobed();
+
+    for source in sources {
+        let media = Media::new(0, &source, true).await;
+
+        if media.probe.is_some() {
+            fillers.lock().await.update(&media);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip(source: &str) -> Media {
+        Media {
+            source: source.to_string(),
+            out: 60.0,
+            duration: 60.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rotate_without_repeat() {
+        let mut pool = FillerPool::new();
+
+        pool.add(
+            FillerSource {
+                weight: 1,
+                no_repeat: 60,
+                ..Default::default()
+            },
+            vec![clip("a1.mp4"), clip("a2.mp4")],
+        );
+        pool.add(
+            FillerSource {
+                weight: 0,
+                ..Default::default()
+            },
+            vec![clip("b1.mp4")],
+        );
+
+        let sources: Vec<String> = [0.0, 60.0, 120.0, 180.0, 3600.0]
+            .iter()
+            .map(|t| pool.next(*t).unwrap().source)
+            .collect();
+
+        assert_eq!(
+            sources,
+            vec!["a1.mp4", "a2.mp4", "b1.mp4", "b1.mp4", "a1.mp4"]
+        );
+    }
+
+    #[test]
+    fn fit_filler_in_gap() {
+        let mut pool = FillerPool::new();
+
+        pool.add(
+            FillerSource {
+                weight: 1,
+                ..Default::default()
+            },
+            vec![
+                Media {
+                    source: "long.mp4".to_string(),
+                    duration: 400.0,
+                    out: 400.0,
+                    ..Default::default()
+                },
+                Media {
+                    source: "mid.mp4".to_string(),
+                    duration: 150.0,
+                    out: 150.0,
+                    ..Default::default()
+                },
+                Media {
+                    source: "short.mp4".to_string(),
+                    duration: 90.0,
+                    out: 90.0,
+                    ..Default::default()
+                },
+            ],
+        );
+
+        // 150 + 90 fills the gap complete, 400 would be cut
+        let first = pool.next_fitting(0.0, 240.0).unwrap();
+        let second = pool
+            .next_fitting(first.duration, 240.0 - first.duration)
+            .unwrap();
+
+        assert_eq!(first.duration + second.duration, 240.0);
+        assert!(pool.next_fitting(240.0, 60.0).is_none());
+        assert_eq!(best_combination(&[400.0, 150.0, 90.0], 240.0), vec![1, 2]);
+    }
 }
@@ -0,0 +1,372 @@
+use std::{
+    collections::HashMap,
+    io::{self, Read},
+    path::Path,
+    time::UNIX_EPOCH,
+};
+
+#[cfg(target_family = "unix")]
+use std::os::unix::fs::MetadataExt;
+
+use log::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::db::{handles, models::MediaHash, DbPool};
+use crate::file::{StorageBackend, WalkOptions};
+use crate::player::utils::include_file_extension;
+use crate::utils::{config::PlayoutConfig, errors::ServiceError, logging::Target};
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct DuplicateGroup {
+    pub hash: String,
+    pub size: i64,
+    pub files: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct DedupeObj {
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct DedupeReport {
+    pub groups: usize,
+    pub linked: usize,
+    /// Saved disk space in bytes.
+    pub saved: i64,
+    pub dry_run: bool,
+    pub errors: Vec<String>,
+}
+
+/// Calculate the SHA-256 sum of a file, as hex string.
+pub async fn file_hash(path: impl AsRef<Path>) -> Result<String, io::Error> {
+    let path = path.as_ref().to_path_buf();
+
+    tokio::task::spawn_blocking(move || {
+        let mut file = std::fs::File::open(path)?;
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0; 1024 * 1024];
+
+        loop {
+            let count = file.read(&mut buffer)?;
+
+            if count == 0 {
+                break;
+            }
+
+            hasher.update(&buffer[..count]);
+        }
+
+        Ok(hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect())
+    })
+    .await?
+}
+
+/// Size and modification time (unix seconds) of a file.
+pub(crate) async fn file_stamp(path: &Path) -> Result<(i64, i64), io::Error> {
+    let metadata = tokio::fs::metadata(path).await?;
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+
+    Ok((metadata.len() as i64, modified))
+}
+
+pub(crate) async fn media_hash(
+    config: &PlayoutConfig,
+    path: &Path,
+    known: Option<&MediaHash>,
+) -> Result<Option<MediaHash>, io::Error> {
+    let (size, modified) = file_stamp(path).await?;
+
+    if known.is_some_and(|k| k.size == size && k.modified == modified) {
+        return Ok(None);
+    }
+
+    let relative = path
+        .strip_prefix(&config.channel.storage)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .to_string();
+
+    Ok(Some(MediaHash {
+        channel_id: config.general.channel_id,
+        path: relative,
+        hash: file_hash(path).await?,
+        size,
+        modified,
+        ..Default::default()
+    }))
+}
+
+/// Hash new uploaded files. Only local storage is supported,
+/// on S3 the objects would have to be downloaded first.
+pub async fn hash_uploads(
+    conn: &DbPool,
+    config: &PlayoutConfig,
+    storage: &StorageBackend,
+    files: &[String],
+) -> Result<(), ServiceError> {
+    if !matches!(storage, StorageBackend::Local(_)) {
+        return Ok(());
+    }
+
+    for file in files {
+        let path = storage.fetch_file_path(file).await?;
+
+        if let Some(media) = media_hash(config, Path::new(&path), None).await? {
+            handles::upsert_media_hash(conn, &media).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Walk through the channel storage, hash new and changed files and drop entries of deleted files.
+pub async fn scan_hashes(
+    conn: &DbPool,
+    config: &PlayoutConfig,
+    storage: &StorageBackend,
+) -> Result<usize, ServiceError> {
+    let id = config.general.channel_id;
+
+    if !matches!(storage, StorageBackend::Local(_)) {
+        return Err(ServiceError::BadRequest(
+            "Hash scan is only supported on local storage".to_string(),
+        ));
+    }
+
+    let mut known: HashMap<String, MediaHash> = handles::select_media_hashes(conn, id)
+        .await?
+        .into_iter()
+        .map(|m| (m.path.clone(), m))
+        .collect();
+    let mut count = 0;
+
+    for path in storage
+        .walk_dir(&config.channel.storage, &WalkOptions::default())
+        .await?
+    {
+        if !path.is_file() || !include_file_extension(config, &path) {
+            continue;
+        }
+
+        let relative = path
+            .strip_prefix(&config.channel.storage)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .to_string();
+        let entry = known.remove(&relative);
+
+        match media_hash(config, &path, entry.as_ref()).await {
+            Ok(Some(media)) => {
+                handles::upsert_media_hash(conn, &media).await?;
+                count += 1;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = id; "Hash <b><magenta>{path:?}</></b> failed: {e}");
+            }
+        }
+    }
+
+    for path in known.keys() {
+        handles::delete_media_hash(conn, id, path).await?;
+    }
+
+    debug!(target: Target::file_mail(), channel = id; "Hash scan done, {count} file(s) updated");
+
+    Ok(count)
+}
+
+/// Check if both paths point already to the same file.
+#[cfg(target_family = "unix")]
+async fn same_inode(a: &Path, b: &Path) -> Result<bool, io::Error> {
+    let a = tokio::fs::metadata(a).await?;
+    let b = tokio::fs::metadata(b).await?;
+
+    Ok(a.dev() == b.dev() && a.ino() == b.ino())
+}
+
+#[cfg(not(target_family = "unix"))]
+async fn same_inode(_a: &Path, _b: &Path) -> Result<bool, io::Error> {
+    Ok(false)
+}
+
+/// Replace `copy` with a hardlink to `keep`. The link is created next to the copy first
+/// and renamed afterwards, so the copy is never missing.
+async fn replace_with_link(keep: &Path, copy: &Path) -> Result<(), io::Error> {
+    let mut temp = copy.to_path_buf();
+    temp.as_mut_os_string().push(".ffplayout_link");
+
+    tokio::fs::hard_link(keep, &temp).await?;
+
+    if let Err(e) = tokio::fs::rename(&temp, copy).await {
+        tokio::fs::remove_file(&temp).await?;
+
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+/// Replace duplicate files on local storage with hardlinks to the first file of a group.
+///
+/// Only files which are unchanged since the last hash scan are linked.
+/// Copies on other file systems can't be linked and are reported as error.
+pub async fn dedupe(
+    conn: &DbPool,
+    config: &PlayoutConfig,
+    storage: &StorageBackend,
+    dry_run: bool,
+) -> Result<DedupeReport, ServiceError> {
+    let id = config.general.channel_id;
+    let root = &config.channel.storage;
+
+    if !matches!(storage, StorageBackend::Local(_)) {
+        return Err(ServiceError::BadRequest(
+            "Deduplication is only supported on local storage".to_string(),
+        ));
+    }
+
+    let mut report = DedupeReport {
+        dry_run,
+        ..Default::default()
+    };
+    let duplicates = handles::select_media_duplicates(conn, id).await?;
+    let media: HashMap<String, MediaHash> = duplicates
+        .iter()
+        .map(|m| (m.path.clone(), m.clone()))
+        .collect();
+
+    for group in group_duplicates(duplicates) {
+        let mut unchanged = vec![];
+
+        // skip files which are modified after the hash scan
+        for file in group.files {
+            if let (Ok(stamp), Some(entry)) =
+                (file_stamp(&root.join(&file)).await, media.get(&file))
+            {
+                if stamp == (entry.size, entry.modified) {
+                    unchanged.push(file);
+                }
+            }
+        }
+
+        let Some((keep, copies)) = unchanged.split_first() else {
+            continue;
+        };
+        let keep_path = root.join(keep);
+        let mut linked = false;
+
+        for copy in copies {
+            let copy_path = root.join(copy);
+
+            match same_inode(&keep_path, &copy_path).await {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(e) => {
+                    report.errors.push(format!("{copy}: {e}"));
+                    continue;
+                }
+            }
+
+            if !dry_run {
+                if let Err(e) = replace_with_link(&keep_path, &copy_path).await {
+                    error!(target: Target::file_mail(), channel = id; "Link <b><magenta>{copy}</></b> failed: {e}");
+                    report.errors.push(format!("{copy}: {e}"));
+                    continue;
+                }
+
+                if let Some(mut entry) = media.get(copy).cloned() {
+                    (entry.size, entry.modified) = file_stamp(&copy_path).await?;
+                    handles::upsert_media_hash(conn, &entry).await?;
+                }
+            }
+
+            linked = true;
+            report.linked += 1;
+            report.saved += group.size;
+        }
+
+        if linked {
+            report.groups += 1;
+        }
+    }
+
+    info!(target: Target::file_mail(), channel = id; "Deduplication done, {} file(s) linked, {} MB saved", report.linked, report.saved / 1_048_576);
+
+    Ok(report)
+}
+
+/// Group files with the same content together.
+pub fn group_duplicates(media: Vec<MediaHash>) -> Vec<DuplicateGroup> {
+    let mut groups: Vec<DuplicateGroup> = vec![];
+
+    for item in media {
+        match groups.last_mut() {
+            Some(group) if group.hash == item.hash => group.files.push(item.path),
+            _ => groups.push(DuplicateGroup {
+                hash: item.hash,
+                size: item.size,
+                files: vec![item.path],
+            }),
+        }
+    }
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_duplicate_hashes() {
+        let media = |path: &str, hash: &str| MediaHash {
+            path: path.to_string(),
+            hash: hash.to_string(),
+            ..Default::default()
+        };
+
+        let groups = group_duplicates(vec![
+            media("a/clip.mp4", "aaa"),
+            media("b/clip_copy.mp4", "aaa"),
+            media("c/spot.mp4", "bbb"),
+            media("d/spot.mp4", "bbb"),
+        ]);
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].files, vec!["a/clip.mp4", "b/clip_copy.mp4"]);
+        assert_eq!(groups[1].hash, "bbb");
+    }
+
+    #[cfg(target_family = "unix")]
+    #[tokio::test]
+    async fn link_duplicate() {
+        let dir = std::env::temp_dir().join(format!("ffplayout_dedupe_{}", std::process::id()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+
+        let keep = dir.join("promo.mp4");
+        let copy = dir.join("promo_copy.mp4");
+        tokio::fs::write(&keep, b"promo").await.unwrap();
+        tokio::fs::write(&copy, b"promo").await.unwrap();
+
+        assert!(!same_inode(&keep, &copy).await.unwrap());
+
+        replace_with_link(&keep, &copy).await.unwrap();
+
+        assert!(same_inode(&keep, &copy).await.unwrap());
+        assert_eq!(tokio::fs::read(&copy).await.unwrap(), b"promo");
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
@@ -0,0 +1,201 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use log::*;
+use tokio::sync::Mutex;
+
+use crate::db::{handles, models::MediaHash, DbPool};
+use crate::file::{
+    utils::{
+        hash::{file_stamp, media_hash},
+        validation::{is_image, probe_errors},
+    },
+    StorageBackend, WalkOptions,
+};
+use crate::player::{
+    controller::ChannelController,
+    utils::{include_file_extension, probe::MediaProbe},
+};
+use crate::utils::{
+    config::PlayoutConfig,
+    errors::ServiceError,
+    logging::Target,
+    time_machine::time_now,
+    webhook::{emit, WebhookEvent},
+};
+
+/// Streams which end more then this fraction before the container duration count as truncated.
+const TRUNCATE_RATIO: f64 = 0.9;
+
+/// Detect files where the container reports a longer duration than the streams contain,
+/// this happens mostly on interrupted copies.
+pub fn truncated(probe: &MediaProbe) -> Option<String> {
+    let duration = probe.format.duration.unwrap_or_default();
+
+    let shortest = probe
+        .video
+        .iter()
+        .filter_map(|v| v.duration)
+        .chain(probe.audio.iter().filter_map(|a| a.duration))
+        .reduce(f64::min)?;
+
+    if duration > 1.0 && shortest < duration * TRUNCATE_RATIO {
+        return Some(format!(
+            "Stream ends at {shortest:.2}s, but container has {duration:.2}s, file may be truncated"
+        ));
+    }
+
+    None
+}
+
+/// Walk through the channel storage and probe new and changed files.
+///
+/// Unreadable, empty or truncated files are stored with an error and reported by mail.
+/// Returns the files which are flagged in this run.
+pub async fn integrity_scan(
+    conn: &DbPool,
+    config: &PlayoutConfig,
+    storage: &StorageBackend,
+) -> Result<Vec<MediaHash>, ServiceError> {
+    let id = config.general.channel_id;
+    let mut flagged = vec![];
+
+    if !matches!(storage, StorageBackend::Local(_)) {
+        debug!(target: Target::file_mail(), channel = id; "Integrity scan is only supported on local storage");
+        return Ok(flagged);
+    }
+
+    let mut known: HashMap<String, MediaHash> = handles::select_media_hashes(conn, id)
+        .await?
+        .into_iter()
+        .map(|m| (m.path.clone(), m))
+        .collect();
+    let mut checked = 0;
+
+    for path in storage
+        .walk_dir(&config.channel.storage, &WalkOptions::default())
+        .await?
+    {
+        if !path.is_file() || !include_file_extension(config, &path) {
+            continue;
+        }
+
+        let relative = path
+            .strip_prefix(&config.channel.storage)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .to_string();
+        let entry = known.remove(&relative);
+
+        let Ok((size, modified)) = file_stamp(&path).await else {
+            continue;
+        };
+
+        if entry
+            .as_ref()
+            .is_some_and(|e| e.size == size && e.modified == modified && e.checked > 0)
+        {
+            continue;
+        }
+
+        let mut media = match media_hash(config, &path, None).await {
+            Ok(Some(media)) => media,
+            Ok(None) => continue,
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = id; "Read <b><magenta>{relative}</></b> failed: {e}");
+                continue;
+            }
+        };
+
+        let mut errors = vec![];
+
+        match MediaProbe::new(&path).await {
+            Ok(probe) => {
+                errors = probe_errors(&probe, is_image(&path), config.processing.audio_only);
+                errors.extend(truncated(&probe));
+                media.duration = probe.format.duration.unwrap_or_default();
+            }
+            Err(e) => errors.push(format!("File is not readable: {e}")),
+        }
+
+        media.checked = time_now(&None).timestamp();
+
+        if !errors.is_empty() {
+            let msg = errors.join(", ");
+
+            error!(target: Target::file_mail(), channel = id; "Integrity check failed for <b><magenta>{relative}</></b>: {msg}");
+            emit(
+                config,
+                WebhookEvent::StorageError,
+                serde_json::json!({"source": relative, "error": msg}),
+            );
+
+            media.error = Some(msg);
+            flagged.push(media.clone());
+        }
+
+        handles::upsert_media_hash(conn, &media).await?;
+        checked += 1;
+    }
+
+    for path in known.keys() {
+        handles::delete_media_hash(conn, id, path).await?;
+    }
+
+    info!(target: Target::file_mail(), channel = id; "Integrity scan done, {checked} file(s) checked, {} flagged", flagged.len());
+
+    Ok(flagged)
+}
+
+/// Run the integrity scan for all channels in the given interval (hours).
+pub async fn integrity_task(
+    conn: DbPool,
+    controllers: Arc<Mutex<ChannelController>>,
+    interval: u64,
+) {
+    let mut timer = tokio::time::interval(Duration::from_secs(interval.max(1) * 3600));
+
+    loop {
+        timer.tick().await;
+
+        let managers = controllers.lock().await.managers.clone();
+
+        for manager in managers {
+            let config = manager.config.lock().await.clone();
+            let storage = manager.storage.lock().await.clone();
+
+            if let Err(e) = integrity_scan(&conn, &config, &storage).await {
+                error!(target: Target::file_mail(), channel = config.general.channel_id; "Integrity scan failed: {e}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::utils::probe::{AudioStream, MediaFormat, VideoStream};
+
+    #[test]
+    fn detect_truncated_media() {
+        let mut probe = MediaProbe {
+            format: MediaFormat {
+                duration: Some(60.0),
+                ..Default::default()
+            },
+            audio: vec![AudioStream {
+                duration: Some(59.9),
+                ..Default::default()
+            }],
+            video: vec![VideoStream {
+                duration: Some(60.0),
+                ..Default::default()
+            }],
+        };
+
+        assert!(truncated(&probe).is_none());
+
+        probe.video[0].duration = Some(12.5);
+
+        assert!(truncated(&probe).is_some());
+    }
+}
@@ -0,0 +1,283 @@
+/// Loudness
+///
+/// First pass of the EBU R128 normalization: the loudness of the media files is measured
+/// in the background and cached in the media table. The playout uses the measurement
+/// for a linear loudnorm filter per clip, which not pumps like the single pass filter.
+use std::{collections::HashMap, path::Path, process::Stdio, sync::Arc, time::Duration};
+
+use log::*;
+use serde::Deserialize;
+use tokio::{process::Command, sync::Mutex};
+
+use crate::db::{handles, models::MediaHash, DbPool};
+use crate::file::{
+    utils::{
+        hash::{file_stamp, media_hash},
+        validation::is_image,
+    },
+    StorageBackend, WalkOptions,
+};
+use crate::player::{controller::ChannelController, utils::include_file_extension};
+use crate::utils::{config::PlayoutConfig, errors::ServiceError, logging::Target};
+
+/// Seconds between the runs of the measurement job.
+const LOUDNESS_INTERVAL: u64 = 600;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Loudness {
+    pub input_i: f64,
+    pub input_tp: f64,
+    pub input_lra: f64,
+    pub input_thresh: f64,
+}
+
+/// Values of the loudnorm json output, ffmpeg writes them as strings.
+#[derive(Deserialize)]
+struct LoudnormStats {
+    input_i: String,
+    input_tp: String,
+    input_lra: String,
+    input_thresh: String,
+}
+
+impl Loudness {
+    pub fn from_media(media: &MediaHash) -> Option<Self> {
+        Some(Self {
+            input_i: media.loudness_i?,
+            input_tp: media.loudness_tp?,
+            input_lra: media.loudness_lra?,
+            input_thresh: media.loudness_thresh?,
+        })
+    }
+
+    /// Loudnorm filter for the second pass.
+    pub fn filter(&self, config: &PlayoutConfig) -> String {
+        format!(
+            "loudnorm=I={}:TP={}:LRA={}:measured_I={}:measured_TP={}:measured_LRA={}:measured_thresh={}:linear=true,aresample=48000",
+            config.processing.loudnorm_i,
+            config.processing.loudnorm_tp,
+            config.processing.loudnorm_lra,
+            self.input_i,
+            self.input_tp,
+            self.input_lra,
+            self.input_thresh
+        )
+    }
+}
+
+/// Parse the json block of the loudnorm filter from the ffmpeg output.
+/// Silent files are measured with `-inf`, this is limited to the range of the filter options.
+fn parse_stats(output: &str) -> Option<Loudness> {
+    let start = output.rfind('{')?;
+    let end = start + output[start..].find('}')?;
+    let stats: LoudnormStats = serde_json::from_str(&output[start..=end]).ok()?;
+    let value =
+        |v: &str, min: f64, max: f64| v.trim().parse::<f64>().ok().map(|v| v.clamp(min, max));
+
+    Some(Loudness {
+        input_i: value(&stats.input_i, -99.0, 0.0)?,
+        input_tp: value(&stats.input_tp, -99.0, 99.0)?,
+        input_lra: value(&stats.input_lra, 0.0, 99.0)?,
+        input_thresh: value(&stats.input_thresh, -99.0, 0.0)?,
+    })
+}
+
+/// Run the first loudnorm pass over the audio track of the file.
+pub async fn measure(config: &PlayoutConfig, path: &Path) -> Result<Loudness, ServiceError> {
+    let filter = format!(
+        "loudnorm=I={}:TP={}:LRA={}:print_format=json",
+        config.processing.loudnorm_i, config.processing.loudnorm_tp, config.processing.loudnorm_lra
+    );
+    let output = Command::new("ffmpeg")
+        .args(["-hide_banner", "-nostats", "-nostdin", "-i"])
+        .arg(path)
+        .args([
+            "-map",
+            &format!("0:a:{}", config.processing.audio_track_index.max(0)),
+            "-af",
+            &filter,
+            "-f",
+            "null",
+            "-",
+        ])
+        .stdout(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await?;
+
+    parse_stats(&String::from_utf8_lossy(&output.stderr)).ok_or_else(|| {
+        ServiceError::Conflict("No loudness measurement in ffmpeg output".to_string())
+    })
+}
+
+/// Measurement of a clip in the channel storage, when the normalization is on.
+pub async fn clip_loudness(
+    conn: &DbPool,
+    config: &PlayoutConfig,
+    source: &str,
+) -> Option<Loudness> {
+    if !config.processing.loudnorm {
+        return None;
+    }
+
+    let relative = Path::new(source)
+        .strip_prefix(&config.channel.storage)
+        .ok()?;
+    let media =
+        handles::select_media_hash(conn, config.general.channel_id, &relative.to_string_lossy())
+            .await
+            .ok()??;
+
+    let loudness = Loudness::from_media(&media);
+
+    if loudness.is_none() {
+        debug!(target: Target::file_mail(), channel = config.general.channel_id; "No loudness measurement for <b><magenta>{source}</></b>, yet");
+    }
+
+    loudness
+}
+
+/// Measure all new and changed files of the channel storage.
+/// Returns the number of measured files.
+pub async fn loudness_scan(
+    conn: &DbPool,
+    config: &PlayoutConfig,
+    storage: &StorageBackend,
+) -> Result<usize, ServiceError> {
+    let id = config.general.channel_id;
+    let mut measured = 0;
+
+    if !matches!(storage, StorageBackend::Local(_)) {
+        debug!(target: Target::file_mail(), channel = id; "Loudness measurement is only supported on local storage");
+        return Ok(measured);
+    }
+
+    let known: HashMap<String, MediaHash> = handles::select_media_hashes(conn, id)
+        .await?
+        .into_iter()
+        .map(|m| (m.path.clone(), m))
+        .collect();
+
+    for path in storage
+        .walk_dir(&config.channel.storage, &WalkOptions::default())
+        .await?
+    {
+        if !path.is_file() || !include_file_extension(config, &path) || is_image(&path) {
+            continue;
+        }
+
+        let relative = path
+            .strip_prefix(&config.channel.storage)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .to_string();
+
+        let Ok((size, modified)) = file_stamp(&path).await else {
+            continue;
+        };
+
+        let mut media = match known.get(&relative) {
+            Some(entry) if entry.size == size && entry.modified == modified => {
+                if entry.loudness_i.is_some() {
+                    continue;
+                }
+
+                entry.clone()
+            }
+            _ => match media_hash(config, &path, None).await {
+                Ok(Some(media)) => media,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!(target: Target::file_mail(), channel = id; "Read <b><magenta>{relative}</></b> failed: {e}");
+                    continue;
+                }
+            },
+        };
+
+        match measure(config, &path).await {
+            Ok(loudness) => {
+                media.loudness_i = Some(loudness.input_i);
+                media.loudness_tp = Some(loudness.input_tp);
+                media.loudness_lra = Some(loudness.input_lra);
+                media.loudness_thresh = Some(loudness.input_thresh);
+
+                handles::upsert_media_hash(conn, &media).await?;
+                measured += 1;
+            }
+            Err(e) => {
+                debug!(target: Target::file_mail(), channel = id; "Loudness measurement of <b><magenta>{relative}</></b> failed: {e}");
+            }
+        }
+    }
+
+    if measured > 0 {
+        info!(target: Target::file_mail(), channel = id; "Loudness of {measured} file(s) measured");
+    }
+
+    Ok(measured)
+}
+
+/// Measure the new files of all channels with loudness normalization.
+pub async fn loudness_task(conn: DbPool, controllers: Arc<Mutex<ChannelController>>) {
+    let mut timer = tokio::time::interval(Duration::from_secs(LOUDNESS_INTERVAL));
+
+    loop {
+        timer.tick().await;
+
+        let managers = controllers.lock().await.managers.clone();
+
+        for manager in managers {
+            let config = manager.config.lock().await.clone();
+
+            if !config.processing.loudnorm {
+                continue;
+            }
+
+            let storage = manager.storage.lock().await.clone();
+
+            if let Err(e) = loudness_scan(&conn, &config, &storage).await {
+                error!(target: Target::file_mail(), channel = config.general.channel_id; "Loudness measurement failed: {e}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_loudnorm_output() {
+        let output = r#"[Parsed_loudnorm_0 @ 0x5581b0b9e8c0]
+{
+	"input_i" : "-27.61",
+	"input_tp" : "-4.47",
+	"input_lra" : "18.06",
+	"input_thresh" : "-39.20",
+	"output_i" : "-16.58",
+	"output_tp" : "-1.50",
+	"output_lra" : "14.78",
+	"output_thresh" : "-27.71",
+	"normalization_type" : "dynamic",
+	"target_offset" : "0.58"
+}
+"#;
+
+        assert_eq!(
+            parse_stats(output),
+            Some(Loudness {
+                input_i: -27.61,
+                input_tp: -4.47,
+                input_lra: 18.06,
+                input_thresh: -39.2,
+            })
+        );
+
+        let silence = output
+            .replace("\"-27.61\"", "\"-inf\"")
+            .replace("\"-39.20\"", "\"-inf\"");
+
+        assert_eq!(parse_stats(&silence).map(|l| l.input_i), Some(-99.0));
+        assert_eq!(parse_stats("no stats"), None);
+    }
+}
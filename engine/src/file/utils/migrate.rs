@@ -0,0 +1,420 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::Ordering,
+};
+
+use async_walkdir::WalkDir;
+use log::*;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tokio_stream::StreamExt;
+
+use crate::db::{handles, models::StorageMigration, DbPool};
+use crate::file::{
+    init_storage, s3::s3_parse_string, select_storage_type, utils::media_map::MediaMap,
+    StorageBackend, StorageType, WalkOptions,
+};
+use crate::player::{
+    controller::ChannelManager,
+    utils::{json_reader, json_writer, JsonPlaylist},
+};
+use crate::utils::{
+    config::{get_config, PlayoutConfig},
+    errors::ServiceError,
+    logging::Target,
+    playlist::{store_playlist, stored_playlist},
+};
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct MigrateObj {
+    pub target: String,
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub switch: bool,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct MigrationStatus {
+    pub running: bool,
+    pub total: usize,
+    pub copied: usize,
+    pub failed: Vec<StorageMigration>,
+    pub playlists: usize,
+    pub switched: bool,
+}
+
+/// Storage relative path of an entry from `walk_dir`, `None` for folders.
+pub(crate) fn relative_path(storage: &StorageBackend, root: &Path, path: &Path) -> Option<String> {
+    match storage {
+        StorageBackend::Local(_) => path.is_file().then(|| {
+            path.strip_prefix(root)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .to_string()
+        }),
+        StorageBackend::S3(_) => {
+            let key = path.to_string_lossy();
+            (!key.ends_with('/')).then(|| key.to_string())
+        }
+    }
+}
+
+/// Storage relative path of a playlist source, `None` when the source is outside of the storage.
+fn source_relative(storage: &StorageBackend, root: &Path, source: &str) -> Option<String> {
+    let interpreted = storage.interpreted_file_path(source);
+
+    match storage {
+        StorageBackend::Local(_) => Path::new(&interpreted)
+            .strip_prefix(root)
+            .ok()
+            .map(|p| p.to_string_lossy().to_string()),
+        StorageBackend::S3(_) => Some(interpreted.trim_start_matches('/').to_string()),
+    }
+}
+
+/// Playlist source of a storage relative path.
+fn storage_source(storage: &StorageBackend, root: &Path, relative: &str) -> String {
+    match storage {
+        StorageBackend::Local(_) => {
+            storage.sanitized_file_path(&root.join(relative).to_string_lossy())
+        }
+        StorageBackend::S3(_) => storage.sanitized_file_path(&format!("/{relative}")),
+    }
+}
+
+/// Key of a storage relative path in the `MediaMap`.
+fn media_key(storage: &StorageBackend, root: &Path, relative: &str) -> String {
+    match storage {
+        StorageBackend::Local(_) => root.join(relative).to_string_lossy().to_string(),
+        StorageBackend::S3(_) => relative.to_string(),
+    }
+}
+
+/// Copy one file between the backends, objects from S3 are buffered in a temporary file.
+pub(crate) async fn copy_file(
+    source: &StorageBackend,
+    target: &StorageBackend,
+    root: &Path,
+    relative: &str,
+) -> Result<i64, ServiceError> {
+    match source {
+        StorageBackend::Local(_) => {
+            let path = root.join(relative);
+            target.import_file(&path, relative).await?;
+
+            Ok(fs::metadata(path).await?.len() as i64)
+        }
+        StorageBackend::S3(_) => {
+            let name = Path::new(relative).file_name().unwrap_or_default();
+            let mut temp = env::temp_dir().join(format!("ffplayout_migrate_{}_", process::id()));
+            temp.as_mut_os_string().push(name);
+
+            source.export_file(relative, &temp).await?;
+
+            let size = fs::metadata(&temp).await?.len() as i64;
+            let result = target.import_file(&temp, relative).await;
+
+            fs::remove_file(&temp).await?;
+
+            result.map(|()| size)
+        }
+    }
+}
+
+/// Point the sources of the playlist, which are migrated, to the new storage.
+fn rewrite_sources(
+    config: &PlayoutConfig,
+    playlist: &mut JsonPlaylist,
+    source: &StorageBackend,
+    target: &StorageBackend,
+    target_root: &Path,
+    migrated: &HashSet<String>,
+) -> bool {
+    let mut changed = false;
+
+    for item in &mut playlist.program {
+        if let Some(relative) = source_relative(source, &config.channel.storage, &item.source)
+            .filter(|r| migrated.contains(r))
+        {
+            item.source = storage_source(target, target_root, &relative);
+            changed = true;
+        }
+    }
+
+    changed
+}
+
+/// Point all playlist sources, which are migrated, to the new storage.
+async fn rewrite_playlists(
+    conn: &DbPool,
+    config: &PlayoutConfig,
+    source: &StorageBackend,
+    target: &StorageBackend,
+    target_root: &Path,
+    migrated: &HashSet<String>,
+    dry_run: bool,
+) -> Result<usize, ServiceError> {
+    let mut count = 0;
+
+    if config.playlist.database {
+        let id = config.general.channel_id;
+
+        for date in handles::select_playlist_dates(conn, id).await? {
+            let mut playlist = stored_playlist(conn, id, &date).await?;
+
+            if rewrite_sources(config, &mut playlist, source, target, target_root, migrated) {
+                count += 1;
+
+                if !dry_run {
+                    store_playlist(conn, id, 0, &playlist).await?;
+                }
+            }
+        }
+
+        return Ok(count);
+    }
+
+    let mut entries = WalkDir::new(&config.channel.playlists);
+
+    while let Some(Ok(entry)) = entries.next().await {
+        let path = entry.path();
+
+        if path.extension().and_then(OsStr::to_str) != Some("json") {
+            continue;
+        }
+
+        let Ok(mut playlist) = json_reader(&path).await else {
+            continue;
+        };
+
+        if rewrite_sources(config, &mut playlist, source, target, target_root, migrated) {
+            count += 1;
+
+            if !dry_run {
+                json_writer(&path, playlist).await?;
+            }
+        }
+    }
+
+    Ok(count)
+}
+
+/// Initialize the backend of a migration or mirror target.
+pub(crate) async fn target_storage(
+    config: &PlayoutConfig,
+    target: &str,
+) -> Result<StorageBackend, ServiceError> {
+    let root = PathBuf::from(target);
+
+    if target.trim().is_empty() || root == config.channel.storage {
+        return Err(ServiceError::BadRequest(
+            "Target must be a different storage".to_string(),
+        ));
+    }
+
+    let storage_type = select_storage_type(&root);
+
+    // storage initialization panics on invalid targets, so check them before
+    match storage_type {
+        StorageType::Local => fs::create_dir_all(&root).await?,
+        StorageType::S3 => {
+            s3_parse_string(target)
+                .map_err(|e| ServiceError::BadRequest(format!("Invalid S3 target: {e}")))?;
+        }
+    }
+
+    let storage = init_storage(storage_type, root, config.storage.extensions.clone()).await;
+
+    if let StorageBackend::S3(s3) = &storage {
+        if let Some(e) = &s3.error {
+            return Err(ServiceError::BadRequest(format!("Target not usable: {e}")));
+        }
+    }
+
+    Ok(storage)
+}
+
+async fn run_migration(
+    conn: &DbPool,
+    manager: &ChannelManager,
+    obj: &MigrateObj,
+    duration: &MediaMap,
+) -> Result<MigrationStatus, ServiceError> {
+    let config = manager.config.lock().await.clone();
+    let source = manager.storage.lock().await.clone();
+    let id = config.general.channel_id;
+    let root = &config.channel.storage;
+    let target_root = PathBuf::from(&obj.target);
+    let target = target_storage(&config, &obj.target).await?;
+
+    handles::delete_storage_migration(conn, id, &obj.target).await?;
+
+    let mut known: HashMap<String, StorageMigration> = handles::select_storage_migration(conn, id)
+        .await?
+        .into_iter()
+        .map(|m| (m.path.clone(), m))
+        .collect();
+    let mut status = MigrationStatus::default();
+    let mut pending = vec![];
+    let mut migrated = HashSet::new();
+
+    for path in source.walk_dir(root, &WalkOptions::default()).await? {
+        let Some(relative) = relative_path(&source, root, &path) else {
+            continue;
+        };
+
+        status.total += 1;
+
+        match known.remove(&relative) {
+            Some(item) if item.done => {
+                status.copied += 1;
+                migrated.insert(relative);
+            }
+            Some(item) => pending.push(item),
+            None => {
+                let item = StorageMigration {
+                    channel_id: id,
+                    target: obj.target.clone(),
+                    path: relative,
+                    ..Default::default()
+                };
+
+                if !obj.dry_run {
+                    handles::upsert_storage_migration(conn, &item).await?;
+                }
+
+                pending.push(item);
+            }
+        }
+    }
+
+    info!(target: Target::file_mail(), channel = id; "Migrate storage: <yellow>{}</> of <yellow>{}</> file(s) left", pending.len(), status.total);
+
+    for mut item in pending {
+        if obj.dry_run {
+            migrated.insert(item.path);
+            continue;
+        }
+
+        match copy_file(&source, &target, root, &item.path).await {
+            Ok(size) => {
+                item.size = size;
+                item.done = true;
+                item.error = None;
+                status.copied += 1;
+                migrated.insert(item.path.clone());
+            }
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = id; "Migrate <b><magenta>{}</></b> failed: {e}", item.path);
+
+                item.error = Some(e.to_string());
+                status.failed.push(item.clone());
+            }
+        }
+
+        handles::upsert_storage_migration(conn, &item).await?;
+    }
+
+    if obj.switch && status.failed.is_empty() {
+        status.playlists = rewrite_playlists(
+            conn,
+            &config,
+            &source,
+            &target,
+            &target_root,
+            &migrated,
+            obj.dry_run,
+        )
+        .await?;
+
+        if !obj.dry_run {
+            for relative in &migrated {
+                duration
+                    .update_obj(
+                        &media_key(&source, root, relative),
+                        &media_key(&target, &target_root, relative),
+                    )
+                    .await?;
+            }
+
+            let mut channel = handles::select_channel(conn, &id).await?;
+            channel.storage.clone_from(&obj.target);
+
+            handles::update_channel(conn, id, channel.clone()).await?;
+            manager.update_config(get_config(conn, id).await?).await;
+            manager.clone().update_channel(&channel).await;
+            manager.start_mirror().await;
+
+            status.switched = true;
+
+            info!(target: Target::file_mail(), channel = id; "Channel storage switched, <yellow>{}</> playlist(s) updated", status.playlists);
+        }
+    }
+
+    Ok(status)
+}
+
+/// Copy the whole channel storage to another backend.
+///
+/// The progress is stored in the database, so an interrupted migration
+/// continues with the missing files, when it is started again with the same target.
+/// With `switch` the playlist sources and `MediaMap` keys are rewritten
+/// and the channel uses the new storage afterwards, but only when all files are copied.
+pub async fn migrate_storage(
+    conn: &DbPool,
+    manager: &ChannelManager,
+    obj: &MigrateObj,
+    duration: &MediaMap,
+) -> Result<MigrationStatus, ServiceError> {
+    if !obj.dry_run && manager.is_migrating.swap(true, Ordering::SeqCst) {
+        return Err(ServiceError::Conflict(
+            "Storage migration is already running".to_string(),
+        ));
+    }
+
+    let result = run_migration(conn, manager, obj, duration).await;
+
+    if !obj.dry_run {
+        manager.is_migrating.store(false, Ordering::SeqCst);
+    }
+
+    result
+}
+
+/// Progress of the current or last migration.
+pub async fn migration_status(
+    conn: &DbPool,
+    manager: &ChannelManager,
+) -> Result<MigrationStatus, ServiceError> {
+    let items = handles::select_storage_migration(conn, manager.id).await?;
+
+    Ok(MigrationStatus {
+        running: manager.is_migrating.load(Ordering::SeqCst),
+        total: items.len(),
+        copied: items.iter().filter(|i| i.done).count(),
+        failed: items.into_iter().filter(|i| i.error.is_some()).collect(),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn local_playlist_source() {
+        let root = env::temp_dir().join("ffplayout_migrate_test");
+        let storage = init_storage(StorageType::Local, root.clone(), vec![]).await;
+        let source = storage_source(&storage, &root, "folder/clip.mp4");
+
+        assert_eq!(
+            source_relative(&storage, &root, &source).as_deref(),
+            Some("folder/clip.mp4")
+        );
+    }
+}
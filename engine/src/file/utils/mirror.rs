@@ -0,0 +1,271 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{mpsc::channel, Arc},
+    time::Duration,
+};
+
+use actix_web::web;
+use log::*;
+use notify::{
+    event::ModifyKind,
+    EventKind::{Create, Modify, Remove},
+    RecursiveMode,
+};
+use notify_debouncer_full::new_debouncer;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::Mutex,
+    time::{sleep, Instant},
+};
+
+use crate::file::{
+    init_storage, select_storage_type,
+    utils::{
+        media_map::{MediaMap, SharedMediaMap},
+        migrate::{copy_file, relative_path, target_storage},
+    },
+    StorageBackend, WalkOptions,
+};
+use crate::utils::{
+    config::PlayoutConfig, errors::ServiceError, logging::Target, time_machine::time_now,
+};
+
+/// Full comparison of primary and mirror storage, to catch changes which the watcher missed.
+const RECONCILE_INTERVAL: Duration = Duration::from_secs(600);
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct MirrorStatus {
+    pub enabled: bool,
+    pub synced: usize,
+    pub pending: usize,
+    /// Seconds since the oldest change, which is not mirrored yet.
+    pub lag: i64,
+    pub last_sync: Option<i64>,
+    pub last_reconcile: Option<i64>,
+    pub failed: Vec<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MirrorOp {
+    Copy,
+    Delete,
+}
+
+struct Mirror {
+    id: i32,
+    source: StorageBackend,
+    target: StorageBackend,
+    root: PathBuf,
+    target_root: PathBuf,
+    cache: web::Data<SharedMediaMap>,
+    status: Arc<Mutex<MirrorStatus>>,
+    /// Pending operations by storage relative path, with the time of the change.
+    queue: HashMap<String, (MirrorOp, i64)>,
+    rescan: bool,
+}
+
+impl Mirror {
+    fn push(&mut self, relative: String, op: MirrorOp) {
+        let now = time_now(&None).timestamp();
+        let entry = self.queue.entry(relative).or_insert((op, now));
+
+        entry.0 = op;
+    }
+
+    /// Translate a watcher event path to a mirror operation.
+    fn push_event(&mut self, path: &Path) {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return;
+        };
+        let relative = relative.to_string_lossy().to_string();
+
+        if path.is_file() {
+            self.push(relative, MirrorOp::Copy);
+        } else if path.is_dir() {
+            // moved in folders have no events for their content
+            self.rescan = true;
+        } else if !path.exists() {
+            self.push(relative, MirrorOp::Delete);
+        }
+    }
+
+    async fn files(storage: &StorageBackend, root: &Path) -> Result<HashSet<String>, ServiceError> {
+        Ok(storage
+            .walk_dir(root, &WalkOptions::default())
+            .await?
+            .iter()
+            .filter_map(|p| relative_path(storage, root, p))
+            .collect())
+    }
+
+    /// Queue all differences between primary and mirror.
+    async fn reconcile(&mut self) -> Result<(), ServiceError> {
+        let source = Self::files(&self.source, &self.root).await?;
+        let target = Self::files(&self.target, &self.target_root).await?;
+
+        for relative in source.difference(&target) {
+            self.push(relative.clone(), MirrorOp::Copy);
+        }
+
+        for relative in target.difference(&source) {
+            self.push(relative.clone(), MirrorOp::Delete);
+        }
+
+        self.status.lock().await.last_reconcile = Some(time_now(&None).timestamp());
+
+        Ok(())
+    }
+
+    async fn delete(&self, relative: &str) -> Result<(), ServiceError> {
+        if matches!(self.target, StorageBackend::Local(_))
+            && !self.target_root.join(relative).exists()
+        {
+            return Ok(());
+        }
+
+        self.target.remove(relative, self.cache.clone(), true).await
+    }
+
+    async fn apply(&mut self) {
+        let mut queue: Vec<(String, (MirrorOp, i64))> = self.queue.drain().collect();
+        queue.sort_by_key(|(_, (_, time))| *time);
+
+        for (relative, (op, _)) in queue {
+            let result = match op {
+                MirrorOp::Copy => copy_file(&self.source, &self.target, &self.root, &relative)
+                    .await
+                    .map(|_| ()),
+                MirrorOp::Delete => self.delete(&relative).await,
+            };
+
+            let mut status = self.status.lock().await;
+
+            match result {
+                Ok(()) => {
+                    status.synced += 1;
+                    status.last_sync = Some(time_now(&None).timestamp());
+                    status.failed.retain(|f| f != &relative);
+                }
+                Err(e) => {
+                    // copies are retried on the next reconcile
+                    error!(target: Target::file_mail(), channel = self.id; "Mirror <b><magenta>{relative}</></b> failed: {e}");
+
+                    if !status.failed.contains(&relative) {
+                        status.failed.push(relative);
+                    }
+                }
+            }
+        }
+
+        let mut status = self.status.lock().await;
+        status.pending = self.queue.len();
+        status.lag = self
+            .queue
+            .values()
+            .map(|(_, time)| *time)
+            .min()
+            .map_or(0, |time| time_now(&None).timestamp() - time);
+    }
+}
+
+/// Keep the mirror target in sync with the channel storage.
+///
+/// Changes on local storage are mirrored from the file watcher events,
+/// additionally both sides are compared completely every [`RECONCILE_INTERVAL`].
+pub async fn mirror_task(config: PlayoutConfig, status: Arc<Mutex<MirrorStatus>>) {
+    let id = config.general.channel_id;
+    let root = config.channel.storage.clone();
+
+    *status.lock().await = MirrorStatus {
+        enabled: true,
+        ..Default::default()
+    };
+
+    let target = match target_storage(&config, &config.storage.mirror).await {
+        Ok(target) => target,
+        Err(e) => {
+            error!(target: Target::file_mail(), channel = id; "Storage mirror not usable: {e}");
+            status.lock().await.error = Some(e.to_string());
+            return;
+        }
+    };
+
+    // own storage instance, clones would share the folder watcher of the channel
+    let source = init_storage(
+        select_storage_type(&root),
+        root.clone(),
+        config.storage.extensions.clone(),
+    )
+    .await;
+    let (tx, rx) = channel();
+    let mut _debouncer = None;
+
+    if matches!(source, StorageBackend::Local(_)) {
+        match new_debouncer(Duration::from_secs(3), None, tx) {
+            Ok(mut debouncer) => match debouncer.watch(&root, RecursiveMode::Recursive) {
+                Ok(()) => _debouncer = Some(debouncer),
+                Err(e) => {
+                    error!(target: Target::file_mail(), channel = id; "Mirror can't watch storage: {e}");
+                }
+            },
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = id; "Mirror can't watch storage: {e}");
+            }
+        }
+    }
+
+    let mut mirror = Mirror {
+        id,
+        source,
+        target,
+        root: root.canonicalize().unwrap_or(root),
+        target_root: PathBuf::from(&config.storage.mirror),
+        cache: web::Data::new(Arc::new(MediaMap::create(0))),
+        status: status.clone(),
+        queue: HashMap::new(),
+        rescan: true,
+    };
+
+    info!(target: Target::file_mail(), channel = id; "Mirror storage to second backend");
+
+    let mut reconciled = Instant::now();
+
+    loop {
+        while let Ok(result) = rx.try_recv() {
+            match result {
+                Ok(events) => {
+                    for event in events.iter().filter(|e| {
+                        matches!(e.kind, Create(_) | Remove(_) | Modify(_))
+                            && !matches!(e.kind, Modify(ModifyKind::Metadata(_)))
+                    }) {
+                        for path in &event.paths {
+                            mirror.push_event(path);
+                        }
+                    }
+                }
+                Err(errors) => errors.iter().for_each(
+                    |error| error!(target: Target::file_mail(), channel = id; "{error:?}"),
+                ),
+            }
+        }
+
+        if mirror.rescan || reconciled.elapsed() >= RECONCILE_INTERVAL {
+            match mirror.reconcile().await {
+                Ok(()) => status.lock().await.error = None,
+                Err(e) => {
+                    error!(target: Target::file_mail(), channel = id; "Mirror reconcile failed: {e}");
+                    status.lock().await.error = Some(e.to_string());
+                }
+            }
+
+            mirror.rescan = false;
+            reconciled = Instant::now();
+        }
+
+        mirror.apply().await;
+
+        sleep(Duration::from_secs(3)).await;
+    }
+}
@@ -1,5 +1,12 @@
+pub mod audit;
 pub mod filler;
+pub mod hash;
+pub mod integrity;
+pub mod loudness;
 pub mod media_map;
+pub mod migrate;
+pub mod mirror;
+pub mod validation;
 pub mod watcher;
 
 pub const ABS_PATH_INDICATOR: &str = "[abs]:";
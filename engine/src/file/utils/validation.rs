@@ -0,0 +1,273 @@
+use std::path::{Component, Path};
+
+use actix_web::web;
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::UploadLimit;
+use crate::file::{utils::media_map::SharedMediaMap, MoveObject, PathObject, StorageBackend};
+use crate::player::utils::probe::MediaProbe;
+use crate::utils::{
+    config::{PlayoutConfig, IMAGE_FORMAT},
+    errors::ServiceError,
+    logging::Target,
+};
+
+/// Folder, relative to the channel storage, where broken uploads are moved to.
+pub const QUARANTINE_FOLDER: &str = "00-quarantine";
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ValidationReport {
+    pub file: String,
+    pub valid: bool,
+    pub duration: f64,
+    pub video_streams: usize,
+    pub audio_streams: usize,
+    pub errors: Vec<String>,
+    pub quarantine: Option<String>,
+}
+
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| IMAGE_FORMAT.contains(&e.to_lowercase().as_str()))
+}
+
+/// Collect all problems of a probed file, which would break playout later.
+pub fn probe_errors(probe: &MediaProbe, is_image: bool, audio_only: bool) -> Vec<String> {
+    let mut errors = vec![];
+
+    if probe.video.is_empty() && (is_image || !audio_only) {
+        errors.push("No video stream found".to_string());
+    }
+
+    if !is_image {
+        if probe.audio.is_empty() {
+            errors.push("No audio stream found".to_string());
+        }
+
+        if probe.format.duration.unwrap_or_default() <= 0.0 {
+            errors.push("Duration is zero or unknown".to_string());
+        }
+    }
+
+    errors
+}
+
+fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split([';', ','])
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+/// Check if the upload limit allows the target folder, which is relative to the channel storage.
+pub fn upload_folder_allowed(limit: &UploadLimit, username: &str, path: &Path) -> bool {
+    let folders: Vec<String> = split_list(&limit.folders)
+        .map(|f| f.replace("{username}", username))
+        .collect();
+
+    if folders.is_empty() {
+        return true;
+    }
+
+    let path = Path::new(path.to_str().unwrap_or_default().trim_start_matches('/'));
+
+    if path.components().any(|c| c == Component::ParentDir) {
+        return false;
+    }
+
+    folders
+        .iter()
+        .any(|f| path.starts_with(f.trim_matches('/')))
+}
+
+/// Violations of the upload limit by an uploaded file, size is in bytes.
+pub fn upload_limit_errors(
+    limit: &UploadLimit,
+    extensions: &[String],
+    file: &str,
+    size: Option<u64>,
+) -> Vec<String> {
+    let mut errors = vec![];
+
+    if limit.max_size > 0 && size.is_some_and(|s| s > limit.max_size as u64 * 1_048_576) {
+        errors.push(format!("File is larger then {} MB", limit.max_size));
+    }
+
+    if !limit.extensions.trim().is_empty() {
+        let ext = Path::new(file)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+
+        if !extensions
+            .iter()
+            .map(String::as_str)
+            .chain(split_list(&limit.extensions))
+            .any(|e| e.eq_ignore_ascii_case(&ext))
+        {
+            errors.push(format!("Extension '{ext}' is not allowed"));
+        }
+    }
+
+    errors
+}
+
+/// Probe uploaded files and reject or quarantine the ones which are not playable.
+/// Files which break the upload limit of the role are removed without probing.
+pub async fn validate_uploads(
+    storage: &StorageBackend,
+    config: &PlayoutConfig,
+    duration: web::Data<SharedMediaMap>,
+    files: Vec<String>,
+    limit: &UploadLimit,
+) -> Result<Vec<ValidationReport>, ServiceError> {
+    let id = config.general.channel_id;
+    let mut reports = vec![];
+
+    for file in files {
+        let mut report = ValidationReport {
+            file: file.clone(),
+            ..Default::default()
+        };
+        let is_image = is_image(Path::new(&file));
+        let input = storage.fetch_file_path(&file).await?;
+        let size = match storage {
+            StorageBackend::Local(_) => tokio::fs::metadata(&input).await.ok().map(|m| m.len()),
+            StorageBackend::S3(_) => None,
+        };
+
+        report.errors = upload_limit_errors(limit, storage.extensions(), &file, size);
+
+        if !report.errors.is_empty() {
+            warn!(target: Target::file_mail(), channel = id; "Uploaded file <b><magenta>{file}</></b> is not allowed: {}", report.errors.join(", "));
+
+            storage.remove(&file, duration.clone(), false).await?;
+            reports.push(report);
+            continue;
+        }
+
+        match MediaProbe::new(&input).await {
+            Ok(probe) => {
+                report.errors = probe_errors(&probe, is_image, config.processing.audio_only);
+                report.video_streams = probe.video.len();
+                report.audio_streams = probe.audio.len();
+                report.duration = probe.format_duration();
+            }
+            Err(e) => report.errors.push(format!("File is not readable: {e}")),
+        }
+
+        report.valid = report.errors.is_empty();
+
+        if !report.valid {
+            warn!(target: Target::file_mail(), channel = id; "Uploaded file <b><magenta>{file}</></b> is invalid: {}", report.errors.join(", "));
+
+            if config.storage.quarantine {
+                report.quarantine = quarantine(storage, &file, duration.clone()).await;
+            }
+
+            if report.quarantine.is_none() {
+                storage.remove(&file, duration.clone(), false).await?;
+            }
+        }
+
+        reports.push(report);
+    }
+
+    Ok(reports)
+}
+
+async fn quarantine(
+    storage: &StorageBackend,
+    file: &str,
+    duration: web::Data<SharedMediaMap>,
+) -> Option<String> {
+    let file_name = Path::new(file).file_name()?.to_string_lossy();
+    let target = format!("{QUARANTINE_FOLDER}/{file_name}");
+    let folder = PathObject::new(format!("{QUARANTINE_FOLDER}/"), None);
+
+    if let Err(e) = storage.mkdir(&folder).await {
+        error!("Create quarantine folder failed: {e}");
+        return None;
+    }
+
+    let move_object = MoveObject {
+        source: file.to_string(),
+        target: target.clone(),
+        force: false,
+    };
+
+    match storage.rename(&move_object, duration).await {
+        Ok(_) => Some(target),
+        Err(e) => {
+            error!("Move <b><magenta>{file}</></b> to quarantine failed: {e}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::utils::probe::{AudioStream, MediaFormat, VideoStream};
+
+    #[test]
+    fn upload_limit_checks() {
+        let limit = UploadLimit {
+            max_size: 1,
+            extensions: "wav".to_string(),
+            folders: "submissions/{username}".to_string(),
+            ..Default::default()
+        };
+        let extensions = vec!["mp4".to_string()];
+
+        assert!(upload_folder_allowed(
+            &limit,
+            "anna",
+            Path::new("/submissions/anna/promo")
+        ));
+        assert!(!upload_folder_allowed(
+            &limit,
+            "anna",
+            Path::new("submissions/ben")
+        ));
+        assert!(!upload_folder_allowed(
+            &limit,
+            "anna",
+            Path::new("submissions/anna/../ben")
+        ));
+        assert!(upload_limit_errors(&limit, &extensions, "clip.MP4", Some(1024)).is_empty());
+        assert!(upload_limit_errors(&limit, &extensions, "jingle.wav", None).is_empty());
+        assert_eq!(
+            upload_limit_errors(&limit, &extensions, "doc.pdf", Some(2_000_000)).len(),
+            2
+        );
+    }
+
+    #[test]
+    fn probe_errors_detection() {
+        let mut probe = MediaProbe {
+            format: MediaFormat {
+                duration: Some(10.0),
+                ..Default::default()
+            },
+            audio: vec![AudioStream::default()],
+            video: vec![VideoStream::default()],
+        };
+
+        assert!(probe_errors(&probe, false, false).is_empty());
+
+        probe.audio.clear();
+        probe.format.duration = Some(0.0);
+
+        assert_eq!(probe_errors(&probe, false, false).len(), 2);
+        assert!(probe_errors(&probe, true, false).is_empty());
+
+        probe.video.clear();
+
+        assert_eq!(probe_errors(&probe, true, true).len(), 1);
+        assert_eq!(probe_errors(&probe, false, true).len(), 2);
+    }
+}
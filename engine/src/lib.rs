@@ -1,6 +1,10 @@
 use std::sync::{Arc, LazyLock, Mutex};
 
-use actix_web::{dev::ServiceRequest, Error, HttpMessage};
+use actix_web::{
+    dev::ServiceRequest,
+    error::{ErrorForbidden, ErrorUnauthorized},
+    web, Error, HttpMessage,
+};
 use actix_web_grants::authorities::AttachAuthorities;
 use actix_web_httpauth::extractors::bearer::BearerAuth;
 use clap::Parser;
@@ -15,9 +19,14 @@ pub mod sse;
 pub mod utils;
 
 use api::auth;
-use db::models::UserMeta;
+use db::{
+    handles,
+    models::{ChannelPermission, Role, UserMeta},
+    DbPool,
+};
 use utils::advanced_config::AdvancedConfig;
 use utils::args_parse::Args;
+use utils::hwaccel::{self, HwAccel};
 
 pub static ARGS: LazyLock<Args> = LazyLock::new(Args::parse);
 pub static DISKS: LazyLock<Arc<Mutex<Disks>>> =
@@ -26,18 +35,104 @@ pub static NETWORKS: LazyLock<Arc<Mutex<Networks>>> =
     LazyLock::new(|| Arc::new(Mutex::new(Networks::new_with_refreshed_list())));
 pub static SYS: LazyLock<Arc<Mutex<System>>> =
     LazyLock::new(|| Arc::new(Mutex::new(System::new_all())));
+pub static HW_ACCEL: LazyLock<Vec<HwAccel>> = LazyLock::new(hwaccel::detect);
+
+/// Check an API token and its scope, the request gets the role `user`
+/// and the channels of the token, which its creator still has access to.
+/// On these channels the token can't do more than its creator.
+async fn api_token_user(req: &ServiceRequest, token: &str) -> Result<UserMeta, Error> {
+    let Some(pool) = req.app_data::<web::Data<DbPool>>() else {
+        return Err(ErrorUnauthorized("No database"));
+    };
+
+    let Some(api_token) = handles::select_api_token_by_hash(pool, &auth::token_hash(token))
+        .await
+        .map_err(|e| ErrorUnauthorized(e.to_string()))?
+    else {
+        return Err(ErrorUnauthorized("Invalid API token"));
+    };
+
+    let now = chrono::Utc::now().timestamp();
+
+    if api_token.expires.is_some_and(|e| e < now) {
+        return Err(ErrorUnauthorized("API token expired"));
+    }
+
+    if !auth::api_token_allows(&api_token.scope, req.method(), req.path()) {
+        return Err(ErrorForbidden(format!(
+            "API token scope {} does not allow this request",
+            api_token.scope
+        )));
+    }
+
+    let user = handles::select_user(pool, api_token.user_id)
+        .await
+        .map_err(|e| ErrorUnauthorized(e.to_string()))?;
+    let role = handles::select_role(pool, &user.role_id.unwrap_or_default())
+        .await
+        .map_err(|e| ErrorUnauthorized(e.to_string()))?;
+    let user_channels = user.channel_ids.unwrap_or_default();
+    let user_permissions = user.permissions.unwrap_or_default();
+
+    let channels = match role {
+        Role::GlobalAdmin => api_token.channel_ids,
+        Role::ChannelAdmin | Role::User => api_token
+            .channel_ids
+            .into_iter()
+            .filter(|c| user_channels.contains(c))
+            .collect(),
+        Role::Guest => return Err(ErrorUnauthorized("API token of a guest")),
+    };
+
+    let permissions = channels
+        .iter()
+        .map(|c| {
+            let creator = match role {
+                Role::User => user_permissions.get(c).cloned().unwrap_or_default(),
+                _ => ChannelPermission::Editor,
+            };
+
+            (*c, auth::api_token_permission(&api_token.scope, &creator))
+        })
+        .collect();
+
+    handles::update_api_token_used(pool, api_token.id, now)
+        .await
+        .map_err(|e| ErrorUnauthorized(e.to_string()))?;
+
+    Ok(UserMeta::new(api_token.user_id, channels, permissions))
+}
 
 pub async fn validator(
     req: ServiceRequest,
     credentials: BearerAuth,
 ) -> Result<ServiceRequest, (Error, ServiceRequest)> {
+    if credentials.token().starts_with(auth::API_TOKEN_PREFIX) {
+        return match api_token_user(&req, credentials.token()).await {
+            Ok(user) => {
+                req.attach(vec![Role::User]);
+                req.extensions_mut().insert(user);
+
+                Ok(req)
+            }
+            Err(e) => Err((e, req)),
+        };
+    }
+
     // We just get permissions from JWT
     match auth::decode_jwt(credentials.token()).await {
+        Ok(claims) if claims.is_refresh() => Err((
+            ErrorUnauthorized("Refresh token is not valid for the API"),
+            req,
+        )),
         Ok(claims) => {
             req.attach(vec![claims.role]);
 
-            req.extensions_mut()
-                .insert(UserMeta::new(claims.id, claims.channels));
+            req.extensions_mut().insert(UserMeta::new(
+                claims.id,
+                claims.channels,
+                claims.permissions,
+            ));
 
             Ok(req)
         }
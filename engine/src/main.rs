@@ -19,10 +19,18 @@ use tokio::{fs::File, io::AsyncReadExt, sync::Mutex};
 use ffplayout::{
     api::routes::*,
     db::{db_drop, db_pool, handles, init_globales},
-    file::utils::media_map::MediaMap,
+    file::utils::{
+        integrity::integrity_task,
+        loudness::loudness_task,
+        media_map::MediaMap,
+        migrate::{migrate_storage, MigrateObj},
+    },
     player::{
         controller::{ChannelController, ChannelManager},
-        utils::{get_date, is_remote, json_validate::validate_playlist, JsonPlaylist},
+        utils::{
+            get_date, import::import_file, is_remote, json_validate::validate_playlist,
+            sec_to_time, simulate::simulate_day, JsonPlaylist,
+        },
     },
     sse::{broadcast::Broadcaster, routes::*, SseAuthState},
     utils::{
@@ -34,12 +42,15 @@ use ffplayout::{
         playlist::generate_playlist,
         time_machine::set_mock_time,
     },
-    validator, ARGS,
+    validator, ARGS, HW_ACCEL,
 };
 
 #[cfg(any(debug_assertions, not(feature = "embed_frontend")))]
 use ffplayout::utils::public_path;
 
+#[cfg(target_family = "unix")]
+use ffplayout::utils::handoff;
+
 #[cfg(all(not(debug_assertions), feature = "embed_frontend"))]
 include!(concat!(env!("OUT_DIR"), "/generated.rs"));
 
@@ -74,6 +85,20 @@ async fn main() -> Result<(), ProcessError> {
 
     if let Some(conn) = &ARGS.listen {
         let channels = handles::select_related_channels(&pool, None).await?;
+        let mut startup_list = vec![];
+
+        if HW_ACCEL.is_empty() {
+            info!("No hardware encoder found");
+        } else {
+            info!(
+                "Hardware encoder: <yellow>{}</>",
+                HW_ACCEL
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
 
         for channel in channels.into_iter() {
             let config = get_config(&pool, channel.id).await?;
@@ -90,14 +115,41 @@ async fn main() -> Result<(), ProcessError> {
             }
 
             mail_queues.lock().await.push(m_queue);
+            manager.start_mirror().await;
 
             if channel_active {
-                manager.start().await?;
+                startup_list.push(manager.clone());
             }
 
             channel_controllers.lock().await.add(manager);
         }
 
+        #[cfg(target_family = "unix")]
+        let predecessor = match &ARGS.handoff_socket {
+            Some(path) => handoff::connect(path).await,
+            None => None,
+        };
+
+        #[cfg(target_family = "unix")]
+        let handoff_enabled = ARGS.handoff_socket.is_some();
+        #[cfg(not(target_family = "unix"))]
+        let handoff_enabled = false;
+
+        if !handoff_enabled {
+            let startup = channel_controllers.lock().await.startup.clone();
+            tokio::spawn(startup.staged_start(std::mem::take(&mut startup_list)));
+        }
+
+        if let Some(hours) = ARGS.integrity_interval {
+            tokio::spawn(integrity_task(
+                pool.clone(),
+                channel_controllers.clone(),
+                hours,
+            ));
+        }
+
+        tokio::spawn(loudness_task(pool.clone(), channel_controllers.clone()));
+
         let (addr, port) = conn
             .split_once(':')
             .map(|(a, p)| (a, p.parse::<u16>().ok()))
@@ -117,7 +169,7 @@ async fn main() -> Result<(), ProcessError> {
         let db_clone = pool.clone();
 
         // no 'allow origin' here, give it to the reverse proxy
-        HttpServer::new(move || {
+        let server = HttpServer::new(move || {
             let auth = HttpAuthentication::bearer(validator);
             let db_pool = web::Data::new(db_clone.clone());
             // Customize logging format to get IP though proxies.
@@ -132,7 +184,12 @@ async fn main() -> Result<(), ProcessError> {
                 .app_data(web::Data::new(shared_duration.clone()))
                 .app_data(web::Data::from(Arc::clone(&broadcast_data)))
                 .wrap(logger)
-                .service(web::scope("/auth").service(login).service(refresh))
+                .service(
+                    web::scope("/auth")
+                        .service(login)
+                        .service(refresh)
+                        .service(logout),
+                )
                 .service(
                     web::scope("/api")
                         .wrap(auth)
@@ -141,6 +198,12 @@ async fn main() -> Result<(), ProcessError> {
                         .service(get_by_name)
                         .service(get_users)
                         .service(remove_user)
+                        .service(get_sessions)
+                        .service(revoke_sessions)
+                        .service(revoke_session)
+                        .service(get_auth_audit)
+                        .service(get_upload_limits)
+                        .service(update_upload_limit)
                         .service(add_advanced_config)
                         .service(get_advanced_config)
                         .service(get_related_advanced_config)
@@ -152,6 +215,49 @@ async fn main() -> Result<(), ProcessError> {
                         .service(get_presets)
                         .service(update_preset)
                         .service(delete_preset)
+                        .service(get_rules)
+                        .service(add_rule)
+                        .service(update_rule)
+                        .service(delete_rule)
+                        .service(get_bumpers)
+                        .service(add_bumper)
+                        .service(update_bumper)
+                        .service(delete_bumper)
+                        .service(get_outputs)
+                        .service(add_output)
+                        .service(update_output)
+                        .service(delete_output)
+                        .service(get_fillers)
+                        .service(add_filler)
+                        .service(update_filler)
+                        .service(delete_filler)
+                        .service(get_filter_templates)
+                        .service(add_filter_template)
+                        .service(update_filter_template)
+                        .service(delete_filter_template)
+                        .service(get_webhooks)
+                        .service(add_webhook)
+                        .service(update_webhook)
+                        .service(delete_webhook)
+                        .service(get_notifiers)
+                        .service(add_notifier)
+                        .service(update_notifier)
+                        .service(delete_notifier)
+                        .service(get_alert_rules)
+                        .service(add_alert_rule)
+                        .service(update_alert_rule)
+                        .service(delete_alert_rule)
+                        .service(get_live_events)
+                        .service(add_live_event)
+                        .service(update_live_event)
+                        .service(delete_live_event)
+                        .service(get_ingest_keys)
+                        .service(add_ingest_key)
+                        .service(update_ingest_key)
+                        .service(delete_ingest_key)
+                        .service(get_api_tokens)
+                        .service(add_api_token)
+                        .service(delete_api_token)
                         .service(get_channel)
                         .service(get_all_channels)
                         .service(patch_channel)
@@ -159,29 +265,63 @@ async fn main() -> Result<(), ProcessError> {
                         .service(remove_channel)
                         .service(update_user)
                         .service(send_text_message)
+                        .service(control_html)
+                        .service(control_voice)
+                        .service(control_emergency)
                         .service(control_playout)
                         .service(media_current)
+                        .service(outputs_status)
+                        .service(encoder_stats)
                         .service(process_control)
+                        .service(startup_queue)
                         .service(get_playlist)
+                        .service(get_playlist_report)
                         .service(save_playlist)
+                        .service(trim_playlist_item)
+                        .service(convert_playlist)
+                        .service(get_playlist_history)
+                        .service(get_scheduled_items)
+                        .service(import_json)
+                        .service(export_json)
                         .service(gen_playlist)
                         .service(del_playlist)
                         .service(get_log)
+                        .service(get_log_tail)
                         .service(file_browser)
                         .service(add_dir)
                         .service(move_rename)
                         .service(remove)
                         .service(save_file)
+                        .service(hash_scan)
+                        .service(get_duplicates)
+                        .service(dedupe_files)
+                        .service(get_integrity)
+                        .service(set_media_validity)
+                        .service(sign_file)
+                        .service(get_storage_audit)
+                        .service(storage_migrate)
+                        .service(get_storage_migration)
+                        .service(get_mirror_status)
                         .service(import_playlist)
                         .service(get_program)
                         .service(get_system_stat)
+                        .service(get_snapshot)
+                        .service(get_screenshots)
                         .service(generate_uuid),
                 )
                 .service(
                     web::scope("/data")
                         .service(validate_uuid)
-                        .service(event_stream),
+                        .service(event_stream)
+                        .service(preview_stream)
+                        .service(ingest_preview_stream)
+                        .service(whep_play)
+                        .service(whep_stop),
                 )
+                .service(get_epg)
+                .service(get_now_playing)
+                .service(whip_publish)
+                .service(whip_unpublish)
                 .service(get_file)
                 .service(get_public);
 
@@ -200,11 +340,30 @@ async fn main() -> Result<(), ProcessError> {
             }
 
             web_app
-        })
-        .bind((addr, port))?
-        .workers(thread_counter())
-        .run()
-        .await?;
+        });
+
+        #[cfg(target_family = "unix")]
+        let server = match ARGS.handoff_socket {
+            Some(_) => server.listen(handoff::bind_shared(addr, port)?)?,
+            None => server.bind((addr, port))?,
+        };
+        #[cfg(not(target_family = "unix"))]
+        let server = server.bind((addr, port))?;
+
+        let server = server.workers(thread_counter()).run();
+
+        #[cfg(target_family = "unix")]
+        if let Some(path) = &ARGS.handoff_socket {
+            tokio::spawn(handoff::handoff_task(
+                path.clone(),
+                predecessor,
+                channel_controllers.clone(),
+                startup_list,
+                server.handle(),
+            ));
+        }
+
+        server.await?;
     } else if ARGS.drop_db {
         db_drop().await;
     } else if let Some(channel_ids) = &ARGS.channel {
@@ -223,6 +382,25 @@ async fn main() -> Result<(), ProcessError> {
             } else if ARGS.generate.is_some() {
                 // run a simple playlist generator and save them to disk
                 generate_playlist(manager).await?;
+            } else if let Some(file) = &ARGS.import_playlist {
+                let date = ARGS.date.clone().unwrap_or_default();
+                let storage = manager.storage.lock().await.clone();
+                let channel_name = manager.channel.lock().await.name.clone();
+                let report =
+                    import_file(&pool, &config, &storage, &date, Some(channel_name), file, 0)
+                        .await?;
+
+                info!(
+                    "Channel <yellow>{channel_id}</>: {} {} item(s) imported",
+                    report.message, report.imported
+                );
+
+                for entry in report.unresolved {
+                    warn!(
+                        "Channel <yellow>{channel_id}</>: skip entry <yellow>{}</> <b><magenta>{}</></b>: {}",
+                        entry.position, entry.source, entry.reason
+                    );
+                }
             } else if ARGS.validate {
                 let mut playlist_path = config.channel.playlists.clone();
                 let start_sec = config.playlist.start_sec.unwrap();
@@ -255,6 +433,60 @@ async fn main() -> Result<(), ProcessError> {
                     Arc::new(AtomicBool::new(false)),
                 )
                 .await;
+            } else if ARGS.simulate {
+                let start_sec = config.playlist.start_sec.unwrap();
+                let date = ARGS
+                    .date
+                    .clone()
+                    .unwrap_or_else(|| get_date(true, start_sec, false, &config.channel.timezone));
+                let report = simulate_day(manager, &date).await?;
+
+                for clip in &report.clips {
+                    let state = if clip.skipped {
+                        "skipped".to_string()
+                    } else if clip.filler {
+                        "filler".to_string()
+                    } else if clip.trimmed > 0.0 {
+                        format!("trimmed {:.3}s", clip.trimmed)
+                    } else {
+                        String::new()
+                    };
+
+                    info!(
+                        "Channel <yellow>{channel_id}</>: {} (planned {}) <b><magenta>{}</></b> in: {:.3} out: {:.3} <yellow>{state}</>",
+                        sec_to_time(clip.start),
+                        clip.planned.map(sec_to_time).unwrap_or_else(|| "-".to_string()),
+                        clip.source,
+                        clip.seek,
+                        clip.out
+                    );
+                }
+
+                info!(
+                    "Channel <yellow>{channel_id}</>: {} simulated, {} of {} played, {} filler(s) with {}, {} trimmed, {} skipped clip(s)",
+                    report.date,
+                    sec_to_time(report.played),
+                    sec_to_time(report.length),
+                    report.fillers,
+                    sec_to_time(report.filler_time),
+                    report.trims,
+                    report.skips
+                );
+            } else if let Some(target) = &ARGS.migrate_storage {
+                let obj = MigrateObj {
+                    target: target.clone(),
+                    dry_run: false,
+                    switch: ARGS.migrate_switch,
+                };
+                let status = migrate_storage(&pool, &manager, &obj, &shared_duration).await?;
+
+                info!(
+                    "Channel <yellow>{channel_id}</>: {} of {} file(s) migrated, {} failed, storage switched: {}",
+                    status.copied,
+                    status.total,
+                    status.failed.len(),
+                    status.switched
+                );
             } else if ARGS.test_mail {
                 mail::send_mail(&config.mail, "This is just a test email...".to_string()).await?;
             }
@@ -8,28 +8,52 @@ use std::{
 };
 
 use async_walkdir::WalkDir;
+use chrono::Utc;
 use log::*;
 use m3u8_rs::Playlist;
 use serde::{Deserialize, Serialize};
-use sqlx::{Pool, Sqlite};
 use tokio::{
     fs,
     io::{self, AsyncReadExt},
     process::{Child, ChildStdout},
     sync::Mutex,
+    task::JoinHandle,
     time::{sleep, Duration, Instant},
 };
 use tokio_stream::StreamExt;
 
-use crate::utils::{config::PlayoutConfig, errors::ServiceError};
+use crate::utils::{
+    alerts::{alert_scheduler, AlertHistory},
+    config::PlayoutConfig,
+    errors::ServiceError,
+};
 use crate::ARGS;
 use crate::{
-    db::{handles, models::Channel},
-    utils::logging::Target,
+    db::{handles, models::Channel, DbPool},
+    utils::logging::{set_log_rotation, Target},
 };
 use crate::{
-    file::{init_storage, select_storage_type, StorageBackend},
-    player::{output::player, utils::Media},
+    file::{
+        init_storage, select_storage_type,
+        utils::{
+            filler::{probe_fillers, FillerPool},
+            mirror::{mirror_task, MirrorStatus},
+        },
+        StorageBackend,
+    },
+    player::{
+        input::{event_scheduler, voice::VoiceOver, whip::WhipSession},
+        output::{
+            extra::OutputHealth, html::HtmlOverlay, player, preview::Preview, published,
+            watchdog::EncoderProgress, whep::WhepSession,
+        },
+        utils::{
+            scte35::{break_duration, SpliceCues},
+            sec_to_time,
+            secondary::{self, preset_scheduler},
+            Media,
+        },
+    },
 };
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -41,6 +65,7 @@ pub enum ProcessUnit {
     Decoder,
     Encoder,
     Ingest,
+    Event,
 }
 
 impl fmt::Display for ProcessUnit {
@@ -49,6 +74,7 @@ impl fmt::Display for ProcessUnit {
             Self::Decoder => write!(f, "Decoder"),
             Self::Encoder => write!(f, "Encoder"),
             Self::Ingest => write!(f, "Ingest"),
+            Self::Event => write!(f, "Event"),
         }
     }
 }
@@ -58,7 +84,7 @@ use ProcessUnit::*;
 #[derive(Clone, Debug)]
 pub struct ChannelManager {
     pub id: i32,
-    pub db_pool: Pool<Sqlite>,
+    pub db_pool: DbPool,
     pub config: Arc<Mutex<PlayoutConfig>>,
     pub channel: Arc<Mutex<Channel>>,
     pub decoder: Arc<Mutex<Option<Child>>>,
@@ -66,21 +92,69 @@ pub struct ChannelManager {
     pub ingest: Arc<Mutex<Option<Child>>>,
     pub ingest_stdout: Arc<Mutex<Option<ChildStdout>>>,
     pub ingest_is_alive: Arc<AtomicBool>,
+    /// Drop the held part of the delayed live ingest.
+    pub ingest_dump: Arc<AtomicBool>,
+    pub event: Arc<Mutex<Option<Child>>>,
+    pub event_stdout: Arc<Mutex<Option<ChildStdout>>>,
+    pub event_is_alive: Arc<AtomicBool>,
     pub is_alive: Arc<AtomicBool>,
+    /// Channel is paused, the player loops the standby slate or is stopped.
+    pub standby: Arc<AtomicBool>,
+    /// Emergency override, the player preempts the program with the emergency slate.
+    pub emergency: Arc<AtomicBool>,
+    emergency_since: Arc<Mutex<Option<Instant>>>,
     pub is_processing: Arc<AtomicBool>,
+    pub is_migrating: Arc<AtomicBool>,
+    pub mirror: Arc<Mutex<MirrorStatus>>,
+    pub mirror_handler: Arc<Mutex<Option<JoinHandle<()>>>>,
     pub filter_chain: Option<Arc<Mutex<Vec<String>>>>,
     pub current_date: Arc<Mutex<String>>,
     pub list_init: Arc<AtomicBool>,
+    /// Channel is taken over from the old engine, its HLS segments are continued.
+    pub taken_over: Arc<AtomicBool>,
     pub current_media: Arc<Mutex<Option<Media>>>,
+    /// Running clip with a new seek value, the player starts it again after the decoder is stopped.
+    pub seek_media: Arc<Mutex<Option<Media>>>,
     pub current_list: Arc<Mutex<Vec<Media>>>,
-    pub filler_list: Arc<Mutex<Vec<Media>>>,
+    pub filler_list: Arc<Mutex<FillerPool>>,
     pub current_index: Arc<AtomicUsize>,
-    pub filler_index: Arc<AtomicUsize>,
+    /// Index from before the next clip got prerolled, as long as the prerolled clip waits.
+    pub preroll: Arc<Mutex<Option<usize>>>,
+    /// The prerolled clip is dropped, the player reads the next clip again.
+    pub preroll_discarded: Arc<AtomicBool>,
     pub storage: Arc<Mutex<StorageBackend>>,
+    pub splice_cues: Arc<Mutex<SpliceCues>>,
+    pub secondary: Arc<Mutex<Option<JoinHandle<()>>>>,
+    /// Health of the additional outputs, which are fed with the program.
+    pub outputs: Arc<Mutex<Vec<Arc<OutputHealth>>>>,
+    /// Index of the stream destination, the primary or one of the backups.
+    pub destination: Arc<AtomicUsize>,
+    /// Encoder failures in a row, on the current destination.
+    pub output_failures: Arc<AtomicUsize>,
+    /// Progress of the running main encoder.
+    pub progress: Arc<Mutex<Option<Arc<EncoderProgress>>>>,
+    /// Low latency program preview for the web UI.
+    pub preview: Arc<Preview>,
+    /// Confidence monitor of the incoming ingest stream.
+    pub ingest_preview: Arc<Preview>,
+    /// Browser graphics of the HTML overlay.
+    pub html: Arc<HtmlOverlay>,
+    /// Voice-over, which ducks the program audio.
+    pub voice: Arc<VoiceOver>,
+    /// Changed config, which is applied with a new encoder at the next clip.
+    pub reload: Arc<AtomicBool>,
+    /// Running WHIP session of the ingest.
+    pub whip: Arc<Mutex<Option<WhipSession>>>,
+    /// WHEP sessions of the preview viewers.
+    pub whep: Arc<Mutex<Vec<WhepSession>>>,
+    /// Played items and encoder restarts, for the alert rules.
+    pub alert_history: Arc<Mutex<AlertHistory>>,
 }
 
 impl ChannelManager {
-    pub async fn new(db_pool: Pool<Sqlite>, channel: Channel, config: PlayoutConfig) -> Self {
+    pub async fn new(db_pool: DbPool, channel: Channel, config: PlayoutConfig) -> Self {
+        set_log_rotation(channel.id, &config.logging);
+
         let s_type = select_storage_type(&config.channel.storage);
         let channel_extensions = channel.extra_extensions.clone();
         let mut extensions = config.storage.extensions.clone();
@@ -102,23 +176,144 @@ impl ChannelManager {
             id: channel.id,
             db_pool,
             is_alive: Arc::new(AtomicBool::new(false)),
+            standby: Arc::new(AtomicBool::new(channel.standby)),
+            emergency: Arc::new(AtomicBool::new(false)),
+            emergency_since: Arc::new(Mutex::new(None)),
             channel: Arc::new(Mutex::new(channel)),
             config: Arc::new(Mutex::new(config)),
             list_init: Arc::new(AtomicBool::new(true)),
+            taken_over: Arc::new(AtomicBool::new(false)),
             current_media: Arc::new(Mutex::new(None)),
+            seek_media: Arc::new(Mutex::new(None)),
             current_list: Arc::new(Mutex::new(vec![Media::default()])),
-            filler_list: Arc::new(Mutex::new(vec![])),
+            filler_list: Arc::new(Mutex::new(FillerPool::new())),
             current_index: Arc::new(AtomicUsize::new(0)),
-            filler_index: Arc::new(AtomicUsize::new(0)),
+            preroll: Arc::new(Mutex::new(None)),
+            preroll_discarded: Arc::new(AtomicBool::new(false)),
             decoder: Arc::new(Mutex::new(None)),
             encoder: Arc::new(Mutex::new(None)),
             ingest: Arc::new(Mutex::new(None)),
             ingest_stdout: Arc::new(Mutex::new(None)),
             ingest_is_alive: Arc::new(AtomicBool::new(false)),
+            ingest_dump: Arc::new(AtomicBool::new(false)),
+            event: Arc::new(Mutex::new(None)),
+            event_stdout: Arc::new(Mutex::new(None)),
+            event_is_alive: Arc::new(AtomicBool::new(false)),
             is_processing: Arc::new(AtomicBool::new(false)),
+            is_migrating: Arc::new(AtomicBool::new(false)),
+            mirror: Arc::new(Mutex::new(MirrorStatus::default())),
+            mirror_handler: Arc::new(Mutex::new(None)),
             filter_chain: None,
             current_date: Arc::new(Mutex::new(String::new())),
             storage,
+            splice_cues: Arc::new(Mutex::new(SpliceCues::default())),
+            secondary: Arc::new(Mutex::new(None)),
+            outputs: Arc::new(Mutex::new(vec![])),
+            destination: Arc::new(AtomicUsize::new(0)),
+            output_failures: Arc::new(AtomicUsize::new(0)),
+            progress: Arc::new(Mutex::new(None)),
+            preview: Arc::new(Preview::default()),
+            ingest_preview: Arc::new(Preview::default()),
+            html: Arc::new(HtmlOverlay::default()),
+            voice: Arc::new(VoiceOver::default()),
+            reload: Arc::new(AtomicBool::new(false)),
+            whip: Arc::new(Mutex::new(None)),
+            whep: Arc::new(Mutex::new(vec![])),
+            alert_history: Arc::new(Mutex::new(AlertHistory::default())),
+        }
+    }
+
+    /// Signal begin and end of advertisement breaks with SCTE-35 cues.
+    pub async fn splice_cue(&self, node: &Media, timed: bool) {
+        if !self.config.lock().await.output.scte35 {
+            return;
+        }
+
+        let duration = break_duration(&self.current_list.lock().await, node);
+        let is_ad = node.category == "advertisement";
+
+        if let Some(event) =
+            self.splice_cues
+                .lock()
+                .await
+                .node_started(Utc::now(), is_ad, duration, timed)
+        {
+            let cue = if event.out_of_network { "out" } else { "in" };
+
+            debug!(target: Target::file_mail(), channel = self.id; "SCTE-35 cue {cue}, event <yellow>{}</>", event.event_id);
+        }
+    }
+
+    /// Start the secondary events of the new item and stop the ones of the previous item.
+    pub async fn secondary_events(&self, node: &Media) {
+        let mut secondary = self.secondary.lock().await;
+
+        if let Some(handle) = secondary.take() {
+            handle.abort();
+        }
+
+        if !node.events.is_empty() {
+            *secondary = Some(tokio::spawn(secondary::run(self.clone(), node.clone())));
+        }
+    }
+
+    /// Switch the channel to standby, or back to the playlist.
+    pub async fn set_standby(&self, standby: bool) -> Result<(), ServiceError> {
+        self.standby.store(standby, Ordering::SeqCst);
+        self.channel.lock().await.standby = standby;
+
+        handles::update_standby(&self.db_pool, self.id, standby).await?;
+
+        Ok(())
+    }
+
+    /// Start or release the emergency override, both are written to the channel log.
+    ///
+    /// Returns `false`, when the override is already in this state.
+    pub async fn set_emergency(&self, active: bool, reason: Option<&str>) -> bool {
+        let mut since = self.emergency_since.lock().await;
+
+        if active == since.is_some() {
+            return false;
+        }
+
+        let reason = reason
+            .filter(|r| !r.trim().is_empty())
+            .map(|r| format!(": <yellow>{}</>", r.trim()))
+            .unwrap_or_default();
+
+        if active {
+            *since = Some(Instant::now());
+            self.emergency.store(true, Ordering::SeqCst);
+
+            warn!(target: Target::file_mail(), channel = self.id; "Emergency override started{reason}");
+        } else {
+            let duration = since
+                .take()
+                .map(|s| s.elapsed().as_secs_f64())
+                .unwrap_or_default();
+            self.emergency.store(false, Ordering::SeqCst);
+
+            warn!(target: Target::file_mail(), channel = self.id; "Emergency override released after <yellow>{}</>{reason}", sec_to_time(duration));
+        }
+
+        true
+    }
+
+    /// Take the seek request, when it belongs to the clip which has just been played.
+    pub async fn take_seek(&self, node: &Media) -> Option<Media> {
+        self.seek_media
+            .lock()
+            .await
+            .take()
+            .filter(|m| m.index == node.index && m.source == node.source)
+    }
+
+    /// Drop a waiting prerolled clip, the index goes back to the clip after the running one.
+    pub async fn discard_preroll(&self) {
+        if let Some(index) = self.preroll.lock().await.take() {
+            self.current_index.store(index, Ordering::SeqCst);
+            self.preroll_discarded.store(true, Ordering::SeqCst);
         }
     }
 
@@ -132,6 +327,7 @@ impl ChannelManager {
         channel.last_date.clone_from(&other.last_date);
         channel.time_shift.clone_from(&other.time_shift);
         channel.timezone.clone_from(&other.timezone);
+        channel.priority.clone_from(&other.priority);
 
         let s_path = Path::new(&other.storage);
         let s_type = select_storage_type(s_path);
@@ -153,9 +349,26 @@ impl ChannelManager {
 
     pub async fn update_config(&self, new_config: PlayoutConfig) {
         let mut config = self.config.lock().await;
+        set_log_rotation(self.id, &new_config.logging);
         *config = new_config;
     }
 
+    /// (Re)start the storage mirror, when a mirror target is configured.
+    pub async fn start_mirror(&self) {
+        let config = self.config.lock().await.clone();
+        let mut handler = self.mirror_handler.lock().await;
+
+        if let Some(task) = handler.take() {
+            task.abort();
+        }
+
+        *self.mirror.lock().await = MirrorStatus::default();
+
+        if !config.storage.mirror.is_empty() {
+            *handler = Some(tokio::spawn(mirror_task(config, self.mirror.clone())));
+        }
+    }
+
     pub async fn start(&self) -> Result<(), ServiceError> {
         if self.is_alive.swap(true, Ordering::SeqCst) {
             return Ok(()); // runs already, don't start multiple instances
@@ -164,6 +377,11 @@ impl ChannelManager {
         let self_clone = self.clone();
         let channel_id = self.channel.lock().await.id;
 
+        // a new start tries the primary destination first
+        self.destination.store(0, Ordering::SeqCst);
+        self.output_failures.store(0, Ordering::SeqCst);
+        self.reload.store(false, Ordering::SeqCst);
+
         handles::update_player(&self.db_pool, channel_id, true).await?;
 
         tokio::spawn(async move {
@@ -241,12 +459,11 @@ impl ChannelManager {
     }
 
     pub async fn stop(&self, unit: ProcessUnit) {
-        self.storage.lock().await.stop_watch().await;
-
         let child = match unit {
             Decoder => &self.decoder,
             Encoder => &self.encoder,
             Ingest => &self.ingest,
+            Event => &self.event,
         };
 
         if let Some(p) = child.lock().await.as_mut() {
@@ -267,6 +484,7 @@ impl ChannelManager {
             Decoder => &self.decoder,
             Encoder => &self.encoder,
             Ingest => &self.ingest,
+            Event => &self.event,
         };
 
         if let Some(proc) = child.lock().await.as_mut() {
@@ -310,27 +528,142 @@ impl ChannelManager {
             if let Err(e) = handles::update_player(&self.db_pool, channel_id, false).await {
                 error!(target: Target::all(), channel = channel_id; "Player status cannot be written: {e}");
             };
+
+            self.set_emergency(false, None).await;
         } else {
             debug!(target: Target::all(), channel = channel_id; "Stop all child processes from channel: <yellow>{channel_id}</>");
         }
 
         self.is_alive.store(false, Ordering::SeqCst);
         self.ingest_is_alive.store(false, Ordering::SeqCst);
+        self.event_is_alive.store(false, Ordering::SeqCst);
+
+        if let Some(handle) = self.secondary.lock().await.take() {
+            handle.abort();
+        }
 
-        for unit in [Decoder, Encoder, Ingest] {
+        self.storage.lock().await.stop_watch().await;
+
+        for unit in [Decoder, Encoder, Ingest, Event] {
             self.stop(unit).await;
         }
     }
 }
 
+/// State of a channel in the startup queue.
+#[derive(Clone, Debug, Default, Copy, Eq, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StartupState {
+    #[default]
+    Queued,
+    Starting,
+    Started,
+    Failed,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct StartupEntry {
+    pub channel_id: i32,
+    pub name: String,
+    pub priority: i32,
+    pub stage: usize,
+    pub state: StartupState,
+    pub error: Option<String>,
+}
+
+/// Holds the channels, which are started on boot, and their current state.
+#[derive(Clone, Debug, Default)]
+pub struct StartupQueue {
+    pub entries: Arc<Mutex<Vec<StartupEntry>>>,
+}
+
+impl StartupQueue {
+    async fn set_state(&self, channel_id: i32, state: StartupState, error: Option<String>) {
+        if let Some(entry) = self
+            .entries
+            .lock()
+            .await
+            .iter_mut()
+            .find(|e| e.channel_id == channel_id)
+        {
+            entry.state = state;
+            entry.error = error;
+        }
+    }
+
+    /// Start channels in stages, ordered by priority (higher value first).
+    ///
+    /// Per stage only `--startup-concurrency` channels are started,
+    /// between the stages we wait `--startup-delay` seconds.
+    /// This prevents, that all ffmpeg instances fire up at the same time.
+    pub async fn staged_start(self, managers: Vec<ChannelManager>) {
+        let concurrency = match ARGS.startup_concurrency {
+            Some(c) if c > 0 => c,
+            _ => managers.len().max(1),
+        };
+        let delay = Duration::from_secs(ARGS.startup_delay.unwrap_or(0));
+        let mut ordered = vec![];
+
+        for manager in managers {
+            let channel = manager.channel.lock().await.clone();
+            ordered.push((channel, manager));
+        }
+
+        ordered.sort_by_key(|(c, _)| (cmp::Reverse(c.priority), c.id));
+
+        *self.entries.lock().await = ordered
+            .iter()
+            .enumerate()
+            .map(|(i, (c, _))| StartupEntry {
+                channel_id: c.id,
+                name: c.name.clone(),
+                priority: c.priority,
+                stage: i / concurrency,
+                ..Default::default()
+            })
+            .collect();
+
+        for (stage, chunk) in ordered.chunks(concurrency).enumerate() {
+            if stage > 0 && !delay.is_zero() {
+                debug!(
+                    "Wait <yellow>{}</> seconds before next startup stage",
+                    delay.as_secs()
+                );
+                sleep(delay).await;
+            }
+
+            for (channel, manager) in chunk {
+                self.set_state(channel.id, StartupState::Starting, None)
+                    .await;
+
+                match manager.start().await {
+                    Ok(()) => {
+                        self.set_state(channel.id, StartupState::Started, None)
+                            .await;
+                    }
+                    Err(e) => {
+                        error!(target: Target::all(), channel = channel.id; "Startup of channel <yellow>{}</> failed: {e}", channel.id);
+                        self.set_state(channel.id, StartupState::Failed, Some(e.to_string()))
+                            .await;
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct ChannelController {
     pub managers: Vec<ChannelManager>,
+    pub startup: StartupQueue,
 }
 
 impl ChannelController {
     pub fn new() -> Self {
-        Self { managers: vec![] }
+        Self {
+            managers: vec![],
+            startup: StartupQueue::default(),
+        }
     }
 
     pub fn add(&mut self, manager: ChannelManager) {
@@ -360,7 +693,12 @@ impl ChannelController {
         indices.reverse();
 
         for i in indices {
-            self.managers.remove(i);
+            let manager = self.managers.remove(i);
+            let mirror = manager.mirror_handler.lock().await.take();
+
+            if let Some(task) = mirror {
+                task.abort();
+            }
         }
     }
 
@@ -392,9 +730,23 @@ async fn run_channel(manager: ChannelManager) -> Result<(), ServiceError> {
             .await
             .fill_filler_list(&config, Some(filler_list.clone()))
             .await;
+
+        tokio::spawn(probe_fillers(filler_list.clone()));
     }
 
-    player(manager).await
+    // scheduled live events run beside the player and switch it to their source
+    let events = tokio::spawn(event_scheduler(manager.clone()));
+    let presets = tokio::spawn(preset_scheduler(manager.clone()));
+    let alerts = tokio::spawn(alert_scheduler(manager.clone()));
+    let published = tokio::spawn(published::monitor(manager.clone()));
+    let result = player(manager).await;
+
+    events.abort();
+    presets.abort();
+    alerts.abort();
+    published.abort();
+
+    result
 }
 
 pub async fn drain_hls_path(path: &Path) -> io::Result<()> {
@@ -417,7 +769,7 @@ pub async fn drain_hls_path(path: &Path) -> io::Result<()> {
 }
 
 /// Recursively searches for all files with the .m3u8 extension in the specified path.
-async fn find_m3u8_files(path: &Path) -> io::Result<Vec<String>> {
+pub(crate) async fn find_m3u8_files(path: &Path) -> io::Result<Vec<String>> {
     let mut m3u8_files = Vec::new();
     let mut entries = WalkDir::new(path);
 
@@ -455,3 +807,36 @@ async fn delete_old_segments<P: AsRef<Path> + Clone + std::fmt::Debug>(
 
     Ok(())
 }
+
+/// Channel manager with an in-memory database for the tests, `extra` SQL changes its defaults.
+///
+/// The folders of the channel are in the temp directory, separated by `name`.
+#[cfg(all(test, not(feature = "mysql")))]
+pub(crate) async fn test_manager(name: &str, extra: &str) -> (DbPool, ChannelManager) {
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    handles::db_migrate(&pool).await.unwrap();
+
+    let root = std::env::temp_dir().join(format!("ffplayout_{name}_{}", std::process::id()));
+    let root = root.to_string_lossy();
+
+    sqlx::query(&format!(
+        "UPDATE global SET logs = '{root}/log', playlists = '{root}/playlists', public = '{root}/public', storage = '{root}/storage';
+        UPDATE channels SET playlists = '{root}/playlists', public = '{root}/public', storage = '{root}/storage';"
+    ))
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    if !extra.is_empty() {
+        sqlx::query(extra).execute(&pool).await.unwrap();
+    }
+
+    let config = PlayoutConfig::new(&pool, 1).await.unwrap();
+    let channel = handles::select_channel(&pool, &1).await.unwrap();
+    let manager = ChannelManager::new(pool.clone(), channel, config).await;
+
+    (pool, manager)
+}
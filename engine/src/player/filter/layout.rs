@@ -0,0 +1,179 @@
+/// Audio layout rules, which remix the channels of a source stream with pan or amerge.
+///
+/// Rules are separated by new line, each has the channel count of the source stream and a preset
+/// or a pan layout after an equal sign, like `6 = center_boost` or `2 = stereo|c0=c1|c1=c0`.
+/// `*` matches all streams, the first matching rule wins.
+use std::{fmt, str::FromStr};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Remix {
+    /// 5.1 downmix to stereo, with louder center for the dialog.
+    CenterBoost,
+    /// Left channel on both sides, for a broken dual mono source.
+    Left,
+    /// Right channel on both sides.
+    Right,
+    /// Both channels mixed to the same signal on both sides.
+    Mono,
+    /// Left and right swapped.
+    Swap,
+    /// This mono stream on the left and the following one on the right.
+    Merge,
+    /// Own layout for the pan filter, like `stereo|c0=c0|c1=c0`.
+    Pan(String),
+}
+
+impl FromStr for Remix {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "center_boost" => Ok(Self::CenterBoost),
+            "left" => Ok(Self::Left),
+            "right" => Ok(Self::Right),
+            "mono" => Ok(Self::Mono),
+            "swap" => Ok(Self::Swap),
+            "merge" => Ok(Self::Merge),
+            pan if pan.contains('|') && !pan.contains([',', ';', '[', ']']) => {
+                Ok(Self::Pan(pan.to_string()))
+            }
+            _ => Err(format!(
+                "Audio layout needs a preset or a pan layout like 'stereo|c0=c1|c1=c0': {s}"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Remix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::CenterBoost => write!(f, "center_boost"),
+            Self::Left => write!(f, "left"),
+            Self::Right => write!(f, "right"),
+            Self::Mono => write!(f, "mono"),
+            Self::Swap => write!(f, "swap"),
+            Self::Merge => write!(f, "merge"),
+            Self::Pan(pan) => write!(f, "{pan}"),
+        }
+    }
+}
+
+impl Remix {
+    /// Filter of the remix, `next` is the input selector of the following stream, for merging.
+    pub fn filter(&self, next: &str) -> String {
+        match self {
+            Self::CenterBoost => {
+                "pan=stereo|FL<c0+1.414*c2+0.5*c4|FR<c1+1.414*c2+0.5*c5".to_string()
+            }
+            Self::Left => "pan=stereo|c0=c0|c1=c0".to_string(),
+            Self::Right => "pan=stereo|c0=c1|c1=c1".to_string(),
+            Self::Mono => "pan=stereo|c0<c0+c1|c1<c0+c1".to_string(),
+            Self::Swap => "pan=stereo|c0=c1|c1=c0".to_string(),
+            Self::Merge => format!("{next}amerge=inputs=2,pan=stereo|c0=c0|c1=c1"),
+            Self::Pan(pan) => format!("pan={pan}"),
+        }
+    }
+
+    /// Lowest channel count of the source, which the remix needs.
+    fn min_channels(&self) -> i64 {
+        match self {
+            Self::CenterBoost => 6,
+            Self::Left | Self::Right | Self::Mono | Self::Swap => 2,
+            Self::Merge | Self::Pan(_) => 1,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LayoutRule {
+    /// Channel count of the source stream, `None` for all.
+    channels: Option<i64>,
+    pub remix: Remix,
+}
+
+impl FromStr for LayoutRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (channels, remix) = s
+            .split_once('=')
+            .ok_or(format!("Audio layout rule needs a layout after '=': {s}"))?;
+        let channels = match channels.trim() {
+            "*" => None,
+            c => Some(
+                c.parse::<i64>()
+                    .ok()
+                    .filter(|c| *c > 0)
+                    .ok_or(format!("Invalid channel count: {c}"))?,
+            ),
+        };
+        let remix = Remix::from_str(remix)?;
+
+        if channels.is_some_and(|c| c < remix.min_channels()) {
+            return Err(format!(
+                "Audio layout {remix} needs at least {} channels: {s}",
+                remix.min_channels()
+            ));
+        }
+
+        Ok(Self { channels, remix })
+    }
+}
+
+pub fn parse_rules(rules: &str) -> Result<Vec<LayoutRule>, String> {
+    rules
+        .lines()
+        .filter(|r| !r.trim().is_empty() && !r.trim_start().starts_with('#'))
+        .map(LayoutRule::from_str)
+        .collect()
+}
+
+/// Remix of the first matching rule, for a source stream with this channel count.
+/// A rule for all streams is skipped, when the stream has not enough channels for it.
+pub fn find_remix(rules: &[LayoutRule], channels: i64) -> Option<&Remix> {
+    rules
+        .iter()
+        .find(|r| {
+            r.channels
+                .map_or(channels >= r.remix.min_channels(), |c| c == channels)
+        })
+        .map(|r| &r.remix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_rules() {
+        let rules = parse_rules(
+            "# 5.1 movies
+            6 = center_boost
+            1 = merge
+            * = stereo|c0=c0|c1=c0",
+        )
+        .unwrap();
+
+        assert_eq!(find_remix(&rules, 6), Some(&Remix::CenterBoost));
+        assert_eq!(find_remix(&rules, 1), Some(&Remix::Merge));
+        assert_eq!(
+            find_remix(&rules, 2).map(|r| r.filter("")),
+            Some("pan=stereo|c0=c0|c1=c0".to_string())
+        );
+        assert_eq!(
+            Remix::Merge.filter("[0:a:1]"),
+            "[0:a:1]amerge=inputs=2,pan=stereo|c0=c0|c1=c1"
+        );
+
+        // presets for all streams are only used with enough channels
+        let rules = parse_rules("* = left").unwrap();
+        assert_eq!(find_remix(&rules, 1), None);
+        assert_eq!(find_remix(&rules, 2), Some(&Remix::Left));
+
+        assert!(parse_rules("2 = center_boost").is_err());
+        assert!(parse_rules("0 = left").is_err());
+        assert!(parse_rules("6 = loud").is_err());
+        assert!(parse_rules("2 = stereo|c0=c1,volume=9").is_err());
+        assert!(parse_rules("2").is_err());
+    }
+}
@@ -0,0 +1,135 @@
+/// Dayparted logos, which replace the static logo in their time window.
+///
+/// Rules are separated by new line, each has the time window of an ingest window with an
+/// optional date range in front and the logo after an equal sign, like
+/// `00:00-06:00 = none` or `2026-10-17..2026-10-18 Sat,Sun 10:00-22:00 = logos/event.png`.
+/// `none` is a clean feed without logo. The first matching rule wins.
+use std::str::FromStr;
+
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::player::input::window::IngestWindow;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogoRule {
+    dates: Option<(NaiveDate, NaiveDate)>,
+    window: IngestWindow,
+    /// Logo path, relative to the storage, `None` hides the logo.
+    pub logo: Option<String>,
+}
+
+fn parse_dates(dates: &str) -> Option<(NaiveDate, NaiveDate)> {
+    let (first, last) = dates.split_once("..").unwrap_or((dates, dates));
+    let first = NaiveDate::parse_from_str(first, "%Y-%m-%d").ok()?;
+    let last = NaiveDate::parse_from_str(last, "%Y-%m-%d").ok()?;
+
+    Some((first, last))
+}
+
+impl FromStr for LogoRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (time, logo) = s
+            .split_once('=')
+            .ok_or(format!("Logo rule needs a logo after '=': {s}"))?;
+        let time = time.trim();
+        let logo = logo.trim();
+
+        if logo.is_empty() {
+            return Err(format!("Logo rule needs a logo after '=': {s}"));
+        }
+
+        let (dates, window) = match time.split_once(char::is_whitespace) {
+            Some((first, rest)) if first.starts_with(|c: char| c.is_ascii_digit()) => {
+                match parse_dates(first) {
+                    Some(dates) => (Some(dates), rest),
+                    None if first.contains('-') && !first.contains(':') => {
+                        return Err(format!("Invalid date range: {first}"));
+                    }
+                    None => (None, time),
+                }
+            }
+            _ => (None, time),
+        };
+
+        if dates.is_some_and(|(first, last)| first > last) {
+            return Err(format!("Date range ends before it starts: {time}"));
+        }
+
+        Ok(Self {
+            dates,
+            window: IngestWindow::from_str(window)?,
+            logo: (!logo.eq_ignore_ascii_case("none")).then(|| logo.to_string()),
+        })
+    }
+}
+
+impl LogoRule {
+    /// The date range belongs to the start of the window, like the weekdays.
+    fn contains(&self, time: NaiveDateTime) -> bool {
+        self.window.contains(time)
+            && self.dates.is_none_or(|(first, last)| {
+                let date = self.window.start_date(time);
+
+                date >= first && date <= last
+            })
+    }
+}
+
+pub fn parse_rules(rules: &str) -> Result<Vec<LogoRule>, String> {
+    rules
+        .lines()
+        .filter(|r| !r.trim().is_empty() && !r.trim_start().starts_with('#'))
+        .map(LogoRule::from_str)
+        .collect()
+}
+
+/// Logo of the first matching rule, `None` when no rule matches and the static logo is used.
+pub fn active_logo(rules: &[LogoRule], time: NaiveDateTime) -> Option<Option<String>> {
+    rules
+        .iter()
+        .find(|r| r.contains(time))
+        .map(|r| r.logo.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(date: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn logo_rules() {
+        let rules = parse_rules(
+            "# special event bug this weekend
+            2026-10-17..2026-10-18 10:00-22:00 = logos/event.png
+            00:00-06:00 = none
+            Sat,Sun 23:00-01:00 = logos/night.png",
+        )
+        .unwrap();
+
+        assert_eq!(rules.len(), 3);
+        assert_eq!(active_logo(&rules, at("2026-10-16 12:00")), None);
+        assert_eq!(active_logo(&rules, at("2026-10-16 05:00")), Some(None));
+        assert_eq!(
+            active_logo(&rules, at("2026-10-17 12:00")),
+            Some(Some("logos/event.png".to_string()))
+        );
+        assert_eq!(active_logo(&rules, at("2026-10-19 12:00")), None);
+
+        // the first matching rule wins, after midnight the clean feed
+        assert_eq!(
+            active_logo(&rules, at("2026-10-17 23:30")),
+            Some(Some("logos/night.png".to_string()))
+        );
+        assert_eq!(active_logo(&rules, at("2026-10-18 00:30")), Some(None));
+
+        assert!(parse_rules("10:00-12:00").is_err());
+        assert!(parse_rules("10:00-12:00 = ").is_err());
+        assert!(parse_rules("2026-10-18..2026-10-17 10:00-12:00 = a.png").is_err());
+        assert!(parse_rules("2026-13-01 10:00-12:00 = a.png").is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use std::{fmt, path::Path, sync::Arc};
+use std::{collections::HashMap, fmt, path::Path, sync::Arc};
 
 use log::*;
 use regex::Regex;
@@ -6,15 +6,24 @@ use shlex::split;
 use tokio::sync::Mutex;
 
 mod custom;
+pub mod layout;
+pub mod logo;
 pub mod v_drawtext;
 
+use crate::file::{clean_raw_abs_path, utils::ABS_PATH_INDICATOR};
 use crate::player::{
     controller::ProcessUnit::{self, *},
-    utils::{calc_aspect, custom_format, fps_calc, fraction, is_close, Media},
+    input::window::{next_start, parse_windows},
+    output::{detect, meter},
+    utils::{
+        calc_aspect, custom_format, fps_calc, fraction, is_close, secondary::SecondaryAction, Media,
+    },
 };
 use crate::utils::{
-    config::{OutputMode::*, PlayoutConfig},
+    config::{AspectMode, OutputMode::*, PlayoutConfig, Transition, IMAGE_FORMAT},
+    epg::programme_title,
     logging::Target,
+    time_machine::time_now,
 };
 use crate::vec_strings;
 
@@ -37,6 +46,16 @@ use FilterType::*;
 
 const HW_FILTER_POSTFIX: &[&str; 6] = &["_cuda", "_npp", "_opencl", "_vaapi", "_vulkan", "_qsv"];
 
+/// Blend stage between the end of the clip and the head of the next clip,
+/// which is an extra input of the decoder.
+#[derive(Debug, Clone, Copy)]
+struct Crossfade {
+    position: i32,
+    offset: f64,
+    duration: f64,
+    audio_tracks: i32,
+}
+
 #[derive(Debug, Clone)]
 pub struct Filters {
     unit: ProcessUnit,
@@ -54,8 +73,11 @@ pub struct Filters {
     config: PlayoutConfig,
     audio_position: i32,
     video_position: i32,
+    /// Source stream of the output audio tracks, when it differs from the track number.
+    audio_streams: HashMap<i32, i32>,
     audio_last: i32,
     video_last: i32,
+    crossfade: Option<Crossfade>,
 }
 
 impl Filters {
@@ -83,12 +105,18 @@ impl Filters {
             config,
             audio_position,
             video_position: 0,
+            audio_streams: HashMap::new(),
             audio_last: -1,
             video_last: -1,
+            crossfade: None,
         }
     }
 
     pub fn add(&mut self, filter: &str, track_nr: i32, filter_type: FilterType) {
+        let stream_nr = match filter_type {
+            Audio => *self.audio_streams.get(&track_nr).unwrap_or(&track_nr),
+            Video => track_nr,
+        };
         let (map, chain, position, last) = match filter_type {
             Audio => (
                 &mut self.audio_map,
@@ -124,7 +152,7 @@ impl Filters {
                 chain_start.push_str(&sep);
             } else {
                 // build audio/video selector like [0:a:0]
-                chain_start.push_str(&format!("{sep}[{position}:{filter_type}:{track_nr}]"));
+                chain_start.push_str(&format!("{sep}[{position}:{filter_type}:{stream_nr}]"));
             }
 
             if self.hw_context
@@ -197,6 +225,11 @@ impl Filters {
         }
     }
 
+    /// The end of the clip is blended with the next clip.
+    pub fn has_crossfade(&self) -> bool {
+        self.crossfade.is_some()
+    }
+
     pub fn cmd(&mut self) -> Vec<String> {
         if !self.output_chain.is_empty() {
             return self.output_chain.clone();
@@ -232,6 +265,10 @@ impl Filters {
             f_chain.push_str(&self.audio_chain);
         }
 
+        if let Some(crossfade) = self.crossfade {
+            self.crossfade_chain(&mut f_chain, crossfade);
+        }
+
         if !f_chain.is_empty() {
             cmd.push("-filter_complex".to_string());
             cmd.push(f_chain);
@@ -240,6 +277,57 @@ impl Filters {
         cmd
     }
 
+    fn crossfade_chain(&mut self, f_chain: &mut String, crossfade: Crossfade) {
+        let Crossfade {
+            position,
+            offset,
+            duration,
+            audio_tracks,
+        } = crossfade;
+        let (width, height) = (self.config.processing.width, self.config.processing.height);
+        let fps = self.config.processing.fps;
+        let v_out = if self.video_last >= 0 {
+            format!("[vout{}]", self.video_last)
+        } else {
+            "[0:v]".to_string()
+        };
+
+        if self.video_last == -1 || self.output_map.contains(&v_out) {
+            // xfade needs the same size, frame rate and time base on both inputs
+            f_chain.push_str(&format!(
+                ";{v_out}settb=AVTB,setsar=1,format=yuv420p[vxa];[{position}:v]scale={width}:{height}:force_original_aspect_ratio=decrease,pad={width}:{height}:(ow-iw)/2:(oh-ih)/2,fps={fps},settb=AVTB,setsar=1,format=yuv420p[vxb];[vxa][vxb]xfade=transition=fade:duration={duration}:offset={offset}[vxf]"
+            ));
+
+            if self.video_last == -1 {
+                // video has no own filter chain, so it is not mapped yet
+                self.output_map.splice(0..0, vec_strings!["-map", "[vxf]"]);
+                self.video_last = 0;
+            } else {
+                for m in &mut self.output_map {
+                    if *m == v_out {
+                        "[vxf]".clone_into(m);
+                    }
+                }
+            }
+        }
+
+        for i in 0..audio_tracks {
+            let a_out = format!("[aout{i}]");
+
+            if self.output_map.contains(&a_out) {
+                f_chain.push_str(&format!(
+                    ";{a_out}[{position}:a:{i}]acrossfade=d={duration}[axf{i}]"
+                ));
+
+                for m in &mut self.output_map {
+                    if *m == a_out {
+                        *m = format!("[axf{i}]");
+                    }
+                }
+            }
+        }
+    }
+
     pub fn map(&mut self) -> Vec<String> {
         if (!self.output_chain.is_empty() && self.config.processing.override_filter)
             || (self
@@ -386,6 +474,41 @@ fn pad(config: &PlayoutConfig, chain: &mut Filters, aspect: f64) {
     }
 }
 
+/// Cut the clip to the channel aspect, in the center.
+fn crop(config: &PlayoutConfig, chain: &mut Filters) {
+    let (numerator, denominator) = fraction(config.processing.aspect, 100);
+
+    chain.add(
+        &format!(
+            "crop='min(iw,ih*{numerator}/{denominator})':'min(ih,iw*{denominator}/{numerator})'"
+        ),
+        0,
+        Video,
+    );
+}
+
+/// Fit a clip with another aspect ratio, with the aspect mode of the item or the channel.
+/// Stretching needs no filter, the scaling does it.
+fn fit_aspect(config: &PlayoutConfig, chain: &mut Filters, node: &Media, aspect: f64) {
+    if is_close(aspect, config.processing.aspect, 0.03) {
+        return;
+    }
+
+    let mode = node.aspect_mode.unwrap_or(config.processing.aspect_mode);
+
+    info!(target: Target::file_mail(), channel = config.general.channel_id;
+        "Aspect <yellow>{aspect:.3}</> differs from <yellow>{:.3}</>, {mode}: <b><magenta>{}</></b>",
+        config.processing.aspect,
+        node.source
+    );
+
+    match mode {
+        AspectMode::Pad => pad(config, chain, aspect),
+        AspectMode::Crop => crop(config, chain),
+        AspectMode::Stretch => {}
+    }
+}
+
 fn fps(config: &PlayoutConfig, chain: &mut Filters, fps: f64) {
     if fps != config.processing.fps {
         let fps_filter = match config.advanced.filter.fps.clone() {
@@ -450,7 +573,44 @@ fn fade(
         }
     }
 
-    if node.seek > 0.0 || node.unit == Ingest {
+    // fade through black at the switch with the live ingest, each side with half of the length
+    let live_duration = config.ingest.transition_duration as f64 / 2000.0;
+    let live_fade = config.ingest.transition == Transition::FadeBlack && live_duration > 0.0;
+
+    if live_fade && node.live_out {
+        chain.add(
+            &format!("{t}fade=out:st=0:d={}", node.out - node.seek),
+            nr,
+            filter_type,
+        );
+
+        return;
+    }
+
+    if config.processing.transition == Transition::FadeBlack && node.unit == Decoder {
+        // fade out and in again, each with half of the transition length
+        let duration = config.processing.transition_duration as f64 / 2000.0;
+        let length = node.out - node.seek;
+
+        if duration > 0.0 && length > duration * 2.0 {
+            chain.add(&format!("{t}fade=in:st=0:d={duration}"), nr, filter_type);
+            chain.add(
+                &format!("{t}fade=out:st={}:d={duration}", length - duration),
+                nr,
+                filter_type,
+            );
+
+            return;
+        }
+    }
+
+    if live_fade && (node.live_in || node.unit == Ingest) {
+        chain.add(
+            &format!("{t}fade=in:st=0:d={live_duration}"),
+            nr,
+            filter_type,
+        );
+    } else if (node.seek > 0.0 && !node.transition_in) || node.unit == Ingest {
         let mut fade_in = format!("{t}fade=in:st=0:d=0.5");
 
         if t == "a" {
@@ -479,16 +639,121 @@ fn fade(
     }
 }
 
-fn overlay(config: &PlayoutConfig, chain: &mut Filters, node: &mut Media) {
-    if config.processing.add_logo
-        && Path::new(&config.processing.logo_path).is_file()
-        && &node.category != "advertisement"
+fn is_image(source: &str) -> bool {
+    source
+        .rsplit_once('.')
+        .is_some_and(|(_, e)| IMAGE_FORMAT.contains(&e.to_lowercase().as_str()))
+}
+
+/// Prepare the crossfade from the node to the next clip.
+///
+/// The head of the next clip is added as extra input to the decoder command
+/// and gets blended into the end of the node. Returns the seconds,
+/// which the next clip has to start later, because they are already played.
+pub fn crossfade(config: &PlayoutConfig, node: &mut Media, next: &Media) -> f64 {
+    let duration = config.processing.transition_duration as f64 / 1000.0;
+    let length = node.out - node.seek;
+    let has_video = |m: &Media| m.probe.as_ref().is_some_and(|p| !p.video.is_empty());
+
+    if config.processing.transition != Transition::Crossfade
+        || duration <= 0.0
+        || node.skip
+        || node.unit != Decoder
+        || node.next_ad
+        || config.output.mode == HLS
+        || config.processing.audio_only
+        || config.processing.copy_audio
+        || config.processing.copy_video
+        || config.processing.override_filter
+        || length < duration * 2.0
+        || next.out - next.seek < duration * 2.0
+        || !has_video(node)
+        || !has_video(next)
+        || is_image(&node.source)
+        || is_image(&next.source)
     {
-        let logo_path = config
-            .processing
-            .logo_path
-            .replace('\\', "/")
-            .replace(':', "\\\\:");
+        return 0.0;
+    }
+
+    let (Some(cmd), Some(filter)) = (node.cmd.as_mut(), node.filter.as_mut()) else {
+        return 0.0;
+    };
+
+    if filter.hw_context || !filter.output_chain.is_empty() {
+        return 0.0;
+    }
+
+    let position = cmd.iter().filter(|c| *c == "-i").count() as i32;
+
+    let start = next.trim_in.unwrap_or_default() + next.seek;
+
+    if start > 0.5 || (next.is_trimmed() && start > 0.0) {
+        cmd.append(&mut vec_strings!["-ss", start]);
+    }
+
+    cmd.append(&mut vec_strings!["-t", duration, "-i", next.source.clone()]);
+
+    // separate audio files are not blended, the audio gets only cut
+    let audio_tracks = if next.audio.is_empty() {
+        next.probe.as_ref().map_or(0, |p| p.audio.len() as i32)
+    } else {
+        0
+    };
+
+    filter.crossfade = Some(Crossfade {
+        position,
+        offset: length - duration,
+        duration,
+        audio_tracks,
+    });
+
+    duration
+}
+
+/// Logo of the item from a secondary event, with its visible time range.
+fn event_logo(config: &PlayoutConfig, node: &Media) -> Option<(String, (f64, f64))> {
+    node.events.iter().find_map(|event| match &event.action {
+        SecondaryAction::Logo { path } => {
+            let (_, logo_path) =
+                clean_raw_abs_path(&config.channel.storage, path, ABS_PATH_INDICATOR).ok()?;
+
+            Some((
+                logo_path.to_string_lossy().to_string(),
+                event.range(node.out - node.seek),
+            ))
+        }
+        _ => None,
+    })
+}
+
+/// Logo of the rule for the clip start, the rules are checked again with every clip.
+fn dayparted_logo(config: &PlayoutConfig) -> Option<Option<String>> {
+    let rules = logo::parse_rules(&config.processing.logo_rules).ok()?;
+    let now = time_now(&config.channel.timezone).naive_local();
+
+    logo::active_logo(&rules, now).map(|logo| {
+        logo.and_then(|path| {
+            clean_raw_abs_path(&config.channel.storage, &path, ABS_PATH_INDICATOR)
+                .ok()
+                .map(|(_, p)| p.to_string_lossy().to_string())
+        })
+    })
+}
+
+fn overlay(config: &PlayoutConfig, chain: &mut Filters, node: &mut Media) {
+    let event_logo = event_logo(config, node);
+    let (logo_path, range) = match event_logo {
+        Some((path, range)) => (path, Some(range)),
+        None => match dayparted_logo(config) {
+            Some(Some(path)) => (path, None),
+            Some(None) => return,
+            None if config.processing.add_logo => (config.processing.logo_path.clone(), None),
+            None => return,
+        },
+    };
+
+    if Path::new(&logo_path).is_file() && &node.category != "advertisement" {
+        let logo_path = logo_path.replace('\\', "/").replace(':', "\\\\:");
 
         let movie = match &config.advanced.filter.logo {
             Some(logo) => {
@@ -531,15 +796,61 @@ fn overlay(config: &PlayoutConfig, chain: &mut Filters, node: &mut Media) {
             chain.add(&scale, 0, Video);
         }
 
-        let overlay = match &config.advanced.filter.overlay_logo {
+        let mut overlay = match &config.advanced.filter.overlay_logo {
             Some(ov) => custom_format(ov, &[&config.processing.logo_position]),
             None => format!("overlay={}:shortest=1", config.processing.logo_position),
         };
 
+        if let Some((start, end)) = range {
+            overlay.push_str(&format!(":enable='between(t,{start},{end})'"));
+        }
+
         chain.add(&overlay, 0, Video);
     }
 }
 
+/// Escape a path or value for a filter option, on filtergraph and option level.
+fn escape_option(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        if matches!(c, '\\' | '\'' | ':' | ',' | ';' | '[' | ']') {
+            escaped.push_str("\\\\\\");
+        }
+
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+/// Burn in the subtitle file of the clip. The timestamps are shifted by the seek,
+/// so that the subtitles stay in sync with the clip.
+fn subtitles(config: &PlayoutConfig, chain: &mut Filters, node: &Media) {
+    let Some(file) = node
+        .subtitle_file
+        .as_ref()
+        .filter(|_| config.processing.subtitles)
+    else {
+        return;
+    };
+
+    let mut filter = format!("subtitles=filename={}", escape_option(file));
+
+    if !config.processing.subtitle_style.is_empty() {
+        filter.push_str(&format!(
+            ":force_style={}",
+            escape_option(&config.processing.subtitle_style)
+        ));
+    }
+
+    if node.seek > 0.0 {
+        filter = format!("setpts=PTS+{}/TB,{filter},setpts=PTS-STARTPTS", node.seek);
+    }
+
+    chain.add(&filter, 0, Video);
+}
+
 fn extend_video(config: &PlayoutConfig, chain: &mut Filters, node: &mut Media) {
     if let Some(video_duration) = node
         .probe
@@ -576,6 +887,203 @@ async fn add_text(
     }
 }
 
+/// Frames of the HTML overlay, the encoder listens for them and scales them to the program size.
+fn html_overlay(config: &PlayoutConfig, chain: &mut Filters, node: &Media) {
+    let Some(socket) = config.processing.html_socket.as_ref() else {
+        return;
+    };
+
+    if node.unit != Encoder {
+        return;
+    }
+
+    chain.add(
+        &format!(
+            "movie=tcp\\\\://{}?listen=1:f=png_pipe",
+            socket.replace(':', "\\\\:")
+        ),
+        0,
+        Video,
+    );
+    chain.add(
+        &format!(
+            "format=rgba,scale={}:{}",
+            config.processing.width, config.processing.height
+        ),
+        0,
+        Video,
+    );
+    chain.add("overlay=0:0:eof_action=pass", 0, Video);
+}
+
+/// Voice-over on all audio tracks, its presence ducks the program audio.
+/// The ducking is the dry part of a strong compressor, which the voice-over triggers.
+fn voice_over(config: &PlayoutConfig, chain: &mut Filters, node: &Media) {
+    let Some(socket) = config.ingest.voice_socket.as_ref() else {
+        return;
+    };
+
+    if node.unit != Encoder {
+        return;
+    }
+
+    let tracks = config.processing.audio_tracks.max(1);
+    let layout = match config.processing.audio_channels {
+        1 => "mono".to_string(),
+        2 => "stereo".to_string(),
+        c => format!("{c}c"),
+    };
+    let mix = 1.0 - 10_f64.powf(-config.ingest.voice_duck.abs() / 20.0);
+    let duck = format!(
+        "sidechaincompress=threshold=0.01:ratio=20:attack={}:release={}:mix={mix:.3}",
+        config.ingest.voice_attack, config.ingest.voice_release
+    );
+    let outputs: String = (0..tracks).map(|i| format!("[vo{i}d][vo{i}m]")).collect();
+
+    for i in 0..tracks {
+        let source = if i == 0 {
+            format!(
+                "amovie=tcp\\\\://{}?listen=1:f=s16le,aresample=48000,aformat=sample_fmts=fltp:channel_layouts={layout},asplit={}{outputs};",
+                socket.replace(':', "\\\\:"),
+                tracks * 2
+            )
+        } else {
+            String::new()
+        };
+
+        chain.add(
+            &format!("anull[pg{i}];{source}[pg{i}][vo{i}d]{duck}[dk{i}];[dk{i}][vo{i}m]amix=inputs=2:duration=first:normalize=0"),
+            i,
+            Audio,
+        );
+    }
+}
+
+/// News ticker, crawls continuously on the encoder, in HLS mode it starts again with every clip.
+fn ticker(config: &PlayoutConfig, chain: &mut Filters, node: &Media) {
+    if config.text.ticker && (config.output.mode == HLS || node.unit == Encoder) {
+        chain.add(&v_drawtext::ticker_node(config), 0, Video);
+    }
+}
+
+/// Clock and station ID, drawn continuously by the encoder, or per clip in HLS mode.
+fn clock(config: &PlayoutConfig, chain: &mut Filters, node: &Media) {
+    if config.text.clock && (config.output.mode == HLS || node.unit == Encoder) {
+        let start = time_now(&config.channel.timezone)
+            .naive_local()
+            .and_utc()
+            .timestamp();
+
+        chain.add(&v_drawtext::clock_node(config, start), 0, Video);
+    }
+}
+
+/// Black and silence detection of the program, on the encoder before the overlays.
+fn detect(config: &PlayoutConfig, chain: &mut Filters, node: &Media, filter_type: FilterType) {
+    if node.unit != Encoder || config.output.mode == HLS {
+        return;
+    }
+
+    let print = escape_option("pipe:2");
+
+    match filter_type {
+        Video if config.processing.black_alert > 0.0 && !config.processing.audio_only => {
+            chain.add(&detect::black_filter(config, &print), 0, Video);
+        }
+        Audio if config.processing.silence_alert > 0.0 => {
+            chain.add(&detect::silence_filter(&print), 0, Audio);
+        }
+        _ => {}
+    }
+}
+
+/// Loudness meter of the program, on the encoder.
+fn loudness_meter(config: &PlayoutConfig, chain: &mut Filters, node: &Media) {
+    if node.unit == Encoder && config.output.mode != HLS && config.processing.loudness_meter {
+        chain.add(&meter::meter_filter(&escape_option("pipe:2")), 0, Audio);
+    }
+}
+
+/// Current title at the begin and next title at the end of the item.
+fn now_next(config: &PlayoutConfig, chain: &mut Filters, node: &Media) {
+    if !config.text.now_next
+        || node.unit != Decoder
+        || node.live_out
+        || &node.category == "advertisement"
+    {
+        return;
+    }
+
+    let length = node.out - node.seek;
+    let duration = (config.text.now_next_duration as f64).min(length / 2.0);
+
+    if duration <= 0.0 {
+        return;
+    }
+
+    let title = programme_title(node.title.clone(), &node.source);
+
+    if !title.is_empty() && !config.text.now_next_now.is_empty() {
+        let text = config.text.now_next_now.replace("{title}", &title);
+
+        chain.add(
+            &v_drawtext::now_next_node(config, "now", &text, 0.0, duration),
+            0,
+            Video,
+        );
+    }
+
+    if let Some(next) = node.next_title.as_ref().filter(|t| !t.is_empty()) {
+        if !config.text.now_next_next.is_empty() {
+            let text = config.text.now_next_next.replace("{title}", next);
+
+            chain.add(
+                &v_drawtext::now_next_node(config, "next", &text, length - duration, length),
+                0,
+                Video,
+            );
+        }
+    }
+}
+
+/// Countdown to the next ingest window, when it begins during the clip.
+fn ingest_countdown(config: &PlayoutConfig, chain: &mut Filters, node: &Media) {
+    if node.unit != Decoder || config.ingest.countdown <= 0 || !config.ingest.any() {
+        return;
+    }
+
+    let Ok(windows) = parse_windows(&config.ingest.windows) else {
+        return;
+    };
+    let now = time_now(&config.channel.timezone).naive_local();
+    let Some(start) = next_start(&windows, now) else {
+        return;
+    };
+
+    let remaining = (start - now).num_milliseconds() as f64 / 1000.0;
+    let begin = (remaining - config.ingest.countdown as f64).max(0.0);
+
+    if begin >= node.out - node.seek {
+        return;
+    }
+
+    let font = if Path::new(&config.text.font_path).is_file() {
+        format!(":fontfile='{}'", config.text.font_path)
+    } else {
+        String::new()
+    };
+    let left = format!("({remaining:.3}-t)");
+
+    chain.add(
+        &format!(
+            "drawtext=text='Live in %{{eif\\:trunc({left}/60)\\:d}}\\:%{{eif\\:mod({left},60)\\:d\\:2}}':{}{font}:enable='between(t,{begin:.3},{remaining:.3})'",
+            config.text.style
+        ),
+        0,
+        Video,
+    );
+}
+
 fn add_audio(config: &PlayoutConfig, chain: &mut Filters, node: &Media, nr: i32) {
     let audio = match config.advanced.filter.aevalsrc.clone() {
         Some(aevalsrc) => custom_format(&aevalsrc, &[node.out - node.seek]),
@@ -588,6 +1096,96 @@ fn add_audio(config: &PlayoutConfig, chain: &mut Filters, node: &Media, nr: i32)
     chain.add(&audio, nr, Audio);
 }
 
+/// Source stream for the output audio track: from the `audio_map` of the playlist item,
+/// by the language of the track, or the stream with the same number.
+/// Without matching stream, the track gets silence.
+fn audio_stream(config: &PlayoutConfig, node: &Media, nr: i32) -> Option<i32> {
+    if Path::new(&node.audio).is_file() {
+        return Some(nr);
+    }
+
+    let streams = node.probe.as_ref().map_or(&[][..], |p| p.audio.as_slice());
+
+    if !node.audio_map.is_empty() {
+        return node
+            .audio_map
+            .get(nr as usize)
+            .copied()
+            .filter(|s| *s >= 0 && (*s as usize) < streams.len());
+    }
+
+    let languages = &config.processing.audio_languages;
+
+    if let Some(language) = languages.get(nr as usize) {
+        // the same language can be used for more tracks, like for audio description
+        let nth = languages[..nr as usize]
+            .iter()
+            .filter(|l| *l == language)
+            .count();
+
+        if let Some((i, _)) = streams
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| {
+                s.language
+                    .as_ref()
+                    .is_some_and(|l| l.eq_ignore_ascii_case(language))
+            })
+            .nth(nth)
+        {
+            return Some(i as i32);
+        }
+    }
+
+    streams.get(nr as usize).map(|_| nr)
+}
+
+/// Remix the channels of the source stream, by the layout of the playlist item or the channel rules.
+/// The remix is the first filter of the track, `merge` takes the following stream as second input.
+fn audio_layout(config: &PlayoutConfig, chain: &mut Filters, node: &Media, nr: i32, stream: i32) {
+    let id = config.general.channel_id;
+    let probe = if Path::new(&node.audio).is_file() {
+        node.probe_audio.as_ref()
+    } else {
+        node.probe.as_ref()
+    };
+    let Some(channels) = probe
+        .and_then(|p| p.audio.get(stream as usize))
+        .map(|a| a.channels)
+    else {
+        return;
+    };
+
+    let remix = if node.audio_layout.is_empty() {
+        let rules = layout::parse_rules(&config.processing.audio_layout).unwrap_or_default();
+
+        layout::find_remix(&rules, channels).cloned()
+    } else {
+        match node.audio_layout.parse::<layout::Remix>() {
+            Ok(remix) => Some(remix),
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = id; "{e}, in <b><magenta>{}</></b>", node.key);
+                None
+            }
+        }
+    };
+
+    if let Some(remix) = remix {
+        let next = format!("[{}:a:{}]", chain.audio_position, stream + 1);
+
+        if remix == layout::Remix::Merge
+            && probe.is_none_or(|p| p.audio.len() <= stream as usize + 1)
+        {
+            warn!(target: Target::file_mail(), channel = id; "No second audio stream to merge in <b><magenta>{}</></b>", node.key);
+            return;
+        }
+
+        debug!(target: Target::file_mail(), channel = id; "Audio layout of track {nr} with {channels} channels: <yellow>{remix}</>");
+
+        chain.add(&remix.filter(&next), nr, Audio);
+    }
+}
+
 fn extend_audio(config: &PlayoutConfig, chain: &mut Filters, node: &mut Media, nr: i32) {
     if !Path::new(&node.audio).is_file() {
         if let Some(audio_duration) = node
@@ -609,6 +1207,15 @@ fn extend_audio(config: &PlayoutConfig, chain: &mut Filters, node: &mut Media, n
     }
 }
 
+/// Second pass of the loudness normalization, on the measured audio track.
+fn loudnorm(config: &PlayoutConfig, chain: &mut Filters, node: &Media, nr: i32) {
+    if config.processing.loudnorm && nr == config.processing.audio_track_index.max(0) {
+        if let Some(loudness) = &node.loudness {
+            chain.add(&loudness.filter(config), nr, Audio);
+        }
+    }
+}
+
 fn audio_volume(config: &PlayoutConfig, chain: &mut Filters, nr: i32) {
     if config.processing.volume != 1.0 {
         let volume = match config.advanced.filter.volume.clone() {
@@ -704,6 +1311,31 @@ fn custom(filter: &str, chain: &mut Filters, nr: i32, filter_type: FilterType) {
     }
 }
 
+/// Video and audio chain of the filter template from the playlist item, or from the channel.
+fn filter_template(config: &PlayoutConfig, node: &Media) -> (String, String) {
+    let name = if node.filter_template.is_empty() {
+        &config.processing.filter_template
+    } else {
+        &node.filter_template
+    };
+
+    if node.unit == Ingest || name.is_empty() {
+        return (String::new(), String::new());
+    }
+
+    match config.templates.iter().find(|t| &t.name == name) {
+        Some(template) => (template.video_filter.clone(), template.audio_filter.clone()),
+        None => {
+            warn!(target: Target::file_mail(), channel = config.general.channel_id;
+                "Filter template <yellow>{name}</> not found, in <b><magenta>{}</></b>",
+                node.key
+            );
+
+            (String::new(), String::new())
+        }
+    }
+}
+
 pub async fn filter_chains(
     config: &PlayoutConfig,
     node: &mut Media,
@@ -727,10 +1359,19 @@ pub async fn filter_chains(
     }
 
     if node.unit == Encoder {
+        detect(config, &mut filters, node, Video);
+
         if !config.processing.audio_only {
+            html_overlay(config, &mut filters, node);
             add_text(config, &mut filters, node, filter_chain).await;
+            ticker(config, &mut filters, node);
+            clock(config, &mut filters, node);
         }
 
+        voice_over(config, &mut filters, node);
+        loudness_meter(config, &mut filters, node);
+        detect(config, &mut filters, node, Audio);
+
         if let Some(f) = config.output.output_filter.clone() {
             process_output_filters(config, &mut filters, &f);
         } else if config.output.output_count > 1 && !config.processing.audio_only {
@@ -753,7 +1394,7 @@ pub async fn filter_chains(
                 let frame_per_sec = fps_calc(&v_stream.frame_rate, 1.0);
 
                 deinterlace(config, &mut filters, &v_stream.field_order);
-                pad(config, &mut filters, aspect);
+                fit_aspect(config, &mut filters, node, aspect);
                 fps(config, &mut filters, frame_per_sec);
                 scale(config, &mut filters, v_stream.width, v_stream.height);
                 setdar(config, &mut filters, aspect);
@@ -765,7 +1406,12 @@ pub async fn filter_chains(
             scale(config, &mut filters, None, None);
         }
 
+        subtitles(config, &mut filters, node);
         add_text(config, &mut filters, node, filter_chain).await;
+        ticker(config, &mut filters, node);
+        clock(config, &mut filters, node);
+        now_next(config, &mut filters, node);
+        ingest_countdown(config, &mut filters, node);
         fade(config, &mut filters, node, 0, Video);
         overlay(config, &mut filters, node);
     }
@@ -776,10 +1422,12 @@ pub async fn filter_chains(
         custom::filter_node(config.general.channel_id, &config.processing.custom_filter)
     };
 
+    let (template_vf, template_af) = filter_template(config, node);
     let (list_vf, list_af) = custom::filter_node(config.general.channel_id, &node.custom_filter);
 
     if !config.processing.copy_video {
         custom(&proc_vf, &mut filters, 0, Video);
+        custom(&template_vf, &mut filters, 0, Video);
         custom(&list_vf, &mut filters, 0, Video);
     }
 
@@ -795,19 +1443,21 @@ pub async fn filter_chains(
 
     if !config.processing.copy_audio {
         for i in audio_indexes {
-            if node
-                .probe
-                .as_ref()
-                .and_then(|p| p.audio.get(i as usize))
-                .is_some()
-                || Path::new(&node.audio).is_file()
-            {
+            if let Some(stream) = audio_stream(config, node, i) {
+                if stream != i {
+                    filters.audio_streams.insert(i, stream);
+                }
+
+                audio_layout(config, &mut filters, node, i, stream);
                 extend_audio(config, &mut filters, node, i);
             } else if node.unit == Decoder && !node.source.contains("color=c=") {
-                warn!(target: Target::file_mail(), channel = config.general.channel_id;
-                    "Missing audio track (id {i}) from <b><magenta>{}</></b>",
-                    node.key
-                );
+                // silence on purpose, when the track is mapped to -1
+                if node.audio_map.get(i as usize) != Some(&-1) {
+                    warn!(target: Target::file_mail(), channel = config.general.channel_id;
+                        "Missing audio track (id {i}) from <b><magenta>{}</></b>",
+                        node.key
+                    );
+                }
 
                 add_audio(config, &mut filters, node, i);
             }
@@ -816,10 +1466,12 @@ pub async fn filter_chains(
             // is important for split filter in HLS mode
             filters.add("anull", i, Audio);
 
+            loudnorm(config, &mut filters, node, i);
             fade(config, &mut filters, node, i, Audio);
             audio_volume(config, &mut filters, i);
 
             custom(&proc_af, &mut filters, i, Audio);
+            custom(&template_af, &mut filters, i, Audio);
             custom(&list_af, &mut filters, i, Audio);
         }
     } else if config.processing.audio_track_index > -1 {
@@ -5,21 +5,79 @@ use tokio::sync::Mutex;
 
 use crate::player::{
     controller::ProcessUnit::*,
-    utils::{custom_format, Media},
+    utils::{custom_format, ticker, Media},
 };
 use crate::utils::config::PlayoutConfig;
 
+fn font(config: &PlayoutConfig) -> String {
+    if Path::new(&config.text.font_path).is_file() {
+        format!(":fontfile='{}'", config.text.font_path)
+    } else {
+        String::new()
+    }
+}
+
+/// Crawl from right to left, the text file is reloaded for new feed entries.
+pub fn ticker_node(config: &PlayoutConfig) -> String {
+    format!(
+        "drawtext@ticker=textfile='{}':reload=1:expansion=none:x='w-mod(t*{},w+tw)':{}{}",
+        ticker::text_file(config).display(),
+        config.text.ticker_speed,
+        config.text.ticker_style,
+        font(config)
+    )
+}
+
+/// Text of the now/next overlay, shown between `begin` and `end` of the clip.
+pub fn now_next_node(
+    config: &PlayoutConfig,
+    name: &str,
+    text: &str,
+    begin: f64,
+    end: f64,
+) -> String {
+    format!(
+        "drawtext@{name}=text={}:expansion=none:enable='between(t,{begin:.3},{end:.3})':{}{}",
+        super::escape_option(text),
+        config.text.now_next_style,
+        font(config)
+    )
+}
+
+/// Clock with the time of the channel: the timestamps of the frames count from `start`,
+/// which is the local time of the channel as unix time.
+pub fn clock_node(config: &PlayoutConfig, start: i64) -> String {
+    let mut format = String::new();
+
+    for c in config
+        .text
+        .clock_format
+        .replace("{channel}", &config.channel.name.replace('%', "%%"))
+        .chars()
+    {
+        // escape the argument of the text expansion
+        if matches!(c, '\\' | '\'' | ':' | '{' | '}') {
+            format.push('\\');
+        }
+
+        format.push(c);
+    }
+
+    format!(
+        "drawtext@clock=text={}:{}{}",
+        super::escape_option(&format!("%{{pts:gmtime:{start}:{format}}}")),
+        config.text.clock_style,
+        font(config)
+    )
+}
+
 pub async fn filter_node(
     config: &PlayoutConfig,
     node: Option<&Media>,
     filter_chain: &Option<Arc<Mutex<Vec<String>>>>,
 ) -> String {
     let mut filter = String::new();
-    let font = if Path::new(&config.text.font_path).is_file() {
-        format!(":fontfile='{}'", config.text.font_path)
-    } else {
-        String::new()
-    };
+    let font = font(config);
 
     let zmq_socket = match node.map(|n| n.unit) {
         Some(Ingest) => config.text.zmq_server_socket.clone(),
@@ -0,0 +1,210 @@
+use std::{process::Stdio, sync::atomic::Ordering};
+
+use log::*;
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    process::Command,
+    time::{sleep, timeout, Duration},
+};
+
+use crate::db::{handles, models::LiveEvent};
+use crate::player::{
+    controller::{ChannelManager, ProcessUnit::*},
+    utils::{prepare_output_cmd, Media},
+};
+use crate::utils::{
+    config::{OutputMode::HLS, PlayoutConfig},
+    errors::ServiceError,
+    logging::{fmt_cmd, log_line, StderrLog, Target},
+    time_machine::time_now,
+};
+use crate::vec_strings;
+
+/// Max time between two checks for the next event, so changed events are noticed.
+const EVENT_POLL: Duration = Duration::from_secs(5);
+
+/// Wait time before a lost event source gets opened again.
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+fn now_millis() -> i64 {
+    time_now(&None).timestamp_millis()
+}
+
+/// Command for pulling the event source. In HLS mode it writes directly to the playlist,
+/// otherwise it sends the processed stream to stdout, like the ingest server.
+async fn event_cmd(
+    manager: &ChannelManager,
+    config: &PlayoutConfig,
+    event: &LiveEvent,
+    remaining: f64,
+) -> Vec<String> {
+    let mut cmd = vec_strings!["-hide_banner", "-nostats", "-v", "level+info"];
+    let mut dummy_media = Media::new(0, &event.name, false).await;
+    let vtt_dummy = config
+        .channel
+        .storage
+        .join(config.processing.vtt_dummy.clone().unwrap_or_default());
+    let with_vtt = config.processing.vtt_enable && vtt_dummy.is_file();
+
+    dummy_media.unit = Ingest;
+    dummy_media.add_filter(config, &manager.filter_chain).await;
+
+    if let Some(ingest_input_cmd) = &config.advanced.ingest.input_cmd {
+        cmd.append(&mut ingest_input_cmd.clone());
+    }
+
+    cmd.append(&mut vec_strings![
+        "-t",
+        format!("{remaining:.3}"),
+        "-i",
+        event.source
+    ]);
+
+    if with_vtt {
+        cmd.append(&mut vec_strings!["-i", vtt_dummy.to_string_lossy()]);
+    }
+
+    if config.output.mode == HLS {
+        return prepare_output_cmd(config, cmd, &dummy_media.filter);
+    }
+
+    if let Some(mut filter) = dummy_media.filter {
+        cmd.append(&mut filter.cmd());
+        cmd.append(&mut filter.map());
+    }
+
+    if with_vtt {
+        cmd.append(&mut vec_strings!("-map", "1:s", "-c:s", "copy"));
+    }
+
+    if let Some(mut processing_cmd) = config.processing.cmd.clone() {
+        cmd.append(&mut processing_cmd);
+    }
+
+    cmd
+}
+
+/// Pull the event source until the event ends. When the source breaks,
+/// the playout goes back to the playlist and the source is opened again.
+async fn run_event(manager: &ChannelManager, mut event: LiveEvent) -> Result<(), ServiceError> {
+    let config = manager.config.lock().await.clone();
+    let id = config.general.channel_id;
+
+    info!(target: Target::file_mail(), channel = id; "Start live event <yellow>{}</> from <b><magenta>{}</></b>", event.name, event.source);
+
+    while manager.is_alive.load(Ordering::SeqCst) && now_millis() < event.end_time * 1000 {
+        let remaining = (event.end_time * 1000 - now_millis()) as f64 / 1000.0;
+        let cmd = event_cmd(manager, &config, &event, remaining).await;
+
+        debug!(target: Target::file_mail(), channel = id;
+            "Event CMD: <bright-blue>ffmpeg {}</>",
+            fmt_cmd(&cmd)
+        );
+
+        let mut proc = Command::new("ffmpeg")
+            .args(cmd)
+            .kill_on_drop(true)
+            .stdout(if config.output.mode == HLS {
+                Stdio::null()
+            } else {
+                Stdio::piped()
+            })
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let event_err = BufReader::new(proc.stderr.take().unwrap());
+
+        *manager.event_stdout.lock().await = proc.stdout.take();
+        *manager.event.lock().await = Some(proc);
+
+        let mut lines = event_err.lines();
+        let mut log = StderrLog::new(&config.logging, Event, id);
+        let level = config.logging.level(Event);
+
+        let monitor = async {
+            while let Ok(Some(line)) = lines.next_line().await {
+                if !manager.event_is_alive.load(Ordering::SeqCst) && line.contains("Input #0") {
+                    info!(target: Target::file_mail(), channel = id; "Switch from {} to live event <yellow>{}</>", config.processing.mode, event.name);
+
+                    manager.event_is_alive.store(true, Ordering::SeqCst);
+                    manager.list_init.store(true, Ordering::SeqCst);
+
+                    // the event has priority over a pushed ingest stream
+                    if manager.ingest_is_alive.load(Ordering::SeqCst) {
+                        manager.stop(Ingest).await;
+                    }
+
+                    if config.output.mode == HLS {
+                        manager.stop(Decoder).await;
+                    }
+                }
+
+                if log.push(&line) {
+                    log_line(id, &line, &level);
+                }
+            }
+        };
+
+        // ffmpeg stops reading at the end time, but a stalled source needs to be stopped
+        let end = Duration::from_millis((event.end_time * 1000 - now_millis()).max(0) as u64);
+
+        if timeout(end + RECONNECT_DELAY, monitor).await.is_err() {
+            manager.stop(Event).await;
+        }
+
+        if log.failed {
+            log.dump(&config.logging).await;
+        }
+
+        if manager.event_is_alive.swap(false, Ordering::SeqCst) {
+            info!(target: Target::file_mail(), channel = id; "Switch from live event <yellow>{}</> to {}", event.name, config.processing.mode);
+        }
+
+        manager.wait(Event).await;
+
+        // event can be changed or deleted while it is running
+        match handles::select_live_event(&manager.db_pool, id, event.id).await? {
+            Some(e) => event = e,
+            None => break,
+        }
+
+        if now_millis() < event.end_time * 1000 {
+            warn!(target: Target::file_mail(), channel = id; "Live event source <b><magenta>{}</></b> is lost, try again", event.source);
+
+            sleep(RECONNECT_DELAY).await;
+        }
+    }
+
+    Ok(())
+}
+
+/// Scheduled Live Events
+///
+/// Wait for the next event of the channel and switch to its source at the start time.
+/// At the end time the player goes back to the playlist or folder.
+pub async fn event_scheduler(manager: ChannelManager) {
+    let id = manager.id;
+
+    while manager.is_alive.load(Ordering::SeqCst) {
+        let now = now_millis();
+
+        match handles::select_next_live_event(&manager.db_pool, id, now / 1000).await {
+            Ok(Some(event)) if event.start_time * 1000 <= now => {
+                if let Err(e) = run_event(&manager, event).await {
+                    error!(target: Target::file_mail(), channel = id; "Live event failed: {e}");
+                    sleep(RECONNECT_DELAY).await;
+                }
+            }
+            Ok(Some(event)) => {
+                let wait = Duration::from_millis((event.start_time * 1000 - now) as u64);
+
+                sleep(wait.min(EVENT_POLL)).await;
+            }
+            Ok(None) => sleep(EVENT_POLL).await,
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = id; "Read live events failed: {e}");
+                sleep(EVENT_POLL).await;
+            }
+        }
+    }
+}
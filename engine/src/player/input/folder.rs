@@ -1,13 +1,135 @@
-use std::sync::atomic::Ordering;
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    path::Path,
+    sync::atomic::Ordering,
+};
 
 use log::*;
-use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 
+use crate::file::{utils::loudness::clip_loudness, WalkOptions};
 use crate::player::{
     controller::ChannelManager,
-    utils::{include_file_extension, time_in_seconds, Media},
+    filter::crossfade,
+    utils::{
+        include_file_extension, playlist_clock, seek_and_length, subtitle_file, time_in_seconds,
+        Media,
+    },
+};
+use crate::utils::{
+    config::{PlayoutConfig, Transition},
+    logging::Target,
+    rules::RuleSet,
 };
-use crate::utils::{config::PlayoutConfig, logging::Target};
+
+/// Begin of a clip on the playlist clock, which the status uses for the played time.
+fn clip_begin(config: &PlayoutConfig) -> f64 {
+    playlist_clock(
+        &config.channel.timezone,
+        config.playlist.start_sec.unwrap_or_default(),
+    )
+    .1
+}
+
+/// Played clips for the no-repeat window in shuffle mode. The time is the sum
+/// of the played lengths, so the window works in playout and in the generator
+/// and is kept over reshuffles.
+#[derive(Debug, Clone, Default)]
+struct PlayHistory {
+    /// Source and begin of the played clips, the latest at the back.
+    clips: VecDeque<(String, f64)>,
+    elapsed: f64,
+}
+
+impl PlayHistory {
+    fn push(&mut self, source: &str, length: f64, items: usize, time: f64) {
+        self.clips.push_back((source.to_string(), self.elapsed));
+        self.elapsed += length.max(0.0);
+
+        while self.clips.len() > items
+            && self
+                .clips
+                .front()
+                .is_some_and(|(_, begin)| self.elapsed - begin >= time)
+        {
+            self.clips.pop_front();
+        }
+    }
+
+    /// Begin of the last play of the clip.
+    fn last_played(&self, source: &str) -> Option<f64> {
+        self.clips
+            .iter()
+            .rev()
+            .find(|(s, _)| s == source)
+            .map(|(_, begin)| *begin)
+    }
+
+    fn is_recent(&self, source: &str, items: usize, time: f64) -> bool {
+        let count = self.clips.len();
+
+        self.clips.iter().enumerate().any(|(i, (s, begin))| {
+            s == source && (count - i <= items || self.elapsed - begin < time)
+        })
+    }
+
+    /// Find the clip, which should play at `position`: the first one from there,
+    /// which is not in the window, or the one played the longest time ago.
+    fn pick(&self, list: &[Media], position: usize, items: usize, time: f64) -> usize {
+        let mut oldest = (position, f64::MAX);
+
+        for (index, item) in list.iter().enumerate().skip(position) {
+            if !self.is_recent(&item.source, items, time) {
+                return index;
+            }
+
+            if let Some(begin) = self
+                .last_played(&item.source)
+                .filter(|begin| *begin < oldest.1)
+            {
+                oldest = (index, begin);
+            }
+        }
+
+        oldest.0
+    }
+}
+
+/// Subfolder of a clip below the storage root.
+fn subfolder(root: &Path, source: &str) -> String {
+    Path::new(source)
+        .strip_prefix(root)
+        .ok()
+        .and_then(Path::parent)
+        .and_then(|p| p.components().next())
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Group the list positions by the weighted subfolders, folders without clips are left out.
+fn weighted_groups(
+    root: &Path,
+    list: &[Media],
+    weights: &BTreeMap<String, u32>,
+) -> Vec<(String, u32, Vec<usize>)> {
+    let mut groups: Vec<(String, u32, Vec<usize>)> = weights
+        .iter()
+        .filter(|(_, weight)| **weight > 0)
+        .map(|(folder, weight)| (folder.clone(), *weight, vec![]))
+        .collect();
+
+    for (index, item) in list.iter().enumerate() {
+        let folder = subfolder(root, &item.source);
+
+        if let Some(group) = groups.iter_mut().find(|g| g.0 == folder) {
+            group.2.push(index);
+        }
+    }
+
+    groups.retain(|g| !g.2.is_empty());
+
+    groups
+}
 
 /// Folder Sources
 ///
@@ -16,6 +138,16 @@ use crate::utils::{config::PlayoutConfig, logging::Target};
 pub struct FolderSource {
     manager: ChannelManager,
     current_node: Media,
+    /// Index and length of the next clip, which head is played in a crossfade.
+    transition_next: Option<(usize, f64)>,
+    rules: RuleSet,
+    /// Due items from the insert rules, they are played before the next clip.
+    inserts: VecDeque<Media>,
+    history: PlayHistory,
+    /// Played clips per weighted subfolder in the current round.
+    cursors: HashMap<String, usize>,
+    /// Position and source of the next weighted clip, when it is picked before for a crossfade.
+    upcoming: Option<(usize, String)>,
 }
 
 impl FolderSource {
@@ -25,6 +157,7 @@ impl FolderSource {
         let mut media_list = vec![];
         let mut index: usize = 0;
         let storage = manager.storage.lock().await.clone();
+        let walk_options = WalkOptions::from_config(config);
 
         if !config.storage.paths.is_empty() && config.general.generate.is_some() {
             path_list.extend(&config.storage.paths);
@@ -42,7 +175,7 @@ impl FolderSource {
                 error!(target: Target::file_mail(), channel = id; "Path not exists: <b><magenta>{path:?}</></b>");
             }
 
-            match storage.walk_dir(path).await {
+            match storage.walk_dir(path, &walk_options).await {
                 Ok(paths) => {
                     for p in paths {
                         if storage.is_file(&p).await && include_file_extension(config, &p) {
@@ -92,9 +225,22 @@ impl FolderSource {
 
         *manager.current_list.lock().await = media_list;
 
+        // the generator applies the rules on the planned time of the playlist
+        let rules = if config.general.generate.is_none() {
+            RuleSet::new(config, &storage).await
+        } else {
+            RuleSet::default()
+        };
+
         Self {
             manager,
             current_node: Media::default(),
+            transition_next: None,
+            rules,
+            inserts: VecDeque::new(),
+            history: PlayHistory::default(),
+            cursors: HashMap::new(),
+            upcoming: None,
         }
     }
 
@@ -104,9 +250,212 @@ impl FolderSource {
         Self {
             manager: manager.clone(),
             current_node: Media::default(),
+            transition_next: None,
+            rules: RuleSet::default(),
+            inserts: VecDeque::new(),
+            history: PlayHistory::default(),
+            cursors: HashMap::new(),
+            upcoming: None,
+        }
+    }
+
+    /// Move a clip to `position`, which has not played in the no-repeat window.
+    async fn avoid_repeat(&self, config: &PlayoutConfig, position: usize) {
+        let items = usize::try_from(config.storage.no_repeat_items).unwrap_or_default();
+        let time = config.storage.no_repeat_time;
+
+        if !config.storage.shuffle || (items == 0 && time <= 0.0) {
+            return;
+        }
+
+        let mut nodes = self.manager.current_list.lock().await;
+
+        if position >= nodes.len() {
+            return;
+        }
+
+        let index = self.history.pick(&nodes, position, items, time);
+
+        if index != position {
+            nodes.swap(position, index);
+            nodes[position].index = Some(position);
+            nodes[index].index = Some(index);
         }
     }
 
+    /// Pick a subfolder by its weight and take its next clip. When a subfolder is through,
+    /// it starts again from the beginning, shuffled again in shuffle mode.
+    async fn pick_weighted(&mut self, config: &PlayoutConfig) -> Option<usize> {
+        if config.storage.weights.is_empty() {
+            return None;
+        }
+
+        let mut nodes = self.manager.current_list.lock().await;
+        let groups = weighted_groups(&config.channel.storage, &nodes, &config.storage.weights);
+        let total: u32 = groups.iter().map(|g| g.1).sum();
+
+        if total == 0 {
+            return None;
+        }
+
+        let mut rng = StdRng::from_os_rng();
+        let mut roll = rng.random_range(0..total);
+        let (folder, _, members) = groups.into_iter().find(|(_, weight, _)| {
+            if roll < *weight {
+                true
+            } else {
+                roll -= weight;
+                false
+            }
+        })?;
+
+        let cursor = self.cursors.entry(folder).or_default();
+
+        if *cursor >= members.len() {
+            *cursor = 0;
+
+            if config.storage.shuffle {
+                let mut items: Vec<Media> = members.iter().map(|i| nodes[*i].clone()).collect();
+                items.shuffle(&mut rng);
+
+                for (index, mut item) in members.iter().zip(items) {
+                    item.index = Some(*index);
+                    nodes[*index] = item;
+                }
+            }
+        }
+
+        let items = usize::try_from(config.storage.no_repeat_items).unwrap_or_default();
+        let time = config.storage.no_repeat_time;
+        let position = members[*cursor];
+
+        if config.storage.shuffle && (items > 0 || time > 0.0) {
+            let group: Vec<Media> = members.iter().map(|i| nodes[*i].clone()).collect();
+            let index = members[self.history.pick(&group, *cursor, items, time)];
+
+            if index != position {
+                nodes.swap(position, index);
+                nodes[position].index = Some(position);
+                nodes[index].index = Some(index);
+            }
+        }
+
+        *cursor += 1;
+
+        Some(position)
+    }
+
+    /// Position of the next weighted clip, from the crossfade or picked now.
+    async fn next_weighted(&mut self, config: &PlayoutConfig) -> Option<usize> {
+        if let Some((index, source)) = self.upcoming.take() {
+            if self
+                .manager
+                .current_list
+                .lock()
+                .await
+                .get(index)
+                .is_some_and(|m| m.source == source)
+            {
+                return Some(index);
+            }
+        }
+
+        self.pick_weighted(config).await
+    }
+
+    /// Prepare the current node and blend its end with the next clip in the list.
+    async fn prepare_node(&mut self, config: &PlayoutConfig, index: usize) {
+        let _ = self.current_node.add_probe(false).await.ok();
+
+        self.history.push(
+            &self.current_node.source,
+            self.current_node.out - self.current_node.seek,
+            usize::try_from(config.storage.no_repeat_items).unwrap_or_default(),
+            config.storage.no_repeat_time,
+        );
+
+        if let Some((_, duration)) = self.transition_next.take().filter(|(i, _)| *i == index) {
+            self.current_node.seek += duration;
+            self.current_node.transition_in = true;
+            self.current_node.cmd = Some(seek_and_length(config, &mut self.current_node));
+        }
+
+        self.current_node.loudness =
+            clip_loudness(&self.manager.db_pool, config, &self.current_node.source).await;
+        self.current_node.subtitle_file =
+            subtitle_file(&self.manager, config, &self.current_node).await;
+        self.current_node
+            .add_filter(config, &self.manager.filter_chain)
+            .await;
+        self.current_node.begin = Some(clip_begin(config));
+
+        // don't blend into a clip, which gets pushed back by an insert
+        if config.processing.transition != Transition::Crossfade
+            || self.rules.is_due(
+                time_in_seconds(&config.channel.timezone) + self.current_node.out
+                    - self.current_node.seek,
+            )
+        {
+            return;
+        }
+
+        // the blended clip must already be the next one
+        let next_index = match self.pick_weighted(config).await {
+            Some(i) => i,
+            None => {
+                self.avoid_repeat(config, index + 1).await;
+                index + 1
+            }
+        };
+
+        let Some(mut next) = self
+            .manager
+            .current_list
+            .lock()
+            .await
+            .get(next_index)
+            .cloned()
+        else {
+            return;
+        };
+
+        if !config.storage.weights.is_empty() {
+            self.upcoming = Some((next_index, next.source.clone()));
+        }
+
+        if next.add_probe(false).await.is_ok() {
+            let duration = crossfade(config, &mut self.current_node, &next);
+
+            if duration > 0.0 {
+                self.transition_next = Some((next_index, duration));
+            }
+        }
+    }
+
+    /// Take the next due item of the insert rules.
+    async fn next_insert(&mut self, config: &PlayoutConfig) -> Option<Media> {
+        if self.rules.changed(&config.rules) {
+            let storage = self.manager.storage.lock().await.clone();
+            self.rules = RuleSet::new(config, &storage).await;
+            self.inserts.clear();
+        }
+
+        if self.inserts.is_empty() && !self.rules.is_empty() {
+            let due = self.rules.due(time_in_seconds(&config.channel.timezone));
+            self.inserts.extend(due);
+        }
+
+        let mut node = self.inserts.pop_front()?;
+
+        node.loudness = clip_loudness(&self.manager.db_pool, config, &node.source).await;
+        node.subtitle_file = subtitle_file(&self.manager, config, &node).await;
+        node.add_filter(config, &self.manager.filter_chain).await;
+        node.begin = Some(clip_begin(config));
+        self.transition_next = None;
+
+        Some(node)
+    }
+
     async fn shuffle(&mut self) {
         let mut rng = StdRng::from_os_rng();
         let mut nodes = self.manager.current_list.lock().await;
@@ -135,16 +484,25 @@ impl FolderSource {
         let config = self.manager.config.lock().await.clone();
         let id = config.general.id;
 
-        if self.manager.current_index.load(Ordering::SeqCst)
+        if config.general.generate.is_none() {
+            if let Some(node) = self.next_insert(&config).await {
+                self.current_node = node;
+
+                return Some(self.current_node.clone());
+            }
+        }
+
+        if let Some(i) = self.next_weighted(&config).await {
+            self.current_node = self.manager.current_list.lock().await[i].clone();
+            self.prepare_node(&config, i).await;
+            self.manager.current_index.store(i + 1, Ordering::SeqCst);
+        } else if self.manager.current_index.load(Ordering::SeqCst)
             < self.manager.current_list.lock().await.len()
         {
             let i = self.manager.current_index.load(Ordering::SeqCst);
+            self.avoid_repeat(&config, i).await;
             self.current_node = self.manager.current_list.lock().await[i].clone();
-            let _ = self.current_node.add_probe(false).await.ok();
-            self.current_node
-                .add_filter(&config, &self.manager.filter_chain)
-                .await;
-            self.current_node.begin = Some(time_in_seconds(&config.channel.timezone));
+            self.prepare_node(&config, i).await;
             self.manager.current_index.fetch_add(1, Ordering::SeqCst);
         } else {
             if config.storage.shuffle {
@@ -161,18 +519,76 @@ impl FolderSource {
                 self.sort().await;
             }
 
+            self.avoid_repeat(&config, 0).await;
             self.current_node = match self.manager.current_list.lock().await.first() {
                 Some(m) => m.clone(),
                 None => return None,
             };
-            let _ = self.current_node.add_probe(false).await.ok();
-            self.current_node
-                .add_filter(&config, &self.manager.filter_chain)
-                .await;
-            self.current_node.begin = Some(time_in_seconds(&config.channel.timezone));
+            self.transition_next = None;
+            self.prepare_node(&config, 0).await;
             self.manager.current_index.store(1, Ordering::SeqCst);
         }
 
         Some(self.current_node.clone())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clips(sources: &[&str]) -> Vec<Media> {
+        sources
+            .iter()
+            .map(|s| Media {
+                source: (*s).to_string(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn no_repeat_window() {
+        let mut history = PlayHistory::default();
+
+        for source in ["a", "b", "c"] {
+            history.push(source, 600.0, 2, 0.0);
+        }
+
+        // the window of two items only holds b and c
+        assert_eq!(history.clips.len(), 2);
+        assert_eq!(history.pick(&clips(&["c", "b", "a"]), 0, 2, 0.0), 2);
+
+        // by time, c and b are in the last 30 minutes
+        assert_eq!(history.pick(&clips(&["c", "d", "a"]), 0, 0, 1800.0), 1);
+
+        // all clips are in the window, take the one played the longest time ago
+        assert_eq!(history.pick(&clips(&["c", "b"]), 0, 2, 0.0), 1);
+    }
+
+    #[test]
+    fn group_weighted_folders() {
+        let list = clips(&[
+            "/tv/media/hits/a.mp4",
+            "/tv/media/library/2020/b.mp4",
+            "/tv/media/ids/c.mp4",
+            "/tv/media/hits/d.mp4",
+            "/tv/media/e.mp4",
+        ]);
+        let weights = BTreeMap::from([
+            ("hits".to_string(), 60),
+            ("library".to_string(), 35),
+            ("promos".to_string(), 5),
+        ]);
+
+        let groups = weighted_groups(Path::new("/tv/media"), &list, &weights);
+
+        assert_eq!(
+            groups,
+            vec![
+                ("hits".to_string(), 60, vec![0, 3]),
+                ("library".to_string(), 35, vec![1]),
+            ]
+        );
+    }
+}
@@ -1,52 +1,167 @@
-use std::{process::Stdio, sync::atomic::Ordering};
+use std::{process::Stdio, sync::atomic::Ordering, time::Duration};
 
 use log::*;
+use serde_json::json;
 use tokio::{
     io::{AsyncBufReadExt, BufReader},
-    process::{ChildStderr, Command},
+    net::UdpSocket,
+    process::{Child, ChildStderr, ChildStdout, Command},
+    time::sleep,
 };
 
 use crate::utils::{
-    config::{PlayoutConfig, FFMPEG_IGNORE_ERRORS, FFMPEG_UNRECOVERABLE_ERRORS},
-    logging::{log_line, Target},
+    config::{Logging, PlayoutConfig, FFMPEG_IGNORE_ERRORS, FFMPEG_UNRECOVERABLE_ERRORS},
+    logging::{log_line, StderrLog, Target},
 };
 use crate::vec_strings;
 use crate::{
+    db::handles,
     player::{
         controller::{ChannelManager, ProcessUnit::*},
-        utils::{is_free_tcp_port, valid_stream, Media},
+        input::{
+            srt,
+            window::{is_open, parse_windows},
+        },
+        output::{archive::ingest_output, preview},
+        utils::{is_free_tcp_port, unexpected_stream, valid_stream, Media},
+    },
+    utils::{
+        errors::ServiceError,
+        logging::fmt_cmd,
+        time_machine::time_now,
+        webhook::{emit, WebhookEvent},
     },
-    utils::{errors::ServiceError, logging::fmt_cmd},
 };
 
+/// Check the key of an incoming stream against the ingest keys of the channel.
+pub async fn authorize_key(manager: &ChannelManager, key: &str, protocol: &str) -> bool {
+    let id = manager.id;
+
+    match handles::select_ingest_key_by_key(&manager.db_pool, id, key).await {
+        Ok(Some(ingest_key)) if ingest_key.active => {
+            info!(target: Target::file_mail(), channel = id; "Accept {protocol} ingest with key <yellow>{}</>", ingest_key.name);
+
+            if let Err(e) = handles::update_ingest_key_used(
+                &manager.db_pool,
+                ingest_key.id,
+                time_now(&None).timestamp(),
+            )
+            .await
+            {
+                error!(target: Target::file_mail(), channel = id; "Unable to save ingest key usage: {e}");
+            }
+
+            true
+        }
+        Ok(Some(ingest_key)) => {
+            warn!(target: Target::file_mail(), channel = id; "Reject {protocol} ingest with inactive key <yellow>{}</>", ingest_key.name);
+
+            false
+        }
+        Ok(None) => {
+            warn!(target: Target::file_mail(), channel = id; "Reject {protocol} ingest with unknown key");
+
+            false
+        }
+        Err(e) => {
+            error!(target: Target::file_mail(), channel = id; "Unable to read ingest keys: {e}");
+
+            false
+        }
+    }
+}
+
+/// Ingest is accepted at the current time, from the windows of the channel.
+pub fn window_open(config: &PlayoutConfig) -> bool {
+    let windows = parse_windows(&config.ingest.windows).unwrap_or_default();
+
+    is_open(&windows, time_now(&config.channel.timezone).naive_local())
+}
+
+/// Stream of a listener, which is not yet live.
+struct Listener {
+    proc: Child,
+    stdout: ChildStdout,
+}
+
 async fn server_monitor(
     id: i32,
-    level: String,
-    ignore: Vec<String>,
+    logging: Logging,
     buffer: BufReader<ChildStderr>,
     manager: ChannelManager,
-) -> Result<(), ServiceError> {
+    mut listener: Option<Listener>,
+) -> Result<bool, ServiceError> {
     let mut is_running = false;
+    let mut log = StderrLog::new(&logging, Ingest, id);
 
     let mut lines = buffer.lines();
-    while let Ok(Some(line)) = lines.next_line().await {
+
+    loop {
+        let line = tokio::select! {
+            line = lines.next_line() => match line {
+                Ok(Some(line)) => line,
+                _ => break,
+            },
+            // a waiting listener ends with the playout, a live one is stopped with the other processes
+            () = sleep(Duration::from_secs(1)), if listener.is_some() => {
+                if !manager.is_alive.load(Ordering::SeqCst) {
+                    break;
+                }
+
+                continue;
+            }
+        };
+
         if !FFMPEG_IGNORE_ERRORS.iter().any(|i| line.contains(*i))
-            && !ignore.iter().any(|i| line.contains(i))
+            && !logging.ignore_lines.iter().any(|i| line.contains(i))
+            && log.push(&line)
         {
-            log_line(id, &line, &level);
+            log_line(id, &line, &logging.ingest_level);
         }
 
         if line.contains("rtmp")
             && (line.contains("Unexpected stream") || line.contains("App field don't match up"))
             && !valid_stream(&line)
+            && !match unexpected_stream(&line) {
+                Some(key) => authorize_key(&manager, &key, "RTMP").await,
+                None => false,
+            }
         {
             warn!(target: Target::file_mail(), channel = id; "Unexpected ingest stream: {line}");
 
-            manager.stop(Ingest).await;
+            if listener.is_none() {
+                manager.stop(Ingest).await;
+            }
 
             break;
         } else if !is_running && line.contains("Input #0") {
+            if !window_open(&*manager.config.lock().await) {
+                warn!(target: Target::file_mail(), channel = id; "Reject ingest stream outside of the ingest windows");
+
+                if listener.is_none() {
+                    manager.stop(Ingest).await;
+                }
+
+                break;
+            }
+
+            if manager.ingest_is_alive.load(Ordering::SeqCst) {
+                warn!(target: Target::file_mail(), channel = id; "Ingest is already live, reject the second stream");
+
+                break;
+            }
+
+            if let Some(Listener { proc, stdout }) = listener.take() {
+                *manager.ingest_stdout.lock().await = Some(stdout);
+                *manager.ingest.lock().await = Some(proc);
+            }
+
             manager.ingest_is_alive.store(true, Ordering::SeqCst);
+            emit(
+                &*manager.config.lock().await,
+                WebhookEvent::IngestConnect,
+                json!({}),
+            );
 
             is_running = true;
         }
@@ -56,39 +171,54 @@ async fn server_monitor(
             .any(|i| line.contains(*i))
         {
             error!(target: Target::file_mail(), channel = id; "Hit unrecoverable error!");
+            emit(
+                &*manager.config.lock().await,
+                WebhookEvent::FfmpegCrash,
+                json!({"unit": "ingest", "line": line}),
+            );
+            log.failed = true;
             manager.channel.lock().await.active = false;
             manager.stop_all(false).await;
         }
     }
 
-    Ok(())
+    if log.failed {
+        log.dump(&logging).await;
+    }
+
+    if is_running {
+        manager.ingest_is_alive.store(false, Ordering::SeqCst);
+        emit(
+            &*manager.config.lock().await,
+            WebhookEvent::IngestDisconnect,
+            json!({}),
+        );
+        manager.wait(Ingest).await;
+    }
+
+    Ok(is_running)
 }
 
-/// ffmpeg Ingest Server
-///
-/// Start ffmpeg in listen mode, and wait for input.
-pub async fn ingest_server(
-    config: PlayoutConfig,
-    manager: ChannelManager,
-) -> Result<(), ServiceError> {
-    let id = config.general.channel_id;
+/// Command of the ingest server, the filters are the same for all inputs.
+pub async fn server_cmd(
+    config: &PlayoutConfig,
+    manager: &ChannelManager,
+    stream_input: &[String],
+) -> Vec<String> {
     let mut server_cmd = vec_strings!["-hide_banner", "-nostats", "-v", "level+info"];
-    let stream_input = config.ingest.input_cmd.clone().unwrap();
     let mut dummy_media = Media::new(0, "Live Stream", false).await;
     dummy_media.unit = Ingest;
-    dummy_media.add_filter(&config, &None).await;
-    let is_alive = manager.is_alive.clone();
-    let ingest_is_alive = manager.ingest_is_alive.clone();
+    dummy_media.add_filter(config, &None).await;
     let vtt_dummy = config
         .channel
         .storage
         .join(config.processing.vtt_dummy.clone().unwrap_or_default());
 
-    if let Some(ingest_input_cmd) = config.advanced.ingest.input_cmd {
+    if let Some(ingest_input_cmd) = &config.advanced.ingest.input_cmd {
         server_cmd.append(&mut ingest_input_cmd.clone());
     }
 
-    server_cmd.append(&mut stream_input.clone());
+    server_cmd.append(&mut stream_input.to_vec());
 
     if config.processing.vtt_enable && vtt_dummy.is_file() {
         server_cmd.append(&mut vec_strings!["-i", vtt_dummy.to_string_lossy()]);
@@ -103,60 +233,207 @@ pub async fn ingest_server(
         server_cmd.append(&mut vec_strings!("-map", "1:s", "-c:s", "copy"));
     }
 
-    if let Some(mut cmd) = config.processing.cmd {
+    if let Some(mut cmd) = config.processing.cmd.clone() {
         server_cmd.append(&mut cmd);
     }
 
-    debug!(target: Target::file_mail(), channel = id;
-        "Server CMD: <bright-blue>ffmpeg {}</>",
-        fmt_cmd(&server_cmd)
-    );
+    if config.ingest.record {
+        let storage = manager.storage.lock().await.clone();
 
-    if let Some(url) = stream_input.iter().find(|s| s.contains("://")) {
-        for num in 0..5 {
-            if is_free_tcp_port(url) {
-                break;
+        match ingest_output(config, &storage).await {
+            Ok(mut cmd) => server_cmd.append(&mut cmd),
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = config.general.channel_id; "Live recording is not possible: {e}");
             }
+        }
+    }
 
-            error!(target: Target::file_mail(), channel = id; "Address <b><magenta>{url}</></b> already in use!");
+    if let Some(address) = manager
+        .ingest_preview
+        .address()
+        .filter(|_| config.ingest.monitor)
+    {
+        server_cmd.append(&mut preview::ingest_output(config, address));
+    }
 
-            if num >= 4 {
-                manager.channel.lock().await.active = false;
+    server_cmd
+}
 
-                return Err(ServiceError::Conflict(
-                    "Can't run ingest server!".to_string(),
-                ));
-            }
+/// Run the ffmpeg listener once, returns if the stream went live.
+///
+/// With `shared`, the listener runs beside another one and gives its process to the channel
+/// only when the stream begins, so the other listener can't be stopped by mistake.
+pub async fn run_listener(
+    config: &PlayoutConfig,
+    manager: &ChannelManager,
+    server_cmd: &[String],
+    shared: bool,
+) -> Result<bool, ServiceError> {
+    let mut server_proc = Command::new("ffmpeg")
+        .args(server_cmd)
+        .kill_on_drop(true)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let ingest_stdout = server_proc.stdout.take().unwrap();
+    let server_err = BufReader::new(server_proc.stderr.take().unwrap());
 
-            tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-        }
+    let listener = if shared {
+        Some(Listener {
+            proc: server_proc,
+            stdout: ingest_stdout,
+        })
+    } else {
+        *manager.ingest_stdout.lock().await = Some(ingest_stdout);
+        *manager.ingest.lock().await = Some(server_proc);
 
-        info!(target: Target::file_mail(), channel = id; "Start ingest server, listening on: <b><magenta>{url}</></b>");
+        None
     };
 
-    while is_alive.load(Ordering::SeqCst) {
-        let proc_ctl = manager.clone();
-        let level = config.logging.ingest_level.clone();
-        let ignore = config.logging.ignore_lines.clone();
-        let mut server_proc = Command::new("ffmpeg")
-            .args(server_cmd.clone())
-            .kill_on_drop(true)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()?;
-        let ingest_stdout = server_proc.stdout.take().unwrap();
-        let server_err = BufReader::new(server_proc.stderr.take().unwrap());
+    let is_running = server_monitor(
+        config.general.channel_id,
+        config.logging.clone(),
+        server_err,
+        manager.clone(),
+        listener,
+    )
+    .await?;
 
-        *manager.ingest_stdout.lock().await = Some(ingest_stdout);
-        *manager.ingest.lock().await = Some(server_proc);
+    if !shared {
+        manager.ingest_is_alive.store(false, Ordering::SeqCst);
+        manager.wait(Ingest).await;
+    }
 
-        server_monitor(id, level, ignore, server_err, proc_ctl).await?;
-        ingest_is_alive.store(false, Ordering::SeqCst);
+    Ok(is_running)
+}
 
-        manager.wait(Ingest).await;
+/// Run the ffmpeg listener again after every stream, as long as the playout runs.
+async fn listen(
+    config: &PlayoutConfig,
+    manager: &ChannelManager,
+    server_cmd: Vec<String>,
+    shared: bool,
+) -> Result<(), ServiceError> {
+    debug!(target: Target::file_mail(), channel = config.general.channel_id;
+        "Server CMD: <bright-blue>ffmpeg {}</>",
+        fmt_cmd(&server_cmd)
+    );
+
+    while manager.is_alive.load(Ordering::SeqCst) {
+        run_listener(config, manager, &server_cmd, shared).await?;
 
         trace!("Restart ingest server");
     }
 
     Ok(())
 }
+
+/// SRT listener, with the gateway for the stream ID in front of it.
+async fn srt_listen(
+    config: &PlayoutConfig,
+    manager: &ChannelManager,
+    shared: bool,
+) -> Result<(), ServiceError> {
+    let id = config.general.channel_id;
+    let address = config
+        .ingest
+        .srt_address
+        .trim()
+        .trim_start_matches("srt://");
+
+    let has_keys = !handles::select_ingest_keys(&manager.db_pool, id)
+        .await?
+        .is_empty();
+
+    if config.ingest.srt_streamid.is_empty() && !has_keys {
+        info!(target: Target::file_mail(), channel = id; "Start SRT ingest, listening on: <b><magenta>{address}</></b>");
+
+        let cmd = server_cmd(config, manager, &config.ingest.srt_input(address)).await;
+
+        return listen(config, manager, cmd, shared).await;
+    }
+
+    let public = UdpSocket::bind(address).await?;
+    // free port on localhost for the listener behind the gateway
+    let local = UdpSocket::bind("127.0.0.1:0").await?.local_addr()?;
+
+    info!(target: Target::file_mail(), channel = id; "Start SRT ingest, listening on: <b><magenta>{address}</></b>, with stream ID check");
+
+    let cmd = server_cmd(
+        config,
+        manager,
+        &config.ingest.srt_input(&local.to_string()),
+    )
+    .await;
+    let gateway = srt::gateway(
+        public,
+        local,
+        config.ingest.srt_streamid.clone(),
+        manager.clone(),
+    );
+
+    tokio::try_join!(
+        async { gateway.await.map_err(ServiceError::from) },
+        listen(config, manager, cmd, shared)
+    )?;
+
+    Ok(())
+}
+
+/// ffmpeg Ingest Server
+///
+/// Start ffmpeg in listen mode, and wait for input.
+/// The SRT listener can run beside the ingest from the input parameters, the first stream goes live.
+pub async fn ingest_server(
+    config: PlayoutConfig,
+    manager: ChannelManager,
+) -> Result<(), ServiceError> {
+    let id = config.general.channel_id;
+    let shared = config.ingest.shared();
+
+    let rtmp = async {
+        if !config.ingest.enable {
+            return Ok(());
+        }
+
+        let stream_input = config.ingest.input_cmd.clone().unwrap();
+
+        if let Some(url) = stream_input.iter().find(|s| s.contains("://")) {
+            for num in 0..5 {
+                if is_free_tcp_port(url) {
+                    break;
+                }
+
+                error!(target: Target::file_mail(), channel = id; "Address <b><magenta>{url}</></b> already in use!");
+
+                if num >= 4 {
+                    manager.channel.lock().await.active = false;
+
+                    return Err(ServiceError::Conflict(
+                        "Can't run ingest server!".to_string(),
+                    ));
+                }
+
+                tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+            }
+
+            info!(target: Target::file_mail(), channel = id; "Start ingest server, listening on: <b><magenta>{url}</></b>");
+        };
+
+        let cmd = server_cmd(&config, &manager, &stream_input).await;
+
+        listen(&config, &manager, cmd, shared).await
+    };
+
+    let srt = async {
+        if config.ingest.srt_enable {
+            srt_listen(&config, &manager, shared).await
+        } else {
+            Ok(())
+        }
+    };
+
+    tokio::try_join!(rtmp, srt)?;
+
+    Ok(())
+}
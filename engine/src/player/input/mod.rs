@@ -1,9 +1,15 @@
 use log::*;
 
+pub mod event;
 pub mod folder;
 pub mod ingest;
 pub mod playlist;
+pub mod srt;
+pub mod voice;
+pub mod whip;
+pub mod window;
 
+pub use event::event_scheduler;
 pub use ingest::ingest_server;
 pub use playlist::CurrentProgram;
 
@@ -11,8 +17,8 @@ use crate::player::{controller::ChannelManager, input::folder::FolderSource, uti
 use crate::utils::{config::ProcessMode::*, logging::Target};
 
 pub enum SourceIterator {
-    Folder(FolderSource),
-    Playlist(CurrentProgram),
+    Folder(Box<FolderSource>),
+    Playlist(Box<CurrentProgram>),
 }
 
 impl SourceIterator {
@@ -22,6 +28,14 @@ impl SourceIterator {
             SourceIterator::Playlist(program) => program.next().await,
         }
     }
+
+    /// The playlist changed since it was read, the next clip must be read again.
+    pub async fn changed(&mut self) -> bool {
+        match self {
+            SourceIterator::Folder(_) => false,
+            SourceIterator::Playlist(program) => program.changed().await,
+        }
+    }
 }
 
 /// Create a source iterator from playlist, or from folder.
@@ -44,13 +58,13 @@ pub async fn source_generator(manager: ChannelManager) -> SourceIterator {
 
             let folder_source = FolderSource::new(&config, manager);
 
-            SourceIterator::Folder(folder_source.await)
+            SourceIterator::Folder(Box::new(folder_source.await))
         }
         Playlist => {
             info!(target: Target::file_mail(), channel = id; "Playout in playlist mode");
             let program = CurrentProgram::new(manager);
 
-            SourceIterator::Playlist(program.await)
+            SourceIterator::Playlist(Box::new(program.await))
         }
     }
 }
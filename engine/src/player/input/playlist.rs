@@ -1,29 +1,68 @@
 use std::{
+    collections::HashMap,
     path::Path,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
+    time::{Duration, Instant},
 };
 
+use chrono::{NaiveDate, TimeDelta};
 use log::*;
+use rand::seq::{IndexedRandom, SliceRandom};
 
 use crate::db::handles;
+use crate::file::{utils::loudness::clip_loudness, WalkOptions};
 use crate::player::{
     controller::ChannelManager,
+    filter::crossfade,
     utils::{
-        gen_dummy, get_delta, is_close, is_remote,
-        json_serializer::{read_json, set_defaults},
-        loop_filler, loop_image, modified_time,
+        gen_dummy, get_delta, include_file_extension, is_close, is_remote,
+        json_serializer::{
+            date_playlist_path, db_playlist_date, db_playlist_revision, feed_modified, read_json,
+            set_defaults,
+        },
+        loop_filler, loop_image, modified_time, playlist_clock, playlist_length,
         probe::MediaProbe,
-        seek_and_length, time_in_seconds, JsonPlaylist, Media,
+        probe_live, seek_and_length, subtitle_file, JsonPlaylist, Media,
     },
 };
 use crate::utils::{
-    config::{PlayoutConfig, IMAGE_FORMAT},
+    config::{PlayoutConfig, Transition, IMAGE_FORMAT},
+    epg::programme_title,
     logging::Target,
+    time_machine::time_now,
+    webhook::{emit, WebhookEvent},
 };
 
+/// Allowed difference of a random clip to the length of its item, as fraction.
+const RANDOM_TOLERANCE: f64 = 0.1;
+
+/// Number of files, which get probed when the media table knows no durations.
+const RANDOM_PROBES: usize = 5;
+
+/// Pick a clip with about the target length: randomly from the clips in the tolerance,
+/// otherwise the one with the nearest length.
+fn random_clip(clips: &[(String, f64)], target: f64) -> Option<usize> {
+    let fitting: Vec<usize> = clips
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, duration))| (duration - target).abs() <= target * RANDOM_TOLERANCE)
+        .map(|(i, _)| i)
+        .collect();
+
+    if let Some(i) = fitting.choose(&mut rand::rng()) {
+        return Some(*i);
+    }
+
+    clips
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| (a.1 - target).abs().total_cmp(&(b.1 - target).abs()))
+        .map(|(i, _)| i)
+}
+
 /// Struct for current playlist.
 ///
 /// Here we prepare the init clip and build a iterator where we pull our clips.
@@ -33,12 +72,21 @@ pub struct CurrentProgram {
     config: PlayoutConfig,
     manager: ChannelManager,
     start_sec: f64,
-    length_sec: f64,
     json_playlist: JsonPlaylist,
     current_node: Media,
     is_alive: Arc<AtomicBool>,
     last_json_path: Option<String>,
     last_node_ad: bool,
+    /// Index, source and length of the next clip, which head is played in a crossfade.
+    transition_next: Option<(usize, String, f64)>,
+    /// Sum of all crossfades in the current playlist, following clips begin this seconds earlier.
+    transition_shift: f64,
+    /// Begin of the playlist, which is chained to the end of the previous date.
+    chained: Option<f64>,
+    /// Last check of the playlist feed for changes.
+    feed_checked: Instant,
+    /// Simulation of the schedule, which leaves the playout status untouched.
+    dry_run: bool,
 }
 
 /// Prepare a playlist iterator.
@@ -52,7 +100,6 @@ impl CurrentProgram {
             config: config.clone(),
             manager,
             start_sec: config.playlist.start_sec.unwrap(),
-            length_sec: config.playlist.length_sec.unwrap(),
             json_playlist: JsonPlaylist::new(
                 "1970-01-01".to_string(),
                 config.playlist.start_sec.unwrap(),
@@ -61,7 +108,58 @@ impl CurrentProgram {
             is_alive,
             last_json_path: None,
             last_node_ad: false,
+            transition_next: None,
+            transition_shift: 0.0,
+            chained: None,
+            feed_checked: Instant::now(),
+            dry_run: false,
+        }
+    }
+
+    /// Iterator for the schedule simulation, the status is not written to the database.
+    pub fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    /// The running playlist changed since it was read, the feed is only polled in its interval.
+    pub async fn changed(&mut self) -> bool {
+        if !self.config.playlist.feed_url.trim().is_empty() {
+            return false;
+        }
+
+        match self.json_playlist.path.clone() {
+            Some(path) => self.playlist_changed(&path).await,
+            None => false,
+        }
+    }
+
+    /// Playlists from the feed are polled in the configured interval,
+    /// files and other remote playlists by their modification time
+    /// and playlists in the database by their revision.
+    async fn playlist_changed(&mut self, path: &str) -> bool {
+        if db_playlist_date(path).is_some() {
+            return db_playlist_revision(&self.manager.db_pool, self.channel_id, path)
+                .await
+                .is_some_and(|revision| self.json_playlist.modified != Some(revision));
+        }
+
+        if self.config.playlist.feed_url.trim().is_empty() {
+            return (Path::new(path).is_file() || is_remote(path))
+                && self.json_playlist.modified != modified_time(path).await;
         }
+
+        let interval = Duration::from_secs(self.config.playlist.feed_interval.max(1) as u64);
+
+        if self.feed_checked.elapsed() < interval {
+            return false;
+        }
+
+        self.feed_checked = Instant::now();
+
+        feed_modified(&self.config, path)
+            .await
+            .is_some_and(|version| self.json_playlist.modified != Some(version))
     }
 
     // Check if there is no current playlist or file got updated,
@@ -71,9 +169,7 @@ impl CurrentProgram {
         let mut reload = false;
 
         if let Some(path) = self.json_playlist.path.clone() {
-            if (Path::new(&path).is_file() || is_remote(&path))
-                && self.json_playlist.modified != modified_time(&path).await
-            {
+            if self.playlist_changed(&path).await {
                 info!(target: Target::file_mail(), channel = self.channel_id; "Reload playlist <b><magenta>{path}</></b>");
                 self.manager.list_init.store(true, Ordering::SeqCst);
                 get_current = true;
@@ -86,6 +182,7 @@ impl CurrentProgram {
         let storage = &self.manager.storage.clone();
         if get_current {
             self.json_playlist = read_json(
+                &self.manager.db_pool,
                 storage,
                 &mut self.config,
                 self.manager.current_list.clone(),
@@ -122,6 +219,12 @@ impl CurrentProgram {
                     .clone_from(&self.json_playlist.date);
             }
 
+            if let Some(start) = self.chained.filter(|_| reload) {
+                // keep the begin of a chained playlist
+                self.json_playlist.start_sec = Some(start);
+                set_defaults(&mut self.json_playlist);
+            }
+
             self.manager
                 .current_list
                 .lock()
@@ -142,8 +245,9 @@ impl CurrentProgram {
     async fn check_for_playlist(&mut self, seek: bool) -> bool {
         let (delta, total_delta) = get_delta(
             &self.config,
-            &time_in_seconds(&self.config.channel.timezone),
+            &playlist_clock(&self.config.channel.timezone, self.start_sec).1,
         );
+        let length_sec = self.length_sec();
         let mut next = false;
 
         let mut duration = self.current_node.out;
@@ -166,21 +270,23 @@ impl CurrentProgram {
         trace!(
             "delta: {delta} | total_delta: {total_delta}, index: {node_index}, last index: {last_index}, init: {} \n        next_start: {next_start} | length_sec: {} | source {}",
             self.manager.list_init.load(Ordering::SeqCst),
-            self.length_sec,
+            length_sec,
             self.current_node.source
         );
 
         // Check if we over the target length or we are close to it, if so we load the next playlist.
         if !self.config.playlist.infinit
-            && (next_start >= self.length_sec
+            && self.chained.is_none()
+            && (next_start >= length_sec
                 || is_close(total_delta, 0.0, 2.0)
-                || is_close(total_delta, self.length_sec, 2.0))
+                || is_close(total_delta, length_sec, 2.0))
         {
             trace!("get next day");
             next = true;
 
             let storage = &self.manager.storage.clone();
             self.json_playlist = read_json(
+                &self.manager.db_pool,
                 storage,
                 &mut self.config,
                 self.manager.current_list.clone(),
@@ -229,6 +335,10 @@ impl CurrentProgram {
 
         self.manager.channel.lock().await.time_shift = time_shift;
 
+        if self.dry_run {
+            return;
+        }
+
         if let Err(e) =
             handles::update_stat(&self.manager.db_pool, self.channel_id, date, time_shift).await
         {
@@ -236,11 +346,15 @@ impl CurrentProgram {
         };
     }
 
-    // Check if last and/or next clip is a advertisement.
+    // Check if last and/or next clip is a advertisement, and get the title of the next clip.
     async fn last_next_ad(&mut self, node: &mut Media) {
         let index = self.manager.current_index.load(Ordering::SeqCst);
         let current_list = self.manager.current_list.lock().await;
 
+        node.next_title = current_list
+            .get(index + 1)
+            .map(|n| programme_title(n.title.clone(), &n.source));
+
         if index + 1 < current_list.len() && &current_list[index + 1].category == "advertisement" {
             node.next_ad = true;
         }
@@ -253,16 +367,15 @@ impl CurrentProgram {
         }
     }
 
-    // Get current time and when we are before start time,
-    // we add full seconds of a day to it.
-    fn get_current_time(&mut self) -> f64 {
-        let mut time_sec = time_in_seconds(&self.config.channel.timezone);
-
-        if time_sec < self.start_sec {
-            time_sec += 86400.0; // self.config.playlist.length_sec.unwrap();
-        }
+    // Get current time on the playlist clock, which counts from the start time
+    // until the next day start, also over midnight and DST changes.
+    fn get_current_time(&self) -> f64 {
+        playlist_clock(&self.config.channel.timezone, self.start_sec).1
+    }
 
-        time_sec
+    // Target length of the current playlist, a full day can have 23 or 25 hours.
+    fn length_sec(&self) -> f64 {
+        playlist_length(&self.config, &self.json_playlist.date)
     }
 
     // On init or reload we need to seek for the current clip.
@@ -337,10 +450,31 @@ impl CurrentProgram {
     async fn fill_end(&mut self, total_delta: f64) {
         // Fill end from playlist
         let index = self.manager.current_index.load(Ordering::SeqCst);
-        let mut media = Media::new(index, "", false).await;
-        media.begin = Some(time_in_seconds(&self.config.channel.timezone));
-        media.duration = total_delta;
-        media.out = total_delta;
+        let filler = self
+            .manager
+            .filler_list
+            .lock()
+            .await
+            .next_fitting(time_now(&None).timestamp() as f64, total_delta);
+
+        // a filler which fits in the gap is played complete, the rest of the gap is filled in the next round
+        let mut media = match filler {
+            Some(mut filler) => {
+                trace!("take fitting filler: {}", filler.source);
+
+                filler.index = Some(index);
+                filler
+            }
+            None => {
+                let mut media = Media::new(index, "", false).await;
+                media.duration = total_delta;
+                media.out = total_delta;
+
+                media
+            }
+        };
+        media.begin = Some(self.get_current_time());
+        media.filler = true;
 
         self.last_next_ad(&mut media).await;
         self.gen_source(media, 0).await;
@@ -359,18 +493,71 @@ impl CurrentProgram {
         self.manager.current_index.fetch_add(1, Ordering::SeqCst);
     }
 
+    /// Load the playlist of the date after the current one and let it begin now.
+    async fn chain_next(&mut self) -> bool {
+        let Ok(date) = NaiveDate::parse_from_str(&self.json_playlist.date, "%Y-%m-%d") else {
+            self.chained = None;
+            return false;
+        };
+        let next_date = (date + TimeDelta::days(1)).format("%Y-%m-%d").to_string();
+        let path = date_playlist_path(&self.config, &next_date)
+            .to_string_lossy()
+            .to_string();
+
+        let exists = match db_playlist_date(&path) {
+            Some(_) => db_playlist_revision(&self.manager.db_pool, self.channel_id, &path)
+                .await
+                .is_some(),
+            None => is_remote(&path) || Path::new(&path).is_file(),
+        };
+
+        if !exists {
+            if self.chained.take().is_some() {
+                info!(target: Target::file_mail(), channel = self.channel_id; "No playlist for <yellow>{next_date}</> to chain, go back to day start");
+            }
+
+            return false;
+        }
+
+        let storage = &self.manager.storage.clone();
+        self.json_playlist = read_json(
+            &self.manager.db_pool,
+            storage,
+            &mut self.config,
+            self.manager.current_list.clone(),
+            Some(path.clone()),
+            self.is_alive.clone(),
+            false,
+            false,
+        )
+        .await;
+
+        info!(target: Target::file_mail(), channel = self.channel_id; "Chain playlist: <b><magenta>{path}</></b>");
+
+        let start = self.get_current_time();
+
+        self.chained = Some(start);
+        self.json_playlist.start_sec = Some(start);
+        set_defaults(&mut self.json_playlist);
+        self.manager
+            .current_list
+            .lock()
+            .await
+            .clone_from(&self.json_playlist.program);
+        self.set_status(&Some(self.json_playlist.date.clone()), 0.0)
+            .await;
+
+        true
+    }
+
     async fn recalculate_begin(&mut self, extend: bool) {
         debug!(target: Target::file_mail(), channel = self.channel_id; "Infinit playlist reaches end, recalculate clip begins. Extend: <yellow>{extend}</>");
 
-        let mut time_sec = time_in_seconds(&self.config.channel.timezone);
+        let mut time_sec = self.get_current_time();
 
         if extend {
             // Calculate the elapsed time since the playlist start
-            let elapsed_sec = if time_sec >= self.start_sec {
-                time_sec - self.start_sec
-            } else {
-                time_sec + 86400.0 - self.start_sec
-            };
+            let elapsed_sec = time_sec - self.start_sec;
 
             // Time passed within the current playlist loop
             let time_in_current_loop = elapsed_sec % self.json_playlist.length.unwrap();
@@ -510,7 +697,102 @@ impl CurrentProgram {
     }
 
     /// Generate the source CMD, or when clip not exist, get a dummy.
+    /// Replace the folder of a random item with a clip from it.
+    async fn resolve_random(&self, node: &mut Media) {
+        let storage = self.manager.storage.lock().await.clone();
+        let folder = if is_remote(&node.source) {
+            storage.interpreted_file_path(&node.key)
+        } else {
+            node.source.clone()
+        };
+        let target = node.out - node.seek;
+
+        let mut files = vec![];
+
+        match storage
+            .walk_dir(&folder, &WalkOptions::from_config(&self.config))
+            .await
+        {
+            Ok(paths) => {
+                for path in paths {
+                    if storage.is_file(&path).await && include_file_extension(&self.config, &path) {
+                        files.push(path);
+                    }
+                }
+            }
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = self.channel_id; "Random folder <b><magenta>{folder}</></b> not readable: {e}");
+                return;
+            }
+        }
+
+        // durations are known from the media table, when the files are indexed
+        let known: HashMap<String, f64> =
+            handles::select_media_hashes(&self.manager.db_pool, self.channel_id)
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|m| m.duration > 0.0)
+                .map(|m| (m.path, m.duration))
+                .collect();
+
+        let mut clips: Vec<(String, f64)> = files
+            .iter()
+            .filter_map(|path| {
+                let relative = path.strip_prefix(&self.config.channel.storage).ok()?;
+                let duration = known.get(&relative.to_string_lossy().to_string())?;
+
+                Some((path.to_string_lossy().to_string(), *duration))
+            })
+            .collect();
+
+        if clips.is_empty() {
+            files.shuffle(&mut rand::rng());
+
+            for path in files.iter().take(RANDOM_PROBES) {
+                let path = path.to_string_lossy().to_string();
+                let input = storage
+                    .fetch_file_path(&path)
+                    .await
+                    .unwrap_or_else(|_| path.clone());
+
+                if let Some(duration) = MediaProbe::new(&input)
+                    .await
+                    .ok()
+                    .and_then(|p| p.format.duration)
+                {
+                    clips.push((path, duration));
+                }
+            }
+        }
+
+        let Some((path, duration)) = random_clip(&clips, target).map(|i| clips[i].clone()) else {
+            error!(target: Target::file_mail(), channel = self.channel_id; "No clip found in random folder <b><magenta>{folder}</></b>");
+            return;
+        };
+
+        node.source = storage
+            .fetch_file_path(&path)
+            .await
+            .unwrap_or_else(|_| path.clone());
+        node.random = false;
+        node.probe = None;
+        node.seek = 0.0;
+        node.duration = duration;
+        node.out = if target > 0.0 {
+            duration.min(target)
+        } else {
+            duration
+        };
+
+        info!(target: Target::file_mail(), channel = self.channel_id; "Random clip from <b><magenta>{}</></b>: <b><magenta>{path}</></b>", node.key);
+    }
+
     pub async fn gen_source(&mut self, mut node: Media, last_index: usize) {
+        if node.random {
+            self.resolve_random(&mut node).await;
+        }
+
         let node_index = node.index.unwrap_or_default();
         let duration = node.out - node.seek;
 
@@ -544,6 +826,18 @@ impl CurrentProgram {
             };
         }
 
+        if node.probe.is_none() && !node.backup.is_empty() && is_remote(&node.source) {
+            let primary = node.source.clone();
+            node.swap_source();
+            node.probe = probe_live(&self.config, &node.source).await;
+
+            if node.probe.is_some() {
+                warn!(target: Target::file_mail(), channel = self.channel_id; "Source <b><magenta>{primary}</></b> not reachable, play backup <b><magenta>{}</></b>", node.source);
+            } else {
+                node.swap_source();
+            }
+        }
+
         // separate if condition, because of node.add_probe() in last condition
         if node.probe.is_some() {
             if node
@@ -568,38 +862,38 @@ impl CurrentProgram {
             // Last index is the index from the last item from the node list.
             if node_index < last_index {
                 error!(target: Target::file_mail(), channel = self.channel_id; "Source not found: <b><magenta>{}</></b>", node.source);
+                emit(
+                    &self.config,
+                    WebhookEvent::StorageError,
+                    serde_json::json!({"source": node.source, "error": "Source not found"}),
+                );
             }
 
-            let fillers = self.manager.filler_list.lock().await;
+            let mut fillers = self.manager.filler_list.lock().await;
 
             // Set list_init to true, to stay in sync.
             self.manager.list_init.store(true, Ordering::SeqCst);
 
-            if self
-                .manager
-                .storage
-                .lock()
-                .await
-                .is_dir(&self.config.storage.filler_path)
-                .await
-                && !fillers.is_empty()
+            let filler_media = if !fillers.is_empty()
+                && (!self.config.fillers.is_empty()
+                    || self
+                        .manager
+                        .storage
+                        .lock()
+                        .await
+                        .is_dir(&self.config.storage.filler_path)
+                        .await)
             {
+                fillers.next(time_now(&None).timestamp() as f64)
+            } else {
+                None
+            };
 
-                let mut index = self.manager.filler_index.fetch_add(1, Ordering::SeqCst);
-
-                if index > fillers.len() - 1 {
-                    index = 0;
-                }
-
-                let mut filler_media = fillers[index].clone();
+            drop(fillers);
 
+            if let Some(mut filler_media) = filler_media {
                 trace!("take filler: {}", filler_media.source);
 
-                if index == fillers.len() - 1 {
-                    // reset index for next round
-                    self.manager.filler_index.store(0, Ordering::SeqCst);
-                }
-
                 if filler_media.probe.is_none() {
                     if let Err(e) = filler_media.add_probe(false).await {
                         error!(target: Target::file_mail(), channel = self.channel_id; "{e:?}");
@@ -689,8 +983,12 @@ impl CurrentProgram {
                 "Generate filler with <yellow>{:.2}</> seconds length!",
                 node.out
             );
+
+            node.filler = true;
         }
 
+        node.loudness = clip_loudness(&self.manager.db_pool, &self.config, &node.source).await;
+        node.subtitle_file = subtitle_file(&self.manager, &self.config, &node).await;
         node.add_filter(&self.config, &self.manager.filter_chain.clone()) // to-do : for fillter announcement
             .await;
 
@@ -704,6 +1002,51 @@ impl CurrentProgram {
         self.current_node = node;
     }
 
+    /// Skip the head of the clip, when it was already played in the crossfade of the previous clip.
+    fn transition_in(&mut self, node: &mut Media, index: usize) {
+        node.begin = node.begin.map(|b| b - self.transition_shift);
+
+        if let Some((_, _, duration)) = self
+            .transition_next
+            .take()
+            .filter(|(i, source, _)| *i == index && *source == node.source)
+        {
+            node.seek += duration;
+            node.transition_in = true;
+            self.transition_shift += duration;
+        }
+    }
+
+    /// Blend the end of the current clip with the next clip from the list.
+    async fn transition_out(&mut self, next_index: usize) {
+        if self.config.processing.transition != Transition::Crossfade
+            || self.current_node.cmd.is_none()
+        {
+            return;
+        }
+
+        let Some(mut next) = self
+            .manager
+            .current_list
+            .lock()
+            .await
+            .get(next_index)
+            .cloned()
+        else {
+            return;
+        };
+
+        if next.probe.is_none() && next.add_probe(false).await.is_err() {
+            return;
+        }
+
+        let duration = crossfade(&self.config, &mut self.current_node, &next);
+
+        if duration > 0.0 {
+            self.transition_next = Some((next_index, next.source, duration));
+        }
+    }
+
     async fn duplicate_for_seek_and_loop(&mut self, node: &mut Media) {
         let mut nodes = self.manager.current_list.lock().await;
         let index = node.index.unwrap_or_default();
@@ -747,6 +1090,9 @@ impl CurrentProgram {
 
         if self.manager.list_init.load(Ordering::SeqCst) {
             trace!("Init playlist, from next iterator");
+            self.transition_next = None;
+            self.transition_shift = 0.0;
+
             let init_clip_is_filler = match self.json_playlist.path {
                 Some(_) => self.init_clip().await,
                 None => false,
@@ -757,13 +1103,9 @@ impl CurrentProgram {
                 // so we fill the gap with a dummy.
                 trace!("Init clip is no filler");
 
-                let mut current_time = time_in_seconds(&self.config.channel.timezone);
+                let current_time = self.get_current_time();
                 let (_, total_delta) = get_delta(&self.config, &current_time);
 
-                if self.start_sec > current_time {
-                    current_time += self.length_sec + 1.0;
-                }
-
                 let mut last_index = 0;
                 let length = self.manager.current_list.lock().await.len();
 
@@ -798,16 +1140,27 @@ impl CurrentProgram {
             }
 
             self.last_next_ad(&mut node).await;
+            self.transition_in(&mut node, index);
             self.timed_source(node, is_last, last_index).await;
 
+            if !is_last {
+                self.transition_out(index + 1).await;
+            }
+
             self.manager.current_index.fetch_add(1, Ordering::SeqCst);
         } else {
             let (_, total_delta) = get_delta(&self.config, &self.start_sec);
 
-            if !self.config.playlist.infinit
-                && self.last_json_path == self.json_playlist.path
-                && total_delta.abs() > 1.0
-            {
+            let list_end =
+                !self.config.playlist.infinit && self.last_json_path == self.json_playlist.path;
+
+            // a chained playlist continues with the next date, also at the regular day start
+            let chained = list_end
+                && self.config.playlist.chain
+                && (total_delta.abs() > 1.0 || self.chained.is_some())
+                && self.chain_next().await;
+
+            if list_end && !chained && total_delta.abs() > 1.0 {
                 // Playlist is to early finish,
                 // and if we have to fill it with a placeholder.
                 trace!("Total delta on list end: {total_delta}");
@@ -828,10 +1181,13 @@ impl CurrentProgram {
             }
 
             self.manager.current_index.store(0, Ordering::SeqCst);
+            self.transition_next = None;
+            self.transition_shift = 0.0;
             self.last_next_ad(&mut first_node).await;
             first_node.last_ad = self.last_node_ad;
 
             self.gen_source(first_node, 0).await;
+            self.transition_out(1).await;
 
             self.manager.current_index.store(1, Ordering::SeqCst);
         }
@@ -839,3 +1195,63 @@ impl CurrentProgram {
         Some(self.current_node.clone())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(not(feature = "mysql"))]
+    use crate::{player::controller::test_manager, utils::time_machine::lookahead};
+
+    #[test]
+    fn pick_random_clip() {
+        let clips = vec![
+            ("a.mp4".to_string(), 1300.0),
+            ("b.mp4".to_string(), 1420.0),
+            ("c.mp4".to_string(), 1790.0),
+        ];
+
+        // only b fits into 10% of 1500 seconds
+        assert_eq!(random_clip(&clips, 1500.0), Some(1));
+        // nothing fits, take the nearest
+        assert_eq!(random_clip(&clips, 2400.0), Some(2));
+        assert_eq!(random_clip(&[], 1500.0), None);
+    }
+
+    #[cfg(not(feature = "mysql"))]
+    #[tokio::test]
+    async fn begin_after_preroll() {
+        let (_, manager) = test_manager(
+            "preroll",
+            "UPDATE configurations SET general_stop_threshold = 1.0;",
+        )
+        .await;
+        manager.is_alive.store(true, Ordering::SeqCst);
+
+        let mut program = CurrentProgram::new(manager).await;
+        let (_, now) = playlist_clock(&program.config.channel.timezone, program.start_sec);
+
+        // the next clip starts in three seconds, when the running clip ends
+        let node = Media {
+            index: Some(2),
+            begin: Some(now + 3.0),
+            source: "missing.mp4".to_string(),
+            out: 60.0,
+            duration: 60.0,
+            ..Default::default()
+        };
+
+        program.timed_source(node.clone(), false, 3).await;
+        assert!(program.current_node.cmd.is_none());
+
+        // prerolled it is read with the clock at its begin, so it is in sync
+        lookahead(3.0, program.timed_source(node.clone(), false, 3)).await;
+        assert!(program.current_node.cmd.is_some());
+        assert_eq!(program.current_node.begin, node.begin);
+
+        let (delta, _) = lookahead(3.0, async {
+            get_delta(&program.config, &node.begin.unwrap())
+        })
+        .await;
+        assert!(delta.abs() < 0.5, "delta: {delta}");
+    }
+}
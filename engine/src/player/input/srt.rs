@@ -0,0 +1,246 @@
+/// Gateway in front of the SRT ingest listener.
+///
+/// ffmpeg accepts every SRT caller, the stream ID is not checked. The gateway receives the packets
+/// on the public address, reads the stream ID from the handshake and forwards only callers with the
+/// configured stream ID, or an ingest key of the channel, to the listener on localhost.
+/// The passphrase is checked by SRT itself.
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    io,
+    net::SocketAddr,
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
+
+use log::*;
+use tokio::{
+    net::UdpSocket,
+    task::JoinHandle,
+    time::{timeout, Instant},
+};
+
+use crate::player::{controller::ChannelManager, input::ingest::authorize_key};
+use crate::utils::logging::Target;
+
+/// Largest UDP payload of SRT.
+const PACKET_SIZE: usize = 1500;
+
+/// Callers without packets for this time are forgotten.
+const PEER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Handshake type of the conclusion, which carries the extensions.
+const CONCLUSION: u32 = 0xFFFF_FFFF;
+
+/// Extension type of the stream ID.
+const EXT_SID: u16 = 5;
+
+/// Start of the handshake extensions: 16 bytes header and 48 bytes handshake.
+const EXT_OFFSET: usize = 64;
+
+#[derive(Debug, PartialEq)]
+enum Packet {
+    /// Conclusion handshake, with the stream ID of the caller.
+    Conclusion(Option<String>),
+    /// Other handshake, like the induction before the conclusion.
+    Handshake,
+    Other,
+}
+
+fn read_u16(packet: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(packet.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn read_u32(packet: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(packet.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// The stream ID is sent in 32 bit words, with the bytes of each word in reversed order.
+fn decode_streamid(content: &[u8]) -> String {
+    let bytes: Vec<u8> = content
+        .chunks(4)
+        .flat_map(|word| word.iter().rev().copied().collect::<Vec<u8>>())
+        .collect();
+
+    String::from_utf8_lossy(&bytes)
+        .trim_end_matches('\0')
+        .to_string()
+}
+
+fn classify(packet: &[u8]) -> Packet {
+    // control packets have the first bit set, handshake is control type 0
+    if read_u16(packet, 0) != Some(0x8000) {
+        return Packet::Other;
+    }
+
+    if read_u32(packet, 36) != Some(CONCLUSION) {
+        return Packet::Handshake;
+    }
+
+    let mut at = EXT_OFFSET;
+
+    while let (Some(ext), Some(words)) = (read_u16(packet, at), read_u16(packet, at + 2)) {
+        let end = at + 4 + usize::from(words) * 4;
+
+        if ext == EXT_SID {
+            return Packet::Conclusion(packet.get(at + 4..end).map(decode_streamid));
+        }
+
+        at = end;
+    }
+
+    Packet::Conclusion(None)
+}
+
+struct Peer {
+    upstream: Arc<UdpSocket>,
+    reply: JoinHandle<()>,
+    allowed: bool,
+    rejected: bool,
+    seen: Instant,
+}
+
+/// Socket to the listener for a new caller, the answers go back to the caller.
+async fn connect_peer(
+    public: &Arc<UdpSocket>,
+    listener: SocketAddr,
+    caller: SocketAddr,
+) -> io::Result<Peer> {
+    let upstream = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    upstream.connect(listener).await?;
+
+    let reply_from = upstream.clone();
+    let reply_to = public.clone();
+
+    let reply = tokio::spawn(async move {
+        let mut buffer = [0; PACKET_SIZE];
+
+        while let Ok(n) = reply_from.recv(&mut buffer).await {
+            if reply_to.send_to(&buffer[..n], caller).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(Peer {
+        upstream,
+        reply,
+        allowed: false,
+        rejected: false,
+        seen: Instant::now(),
+    })
+}
+
+/// Forward callers with the stream ID to the listener, as long as the channel runs.
+pub async fn gateway(
+    public: UdpSocket,
+    listener: SocketAddr,
+    streamid: String,
+    manager: ChannelManager,
+) -> io::Result<()> {
+    let public = Arc::new(public);
+    let mut peers: HashMap<SocketAddr, Peer> = HashMap::new();
+    let mut buffer = [0; PACKET_SIZE];
+
+    while manager.is_alive.load(Ordering::SeqCst) {
+        let Ok(received) = timeout(Duration::from_secs(1), public.recv_from(&mut buffer)).await
+        else {
+            peers.retain(|_, peer| {
+                let active = peer.seen.elapsed() < PEER_TIMEOUT;
+
+                if !active {
+                    peer.reply.abort();
+                }
+
+                active
+            });
+
+            continue;
+        };
+
+        let (n, caller) = received?;
+        let packet = &buffer[..n];
+
+        let peer = match peers.entry(caller) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(connect_peer(&public, listener, caller).await?),
+        };
+
+        peer.seen = Instant::now();
+
+        let forward = match classify(packet) {
+            // the conclusion is sent again until the listener answers, it is checked only once
+            Packet::Conclusion(id) if !peer.allowed && !peer.rejected => {
+                let id = id.unwrap_or_default();
+
+                peer.allowed = (!streamid.is_empty() && id == streamid)
+                    || (!id.is_empty() && authorize_key(&manager, &id, "SRT").await);
+
+                if !peer.allowed {
+                    warn!(target: Target::file_mail(), channel = manager.id; "Reject SRT caller <b><magenta>{caller}</></b>");
+                }
+
+                peer.rejected = !peer.allowed;
+                peer.allowed
+            }
+            Packet::Conclusion(_) => peer.allowed,
+            Packet::Handshake => true,
+            Packet::Other => peer.allowed,
+        };
+
+        if forward {
+            peer.upstream.send(packet).await?;
+        }
+    }
+
+    for peer in peers.values() {
+        peer.reply.abort();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conclusion(streamid: &str) -> Vec<u8> {
+        let mut packet = vec![0x80, 0, 0, 0];
+        packet.resize(36, 0);
+        packet.extend(CONCLUSION.to_be_bytes());
+        packet.resize(EXT_OFFSET, 0);
+
+        // handshake request extension before the stream ID
+        packet.extend([0, 1, 0, 3]);
+        packet.extend([0; 12]);
+
+        let mut sid = streamid.as_bytes().to_vec();
+        sid.resize(sid.len().div_ceil(4) * 4, 0);
+
+        packet.extend(EXT_SID.to_be_bytes());
+        packet.extend(((sid.len() / 4) as u16).to_be_bytes());
+
+        for word in sid.chunks(4) {
+            packet.extend(word.iter().rev());
+        }
+
+        packet
+    }
+
+    #[test]
+    fn srt_handshake_streamid() {
+        assert_eq!(
+            classify(&conclusion("live/studio")),
+            Packet::Conclusion(Some("live/studio".to_string()))
+        );
+
+        let mut induction = conclusion("");
+        induction[36..40].copy_from_slice(&1_u32.to_be_bytes());
+        assert_eq!(classify(&induction), Packet::Handshake);
+
+        let mut no_sid = conclusion("");
+        no_sid.truncate(EXT_OFFSET + 16);
+        assert_eq!(classify(&no_sid), Packet::Conclusion(None));
+
+        assert_eq!(classify(&[0x12, 0x34, 0, 0, 0, 0]), Packet::Other);
+    }
+}
@@ -0,0 +1,260 @@
+use std::{
+    collections::VecDeque,
+    process::Stdio,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, PoisonError,
+    },
+    time::Duration,
+};
+
+use log::*;
+use serde::Deserialize;
+use shlex::split;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    process::Command,
+    time::{interval, sleep},
+};
+
+use crate::player::{
+    controller::{ChannelManager, ProcessUnit::*},
+    utils::stderr_reader,
+};
+use crate::utils::{config::PlayoutConfig, logging::Target};
+use crate::vec_strings;
+
+/// Sample rate of the voice-over, the encoder reads it as mono s16le.
+pub const VOICE_RATE: usize = 44100;
+
+/// Bytes of 20 milliseconds mono s16le audio.
+const CHUNK: usize = VOICE_RATE * 2 / 50;
+
+/// Voice-over is dropped, when it is more than one second behind.
+const MAX_BUFFER: usize = VOICE_RATE * 2;
+
+/// Wait time, before the voice-over input listens again.
+const RESTART_DELAY: Duration = Duration::from_secs(1);
+
+/// Play a file from the storage as voice-over, or stop the playing file.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct VoiceControl {
+    #[serde(default)]
+    pub file: Option<String>,
+    #[serde(default)]
+    pub stop: bool,
+}
+
+/// Audio of the live voice-over and the triggered files, which ducks the program in the encoder.
+#[derive(Debug, Default)]
+pub struct VoiceOver {
+    buffer: Mutex<VecDeque<u8>>,
+    /// Changes with every trigger, a playing file stops when it is not its own anymore.
+    file: AtomicU64,
+}
+
+impl VoiceOver {
+    fn push(&self, data: &[u8]) {
+        let mut buffer = self.buffer.lock().unwrap_or_else(PoisonError::into_inner);
+        buffer.extend(data);
+
+        if buffer.len() > MAX_BUFFER {
+            // keep whole samples
+            let over = (buffer.len() - MAX_BUFFER + 1) & !1;
+            buffer.drain(..over);
+        }
+    }
+
+    /// Next 20 milliseconds of audio, filled with silence.
+    fn pop(&self) -> Vec<u8> {
+        let mut buffer = self.buffer.lock().unwrap_or_else(PoisonError::into_inner);
+        let len = buffer.len().min(CHUNK) & !1;
+        let mut chunk: Vec<u8> = buffer.drain(..len).collect();
+        chunk.resize(CHUNK, 0);
+
+        chunk
+    }
+
+    fn clear(&self) {
+        self.buffer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+
+    /// Stop the playing file, the next trigger gets the returned number.
+    pub fn stop_file(&self) -> u64 {
+        let file = self.file.fetch_add(1, Ordering::SeqCst) + 1;
+        self.clear();
+
+        file
+    }
+
+    fn is_playing(&self, file: u64) -> bool {
+        self.file.load(Ordering::SeqCst) == file
+    }
+}
+
+/// ffmpeg arguments, which decode the input to the raw voice-over format.
+fn voice_cmd(input: Vec<String>, realtime: bool) -> Vec<String> {
+    let mut cmd = vec_strings!["-hide_banner", "-nostats", "-v", "level+error"];
+
+    if realtime {
+        cmd.push("-re".to_string());
+    }
+
+    cmd.extend(input);
+    cmd.extend(vec_strings![
+        "-vn", "-ac", "1", "-ar", VOICE_RATE, "-f", "s16le", "pipe:1"
+    ]);
+
+    cmd
+}
+
+/// Decode with ffmpeg and send the audio to the voice-over, until ffmpeg ends or `is_active` is false.
+async fn decode(
+    manager: &ChannelManager,
+    config: &PlayoutConfig,
+    cmd: Vec<String>,
+    is_active: impl Fn() -> bool,
+) -> Result<(), String> {
+    let id = config.general.channel_id;
+    let mut proc = Command::new("ffmpeg")
+        .args(cmd)
+        .kill_on_drop(true)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    let mut stdout = proc.stdout.take().ok_or("ffmpeg has no stdout")?;
+    let stderr = BufReader::new(proc.stderr.take().ok_or("ffmpeg has no stderr")?);
+
+    tokio::spawn(stderr_reader(
+        stderr,
+        config.logging.clone(),
+        Ingest,
+        id,
+        None,
+    ));
+
+    read(&mut stdout, manager, is_active).await;
+
+    if let Err(e) = proc.kill().await {
+        debug!(target: Target::file_mail(), channel = id; "Voice-over process is already stopped: {e}");
+    }
+
+    Ok(())
+}
+
+async fn read(
+    stdout: &mut (impl AsyncRead + Unpin),
+    manager: &ChannelManager,
+    is_active: impl Fn() -> bool,
+) {
+    let mut data = [0; CHUNK];
+
+    while manager.is_alive.load(Ordering::SeqCst) && is_active() {
+        match stdout.read(&mut data).await {
+            Ok(0) | Err(_) => break,
+            Ok(len) => manager.voice.push(&data[..len]),
+        }
+    }
+}
+
+/// Listen for the live voice-over, with the input parameters of the config.
+pub async fn server(manager: ChannelManager, config: PlayoutConfig) {
+    let id = config.general.channel_id;
+    let Some(input) = split(&config.ingest.voice_param) else {
+        error!(target: Target::file_mail(), channel = id; "Voice-over input parameters are not valid");
+        return;
+    };
+
+    if input.is_empty() {
+        return;
+    }
+
+    info!(target: Target::file_mail(), channel = id; "Start voice-over input: <b><magenta>{}</></b>", config.ingest.voice_param);
+
+    while manager.is_alive.load(Ordering::SeqCst) {
+        if let Err(e) = decode(&manager, &config, voice_cmd(input.clone(), false), || true).await {
+            error!(target: Target::file_mail(), channel = id; "Voice-over input failed: {e}");
+        }
+
+        sleep(RESTART_DELAY).await;
+    }
+}
+
+/// Play a file as voice-over in real time, a running file is replaced.
+pub async fn play_file(manager: ChannelManager, path: String) {
+    let config = manager.config.lock().await.clone();
+    let id = config.general.channel_id;
+    let file = manager.voice.stop_file();
+    let voice = manager.voice.clone();
+
+    info!(target: Target::file_mail(), channel = id; "Play voice-over <b><magenta>{path}</></b>");
+
+    let cmd = voice_cmd(vec_strings!["-i", path], true);
+
+    if let Err(e) = decode(&manager, &config, cmd, || voice.is_playing(file)).await {
+        error!(target: Target::file_mail(), channel = id; "Voice-over file failed: {e}");
+    }
+}
+
+/// Send the voice-over in real time to the encoder, which listens on the voice socket of the config.
+/// Without voice-over silence is sent, so the program is not ducked.
+pub async fn feed(manager: ChannelManager) {
+    while manager.is_alive.load(Ordering::SeqCst) {
+        let socket = manager.config.lock().await.ingest.voice_socket.clone();
+
+        if let Some(socket) = socket {
+            if let Ok(mut stream) = TcpStream::connect(socket).await {
+                let mut tick = interval(Duration::from_millis(20));
+
+                while manager.is_alive.load(Ordering::SeqCst) {
+                    tick.tick().await;
+
+                    if stream.write_all(&manager.voice.pop()).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        sleep(Duration::from_millis(500)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn voice_buffer() {
+        let voice = VoiceOver::default();
+
+        // silence without voice-over
+        assert_eq!(voice.pop(), vec![0; CHUNK]);
+
+        voice.push(&[1; 101]);
+        let chunk = voice.pop();
+
+        // only whole samples are taken, the odd byte waits for its second half
+        assert_eq!(chunk[..100], [1; 100]);
+        assert_eq!(chunk[100..], vec![0; CHUNK - 100]);
+
+        voice.push(&[2]);
+        assert_eq!(voice.pop()[..2], [1, 2]);
+
+        // the voice-over doesn't fall behind
+        voice.push(&vec![3; MAX_BUFFER + 11]);
+        assert!(voice.buffer.lock().unwrap().len() <= MAX_BUFFER);
+
+        let file = voice.stop_file();
+        assert!(voice.is_playing(file));
+        assert_eq!(voice.pop(), vec![0; CHUNK]);
+
+        voice.stop_file();
+        assert!(!voice.is_playing(file));
+    }
+}
@@ -0,0 +1,189 @@
+/// WHIP ingest over a WebRTC media server, like MediaMTX.
+///
+/// The media server handles WebRTC: the offer of the client is passed to the WHIP endpoint
+/// of the server, and the ingest reads the published stream back from the server.
+/// From there it runs through the same filters as the other ingest streams.
+use std::{sync::atomic::Ordering, time::Duration};
+
+use log::*;
+use reqwest::{header, StatusCode, Url};
+use tokio::time::sleep;
+use uuid::Uuid;
+
+use crate::player::{
+    controller::ChannelManager,
+    input::ingest::{run_listener, server_cmd, window_open},
+};
+use crate::utils::{
+    config::{OutputMode::HLS, PlayoutConfig},
+    errors::ServiceError,
+    logging::Target,
+};
+use crate::vec_strings;
+
+/// The stream is published some time after the answer, when WebRTC is connected.
+const PUBLISH_ATTEMPTS: usize = 10;
+
+/// Timeout for requests to the media server.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug)]
+pub struct WhipSession {
+    pub id: String,
+    /// Resource of the session on the media server.
+    location: Url,
+}
+
+/// Pass the offer to the media server and start reading the stream, returns the answer and the session ID.
+pub async fn publish(
+    manager: &ChannelManager,
+    offer: String,
+) -> Result<(String, String), ServiceError> {
+    let config = manager.config.lock().await.clone();
+    let id = config.general.channel_id;
+
+    if !config.ingest.whip_enable {
+        return Err(ServiceError::ServiceUnavailable(
+            "WHIP ingest is disabled".to_string(),
+        ));
+    }
+
+    if !manager.is_alive.load(Ordering::SeqCst) || config.output.mode == HLS {
+        return Err(ServiceError::ServiceUnavailable(
+            "Channel is not ready for WHIP ingest".to_string(),
+        ));
+    }
+
+    if !window_open(&config) {
+        warn!(target: Target::file_mail(), channel = id; "Reject WHIP client outside of the ingest windows");
+
+        return Err(ServiceError::Forbidden(
+            "Ingest is closed at this time".to_string(),
+        ));
+    }
+
+    let mut whip = manager.whip.lock().await;
+
+    if whip.is_some() || manager.ingest_is_alive.load(Ordering::SeqCst) {
+        return Err(ServiceError::Conflict("Ingest is already live".to_string()));
+    }
+
+    let url = Url::parse(&config.ingest.whip_url)
+        .map_err(|e| ServiceError::Conflict(format!("Invalid WHIP url: {e}")))?;
+
+    let response = reqwest::Client::new()
+        .post(url.clone())
+        .header(header::CONTENT_TYPE, "application/sdp")
+        .body(offer)
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| ServiceError::ServiceUnavailable(format!("Media server: {e}")))?;
+
+    if response.status() != StatusCode::CREATED {
+        warn!(target: Target::file_mail(), channel = id; "WHIP offer rejected by media server: <yellow>{}</>", response.status());
+
+        return Err(ServiceError::BadRequest(format!(
+            "Offer rejected by media server: {}",
+            response.status()
+        )));
+    }
+
+    let location = response
+        .headers()
+        .get(header::LOCATION)
+        .and_then(|l| l.to_str().ok())
+        .and_then(|l| url.join(l).ok())
+        .ok_or(ServiceError::ServiceUnavailable(
+            "Media server sent no session".to_string(),
+        ))?;
+    let answer = response
+        .text()
+        .await
+        .map_err(|e| ServiceError::ServiceUnavailable(format!("Media server: {e}")))?;
+    let session = WhipSession {
+        id: Uuid::new_v4().simple().to_string(),
+        location,
+    };
+
+    info!(target: Target::file_mail(), channel = id; "WHIP session <yellow>{}</> started", session.id);
+
+    *whip = Some(session.clone());
+
+    tokio::spawn(read_stream(config, manager.clone(), session.id.clone()));
+
+    Ok((answer, session.id))
+}
+
+/// End the session on the media server, the ingest ends with the stream.
+pub async fn unpublish(manager: &ChannelManager, session_id: &str) -> Result<(), ServiceError> {
+    let mut whip = manager.whip.lock().await;
+
+    match whip.take() {
+        Some(session) if session.id == session_id => {
+            close(manager.id, &session).await;
+
+            Ok(())
+        }
+        session => {
+            *whip = session;
+
+            Err(ServiceError::BadRequest("Session not found".to_string()))
+        }
+    }
+}
+
+async fn close(channel_id: i32, session: &WhipSession) {
+    info!(target: Target::file_mail(), channel = channel_id; "WHIP session <yellow>{}</> ended", session.id);
+
+    if let Err(e) = reqwest::Client::new()
+        .delete(session.location.clone())
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+    {
+        warn!(target: Target::file_mail(), channel = channel_id; "Unable to end WHIP session on media server: {e}");
+    }
+}
+
+/// Read the published stream, until it ends or the session is closed.
+async fn read_stream(config: PlayoutConfig, manager: ChannelManager, session_id: String) {
+    let id = config.general.channel_id;
+    let cmd = server_cmd(
+        &config,
+        &manager,
+        &vec_strings!["-i", config.ingest.whip_source],
+    )
+    .await;
+    let is_session = || async {
+        manager
+            .whip
+            .lock()
+            .await
+            .as_ref()
+            .is_some_and(|s| s.id == session_id)
+    };
+
+    for _ in 0..PUBLISH_ATTEMPTS {
+        if !is_session().await {
+            return;
+        }
+
+        match run_listener(&config, &manager, &cmd, true).await {
+            Ok(true) => break,
+            Ok(false) => sleep(Duration::from_secs(1)).await,
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = id; "WHIP ingest failed: {e}");
+                break;
+            }
+        }
+    }
+
+    let mut whip = manager.whip.lock().await;
+
+    if whip.as_ref().is_some_and(|s| s.id == session_id) {
+        if let Some(session) = whip.take() {
+            close(id, &session).await;
+        }
+    }
+}
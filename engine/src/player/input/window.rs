@@ -0,0 +1,175 @@
+/// Time windows, in which the ingest is accepted.
+///
+/// Windows are separated by semicolon or new line, each has optional weekdays and a time range,
+/// like `Mon-Fri 18:00-20:00; Sat,Sun 10:00-12:30`. A range, which ends before it starts,
+/// goes over midnight and belongs to the weekday of its start.
+use std::str::FromStr;
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Timelike, Weekday};
+
+const DAY: u32 = 86400;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IngestWindow {
+    /// Weekdays from Monday.
+    days: [bool; 7],
+    start: u32,
+    end: u32,
+}
+
+fn parse_time(time: &str) -> Result<u32, String> {
+    if time.trim() == "24:00" {
+        return Ok(DAY);
+    }
+
+    NaiveTime::parse_from_str(time.trim(), "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(time.trim(), "%H:%M:%S"))
+        .map(|t| t.num_seconds_from_midnight())
+        .map_err(|_| format!("Invalid time: {time}"))
+}
+
+fn parse_day(day: &str) -> Result<usize, String> {
+    Weekday::from_str(day.trim())
+        .map(|d| d.num_days_from_monday() as usize)
+        .map_err(|_| format!("Invalid weekday: {day}"))
+}
+
+impl FromStr for IngestWindow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (days, range) = match s.rsplit_once(char::is_whitespace) {
+            Some((days, range)) => (Some(days), range),
+            None => (None, s),
+        };
+        let (start, end) = range
+            .split_once('-')
+            .ok_or(format!("Invalid time range: {range}"))?;
+        let mut window = Self {
+            days: [days.is_none(); 7],
+            start: parse_time(start)?,
+            end: parse_time(end)?,
+        };
+
+        if window.start == window.end {
+            return Err(format!("Empty time range: {range}"));
+        }
+
+        for part in days
+            .unwrap_or_default()
+            .split(',')
+            .filter(|p| !p.trim().is_empty())
+        {
+            match part.split_once('-') {
+                Some((first, last)) => {
+                    let (first, last) = (parse_day(first)?, parse_day(last)?);
+                    let mut day = first;
+
+                    loop {
+                        window.days[day] = true;
+
+                        if day == last {
+                            break;
+                        }
+
+                        day = (day + 1) % 7;
+                    }
+                }
+                None => window.days[parse_day(part)?] = true,
+            }
+        }
+
+        Ok(window)
+    }
+}
+
+impl IngestWindow {
+    pub fn contains(&self, time: NaiveDateTime) -> bool {
+        let sec = time.num_seconds_from_midnight();
+        let day = time.weekday().num_days_from_monday() as usize;
+
+        if self.start < self.end {
+            self.days[day] && sec >= self.start && sec < self.end
+        } else {
+            (self.days[day] && sec >= self.start) || (self.days[(day + 6) % 7] && sec < self.end)
+        }
+    }
+
+    /// Date of the window start, after midnight it is the day before.
+    pub fn start_date(&self, time: NaiveDateTime) -> NaiveDate {
+        if self.start > self.end && time.num_seconds_from_midnight() < self.end {
+            time.date() - TimeDelta::days(1)
+        } else {
+            time.date()
+        }
+    }
+}
+
+pub fn parse_windows(windows: &str) -> Result<Vec<IngestWindow>, String> {
+    windows
+        .split([';', '\n'])
+        .filter(|w| !w.trim().is_empty())
+        .map(IngestWindow::from_str)
+        .collect()
+}
+
+/// Ingest is accepted at this time, without windows it is always accepted.
+pub fn is_open(windows: &[IngestWindow], time: NaiveDateTime) -> bool {
+    windows.is_empty() || windows.iter().any(|w| w.contains(time))
+}
+
+/// Begin of the next window after this time, within one week.
+pub fn next_start(windows: &[IngestWindow], time: NaiveDateTime) -> Option<NaiveDateTime> {
+    (0..=7)
+        .flat_map(|d| {
+            let date = time.date() + TimeDelta::days(d);
+            let day = date.weekday().num_days_from_monday() as usize;
+
+            windows
+                .iter()
+                .filter(move |w| w.days[day] && w.start < DAY)
+                .map(move |w| date.and_time(NaiveTime::MIN) + TimeDelta::seconds(w.start.into()))
+        })
+        .filter(|start| *start > time)
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(date: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn ingest_windows() {
+        let windows = parse_windows("Mon-Fri 18:00-20:00; Sat,Sun 22:00-02:00").unwrap();
+
+        // 2026-10-16 is a Friday
+        assert!(is_open(&windows, at("2026-10-16 19:30")));
+        assert!(!is_open(&windows, at("2026-10-16 20:00")));
+        assert!(!is_open(&windows, at("2026-10-17 03:00")));
+        assert!(is_open(&windows, at("2026-10-18 01:00")));
+        assert!(is_open(&windows, at("2026-10-19 01:00")));
+        assert!(!is_open(&windows, at("2026-10-20 01:00")));
+        assert!(is_open(&[], at("2026-10-20 01:00")));
+
+        assert_eq!(
+            next_start(&windows, at("2026-10-16 19:00")),
+            Some(at("2026-10-17 22:00"))
+        );
+        assert_eq!(
+            next_start(
+                &parse_windows("12:00-13:00").unwrap(),
+                at("2026-10-16 12:30")
+            ),
+            Some(at("2026-10-17 12:00"))
+        );
+
+        assert!(parse_windows("Fri 18:00").is_err());
+        assert!(parse_windows("Xyz 18:00-19:00").is_err());
+        assert!(parse_windows("18:00-18:00").is_err());
+    }
+}
@@ -0,0 +1,390 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::Ordering,
+    time::{Duration, SystemTime},
+};
+
+use chrono::{Local, NaiveDate, TimeDelta};
+use log::*;
+use tokio::{fs, time::sleep};
+
+use crate::db::models::ExtraOutput;
+use crate::file::{StorageBackend, WalkOptions};
+use crate::player::controller::ChannelManager;
+use crate::utils::{config::PlayoutConfig, errors::ServiceError, logging::Target};
+use crate::vec_strings;
+
+/// Name of the spool folder, where the recorder writes the running hour.
+const SPOOL: &str = ".spool";
+
+/// Interval for moving finished recordings into the storage.
+const STORE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A recording without changes for this time is finished.
+const SETTLE_TIME: Duration = Duration::from_secs(30);
+
+/// Local folder for the running recording below `path`. On S3 the recording is uploaded after it is finished.
+fn spool_dir(config: &PlayoutConfig, storage: &StorageBackend, path: &str, name: &str) -> PathBuf {
+    match storage {
+        StorageBackend::Local(_) => config.channel.storage.join(path).join(SPOOL),
+        StorageBackend::S3(_) => std::env::temp_dir()
+            .join("ffplayout")
+            .join(format!("{name}_{}", config.general.channel_id)),
+    }
+}
+
+/// Recorder as additional output, it writes hour-aligned MPEG-TS files to the spool folder.
+pub async fn output(
+    config: &PlayoutConfig,
+    storage: &StorageBackend,
+) -> Result<ExtraOutput, ServiceError> {
+    let spool = spool_dir(config, storage, &config.archive.path, "archive");
+    fs::create_dir_all(&spool).await?;
+
+    let target = spool.join("%Y-%m-%d_%H-%M-%S.ts");
+
+    Ok(ExtraOutput {
+        id: 0,
+        channel_id: config.general.channel_id,
+        name: "Archive".to_string(),
+        param: format!(
+            "{} -f segment -segment_time 3600 -segment_atclocktime 1 -reset_timestamps 1 -strftime 1 -segment_format mpegts {}",
+            config.archive.param,
+            shlex::try_quote(&target.to_string_lossy()).unwrap_or_default()
+        ),
+        active: true,
+    })
+}
+
+/// Screenshot encoder as additional output, it writes a program frame every few minutes to the spool folder.
+pub async fn screenshot_output(
+    config: &PlayoutConfig,
+    storage: &StorageBackend,
+) -> Result<ExtraOutput, ServiceError> {
+    let spool = spool_dir(
+        config,
+        storage,
+        &config.archive.screenshot_path,
+        "screenshots",
+    );
+    fs::create_dir_all(&spool).await?;
+
+    let target = spool.join("%Y-%m-%d_%H-%M-%S.jpg");
+
+    Ok(ExtraOutput {
+        id: 0,
+        channel_id: config.general.channel_id,
+        name: "Screenshots".to_string(),
+        param: format!(
+            "-an -r 1/{} -c:v mjpeg -q:v 4 -f image2 -strftime 1 {}",
+            config.archive.screenshot_interval.max(1) * 60,
+            shlex::try_quote(&target.to_string_lossy()).unwrap_or_default()
+        ),
+        active: true,
+    })
+}
+
+/// Recorder for the live ingest, as additional output of the ingest server.
+///
+/// The stream is copied without encoding, the file name gets the time when the stream begins.
+pub async fn ingest_output(
+    config: &PlayoutConfig,
+    storage: &StorageBackend,
+) -> Result<Vec<String>, ServiceError> {
+    let spool = spool_dir(config, storage, &config.ingest.record_path, "live");
+    fs::create_dir_all(&spool).await?;
+
+    let target = spool.join("%Y-%m-%d_%H-%M-%S.ts");
+
+    Ok(vec_strings![
+        "-map",
+        "0:v?",
+        "-map",
+        "0:a?",
+        "-c",
+        "copy",
+        "-f",
+        "segment",
+        "-segment_time",
+        "86400",
+        "-reset_timestamps",
+        "1",
+        "-strftime",
+        "1",
+        "-segment_format",
+        "mpegts",
+        target.to_string_lossy()
+    ])
+}
+
+/// Path in the archive folder for a recording: `2026-10-17_14-00-00.ts` is stored as `2026-10-17/14-00-00.ts`.
+fn archive_name(file_name: &str) -> Option<String> {
+    let (date, time) = file_name.split_once('_')?;
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+
+    Some(format!("{date}/{time}"))
+}
+
+/// Recording is older than the retention, the date comes from its day folder.
+fn expired(path: &Path, today: NaiveDate, retention: i64) -> bool {
+    if retention <= 0 {
+        return false;
+    }
+
+    path.parent()
+        .and_then(|p| p.file_name())
+        .and_then(|d| NaiveDate::parse_from_str(&d.to_string_lossy(), "%Y-%m-%d").ok())
+        .is_some_and(|d| d < today - TimeDelta::days(retention))
+}
+
+/// Move the finished recordings from the spool folder into the folder in the storage.
+async fn store(
+    config: &PlayoutConfig,
+    storage: &StorageBackend,
+    spool: &Path,
+    folder: &str,
+) -> Result<(), ServiceError> {
+    let id = config.general.channel_id;
+    let mut entries = fs::read_dir(spool).await?;
+
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let modified = entry.metadata().await?.modified()?;
+
+        if SystemTime::now()
+            .duration_since(modified)
+            .is_ok_and(|age| age < SETTLE_TIME)
+        {
+            continue;
+        }
+
+        let Some(name) = archive_name(&file_name) else {
+            continue;
+        };
+        let target = format!("{folder}/{name}");
+
+        match storage {
+            StorageBackend::Local(_) => {
+                let target = config.channel.storage.join(&target);
+
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent).await?;
+                }
+
+                fs::rename(&path, target).await?;
+            }
+            StorageBackend::S3(_) => {
+                storage.import_file(&path, &target).await?;
+                fs::remove_file(&path).await?;
+            }
+        }
+
+        debug!(target: Target::file_mail(), channel = id; "Archive file <b><magenta>{target}</></b> stored");
+    }
+
+    Ok(())
+}
+
+/// Delete the files in the folder, which are older than the retention.
+async fn clean(
+    config: &PlayoutConfig,
+    storage: &StorageBackend,
+    folder: &str,
+    retention: i64,
+) -> Result<(), ServiceError> {
+    let id = config.general.channel_id;
+    let today = Local::now().date_naive();
+    let root = match storage {
+        StorageBackend::Local(_) => config.channel.storage.join(folder),
+        StorageBackend::S3(_) => PathBuf::from(folder),
+    };
+
+    for path in storage.walk_dir(&root, &WalkOptions::default()).await? {
+        if path.components().any(|c| c.as_os_str() == SPOOL) || !expired(&path, today, retention) {
+            continue;
+        }
+
+        let relative = path
+            .strip_prefix(&config.channel.storage)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .to_string();
+
+        match storage.delete_file(&relative).await {
+            Ok(()) => {
+                info!(target: Target::file_mail(), channel = id; "Archive file <b><magenta>{relative}</></b> expired and deleted");
+            }
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = id; "Delete archive file <b><magenta>{relative}</></b> failed: {e}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Store finished files and delete expired ones, as long as the playout runs.
+async fn keep(
+    manager: ChannelManager,
+    config: PlayoutConfig,
+    folder: &str,
+    retention: i64,
+    name: &str,
+) {
+    let id = config.general.channel_id;
+    let storage = manager.storage.lock().await.clone();
+    let spool = spool_dir(&config, &storage, folder, name);
+    let mut cleaned: Option<NaiveDate> = None;
+
+    while manager.is_alive.load(Ordering::SeqCst) {
+        if let Err(e) = store(&config, &storage, &spool, folder).await {
+            error!(target: Target::file_mail(), channel = id; "Store {name} failed: {e}");
+        }
+
+        let today = Local::now().date_naive();
+
+        if cleaned != Some(today) {
+            if let Err(e) = clean(&config, &storage, folder, retention).await {
+                error!(target: Target::file_mail(), channel = id; "Clean up {name} failed: {e}");
+            }
+
+            cleaned = Some(today);
+        }
+
+        sleep(STORE_INTERVAL).await;
+    }
+}
+
+/// Store finished recordings and delete expired ones, as long as the playout runs.
+pub async fn run(manager: ChannelManager, config: PlayoutConfig) {
+    let (folder, retention) = (config.archive.path.clone(), config.archive.retention);
+
+    keep(manager, config, &folder, retention, "archive").await;
+}
+
+/// Store the screenshots and delete expired ones, as long as the playout runs.
+pub async fn run_screenshots(manager: ChannelManager, config: PlayoutConfig) {
+    let (folder, retention) = (
+        config.archive.screenshot_path.clone(),
+        config.archive.screenshot_retention,
+    );
+
+    keep(manager, config, &folder, retention, "screenshots").await;
+}
+
+/// Screenshots of the day, with their time and path in the storage.
+pub async fn screenshots(
+    config: &PlayoutConfig,
+    storage: &StorageBackend,
+    date: NaiveDate,
+) -> Result<Vec<(String, String)>, ServiceError> {
+    let folder = format!(
+        "{}/{}",
+        config.archive.screenshot_path,
+        date.format("%Y-%m-%d")
+    );
+    let root = match storage {
+        StorageBackend::Local(_) => config.channel.storage.join(&folder),
+        StorageBackend::S3(_) => PathBuf::from(&folder),
+    };
+    let options = WalkOptions {
+        depth: Some(1),
+        extensions: vec_strings!["jpg"],
+        ..Default::default()
+    };
+
+    if matches!(storage, StorageBackend::Local(_)) && !root.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut shots = storage
+        .walk_dir(&root, &options)
+        .await?
+        .into_iter()
+        .filter_map(|path| {
+            let time = screenshot_time(&path)?;
+            let relative = path
+                .strip_prefix(&config.channel.storage)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .to_string();
+
+            Some((time, relative))
+        })
+        .collect::<Vec<_>>();
+
+    shots.sort();
+
+    Ok(shots)
+}
+
+/// Time of the screenshot from its file name: `14-10-00.jpg` is `14:10:00`.
+fn screenshot_time(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_string_lossy();
+    let time = chrono::NaiveTime::parse_from_str(&stem, "%H-%M-%S").ok()?;
+
+    Some(time.format("%H:%M:%S").to_string())
+}
+
+/// Store finished live recordings, as long as the playout runs.
+pub async fn run_ingest(manager: ChannelManager, config: PlayoutConfig) {
+    let id = config.general.channel_id;
+    let storage = manager.storage.lock().await.clone();
+    let spool = spool_dir(&config, &storage, &config.ingest.record_path, "live");
+
+    while manager.is_alive.load(Ordering::SeqCst) {
+        if let Err(e) = store(&config, &storage, &spool, &config.ingest.record_path).await {
+            error!(target: Target::file_mail(), channel = id; "Store live recordings failed: {e}");
+        }
+
+        sleep(STORE_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_files() {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 17).unwrap();
+
+        assert_eq!(
+            archive_name("2026-10-17_14-00-00.ts"),
+            Some("2026-10-17/14-00-00.ts".to_string())
+        );
+        assert_eq!(archive_name("segments.csv"), None);
+        assert_eq!(archive_name("test_file.ts"), None);
+
+        assert!(expired(
+            Path::new("archive/2026-10-09/23-00-00.ts"),
+            today,
+            7
+        ));
+        assert!(!expired(
+            Path::new("archive/2026-10-10/00-00-00.ts"),
+            today,
+            7
+        ));
+        assert!(!expired(
+            Path::new("archive/2026-01-01/00-00-00.ts"),
+            today,
+            0
+        ));
+        assert!(!expired(Path::new("archive/other/clip.ts"), today, 7));
+
+        assert_eq!(
+            archive_name("2026-10-17_14-10-00.jpg"),
+            Some("2026-10-17/14-10-00.jpg".to_string())
+        );
+        assert_eq!(
+            screenshot_time(Path::new("screenshots/2026-10-17/14-10-00.jpg")),
+            Some("14:10:00".to_string())
+        );
+        assert_eq!(
+            screenshot_time(Path::new("screenshots/2026-10-17/cover.jpg")),
+            None
+        );
+    }
+}
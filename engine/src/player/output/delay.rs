@@ -0,0 +1,95 @@
+/// Delay buffer for the live ingest.
+///
+/// The stream is held in memory for the delay, before it goes to the encoder. A dump drops
+/// the held part, so it never goes on air, and a filler covers the gap until the buffer is full again.
+use std::io;
+
+use tokio::{
+    io::AsyncReadExt,
+    process::ChildStdout,
+    sync::mpsc::{unbounded_channel, UnboundedReceiver},
+    task::JoinHandle,
+    time::Instant,
+};
+
+/// Longest delay in seconds, the buffer is held in memory.
+pub const MAX_DELAY: i64 = 600;
+
+/// Size of an MPEG-TS packet, the buffer holds only whole packets, so a dump cuts between them.
+const TS_PACKET: usize = 188;
+
+const CHUNK_SIZE: usize = TS_PACKET * 348;
+
+/// Part of the stream with its arrival time.
+pub type Chunk = (Instant, Vec<u8>);
+
+/// Take the whole packets from the pending bytes, the rest waits for the next read.
+fn whole_packets(pending: &mut Vec<u8>) -> Option<Vec<u8>> {
+    let length = pending.len() - pending.len() % TS_PACKET;
+
+    if length == 0 {
+        return None;
+    }
+
+    let rest = pending.split_off(length);
+
+    Some(std::mem::replace(pending, rest))
+}
+
+/// Read the stream into timed chunks, until it ends.
+pub fn read(mut source: ChildStdout) -> (UnboundedReceiver<Chunk>, JoinHandle<io::Result<()>>) {
+    let (sender, chunks) = unbounded_channel();
+
+    let reader = tokio::spawn(async move {
+        let mut buffer = vec![0; CHUNK_SIZE];
+        let mut pending = vec![];
+
+        loop {
+            let n = source.read(&mut buffer).await?;
+
+            if n == 0 {
+                break;
+            }
+
+            pending.extend_from_slice(&buffer[..n]);
+
+            if let Some(chunk) = whole_packets(&mut pending) {
+                if sender.send((Instant::now(), chunk)).is_err() {
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    });
+
+    (chunks, reader)
+}
+
+/// Drop the held chunks, returns the dropped bytes.
+pub fn dump(chunks: &mut UnboundedReceiver<Chunk>) -> usize {
+    let mut dropped = 0;
+
+    while let Ok((_, chunk)) = chunks.try_recv() {
+        dropped += chunk.len();
+    }
+
+    dropped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_whole_packets() {
+        let mut pending = vec![0; 100];
+        assert_eq!(whole_packets(&mut pending), None);
+
+        pending.extend([1; 300]);
+        let chunk = whole_packets(&mut pending).unwrap();
+
+        assert_eq!(chunk.len(), 376);
+        assert_eq!(pending, vec![1; 24]);
+    }
+}
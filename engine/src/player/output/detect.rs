@@ -0,0 +1,198 @@
+/// Black and silence detection of the program, with blackdetect and silencedetect in the encoder.
+///
+/// The filters write the metadata of the frames, where black or silence starts and ends, to stderr,
+/// so the detection works with every log level of the encoder. The alert is sent, when the
+/// black or silence lasts longer than the time from the config.
+use std::{sync::Mutex, time::Duration};
+
+use log::*;
+use serde_json::json;
+use tokio::time::Instant;
+
+use crate::utils::{
+    config::{OutputMode::HLS, PlayoutConfig},
+    logging::Target,
+    webhook::{emit, WebhookEvent},
+};
+
+/// Noise level of the silence.
+const SILENCE_NOISE: &str = "-60dB";
+
+/// Silence is reported by silencedetect after this time.
+const SILENCE_DURATION: f64 = 1.0;
+
+#[derive(Debug, Default)]
+struct State {
+    since: Option<Instant>,
+    alerted: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Detection {
+    config: PlayoutConfig,
+    black: Option<Duration>,
+    silence: Option<Duration>,
+    black_state: Mutex<State>,
+    silence_state: Mutex<State>,
+}
+
+impl Detection {
+    pub fn new(config: &PlayoutConfig) -> Self {
+        let threshold = |seconds: f64| {
+            (seconds > 0.0 && config.output.mode != HLS).then(|| Duration::from_secs_f64(seconds))
+        };
+
+        Self {
+            config: config.clone(),
+            black: threshold(config.processing.black_alert)
+                .filter(|_| !config.processing.audio_only),
+            silence: threshold(config.processing.silence_alert),
+            ..Default::default()
+        }
+    }
+
+    /// Take the line, when it is from the metadata of the detection filters.
+    pub fn read_line(&self, line: &str) -> bool {
+        let Some((key, _)) = line.strip_prefix("lavfi.").and_then(|l| l.split_once('=')) else {
+            return false;
+        };
+
+        match key {
+            "black_start" => start(&self.black_state, Duration::ZERO),
+            "black_end" => self.end(&self.black_state, WebhookEvent::Black, "Black video"),
+            "silence_start" => start(
+                &self.silence_state,
+                Duration::from_secs_f64(SILENCE_DURATION),
+            ),
+            "silence_end" => {
+                self.end(&self.silence_state, WebhookEvent::Silence, "Silent audio");
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn end(&self, state: &Mutex<State>, event: WebhookEvent, name: &str) {
+        let mut state = state.lock().unwrap();
+
+        if let Some(since) = state.since.take().filter(|_| state.alerted) {
+            let seconds = since.elapsed().as_secs();
+
+            info!(target: Target::file_mail(), channel = self.config.general.channel_id;
+                "{name} of the program ended after <yellow>{seconds}</> seconds"
+            );
+
+            emit(
+                &self.config,
+                event,
+                json!({"state": "end", "duration": seconds}),
+            );
+        }
+
+        state.alerted = false;
+    }
+
+    /// Send the alerts, when black or silence lasts longer than the threshold.
+    pub fn check(&self) {
+        for (threshold, state, event, name) in [
+            (
+                self.black,
+                &self.black_state,
+                WebhookEvent::Black,
+                "video is black",
+            ),
+            (
+                self.silence,
+                &self.silence_state,
+                WebhookEvent::Silence,
+                "audio is silent",
+            ),
+        ] {
+            let Some(threshold) = threshold else {
+                continue;
+            };
+            let mut state = state.lock().unwrap();
+
+            if let Some(since) = state
+                .since
+                .filter(|s| !state.alerted && s.elapsed() >= threshold)
+            {
+                let seconds = since.elapsed().as_secs();
+
+                error!(target: Target::file_mail(), channel = self.config.general.channel_id;
+                    "Program {name} for <yellow>{seconds}</> seconds"
+                );
+
+                emit(
+                    &self.config,
+                    event,
+                    json!({"state": "start", "duration": seconds}),
+                );
+
+                state.alerted = true;
+            }
+        }
+    }
+
+    pub fn is_black(&self) -> bool {
+        self.black_state.lock().unwrap().alerted
+    }
+
+    pub fn is_silent(&self) -> bool {
+        self.silence_state.lock().unwrap().alerted
+    }
+}
+
+fn start(state: &Mutex<State>, detected_after: Duration) {
+    let mut state = state.lock().unwrap();
+    let now = Instant::now();
+
+    state.since = Some(now.checked_sub(detected_after).unwrap_or(now));
+}
+
+/// Detection filter of the video, `print` writes the metadata to stderr.
+pub fn black_filter(config: &PlayoutConfig, print: &str) -> String {
+    format!(
+        "blackdetect=d={}:pix_th=0.10,metadata=mode=print:key=lavfi.black_start:file={print},metadata=mode=print:key=lavfi.black_end:file={print}",
+        config.processing.black_alert
+    )
+}
+
+/// Detection filter of the audio, `print` writes the metadata to stderr.
+pub fn silence_filter(print: &str) -> String {
+    format!(
+        "silencedetect=n={SILENCE_NOISE}:d={SILENCE_DURATION},ametadata=mode=print:key=lavfi.silence_start:file={print},ametadata=mode=print:key=lavfi.silence_end:file={print}"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::config::OutputMode::Stream;
+
+    #[test]
+    fn detection_lines() {
+        let mut config = PlayoutConfig::default();
+        config.output.mode = Stream;
+        config.processing.black_alert = 0.001;
+
+        let detection = Detection::new(&config);
+
+        assert!(detection.silence.is_none());
+        assert!(!detection.read_line("frame=250"));
+        assert!(detection.read_line("lavfi.black_start=0.48"));
+
+        std::thread::sleep(Duration::from_millis(5));
+        detection.check();
+        assert!(detection.is_black());
+
+        assert!(detection.read_line("lavfi.black_end=2.4"));
+        assert!(!detection.is_black());
+
+        // silence without threshold is no alert
+        assert!(detection.read_line("lavfi.silence_start=3"));
+        detection.check();
+        assert!(!detection.is_silent());
+    }
+}
@@ -0,0 +1,536 @@
+#[cfg(target_family = "unix")]
+use std::{
+    fs::File,
+    io::{Read, Write},
+    sync::PoisonError,
+};
+use std::{
+    io,
+    process::Stdio,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{sync_channel, Receiver, SyncSender, TrySendError},
+        Arc,
+    },
+    time::Duration,
+};
+
+use log::*;
+use serde::Serialize;
+use shlex::split;
+#[cfg(not(target_family = "unix"))]
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::{
+    io::BufReader,
+    process::{ChildStdin, ChildStdout, Command},
+    task::{spawn_blocking, JoinHandle},
+    time::sleep,
+};
+
+use crate::db::models::ExtraOutput;
+use crate::player::{
+    controller::{ChannelManager, ProcessUnit::*},
+    output::{archive, preview, snapshot},
+    utils::{prepare_output_cmd, stderr_reader, Media},
+};
+use crate::utils::{
+    config::PlayoutConfig,
+    logging::{fmt_cmd, Target},
+};
+use crate::vec_strings;
+
+/// Size of the chunks, which are read from the decoder when additional outputs are fed.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Number of chunks an additional output can lag behind, before its data gets dropped.
+const QUEUE_SIZE: usize = 128;
+
+/// Pause before a stopped output gets started again.
+const RESTART_DELAY: Duration = Duration::from_secs(5);
+
+/// Health of an additional output, shared between its supervisor, the feed and the API.
+#[derive(Debug, Default)]
+pub struct OutputHealth {
+    pub id: i32,
+    pub name: String,
+    pub running: AtomicBool,
+    pub restarts: AtomicU64,
+    pub written: AtomicU64,
+    pub dropped: AtomicU64,
+    sender: std::sync::Mutex<Option<SyncSender<Vec<u8>>>>,
+}
+
+impl OutputHealth {
+    fn new(output: &ExtraOutput) -> Self {
+        Self {
+            id: output.id,
+            name: output.name.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Queue a chunk for the encoder, a lagging output loses the chunk instead of holding the program.
+    fn send(&self, chunk: &[u8]) {
+        let mut sender = self.sender.lock().unwrap();
+
+        if let Some(tx) = sender.as_ref() {
+            match tx.try_send(chunk.to_vec()) {
+                Ok(()) => {
+                    self.written
+                        .fetch_add(chunk.len() as u64, Ordering::Relaxed);
+                }
+                Err(TrySendError::Full(_)) => {
+                    self.dropped
+                        .fetch_add(chunk.len() as u64, Ordering::Relaxed);
+                }
+                Err(TrySendError::Disconnected(_)) => {
+                    *sender = None;
+                }
+            }
+        }
+    }
+
+    pub fn status(&self) -> OutputStatus {
+        OutputStatus {
+            id: self.id,
+            name: self.name.clone(),
+            running: self.running.load(Ordering::SeqCst),
+            restarts: self.restarts.load(Ordering::Relaxed),
+            written: self.written.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Status of an additional output, for the API.
+#[derive(Debug, Clone, Serialize)]
+pub struct OutputStatus {
+    pub id: i32,
+    pub name: String,
+    pub running: bool,
+    pub restarts: u64,
+    /// Bytes from the program, which are given to the encoder.
+    pub written: u64,
+    /// Bytes which are dropped, because the encoder was too slow.
+    pub dropped: u64,
+}
+
+/// Input of the main encoder.
+///
+/// On unix the pipe is used as a file, so the copy runs on a blocking thread and `io::copy`
+/// can use `splice`. Other targets write to the pipe from the runtime.
+#[cfg(target_family = "unix")]
+type MainInput = std::sync::Mutex<File>;
+
+#[cfg(not(target_family = "unix"))]
+type MainInput = tokio::sync::Mutex<ChildStdin>;
+
+/// Program stream to the main encoder and to the additional outputs.
+#[derive(Debug)]
+pub struct Feed {
+    main: MainInput,
+    outputs: Vec<Arc<OutputHealth>>,
+}
+
+impl Feed {
+    pub fn new(main: ChildStdin, outputs: Vec<Arc<OutputHealth>>) -> io::Result<Self> {
+        Ok(Self {
+            main: MainInput::new(main_input(main)?),
+            outputs,
+        })
+    }
+
+    /// Feed a new main encoder, the input of the old one gets closed.
+    ///
+    /// This waits until a running copy is finished, so the switch happens between two clips.
+    pub async fn replace(&self, main: ChildStdin) -> io::Result<()> {
+        let main = main_input(main)?;
+
+        #[cfg(target_family = "unix")]
+        {
+            *self.main.lock().unwrap_or_else(PoisonError::into_inner) = main;
+        }
+
+        #[cfg(not(target_family = "unix"))]
+        {
+            *self.main.lock().await = main;
+        }
+
+        Ok(())
+    }
+
+    /// Write a part of the program to all encoders.
+    pub async fn write(self: &Arc<Self>, data: Vec<u8>) -> io::Result<()> {
+        #[cfg(target_family = "unix")]
+        let data = {
+            let feed = self.clone();
+
+            spawn_blocking(move || {
+                let main = feed.main.lock().unwrap_or_else(PoisonError::into_inner);
+
+                (&*main).write_all(&data).map(|()| data)
+            })
+            .await??
+        };
+
+        #[cfg(not(target_family = "unix"))]
+        self.main.lock().await.write_all(&data).await?;
+
+        for output in &self.outputs {
+            output.send(&data);
+        }
+
+        Ok(())
+    }
+
+    /// Forward the stream of a decoder or ingest process to all encoders, until the source ends.
+    ///
+    /// The main encoder gets every byte and gives the backpressure.
+    #[cfg(target_family = "unix")]
+    pub fn forward(
+        self: &Arc<Self>,
+        source: ChildStdout,
+    ) -> io::Result<JoinHandle<io::Result<u64>>> {
+        let source = File::from(source.into_owned_fd()?);
+        let feed = self.clone();
+
+        Ok(spawn_blocking(move || feed.copy(source)))
+    }
+
+    /// Forward the stream of a decoder or ingest process to all encoders, until the source ends.
+    ///
+    /// The main encoder gets every byte and gives the backpressure.
+    #[cfg(not(target_family = "unix"))]
+    pub fn forward(
+        self: &Arc<Self>,
+        mut source: ChildStdout,
+    ) -> io::Result<JoinHandle<io::Result<u64>>> {
+        let feed = self.clone();
+
+        Ok(tokio::spawn(async move {
+            let mut main = feed.main.lock().await;
+
+            if feed.outputs.is_empty() {
+                return tokio::io::copy(&mut source, &mut *main).await;
+            }
+
+            let mut buffer = vec![0; CHUNK_SIZE];
+            let mut total = 0;
+
+            loop {
+                let n = source.read(&mut buffer).await?;
+
+                if n == 0 {
+                    break;
+                }
+
+                main.write_all(&buffer[..n]).await?;
+
+                for output in &feed.outputs {
+                    output.send(&buffer[..n]);
+                }
+
+                total += n as u64;
+            }
+
+            Ok(total)
+        }))
+    }
+
+    /// Copy the source to all encoders, without additional outputs `io::copy` can use `splice`
+    /// between the pipes.
+    #[cfg(target_family = "unix")]
+    fn copy(&self, mut source: File) -> io::Result<u64> {
+        let main = self.main.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if self.outputs.is_empty() {
+            return io::copy(&mut source, &mut &*main);
+        }
+
+        let mut buffer = vec![0; CHUNK_SIZE];
+        let mut total = 0;
+
+        loop {
+            let n = match source.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            (&*main).write_all(&buffer[..n])?;
+
+            for output in &self.outputs {
+                output.send(&buffer[..n]);
+            }
+
+            total += n as u64;
+        }
+
+        Ok(total)
+    }
+}
+
+#[cfg(target_family = "unix")]
+fn main_input(main: ChildStdin) -> io::Result<File> {
+    Ok(File::from(main.into_owned_fd()?))
+}
+
+#[cfg(not(target_family = "unix"))]
+fn main_input(main: ChildStdin) -> io::Result<ChildStdin> {
+    Ok(main)
+}
+
+/// Write the queued chunks to the encoder of an additional output, until the queue or the encoder ends.
+#[cfg(target_family = "unix")]
+fn write_queue(stdin: ChildStdin, rx: Receiver<Vec<u8>>) {
+    let Ok(mut stdin) = stdin.into_owned_fd().map(File::from) else {
+        return;
+    };
+
+    spawn_blocking(move || {
+        for chunk in rx {
+            if stdin.write_all(&chunk).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Write the queued chunks to the encoder of an additional output, until the queue or the encoder ends.
+#[cfg(not(target_family = "unix"))]
+fn write_queue(mut stdin: ChildStdin, rx: Receiver<Vec<u8>>) {
+    let handle = tokio::runtime::Handle::current();
+
+    spawn_blocking(move || {
+        for chunk in rx {
+            if handle.block_on(stdin.write_all(&chunk)).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Encoder command of an additional output, with the same encoder filters as the main output.
+async fn output_cmd(config: &PlayoutConfig, output: &ExtraOutput, log_format: &str) -> Vec<String> {
+    let mut config = config.clone();
+    let enc_prefix = vec_strings!["-hide_banner", "-nostats", "-v", log_format, "-i", "pipe:0"];
+
+    config.output.output_cmd = split(&output.param).map(|cmd| {
+        config
+            .processing
+            .hw
+            .translate_x264(cmd, config.processing.fps)
+    });
+    config.output.output_count = 1;
+    config.output.output_filter = None;
+
+    let mut media = Media {
+        unit: Encoder,
+        ..Default::default()
+    };
+    media.add_filter(&config, &None).await;
+
+    prepare_output_cmd(&config, enc_prefix, &media.filter)
+}
+
+/// Run the encoder of an additional output and start it again, when it stops while the playout runs.
+async fn supervise(
+    manager: ChannelManager,
+    config: PlayoutConfig,
+    output: ExtraOutput,
+    health: Arc<OutputHealth>,
+    preview: Option<Arc<preview::Preview>>,
+    log_format: String,
+) {
+    let id = config.general.channel_id;
+    let is_alive = manager.is_alive.clone();
+    let enc_cmd = output_cmd(&config, &output, &log_format).await;
+
+    debug!(target: Target::file_mail(), channel = id;
+        "Output <yellow>{}</> CMD: <bright-blue>ffmpeg {}</>",
+        output.name,
+        fmt_cmd(&enc_cmd)
+    );
+
+    while is_alive.load(Ordering::SeqCst) {
+        let mut proc = match Command::new("ffmpeg")
+            .args(&enc_cmd)
+            .kill_on_drop(true)
+            .stdin(Stdio::piped())
+            .stdout(if preview.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::inherit()
+            })
+            .stderr(Stdio::piped())
+            .spawn()
+        {
+            Ok(proc) => proc,
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = id; "Output <yellow>{}</> can't start: {e}", output.name);
+                sleep(RESTART_DELAY).await;
+                continue;
+            }
+        };
+
+        let stderr = BufReader::new(proc.stderr.take().unwrap());
+        tokio::spawn(stderr_reader(
+            stderr,
+            config.logging.clone(),
+            Encoder,
+            id,
+            None,
+        ));
+
+        if let (Some(preview), Some(stdout)) = (preview.clone(), proc.stdout.take()) {
+            tokio::spawn(async move { preview::publish(stdout, &preview).await });
+        }
+
+        if let Some(stdin) = proc.stdin.take() {
+            let (tx, rx) = sync_channel::<Vec<u8>>(QUEUE_SIZE);
+
+            write_queue(stdin, rx);
+            *health.sender.lock().unwrap() = Some(tx);
+        }
+
+        health.running.store(true, Ordering::SeqCst);
+        info!(target: Target::file_mail(), channel = id; "Output <yellow>{}</> started", output.name);
+
+        loop {
+            tokio::select! {
+                _ = proc.wait() => break,
+                () = sleep(Duration::from_secs(1)) => {
+                    if !is_alive.load(Ordering::SeqCst) {
+                        let _ = proc.kill().await;
+                        break;
+                    }
+                }
+            }
+        }
+
+        health.running.store(false, Ordering::SeqCst);
+        *health.sender.lock().unwrap() = None;
+
+        if is_alive.load(Ordering::SeqCst) {
+            health.restarts.fetch_add(1, Ordering::Relaxed);
+            error!(target: Target::file_mail(), channel = id;
+                "Output <yellow>{}</> stopped, restart in {}s", output.name, RESTART_DELAY.as_secs()
+            );
+            sleep(RESTART_DELAY).await;
+        }
+    }
+}
+
+/// Start the encoders of the active additional outputs and return their health for the program feed.
+///
+/// The archive recorder, the preview and the snapshots run as additional outputs too.
+pub async fn start(
+    manager: &ChannelManager,
+    config: &PlayoutConfig,
+    log_format: &str,
+) -> Vec<Arc<OutputHealth>> {
+    let mut active = config
+        .outputs
+        .iter()
+        .filter(|o| o.active)
+        .map(|o| (o.clone(), None))
+        .collect::<Vec<_>>();
+    let mut outputs = vec![];
+
+    if config.archive.enable {
+        let storage = manager.storage.lock().await.clone();
+
+        match archive::output(config, &storage).await {
+            Ok(recorder) => {
+                active.push((recorder, None));
+                tokio::spawn(archive::run(manager.clone(), config.clone()));
+            }
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = config.general.channel_id; "Archive can't start: {e}");
+            }
+        }
+    }
+
+    if config.archive.screenshots {
+        let storage = manager.storage.lock().await.clone();
+
+        match archive::screenshot_output(config, &storage).await {
+            Ok(output) => {
+                active.push((output, None));
+                tokio::spawn(archive::run_screenshots(manager.clone(), config.clone()));
+            }
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = config.general.channel_id; "Screenshot archive can't start: {e}");
+            }
+        }
+    }
+
+    if config.output.snapshot {
+        match snapshot::output(config).await {
+            Ok(output) => active.push((output, None)),
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = config.general.channel_id; "Snapshot can't start: {e}");
+            }
+        }
+    }
+
+    if config.output.preview {
+        active.push((preview::output(config), Some(manager.preview.clone())));
+
+        if let Some(output) = preview::whep_output(config) {
+            active.push((output, None));
+        }
+    }
+
+    for (output, preview) in active {
+        let health = Arc::new(OutputHealth::new(&output));
+
+        tokio::spawn(supervise(
+            manager.clone(),
+            config.clone(),
+            output,
+            health.clone(),
+            preview,
+            log_format.to_string(),
+        ));
+
+        outputs.push(health);
+    }
+
+    manager.outputs.lock().await.clone_from(&outputs);
+
+    outputs
+}
+
+#[cfg(all(test, target_family = "unix"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feed_outputs() {
+        let dir = std::env::temp_dir().join(format!("ffplayout-feed-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("source"), vec![7; CHUNK_SIZE * 2 + 10]).unwrap();
+
+        let running = Arc::new(OutputHealth::default());
+        let stopped = Arc::new(OutputHealth::default());
+        let (tx, rx) = sync_channel(QUEUE_SIZE);
+        *running.sender.lock().unwrap() = Some(tx);
+
+        let feed = Feed {
+            main: MainInput::new(File::create(dir.join("main")).unwrap()),
+            outputs: vec![running.clone(), stopped.clone()],
+        };
+        let copied = feed.copy(File::open(dir.join("source")).unwrap()).unwrap();
+        let received = rx.try_iter().map(|c| c.len() as u64).sum::<u64>();
+
+        assert_eq!(copied, (CHUNK_SIZE * 2 + 10) as u64);
+        assert_eq!(std::fs::metadata(dir.join("main")).unwrap().len(), copied);
+        assert_eq!(received, copied);
+        assert_eq!(running.status().written, copied);
+        assert_eq!(stopped.status().written, 0);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
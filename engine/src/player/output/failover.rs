@@ -0,0 +1,149 @@
+use std::{sync::atomic::Ordering, time::Duration};
+
+use log::*;
+use serde_json::json;
+
+use crate::player::controller::ChannelManager;
+use crate::utils::{
+    config::{OutputMode::*, PlayoutConfig},
+    logging::Target,
+    webhook::{emit, WebhookEvent},
+};
+
+/// Encoder failures in a row, before the next destination is used.
+const FAILOVER_AFTER: usize = 3;
+
+/// An encoder which ran shorter than this, counts as failed.
+const FAILOVER_WINDOW: Duration = Duration::from_secs(60);
+
+/// Destinations of the stream output by priority, empty when the output has no backup.
+pub fn destinations(config: &PlayoutConfig) -> Vec<String> {
+    let output = &config.output;
+
+    if !matches!(output.mode, Stream | Srt | Icecast)
+        || output.backup.is_empty()
+        || output.output_count > 1
+    {
+        return vec![];
+    }
+
+    let Some(primary) = output.output_cmd.as_ref().and_then(|c| c.last()) else {
+        return vec![];
+    };
+
+    let mut list = vec![primary.clone()];
+    list.extend(output.backup.iter().cloned());
+
+    list
+}
+
+/// Config for the encoder, which pushes to the current destination.
+pub fn encoder_config(manager: &ChannelManager, config: &PlayoutConfig) -> PlayoutConfig {
+    let list = destinations(config);
+    let mut config = config.clone();
+
+    if list.is_empty() {
+        return config;
+    }
+
+    let index = manager.destination.load(Ordering::SeqCst) % list.len();
+
+    if let Some(cmd) = config.output.output_cmd.as_mut() {
+        if let Some(last) = cmd.last_mut() {
+            last.clone_from(&list[index]);
+        }
+    }
+
+    config
+}
+
+/// Count the failed run and give the next destination, when there are too many failures.
+fn failover_step(failures: usize, ran: Duration, index: usize, len: usize) -> (usize, usize) {
+    let failures = if ran < FAILOVER_WINDOW {
+        failures + 1
+    } else {
+        1
+    };
+
+    if failures >= FAILOVER_AFTER {
+        (0, (index + 1) % len)
+    } else {
+        (failures, index)
+    }
+}
+
+/// The encoder stopped while the playout runs, switch to the next destination when it fails repeatedly.
+pub async fn encoder_ended(manager: &ChannelManager, config: &PlayoutConfig, ran: Duration) {
+    if !manager.is_alive.load(Ordering::SeqCst) {
+        return;
+    }
+
+    emit(
+        config,
+        WebhookEvent::FfmpegCrash,
+        json!({"unit": "encoder", "runtime": ran.as_secs()}),
+    );
+    manager.alert_history.lock().await.restart();
+
+    let list = destinations(config);
+
+    if list.is_empty() {
+        return;
+    }
+
+    let id = config.general.channel_id;
+    let index = manager.destination.load(Ordering::SeqCst) % list.len();
+    let failures = manager.output_failures.load(Ordering::SeqCst);
+    let (failures, next) = failover_step(failures, ran, index, list.len());
+
+    manager.output_failures.store(failures, Ordering::SeqCst);
+
+    if next != index {
+        manager.destination.store(next, Ordering::SeqCst);
+
+        error!(target: Target::file_mail(), channel = id;
+            "Output to <b><magenta>{}</></b> failed {FAILOVER_AFTER} times, switch to <b><magenta>{}</></b>",
+            list[index],
+            list[next]
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_failover() {
+        let short = Duration::from_secs(5);
+        let long = Duration::from_secs(600);
+
+        assert_eq!(failover_step(0, short, 0, 3), (1, 0));
+        assert_eq!(failover_step(1, short, 0, 3), (2, 0));
+        assert_eq!(failover_step(2, short, 0, 3), (0, 1));
+        assert_eq!(failover_step(2, long, 0, 3), (1, 0));
+        assert_eq!(failover_step(2, short, 2, 3), (0, 0));
+
+        let mut config = PlayoutConfig::default();
+        config.output.mode = Stream;
+        config.output.output_count = 1;
+        config.output.output_cmd = Some(vec![
+            "-f".to_string(),
+            "flv".to_string(),
+            "rtmp://primary/live".to_string(),
+        ]);
+
+        assert!(destinations(&config).is_empty());
+
+        config.output.backup = vec!["rtmp://backup/live".to_string()];
+
+        assert_eq!(
+            destinations(&config),
+            vec!["rtmp://primary/live", "rtmp://backup/live"]
+        );
+
+        config.output.mode = HLS;
+
+        assert!(destinations(&config).is_empty());
+    }
+}
@@ -17,23 +17,32 @@ out:
 
 */
 
-use std::{process::Stdio, sync::atomic::Ordering};
+use std::{process::Stdio, sync::atomic::Ordering, time::Duration};
 
 use log::*;
+use serde_json::json;
 use tokio::{
     io::{AsyncBufReadExt, BufReader},
     process::Command,
 };
 
-use crate::utils::{logging::log_line, task_runner};
+use super::{emergency_media, segments::clean_segments, slate_media};
+use crate::utils::{
+    logging::{log_line, StderrLog},
+    task_runner,
+    webhook::{emit, WebhookEvent},
+};
 use crate::vec_strings;
 use crate::{
     player::{
         controller::{ChannelManager, ProcessUnit::*},
-        input::source_generator,
+        input::{
+            ingest::{authorize_key, window_open},
+            source_generator,
+        },
         utils::{
             get_delta, is_free_tcp_port, prepare_output_cmd, sec_to_time, stderr_reader,
-            valid_stream, Media,
+            unexpected_stream, valid_stream, Media,
         },
     },
     utils::{
@@ -127,6 +136,7 @@ async fn ingest_writer(manager: ChannelManager) -> Result<(), ServiceError> {
 
         let server_err = BufReader::new(server_proc.stderr.take().unwrap());
         let mut lines = server_err.lines();
+        let mut log = StderrLog::new(&config.logging, Ingest, id);
 
         *manager.ingest.lock().await = Some(server_proc);
         is_running = false;
@@ -135,9 +145,24 @@ async fn ingest_writer(manager: ChannelManager) -> Result<(), ServiceError> {
             if line.contains("rtmp")
                 && (line.contains("Unexpected stream") || line.contains("App field don't match up"))
                 && !valid_stream(&line)
+                && !match unexpected_stream(&line) {
+                    Some(key) => authorize_key(&manager, &key, "RTMP").await,
+                    None => false,
+                }
             {
                 warn!(target: Target::file_mail(), channel = id; "Unexpected ingest stream: {line}");
 
+                manager.stop(Ingest).await;
+            } else if !is_running && line.contains("Input #0") && !window_open(&config) {
+                warn!(target: Target::file_mail(), channel = id; "Reject ingest stream outside of the ingest windows");
+
+                manager.stop(Ingest).await;
+            } else if !is_running
+                && line.contains("Input #0")
+                && manager.emergency.load(Ordering::SeqCst)
+            {
+                warn!(target: Target::file_mail(), channel = id; "Reject ingest stream during the emergency override");
+
                 manager.stop(Ingest).await;
             } else if !is_running && line.contains("Input #0") {
                 level = &config.logging.ingest_level;
@@ -146,15 +171,23 @@ async fn ingest_writer(manager: ChannelManager) -> Result<(), ServiceError> {
                 is_running = true;
 
                 info!(target: Target::file_mail(), channel = id; "Switch from {} to live ingest", config.processing.mode);
+                emit(&config, WebhookEvent::IngestConnect, json!({}));
 
                 manager.stop(Decoder).await;
             }
 
-            log_line(id, &line, level);
+            if log.push(&line) {
+                log_line(id, &line, level);
+            }
+        }
+
+        if log.failed {
+            log.dump(&config.logging).await;
         }
 
         if ingest_is_alive.load(Ordering::SeqCst) {
             info!(target: Target::file_mail(), channel = id; "Switch from live ingest to {}", config.processing.mode);
+            emit(&config, WebhookEvent::IngestDisconnect, json!({}));
         }
 
         ingest_is_alive.store(false, Ordering::SeqCst);
@@ -178,10 +211,54 @@ async fn write(manager: &ChannelManager, ff_log_format: &str) -> Result<(), Serv
     let is_alive = manager.is_alive.clone();
 
     let mut get_source = get_source.await;
+    let has_ladder = !config.output.hls_ladder.is_empty();
+    let live = config.channel.public.join("live");
+    // segments which are out of the playlists, with some reserve for slow clients
+    let segment_age = Duration::from_secs(
+        ((config.output.hls_list_size + 2) * config.output.hls_time.max(1)) as u64,
+    );
+
+    manager.splice_cues.lock().await.reset();
+
+    // after an engine handoff the players continue with the segments of the old engine
+    if has_ladder && !manager.taken_over.swap(false, Ordering::SeqCst) {
+        clean_segments(&config, &live, None).await;
+    }
+
+    // a seeked clip is played again before the next one
+    let mut seeked = None;
+    // emergency clip, which doesn't loop, releases the override when it ends
+    let mut emergency_once = false;
+
+    loop {
+        if std::mem::take(&mut emergency_once) {
+            manager.set_emergency(false, None).await;
+        }
+
+        // the slates and a seeked clip are no new items
+        let (node, is_seek) = if manager.emergency.load(Ordering::SeqCst) {
+            emergency_once = !config.storage.emergency_loop;
+
+            (emergency_media(manager, &config).await, true)
+        } else if manager.standby.load(Ordering::SeqCst) {
+            match slate_media(manager, &config).await {
+                Some(node) => (node, true),
+                None if !is_alive.load(Ordering::SeqCst) => break,
+                None => {
+                    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+                    continue;
+                }
+            }
+        } else if let Some(node) = seeked.take() {
+            (node, true)
+        } else {
+            match get_source.next().await {
+                Some(node) => (node, false),
+                None => break,
+            }
+        };
 
-    while let Some(node) = get_source.next().await {
         *current_media.lock().await = Some(node.clone());
-        let ignore = config.logging.ignore_lines.clone();
 
         if !is_alive.load(Ordering::SeqCst) {
             break;
@@ -202,6 +279,12 @@ async fn write(manager: &ChannelManager, ff_log_format: &str) -> Result<(), Serv
             node.key
         );
 
+        // every clip has its own ffmpeg process, so the timestamps start always new
+        if !is_seek {
+            manager.splice_cue(&node, false).await;
+            manager.secondary_events(&node).await;
+        }
+
         if config.task.enable {
             if config.task.path.is_file() {
                 let manager3 = manager.clone();
@@ -256,11 +339,18 @@ async fn write(manager: &ChannelManager, ff_log_format: &str) -> Result<(), Serv
         let dec_err = BufReader::new(dec_proc.stderr.take().unwrap());
         *manager.decoder.lock().await = Some(dec_proc);
 
-        stderr_reader(dec_err, ignore, Decoder, id).await?;
+        stderr_reader(dec_err, config.logging.clone(), Decoder, id, None).await?;
 
         manager.wait(Decoder).await;
+        seeked = manager.take_seek(&node).await;
+
+        if has_ladder {
+            clean_segments(&config, &live, Some(segment_age)).await;
+        }
 
-        while ingest_is_alive.load(Ordering::SeqCst) {
+        while ingest_is_alive.load(Ordering::SeqCst)
+            || manager.event_is_alive.load(Ordering::SeqCst)
+        {
             tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
         }
     }
@@ -0,0 +1,373 @@
+use std::{
+    process::Stdio,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    time::Duration,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures_util::{SinkExt, StreamExt};
+use log::*;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    process::{Child, Command},
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    time::{interval, sleep, timeout, MissedTickBehavior},
+};
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+use crate::player::controller::ChannelManager;
+use crate::utils::{config::PlayoutConfig, logging::Target};
+use crate::vec_strings;
+
+/// Frames per second, which the encoder gets from the browser.
+pub const HTML_FPS: u64 = 25;
+
+/// Transparent 1x1 PNG, until the browser sends the first frame.
+const EMPTY_FRAME: &[u8] = &[
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00, 0x00, 0x1f, 0x15, 0xc4,
+    0x89, 0x00, 0x00, 0x00, 0x0b, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0x60, 0x00, 0x02, 0x00,
+    0x00, 0x05, 0x00, 0x01, 0x7a, 0x5e, 0xab, 0x3f, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44,
+    0xae, 0x42, 0x60, 0x82,
+];
+
+/// Time for the browser to open the DevTools connection.
+const START_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Wait time, before a crashed browser is started again.
+const RESTART_DELAY: Duration = Duration::from_secs(5);
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Load a page, or run a script in the loaded page, like to start an animation.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct HtmlControl {
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub script: Option<String>,
+}
+
+/// Browser graphics, which the encoder composites over the program.
+#[derive(Debug)]
+pub struct HtmlOverlay {
+    frame: Mutex<Arc<Vec<u8>>>,
+    commands: Mutex<Option<UnboundedSender<HtmlControl>>>,
+}
+
+impl Default for HtmlOverlay {
+    fn default() -> Self {
+        Self {
+            frame: Mutex::new(Arc::new(EMPTY_FRAME.to_vec())),
+            commands: Mutex::new(None),
+        }
+    }
+}
+
+impl HtmlOverlay {
+    fn frame(&self) -> Arc<Vec<u8>> {
+        self.frame
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    fn set_frame(&self, frame: Vec<u8>) {
+        *self.frame.lock().unwrap_or_else(PoisonError::into_inner) = Arc::new(frame);
+    }
+
+    fn set_commands(&self, commands: Option<UnboundedSender<HtmlControl>>) {
+        *self.commands.lock().unwrap_or_else(PoisonError::into_inner) = commands;
+    }
+
+    /// Forward the control to the browser, `false` when no browser is running.
+    pub fn control(&self, control: HtmlControl) -> bool {
+        self.commands
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+            .is_some_and(|c| c.send(control).is_ok())
+    }
+}
+
+/// Start the headless browser, it prints the DevTools address on stderr.
+async fn launch(config: &PlayoutConfig) -> Result<(Child, String), String> {
+    let profile =
+        std::env::temp_dir().join(format!("ffplayout_html_{}", config.general.channel_id));
+    let mut child = Command::new(config.processing.html_browser.trim())
+        .args(vec_strings![
+            "--headless=new",
+            "--disable-gpu",
+            "--hide-scrollbars",
+            "--mute-audio",
+            "--remote-debugging-port=0",
+            format!("--user-data-dir={}", profile.display()),
+            format!(
+                "--window-size={},{}",
+                config.processing.width, config.processing.height
+            ),
+            "about:blank"
+        ])
+        .kill_on_drop(true)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    let stderr = child.stderr.take().ok_or("Browser has no stderr")?;
+    let mut lines = BufReader::new(stderr).lines();
+
+    let address = timeout(START_TIMEOUT, async {
+        while let Ok(Some(line)) = lines.next_line().await {
+            if let Some(address) = line.strip_prefix("DevTools listening on ") {
+                return Some(address.trim().to_string());
+            }
+        }
+
+        None
+    })
+    .await
+    .ok()
+    .flatten()
+    .ok_or("Browser sends no DevTools address")?;
+
+    // keep reading, a full pipe would block the browser
+    tokio::spawn(async move { while let Ok(Some(_)) = lines.next_line().await {} });
+
+    Ok((child, address))
+}
+
+async fn send(
+    ws: &mut Socket,
+    id: &mut u64,
+    session: Option<&str>,
+    method: &str,
+    params: Value,
+) -> Result<u64, String> {
+    *id += 1;
+    let mut msg = json!({"id": *id, "method": method, "params": params});
+
+    if let Some(session) = session {
+        msg["sessionId"] = json!(session);
+    }
+
+    ws.send(Message::text(msg.to_string()))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(*id)
+}
+
+/// Result of a command, events before it are dropped.
+async fn answer(ws: &mut Socket, id: u64) -> Result<Value, String> {
+    while let Some(msg) = ws.next().await {
+        if let Message::Text(text) = msg.map_err(|e| e.to_string())? {
+            let value: Value = serde_json::from_str(text.as_str()).map_err(|e| e.to_string())?;
+
+            if value["id"] == id {
+                if let Some(e) = value.get("error") {
+                    return Err(e.to_string());
+                }
+
+                return Ok(value["result"].clone());
+            }
+        }
+    }
+
+    Err("DevTools connection is closed".to_string())
+}
+
+async fn command(
+    ws: &mut Socket,
+    id: &mut u64,
+    session: Option<&str>,
+    method: &str,
+    params: Value,
+) -> Result<Value, String> {
+    let cmd = send(ws, id, session, method, params).await?;
+
+    answer(ws, cmd).await
+}
+
+/// Open the page with transparent background and forward its screencast,
+/// until the browser ends or the channel stops.
+async fn session(
+    overlay: &HtmlOverlay,
+    config: &PlayoutConfig,
+    address: &str,
+    url: &mut String,
+    commands: &mut UnboundedReceiver<HtmlControl>,
+    is_alive: &AtomicBool,
+) -> Result<(), String> {
+    let (width, height) = (config.processing.width, config.processing.height);
+    let (mut ws, _) = connect_async(address).await.map_err(|e| e.to_string())?;
+    let mut id = 0;
+    let page = if url.is_empty() {
+        "about:blank"
+    } else {
+        url.as_str()
+    };
+
+    let target = command(
+        &mut ws,
+        &mut id,
+        None,
+        "Target.createTarget",
+        json!({"url": page}),
+    )
+    .await?["targetId"]
+        .clone();
+    let session = command(
+        &mut ws,
+        &mut id,
+        None,
+        "Target.attachToTarget",
+        json!({"targetId": target, "flatten": true}),
+    )
+    .await?["sessionId"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    let session = Some(session.as_str());
+
+    for (method, params) in [
+        (
+            "Emulation.setDefaultBackgroundColorOverride",
+            json!({"color": {"r": 0, "g": 0, "b": 0, "a": 0}}),
+        ),
+        (
+            "Emulation.setDeviceMetricsOverride",
+            json!({"width": width, "height": height, "deviceScaleFactor": 1, "mobile": false}),
+        ),
+        ("Page.enable", json!({})),
+        (
+            "Page.startScreencast",
+            json!({"format": "png", "maxWidth": width, "maxHeight": height}),
+        ),
+    ] {
+        command(&mut ws, &mut id, session, method, params).await?;
+    }
+
+    let mut check = interval(Duration::from_secs(1));
+
+    loop {
+        tokio::select! {
+            msg = ws.next() => {
+                let Some(msg) = msg else {
+                    return Err("DevTools connection is closed".to_string());
+                };
+
+                if let Message::Text(text) = msg.map_err(|e| e.to_string())? {
+                    let value: Value = serde_json::from_str(text.as_str()).unwrap_or_default();
+
+                    if value["method"] == "Page.screencastFrame" {
+                        let params = &value["params"];
+
+                        if let Ok(frame) = STANDARD.decode(params["data"].as_str().unwrap_or_default()) {
+                            overlay.set_frame(frame);
+                        }
+
+                        send(&mut ws, &mut id, session, "Page.screencastFrameAck", json!({"sessionId": params["sessionId"]})).await?;
+                    } else if let Some(e) = value.get("error") {
+                        warn!(target: Target::file_mail(), channel = config.general.channel_id; "HTML overlay command failed: {e}");
+                    }
+                }
+            }
+            control = commands.recv() => {
+                let Some(control) = control else {
+                    return Ok(());
+                };
+
+                if let Some(page) = control.url {
+                    send(&mut ws, &mut id, session, "Page.navigate", json!({"url": page})).await?;
+                    *url = page;
+                }
+
+                if let Some(script) = control.script {
+                    send(&mut ws, &mut id, session, "Runtime.evaluate", json!({"expression": script})).await?;
+                }
+            }
+            _ = check.tick() => {
+                if !is_alive.load(Ordering::SeqCst) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Run the browser for the HTML overlay, a crashed browser is started again with the last page.
+pub async fn run(manager: ChannelManager, config: PlayoutConfig) {
+    let id = config.general.channel_id;
+    let overlay = manager.html.clone();
+    let (sender, mut commands) = unbounded_channel();
+    let mut url = config.processing.html_url.clone();
+
+    overlay.set_commands(Some(sender));
+
+    while manager.is_alive.load(Ordering::SeqCst) {
+        match launch(&config).await {
+            Ok((mut browser, address)) => {
+                if let Err(e) = session(
+                    &overlay,
+                    &config,
+                    &address,
+                    &mut url,
+                    &mut commands,
+                    &manager.is_alive,
+                )
+                .await
+                {
+                    error!(target: Target::file_mail(), channel = id; "HTML overlay failed: {e}");
+                }
+
+                if let Err(e) = browser.kill().await {
+                    warn!(target: Target::file_mail(), channel = id; "Stop browser of the HTML overlay failed: {e}");
+                }
+            }
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = id; "Start browser <b><magenta>{}</></b> failed: {e}", config.processing.html_browser);
+            }
+        }
+
+        overlay.set_frame(EMPTY_FRAME.to_vec());
+
+        if manager.is_alive.load(Ordering::SeqCst) {
+            sleep(RESTART_DELAY).await;
+        }
+    }
+
+    overlay.set_commands(None);
+}
+
+/// Send the latest frame to the encoder, which listens on the HTML socket of the config.
+/// After an encoder restart the new socket is taken.
+pub async fn feed(manager: ChannelManager) {
+    let overlay = manager.html.clone();
+
+    while manager.is_alive.load(Ordering::SeqCst) {
+        let socket = manager.config.lock().await.processing.html_socket.clone();
+
+        if let Some(socket) = socket {
+            if let Ok(mut stream) = TcpStream::connect(socket).await {
+                let mut tick = interval(Duration::from_millis(1000 / HTML_FPS));
+                tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+                while manager.is_alive.load(Ordering::SeqCst) {
+                    tick.tick().await;
+
+                    if stream.write_all(&overlay.frame()).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        sleep(Duration::from_millis(500)).await;
+    }
+}
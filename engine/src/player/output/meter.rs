@@ -0,0 +1,164 @@
+/// Loudness meter of the program, with ebur128 in the encoder.
+///
+/// The filter writes the momentary, short-term and integrated loudness ten times per second
+/// to stderr. The integrated loudness of the single items is calculated from the momentary
+/// values, which are the 400 ms blocks of EBU R128, with the same gates.
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+use crate::utils::config::{OutputMode::HLS, PlayoutConfig};
+
+/// Lowest value of the meter and absolute gate of the integrated loudness.
+const FLOOR: f64 = -70.0;
+
+/// Relative gate of the integrated loudness, under the ungated loudness.
+const RELATIVE_GATE: f64 = -10.0;
+
+/// Loudness values in LUFS.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Loudness {
+    pub momentary: f64,
+    pub short_term: f64,
+    /// Integrated loudness since the start of the encoder.
+    pub integrated: f64,
+    /// Integrated loudness of the running item.
+    pub item: f64,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    values: Loudness,
+    /// Momentary loudness of the running item.
+    blocks: Vec<f64>,
+}
+
+#[derive(Debug, Default)]
+pub struct LoudnessMeter {
+    enabled: bool,
+    state: Mutex<State>,
+}
+
+impl LoudnessMeter {
+    pub fn new(config: &PlayoutConfig) -> Self {
+        Self {
+            enabled: config.processing.loudness_meter && config.output.mode != HLS,
+            ..Default::default()
+        }
+    }
+
+    /// Take the line, when it is from the metadata of ebur128.
+    pub fn read_line(&self, line: &str) -> bool {
+        let Some((key, value)) = line
+            .strip_prefix("lavfi.r128.")
+            .and_then(|l| l.split_once('='))
+        else {
+            return false;
+        };
+        let value = value.trim().parse::<f64>().unwrap_or(FLOOR).max(FLOOR);
+        let mut state = self.state.lock().unwrap();
+
+        match key {
+            "M" => {
+                state.values.momentary = value;
+                state.blocks.push(value);
+            }
+            "S" => state.values.short_term = value,
+            "I" => state.values.integrated = value,
+            _ => return false,
+        }
+
+        true
+    }
+
+    pub fn values(&self) -> Option<Loudness> {
+        self.enabled.then(|| {
+            let state = self.state.lock().unwrap();
+
+            Loudness {
+                item: integrated(&state.blocks),
+                ..state.values
+            }
+        })
+    }
+
+    /// Integrated loudness of the finished item, the meter starts with the next one.
+    pub fn next_item(&self) -> Option<f64> {
+        let mut state = self.state.lock().unwrap();
+        let blocks = std::mem::take(&mut state.blocks);
+
+        (self.enabled && !blocks.is_empty()).then(|| integrated(&blocks))
+    }
+}
+
+/// Gated loudness of the blocks, like in EBU R128.
+fn integrated(blocks: &[f64]) -> f64 {
+    let mean = |gate: f64| {
+        let (sum, count) = blocks
+            .iter()
+            .filter(|b| **b > gate)
+            .fold((0.0, 0), |(sum, count), b| {
+                (sum + 10_f64.powf(b / 10.0), count + 1)
+            });
+
+        (count > 0).then(|| 10.0 * (sum / count as f64).log10())
+    };
+
+    mean(FLOOR)
+        .and_then(|ungated| mean(ungated + RELATIVE_GATE))
+        .unwrap_or(FLOOR)
+}
+
+/// Meter filter of the audio, `print` writes the metadata to stderr.
+///
+/// The audio comes in frames of 100 ms, so the values are written ten times per second.
+pub fn meter_filter(print: &str) -> String {
+    let keys = ["M", "S", "I"]
+        .iter()
+        .map(|k| format!("ametadata=mode=print:key=lavfi.r128.{k}:file={print}"))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    format!("asetnsamples=n=4800,ebur128=metadata=1,{keys}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::config::OutputMode::Stream;
+
+    #[test]
+    fn loudness_meter() {
+        let mut config = PlayoutConfig::default();
+        config.output.mode = Stream;
+        config.processing.loudness_meter = true;
+
+        let meter = LoudnessMeter::new(&config);
+
+        assert!(!meter.read_line("lavfi.black_start=1.2"));
+
+        for line in [
+            "lavfi.r128.M=-23.000",
+            "lavfi.r128.S=-24.500",
+            "lavfi.r128.I=-23.800",
+            "lavfi.r128.M=-23.000",
+            // silence is under the gate
+            "lavfi.r128.M=-inf",
+        ] {
+            assert!(meter.read_line(line));
+        }
+
+        let values = meter.values().unwrap();
+
+        assert_eq!(values.momentary, FLOOR);
+        assert_eq!(values.short_term, -24.5);
+        assert!((values.item + 23.0).abs() < 0.001);
+
+        // quiet blocks under the relative gate don't count
+        meter.read_line("lavfi.r128.M=-45.0");
+        assert!((meter.next_item().unwrap() + 23.0).abs() < 0.001);
+        assert_eq!(meter.next_item(), None);
+
+        assert_eq!(LoudnessMeter::default().values(), None);
+    }
+}
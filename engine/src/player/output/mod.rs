@@ -1,68 +1,790 @@
-use std::{process::Stdio, sync::atomic::Ordering};
+use std::{
+    process::Stdio,
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
 
 use log::*;
+use serde_json::json;
 use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt, BufReader, BufWriter},
-    process::{ChildStdin, Command},
+    io::BufReader,
+    net::UdpSocket,
+    process::{Child, ChildStdout, Command},
+    task::JoinHandle,
+    time::{interval_at, sleep, sleep_until, timeout, Instant},
 };
 
+pub mod archive;
+mod dash;
+pub mod delay;
 mod desktop;
+pub mod detect;
+pub mod extra;
+mod failover;
 mod hls;
+pub mod html;
+mod icecast;
+pub mod meter;
 mod null;
+pub mod preview;
+pub mod published;
+pub mod reload;
+mod segments;
+pub mod snapshot;
 mod stream;
+pub mod watchdog;
+pub mod whep;
 
 use crate::player::{
     controller::{ChannelManager, ProcessUnit::*},
-    input::{ingest_server, source_generator},
-    utils::{sec_to_time, stderr_reader},
+    filter::Filters,
+    input::{ingest_server, source_generator, voice, SourceIterator},
+    output::{detect::Detection, extra::Feed, meter::LoudnessMeter, watchdog::EncoderProgress},
+    utils::{
+        caption_input_index, gen_dummy, is_remote, loop_filler, loop_image, probe_live,
+        sec_to_time, seek_and_length, stderr_reader, ticker, Media,
+    },
 };
 use crate::utils::{
-    config::OutputMode::*,
+    config::{OutputMode::*, PlayoutConfig, ProcessMode::Folder, Transition, IMAGE_FORMAT},
     errors::ServiceError,
     logging::{fmt_cmd, Target},
     task_runner,
+    time_machine::lookahead,
+    webhook::{emit, media_data, WebhookEvent},
 };
 use crate::vec_strings;
 
+/// Seconds before the end of a clip, where the decoder for the next clip gets started.
+const PREROLL: f64 = 3.0;
+
+/// Length of one loop from the standby slate.
+const STANDBY_LOOP: f64 = 3600.0;
+
+/// Interval for checking, if the live ingest is running.
+const INGEST_POLL: Duration = Duration::from_millis(100);
+
+/// Time to wait for the encoder to exit, after the playout ended with an error.
+const ENCODER_EXIT: Duration = Duration::from_secs(2);
+
+/// Interval for checking, if the primary source of a playing backup is back.
+const FAILBACK_POLL: Duration = Duration::from_secs(10);
+
+/// Decoder process, which is started and waits on its stdout for reading.
+struct PrerolledDecoder {
+    node: Media,
+    proc: Child,
+    stdout: ChildStdout,
+    stderr_task: JoinHandle<Result<(), ServiceError>>,
+}
+
+fn decoder_cmd(config: &PlayoutConfig, node: &Media, ff_log_format: &str) -> Option<Vec<String>> {
+    let mut cmd = node.cmd.clone()?;
+    let mut dec_cmd = vec_strings!["-hide_banner", "-nostats", "-v", &ff_log_format];
+
+    if let Some(decoder_input_cmd) = &config.advanced.decoder.input_cmd {
+        dec_cmd.append(&mut decoder_input_cmd.clone());
+    }
+
+    dec_cmd.append(&mut cmd);
+
+    if let Some(mut filter) = node.filter.clone() {
+        dec_cmd.append(&mut filter.cmd());
+        dec_cmd.append(&mut filter.map());
+    }
+
+    if let Some(vtt) = dec_cmd
+        .iter()
+        .position(|s| s.ends_with(".vtt"))
+        .filter(|_| config.processing.vtt_enable)
+    {
+        // count only the inputs until the subtitle, a crossfade adds the next clip after it
+        let i = dec_cmd[..vtt]
+            .iter()
+            .filter(|&n| n == "-i")
+            .count()
+            .saturating_sub(1);
+
+        dec_cmd.append(&mut vec_strings!("-map", format!("{i}:s"), "-c:s", "copy"));
+    } else if let Some(i) = caption_input_index(config, &dec_cmd) {
+        dec_cmd.append(&mut vec_strings!("-map", format!("{i}:s"), "-c:s", "copy"));
+    }
+
+    if let Some(cmd) = &config.processing.cmd {
+        dec_cmd.extend_from_slice(cmd);
+    }
+
+    Some(dec_cmd)
+}
+
+/// Get the next playable node from the source iterator.
+async fn next_node(node_sources: &mut SourceIterator) -> Option<Media> {
+    while let Some(node) = node_sources.next().await {
+        trace!("Decoder CMD: {:?}", node.cmd);
+
+        node.cmd.as_ref()?;
+
+        if node.skip {
+            // skip is different from node.cmd = None.
+            // This source is valid, but too short to play,
+            // so better skip it and go to the next one.
+            continue;
+        }
+
+        return Some(node);
+    }
+
+    None
+}
+
+/// Get the next playable node from the source iterator and start its decoder.
+///
+/// The decoder opens the input and fills the pipe, but blocks until its output gets read,
+/// so the switch to the next clip doesn't wait for the ffmpeg startup.
+async fn preroll_decoder(
+    config: &PlayoutConfig,
+    node_sources: &mut SourceIterator,
+    ff_log_format: &str,
+) -> Result<Option<PrerolledDecoder>, ServiceError> {
+    let Some(node) = next_node(node_sources).await else {
+        return Ok(None);
+    };
+    let Some(dec_cmd) = decoder_cmd(config, &node, ff_log_format) else {
+        return Ok(None);
+    };
+
+    spawn_decoder(config, node, dec_cmd).map(Some)
+}
+
+/// Decoders for the clips after the running one.
+///
+/// The next clip is prerolled shortly before the running clip ends. A seeked, or continued clip
+/// plays before the prerolled clip, which waits in the queue until it is finished.
+#[derive(Default)]
+struct Preroll {
+    next: Option<PrerolledDecoder>,
+    queued: Option<PrerolledDecoder>,
+    /// The next clip is set for the running one.
+    ready: bool,
+}
+
+impl Preroll {
+    /// Take the decoder of the next clip, which gets the running one.
+    async fn take(&mut self, manager: &ChannelManager) -> Option<PrerolledDecoder> {
+        let decoder = self.next.take()?;
+        self.ready = false;
+
+        if self.queued.is_none() {
+            // the prerolled clip is playing now
+            *manager.preroll.lock().await = None;
+        }
+
+        Some(decoder)
+    }
+
+    /// Preroll the next clip, `lead` seconds before the running clip ends.
+    ///
+    /// The playlist is read with the clock at the begin of the next clip,
+    /// so its delta and the playlist date are the same as without preroll.
+    async fn start(
+        &mut self,
+        manager: &ChannelManager,
+        config: &PlayoutConfig,
+        node_sources: &mut SourceIterator,
+        ff_log_format: &str,
+        lead: f64,
+    ) -> Result<(), ServiceError> {
+        self.next = match self.queued.take() {
+            Some(decoder) => Some(decoder),
+            None => {
+                *manager.preroll.lock().await = Some(manager.current_index.load(Ordering::SeqCst));
+
+                lookahead(lead, preroll_decoder(config, node_sources, ff_log_format)).await?
+            }
+        };
+        self.ready = true;
+
+        Ok(())
+    }
+
+    /// Set the next clip, when it is not prerolled yet.
+    async fn fill(
+        &mut self,
+        config: &PlayoutConfig,
+        node_sources: &mut SourceIterator,
+        ff_log_format: &str,
+    ) -> Result<(), ServiceError> {
+        if !self.ready {
+            self.next = match self.queued.take() {
+                Some(decoder) => Some(decoder),
+                None => preroll_decoder(config, node_sources, ff_log_format).await?,
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Play the decoder before the prerolled clip.
+    fn insert(&mut self, decoder: PrerolledDecoder) {
+        if self.ready {
+            self.queued = self.next.take();
+        }
+
+        self.next = Some(decoder);
+    }
+
+    /// Drop the queued clip, it is outdated.
+    async fn discard_queued(&mut self, manager: &ChannelManager) {
+        drop(self.queued.take());
+        *manager.preroll.lock().await = None;
+    }
+
+    /// Drop all prerolled clips, they are outdated.
+    async fn discard(&mut self, manager: &ChannelManager) {
+        drop(self.next.take());
+        self.ready = false;
+        self.discard_queued(manager).await;
+    }
+}
+
+/// Start the decoder for the node, it blocks until its output gets read.
+fn spawn_decoder(
+    config: &PlayoutConfig,
+    node: Media,
+    dec_cmd: Vec<String>,
+) -> Result<PrerolledDecoder, ServiceError> {
+    let id = config.general.channel_id;
+
+    debug!(target: Target::file_mail(), channel = id;
+        "Decoder CMD: <bright-blue>ffmpeg {}</>",
+        fmt_cmd(&dec_cmd)
+    );
+
+    // create ffmpeg decoder instance, for reading the input files
+    let mut proc = Command::new("ffmpeg")
+        .args(dec_cmd)
+        .kill_on_drop(true)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let stdout = proc.stdout.take().unwrap();
+    let dec_err = BufReader::new(proc.stderr.take().unwrap());
+    let stderr_task = tokio::spawn(stderr_reader(
+        dec_err,
+        config.logging.clone(),
+        Decoder,
+        id,
+        None,
+    ));
+
+    Ok(PrerolledDecoder {
+        node,
+        proc,
+        stdout,
+        stderr_task,
+    })
+}
+
+/// Continue the rest of the clip with its backup, when the source ended too early,
+/// or with the source again, when it is back.
+async fn failover_node(
+    manager: &ChannelManager,
+    config: &PlayoutConfig,
+    node: &Media,
+    remaining: f64,
+    fail_back: bool,
+) -> Option<Media> {
+    if node.backup.is_empty() || remaining < 1.0 || (node.on_backup && !fail_back) {
+        return None;
+    }
+
+    let id = config.general.channel_id;
+    let mut next = node.clone();
+    next.swap_source();
+    next.probe = probe_live(config, &next.source).await;
+
+    if next.probe.is_none() {
+        error!(target: Target::file_mail(), channel = id; "Backup <b><magenta>{}</></b> is not reachable", next.source);
+
+        return None;
+    }
+
+    if fail_back {
+        info!(target: Target::file_mail(), channel = id; "Source <b><magenta>{}</></b> is back, switch from backup", next.source);
+    } else {
+        warn!(target: Target::file_mail(), channel = id; "Source <b><magenta>{}</></b> failed, switch to backup <b><magenta>{}</></b>", node.source, next.source);
+    }
+
+    next.seek = 0.0;
+    next.out = remaining;
+    next.duration = remaining;
+    next.trim_in = None;
+    next.trim_out = None;
+    next.transition_in = false;
+    next.cmd = Some(seek_and_length(config, &mut next));
+    next.add_filter(config, &manager.filter_chain).await;
+
+    Some(next)
+}
+
+/// Fade through black is set for the switch with the live ingest.
+fn live_fade(config: &PlayoutConfig) -> bool {
+    config.ingest.transition == Transition::FadeBlack && config.ingest.transition_duration > 0
+}
+
+/// Part of the clip, which was interrupted by the live ingest at the position.
+///
+/// The tail before live fades to black, the rest after live fades in from black.
+/// Images, loops and remote sources are not continued.
+async fn live_cut(
+    manager: &ChannelManager,
+    config: &PlayoutConfig,
+    node: &Media,
+    position: f64,
+    live_out: bool,
+    ff_log_format: &str,
+) -> Option<(Media, Vec<String>)> {
+    let mut end = node.out;
+
+    if live_out {
+        end = end.min(position + config.ingest.transition_duration as f64 / 2000.0);
+    }
+
+    if end - position < 0.5
+        || node.duration <= 0.0
+        || node.out > node.duration
+        || is_remote(&node.source)
+        || node
+            .source
+            .rsplit_once('.')
+            .is_some_and(|(_, e)| IMAGE_FORMAT.contains(&e.to_lowercase().as_str()))
+    {
+        return None;
+    }
+
+    let mut cut = node.clone();
+    cut.seek = position;
+    cut.out = end;
+    cut.transition_in = false;
+    cut.live_in = !live_out;
+    cut.live_out = live_out;
+    cut.cmd = Some(seek_and_length(config, &mut cut));
+    cut.add_filter(config, &manager.filter_chain).await;
+    let dec_cmd = decoder_cmd(config, &cut, ff_log_format)?;
+
+    Some((cut, dec_cmd))
+}
+
+/// Get the first node after the live ingest and start its decoder, it fades in from black.
+///
+/// A clip with a crossfade to the next one keeps its filter, the crossfade belongs to its command.
+async fn live_return(
+    manager: &ChannelManager,
+    config: &PlayoutConfig,
+    node_sources: &mut SourceIterator,
+    ff_log_format: &str,
+) -> Result<Option<PrerolledDecoder>, ServiceError> {
+    let Some(mut node) = next_node(node_sources).await else {
+        return Ok(None);
+    };
+
+    if live_fade(config) && !node.filter.as_ref().is_some_and(Filters::has_crossfade) {
+        node.live_in = true;
+        node.add_filter(config, &manager.filter_chain).await;
+    }
+
+    let Some(dec_cmd) = decoder_cmd(config, &node, ff_log_format) else {
+        return Ok(None);
+    };
+
+    spawn_decoder(config, node, dec_cmd).map(Some)
+}
+
+/// Holding slate for the standby mode, it loops for one hour and is started again when the standby lasts longer.
+async fn slate_media(manager: &ChannelManager, config: &PlayoutConfig) -> Option<Media> {
+    let source = config.storage.slate_path.to_string_lossy().to_string();
+    let mut node = Media::new(0, &source, true).await;
+
+    if node.probe.is_none() || source.is_empty() {
+        error!(target: Target::file_mail(), channel = config.general.channel_id; "Standby slate <b><magenta>{source}</></b> is not playable");
+
+        return None;
+    }
+
+    node.out = STANDBY_LOOP;
+
+    node.cmd = Some(
+        if source
+            .rsplit_once('.')
+            .is_some_and(|(_, e)| IMAGE_FORMAT.contains(&e.to_lowercase().as_str()))
+        {
+            loop_image(config, &node)
+        } else {
+            if node.duration <= 0.0 {
+                node.duration = STANDBY_LOOP;
+            }
+
+            loop_filler(config, &node)
+        },
+    );
+    node.add_filter(config, &manager.filter_chain).await;
+
+    Some(node)
+}
+
+/// Loop the standby slate, until the channel gets resumed.
+async fn standby(
+    manager: &ChannelManager,
+    config: &PlayoutConfig,
+    encoder: &Arc<Feed>,
+    ff_log_format: &str,
+) -> Result<(), ServiceError> {
+    let id = config.general.channel_id;
+
+    info!(target: Target::file_mail(), channel = id; "Switch from {} to standby", config.processing.mode);
+
+    while manager.standby.load(Ordering::SeqCst) && manager.is_alive.load(Ordering::SeqCst) {
+        let Some((node, dec_cmd)) = slate_media(manager, config).await.and_then(|node| {
+            let cmd = decoder_cmd(config, &node, ff_log_format)?;
+            Some((node, cmd))
+        }) else {
+            sleep(Duration::from_secs(1)).await;
+            continue;
+        };
+
+        let PrerolledDecoder {
+            proc,
+            stdout,
+            stderr_task,
+            ..
+        } = spawn_decoder(config, node, dec_cmd)?;
+
+        *manager.decoder.lock().await = Some(proc);
+
+        encoder.forward(stdout)?.await??;
+
+        manager.wait(Decoder).await;
+        stderr_task.await??;
+    }
+
+    info!(target: Target::file_mail(), channel = id; "Switch from standby to {}", config.processing.mode);
+
+    Ok(())
+}
+
+/// Emergency slate, a looped clip or image plays for one hour and is started again when the override lasts longer.
+/// A dummy clip stands in, when the slate is not playable.
+pub(crate) async fn emergency_media(manager: &ChannelManager, config: &PlayoutConfig) -> Media {
+    let source = config.storage.emergency_path.to_string_lossy().to_string();
+    let mut node = emergency_source(config, Media::new(0, &source, true).await);
+
+    node.add_filter(config, &manager.filter_chain).await;
+
+    node
+}
+
+/// Source and command of the probed emergency slate.
+pub(crate) fn emergency_source(config: &PlayoutConfig, mut node: Media) -> Media {
+    let source = node.source.clone();
+
+    if node.probe.is_none() || source.is_empty() {
+        error!(target: Target::file_mail(), channel = config.general.channel_id; "Emergency slate <b><magenta>{source}</></b> is not playable");
+
+        let (source, cmd) = gen_dummy(config, STANDBY_LOOP);
+        node.source = source;
+        node.duration = STANDBY_LOOP;
+        node.out = STANDBY_LOOP;
+        node.cmd = Some(cmd);
+    } else if source
+        .rsplit_once('.')
+        .is_some_and(|(_, e)| IMAGE_FORMAT.contains(&e.to_lowercase().as_str()))
+    {
+        node.out = STANDBY_LOOP;
+        node.cmd = Some(loop_image(config, &node));
+    } else if config.storage.emergency_loop {
+        node.out = STANDBY_LOOP;
+        node.cmd = Some(loop_filler(config, &node));
+    }
+
+    node
+}
+
+/// Play the emergency slate, until the override gets released or a clip, which doesn't loop, ends.
+async fn emergency(
+    manager: &ChannelManager,
+    config: &PlayoutConfig,
+    encoder: &Arc<Feed>,
+    ff_log_format: &str,
+) -> Result<(), ServiceError> {
+    let id = config.general.channel_id;
+
+    info!(target: Target::file_mail(), channel = id; "Switch from {} to emergency slate", config.processing.mode);
+
+    while manager.emergency.load(Ordering::SeqCst) && manager.is_alive.load(Ordering::SeqCst) {
+        let node = emergency_media(manager, config).await;
+        let Some(dec_cmd) = decoder_cmd(config, &node, ff_log_format) else {
+            sleep(Duration::from_secs(1)).await;
+            continue;
+        };
+
+        info!(target: Target::file_mail(), channel = id;
+            "Play for <yellow>{}</>: <b><magenta>{}</></b>",
+            sec_to_time(node.out - node.seek),
+            node.source
+        );
+
+        let PrerolledDecoder {
+            proc,
+            stdout,
+            stderr_task,
+            ..
+        } = spawn_decoder(config, node, dec_cmd)?;
+
+        *manager.decoder.lock().await = Some(proc);
+
+        encoder.forward(stdout)?.await??;
+
+        manager.wait(Decoder).await;
+        stderr_task.await??;
+
+        if !config.storage.emergency_loop {
+            manager.set_emergency(false, None).await;
+        }
+    }
+
+    info!(target: Target::file_mail(), channel = id; "Switch from emergency slate to {}", config.processing.mode);
+
+    Ok(())
+}
+
+/// Filler for the gap in the delayed live ingest, from the storage filler or a dummy clip.
+async fn delay_filler(manager: &ChannelManager, config: &PlayoutConfig, duration: f64) -> Media {
+    let source = config.storage.filler_path.to_string_lossy().to_string();
+    let mut node = Media::new(0, &source, true).await;
+
+    node.out = duration;
+
+    if node.probe.is_some() && config.storage.filler_path.is_file() {
+        node.cmd = Some(
+            if source
+                .rsplit_once('.')
+                .is_some_and(|(_, e)| IMAGE_FORMAT.contains(&e.to_lowercase().as_str()))
+            {
+                loop_image(config, &node)
+            } else {
+                if node.duration <= 0.0 {
+                    node.duration = duration;
+                }
+
+                loop_filler(config, &node)
+            },
+        );
+    } else {
+        let (source, cmd) = gen_dummy(config, duration);
+        node.source = source;
+        node.duration = duration;
+        node.cmd = Some(cmd);
+    }
+
+    node.add_filter(config, &manager.filter_chain).await;
+
+    node
+}
+
+/// Play the filler for the length of the delay, while the buffer gets filled.
+async fn delay_cover(
+    manager: &ChannelManager,
+    config: &PlayoutConfig,
+    encoder: &Arc<Feed>,
+    ff_log_format: &str,
+) -> Result<(), ServiceError> {
+    let node = delay_filler(manager, config, config.ingest.delay as f64).await;
+    let Some(dec_cmd) = decoder_cmd(config, &node, ff_log_format) else {
+        return Ok(());
+    };
+
+    let PrerolledDecoder {
+        proc,
+        stdout,
+        stderr_task,
+        ..
+    } = spawn_decoder(config, node, dec_cmd)?;
+
+    *manager.decoder.lock().await = Some(proc);
+
+    encoder.forward(stdout)?.await??;
+    stderr_task.await??;
+
+    Ok(())
+}
+
+/// Forward the live ingest with the delay from the config, until it ends.
+async fn delayed_live(
+    manager: &ChannelManager,
+    config: &PlayoutConfig,
+    source: ChildStdout,
+    encoder: &Arc<Feed>,
+    ff_log_format: &str,
+) -> Result<(), ServiceError> {
+    let id = config.general.channel_id;
+    let delay = Duration::from_secs(config.ingest.delay as u64);
+    let (mut chunks, reader) = delay::read(source);
+
+    info!(target: Target::file_mail(), channel = id; "Delay live ingest by <yellow>{}</> seconds", config.ingest.delay);
+
+    manager.ingest_dump.store(false, Ordering::SeqCst);
+    delay_cover(manager, config, encoder, ff_log_format).await?;
+
+    while let Some((arrived, chunk)) = chunks.recv().await {
+        let due = arrived + delay;
+
+        while Instant::now() < due && manager.is_alive.load(Ordering::SeqCst) {
+            if manager.ingest_dump.load(Ordering::SeqCst) {
+                break;
+            }
+
+            sleep_until(due.min(Instant::now() + INGEST_POLL)).await;
+        }
+
+        // the held part is dropped, the emergency slate goes on air at once
+        if !manager.is_alive.load(Ordering::SeqCst) || manager.emergency.load(Ordering::SeqCst) {
+            break;
+        }
+
+        if manager.ingest_dump.swap(false, Ordering::SeqCst) {
+            let dropped = chunk.len() + delay::dump(&mut chunks);
+
+            warn!(target: Target::file_mail(), channel = id; "Dump <yellow>{}</> KB of the delayed live ingest", dropped / 1024);
+
+            delay_cover(manager, config, encoder, ff_log_format).await?;
+            continue;
+        }
+
+        encoder.write(chunk).await?;
+    }
+
+    reader.abort();
+
+    Ok(())
+}
+
+/// Forward the live ingest or a scheduled live event to the encoder, as long as one is running.
+async fn live(
+    manager: &ChannelManager,
+    config: &PlayoutConfig,
+    encoder: &Arc<Feed>,
+    ff_log_format: &str,
+) -> Result<(), ServiceError> {
+    loop {
+        // scheduled events have priority over the ingest
+        let (live_stdout, delayed) = if manager.emergency.load(Ordering::SeqCst) {
+            break;
+        } else if manager.event_is_alive.load(Ordering::SeqCst) {
+            (manager.event_stdout.lock().await.take(), false)
+        } else if manager.ingest_is_alive.load(Ordering::SeqCst) {
+            (
+                manager.ingest_stdout.lock().await.take(),
+                config.ingest.delay > 0,
+            )
+        } else {
+            break;
+        };
+
+        match live_stdout {
+            Some(stdout) if delayed => {
+                delayed_live(manager, config, stdout, encoder, ff_log_format).await?;
+            }
+            Some(stdout) => {
+                encoder.forward(stdout)?.await??;
+            }
+            None => sleep(INGEST_POLL).await,
+        }
+    }
+
+    Ok(())
+}
+
+/// Log the integrated loudness of the finished item, for the as-run record of the channel,
+/// send the end of the item and the start of the next one to the webhooks
+/// and count the next one for the alert rules.
+async fn item_change(manager: &ChannelManager, last_item: &mut Option<Media>, node: &Media) {
+    let loudness = match manager.progress.lock().await.as_ref() {
+        Some(progress) => progress.meter.next_item(),
+        None => None,
+    };
+    let config = manager.config.lock().await;
+
+    if let Some(item) = last_item.take() {
+        if let Some(loudness) = loudness {
+            info!(target: Target::file_mail(), channel = manager.id;
+                "Loudness <yellow>{loudness:.1}</> LUFS: <b><magenta>{}</></b>", item.source
+            );
+        }
+
+        let mut data = media_data(&item);
+        data["loudness"] = json!(loudness);
+
+        emit(&config, WebhookEvent::ClipEnd, data);
+    }
+
+    emit(&config, WebhookEvent::ClipStart, media_data(node));
+    manager.alert_history.lock().await.item(node);
+    *last_item = Some(node.clone());
+}
+
+/// Play the sources, until the playout ends or the encoder gets reloaded.
+///
+/// Returns `true`, when a changed config needs a new encoder.
 async fn play(
     manager: ChannelManager,
-    mut enc_writer: BufWriter<ChildStdin>,
+    encoder: Arc<Feed>,
     ff_log_format: &str,
-) -> Result<(), ServiceError> {
+) -> Result<bool, ServiceError> {
     let config = manager.config.lock().await.clone();
     let id = config.general.channel_id;
     let playlist_init = manager.list_init.clone();
     let is_alive = manager.is_alive.clone();
     let ingest_is_alive = manager.ingest_is_alive.clone();
-    let mut buffer = vec![0u8; 64 * 1024];
-    let mut live_on = false;
+    let event_is_alive = manager.event_is_alive.clone();
+
+    manager.splice_cues.lock().await.reset();
+
+    if manager.emergency.load(Ordering::SeqCst) {
+        emergency(&manager, &config, &encoder, ff_log_format).await?;
+    }
+
+    if manager.standby.load(Ordering::SeqCst) {
+        standby(&manager, &config, &encoder, ff_log_format).await?;
+    }
+
+    *manager.preroll.lock().await = None;
+    manager.preroll_discarded.store(false, Ordering::SeqCst);
 
     // get source iterator
     let mut node_sources = source_generator(manager.clone()).await;
+    let mut preroll = Preroll::default();
+    preroll
+        .fill(&config, &mut node_sources, ff_log_format)
+        .await?;
+    let mut seeked = false;
+    // finished item for the loudness log
+    let mut last_item = None;
 
-    while let Some(node) = node_sources.next().await {
+    while let Some(decoder) = preroll.take(&manager).await {
+        let PrerolledDecoder {
+            node,
+            proc,
+            stdout: decoder_stdout,
+            stderr_task: error_decoder_task,
+        } = decoder;
         *manager.current_media.lock().await = Some(node.clone());
-        let ignore_dec = config.logging.ignore_lines.clone();
 
         if !is_alive.load(Ordering::SeqCst) {
             debug!(target: Target::file_mail(), channel = id; "Playout is stopped, break out from source loop");
             break;
         }
 
-        trace!("Decoder CMD: {:?}", node.cmd);
-
-        let mut cmd = match node.cmd {
-            Some(cmd) => cmd,
-            None => break,
-        };
-
-        if node.skip {
-            // skip is different from node.cmd = None.
-            // This source is valid, but too short to play,
-            // so better skip it and go to the next one.
-            continue;
-        }
-
         let c_index = if cfg!(debug_assertions) {
             format!(
                 " ({}/{})",
@@ -80,6 +802,19 @@ async fn play(
             node.audio
         );
 
+        if seeked {
+            // the clip is only continued at another position
+            seeked = false;
+        } else {
+            manager.splice_cue(&node, true).await;
+            manager.secondary_events(&node).await;
+            item_change(&manager, &mut last_item, &node).await;
+
+            if config.output.mode == Icecast {
+                tokio::spawn(icecast::update_metadata(config.clone(), node.clone()));
+            }
+        }
+
         if config.task.enable {
             if config.task.path.is_file() {
                 let channel_mgr_3 = manager.clone();
@@ -93,100 +828,225 @@ async fn play(
             }
         }
 
-        let mut dec_cmd = vec_strings!["-hide_banner", "-nostats", "-v", &ff_log_format];
+        *manager.clone().decoder.lock().await = Some(proc);
 
-        if let Some(decoder_input_cmd) = &config.advanced.decoder.input_cmd {
-            dec_cmd.append(&mut decoder_input_cmd.clone());
-        }
+        let preroll_at =
+            Instant::now() + Duration::from_secs_f64((node.out - node.seek - PREROLL).max(0.0));
 
-        dec_cmd.append(&mut cmd);
+        let started = Instant::now();
+        let mut pipe = encoder.forward(decoder_stdout)?;
+        let mut switch_live = false;
+        let mut fail_back = false;
+        let mut failback_poll = interval_at(started + FAILBACK_POLL, FAILBACK_POLL);
 
-        if let Some(mut filter) = node.filter {
-            dec_cmd.append(&mut filter.cmd());
-            dec_cmd.append(&mut filter.map());
+        loop {
+            tokio::select! {
+                result = &mut pipe => {
+                    result??;
+                    break;
+                }
+                () = sleep_until(preroll_at), if !preroll.ready => {
+                    let lead = (node.out - node.seek - started.elapsed().as_secs_f64()).max(0.0);
+
+                    preroll.start(&manager, &config, &mut node_sources, ff_log_format, lead).await?;
+                }
+                () = sleep(INGEST_POLL) => {
+                    if (ingest_is_alive.load(Ordering::SeqCst) || event_is_alive.load(Ordering::SeqCst))
+                        && !manager.emergency.load(Ordering::SeqCst)
+                    {
+                        switch_live = true;
+                        break;
+                    }
+                }
+                _ = failback_poll.tick(), if node.on_backup && !fail_back => {
+                    if probe_live(&config, &node.backup).await.is_some() {
+                        // the rest of the clip is played from the source again
+                        fail_back = true;
+                        manager.stop(Decoder).await;
+                    }
+                }
+            }
         }
 
-        if config.processing.vtt_enable && dec_cmd.iter().any(|s| s.ends_with(".vtt")) {
-            let i = dec_cmd
-                .iter()
-                .filter(|&n| n == "-i")
-                .count()
-                .saturating_sub(1);
+        if switch_live {
+            info!(target: Target::file_mail(), channel = id; "Switch from {} to live ingest", config.processing.mode);
+            playlist_init.store(true, Ordering::SeqCst);
 
-            dec_cmd.append(&mut vec_strings!("-map", format!("{i}:s"), "-c:s", "copy"));
-        }
+            let position = node.seek + started.elapsed().as_secs_f64();
+            let folder = config.processing.mode == Folder;
 
-        if let Some(cmd) = &config.processing.cmd {
-            dec_cmd.extend_from_slice(cmd);
-        }
+            // the tail is started before the decoder stops, so there is no gap in the stream
+            let tail = if live_fade(&config) {
+                live_cut(&manager, &config, &node, position, true, ff_log_format)
+                    .await
+                    .map(|(n, cmd)| spawn_decoder(&config, n, cmd))
+                    .transpose()?
+            } else {
+                None
+            };
 
-        debug!(target: Target::file_mail(), channel = id;
-            "Decoder CMD: <bright-blue>ffmpeg {}</>",
-            fmt_cmd(&dec_cmd)
-        );
+            // the decoder must be finished, before the ingest writes to the encoder
+            manager.stop(Decoder).await;
+            let _ = pipe.await;
 
-        // create ffmpeg decoder instance, for reading the input files
-        let mut dec_proc = Command::new("ffmpeg")
-            .args(dec_cmd)
-            .kill_on_drop(true)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()?;
+            if let Some(PrerolledDecoder {
+                proc,
+                stdout,
+                stderr_task,
+                ..
+            }) = tail
+            {
+                *manager.decoder.lock().await = Some(proc);
 
-        let mut decoder_stdout = dec_proc.stdout.take().unwrap();
-        let dec_err = BufReader::new(dec_proc.stderr.take().unwrap());
+                encoder.forward(stdout)?.await??;
+                stderr_task.await??;
+            }
 
-        *manager.clone().decoder.lock().await = Some(dec_proc);
+            // playlist gets initialized again after live, so the next clip is outdated,
+            // in folder mode a prerolled clip stays the next one
+            if !folder || !preroll.ready {
+                preroll.discard(&manager).await;
+            } else {
+                preroll.discard_queued(&manager).await;
+            }
 
-        let error_decoder_task = tokio::spawn(stderr_reader(dec_err, ignore_dec, Decoder, id));
+            live(&manager, &config, &encoder, ff_log_format).await?;
 
-        loop {
-            if ingest_is_alive.load(Ordering::SeqCst) {
-                // read from ingest server instance
-                if !live_on {
-                    info!(target: Target::file_mail(), channel = id; "Switch from {} to live ingest", config.processing.mode);
-                    playlist_init.store(true, Ordering::SeqCst);
-
-                    manager.stop(Decoder).await;
-                    live_on = true;
-                }
+            info!(target: Target::file_mail(), channel = id; "Switch from live ingest to {}", config.processing.mode);
 
-                let mut ingest_stdout_guard = manager.ingest_stdout.lock().await;
-                if let Some(ref mut ingest_stdout) = *ingest_stdout_guard {
-                    let num = ingest_stdout.read(&mut buffer[..]).await?;
+            if !preroll.ready {
+                // folder mode continues the interrupted clip
+                let rest = if folder {
+                    live_cut(&manager, &config, &node, position, false, ff_log_format).await
+                } else {
+                    None
+                };
 
-                    if num == 0 {
-                        continue;
+                preroll.next = match rest {
+                    Some((rest, dec_cmd)) => {
+                        seeked = true;
+                        Some(spawn_decoder(&config, rest, dec_cmd)?)
+                    }
+                    None => {
+                        live_return(&manager, &config, &mut node_sources, ff_log_format).await?
                     }
+                };
+                preroll.ready = true;
+            }
+        }
 
-                    enc_writer.write_all(&buffer[..num]).await?;
-                }
-            } else {
-                // read from decoder instance
-                if live_on {
-                    info!(target: Target::file_mail(), channel = id; "Switch from live ingest to {}", config.processing.mode);
+        manager.wait(Decoder).await;
+        error_decoder_task.await??;
 
-                    live_on = false;
-                    break;
-                }
+        // a killed decoder is stopped on purpose, from a control command or for the fail back
+        let killed = manager
+            .decoder
+            .lock()
+            .await
+            .as_mut()
+            .and_then(|p| p.try_wait().ok().flatten())
+            .is_some_and(|status| status.code().is_none());
 
-                let num = decoder_stdout.read(&mut buffer[..]).await?;
+        if manager.emergency.load(Ordering::SeqCst) {
+            // the program continues at the current time, after the override is released
+            preroll.discard(&manager).await;
+            playlist_init.store(true, Ordering::SeqCst);
 
-                if num == 0 {
-                    break;
-                }
+            emergency(&manager, &config, &encoder, ff_log_format).await?;
 
-                enc_writer.write_all(&buffer[..num]).await?;
+            if !manager.standby.load(Ordering::SeqCst) {
+                preroll
+                    .fill(&config, &mut node_sources, ff_log_format)
+                    .await?;
+                continue;
             }
         }
 
-        drop(decoder_stdout);
+        if manager.standby.load(Ordering::SeqCst) {
+            // a prerolled clip is outdated, after standby the playlist continues at the current time
+            preroll.discard(&manager).await;
+            playlist_init.store(true, Ordering::SeqCst);
 
-        manager.wait(Decoder).await;
-        error_decoder_task.await??;
+            standby(&manager, &config, &encoder, ff_log_format).await?;
+
+            preroll
+                .fill(&config, &mut node_sources, ff_log_format)
+                .await?;
+            continue;
+        }
+
+        // a control command, or a changed playlist, makes the prerolled clip outdated
+        let preroll_pending = manager.preroll.lock().await.is_some();
+
+        if manager.preroll_discarded.swap(false, Ordering::SeqCst)
+            || (preroll_pending
+                && (playlist_init.load(Ordering::SeqCst) || node_sources.changed().await))
+        {
+            preroll.discard(&manager).await;
+        }
+
+        if manager.reload.swap(false, Ordering::SeqCst) {
+            // the prerolled clip is built with the old config
+            preroll.discard(&manager).await;
+            playlist_init.store(true, Ordering::SeqCst);
+
+            return Ok(true);
+        }
+
+        let seek = manager.take_seek(&node).await.and_then(|n| {
+            let cmd = decoder_cmd(&config, &n, ff_log_format)?;
+            Some((n, cmd))
+        });
+        let failover = if seek.is_none()
+            && !switch_live
+            && (fail_back || !killed)
+            && is_alive.load(Ordering::SeqCst)
+        {
+            let remaining = node.out - node.seek - started.elapsed().as_secs_f64();
+
+            failover_node(&manager, &config, &node, remaining, fail_back)
+                .await
+                .and_then(|n| {
+                    let cmd = decoder_cmd(&config, &n, ff_log_format)?;
+                    Some((n, cmd))
+                })
+        } else {
+            None
+        };
+
+        if let Some((seek_node, dec_cmd)) = seek {
+            preroll.insert(spawn_decoder(&config, seek_node, dec_cmd)?);
+            seeked = true;
+        } else if let Some((failover, dec_cmd)) = failover {
+            preroll.insert(spawn_decoder(&config, failover, dec_cmd)?);
+            seeked = true;
+        } else {
+            preroll
+                .fill(&config, &mut node_sources, ff_log_format)
+                .await?;
+        }
     }
 
-    Ok(())
+    Ok(false)
+}
+
+/// Start the main encoder for the output mode.
+async fn spawn_encoder(
+    manager: &ChannelManager,
+    config: &PlayoutConfig,
+    log_format: &str,
+) -> Result<Child, ServiceError> {
+    match config.output.mode {
+        Dash => dash::output(config, log_format).await,
+        Desktop => desktop::output(config, log_format).await,
+        Null => null::output(config, log_format).await,
+        Stream | Srt | Multicast | Icecast => {
+            let enc_config = failover::encoder_config(manager, config);
+
+            stream::output(&enc_config, log_format).await
+        }
+        _ => panic!("Output mode doesn't exists!"),
+    }
 }
 
 /// Player
@@ -201,62 +1061,201 @@ async fn play(
 pub async fn player(manager: ChannelManager) -> Result<(), ServiceError> {
     let config = manager.config.lock().await.clone();
     let config_clone = config.clone();
-    let ff_log_format = format!("level+{}", config.logging.ffmpeg_level.to_lowercase());
-    let ignore_enc = config.logging.ignore_lines.clone();
+    let dec_log_format = format!("level+{}", config.logging.level(Decoder));
+    let enc_log_format = format!("level+{}", config.logging.level(Encoder));
+    let logging = config.logging.clone();
     let channel_id = config.general.channel_id;
 
+    if config.output.scte35
+        && config.output.mode != HLS
+        && !config
+            .output
+            .output_cmd
+            .as_ref()
+            .is_some_and(|c| c.contains(&"hls".to_string()))
+    {
+        warn!(target: Target::file_mail(), channel = channel_id; "SCTE-35 cues are only written to HLS playlists");
+    }
+
+    if config.text.ticker && !config.processing.audio_only {
+        ticker::init(&config);
+        tokio::spawn(ticker::run(manager.clone(), config.clone()));
+    }
+
+    if config.processing.html_socket.is_some() {
+        tokio::spawn(html::run(manager.clone(), config.clone()));
+        tokio::spawn(html::feed(manager.clone()));
+    }
+
+    if config.ingest.voice_socket.is_some() {
+        tokio::spawn(voice::server(manager.clone(), config.clone()));
+        tokio::spawn(voice::feed(manager.clone()));
+    } else if config.ingest.voice {
+        warn!(target: Target::file_mail(), channel = channel_id; "Voice-over needs a continuous encoder with audio filtering, one output and no copied audio");
+    }
+
     if config.output.mode == HLS {
-        hls::writer(&manager, &ff_log_format).await?;
+        if config.processing.html {
+            warn!(target: Target::file_mail(), channel = channel_id; "HTML overlay needs a continuous encoder and doesn't run in HLS mode");
+        }
+
+        if config.archive.enable
+            || config.archive.screenshots
+            || config.outputs.iter().any(|o| o.active)
+        {
+            warn!(target: Target::file_mail(), channel = channel_id; "Additional outputs and the archive need a continuous encoder and don't run in HLS mode");
+        }
+
+        if config.ingest.record {
+            warn!(target: Target::file_mail(), channel = channel_id; "Live recording doesn't run in HLS mode");
+        }
+
+        hls::writer(&manager, &dec_log_format).await?;
         manager.stop_all(false).await;
 
         return Ok(());
     }
 
     // get ffmpeg output instance
-    let mut enc_proc = match config.output.mode {
-        Desktop => desktop::output(&config, &ff_log_format).await?,
-        Null => null::output(&config, &ff_log_format).await?,
-        Stream => stream::output(&config, &ff_log_format).await?,
-        _ => panic!("Output mode doesn't exists!"),
-    };
+    let mut enc_proc = spawn_encoder(&manager, &config, &enc_log_format).await?;
 
     let enc_err = BufReader::new(enc_proc.stderr.take().unwrap());
-    let enc_writer = BufWriter::new(enc_proc.stdin.take().unwrap());
+    let outputs = extra::start(&manager, &config, &enc_log_format).await;
+    let encoder = Arc::new(Feed::new(enc_proc.stdin.take().unwrap(), outputs)?);
 
     *manager.encoder.lock().await = Some(enc_proc);
     let mgr_clone2 = manager.clone();
 
-    // spawn a task to log ffmpeg output error messages
-    let handle_enc_stderr = tokio::spawn(stderr_reader(enc_err, ignore_enc, Encoder, channel_id));
+    // spawn a task to log ffmpeg output error messages and to follow the encoder progress
+    let mut progress = Arc::new(EncoderProgress::new(
+        Detection::new(&config),
+        LoudnessMeter::new(&config),
+    ));
+    *manager.progress.lock().await = Some(progress.clone());
+    let mut handle_enc_stderr = tokio::spawn(stderr_reader(
+        enc_err,
+        logging,
+        Encoder,
+        channel_id,
+        Some(progress.clone()),
+    ));
 
     // spawn a task for ffmpeg ingest server and create a channel for package sending
-    let handle_ingest = if config.ingest.enable {
+    let mut handle_ingest = if config.ingest.enable || config.ingest.srt_enable {
         Some(tokio::spawn(ingest_server(config_clone, mgr_clone2)))
     } else {
         None
     };
 
-    tokio::select! {
-        result = handle_enc_stderr => {
-            result??;
+    if config.ingest.monitor && config.ingest.any() {
+        match UdpSocket::bind("127.0.0.1:0").await {
+            Ok(socket) => {
+                let mgr = manager.clone();
+                manager.ingest_preview.set_address(socket.local_addr().ok());
+
+                tokio::spawn(async move {
+                    preview::monitor(socket, &mgr.ingest_preview, &mgr.is_alive).await;
+                });
+            }
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = channel_id; "Ingest monitor is not possible: {e}");
+            }
         }
+    }
 
-        result = async {
-            if let Some(f) = handle_ingest {
-                f.await?
-            } else {
-                Ok(())
+    if config.ingest.record && config.ingest.any() {
+        tokio::spawn(archive::run_ingest(manager.clone(), config.clone()));
+    }
+
+    let mut config = config;
+    let mut started = Instant::now();
+    let mut encoder_ended = false;
+
+    let result = loop {
+        tokio::select! {
+            result = &mut handle_enc_stderr => {
+                encoder_ended = true;
+                break result.map_err(ServiceError::from).and_then(|r| r);
+            }
+
+            result = async {
+                match handle_ingest.as_mut() {
+                    Some(f) => f.await?,
+                    None => Ok(()),
+                }
+            }, if handle_ingest.is_some() => {
+                break result;
+            }
+
+            result = play(manager.clone(), encoder.clone(), &dec_log_format) => {
+                match result {
+                    Ok(true) => {}
+                    result => break result.map(|_| ()),
+                }
+            }
+
+            error = watchdog::watch(&progress, channel_id) => {
+                break Err(error);
             }
-        }, if handle_ingest.is_some() => {
-            result?;
         }
 
-        result = play(manager.clone(), enc_writer, &ff_log_format) => {
-            result?;
+        // reload: the new encoder gets the feed, the old one ends with the end of its input
+        let new_config = manager.config.lock().await.clone();
+        let log_format = format!("level+{}", new_config.logging.level(Encoder));
+
+        let mut new_proc = match spawn_encoder(&manager, &new_config, &log_format).await {
+            Ok(proc) => proc,
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = channel_id; "New encoder failed, keep the running one: {e}");
+                continue;
+            }
+        };
+
+        let new_err = BufReader::new(new_proc.stderr.take().unwrap());
+        encoder.replace(new_proc.stdin.take().unwrap()).await?;
+
+        progress = Arc::new(EncoderProgress::new(
+            Detection::new(&new_config),
+            LoudnessMeter::new(&new_config),
+        ));
+        *manager.progress.lock().await = Some(progress.clone());
+        // the old reader logs the last lines of the old encoder
+        handle_enc_stderr = tokio::spawn(stderr_reader(
+            new_err,
+            new_config.logging.clone(),
+            Encoder,
+            channel_id,
+            Some(progress.clone()),
+        ));
+
+        if let Some(mut old_proc) = manager.encoder.lock().await.replace(new_proc) {
+            tokio::spawn(async move {
+                if timeout(ENCODER_EXIT, old_proc.wait()).await.is_err() {
+                    let _ = old_proc.kill().await;
+                }
+            });
         }
+
+        info!(target: Target::file_mail(), channel = channel_id; "Config applied with a new encoder");
+
+        config = new_config;
+        started = Instant::now();
+    };
+
+    // the decoder pipe can break before the encoder error is read, so check the process itself
+    let encoder_exited = encoder_ended
+        || match manager.encoder.lock().await.as_mut() {
+            Some(proc) if result.is_err() => timeout(ENCODER_EXIT, proc.wait()).await.is_ok(),
+            _ => false,
+        };
+
+    *manager.progress.lock().await = None;
+
+    if encoder_exited {
+        failover::encoder_ended(&manager, &config, started.elapsed()).await;
     }
 
     trace!("Out of source loop");
 
-    Ok(())
+    result
 }
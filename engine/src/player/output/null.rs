@@ -11,6 +11,7 @@ use crate::vec_strings;
 use crate::{
     player::{
         controller::ProcessUnit::*,
+        output::watchdog::PROGRESS_ARGS,
         utils::{prepare_output_cmd, Media},
     },
     utils::errors::ServiceError,
@@ -22,6 +23,7 @@ use crate::{
 pub async fn output(config: &PlayoutConfig, log_format: &str) -> Result<Child, ServiceError> {
     let id = config.general.channel_id;
     let mut enc_prefix = vec_strings!["-hide_banner", "-nostats", "-v", log_format];
+    enc_prefix.extend(PROGRESS_ARGS.map(String::from));
     let mut media = Media {
         unit: Encoder,
         ..Default::default()
@@ -0,0 +1,238 @@
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, PoisonError,
+    },
+    time::Duration,
+};
+
+use actix_web::web::Bytes;
+use tokio::{
+    io::AsyncReadExt, net::UdpSocket, process::ChildStdout, sync::broadcast, time::timeout,
+};
+
+use crate::db::models::ExtraOutput;
+use crate::utils::config::PlayoutConfig;
+use crate::vec_strings;
+
+/// Size of a MPEG-TS packet, the viewers get only whole packets.
+const TS_PACKET: usize = 188;
+
+/// Packets in one chunk, around 25ms of the preview bitrate.
+const CHUNK_PACKETS: usize = 16;
+
+/// Chunks a viewer can lag behind, before it loses data.
+const VIEWER_QUEUE: usize = 256;
+
+/// Width of the preview, the height follows the aspect of the processing.
+const PREVIEW_WIDTH: i64 = 640;
+
+/// UDP payload of the ingest monitor, 7 MPEG-TS packets.
+const MONITOR_PACKET: usize = TS_PACKET * 7;
+
+/// The ingest monitor stops running, when no stream arrives for this time.
+const MONITOR_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Low latency program preview, as MPEG-TS stream for the web UI.
+#[derive(Debug)]
+pub struct Preview {
+    pub running: AtomicBool,
+    sender: broadcast::Sender<Bytes>,
+    /// Local address, where the ingest server sends the monitor stream to.
+    address: Mutex<Option<SocketAddr>>,
+}
+
+impl Default for Preview {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(VIEWER_QUEUE);
+
+        Self {
+            running: AtomicBool::new(false),
+            sender,
+            address: Mutex::new(None),
+        }
+    }
+}
+
+impl Preview {
+    /// New viewer, it starts with the next chunk.
+    pub fn subscribe(&self) -> broadcast::Receiver<Bytes> {
+        self.sender.subscribe()
+    }
+
+    pub fn address(&self) -> Option<SocketAddr> {
+        *self.address.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn set_address(&self, address: Option<SocketAddr>) {
+        *self.address.lock().unwrap_or_else(PoisonError::into_inner) = address;
+    }
+}
+
+fn preview_size(config: &PlayoutConfig) -> (i64, i64) {
+    let height =
+        (PREVIEW_WIDTH as f64 / config.processing.aspect.max(0.1) / 2.0).round() as i64 * 2;
+    let gop = (config.processing.fps / 2.0).round().max(1.0) as i64;
+
+    (height, gop)
+}
+
+/// Preview encoder as additional output: small size, short GOP and no muxer buffering.
+pub fn output(config: &PlayoutConfig) -> ExtraOutput {
+    let (height, gop) = preview_size(config);
+
+    ExtraOutput {
+        id: 0,
+        channel_id: config.general.channel_id,
+        name: "Preview".to_string(),
+        param: format!(
+            "-s {PREVIEW_WIDTH}x{height} -c:v libx264 -b:v 800k -maxrate 800k -bufsize 400k \
+            -x264-params keyint={gop}:min-keyint={gop}:scenecut=-1 -preset veryfast -tune zerolatency \
+            -c:a aac -b:a 96k -ar 44100 -f mpegts -flush_packets 1 -muxdelay 0 pipe:1"
+        ),
+        active: true,
+    }
+}
+
+/// Preview encoder for WebRTC, which publishes to the media server behind the WHEP endpoint.
+///
+/// WebRTC needs Opus audio and H.264 without B-frames, RTSP and SRT can carry both.
+pub fn whep_output(config: &PlayoutConfig) -> Option<ExtraOutput> {
+    let target = config.output.preview_publish.trim();
+
+    if config.output.preview_whep_url.trim().is_empty() || target.is_empty() {
+        return None;
+    }
+
+    let (height, gop) = preview_size(config);
+    let format = if target.starts_with("rtsp") {
+        "-f rtsp -rtsp_transport tcp"
+    } else {
+        "-f mpegts"
+    };
+
+    Some(ExtraOutput {
+        id: 0,
+        channel_id: config.general.channel_id,
+        name: "Preview WebRTC".to_string(),
+        param: format!(
+            "-s {PREVIEW_WIDTH}x{height} -c:v libx264 -profile:v baseline -pix_fmt yuv420p -b:v 800k -maxrate 800k -bufsize 400k \
+            -x264-params keyint={gop}:min-keyint={gop}:scenecut=-1 -preset veryfast -tune zerolatency \
+            -c:a libopus -b:a 96k -ar 48000 -ac 2 {format} {target}"
+        ),
+        active: true,
+    })
+}
+
+/// Read the stream of the preview encoder and send it to the viewers.
+pub async fn publish(mut stdout: ChildStdout, preview: &Preview) {
+    let mut buffer = vec![0; TS_PACKET * CHUNK_PACKETS];
+
+    preview.running.store(true, Ordering::SeqCst);
+
+    while stdout.read_exact(&mut buffer).await.is_ok() {
+        // no viewer is no error
+        let _ = preview.sender.send(Bytes::copy_from_slice(&buffer));
+    }
+
+    preview.running.store(false, Ordering::SeqCst);
+}
+
+/// Monitor of the ingest server, the incoming stream in preview size to the local socket.
+pub fn ingest_output(config: &PlayoutConfig, address: SocketAddr) -> Vec<String> {
+    let (height, gop) = preview_size(config);
+
+    vec_strings![
+        "-map",
+        "0:v?",
+        "-map",
+        "0:a?",
+        "-s",
+        format!("{PREVIEW_WIDTH}x{height}"),
+        "-c:v",
+        "libx264",
+        "-b:v",
+        "500k",
+        "-x264-params",
+        format!("keyint={gop}:min-keyint={gop}:scenecut=-1"),
+        "-preset",
+        "ultrafast",
+        "-tune",
+        "zerolatency",
+        "-c:a",
+        "aac",
+        "-b:a",
+        "64k",
+        "-ar",
+        "44100",
+        "-f",
+        "mpegts",
+        format!("udp://{address}?pkt_size={MONITOR_PACKET}")
+    ]
+}
+
+/// Receive the stream of the ingest monitor and send it to the viewers, as long as the channel runs.
+///
+/// A new playout start sets a new socket address, then the old monitor ends.
+pub async fn monitor(socket: UdpSocket, preview: &Preview, is_alive: &AtomicBool) {
+    let mut buffer = vec![0; MONITOR_PACKET];
+    let address = socket.local_addr().ok();
+
+    while is_alive.load(Ordering::SeqCst) && preview.address() == address {
+        match timeout(MONITOR_TIMEOUT, socket.recv(&mut buffer)).await {
+            Ok(Ok(n)) => {
+                preview.running.store(true, Ordering::SeqCst);
+
+                // a datagram holds only whole packets
+                let _ = preview
+                    .sender
+                    .send(Bytes::copy_from_slice(&buffer[..n - n % TS_PACKET]));
+            }
+            Ok(Err(_)) | Err(_) => preview.running.store(false, Ordering::SeqCst),
+        }
+    }
+
+    if preview.address() == address {
+        preview.set_address(None);
+        preview.running.store(false, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preview_output() {
+        let mut config = PlayoutConfig::default();
+        config.processing.aspect = 16.0 / 9.0;
+        config.processing.fps = 25.0;
+
+        let output = output(&config);
+
+        assert!(output.param.starts_with("-s 640x360 "));
+        assert!(output.param.contains("keyint=13:min-keyint=13"));
+        assert!(output
+            .param
+            .ends_with("-f mpegts -flush_packets 1 -muxdelay 0 pipe:1"));
+
+        let cmd = ingest_output(&config, "127.0.0.1:40000".parse().unwrap());
+
+        assert_eq!(cmd[..4], ["-map", "0:v?", "-map", "0:a?"]);
+        assert!(cmd.contains(&"640x360".to_string()));
+        assert_eq!(cmd.last().unwrap(), "udp://127.0.0.1:40000?pkt_size=1316");
+
+        assert!(whep_output(&config).is_none());
+
+        config.output.preview_whep_url = "http://127.0.0.1:8889/preview1/whep".to_string();
+        config.output.preview_publish = "rtsp://127.0.0.1:8554/preview1".to_string();
+
+        let output = whep_output(&config).unwrap();
+
+        assert!(output.param.contains("-profile:v baseline"));
+        assert!(output
+            .param
+            .ends_with("-c:a libopus -b:a 96k -ar 48000 -ac 2 -f rtsp -rtsp_transport tcp rtsp://127.0.0.1:8554/preview1"));
+    }
+}
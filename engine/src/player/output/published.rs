@@ -0,0 +1,254 @@
+/// Monitor of the published output.
+///
+/// The prober pulls the stream from the monitor URL, like the HLS playlist on the CDN or the RTMP
+/// server, decodes a few seconds and compares the streams with the program. This catches failures
+/// between the encoder and the viewers, which the encoder itself doesn't see.
+use std::{process::Stdio, sync::atomic::Ordering};
+
+use log::*;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::{
+    process::Command,
+    time::{sleep, timeout, Duration},
+};
+
+use crate::player::controller::ChannelManager;
+use crate::utils::{
+    config::{OutputMode, PlayoutConfig},
+    logging::Target,
+    webhook::{emit, WebhookEvent},
+};
+
+/// Seconds of the stream, which are decoded for the check.
+const PROBE_LENGTH: &str = "%+5";
+
+/// A probe which takes longer than this, counts as failed.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Shortest time between two probes.
+pub const MIN_INTERVAL: i64 = 30;
+
+#[derive(Debug, Default, Deserialize)]
+struct ProbeStream {
+    codec_type: Option<String>,
+    width: Option<i64>,
+    height: Option<i64>,
+    nb_read_frames: Option<String>,
+}
+
+impl ProbeStream {
+    fn decoded(&self, kind: &str) -> bool {
+        self.codec_type.as_deref() == Some(kind)
+            && self
+                .nb_read_frames
+                .as_deref()
+                .and_then(|f| f.parse::<u64>().ok())
+                .is_some_and(|f| f > 0)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ProbeResult {
+    #[serde(default)]
+    streams: Vec<ProbeStream>,
+}
+
+/// What the published output should have.
+#[derive(Debug, PartialEq, Eq)]
+struct Expected {
+    /// Size of the program, `None` when any size is fine, like with an HLS ladder.
+    size: Option<(i64, i64)>,
+    video: bool,
+}
+
+impl Expected {
+    fn new(config: &PlayoutConfig) -> Self {
+        let video = !config.processing.audio_only && config.output.mode != OutputMode::Icecast;
+        let ladder = !config.output.hls_ladder.is_empty()
+            && matches!(config.output.mode, OutputMode::HLS | OutputMode::Dash);
+
+        Self {
+            size: (video && !ladder).then_some((config.processing.width, config.processing.height)),
+            video,
+        }
+    }
+}
+
+/// Compare the decoded streams with the program, gives the reason of a mismatch.
+fn verify(result: &ProbeResult, expected: &Expected) -> Result<(), String> {
+    let videos = result
+        .streams
+        .iter()
+        .filter(|s| s.decoded("video"))
+        .collect::<Vec<_>>();
+
+    if expected.video && videos.is_empty() {
+        return Err("no decodable video".to_string());
+    }
+
+    if let Some((width, height)) = expected.size {
+        if !videos
+            .iter()
+            .any(|s| s.width == Some(width) && s.height == Some(height))
+        {
+            let found = videos
+                .iter()
+                .map(|s| format!("{}x{}", s.width.unwrap_or(0), s.height.unwrap_or(0)))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            return Err(format!("resolution {found}, expected {width}x{height}"));
+        }
+    }
+
+    if !result.streams.iter().any(|s| s.decoded("audio")) {
+        return Err("no decodable audio".to_string());
+    }
+
+    Ok(())
+}
+
+/// Pull and decode the beginning of the published stream.
+async fn probe(url: &str) -> Result<ProbeResult, String> {
+    let mut cmd = Command::new("ffprobe");
+
+    cmd.args([
+        "-v",
+        "error",
+        "-rw_timeout",
+        "15000000",
+        "-read_intervals",
+        PROBE_LENGTH,
+        "-count_frames",
+        "-show_streams",
+        "-print_format",
+        "json",
+        url,
+    ])
+    .stdin(Stdio::null())
+    .kill_on_drop(true);
+
+    let out = timeout(PROBE_TIMEOUT, cmd.output())
+        .await
+        .map_err(|_| "no response in time".to_string())?
+        .map_err(|e| e.to_string())?;
+
+    if !out.status.success() {
+        let err = String::from_utf8_lossy(&out.stderr);
+
+        return Err(err.lines().last().unwrap_or("not readable").to_string());
+    }
+
+    serde_json::from_slice(&out.stdout).map_err(|e| e.to_string())
+}
+
+/// Probe the published output in the monitor interval, as long as the playout runs.
+///
+/// A failed check is reported once, with mail and webhook, and again only after the output was fine.
+pub async fn monitor(manager: ChannelManager) {
+    let id = manager.id;
+    let mut failing = false;
+
+    while manager.is_alive.load(Ordering::SeqCst) {
+        let config = manager.config.lock().await.clone();
+        let interval = config.output.monitor_interval.max(MIN_INTERVAL) as u64;
+
+        // the first probe waits one interval, so the output has time to reach its target
+        sleep(Duration::from_secs(interval)).await;
+
+        let url = config.output.monitor_url.trim();
+
+        if url.is_empty() || !manager.is_alive.load(Ordering::SeqCst) {
+            continue;
+        }
+
+        let result = match probe(url).await {
+            Ok(result) => verify(&result, &Expected::new(&config)),
+            Err(e) => Err(format!("not readable: {e}")),
+        };
+
+        match result {
+            Err(reason) if !failing => {
+                failing = true;
+
+                error!(target: Target::file_mail(), channel = id; "Published output <b><magenta>{url}</></b> failed: {reason}");
+                emit(
+                    &config,
+                    WebhookEvent::OutputError,
+                    json!({"url": url, "reason": reason}),
+                );
+            }
+            Ok(()) if failing => {
+                failing = false;
+
+                info!(target: Target::file_mail(), channel = id; "Published output <b><magenta>{url}</></b> is fine again");
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream(kind: &str, size: Option<(i64, i64)>, frames: &str) -> ProbeStream {
+        ProbeStream {
+            codec_type: Some(kind.to_string()),
+            width: size.map(|s| s.0),
+            height: size.map(|s| s.1),
+            nb_read_frames: Some(frames.to_string()),
+        }
+    }
+
+    #[test]
+    fn published_output_check() {
+        let expected = Expected {
+            size: Some((1920, 1080)),
+            video: true,
+        };
+        let mut result = ProbeResult {
+            streams: vec![
+                stream("video", Some((1920, 1080)), "125"),
+                stream("audio", None, "235"),
+            ],
+        };
+
+        assert_eq!(verify(&result, &expected), Ok(()));
+
+        result.streams[0] = stream("video", Some((1280, 720)), "125");
+        assert_eq!(
+            verify(&result, &expected),
+            Err("resolution 1280x720, expected 1920x1080".to_string())
+        );
+
+        let ladder = Expected {
+            size: None,
+            video: true,
+        };
+        assert_eq!(verify(&result, &ladder), Ok(()));
+
+        result.streams[1] = stream("audio", None, "0");
+        assert_eq!(
+            verify(&result, &ladder),
+            Err("no decodable audio".to_string())
+        );
+
+        result.streams.remove(0);
+        assert_eq!(
+            verify(&result, &ladder),
+            Err("no decodable video".to_string())
+        );
+
+        let parsed: ProbeResult = serde_json::from_str(
+            r#"{"streams": [{"codec_type": "audio", "nb_read_frames": "50", "channels": 2}]}"#,
+        )
+        .unwrap();
+        let radio = Expected {
+            size: None,
+            video: false,
+        };
+        assert_eq!(verify(&parsed, &radio), Ok(()));
+    }
+}
@@ -0,0 +1,89 @@
+use serde::Serialize;
+
+use crate::utils::config::{Output, OutputMode::*, PlayoutConfig};
+
+/// How a changed config gets applied to a running channel.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigReload {
+    /// Nothing from the playout has changed.
+    None,
+    /// A new encoder takes over at the next clip, the playlist continues at the current position.
+    Encoder,
+    /// The changes need a restart of the playout.
+    Restart,
+}
+
+fn changed<T: Serialize>(old: &T, new: &T) -> bool {
+    serde_json::to_value(old).ok() != serde_json::to_value(new).ok()
+}
+
+/// Output settings, which go to the encoder, the monitor of the published stream
+/// and the WebRTC preview read their own.
+fn encoder_output(output: &Output) -> Output {
+    Output {
+        monitor_url: String::new(),
+        monitor_interval: 0,
+        preview_whep_url: String::new(),
+        preview_publish: String::new(),
+        ..output.clone()
+    }
+}
+
+/// Compare the running config with the new one.
+///
+/// HLS has no separate encoder, and the ingest, the storage and the additional outputs
+/// are set up once at start, for them a restart is still needed.
+pub fn config_reload(old: &PlayoutConfig, new: &PlayoutConfig) -> ConfigReload {
+    let playout_changed = changed(&encoder_output(&old.output), &encoder_output(&new.output))
+        || changed(&old.processing, &new.processing)
+        || changed(&old.text, &new.text)
+        || changed(&old.playlist, &new.playlist);
+
+    if changed(&old.ingest, &new.ingest)
+        || changed(&old.storage, &new.storage)
+        || changed(&old.archive, &new.archive)
+        || old.output.preview != new.output.preview
+        || old.output.preview_publish != new.output.preview_publish
+        || old.output.snapshot != new.output.snapshot
+        || (playout_changed && (old.output.mode == HLS || new.output.mode == HLS))
+    {
+        ConfigReload::Restart
+    } else if playout_changed {
+        ConfigReload::Encoder
+    } else {
+        ConfigReload::None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reload_for_changes() {
+        let mut old = PlayoutConfig::default();
+        old.output.mode = Stream;
+        let mut new = old.clone();
+
+        new.mail.recipient = "admin@example.org".to_string();
+        new.output.monitor_url = "https://cdn.example.org/live/stream.m3u8".to_string();
+        new.output.preview_whep_url = "http://127.0.0.1:8889/preview1/whep".to_string();
+        assert_eq!(config_reload(&old, &new), ConfigReload::None);
+
+        new.processing.width = 1280;
+        new.output.output_param = "-c:v libx264 -f flv rtmp://127.0.0.1/live".to_string();
+        assert_eq!(config_reload(&old, &new), ConfigReload::Encoder);
+
+        new.output.mode = HLS;
+        assert_eq!(config_reload(&old, &new), ConfigReload::Restart);
+
+        let mut new = old.clone();
+        new.ingest.enable = true;
+        assert_eq!(config_reload(&old, &new), ConfigReload::Restart);
+
+        let mut new = old.clone();
+        new.output.preview_publish = "rtsp://127.0.0.1:8554/preview1".to_string();
+        assert_eq!(config_reload(&old, &new), ConfigReload::Restart);
+    }
+}
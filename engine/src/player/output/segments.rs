@@ -0,0 +1,66 @@
+/// Segmenter files of the HLS and DASH outputs, which are written by ffmpeg to the public folder.
+use std::{
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use log::*;
+
+use crate::utils::{config::PlayoutConfig, logging::Target};
+
+const SEGMENTS: [&str; 2] = ["ts", "m4s"];
+const MANIFESTS: [&str; 2] = ["m3u8", "mpd"];
+
+/// Generated files are named `stream_*`, beside the master playlist and the MPD.
+fn is_generated(name: &str) -> bool {
+    name.starts_with("stream_") || name == "master.m3u8" || name == "stream.mpd"
+}
+
+/// Delete generated segments and manifests in `dir`. With `max_age` only segments,
+/// which are older and so not in the playlists anymore, otherwise all files from the last run.
+pub async fn clean_segments(config: &PlayoutConfig, dir: &Path, max_age: Option<Duration>) {
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return;
+    };
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let ext = path.extension().unwrap_or_default().to_string_lossy();
+
+        if !is_generated(&name) {
+            continue;
+        }
+
+        let outdated = match max_age {
+            Some(age) => {
+                SEGMENTS.contains(&ext.as_ref())
+                    && entry
+                        .metadata()
+                        .await
+                        .and_then(|m| m.modified())
+                        .ok()
+                        .and_then(|m| SystemTime::now().duration_since(m).ok())
+                        .is_some_and(|d| d > age)
+            }
+            None => SEGMENTS.contains(&ext.as_ref()) || MANIFESTS.contains(&ext.as_ref()),
+        };
+
+        if outdated {
+            if let Err(e) = tokio::fs::remove_file(&path).await {
+                error!(target: Target::file_mail(), channel = config.general.channel_id; "Delete segment <b><magenta>{}</></b>: {e}", path.display());
+            }
+        }
+    }
+}
+
+/// Folder of the manifest in the output command.
+pub fn manifest_dir<'a>(config: &'a PlayoutConfig, ext: &str) -> Option<&'a Path> {
+    config
+        .output
+        .output_cmd
+        .as_ref()?
+        .iter()
+        .find(|p| p.ends_with(ext))
+        .and_then(|p| Path::new(p).parent())
+}
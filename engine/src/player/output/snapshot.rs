@@ -0,0 +1,46 @@
+use std::{path::PathBuf, time::Duration};
+
+use tokio::fs;
+
+use crate::db::models::ExtraOutput;
+use crate::utils::{config::PlayoutConfig, errors::ServiceError};
+
+/// Seconds between two snapshots.
+pub const SNAPSHOT_INTERVAL: u64 = 5;
+
+/// A snapshot older than this is from a stopped channel.
+pub const SNAPSHOT_MAX_AGE: Duration = Duration::from_secs(SNAPSHOT_INTERVAL * 6);
+
+/// Width of the snapshot, the height follows the aspect of the processing.
+const SNAPSHOT_WIDTH: i64 = 640;
+
+/// File of the latest program frame.
+pub fn path(config: &PlayoutConfig) -> PathBuf {
+    std::env::temp_dir()
+        .join("ffplayout")
+        .join(format!("snapshot_{}.jpg", config.general.channel_id))
+}
+
+/// Snapshot encoder as additional output, it overwrites the image every few seconds.
+pub async fn output(config: &PlayoutConfig) -> Result<ExtraOutput, ServiceError> {
+    let target = path(config);
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    let height =
+        (SNAPSHOT_WIDTH as f64 / config.processing.aspect.max(0.1) / 2.0).round() as i64 * 2;
+
+    Ok(ExtraOutput {
+        id: 0,
+        channel_id: config.general.channel_id,
+        name: "Snapshot".to_string(),
+        param: format!(
+            "-an -r 1/{SNAPSHOT_INTERVAL} -s {SNAPSHOT_WIDTH}x{height} -c:v mjpeg -q:v 5 \
+            -f image2 -update 1 -atomic_writing 1 {}",
+            shlex::try_quote(&target.to_string_lossy()).unwrap_or_default()
+        ),
+        active: true,
+    })
+}
@@ -0,0 +1,262 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use log::*;
+use serde::Serialize;
+use tokio::time::{interval, Instant};
+
+use super::{
+    detect::Detection,
+    meter::{Loudness, LoudnessMeter},
+};
+use crate::utils::{errors::ServiceError, logging::Target};
+
+/// Encoder options, which write the progress every second to stderr.
+pub const PROGRESS_ARGS: [&str; 4] = ["-stats_period", "1", "-progress", "pipe:2"];
+
+/// Encoder, which makes no progress for this time, counts as stalled.
+const ENCODER_STALL: Duration = Duration::from_secs(15);
+
+/// Encoder, which runs slower than this speed, falls behind real time.
+const REALTIME_SPEED: f64 = 0.98;
+
+/// Statistics of the main encoder, for the status API.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EncoderStats {
+    pub frame: u64,
+    pub fps: f64,
+    /// Output bitrate in kbit/s.
+    pub bitrate: f64,
+    pub speed: f64,
+    pub dup_frames: u64,
+    pub drop_frames: u64,
+    /// Encoder runs slower than real time.
+    pub behind: bool,
+    /// Program video is black longer than the alert time.
+    pub black: bool,
+    /// Program audio is silent longer than the alert time.
+    pub silent: bool,
+    /// Values of the loudness meter, when it's on.
+    pub loudness: Option<Loudness>,
+}
+
+/// Progress of the main encoder, from its stderr.
+#[derive(Debug)]
+pub struct EncoderProgress {
+    started: Instant,
+    /// Milliseconds since start, when the output time moved last, 0 before the first progress.
+    moved: AtomicU64,
+    out_time: AtomicU64,
+    frame: AtomicU64,
+    /// Float values are stored as bits.
+    fps: AtomicU64,
+    bitrate: AtomicU64,
+    speed: AtomicU64,
+    dup_frames: AtomicU64,
+    drop_frames: AtomicU64,
+    detection: Detection,
+    pub meter: LoudnessMeter,
+}
+
+impl Default for EncoderProgress {
+    fn default() -> Self {
+        Self::new(Detection::default(), LoudnessMeter::default())
+    }
+}
+
+impl EncoderProgress {
+    pub fn new(detection: Detection, meter: LoudnessMeter) -> Self {
+        Self {
+            started: Instant::now(),
+            moved: AtomicU64::new(0),
+            out_time: AtomicU64::new(0),
+            frame: AtomicU64::new(0),
+            fps: AtomicU64::new(0),
+            bitrate: AtomicU64::new(0),
+            speed: AtomicU64::new(0),
+            dup_frames: AtomicU64::new(0),
+            drop_frames: AtomicU64::new(0),
+            detection,
+            meter,
+        }
+    }
+
+    /// Take the line, when it is from the progress report or the metadata of the filters.
+    ///
+    /// Progress lines are `key=value` pairs without log level, the output time
+    /// is used, because it also moves for audio only channels.
+    pub fn read_line(&self, line: &str) -> bool {
+        // header of the frame metadata
+        if line.starts_with("frame:") && line.contains("pts_time:") {
+            return true;
+        }
+
+        if self.detection.read_line(line) || self.meter.read_line(line) {
+            return true;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            return false;
+        };
+
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return false;
+        }
+
+        match key {
+            "out_time_us" => {
+                if let Ok(time) = value.trim().parse::<u64>() {
+                    if self.out_time.fetch_max(time, Ordering::SeqCst) < time {
+                        let elapsed = self.started.elapsed().as_millis() as u64;
+                        self.moved.store(elapsed.max(1), Ordering::SeqCst);
+                    }
+                }
+            }
+            "frame" => store_int(&self.frame, value),
+            "dup_frames" => store_int(&self.dup_frames, value),
+            "drop_frames" => store_int(&self.drop_frames, value),
+            "fps" => self
+                .fps
+                .store(float_value(value, "").to_bits(), Ordering::Relaxed),
+            "bitrate" => self
+                .bitrate
+                .store(float_value(value, "kbits/s").to_bits(), Ordering::Relaxed),
+            "speed" => self
+                .speed
+                .store(float_value(value, "x").to_bits(), Ordering::Relaxed),
+            _ => {}
+        }
+
+        true
+    }
+
+    pub fn stats(&self) -> EncoderStats {
+        let speed = f64::from_bits(self.speed.load(Ordering::Relaxed));
+
+        EncoderStats {
+            frame: self.frame.load(Ordering::Relaxed),
+            fps: f64::from_bits(self.fps.load(Ordering::Relaxed)),
+            bitrate: f64::from_bits(self.bitrate.load(Ordering::Relaxed)),
+            speed,
+            dup_frames: self.dup_frames.load(Ordering::Relaxed),
+            drop_frames: self.drop_frames.load(Ordering::Relaxed),
+            behind: speed > 0.0 && speed < REALTIME_SPEED,
+            black: self.detection.is_black(),
+            silent: self.detection.is_silent(),
+            loudness: self.meter.values(),
+        }
+    }
+
+    /// Time since the output time moved last, `None` before the first progress.
+    fn idle(&self) -> Option<Duration> {
+        match self.moved.load(Ordering::SeqCst) {
+            0 => None,
+            moved => Some(
+                self.started
+                    .elapsed()
+                    .saturating_sub(Duration::from_millis(moved)),
+            ),
+        }
+    }
+}
+
+/// Float of a progress value, like `4000.5kbits/s` or `1.01x`, `N/A` is 0.
+fn float_value(value: &str, unit: &str) -> f64 {
+    value
+        .trim()
+        .trim_end_matches(unit)
+        .parse::<f64>()
+        .unwrap_or_default()
+}
+
+fn store_int(atomic: &AtomicU64, value: &str) {
+    if let Ok(v) = value.trim().parse::<u64>() {
+        atomic.store(v, Ordering::Relaxed);
+    }
+}
+
+/// Watch the progress of the encoder and end with an error, when it stalls.
+///
+/// The error ends the playout, which gets started again and continues at the current playlist position.
+/// The watch begins with the first progress, so a slow start of the encoder is no stall.
+pub async fn watch(progress: &EncoderProgress, channel_id: i32) -> ServiceError {
+    let mut check = interval(Duration::from_secs(1));
+
+    loop {
+        check.tick().await;
+        progress.detection.check();
+
+        if let Some(idle) = progress.idle().filter(|idle| *idle >= ENCODER_STALL) {
+            error!(target: Target::file_mail(), channel = channel_id; "Encoder stalled for <yellow>{}</> seconds, restart playout", idle.as_secs());
+
+            return ServiceError::Conflict("Encoder stalled".to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encoder_progress_lines() {
+        let progress = EncoderProgress::default();
+
+        assert!(!progress.read_line("[info] Stream mapping:"));
+        assert!(!progress.read_line("[warning] key=value in a message"));
+        assert!(progress.read_line("frame:12   pts:480     pts_time:0.48"));
+        assert_eq!(progress.idle(), None);
+
+        assert!(progress.read_line("frame=250"));
+        assert!(progress.read_line("out_time_us=10000000"));
+        assert!(progress.read_line("progress=continue"));
+        assert!(progress.idle().is_some());
+
+        let moved = progress.moved.load(Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(5));
+
+        // same output time is no progress
+        progress.read_line("out_time_us=10000000");
+        assert_eq!(progress.moved.load(Ordering::SeqCst), moved);
+
+        progress.read_line("out_time_us=11000000");
+        assert!(progress.moved.load(Ordering::SeqCst) > moved);
+    }
+
+    #[test]
+    fn encoder_progress_stats() {
+        let progress = EncoderProgress::default();
+
+        for line in [
+            "frame=500",
+            "fps=24.87",
+            "bitrate=4012.3kbits/s",
+            "dup_frames=3",
+            "drop_frames=1",
+            "speed=0.95x",
+        ] {
+            assert!(progress.read_line(line));
+        }
+
+        assert_eq!(
+            progress.stats(),
+            EncoderStats {
+                frame: 500,
+                fps: 24.87,
+                bitrate: 4012.3,
+                speed: 0.95,
+                dup_frames: 3,
+                drop_frames: 1,
+                behind: true,
+                ..Default::default()
+            }
+        );
+
+        progress.read_line("speed=1.01x");
+        progress.read_line("bitrate=N/A");
+        assert!(!progress.stats().behind);
+        assert_eq!(progress.stats().bitrate, 0.0);
+    }
+}
@@ -0,0 +1,122 @@
+/// WHEP playback of the low latency preview, over a WebRTC media server like MediaMTX.
+///
+/// The preview encoder publishes to the media server, and the offer of the viewer is passed
+/// to the WHEP endpoint of the server. The engine keeps only the sessions, for ending them.
+use std::{sync::atomic::Ordering, time::Duration};
+
+use log::*;
+use reqwest::{header, StatusCode, Url};
+use uuid::Uuid;
+
+use crate::player::controller::ChannelManager;
+use crate::utils::{errors::ServiceError, logging::Target};
+
+/// Timeout for requests to the media server.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sessions, which are kept for ending them. Viewers, which are gone without ending their session,
+/// are closed by the media server, so the oldest session gets dropped.
+const MAX_SESSIONS: usize = 32;
+
+#[derive(Clone, Debug)]
+pub struct WhepSession {
+    pub id: String,
+    /// Resource of the session on the media server.
+    location: Url,
+}
+
+/// Pass the offer of a viewer to the media server, returns the answer and the session ID.
+pub async fn play(
+    manager: &ChannelManager,
+    offer: String,
+) -> Result<(String, String), ServiceError> {
+    let config = manager.config.lock().await.clone();
+    let id = config.general.channel_id;
+
+    if !config.output.preview || config.output.preview_whep_url.trim().is_empty() {
+        return Err(ServiceError::ServiceUnavailable(
+            "WebRTC preview is disabled".to_string(),
+        ));
+    }
+
+    if !manager.is_alive.load(Ordering::SeqCst) {
+        return Err(ServiceError::ServiceUnavailable(
+            "Preview is not running".to_string(),
+        ));
+    }
+
+    let url = Url::parse(&config.output.preview_whep_url)
+        .map_err(|e| ServiceError::Conflict(format!("Invalid WHEP url: {e}")))?;
+
+    let response = reqwest::Client::new()
+        .post(url.clone())
+        .header(header::CONTENT_TYPE, "application/sdp")
+        .body(offer)
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| ServiceError::ServiceUnavailable(format!("Media server: {e}")))?;
+
+    if response.status() != StatusCode::CREATED {
+        warn!(target: Target::file_mail(), channel = id; "WHEP offer rejected by media server: <yellow>{}</>", response.status());
+
+        return Err(ServiceError::BadRequest(format!(
+            "Offer rejected by media server: {}",
+            response.status()
+        )));
+    }
+
+    let location = response
+        .headers()
+        .get(header::LOCATION)
+        .and_then(|l| l.to_str().ok())
+        .and_then(|l| url.join(l).ok())
+        .ok_or(ServiceError::ServiceUnavailable(
+            "Media server sent no session".to_string(),
+        ))?;
+    let answer = response
+        .text()
+        .await
+        .map_err(|e| ServiceError::ServiceUnavailable(format!("Media server: {e}")))?;
+    let session = WhepSession {
+        id: Uuid::new_v4().simple().to_string(),
+        location,
+    };
+
+    debug!(target: Target::file_mail(), channel = id; "WHEP preview session <yellow>{}</> started", session.id);
+
+    let mut sessions = manager.whep.lock().await;
+
+    if sessions.len() >= MAX_SESSIONS {
+        sessions.remove(0);
+    }
+
+    sessions.push(session.clone());
+
+    Ok((answer, session.id))
+}
+
+/// End the session of a viewer on the media server.
+pub async fn stop(manager: &ChannelManager, session_id: &str) -> Result<(), ServiceError> {
+    let mut sessions = manager.whep.lock().await;
+    let session = sessions
+        .iter()
+        .position(|s| s.id == session_id)
+        .map(|i| sessions.remove(i))
+        .ok_or(ServiceError::BadRequest("Session not found".to_string()))?;
+
+    drop(sessions);
+
+    debug!(target: Target::file_mail(), channel = manager.id; "WHEP preview session <yellow>{}</> ended", session.id);
+
+    if let Err(e) = reqwest::Client::new()
+        .delete(session.location)
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+    {
+        warn!(target: Target::file_mail(), channel = manager.id; "Unable to end WHEP session on media server: {e}");
+    }
+
+    Ok(())
+}
@@ -1,24 +1,394 @@
-/// Import text/m3u file and create a playlist out of it
-use std::{
-    io::{Error, ErrorKind},
-    path::Path,
-};
+/// Import rundowns from M3U/text, XSPF, CSV or BXF files and create a playlist out of it.
+///
+/// Sources are resolved against the channel storage, entries which can not be found
+/// or read are skipped and listed in the report.
+use std::{io::Error, path::Path};
+
+use chrono::NaiveDate;
+use percent_encoding::percent_decode_str;
+use serde::{Deserialize, Serialize};
+use tokio::fs::{create_dir_all, read_to_string};
 
-use tokio::{
-    fs::{create_dir_all, File},
-    io::{AsyncBufReadExt, BufReader},
+use crate::db::DbPool;
+use crate::file::StorageBackend;
+use crate::player::utils::{
+    get_date, is_remote, json_reader, json_serializer::JsonPlaylist, json_writer, time_to_sec,
+    Media,
 };
+use crate::utils::{
+    config::PlayoutConfig,
+    playlist::{store_playlist, stored_playlist},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// M3U or plain text, one source per line
+    M3u,
+    Xspf,
+    Csv,
+    /// BXF schedule from traffic systems, or a simple XML with event elements
+    Bxf,
+}
+
+impl ImportFormat {
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase)
+            .as_deref()
+        {
+            Some("xspf") => Self::Xspf,
+            Some("csv") => Self::Csv,
+            Some("bxf" | "xml") => Self::Bxf,
+            _ => Self::M3u,
+        }
+    }
+}
+
+/// Entry from the rundown, before it is resolved against the storage.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImportEntry {
+    pub source: String,
+    pub title: Option<String>,
+    pub seek: Option<f64>,
+    pub out: Option<f64>,
+    pub duration: Option<f64>,
+    /// Planned start, only for sorting the entries.
+    pub start: Option<f64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct UnresolvedEntry {
+    pub position: usize,
+    pub source: String,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ImportReport {
+    pub date: String,
+    pub message: String,
+    pub imported: usize,
+    pub unresolved: Vec<UnresolvedEntry>,
+}
+
+/// Time values can be seconds or `hh:mm:ss[.ms]`.
+fn parse_time(value: &str) -> Option<f64> {
+    let value = value.trim();
+
+    if value.is_empty() {
+        None
+    } else if value.contains(':') {
+        Some(time_to_sec(value, &None))
+    } else {
+        value.parse().ok()
+    }
+}
+
+fn clean_location(location: &str) -> String {
+    let location = location.trim();
+
+    match location.strip_prefix("file://") {
+        Some(path) => percent_decode_str(path).decode_utf8_lossy().to_string(),
+        None => location.to_string(),
+    }
+}
+
+/// M3U with optional `#EXTINF:<duration>,<title>` lines, or plain text lists.
+pub fn parse_m3u(content: &str) -> Vec<ImportEntry> {
+    let mut entries = vec![];
+    let mut info: Option<(Option<f64>, Option<String>)> = None;
+
+    for line in content.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(extinf) = line.strip_prefix("#EXTINF:") {
+            let (duration, title) = extinf.split_once(',').unwrap_or((extinf, ""));
+            let title = title.trim();
+
+            info = Some((
+                parse_time(duration).filter(|d| *d > 0.0),
+                (!title.is_empty()).then(|| title.to_string()),
+            ));
+        } else if !line.starts_with('#') {
+            let (duration, title) = info.take().unwrap_or_default();
+
+            entries.push(ImportEntry {
+                source: clean_location(line),
+                title,
+                duration,
+                ..Default::default()
+            });
+        }
+    }
+
+    entries
+}
+
+/// XSPF playlists, durations are in milliseconds.
+pub fn parse_xspf(content: &str) -> Result<Vec<ImportEntry>, String> {
+    let doc = roxmltree::Document::parse(content).map_err(|e| e.to_string())?;
+    let child_text = |node: roxmltree::Node, name: &str| {
+        node.children()
+            .find(|c| c.has_tag_name(name))
+            .and_then(|c| c.text())
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(str::to_string)
+    };
+
+    Ok(doc
+        .descendants()
+        .filter(|n| n.has_tag_name("track"))
+        .map(|track| ImportEntry {
+            source: child_text(track, "location")
+                .map(|l| clean_location(&l))
+                .unwrap_or_default(),
+            title: child_text(track, "title"),
+            duration: child_text(track, "duration")
+                .and_then(|d| d.parse::<f64>().ok())
+                .map(|d| d / 1000.0),
+            ..Default::default()
+        })
+        .collect())
+}
+
+/// CSV with header, the source column is required: `source`, `file`, `path` or `location`.
+/// Optional columns are `title`, `in`, `out` and `duration`, in seconds or `hh:mm:ss`.
+pub fn parse_csv(content: &str) -> Result<Vec<ImportEntry>, String> {
+    let delimiter = match content.lines().next() {
+        Some(header) if header.matches(';').count() > header.matches(',').count() => b';',
+        _ => b',',
+    };
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+    let header: Vec<String> = reader
+        .headers()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(str::to_lowercase)
+        .collect();
+    let column = |names: &[&str]| header.iter().position(|h| names.contains(&h.as_str()));
+    let source = column(&["source", "file", "path", "location"])
+        .ok_or("CSV needs a source column".to_string())?;
+    let title = column(&["title", "name"]);
+    let seek = column(&["in", "seek"]);
+    let out = column(&["out"]);
+    let duration = column(&["duration", "length"]);
+    let mut entries = vec![];
+
+    for record in reader.records() {
+        let record = record.map_err(|e| e.to_string())?;
+        let field = |index: Option<usize>| {
+            index
+                .and_then(|i| record.get(i))
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+
+        entries.push(ImportEntry {
+            source: clean_location(&field(Some(source)).unwrap_or_default()),
+            title: field(title),
+            seek: field(seek).and_then(|v| parse_time(&v)),
+            out: field(out).and_then(|v| parse_time(&v)),
+            duration: field(duration).and_then(|v| parse_time(&v)),
+            ..Default::default()
+        });
+    }
+
+    Ok(entries)
+}
+
+/// SMPTE timecode `hh:mm:ss:ff` (or `;ff` for drop frame), other values like [`parse_time`].
+fn parse_timecode(value: &str, fps: f64) -> Option<f64> {
+    let parts: Vec<&str> = value.trim().split([':', ';']).collect();
+
+    if parts.len() == 4 {
+        let mut t = parts.iter().filter_map(|p| p.parse::<f64>().ok());
+
+        return Some(t.next()? * 3600.0 + t.next()? * 60.0 + t.next()? + t.next()? / fps.max(1.0));
+    }
+
+    parse_time(value)
+}
+
+fn local_name_is(node: &roxmltree::Node, names: &[&str]) -> bool {
+    node.is_element()
+        && names
+            .iter()
+            .any(|n| node.tag_name().name().eq_ignore_ascii_case(n))
+}
+
+/// Text of the first descendant with one of the names, a `SmpteTimeCode` inside is preferred.
+fn descendant_text(node: roxmltree::Node, names: &[&str]) -> Option<String> {
+    let found = node.descendants().find(|n| local_name_is(n, names))?;
+    let text = found
+        .descendants()
+        .find(|n| local_name_is(n, &["SmpteTimeCode"]))
+        .unwrap_or(found)
+        .text()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .or_else(|| found.attribute("value"))?;
+
+    Some(text.to_string())
+}
+
+/// BXF schedules, or a simplified XML mapping with `event` elements.
+///
+/// Only primary events are imported, secondary events (logos, graphics) are skipped.
+/// Returns the broadcast date, when the schedule has one.
+pub fn parse_bxf(content: &str, fps: f64) -> Result<(Option<String>, Vec<ImportEntry>), String> {
+    let doc = roxmltree::Document::parse(content).map_err(|e| e.to_string())?;
+    let mut date = None;
+    let mut entries = vec![];
+
+    for event in doc
+        .descendants()
+        .filter(|n| local_name_is(n, &["ScheduledEvent", "Event"]))
+    {
+        let primary = event
+            .descendants()
+            .find(|n| local_name_is(n, &["EventData"]))
+            .and_then(|n| n.attribute("eventType"))
+            .is_none_or(|t| t.eq_ignore_ascii_case("Primary"));
+
+        if !primary {
+            continue;
+        }
+
+        if date.is_none() {
+            date = event
+                .descendants()
+                .find_map(|n| n.attribute("broadcastDate").or(n.attribute("date")))
+                .map(str::to_string);
+        }
 
-use crate::player::utils::{json_reader, json_serializer::JsonPlaylist, json_writer, Media};
+        let seek = descendant_text(event, &["SOM", "In"]).and_then(|v| parse_timecode(&v, fps));
+        let duration =
+            descendant_text(event, &["Duration", "Length"]).and_then(|v| parse_timecode(&v, fps));
 
+        entries.push(ImportEntry {
+            source: descendant_text(event, &["PathName", "FileName", "Source", "HouseNumber"])
+                .map(|s| clean_location(&s))
+                .unwrap_or_default(),
+            title: descendant_text(event, &["Name", "Title"]),
+            seek,
+            out: duration.map(|d| seek.unwrap_or_default() + d),
+            duration: None,
+            start: descendant_text(event, &["StartDateTime", "Start"])
+                .and_then(|v| parse_timecode(&v, fps)),
+        });
+    }
+
+    entries.sort_by(|a, b| {
+        a.start
+            .unwrap_or_default()
+            .total_cmp(&b.start.unwrap_or_default())
+    });
+
+    Ok((date, entries))
+}
+
+/// Find the source in the storage: as it is, or relative to the storage root.
+async fn resolve_source(storage: &StorageBackend, source: &str) -> Option<String> {
+    if is_remote(source) {
+        return Some(source.to_string());
+    }
+
+    if storage.is_file(source).await {
+        return Some(source.to_string());
+    }
+
+    match storage.fetch_file_path(source).await {
+        Ok(path) if storage.is_file(&path).await => Some(path),
+        _ => None,
+    }
+}
+
+async fn entry_media(storage: &StorageBackend, entry: &ImportEntry) -> Result<Media, String> {
+    if entry.source.is_empty() {
+        return Err("No source".to_string());
+    }
+
+    let source = resolve_source(storage, &entry.source)
+        .await
+        .ok_or("Source not found in storage".to_string())?;
+    let mut media = Media::new(0, &source, true).await;
+
+    if media.duration <= 0.0 {
+        media.duration = entry.duration.ok_or("Source is not readable".to_string())?;
+    }
+
+    media.seek = entry.seek.unwrap_or_default();
+    media.out = entry.out.unwrap_or(media.duration).min(media.duration);
+
+    if entry.title.is_some() {
+        media.title.clone_from(&entry.title);
+    }
+
+    if media.out <= media.seek {
+        return Err(format!(
+            "Out point {} is not after in point {}",
+            media.out, media.seek
+        ));
+    }
+
+    Ok(media)
+}
+
+/// Read the rundown file and add its entries to the playlist from the given date.
+///
+/// A BXF schedule replaces the playlist and, without a date, the broadcast date from the file is used.
+/// Otherwise the date defaults to today.
 pub async fn import_file(
-    playlist_root: &Path,
+    conn: &DbPool,
+    config: &PlayoutConfig,
+    storage: &StorageBackend,
     date: &str,
     channel_name: Option<String>,
     path: &Path,
-) -> Result<String, Error> {
-    let file = File::open(path).await?;
-    let reader = BufReader::new(file);
+    user_id: i32,
+) -> Result<ImportReport, Error> {
+    let playlist_root = &config.channel.playlists;
+    let content = read_to_string(path).await?;
+    let format = ImportFormat::from_path(path);
+    let mut date = date.to_string();
+    let entries = match format {
+        ImportFormat::M3u => parse_m3u(&content),
+        ImportFormat::Xspf => parse_xspf(&content).map_err(Error::other)?,
+        ImportFormat::Csv => parse_csv(&content).map_err(Error::other)?,
+        ImportFormat::Bxf => {
+            let (bxf_date, entries) =
+                parse_bxf(&content, config.processing.fps).map_err(Error::other)?;
+
+            if date.is_empty() {
+                date = bxf_date.unwrap_or_default();
+            }
+
+            entries
+        }
+    };
+
+    if date.is_empty() {
+        date = get_date(
+            false,
+            config.playlist.start_sec.unwrap_or_default(),
+            false,
+            &config.channel.timezone,
+        );
+    }
+
+    if NaiveDate::parse_from_str(&date, "%Y-%m-%d").is_err() {
+        return Err(Error::other(format!("Invalid playlist date: '{date}'")));
+    }
+
+    let mut report = ImportReport {
+        date: date.to_string(),
+        ..Default::default()
+    };
     let mut playlist = JsonPlaylist {
         channel: channel_name.unwrap_or_else(|| "Channel 1".to_string()),
         date: date.to_string(),
@@ -29,14 +399,11 @@ pub async fn import_file(
         program: vec![],
     };
 
-    if !playlist_root.is_dir() {
-        return Err(Error::new(
-            ErrorKind::Other,
-            format!(
-                "Playlist folder <b><magenta>{:?}</></b> not exists!",
-                playlist_root,
-            ),
-        ));
+    if !config.playlist.database && !playlist_root.is_dir() {
+        return Err(Error::other(format!(
+            "Playlist folder <b><magenta>{:?}</></b> not exists!",
+            playlist_root,
+        )));
     }
 
     let d: Vec<&str> = date.split('-').collect();
@@ -45,37 +412,151 @@ pub async fn import_file(
     let playlist_path = playlist_root.join(year).join(month);
     let playlist_file = &playlist_path.join(format!("{date}.json"));
 
-    create_dir_all(playlist_path).await?;
-
-    let mut lines = reader.lines();
-    while let Some(line) = lines.next_line().await? {
-        if !line.starts_with('#') {
-            let item = Media::new(0, &line, true).await;
+    if !config.playlist.database {
+        create_dir_all(playlist_path).await?;
+    }
 
-            if item.duration > 0.0 {
-                playlist.program.push(item);
-            }
+    for (index, entry) in entries.iter().enumerate() {
+        match entry_media(storage, entry).await {
+            Ok(item) => playlist.program.push(item),
+            Err(reason) => report.unresolved.push(UnresolvedEntry {
+                position: index + 1,
+                source: entry.source.clone(),
+                reason,
+            }),
         }
     }
 
-    let mut file_exists = false;
+    report.imported = playlist.program.len();
 
-    if playlist_file.is_file() {
-        file_exists = true;
-        let mut existing_data = json_reader(playlist_file).await?;
+    let existing = if format == ImportFormat::Bxf {
+        None
+    } else if config.playlist.database {
+        stored_playlist(conn, config.general.channel_id, &date)
+            .await
+            .ok()
+    } else if playlist_file.is_file() {
+        Some(json_reader(playlist_file).await?)
+    } else {
+        None
+    };
+    let file_exists = existing.is_some();
+
+    if let Some(mut existing_data) = existing {
         existing_data.program.append(&mut playlist.program);
 
         playlist.program = existing_data.program;
     };
 
-    let msg = if file_exists {
+    report.message = if file_exists {
         format!("Update playlist from {date} success!")
     } else {
         format!("Write playlist from {date} success!")
     };
 
+    if config.playlist.database {
+        return match store_playlist(conn, config.general.channel_id, user_id, &playlist).await {
+            Ok(_) => Ok(report),
+            Err(e) => Err(Error::other(e.to_string())),
+        };
+    }
+
     match json_writer(playlist_file, playlist).await {
-        Ok(_) => Ok(msg),
-        Err(e) => Err(Error::new(ErrorKind::Other, e)),
+        Ok(_) => Ok(report),
+        Err(e) => Err(Error::other(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rundowns() {
+        let m3u = parse_m3u(
+            "#EXTM3U\n#EXTINF:120.5,Opener\n/media/opener.mp4\n\nclips/news.mp4\nfile:///media/with%20space.mp4\n",
+        );
+
+        assert_eq!(m3u.len(), 3);
+        assert_eq!(m3u[0].title.as_deref(), Some("Opener"));
+        assert_eq!(m3u[0].duration, Some(120.5));
+        assert_eq!(m3u[1].title, None);
+        assert_eq!(m3u[2].source, "/media/with space.mp4");
+
+        let xspf = parse_xspf(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <playlist version="1" xmlns="http://xspf.org/ns/0/">
+                <trackList>
+                    <track><location>file:///media/a.mp4</location><title>A</title><duration>90000</duration></track>
+                    <track><location>clips/b.mp4</location></track>
+                </trackList>
+            </playlist>"#,
+        )
+        .unwrap();
+
+        assert_eq!(xspf.len(), 2);
+        assert_eq!(xspf[0].source, "/media/a.mp4");
+        assert_eq!(xspf[0].duration, Some(90.0));
+        assert_eq!(xspf[1].title, None);
+
+        let csv = parse_csv(
+            "Title;File;In;Out\nNews;clips/news.mp4;00:00:05;00:10:00\nPromo;promo.mp4;;\n",
+        )
+        .unwrap();
+
+        assert_eq!(csv.len(), 2);
+        assert_eq!(csv[0].source, "clips/news.mp4");
+        assert_eq!(csv[0].seek, Some(5.0));
+        assert_eq!(csv[0].out, Some(600.0));
+        assert_eq!(csv[1].out, None);
+        assert!(parse_csv("title,duration\nA,10\n").is_err());
+    }
+
+    #[test]
+    fn parse_bxf_schedule() {
+        let (date, entries) = parse_bxf(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <BxfMessage xmlns="http://smpte-ra.org/schemas/2021/2019/BXF">
+                <BxfData><Schedule>
+                    <ScheduledEvent>
+                        <EventData eventType="Primary">
+                            <StartDateTime><SmpteDateTime broadcastDate="2024-03-01"><SmpteTimeCode>06:30:00:00</SmpteTimeCode></SmpteDateTime></StartDateTime>
+                            <LengthOption><Duration><SmpteDuration><SmpteTimeCode>00:10:00:12</SmpteTimeCode></SmpteDuration></Duration></LengthOption>
+                        </EventData>
+                        <Content><Name>Magazine</Name><Media><SOM><SmpteTimeCode>00:00:02:00</SmpteTimeCode></SOM><PathName>shows/magazine.mp4</PathName></Media></Content>
+                    </ScheduledEvent>
+                    <ScheduledEvent>
+                        <EventData eventType="NonPrimary"><StartDateTime><SmpteDateTime broadcastDate="2024-03-01"><SmpteTimeCode>06:00:05:00</SmpteTimeCode></SmpteDateTime></StartDateTime></EventData>
+                        <Content><Name>Logo</Name></Content>
+                    </ScheduledEvent>
+                    <ScheduledEvent>
+                        <EventData eventType="Primary">
+                            <StartDateTime><SmpteDateTime broadcastDate="2024-03-01"><SmpteTimeCode>06:00:00;00</SmpteTimeCode></SmpteDateTime></StartDateTime>
+                            <LengthOption><Duration><SmpteDuration><SmpteTimeCode>00:30:00;00</SmpteTimeCode></SmpteDuration></Duration></LengthOption>
+                        </EventData>
+                        <Content><ContentId><HouseNumber>NEWS0600</HouseNumber></ContentId><Name>News</Name></Content>
+                    </ScheduledEvent>
+                </Schedule></BxfData>
+            </BxfMessage>"#,
+            25.0,
+        )
+        .unwrap();
+
+        assert_eq!(date.as_deref(), Some("2024-03-01"));
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].source, "NEWS0600");
+        assert_eq!(entries[0].out, Some(1800.0));
+        assert_eq!(entries[1].title.as_deref(), Some("Magazine"));
+        assert_eq!(entries[1].seek, Some(2.0));
+        assert_eq!(entries[1].out, Some(602.48));
+
+        let (_, simple) = parse_bxf(
+            r#"<schedule><event><source>clips/a.mp4</source><duration>00:01:00</duration></event></schedule>"#,
+            25.0,
+        )
+        .unwrap();
+
+        assert_eq!(simple[0].source, "clips/a.mp4");
+        assert_eq!(simple[0].out, Some(60.0));
     }
 }
@@ -0,0 +1,252 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::player::utils::{JsonPlaylist, Media};
+
+const PROGRAM_KEYS: [&str; 5] = ["program", "playlist", "items", "clips", "list"];
+const DATE_KEYS: [&str; 2] = ["date", "day"];
+const CHANNEL_KEYS: [&str; 2] = ["channel", "channel_name"];
+const SOURCE_KEYS: [&str; 5] = ["source", "src", "file", "path", "filename"];
+const SEEK_KEYS: [&str; 4] = ["in", "seek", "inpoint", "start_offset"];
+const OUT_KEYS: [&str; 2] = ["out", "outpoint"];
+const DURATION_KEYS: [&str; 3] = ["duration", "length", "dur"];
+const TITLE_KEYS: [&str; 2] = ["title", "name"];
+const FILTER_KEYS: [&str; 2] = ["custom_filter", "filter"];
+const DATE_FORMATS: [&str; 4] = ["%Y-%m-%d", "%d.%m.%Y", "%Y/%m/%d", "%Y%m%d"];
+
+/// Result of reading a playlist, which may be in an older schema.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct LegacyReport {
+    pub file: Option<String>,
+    pub legacy: bool,
+    pub converted: usize,
+    pub notes: Vec<String>,
+    pub unconvertible: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// Find the first existing key, note it when it is not the current field name.
+fn pick<'a>(
+    obj: &'a Map<String, Value>,
+    keys: &[&str],
+    notes: &mut Vec<String>,
+    context: &str,
+) -> Option<&'a Value> {
+    for key in keys {
+        if let Some(value) = obj.get(*key).filter(|v| !v.is_null()) {
+            if key != &keys[0] {
+                let note = format!("{context}: field '{key}' renamed to '{}'", keys[0]);
+
+                if !notes.contains(&note) {
+                    notes.push(note);
+                }
+            }
+
+            return Some(value);
+        }
+    }
+
+    None
+}
+
+/// Read time values as seconds. Supported are numbers, numeric strings
+/// and timestamps in the form of `HH:MM:SS.ms` or `MM:SS`.
+pub fn legacy_time(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => {
+            let s = s.trim();
+
+            if let Ok(sec) = s.parse::<f64>() {
+                return Some(sec);
+            }
+
+            let parts = s
+                .split(':')
+                .map(|p| p.parse::<f64>().ok())
+                .collect::<Option<Vec<f64>>>()?;
+
+            match parts[..] {
+                [h, m, s] => Some(h * 3600.0 + m * 60.0 + s),
+                [m, s] => Some(m * 60.0 + s),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn legacy_date(value: &str) -> Option<String> {
+    DATE_FORMATS
+        .iter()
+        .find_map(|f| NaiveDate::parse_from_str(value.trim(), f).ok())
+        .map(|d| d.format("%Y-%m-%d").to_string())
+}
+
+fn legacy_string(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Number(n)) => n.to_string(),
+        _ => String::new(),
+    }
+}
+
+fn legacy_media(index: usize, value: &Value, report: &mut LegacyReport) -> Result<Media, String> {
+    let context = "program";
+    let notes = &mut report.notes;
+    let obj = value
+        .as_object()
+        .ok_or_else(|| format!("Item {index}: is not an object"))?;
+    let source = legacy_string(pick(obj, &SOURCE_KEYS, notes, context));
+
+    if source.is_empty() {
+        return Err(format!("Item {index}: no source"));
+    }
+
+    let mut time = |keys: &[&str]| -> Result<Option<f64>, String> {
+        match pick(obj, keys, notes, context) {
+            Some(v) => legacy_time(v)
+                .map(Some)
+                .ok_or_else(|| format!("Item {index}: invalid time value '{v}' in '{}'", keys[0])),
+            None => Ok(None),
+        }
+    };
+
+    let seek = time(&SEEK_KEYS)?.unwrap_or_default();
+    let (out, duration) = match (time(&OUT_KEYS)?, time(&DURATION_KEYS)?) {
+        (Some(out), Some(duration)) => (out, duration),
+        (Some(out), None) => (out, out),
+        (None, Some(duration)) => (duration, duration),
+        (None, None) => return Err(format!("Item {index}: neither out point nor duration")),
+    };
+
+    if out <= seek {
+        return Err(format!("Item {index}: out point is not after in point"));
+    }
+
+    let title = legacy_string(pick(obj, &TITLE_KEYS, notes, context));
+
+    Ok(Media {
+        title: (!title.is_empty()).then_some(title),
+        seek,
+        out,
+        duration,
+        category: legacy_string(obj.get("category")),
+        source,
+        audio: legacy_string(obj.get("audio")),
+        custom_filter: legacy_string(pick(obj, &FILTER_KEYS, notes, context)),
+        ..Media::default()
+    })
+}
+
+/// Parse playlist content. When it does not fit the current schema,
+/// try to convert it from one of the older ffplayout playlist formats.
+///
+/// The `fallback_date` is used when the old playlist has no readable date, normally it comes from the file name.
+pub fn normalize_playlist(
+    contents: &str,
+    fallback_date: Option<&str>,
+) -> Result<(JsonPlaylist, LegacyReport), String> {
+    let mut report = LegacyReport::default();
+
+    let current_error = match serde_json::from_str::<JsonPlaylist>(contents) {
+        Ok(playlist) => {
+            report.converted = playlist.program.len();
+            return Ok((playlist, report));
+        }
+        Err(e) => e.to_string(),
+    };
+
+    let value: Value = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+    let root = match &value {
+        Value::Object(obj) => obj.clone(),
+        Value::Array(_) => {
+            report
+                .notes
+                .push("Plain program list without header".to_string());
+            Map::from_iter([("program".to_string(), value.clone())])
+        }
+        _ => return Err(current_error),
+    };
+
+    let program = pick(&root, &PROGRAM_KEYS, &mut report.notes, "root")
+        .and_then(|p| p.as_array())
+        .ok_or(current_error)?;
+
+    let date = match pick(&root, &DATE_KEYS, &mut report.notes, "root") {
+        Some(Value::String(d)) => legacy_date(d),
+        _ => None,
+    }
+    .or_else(|| fallback_date.and_then(legacy_date))
+    .ok_or("No valid playlist date found")?;
+
+    let channel = match pick(&root, &CHANNEL_KEYS, &mut report.notes, "root") {
+        Some(Value::String(c)) => c.clone(),
+        _ => "Channel 1".to_string(),
+    };
+
+    let mut playlist = JsonPlaylist {
+        channel,
+        date,
+        start_sec: None,
+        length: None,
+        path: None,
+        modified: None,
+        program: vec![],
+    };
+
+    for (i, item) in program.iter().enumerate() {
+        match legacy_media(i, item, &mut report) {
+            Ok(media) => playlist.program.push(media),
+            Err(e) => report.unconvertible.push(e),
+        }
+    }
+
+    report.legacy = true;
+    report.converted = playlist.program.len();
+
+    Ok((playlist, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_legacy_playlist() {
+        let contents = r#"{
+            "channel_name": "Old Channel",
+            "day": "24.12.2019",
+            "playlist": [
+                {"file": "/media/intro.mp4", "in": "00:00:05", "out": "00:01:00.5"},
+                {"src": "/media/clip.mp4", "length": 120},
+                {"title": "no source", "duration": 10},
+                {"source": "/media/broken.mp4", "out": "abc"}
+            ]
+        }"#;
+
+        let (playlist, report) = normalize_playlist(contents, None).unwrap();
+
+        assert!(report.legacy);
+        assert_eq!(playlist.date, "2019-12-24");
+        assert_eq!(playlist.channel, "Old Channel");
+        assert_eq!(playlist.program.len(), 2);
+        assert_eq!(playlist.program[0].seek, 5.0);
+        assert_eq!(playlist.program[0].out, 60.5);
+        assert_eq!(playlist.program[1].out, 120.0);
+        assert_eq!(report.unconvertible.len(), 2);
+    }
+
+    #[test]
+    fn keep_current_playlist() {
+        let contents = r#"{"channel": "Channel 1", "date": "2024-01-01", "program": [
+            {"in": 0, "out": 10, "duration": 10, "source": "/media/clip.mp4"}
+        ]}"#;
+
+        let (_, report) = normalize_playlist(contents, None).unwrap();
+
+        assert!(!report.legacy);
+        assert_eq!(report.converted, 1);
+    }
+}
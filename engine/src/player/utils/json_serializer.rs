@@ -1,18 +1,35 @@
 use std::{
-    path::Path,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
     sync::{atomic::AtomicBool, Arc},
 };
 
 use log::*;
+use reqwest::header::AUTHORIZATION;
 use serde::{Deserialize, Serialize};
 use tokio::{fs::File, io::AsyncReadExt, sync::Mutex};
 
-use crate::utils::{config::DUMMY_LEN, logging::Target};
+use crate::utils::{
+    config::DUMMY_LEN,
+    errors::ServiceError,
+    logging::Target,
+    playlist::stored_playlist,
+    rules::add_bumpers,
+    webhook::{emit, WebhookEvent},
+};
+
+/// Maximum depth of nested playlist includes.
+const INCLUDE_DEPTH: usize = 5;
+
+/// Playlists in the database have this prefix and the date as path.
+pub const DB_PLAYLIST: &str = "db://";
+
 use crate::{
+    db::{handles, DbPool},
     file::StorageBackend,
     player::utils::{
-        get_date, is_remote, json_validate::validate_playlist, modified_time, time_from_header,
-        Media, PlayoutConfig,
+        get_date, is_remote, json_legacy::normalize_playlist, json_validate::validate_playlist,
+        modified_time, time_from_header, Media, PlayoutConfig,
     },
 };
 
@@ -43,6 +60,7 @@ impl JsonPlaylist {
         let media = Media {
             begin: Some(start),
             title: None,
+            description: None,
             duration: DUMMY_LEN,
             out: DUMMY_LEN,
             ..Media::default()
@@ -92,9 +110,218 @@ pub fn set_defaults(playlist: &mut JsonPlaylist) {
     playlist.length = Some(length);
 }
 
+/// Playlist file of the given date, in the channel playlist folder.
+pub fn date_playlist_path(config: &PlayoutConfig, date: &str) -> PathBuf {
+    if let Some(url) = config.playlist.feed(date) {
+        return PathBuf::from(url);
+    }
+
+    if config.playlist.database {
+        return PathBuf::from(format!("{DB_PLAYLIST}{date}"));
+    }
+
+    let mut playlist_path = config.channel.playlists.clone();
+
+    if playlist_path.is_dir() || is_remote(&config.channel.playlists.to_string_lossy()) {
+        let d: Vec<&str> = date.split('-').collect();
+        playlist_path = playlist_path
+            .join(d[0])
+            .join(d[1])
+            .join(date)
+            .with_extension("json");
+    }
+
+    playlist_path
+}
+
+/// Date of a playlist path, which points to the database.
+pub fn db_playlist_date(path: &str) -> Option<&str> {
+    path.strip_prefix(DB_PLAYLIST)
+}
+
+/// Revision of a playlist in the database, `None` when it not exists.
+pub async fn db_playlist_revision(conn: &DbPool, channel_id: i32, path: &str) -> Option<String> {
+    let date = db_playlist_date(path)?;
+
+    handles::select_playlist(conn, channel_id, date)
+        .await
+        .ok()
+        .map(|p| p.revision.to_string())
+}
+
+/// Request for a remote playlist, with the authorization of the playlist feed.
+pub fn remote_request(config: &PlayoutConfig, url: &str) -> reqwest::RequestBuilder {
+    let request = reqwest::Client::new().get(url);
+
+    if config.playlist.feed_url.trim().is_empty() || config.playlist.feed_auth.is_empty() {
+        return request;
+    }
+
+    request.header(AUTHORIZATION, &config.playlist.feed_auth)
+}
+
+/// Feeds send no reliable modification time, the playlist version is the hash of the body.
+fn body_hash(body: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+
+    format!("{:016x}", hasher.finish())
+}
+
+/// Version of the playlist in the feed, `None` when the feed is not reachable.
+pub async fn feed_modified(config: &PlayoutConfig, url: &str) -> Option<String> {
+    let resp = remote_request(config, url).send().await.ok()?;
+
+    if !resp.status().is_success() {
+        warn!(target: Target::file_mail(), channel = config.general.channel_id; "Playlist feed answered with status {}", resp.status());
+        return None;
+    }
+
+    resp.text().await.ok().map(|body| body_hash(&body))
+}
+
+/// Included playlist, relative paths are in the channel playlist folder.
+fn include_path(config: &PlayoutConfig, source: &str) -> String {
+    if is_remote(source) || Path::new(source).is_absolute() {
+        return source.to_string();
+    }
+
+    config
+        .channel
+        .playlists
+        .join(source)
+        .to_string_lossy()
+        .to_string()
+}
+
+async fn read_include(config: &PlayoutConfig, path: &str) -> Result<Vec<Media>, String> {
+    let contents = if is_remote(path) {
+        remote_request(config, path)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| e.to_string())?
+            .text()
+            .await
+            .map_err(|e| e.to_string())?
+    } else {
+        tokio::fs::read_to_string(path)
+            .await
+            .map_err(|e| e.to_string())?
+    };
+
+    // blocks need no date, they can be a plain list of clips too
+    if let Ok(program) = serde_json::from_str::<Vec<Media>>(&contents) {
+        return Ok(program);
+    }
+
+    normalize_playlist(&contents, Some("1970-01-01")).map(|(p, _)| p.program)
+}
+
+/// Replace the include items with the clips of their playlist blocks.
+/// Blocks can include other blocks, loops and not readable blocks are left out.
+pub async fn expand_includes(config: &PlayoutConfig, program: Vec<Media>) -> Vec<Media> {
+    let id = config.general.channel_id;
+    let mut list = vec![];
+    let mut pending: Vec<(Media, Vec<String>)> =
+        program.into_iter().rev().map(|m| (m, vec![])).collect();
+
+    while let Some((item, parents)) = pending.pop() {
+        if !item.include {
+            list.push(item);
+            continue;
+        }
+
+        let path = include_path(config, &item.source);
+
+        if parents.contains(&path) || parents.len() >= INCLUDE_DEPTH {
+            error!(target: Target::file_mail(), channel = id; "Include <b><magenta>{path}</></b> is nested too deep, or includes itself");
+            continue;
+        }
+
+        match read_include(config, &path).await {
+            Ok(block) => {
+                let mut chain = parents;
+                chain.push(path);
+
+                for media in block.into_iter().rev() {
+                    pending.push((media, chain.clone()));
+                }
+            }
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = id; "Include <b><magenta>{path}</></b> not readable: {e}");
+            }
+        }
+    }
+
+    list
+}
+
+/// Playlist from the database or the playlist file, with its version.
+async fn local_playlist(
+    conn: &DbPool,
+    id: i32,
+    path: &str,
+    date: &str,
+    start_sec: f64,
+) -> Option<(JsonPlaylist, Option<String>)> {
+    if let Some(db_date) = db_playlist_date(path) {
+        return match stored_playlist(conn, id, db_date).await {
+            Ok(playlist) => {
+                let modified = playlist.modified.clone();
+
+                Some((playlist, modified))
+            }
+            Err(ServiceError::NoContent(_)) => None,
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = id; "Playlist not readable from database! {e}");
+                None
+            }
+        };
+    }
+
+    if !Path::new(path).is_file() {
+        return None;
+    }
+
+    let modified = modified_time(path).await;
+
+    let mut f = File::options()
+        .read(true)
+        .write(false)
+        .open(path)
+        .await
+        .expect("Open json playlist file.");
+    let mut contents = String::new();
+    f.read_to_string(&mut contents)
+        .await
+        .expect("Read playlist content.");
+    let playlist = match normalize_playlist(&contents, Some(date)) {
+        Ok((p, report)) => {
+            if report.legacy {
+                warn!(target: Target::file_mail(), channel = id; "Playlist <b><magenta>{path}</></b> is in a legacy format, converted {} item(s), {} unconvertible", report.converted, report.unconvertible.len());
+
+                for item in &report.unconvertible {
+                    warn!(target: Target::file_mail(), channel = id; "Skip: {item}");
+                }
+            }
+
+            p
+        }
+        Err(e) => {
+            error!(target: Target::file_mail(), channel = id; "Playlist file not readable! {e}");
+            JsonPlaylist::new(date.to_string(), start_sec)
+        }
+    };
+
+    Some((playlist, modified))
+}
+
 /// Read json playlist file, fills JsonPlaylist struct and set some extra values,
 /// which we need to process.
+#[allow(clippy::too_many_arguments)]
 pub async fn read_json(
+    conn: &DbPool,
     storage: &Arc<Mutex<StorageBackend>>,
     config: &mut PlayoutConfig,
     current_list: Arc<Mutex<Vec<Media>>>,
@@ -106,18 +333,9 @@ pub async fn read_json(
     let storage = storage.lock().await;
     let id = config.general.channel_id;
     let config_clone = config.clone();
-    let mut playlist_path = config.channel.playlists.clone();
     let start_sec = config.playlist.start_sec.unwrap();
     let date = get_date(seek, start_sec, get_next, &config.channel.timezone);
-
-    if playlist_path.is_dir() || is_remote(&config.channel.playlists.to_string_lossy()) {
-        let d: Vec<&str> = date.split('-').collect();
-        playlist_path = playlist_path
-            .join(d[0])
-            .join(d[1])
-            .join(date.clone())
-            .with_extension("json");
-    }
+    let mut playlist_path = date_playlist_path(config, &date);
 
     let mut current_file = playlist_path.as_path().display().to_string();
 
@@ -127,23 +345,33 @@ pub async fn read_json(
     }
 
     if is_remote(&current_file) {
-        if let Ok(resp) = reqwest::Client::new().get(&current_file).send().await {
+        if let Ok(resp) = remote_request(config, &current_file).send().await {
             if resp.status().is_success() {
                 let headers = resp.headers().clone();
 
                 if let Ok(body) = resp.text().await {
-                    let mut playlist: JsonPlaylist = match serde_json::from_str(&body) {
-                        Ok(p) => p,
+                    let mut playlist = match normalize_playlist(&body, Some(&date)) {
+                        Ok((p, report)) => {
+                            if report.legacy {
+                                warn!(target: Target::file_mail(), channel = id; "Remote playlist is in a legacy format, converted {} item(s), {} unconvertible", report.converted, report.unconvertible.len());
+                            }
+
+                            p
+                        }
                         Err(e) => {
                             error!(target: Target::file_mail(), channel = id; "Could't read remote json playlist. {e:?}");
                             JsonPlaylist::new(date, start_sec)
                         }
                     };
 
+                    playlist.program = expand_includes(config, playlist.program).await;
+                    playlist.program = add_bumpers(config, &storage, playlist.program).await;
                     playlist.path = Some(current_file);
                     playlist.start_sec = Some(start_sec);
 
-                    if let Some(time) = time_from_header(&headers) {
+                    if !config.playlist.feed_url.trim().is_empty() {
+                        playlist.modified = Some(body_hash(&body));
+                    } else if let Some(time) = time_from_header(&headers) {
                         playlist.modified = Some(time.to_string());
                     }
 
@@ -164,26 +392,11 @@ pub async fn read_json(
                 }
             }
         }
-    } else if playlist_path.is_file() {
-        let modified = modified_time(&current_file).await;
-
-        let mut f = File::options()
-            .read(true)
-            .write(false)
-            .open(&current_file)
-            .await
-            .expect("Open json playlist file.");
-        let mut contents = String::new();
-        f.read_to_string(&mut contents)
-            .await
-            .expect("Read playlist content.");
-        let mut playlist: JsonPlaylist = match serde_json::from_str(&contents) {
-            Ok(p) => p,
-            Err(e) => {
-                error!(target: Target::file_mail(), channel = id; "Playlist file not readable! {e}");
-                JsonPlaylist::new(date.clone(), start_sec)
-            }
-        };
+    } else if let Some((mut playlist, modified)) =
+        local_playlist(conn, id, &current_file, &date, start_sec).await
+    {
+        playlist.program = expand_includes(config, playlist.program).await;
+        playlist.program = add_bumpers(config, &storage, playlist.program).await;
 
         // bind source to key
         // and fetched_path to source
@@ -221,5 +434,85 @@ pub async fn read_json(
 
     error!(target: Target::file_mail(), channel = id; "Playlist <b><magenta>{current_file}</></b> not exist!");
 
+    emit(
+        config,
+        WebhookEvent::PlaylistMissing,
+        serde_json::json!({"date": date, "path": current_file}),
+    );
+
     JsonPlaylist::new(date, start_sec)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn playlist_feed_path() {
+        let mut config = PlayoutConfig::default();
+        config.channel.playlists = PathBuf::from("/playlists");
+        config.playlist.feed_url = "https://cms.example.org/schedule/{date}.json".to_string();
+
+        assert_eq!(
+            date_playlist_path(&config, "2024-05-01"),
+            PathBuf::from("https://cms.example.org/schedule/2024-05-01.json")
+        );
+
+        config.playlist.feed_url = String::new();
+
+        assert_eq!(
+            date_playlist_path(&config, "2024-05-01"),
+            PathBuf::from("/playlists")
+        );
+
+        assert_eq!(body_hash("{}"), body_hash("{}"));
+        assert_ne!(body_hash("{}"), body_hash("{ }"));
+    }
+
+    #[tokio::test]
+    async fn expand_playlist_includes() {
+        let dir = std::env::temp_dir().join("ffplayout_includes");
+        std::fs::create_dir_all(dir.join("blocks")).unwrap();
+
+        let clip = |source: &str| {
+            format!(r#"{{"in": 0, "out": 10, "duration": 10, "source": "{source}"}}"#)
+        };
+        let include = |source: &str| {
+            format!(
+                r#"{{"in": 0, "out": 0, "duration": 0, "source": "{source}", "include": true}}"#
+            )
+        };
+
+        std::fs::write(
+            dir.join("blocks/morning.json"),
+            format!("[{}, {}]", clip("a.mp4"), include("blocks/ads.json")),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("blocks/ads.json"),
+            format!("[{}, {}]", clip("ad.mp4"), include("blocks/morning.json")),
+        )
+        .unwrap();
+
+        let mut config = PlayoutConfig::default();
+        config.channel.playlists = dir;
+
+        let program: Vec<Media> = serde_json::from_str(&format!(
+            "[{}, {}, {}, {}]",
+            clip("first.mp4"),
+            include("blocks/morning.json"),
+            include("blocks/missing.json"),
+            clip("last.mp4")
+        ))
+        .unwrap();
+
+        let sources: Vec<String> = expand_includes(&config, program)
+            .await
+            .into_iter()
+            .map(|m| m.source)
+            .collect();
+
+        // the loop back to the morning block and the missing block are left out
+        assert_eq!(sources, vec!["first.mp4", "a.mp4", "ad.mp4", "last.mp4"]);
+    }
+}
@@ -1,4 +1,6 @@
 use std::{
+    collections::HashMap,
+    path::Path,
     process::Stdio,
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -7,25 +9,299 @@ use std::{
     time::Instant,
 };
 
+use chrono::NaiveDate;
 use log::*;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use tokio::{
     io::{AsyncBufReadExt, BufReader},
     process::Command,
     sync::Mutex,
 };
 
+use crate::file::{utils::validation::is_image, StorageBackend};
 use crate::player::filter::FilterType::Audio;
 use crate::player::utils::{
-    is_close, is_remote, loop_image, sec_to_time, seek_and_length, JsonPlaylist, Media,
+    is_close, is_remote, loop_image, playlist_length, probe::MediaProbe, sec_to_time,
+    seek_and_length, JsonPlaylist, Media,
 };
 use crate::utils::{
     config::{OutputMode::Null, PlayoutConfig, FFMPEG_IGNORE_ERRORS, IMAGE_FORMAT},
     errors::ProcessError,
     logging::Target,
+    validity::{self, Validity},
 };
 use crate::vec_strings;
 
+/// Maximal difference in seconds, which is still seen as equal.
+const TOLERANCE: f64 = 1.2;
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// Source not exists in the storage.
+    Missing,
+    /// Source exists, but ffprobe can not read it.
+    Unreadable,
+    /// Out point is not after the in point.
+    InvalidRange,
+    /// Probed duration differs from the declared duration.
+    DurationMismatch,
+    /// Source ends before the out point, this results in a gap on air.
+    ShortSource,
+    /// Playlist ends before the next day start.
+    Gap,
+    /// Playlist runs over the next day start.
+    Overlap,
+    /// Item is not valid yet on the playlist date.
+    NotYetValid,
+    /// Item is expired on the playlist date.
+    Expired,
+    /// Item expires soon after the playlist date, this is only a warning.
+    Expiring,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PlaylistIssue {
+    pub kind: IssueKind,
+    pub index: Option<usize>,
+    /// Begin on the playlist clock, in seconds.
+    pub begin: f64,
+    pub source: Option<String>,
+    /// Length of the gap or overlap, or the difference of the durations.
+    pub seconds: Option<f64>,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PlaylistReport {
+    pub date: String,
+    pub valid: bool,
+    pub items: usize,
+    pub length: f64,
+    pub target_length: f64,
+    pub issues: Vec<PlaylistIssue>,
+    /// Hints which do not make the playlist invalid, like soon expiring items.
+    pub warnings: Vec<PlaylistIssue>,
+}
+
+impl PlaylistIssue {
+    fn item(kind: IssueKind, index: usize, begin: f64, item: &Media, message: String) -> Self {
+        Self {
+            kind,
+            index: Some(index),
+            begin,
+            source: Some(item.source.clone()),
+            seconds: None,
+            message,
+        }
+    }
+}
+
+/// Compare the playlist length with the target length, to find a gap or an overlap at the day end.
+fn timeline_issues(program: &[Media], start: f64, target_length: f64) -> Vec<PlaylistIssue> {
+    let end = start + target_length;
+    let length: f64 = program.iter().map(|item| item.out - item.seek).sum();
+    let mut begin = start;
+
+    if start + length > end + TOLERANCE {
+        for (index, item) in program.iter().enumerate() {
+            if begin + item.out - item.seek > end {
+                return vec![PlaylistIssue {
+                    seconds: Some(start + length - end),
+                    message: format!(
+                        "Program runs {} over the next day start",
+                        sec_to_time(start + length - end)
+                    ),
+                    ..PlaylistIssue::item(IssueKind::Overlap, index, begin, item, String::new())
+                }];
+            }
+
+            begin += item.out - item.seek;
+        }
+    } else if end - (start + length) > TOLERANCE {
+        return vec![PlaylistIssue {
+            kind: IssueKind::Gap,
+            index: None,
+            begin: start + length,
+            source: None,
+            seconds: Some(end - start - length),
+            message: format!(
+                "Program ends {} before the next day start",
+                sec_to_time(end - start - length)
+            ),
+        }];
+    }
+
+    vec![]
+}
+
+/// Check a playlist without playing it and collect all problems in a report:
+///
+/// - sources exists in the storage and can be probed
+/// - probed durations match the declared durations and out points
+/// - program fills the target length, without gap or overlap
+/// - items are valid on the playlist date
+pub async fn playlist_report(
+    config: &PlayoutConfig,
+    storage: &StorageBackend,
+    playlist: &JsonPlaylist,
+    library: &HashMap<String, (Option<String>, Option<String>)>,
+) -> PlaylistReport {
+    let start = config.playlist.start_sec.unwrap_or_default();
+    let target_length = playlist_length(config, &playlist.date);
+    let day = NaiveDate::parse_from_str(&playlist.date, "%Y-%m-%d").ok();
+    let mut issues = vec![];
+    let mut warnings = vec![];
+    let mut begin = start;
+
+    for (index, item) in playlist.program.iter().enumerate() {
+        if let Some(day) = day {
+            let mut dated = item.clone();
+            dated.source = storage.interpreted_file_path(&item.source);
+            validity::apply_library(&mut dated, library);
+
+            match validity::check(&dated, day) {
+                Validity::NotYet(from) => issues.push(PlaylistIssue::item(
+                    IssueKind::NotYetValid,
+                    index,
+                    begin,
+                    item,
+                    format!("Item is valid from {from}"),
+                )),
+                Validity::Expired(to) => issues.push(PlaylistIssue::item(
+                    IssueKind::Expired,
+                    index,
+                    begin,
+                    item,
+                    format!("Item expired on {to}"),
+                )),
+                Validity::Expiring(to) => warnings.push(PlaylistIssue::item(
+                    IssueKind::Expiring,
+                    index,
+                    begin,
+                    item,
+                    format!("Item expires on {to}"),
+                )),
+                Validity::Valid => (),
+            }
+        }
+
+        if item.out <= item.seek {
+            issues.push(PlaylistIssue::item(
+                IssueKind::InvalidRange,
+                index,
+                begin,
+                item,
+                format!("Out point {} is not after in point {}", item.out, item.seek),
+            ));
+        }
+
+        if item.include || item.random {
+            // blocks are expanded on load, random clips are picked on play
+            if item.random && !storage.is_dir(&item.source).await {
+                issues.push(PlaylistIssue::item(
+                    IssueKind::Missing,
+                    index,
+                    begin,
+                    item,
+                    "Folder not exists".to_string(),
+                ));
+            }
+
+            begin += item.out - item.seek;
+            continue;
+        }
+
+        let input = if is_remote(&item.source) {
+            Some(item.source.clone())
+        } else if storage.is_file(&item.source).await {
+            Some(
+                storage
+                    .fetch_file_path(&item.source)
+                    .await
+                    .unwrap_or_else(|_| item.source.clone()),
+            )
+        } else {
+            None
+        };
+
+        match input {
+            Some(input) => match MediaProbe::new(&input).await {
+                Ok(probe) if !is_image(Path::new(&item.source)) => {
+                    let duration = probe.format_duration();
+
+                    if !is_close(item.duration, duration, TOLERANCE) {
+                        let mut issue = PlaylistIssue::item(
+                            IssueKind::DurationMismatch,
+                            index,
+                            begin,
+                            item,
+                            format!(
+                                "File duration {} differs from playlist value {}",
+                                sec_to_time(duration),
+                                sec_to_time(item.duration)
+                            ),
+                        );
+                        issue.seconds = Some(item.duration - duration);
+                        issues.push(issue);
+                    }
+
+                    if item.out > duration + TOLERANCE {
+                        let mut issue = PlaylistIssue::item(
+                            IssueKind::ShortSource,
+                            index,
+                            begin,
+                            item,
+                            format!(
+                                "File ends {} before the out point",
+                                sec_to_time(item.out - duration)
+                            ),
+                        );
+                        issue.seconds = Some(item.out - duration);
+                        issues.push(issue);
+                    }
+                }
+                Ok(_) => (),
+                Err(e) => issues.push(PlaylistIssue::item(
+                    IssueKind::Unreadable,
+                    index,
+                    begin,
+                    item,
+                    format!("File is not readable: {e}"),
+                )),
+            },
+            None => issues.push(PlaylistIssue::item(
+                IssueKind::Missing,
+                index,
+                begin,
+                item,
+                "File not exists".to_string(),
+            )),
+        }
+
+        begin += item.out - item.seek;
+    }
+
+    if !config.playlist.infinit {
+        issues.append(&mut timeline_issues(
+            &playlist.program,
+            start,
+            target_length,
+        ));
+    }
+
+    PlaylistReport {
+        date: playlist.date.clone(),
+        valid: issues.is_empty(),
+        items: playlist.program.len(),
+        length: begin - start,
+        target_length,
+        issues,
+        warnings,
+    }
+}
+
 /// Validate a single media file.
 ///
 /// - Check if file exists
@@ -172,13 +448,14 @@ pub async fn validate_playlist(
         config.text.add_text = false;
     }
 
-    let mut length = config.playlist.length_sec.unwrap();
+    let mut length = playlist_length(&config, &date);
     let mut begin = config.playlist.start_sec.unwrap();
 
     length += begin;
 
     debug!(target: Target::file_mail(), channel = id; "Validate playlist from: <yellow>{date}</>");
     let timer = Instant::now();
+    let day = NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok();
 
     for (index, item) in playlist.program.iter_mut().enumerate() {
         if !is_alive.load(Ordering::SeqCst) {
@@ -187,7 +464,29 @@ pub async fn validate_playlist(
 
         let pos = index + 1;
 
-        if !is_remote(&item.source) {
+        match day.map(|d| validity::check(item, d)) {
+            Some(Validity::NotYet(from)) => {
+                error!(target: Target::file_mail(), channel = id;
+                    "[Validation] Source on position <yellow>{pos:0>3}</> is valid from <yellow>{from}</>: <b><magenta>{}</></b>",
+                    item.source
+                );
+            }
+            Some(Validity::Expired(to)) => {
+                error!(target: Target::file_mail(), channel = id;
+                    "[Validation] Source on position <yellow>{pos:0>3}</> expired on <yellow>{to}</>: <b><magenta>{}</></b>",
+                    item.source
+                );
+            }
+            Some(Validity::Expiring(to)) => {
+                warn!(target: Target::file_mail(), channel = id;
+                    "[Validation] Source on position <yellow>{pos:0>3}</> expires on <yellow>{to}</>: <b><magenta>{}</></b>",
+                    item.source
+                );
+            }
+            _ => (),
+        }
+
+        if !is_remote(&item.source) && !item.random {
             if item.audio.is_empty() {
                 if let Err(e) = item.add_probe(false).await {
                     error!(target: Target::file_mail(), channel = id;
@@ -264,3 +563,35 @@ pub async fn validate_playlist(
         sec_to_time(begin - config.playlist.start_sec.unwrap())
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip(out: f64) -> Media {
+        Media {
+            source: format!("clip_{out}.mp4"),
+            out,
+            duration: out,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn timeline_gap_and_overlap() {
+        let program = vec![clip(3600.0), clip(1800.0), clip(1800.0)];
+
+        assert!(timeline_issues(&program, 21600.0, 7200.0).is_empty());
+
+        let gap = timeline_issues(&program, 21600.0, 7500.0);
+        assert_eq!(gap[0].kind, IssueKind::Gap);
+        assert_eq!(gap[0].begin, 28800.0);
+        assert_eq!(gap[0].seconds, Some(300.0));
+
+        let overlap = timeline_issues(&program, 21600.0, 6000.0);
+        assert_eq!(overlap[0].kind, IssueKind::Overlap);
+        assert_eq!(overlap[0].index, Some(2));
+        assert_eq!(overlap[0].begin, 27000.0);
+        assert_eq!(overlap[0].seconds, Some(1200.0));
+    }
+}
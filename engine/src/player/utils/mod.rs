@@ -7,9 +7,10 @@ use std::{
     process::{exit, Stdio},
     str::FromStr,
     sync::{atomic::Ordering, Arc},
+    time::Duration,
 };
 
-use chrono::{prelude::*, TimeDelta};
+use chrono::{prelude::*, MappedLocalTime, TimeDelta};
 use chrono_tz::Tz;
 use log::*;
 use probe::MediaProbe;
@@ -26,22 +27,33 @@ use tokio::{
 };
 
 pub mod import;
+pub mod json_legacy;
 pub mod json_serializer;
 pub mod json_validate;
 pub mod probe;
+pub mod scte35;
+pub mod secondary;
+pub mod simulate;
+pub mod ticker;
 
+use crate::file::{utils::loudness::Loudness, StorageBackend};
 use crate::player::{
     controller::{
         ChannelManager,
         ProcessUnit::{self, *},
     },
     filter::{filter_chains, Filters},
+    output::watchdog::EncoderProgress,
+    utils::secondary::SecondaryEvent,
 };
 use crate::utils::{
-    config::{OutputMode::*, PlayoutConfig, FFMPEG_IGNORE_ERRORS, FFMPEG_UNRECOVERABLE_ERRORS},
+    config::{
+        AspectMode, Logging, OutputMode::*, PlayoutConfig, FFMPEG_IGNORE_ERRORS,
+        FFMPEG_UNRECOVERABLE_ERRORS,
+    },
     errors::ServiceError,
-    logging::Target,
-    time_machine::time_now,
+    logging::{StderrLog, Target},
+    time_machine::{local_timezone, time_now},
 };
 pub use json_serializer::{read_json, JsonPlaylist};
 
@@ -63,6 +75,15 @@ pub fn valid_stream(msg: &str) -> bool {
     false
 }
 
+/// Stream name, which the RTMP client sent instead of the expected one.
+pub fn unexpected_stream(msg: &str) -> Option<String> {
+    let (_, rest) = msg.split_once("Unexpected stream ")?;
+    let (name, _) = rest.split_once(',')?;
+    let name = name.trim();
+
+    (!name.is_empty()).then(|| name.to_string())
+}
+
 /// Prepare output parameters
 ///
 /// Seek for multiple outputs and add mapping for it.
@@ -124,10 +145,21 @@ pub fn prepare_output_cmd(
         }
     }
 
+    for (i, language) in config.processing.audio_languages.iter().enumerate() {
+        if (i as i32) < config.processing.audio_tracks {
+            cmd.append(&mut vec_strings![
+                format!("-metadata:s:a:{i}"),
+                format!("language={language}")
+            ]);
+        }
+    }
+
     if config.processing.vtt_enable {
         let i = cmd.iter().filter(|&n| n == "-i").count().saturating_sub(1);
 
         cmd.append(&mut vec_strings!("-map", format!("{i}:s?")));
+    } else if config.processing.captions {
+        cmd.append(&mut vec_strings!("-map", "0:s?", "-c:s", "copy"));
     }
 
     cmd.append(&mut output_params);
@@ -165,11 +197,17 @@ pub async fn get_data_map(manager: &ChannelManager) -> Map<String, Value> {
     media.source = media.key.clone(); // to-do : check it later!
 
     let channel = manager.channel.lock().await.clone();
-    let config = manager.config.lock().await.processing.clone();
+    let (config, start_sec) = {
+        let config = manager.config.lock().await;
+        (
+            config.processing.clone(),
+            config.playlist.start_sec.unwrap_or_default(),
+        )
+    };
     let ingest_is_alive = manager.ingest_is_alive.load(Ordering::SeqCst);
 
     let mut data_map = Map::new();
-    let current_time = time_in_seconds(&channel.timezone);
+    let (_, current_time) = playlist_clock(&channel.timezone, start_sec);
     let shift = channel.time_shift;
     let begin = media.begin.unwrap_or(0.0) - shift;
     let played_time = current_time - begin;
@@ -190,6 +228,66 @@ pub async fn get_data_map(manager: &ChannelManager) -> Map<String, Value> {
     data_map
 }
 
+/// Extended status for the live stream of dashboards, with remaining time, next item and encoder health.
+pub async fn get_status_map(manager: &ChannelManager) -> Map<String, Value> {
+    let mut data_map = get_data_map(manager).await;
+    let media = manager.current_media.lock().await.clone();
+    let elapsed = data_map
+        .get("elapsed")
+        .and_then(Value::as_f64)
+        .unwrap_or_default();
+    let remaining = media
+        .as_ref()
+        .map_or(0.0, |m| (m.out - m.seek - elapsed).max(0.0));
+    let next = match media.as_ref().and_then(|m| m.index) {
+        Some(index) => manager
+            .current_list
+            .lock()
+            .await
+            .get(index + 1)
+            .cloned()
+            .map_or(Value::Null, get_media_map),
+        None => Value::Null,
+    };
+    let outputs = manager
+        .outputs
+        .lock()
+        .await
+        .iter()
+        .map(|o| o.status())
+        .collect::<Vec<_>>();
+
+    data_map.insert(
+        "running".to_string(),
+        json!(manager.is_alive.load(Ordering::SeqCst)),
+    );
+    data_map.insert(
+        "standby".to_string(),
+        json!(manager.standby.load(Ordering::SeqCst)),
+    );
+    data_map.insert(
+        "emergency".to_string(),
+        json!(manager.emergency.load(Ordering::SeqCst)),
+    );
+    data_map.insert(
+        "remaining".to_string(),
+        json!((remaining * 1000.0).round() / 1000.0),
+    );
+    data_map.insert("next".to_string(), next);
+    data_map.insert(
+        "encoder".to_string(),
+        json!({
+            "running": manager.encoder.lock().await.is_some(),
+            "destination": manager.destination.load(Ordering::SeqCst),
+            "failures": manager.output_failures.load(Ordering::SeqCst),
+            "stats": manager.progress.lock().await.as_ref().map(|p| p.stats()),
+            "outputs": outputs,
+        }),
+    );
+
+    data_map
+}
+
 /// Video clip struct to hold some important states and comments for current media.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Media {
@@ -203,11 +301,20 @@ pub struct Media {
     pub index: Option<usize>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
+    /// Programme description for the EPG.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
     #[serde(rename = "in")]
     pub seek: f64,
     pub out: f64,
     pub duration: f64,
 
+    /// Editor trim points in media time, `in` and `out` are relative to `trim_in`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trim_in: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trim_out: Option<f64>,
+
     #[serde(skip_serializing, skip_deserializing)]
     pub duration_audio: f64,
 
@@ -220,6 +327,20 @@ pub struct Media {
     #[serde(deserialize_with = "null_string")]
     pub source: String,
 
+    /// The source is a playlist block, which gets expanded in place of this item.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include: bool,
+
+    /// The source is a folder, a random clip from it with about the length of the item is played.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub random: bool,
+
+    /// First and last day, as `YYYY-MM-DD`, on which the item can be scheduled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_from: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_to: Option<String>,
+
     #[serde(
         default,
         deserialize_with = "null_string",
@@ -227,6 +348,42 @@ pub struct Media {
     )]
     pub audio: String,
 
+    /// Backup url for live and remote sources, which is played when the source fails.
+    #[serde(
+        default,
+        deserialize_with = "null_string",
+        skip_serializing_if = "is_empty_string"
+    )]
+    pub backup: String,
+
+    /// The backup is playing, `backup` holds the primary source then.
+    #[serde(skip_serializing, skip_deserializing)]
+    pub on_backup: bool,
+
+    /// Source audio stream for each output track, `-1` for silence.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub audio_map: Vec<i32>,
+
+    /// Fitting of another aspect ratio, instead of the channel default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aspect_mode: Option<AspectMode>,
+
+    /// Remix of the audio channels, like `left` or `stereo|c0=c1|c1=c0`, instead of the channel rules.
+    #[serde(default, skip_serializing_if = "is_empty_string")]
+    pub audio_layout: String,
+
+    /// Subtitle file for the burn-in, instead of the `.srt`/`.ass` file beside the clip.
+    #[serde(
+        default,
+        deserialize_with = "null_string",
+        skip_serializing_if = "is_empty_string"
+    )]
+    pub subtitle: String,
+
+    /// Resolved subtitle file or url, which is burned in.
+    #[serde(skip_serializing, skip_deserializing)]
+    pub subtitle_file: Option<String>,
+
     #[serde(skip_serializing, skip_deserializing)]
     pub cmd: Option<Vec<String>>,
 
@@ -236,21 +393,52 @@ pub struct Media {
     #[serde(default, skip_serializing_if = "is_empty_string")]
     pub custom_filter: String,
 
+    /// Name of the filter template, instead of the template of the channel.
+    #[serde(default, skip_serializing_if = "is_empty_string")]
+    pub filter_template: String,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<SecondaryEvent>,
+
     #[serde(skip_serializing, skip_deserializing)]
     pub probe: Option<MediaProbe>,
 
     #[serde(skip_serializing, skip_deserializing)]
     pub probe_audio: Option<MediaProbe>,
 
+    /// Cached loudness measurement for the normalization.
+    #[serde(skip_serializing, skip_deserializing)]
+    pub loudness: Option<Loudness>,
+
     #[serde(skip_serializing, skip_deserializing)]
     pub last_ad: bool,
 
     #[serde(skip_serializing, skip_deserializing)]
     pub next_ad: bool,
 
+    /// Title of the following item, for the now/next overlay.
+    #[serde(default, skip_serializing, skip_deserializing)]
+    pub next_title: Option<String>,
+
     #[serde(default, skip_serializing, skip_deserializing)]
     pub skip: bool,
 
+    /// Filler or placeholder, which is played instead of the playlist item.
+    #[serde(default, skip_serializing, skip_deserializing)]
+    pub filler: bool,
+
+    /// Start of the clip is already played in the crossfade from the previous clip.
+    #[serde(default, skip_serializing, skip_deserializing)]
+    pub transition_in: bool,
+
+    /// Clip fades in from black after the live ingest, with the ingest transition.
+    #[serde(default, skip_serializing, skip_deserializing)]
+    pub live_in: bool,
+
+    /// Tail of the interrupted clip, which fades to black before the live ingest.
+    #[serde(default, skip_serializing, skip_deserializing)]
+    pub live_out: bool,
+
     #[serde(default, skip_serializing)]
     pub unit: ProcessUnit,
 }
@@ -273,25 +461,63 @@ impl Media {
             begin: None,
             index: Some(index),
             title: None,
+            description: None,
             seek: 0.0,
             out: duration,
             duration,
+            trim_in: None,
+            trim_out: None,
             duration_audio: 0.0,
             category: String::new(),
             source: src.to_string(),
             audio: String::new(),
+            include: false,
+            random: false,
+            valid_from: None,
+            valid_to: None,
+            backup: String::new(),
+            on_backup: false,
+            audio_map: vec![],
+            aspect_mode: None,
+            audio_layout: String::new(),
+            subtitle: String::new(),
+            subtitle_file: None,
             cmd: Some(vec_strings!["-i", src]),
             filter: None,
             custom_filter: String::new(),
+            filter_template: String::new(),
+            events: vec![],
             probe,
             probe_audio: None,
+            loudness: None,
             last_ad: false,
             next_ad: false,
+            next_title: None,
             skip: false,
+            filler: false,
+            transition_in: false,
+            live_in: false,
+            live_out: false,
             unit: Decoder,
         }
     }
 
+    pub fn is_trimmed(&self) -> bool {
+        self.trim_in.is_some() || self.trim_out.is_some()
+    }
+
+    /// Length of the media between the trim points, `full` is the length of the whole file.
+    pub fn trimmed_length(&self, full: f64) -> f64 {
+        (self.trim_out.unwrap_or(full).min(full) - self.trim_in.unwrap_or_default()).max(0.0)
+    }
+
+    /// Switch between the source and its backup.
+    pub fn swap_source(&mut self) {
+        std::mem::swap(&mut self.source, &mut self.backup);
+        self.on_backup = !self.on_backup;
+        self.probe = None;
+    }
+
     pub async fn add_probe(&mut self, check_audio: bool) -> Result<(), String> {
         let mut errors = vec![];
 
@@ -303,6 +529,7 @@ impl Media {
                     if let Some(dur) = probe
                         .format
                         .duration
+                        .map(|d| self.trimmed_length(d))
                         .filter(|d| !is_close(*d, self.duration, 0.5))
                     {
                         self.duration = dur;
@@ -353,21 +580,43 @@ impl Default for Media {
             begin: None,
             index: Some(0),
             title: None,
+            description: None,
             seek: 0.0,
             out: 0.0,
             duration: 0.0,
+            trim_in: None,
+            trim_out: None,
             duration_audio: 0.0,
             category: String::new(),
             source: String::new(),
             audio: String::new(),
+            include: false,
+            random: false,
+            valid_from: None,
+            valid_to: None,
+            backup: String::new(),
+            on_backup: false,
+            audio_map: vec![],
+            aspect_mode: None,
+            audio_layout: String::new(),
+            subtitle: String::new(),
+            subtitle_file: None,
             cmd: Some(vec_strings!["-i", String::new()]),
             filter: None,
             custom_filter: String::new(),
+            filter_template: String::new(),
+            events: vec![],
             probe: None,
             probe_audio: None,
+            loudness: None,
             last_ad: false,
             next_ad: false,
+            next_title: None,
             skip: false,
+            filler: false,
+            transition_in: false,
+            live_in: false,
+            live_out: false,
             unit: Decoder,
         }
     }
@@ -376,13 +625,27 @@ impl Default for Media {
 impl PartialEq for Media {
     fn eq(&self, other: &Self) -> bool {
         self.title == other.title
+            && self.description == other.description
             && self.seek == other.seek
             && self.out == other.out
             && self.duration == other.duration
+            && self.trim_in == other.trim_in
+            && self.trim_out == other.trim_out
             && self.source == other.source
             && self.category == other.category
             && self.audio == other.audio
+            && self.include == other.include
+            && self.random == other.random
+            && self.valid_from == other.valid_from
+            && self.valid_to == other.valid_to
+            && self.backup == other.backup
+            && self.audio_map == other.audio_map
+            && self.aspect_mode == other.aspect_mode
+            && self.audio_layout == other.audio_layout
+            && self.subtitle == other.subtitle
             && self.custom_filter == other.custom_filter
+            && self.filter_template == other.filter_template
+            && self.events == other.events
     }
 }
 
@@ -416,7 +679,8 @@ pub async fn json_reader(path: &PathBuf) -> Result<JsonPlaylist, Error> {
     let mut f = File::options().read(true).write(false).open(path).await?;
     let mut contents = String::new();
     f.read_to_string(&mut contents).await?;
-    let p: JsonPlaylist = serde_json::from_str(&contents)?;
+    let date = path.file_stem().and_then(OsStr::to_str);
+    let (p, _) = json_legacy::normalize_playlist(&contents, date).map_err(Error::other)?;
 
     Ok(p)
 }
@@ -434,10 +698,85 @@ pub async fn json_writer(path: &PathBuf, data: JsonPlaylist) -> Result<(), Error
     Ok(())
 }
 
-/// Get current time in seconds.
+/// Local time of the playlist start at the given date.
+///
+/// When the start falls into a DST gap, it moves forward by the length of the gap,
+/// when the start exists twice (clocks go back), the earlier one is used.
+pub fn day_start_time(tz: &Tz, date: NaiveDate, start_sec: f64) -> DateTime<Tz> {
+    let naive =
+        date.and_time(NaiveTime::MIN) + TimeDelta::milliseconds((start_sec * 1000.0) as i64);
+
+    match tz.from_local_datetime(&naive) {
+        MappedLocalTime::Single(time) | MappedLocalTime::Ambiguous(time, _) => time,
+        MappedLocalTime::None => {
+            // take the offset from before the gap
+            let offset = tz
+                .offset_from_utc_datetime(&(naive - TimeDelta::days(1)))
+                .fix()
+                .local_minus_utc();
+
+            tz.from_utc_datetime(&(naive - TimeDelta::seconds(i64::from(offset))))
+        }
+    }
+}
+
+/// Real length of a playlist day, in seconds. On DST days this is 23 or 25 hours.
+pub fn day_length(timezone: &Option<Tz>, date: NaiveDate, start_sec: f64) -> f64 {
+    let tz = local_timezone(timezone);
+    let begin = day_start_time(&tz, date, start_sec);
+    let end = day_start_time(&tz, date + TimeDelta::days(1), start_sec);
+
+    (end - begin).num_milliseconds() as f64 / 1000.0
+}
+
+/// Target length of the playlist from the given date.
+///
+/// A full day (length 24:00:00) follows the real day length, on DST days.
+pub fn playlist_length(config: &PlayoutConfig, date: &str) -> f64 {
+    let length = config.playlist.length_sec.unwrap_or(86400.0);
+
+    match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        Ok(date) if length == 86400.0 => day_length(
+            &config.channel.timezone,
+            date,
+            config.playlist.start_sec.unwrap_or_default(),
+        ),
+        _ => length,
+    }
+}
+
+/// Get current playlist date and playlist time in seconds.
+///
+/// The time is the playlist start plus the real time since the start of the current playlist day,
+/// so it not jumps with the wall clock on DST days and goes up to `start_sec` + 23 or 25 hours.
+pub fn playlist_clock(timezone: &Option<Tz>, start_sec: f64) -> (NaiveDate, f64) {
+    playlist_clock_at(time_now(timezone), start_sec)
+}
+
+/// Playlist date and playlist time in seconds at the given time, see [`playlist_clock`].
+pub fn playlist_clock_at(now: DateTime<Tz>, start_sec: f64) -> (NaiveDate, f64) {
+    let tz = now.timezone();
+    let mut date = now.date_naive();
+    let mut begin = day_start_time(&tz, date, start_sec);
+
+    if now < begin {
+        date = date.pred_opt().unwrap_or(date);
+        begin = day_start_time(&tz, date, start_sec);
+    }
+
+    let elapsed = (now - begin).num_microseconds().unwrap_or_default() as f64 / 1000000.0;
+
+    (date, start_sec + elapsed)
+}
+
+/// Get current wall clock time in seconds, for times of the day like in the insert rules.
+/// Playlist positions must use [`playlist_clock`], which not jumps on DST days.
 pub fn time_in_seconds(timezone: &Option<Tz>) -> f64 {
-    let local: DateTime<Tz> = time_now(timezone);
+    seconds_of_day(&time_now(timezone))
+}
 
+/// Wall clock time of the given time in seconds, see [`time_in_seconds`].
+pub fn seconds_of_day(local: &DateTime<Tz>) -> f64 {
     (local.hour() * 3600 + local.minute() * 60 + local.second()) as f64
         + (local.nanosecond() as f64 / 1000000000.0)
 }
@@ -447,21 +786,17 @@ pub fn time_in_seconds(timezone: &Option<Tz>) -> f64 {
 /// - When time is before playlist start, get date from yesterday.
 /// - When given next_start is over target length (normally a full day), get date from tomorrow.
 pub fn get_date(seek: bool, start: f64, get_next: bool, timezone: &Option<Tz>) -> String {
-    let local: DateTime<Tz> = time_now(timezone);
+    let (date, time) = playlist_clock(timezone, start);
 
-    if seek && start > time_in_seconds(timezone) {
-        return (local - TimeDelta::try_days(1).unwrap())
-            .format("%Y-%m-%d")
-            .to_string();
+    if seek {
+        return date.format("%Y-%m-%d").to_string();
     }
 
-    if start == 0.0 && get_next && time_in_seconds(timezone) > 86397.9 {
-        return (local + TimeDelta::try_days(1).unwrap())
-            .format("%Y-%m-%d")
-            .to_string();
+    if start == 0.0 && get_next && day_length(timezone, date, start) - time < 2.1 {
+        return (date + TimeDelta::days(1)).format("%Y-%m-%d").to_string();
     }
 
-    local.format("%Y-%m-%d").to_string()
+    time_now(timezone).format("%Y-%m-%d").to_string()
 }
 
 pub fn time_from_header(headers: &header::HeaderMap) -> Option<DateTime<Local>> {
@@ -476,6 +811,16 @@ pub fn time_from_header(headers: &header::HeaderMap) -> Option<DateTime<Local>>
     None
 }
 
+/// Probe a live source, which gives up after the failover timeout.
+pub async fn probe_live(config: &PlayoutConfig, source: &str) -> Option<MediaProbe> {
+    let limit = Duration::from_secs_f64(config.processing.failover_timeout.max(1.0));
+
+    tokio::time::timeout(limit, MediaProbe::new(source))
+        .await
+        .ok()?
+        .ok()
+}
+
 /// Get file modification time.
 pub async fn modified_time(path: &str) -> Option<String> {
     if is_remote(path) {
@@ -547,29 +892,28 @@ pub fn sum_durations(clip_list: &[Media]) -> f64 {
 ///
 /// We also get here the global delta between clip start and time when a new playlist should start.
 pub fn get_delta(config: &PlayoutConfig, begin: &f64) -> (f64, f64) {
-    let mut current_time = time_in_seconds(&config.channel.timezone);
     let start = config.playlist.start_sec.unwrap();
+    let (date, mut current_time) = playlist_clock(&config.channel.timezone, start);
+    let day_length = day_length(&config.channel.timezone, date, start);
     let length = config.playlist.length_sec.unwrap_or(86400.0);
-    let mut target_length = 86400.0;
+    let mut target_length = day_length;
 
-    if length > 0.0 && length != target_length {
+    if length > 0.0 && length != 86400.0 {
         target_length = length;
     }
 
-    if begin == &start && start == 0.0 && 86400.0 - current_time < 4.0 {
-        current_time -= 86400.0;
-    } else if start >= current_time && begin != &start {
-        current_time += 86400.0;
+    if begin == &start && start + day_length - current_time < 4.0 {
+        current_time -= day_length;
     }
 
     let mut current_delta = begin - current_time;
 
     if is_close(
         current_delta.abs(),
-        86400.0,
+        day_length,
         config.general.stop_threshold + 2.0,
     ) {
-        current_delta = current_delta.abs() - 86400.0;
+        current_delta = current_delta.abs() - day_length;
     }
 
     let total_delta = if current_time < start {
@@ -581,6 +925,134 @@ pub fn get_delta(config: &PlayoutConfig, begin: &f64) -> (f64, f64) {
     (current_delta, total_delta)
 }
 
+/// Sidecar with DVB subtitle or teletext streams: a transport stream with the name
+/// of the clip and the extension `.dvb`, otherwise the dummy, which keeps the subtitle
+/// stream in the output continuous.
+pub fn caption_file(config: &PlayoutConfig, source: Option<&str>) -> Option<PathBuf> {
+    if !config.processing.captions {
+        return None;
+    }
+
+    if let Some(sidecar) = source
+        .map(|s| Path::new(s).with_extension("dvb"))
+        .filter(|p| p.is_file())
+    {
+        return Some(sidecar);
+    }
+
+    let dummy = config
+        .channel
+        .storage
+        .join(&config.processing.captions_dummy);
+
+    if !config.processing.captions_dummy.is_empty() && dummy.is_file() {
+        Some(dummy)
+    } else {
+        error!("Captions enabled, but no sidecar or dummy file found!");
+        None
+    }
+}
+
+/// Input arguments for the caption sidecar, the seek is used only on the sidecar of the clip.
+fn caption_input(
+    config: &PlayoutConfig,
+    source: Option<&str>,
+    seek: Option<f64>,
+    length: f64,
+) -> Vec<String> {
+    let Some(file) = caption_file(config, source) else {
+        return vec![];
+    };
+    let mut cmd = vec![];
+
+    if let Some(seek) = seek.filter(|_| file.extension().is_some_and(|e| e == "dvb")) {
+        cmd.append(&mut vec_strings!["-ss", seek]);
+    }
+
+    cmd.append(&mut vec_strings![
+        "-i",
+        file.to_string_lossy(),
+        "-t",
+        length
+    ]);
+
+    cmd
+}
+
+/// Position of the caption input, counted from the inputs of the command.
+pub fn caption_input_index(config: &PlayoutConfig, cmd: &[String]) -> Option<usize> {
+    if !config.processing.captions {
+        return None;
+    }
+
+    let dummy = config
+        .channel
+        .storage
+        .join(&config.processing.captions_dummy);
+
+    cmd.windows(2)
+        .filter(|w| w[0] == "-i")
+        .position(|w| w[1].ends_with(".dvb") || Path::new(&w[1]) == dummy)
+}
+
+const SUBTITLE_EXTENSIONS: [&str; 2] = ["srt", "ass"];
+
+/// Find the subtitle file for the burn-in: the one from the playlist item,
+/// or a `.srt`/`.ass` file with the same name beside the clip, also on S3 storage.
+pub async fn subtitle_file(
+    manager: &ChannelManager,
+    config: &PlayoutConfig,
+    node: &Media,
+) -> Option<String> {
+    if !config.processing.subtitles || node.source.is_empty() {
+        return None;
+    }
+
+    let storage = manager.storage.lock().await.clone();
+    let local = matches!(storage, StorageBackend::Local(_));
+
+    if local && is_remote(&node.source) {
+        return None;
+    }
+
+    if !node.subtitle.is_empty() {
+        if !local {
+            return storage.fetch_file_path(&node.subtitle).await.ok();
+        }
+
+        if Path::new(&node.subtitle).is_file() {
+            return Some(node.subtitle.clone());
+        }
+
+        warn!(target: Target::file_mail(), channel = config.general.channel_id;
+            "Subtitle <b><magenta>{}</></b> not found!", node.subtitle
+        );
+
+        return None;
+    }
+
+    if local {
+        return SUBTITLE_EXTENSIONS
+            .iter()
+            .map(|ext| Path::new(&node.source).with_extension(ext))
+            .find(|p| p.is_file())
+            .map(|p| p.to_string_lossy().to_string());
+    }
+
+    // on S3 the source is a presigned url, the object path comes from the key
+    let object = storage.interpreted_file_path(&node.key);
+
+    for ext in SUBTITLE_EXTENSIONS {
+        let path = Path::new(object.trim_start_matches('/')).with_extension(ext);
+
+        if storage.is_file(&path).await {
+            return storage.fetch_file_path(&path.to_string_lossy()).await.ok();
+        }
+    }
+
+    None
+}
+
 /// Loop image until target duration is reached.
 pub fn loop_image(config: &PlayoutConfig, node: &Media) -> Vec<String> {
     let duration = node.out - node.seek;
@@ -626,6 +1098,13 @@ pub fn loop_image(config: &PlayoutConfig, node: &Media) -> Vec<String> {
         }
     }
 
+    source_cmd.append(&mut caption_input(
+        config,
+        Some(&node.source),
+        Some(node.seek).filter(|s| *s > 0.5),
+        duration,
+    ));
+
     source_cmd
 }
 
@@ -667,22 +1146,49 @@ pub fn loop_filler(config: &PlayoutConfig, node: &Media) -> Vec<String> {
         }
     }
 
+    // a sidecar would not loop together with the clip
+    let source = Some(node.source.as_str()).filter(|_| loop_count <= 1);
+    source_cmd.append(&mut caption_input(config, source, None, node.out));
+
     source_cmd
 }
 
 /// Set clip seek in and length value.
 pub fn seek_and_length(config: &PlayoutConfig, node: &mut Media) -> Vec<String> {
-    let loop_count = (node.out / node.duration).ceil() as i32;
+    let trimmed = node.is_trimmed();
+    let mut loop_count = (node.out / node.duration).ceil() as i32;
     let mut source_cmd = vec![];
     let mut cut_audio = false;
     let mut loop_audio = false;
     let remote_source = is_remote(&node.source);
 
+    if trimmed && loop_count > 1 {
+        // stream_loop would repeat the whole file, not only the trimmed part
+        warn!(
+            "Trimmed clip <b><magenta>{}</></b> can't loop, play it only once",
+            node.source
+        );
+        loop_count = 1;
+    }
+
     if remote_source && node.probe.clone().and_then(|f| f.format.duration).is_none() {
         node.out -= node.seek;
         node.seek = 0.0;
-    } else if node.seek > 0.5 {
-        source_cmd.append(&mut vec_strings!["-ss", node.seek]);
+    }
+
+    let start = node.trim_in.unwrap_or_default() + node.seek;
+    let seek = start > 0.5 || (trimmed && start > 0.0);
+
+    if seek {
+        source_cmd.append(&mut vec_strings!["-ss", start]);
+    }
+
+    if remote_source && !node.backup.is_empty() && config.processing.failover_timeout > 0.0 {
+        // let the decoder end, when the source sends no data, so the backup can take over
+        source_cmd.append(&mut vec_strings![
+            "-rw_timeout",
+            (config.processing.failover_timeout * 1_000_000.0) as i64
+        ]);
     }
 
     if loop_count > 1 {
@@ -693,13 +1199,13 @@ pub fn seek_and_length(config: &PlayoutConfig, node: &mut Media) -> Vec<String>
 
     source_cmd.append(&mut vec_strings!["-i", node.source.clone()]);
 
-    if node.duration > node.out || remote_source || loop_count > 1 {
+    if node.duration > node.out || remote_source || loop_count > 1 || trimmed {
         source_cmd.append(&mut vec_strings!["-t", node.out - node.seek]);
     }
 
     if !node.audio.is_empty() {
-        if node.seek > 0.5 {
-            source_cmd.append(&mut vec_strings!["-ss", node.seek]);
+        if seek {
+            source_cmd.append(&mut vec_strings!["-ss", start]);
         }
 
         if node.duration_audio > node.out {
@@ -723,8 +1229,8 @@ pub fn seek_and_length(config: &PlayoutConfig, node: &mut Media) -> Vec<String>
             .storage
             .join(config.processing.vtt_dummy.clone().unwrap_or_default());
 
-        if node.seek > 0.5 {
-            source_cmd.append(&mut vec_strings!["-ss", node.seek]);
+        if seek {
+            source_cmd.append(&mut vec_strings!["-ss", start]);
         }
 
         if let Some(vtt_path) = if vtt_file.is_file() {
@@ -747,6 +1253,14 @@ pub fn seek_and_length(config: &PlayoutConfig, node: &mut Media) -> Vec<String>
         }
     }
 
+    let source = Some(node.source.as_str()).filter(|_| loop_count <= 1);
+    source_cmd.append(&mut caption_input(
+        config,
+        source,
+        seek.then_some(start),
+        node.out - node.seek,
+    ));
+
     source_cmd
 }
 
@@ -784,6 +1298,8 @@ pub fn gen_dummy(config: &PlayoutConfig, duration: f64) -> (String, Vec<String>)
         }
     }
 
+    source_cmd.append(&mut caption_input(config, None, None, duration));
+
     (source, source_cmd)
 }
 
@@ -859,6 +1375,21 @@ pub fn include_file_extension(config: &PlayoutConfig, file_path: &Path) -> bool
         }
     }
 
+    if config.output.mode == Dash {
+        if let Some(mpd_path) = config
+            .output
+            .output_cmd
+            .as_ref()
+            .and_then(|cmd| cmd.iter().find(|s| s.ends_with(".mpd")))
+        {
+            if let Some(p) = Path::new(mpd_path).parent() {
+                if file_path.starts_with(p) {
+                    include = false;
+                }
+            }
+        }
+    }
+
     include
 }
 
@@ -866,19 +1397,29 @@ pub fn include_file_extension(config: &PlayoutConfig, file_path: &Path) -> bool
 /// and log the output.
 pub async fn stderr_reader(
     buffer: tokio::io::BufReader<ChildStderr>,
-    ignore: Vec<String>,
+    logging: Logging,
     suffix: ProcessUnit,
     channel_id: i32,
+    progress: Option<Arc<EncoderProgress>>,
 ) -> Result<(), ServiceError> {
     let mut lines = buffer.lines();
+    let mut log = StderrLog::new(&logging, suffix, channel_id);
 
     while let Some(line) = lines.next_line().await? {
+        if progress.as_ref().is_some_and(|p| p.read_line(&line)) {
+            continue;
+        }
+
         if FFMPEG_IGNORE_ERRORS.iter().any(|i| line.contains(*i))
-            || ignore.iter().any(|i| line.contains(i))
+            || logging.ignore_lines.iter().any(|i| line.contains(i))
         {
             continue;
         }
 
+        if !log.push(&line) {
+            continue;
+        }
+
         if line.contains("[info]") {
             info!(target: Target::file_mail(), channel = channel_id;
                 "<bright black>[{suffix}]</> {}",
@@ -901,6 +1442,8 @@ pub async fn stderr_reader(
                 || (line.contains("No such file or directory")
                     && !line.contains("failed to delete old segment"))
             {
+                log.dump(&logging).await;
+
                 return Err(ServiceError::Conflict(
                     "Hit unrecoverable error!".to_string(),
                 ));
@@ -908,6 +1451,10 @@ pub async fn stderr_reader(
         }
     }
 
+    if log.failed {
+        log.dump(&logging).await;
+    }
+
     Ok(())
 }
 
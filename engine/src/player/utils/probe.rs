@@ -91,6 +91,12 @@ pub struct Stream {
     pub height: Option<i64>,
     pub r_frame_rate: String,
     pub field_order: Option<String>,
+    pub tags: Option<StreamTags>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct StreamTags {
+    pub language: Option<String>,
 }
 
 #[serde_as]
@@ -128,6 +134,8 @@ pub struct AudioStream {
     pub codec_name: Option<String>,
     pub duration: Option<f64>,
     pub sample_rate: Option<i64>,
+    #[serde(default)]
+    pub language: Option<String>,
 }
 
 impl AudioStream {
@@ -137,6 +145,7 @@ impl AudioStream {
             codec_name: stream.codec_name,
             duration: stream.duration,
             sample_rate: stream.sample_rate,
+            language: stream.tags.and_then(|t| t.language),
         }
     }
 }
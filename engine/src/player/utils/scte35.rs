@@ -0,0 +1,342 @@
+/// SCTE-35 Cues
+///
+/// Advertisement breaks are signaled with `splice_insert` messages,
+/// one out of network at the begin of a break and one back to the network at its end.
+/// They are added to the served HLS playlists, as `EXT-X-DATERANGE` and `EXT-X-CUE-OUT/IN` tags.
+use std::collections::VecDeque;
+
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+
+use crate::player::utils::Media;
+
+/// PTS values have 33 bits.
+const PTS_MASK: u64 = (1 << 33) - 1;
+
+/// Events which are kept for tagging the playlist, older ones are not in the HLS window anymore.
+const MAX_EVENTS: usize = 200;
+
+fn crc32_mpeg2(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFF_u32;
+
+    for byte in data {
+        crc ^= u32::from(*byte) << 24;
+
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 == 0 {
+                crc << 1
+            } else {
+                (crc << 1) ^ 0x04C1_1DB7
+            };
+        }
+    }
+
+    crc
+}
+
+/// `splice_time` and `break_duration` share the layout: one flag, six reserved bits and 33 bits value.
+fn time_field(flag: bool, value: u64) -> [u8; 5] {
+    let value = value & PTS_MASK;
+
+    [
+        (u8::from(flag) << 7) | 0x7E | (value >> 32) as u8,
+        (value >> 24) as u8,
+        (value >> 16) as u8,
+        (value >> 8) as u8,
+        value as u8,
+    ]
+}
+
+/// Create a `splice_info_section` with a `splice_insert` command.
+///
+/// Without `pts` the splice is immediate, `duration` is in seconds and returns automatically.
+pub fn splice_insert(
+    event_id: u32,
+    out_of_network: bool,
+    pts: Option<u64>,
+    duration: Option<f64>,
+) -> Vec<u8> {
+    let mut command = event_id.to_be_bytes().to_vec();
+
+    // not cancelled
+    command.push(0x7F);
+    // program splice and event id compliance flag are always set
+    command.push(
+        (u8::from(out_of_network) << 7)
+            | 0x40
+            | (u8::from(duration.is_some()) << 5)
+            | (u8::from(pts.is_none()) << 4)
+            | 0x0F,
+    );
+
+    if let Some(pts) = pts {
+        command.extend(time_field(true, pts));
+    }
+
+    if let Some(duration) = duration {
+        command.extend(time_field(true, (duration * 90000.0).round() as u64));
+    }
+
+    // unique_program_id, avail_num, avails_expected
+    command.extend([0x00, 0x00, 0x00, 0x00]);
+
+    let mut section = vec![
+        0xFC,
+        0x00,
+        0x00,
+        0x00,
+        0x00,
+        0x00,
+        0x00,
+        0x00,
+        0x00,
+        0x00,
+        0xFF,
+        0xF0 | ((command.len() >> 8) & 0x0F) as u8,
+        command.len() as u8,
+        0x05,
+    ];
+
+    section.extend(command);
+    // descriptor_loop_length
+    section.extend([0x00, 0x00]);
+
+    let section_length = section.len() + 4 - 3;
+    section[1] = 0x30 | ((section_length >> 8) & 0x0F) as u8;
+    section[2] = section_length as u8;
+
+    let crc = crc32_mpeg2(&section);
+    section.extend(crc.to_be_bytes());
+
+    section
+}
+
+/// Length of the advertisement break, which starts with `node`.
+pub fn break_duration(list: &[Media], node: &Media) -> Option<f64> {
+    let index = node.index?;
+
+    if list.get(index).is_none_or(|m| m.source != node.source) {
+        return None;
+    }
+
+    let duration: f64 = list
+        .iter()
+        .skip(index)
+        .take_while(|m| m.category == "advertisement")
+        .map(|m| m.out - m.seek)
+        .sum();
+
+    (duration > 0.0).then_some(duration)
+}
+
+#[derive(Clone, Debug)]
+pub struct SpliceEvent {
+    pub event_id: u32,
+    pub out_of_network: bool,
+    pub time: DateTime<Utc>,
+    /// Begin of the break, on the cue in it is the time of the cue out.
+    pub break_start: DateTime<Utc>,
+    pub duration: Option<f64>,
+    pub section: Vec<u8>,
+}
+
+impl SpliceEvent {
+    fn hex(&self) -> String {
+        self.section.iter().map(|b| format!("{b:02X}")).collect()
+    }
+
+    fn tags(&self) -> Vec<String> {
+        let id = self.event_id;
+        let start = self
+            .break_start
+            .to_rfc3339_opts(SecondsFormat::Millis, true);
+
+        if self.out_of_network {
+            let planned = self
+                .duration
+                .map(|d| format!("PLANNED-DURATION={d:.3},"))
+                .unwrap_or_default();
+            let cue_out = self
+                .duration
+                .map(|d| format!("#EXT-X-CUE-OUT:{d:.3}"))
+                .unwrap_or("#EXT-X-CUE-OUT".to_string());
+
+            vec![
+                format!(
+                    "#EXT-X-DATERANGE:ID=\"splice-{id}\",START-DATE=\"{start}\",{planned}SCTE35-OUT=0x{}",
+                    self.hex()
+                ),
+                cue_out,
+            ]
+        } else {
+            let end = self.time.to_rfc3339_opts(SecondsFormat::Millis, true);
+
+            vec![
+                format!(
+                    "#EXT-X-DATERANGE:ID=\"splice-{id}\",START-DATE=\"{start}\",END-DATE=\"{end}\",SCTE35-IN=0x{}",
+                    self.hex()
+                ),
+                "#EXT-X-CUE-IN".to_string(),
+            ]
+        }
+    }
+}
+
+fn parse_date_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .or_else(|_| DateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f%z"))
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+#[derive(Debug, Default)]
+pub struct SpliceCues {
+    events: VecDeque<SpliceEvent>,
+    event_id: u32,
+    break_start: Option<DateTime<Utc>>,
+    /// Start of the output, the cue PTS are relative to it.
+    output_start: Option<DateTime<Utc>>,
+}
+
+impl SpliceCues {
+    /// New output process, timestamps start again.
+    pub fn reset(&mut self) {
+        self.output_start = None;
+        self.break_start = None;
+    }
+
+    /// Register the begin of a clip and create a cue, when a break starts or ends.
+    ///
+    /// With `timed` the cue gets the PTS since output start, otherwise it splices immediately.
+    pub fn node_started(
+        &mut self,
+        time: DateTime<Utc>,
+        is_ad: bool,
+        duration: Option<f64>,
+        timed: bool,
+    ) -> Option<SpliceEvent> {
+        let output_start = *self.output_start.get_or_insert(time);
+        let pts = timed.then(|| ((time - output_start).num_milliseconds().max(0) as u64) * 90);
+
+        let event = match (is_ad, self.break_start) {
+            (true, None) => {
+                self.event_id = self.event_id.wrapping_add(1);
+                self.break_start = Some(time);
+
+                SpliceEvent {
+                    event_id: self.event_id,
+                    out_of_network: true,
+                    time,
+                    break_start: time,
+                    duration,
+                    section: splice_insert(self.event_id, true, pts, duration),
+                }
+            }
+            (false, Some(break_start)) => {
+                self.break_start = None;
+
+                SpliceEvent {
+                    event_id: self.event_id,
+                    out_of_network: false,
+                    time,
+                    break_start,
+                    duration: None,
+                    section: splice_insert(self.event_id, false, pts, None),
+                }
+            }
+            _ => return None,
+        };
+
+        self.events.push_back(event.clone());
+
+        while self.events.len() > MAX_EVENTS {
+            self.events.pop_front();
+        }
+
+        Some(event)
+    }
+
+    /// Add the cue tags in front of the segments, in which the splice happens.
+    ///
+    /// Segments are matched by `EXT-X-PROGRAM-DATE-TIME`, playlists without it stay untouched.
+    pub fn tag_playlist(&self, playlist: &str) -> String {
+        let mut tagged = String::with_capacity(playlist.len());
+        let mut segment_start = None;
+
+        for line in playlist.lines() {
+            if let Some(value) = line.strip_prefix("#EXT-X-PROGRAM-DATE-TIME:") {
+                segment_start = parse_date_time(value.trim());
+            } else if let Some(value) = line.strip_prefix("#EXTINF:") {
+                let duration = value
+                    .split(',')
+                    .next()
+                    .and_then(|d| d.trim().parse::<f64>().ok())
+                    .unwrap_or_default();
+
+                if let Some(start) = segment_start {
+                    let end = start + TimeDelta::milliseconds((duration * 1000.0) as i64);
+
+                    for event in self
+                        .events
+                        .iter()
+                        .filter(|e| e.time >= start && e.time < end)
+                    {
+                        for tag in event.tags() {
+                            tagged.push_str(&tag);
+                            tagged.push('\n');
+                        }
+                    }
+
+                    segment_start = Some(end);
+                }
+            }
+
+            tagged.push_str(line);
+            tagged.push('\n');
+        }
+
+        tagged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splice_insert_section() {
+        let section = splice_insert(7, true, Some(900_000), Some(30.0));
+
+        assert_eq!(section[0], 0xFC);
+        assert_eq!(
+            ((usize::from(section[1]) & 0x0F) << 8) + usize::from(section[2]),
+            section.len() - 3
+        );
+        // command type and out of network, program splice, duration flags
+        assert_eq!(section[13], 0x05);
+        assert_eq!(section[19], 0xEF);
+        // crc over the whole section, including the crc, is zero
+        assert_eq!(crc32_mpeg2(&section), 0);
+    }
+
+    #[test]
+    fn tag_hls_playlist() {
+        let mut cues = SpliceCues::default();
+        let start = parse_date_time("2025-03-01T10:00:00.000+0000").unwrap();
+
+        cues.node_started(start, false, None, true);
+        cues.node_started(start + TimeDelta::seconds(8), true, Some(20.0), true);
+        cues.node_started(start + TimeDelta::seconds(28), false, None, true);
+
+        let playlist = "#EXTM3U\n#EXT-X-PROGRAM-DATE-TIME:2025-03-01T10:00:00.000+0000\n#EXTINF:6.000000,\nstream-0.ts\n#EXTINF:6.000000,\nstream-1.ts\n#EXTINF:6.000000,\nstream-2.ts\n#EXTINF:6.000000,\nstream-3.ts\n#EXTINF:6.000000,\nstream-4.ts\n";
+        let tagged = cues.tag_playlist(playlist);
+        let lines: Vec<&str> = tagged.lines().collect();
+
+        assert!(lines[4].starts_with("#EXT-X-DATERANGE:ID=\"splice-1\""));
+        assert!(lines[4].contains("PLANNED-DURATION=20.000"));
+        assert_eq!(lines[5], "#EXT-X-CUE-OUT:20.000");
+        assert_eq!(lines[6], "#EXTINF:6.000000,");
+        assert!(lines[12].contains("SCTE35-IN=0x"));
+        assert_eq!(lines[13], "#EXT-X-CUE-IN");
+        assert_eq!(lines[15], "stream-4.ts");
+    }
+}
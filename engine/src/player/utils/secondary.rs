@@ -0,0 +1,329 @@
+/// Secondary Events
+///
+/// Playlist items can carry events, which follow the programming: show a logo,
+/// fire a text preset or trigger a webhook, at `offset` seconds after the item starts.
+/// Logos are part of the clip filter, text and webhooks are executed by the player.
+/// Text presets with a schedule are fired every day at their times.
+use std::{sync::atomic::Ordering, time::Duration};
+
+use chrono::NaiveTime;
+use log::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::{
+    task::JoinHandle,
+    time::{interval, sleep, Instant},
+};
+
+use crate::db::{handles, models::TextPreset};
+use crate::player::{controller::ChannelManager, utils::Media};
+use crate::utils::{control::send_message, logging::Target, time_machine::time_now, TextFilter};
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Check interval of the preset schedules.
+const SCHEDULE_POLL: Duration = Duration::from_millis(500);
+
+/// Changed presets are used after this time.
+const PRESET_RELOAD: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SecondaryAction {
+    /// Logo file, relative paths are inside the channel storage.
+    Logo {
+        path: String,
+    },
+    /// Name of a text preset from the channel.
+    Text {
+        preset: String,
+    },
+    Webhook {
+        url: String,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct SecondaryEvent {
+    /// Seconds after the item start.
+    #[serde(default)]
+    pub offset: f64,
+    /// Show logo or text only for this time, otherwise until the item ends.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+    #[serde(flatten)]
+    pub action: SecondaryAction,
+}
+
+impl SecondaryEvent {
+    /// Time range inside the item, in which the event is active.
+    pub fn range(&self, length: f64) -> (f64, f64) {
+        let start = self.offset.max(0.0);
+        let end = self.duration.map_or(length, |d| (start + d).min(length));
+
+        (start, end)
+    }
+}
+
+async fn fire_text(manager: &ChannelManager, preset: &str) {
+    let id = manager.id;
+
+    let presets = match handles::select_presets(&manager.db_pool, id).await {
+        Ok(presets) => presets,
+        Err(e) => {
+            error!(target: Target::file_mail(), channel = id; "Read text presets failed: {e}");
+            return;
+        }
+    };
+
+    let Some(preset) = presets.iter().find(|p| p.name == preset) else {
+        error!(target: Target::file_mail(), channel = id; "Text preset <yellow>{preset}</> not exists!");
+        return;
+    };
+
+    send_preset(manager, preset).await;
+}
+
+async fn send_preset(manager: &ChannelManager, preset: &TextPreset) {
+    if let Err(e) = send_message(manager.clone(), TextFilter::from(preset)).await {
+        error!(target: Target::file_mail(), channel = manager.id; "Send text preset <yellow>{}</> failed: {e}", preset.name);
+    }
+}
+
+async fn clear_text(manager: &ChannelManager) {
+    let message = TextFilter {
+        text: Some(String::new()),
+        ..Default::default()
+    };
+
+    if let Err(e) = send_message(manager.clone(), message).await {
+        error!(target: Target::file_mail(), channel = manager.id; "Clear text failed: {e}");
+    }
+}
+
+async fn fire_webhook(manager: &ChannelManager, url: &str, node: &Media) {
+    let body = json!({
+        "channel": manager.id,
+        "title": node.title,
+        "source": node.source,
+        "category": node.category,
+        "in": node.seek,
+        "out": node.out,
+    });
+
+    match reqwest::Client::new()
+        .post(url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .json(&body)
+        .send()
+        .await
+    {
+        Ok(resp) if !resp.status().is_success() => {
+            error!(target: Target::file_mail(), channel = manager.id; "Webhook <b><magenta>{url}</></b> returns: {}", resp.status());
+        }
+        Ok(_) => (),
+        Err(e) => {
+            error!(target: Target::file_mail(), channel = manager.id; "Webhook <b><magenta>{url}</></b> failed: {e}");
+        }
+    }
+}
+
+/// Execute text and webhook events of the item, which starts now.
+///
+/// The task gets aborted when the next item starts, so events never leak into other programs.
+pub async fn run(manager: ChannelManager, node: Media) {
+    let start = Instant::now();
+    let length = node.out - node.seek;
+    let mut steps = vec![];
+
+    for event in &node.events {
+        let (begin, end) = event.range(length);
+
+        if begin >= length {
+            continue;
+        }
+
+        match &event.action {
+            SecondaryAction::Text { .. } => {
+                steps.push((begin, Some(event)));
+
+                if event.duration.is_some() {
+                    steps.push((end, None));
+                }
+            }
+            SecondaryAction::Webhook { .. } => steps.push((begin, Some(event))),
+            SecondaryAction::Logo { .. } => (),
+        }
+    }
+
+    steps.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    for (at, event) in steps {
+        sleep(
+            Duration::from_secs_f64(at)
+                .checked_sub(start.elapsed())
+                .unwrap_or_default(),
+        )
+        .await;
+
+        match event.map(|e| &e.action) {
+            Some(SecondaryAction::Text { preset }) => {
+                debug!(target: Target::file_mail(), channel = manager.id; "Secondary event: text preset <yellow>{preset}</>");
+                fire_text(&manager, preset).await;
+            }
+            Some(SecondaryAction::Webhook { url }) => {
+                debug!(target: Target::file_mail(), channel = manager.id; "Secondary event: webhook <b><magenta>{url}</></b>");
+                fire_webhook(&manager, url, &node).await;
+            }
+            Some(SecondaryAction::Logo { .. }) => (),
+            None => clear_text(&manager).await,
+        }
+    }
+}
+
+/// Daily times of a preset schedule, separated by semicolon, like `07:59:50;19:59:50`.
+pub fn parse_schedule(schedule: &str) -> Result<Vec<NaiveTime>, String> {
+    schedule
+        .split(';')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(|t| {
+            NaiveTime::parse_from_str(t, "%H:%M:%S")
+                .or_else(|_| NaiveTime::parse_from_str(t, "%H:%M"))
+                .map_err(|_| format!("Invalid schedule time: {t}"))
+        })
+        .collect()
+}
+
+/// The time is after the last check and not after now, also over midnight.
+fn is_due(time: NaiveTime, last: NaiveTime, now: NaiveTime) -> bool {
+    if last <= now {
+        last < time && time <= now
+    } else {
+        last < time || time <= now
+    }
+}
+
+/// Fire the text presets at the daily times of their schedule, in the timezone of the channel.
+/// With a duration the text is cleared afterwards, a following preset cancels the clearing.
+pub async fn preset_scheduler(manager: ChannelManager) {
+    let id = manager.id;
+    let timezone = manager.config.lock().await.channel.timezone;
+    let mut presets = vec![];
+    let mut loaded: Option<Instant> = None;
+    let mut clear: Option<JoinHandle<()>> = None;
+    let mut last = time_now(&timezone).time();
+    let mut tick = interval(SCHEDULE_POLL);
+
+    while manager.is_alive.load(Ordering::SeqCst) {
+        tick.tick().await;
+
+        if loaded.is_none_or(|l| l.elapsed() >= PRESET_RELOAD) {
+            match handles::select_presets(&manager.db_pool, id).await {
+                Ok(list) => presets = list,
+                Err(e) => {
+                    error!(target: Target::file_mail(), channel = id; "Read text presets failed: {e}");
+                }
+            }
+
+            loaded = Some(Instant::now());
+        }
+
+        let now = time_now(&timezone).time();
+
+        for preset in &presets {
+            let times = parse_schedule(&preset.schedule).unwrap_or_default();
+
+            if !times.iter().any(|t| is_due(*t, last, now)) {
+                continue;
+            }
+
+            info!(target: Target::file_mail(), channel = id; "Scheduled text preset <yellow>{}</>", preset.name);
+
+            send_preset(&manager, preset).await;
+
+            if let Some(task) = clear.take() {
+                task.abort();
+            }
+
+            if preset.duration > 0.0 {
+                let manager = manager.clone();
+                let duration = Duration::from_secs_f64(preset.duration);
+
+                clear = Some(tokio::spawn(async move {
+                    sleep(duration).await;
+                    clear_text(&manager).await;
+                }));
+            }
+        }
+
+        last = now;
+    }
+
+    if let Some(task) = clear {
+        task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_secondary_events() {
+        let events: Vec<SecondaryEvent> = serde_json::from_str(
+            r#"[
+                {"type": "logo", "path": "logos/show.png"},
+                {"type": "text", "preset": "Next up", "offset": 60, "duration": 15},
+                {"type": "webhook", "url": "http://127.0.0.1:9000/start", "offset": 0.5}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            events[0].action,
+            SecondaryAction::Logo {
+                path: "logos/show.png".to_string()
+            }
+        );
+        assert_eq!(events[0].range(300.0), (0.0, 300.0));
+        assert_eq!(events[1].range(300.0), (60.0, 75.0));
+        assert_eq!(events[1].range(70.0), (60.0, 70.0));
+        assert_eq!(events[2].offset, 0.5);
+    }
+
+    #[test]
+    fn preset_schedule() {
+        let time = |t| NaiveTime::parse_from_str(t, "%H:%M:%S%.f").unwrap();
+        let times = parse_schedule("07:59:50; 19:59:50;00:00").unwrap();
+
+        assert_eq!(
+            times,
+            vec![time("07:59:50"), time("19:59:50"), time("00:00:00")]
+        );
+        assert!(parse_schedule("").unwrap().is_empty());
+        assert!(parse_schedule("25:00").is_err());
+
+        assert!(is_due(
+            time("19:59:50"),
+            time("19:59:49.6"),
+            time("19:59:50.1")
+        ));
+        assert!(!is_due(
+            time("19:59:50"),
+            time("19:59:50"),
+            time("19:59:50.4")
+        ));
+        // over midnight
+        assert!(is_due(
+            time("00:00:00"),
+            time("23:59:59.8"),
+            time("00:00:00.3")
+        ));
+        assert!(!is_due(
+            time("12:00:00"),
+            time("23:59:59.8"),
+            time("00:00:00.3")
+        ));
+    }
+}
@@ -0,0 +1,285 @@
+/// Simulate the schedule of one day, with real probing and filter building but without encoder.
+///
+/// The clock is frozen and moves forward by the length of every clip, so the report shows
+/// the starts, trims and fillers like the playout would have them.
+use std::sync::atomic::Ordering;
+
+use chrono::{NaiveDate, TimeDelta, Utc};
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use crate::player::{
+    controller::ChannelManager,
+    input::CurrentProgram,
+    utils::{day_start_time, playlist_length, Media},
+};
+use crate::utils::{
+    errors::ProcessError,
+    logging::Target,
+    time_machine::{freeze_time, local_timezone},
+};
+
+/// A clip needs to be shorter by more than one frame, to count as trimmed.
+const TRIM_TOLERANCE: f64 = 0.04;
+
+/// Protection against endless loops from broken playlists.
+const MAX_CLIPS: usize = 100_000;
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct SimulatedClip {
+    pub index: Option<usize>,
+    /// Begin from the playlist.
+    pub planned: Option<f64>,
+    /// Begin in the simulation.
+    pub start: f64,
+    pub seek: f64,
+    pub out: f64,
+    pub source: String,
+    pub category: String,
+    /// Filler or placeholder, instead of the playlist item.
+    pub filler: bool,
+    /// Seconds, which are cut from the planned length.
+    pub trimmed: f64,
+    pub skipped: bool,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct SimulationReport {
+    pub date: String,
+    /// Target length of the day.
+    pub length: f64,
+    pub played: f64,
+    pub fillers: usize,
+    pub filler_time: f64,
+    pub trims: usize,
+    pub skips: usize,
+    pub clips: Vec<SimulatedClip>,
+}
+
+impl SimulationReport {
+    fn push(&mut self, clip: SimulatedClip) {
+        let length = clip.out - clip.seek;
+
+        if clip.skipped {
+            self.skips += 1;
+        } else {
+            self.played += length;
+
+            if clip.filler {
+                self.fillers += 1;
+                self.filler_time += length;
+            }
+
+            if clip.trimmed > 0.0 {
+                self.trims += 1;
+            }
+        }
+
+        self.clips.push(clip);
+    }
+}
+
+/// Compare the clip, which would be played, with the item from the playlist.
+fn simulated_clip(node: &Media, planned: Option<&Media>, start: f64) -> SimulatedClip {
+    let (filler, trimmed) = match planned {
+        Some(item) if item.random || item.source == node.source => {
+            let cut = (item.out - item.seek) - (node.out - node.seek);
+
+            (false, if cut > TRIM_TOLERANCE { cut } else { 0.0 })
+        }
+        _ => (true, 0.0),
+    };
+
+    SimulatedClip {
+        index: node.index,
+        planned: planned.and_then(|p| p.begin),
+        start,
+        seek: node.seek,
+        out: node.out,
+        source: node.source.clone(),
+        category: node.category.clone(),
+        filler,
+        trimmed,
+        skipped: node.skip || node.cmd.is_none(),
+    }
+}
+
+/// Playout state of the channel, which the simulation changes.
+///
+/// It is restored, and the clock runs again, when the simulation ends, also on errors.
+struct SimulationGuard {
+    manager: ChannelManager,
+    is_alive: bool,
+    list_init: bool,
+    skip_validation: bool,
+    time_shift: f64,
+    last_date: Option<String>,
+}
+
+impl SimulationGuard {
+    async fn new(manager: &ChannelManager) -> Self {
+        let mut config = manager.config.lock().await;
+        let mut channel = manager.channel.lock().await;
+
+        Self {
+            manager: manager.clone(),
+            is_alive: manager.is_alive.swap(true, Ordering::SeqCst),
+            list_init: manager.list_init.swap(true, Ordering::SeqCst),
+            // validation runs in the background and would probe all clips a second time
+            skip_validation: std::mem::replace(&mut config.general.skip_validation, true),
+            time_shift: std::mem::take(&mut channel.time_shift),
+            last_date: channel.last_date.take(),
+        }
+    }
+}
+
+impl Drop for SimulationGuard {
+    fn drop(&mut self) {
+        freeze_time(None);
+
+        self.manager.is_alive.store(self.is_alive, Ordering::SeqCst);
+        self.manager
+            .list_init
+            .store(self.list_init, Ordering::SeqCst);
+
+        if let Ok(mut config) = self.manager.config.try_lock() {
+            config.general.skip_validation = self.skip_validation;
+        }
+
+        if let Ok(mut channel) = self.manager.channel.try_lock() {
+            channel.time_shift = self.time_shift;
+            channel.last_date = self.last_date.take();
+        }
+    }
+}
+
+/// Walk through the playlist of the given date, from day start until the target length is reached.
+///
+/// The simulation freezes the global clock and changes the playout state of the channel,
+/// so it must never run inside a live engine, it is only called from the `--simulate` command line.
+pub async fn simulate_day(
+    manager: ChannelManager,
+    date: &str,
+) -> Result<SimulationReport, ProcessError> {
+    let day = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|e| ProcessError::Custom(format!("Invalid date {date}: {e}")))?;
+
+    let _guard = SimulationGuard::new(&manager).await;
+    let config = manager.config.lock().await.clone();
+
+    let start_sec = config.playlist.start_sec.unwrap_or_default();
+    let begin = day_start_time(&local_timezone(&config.channel.timezone), day, start_sec)
+        .with_timezone(&Utc);
+
+    if manager.filler_list.lock().await.is_empty() {
+        manager
+            .storage
+            .lock()
+            .await
+            .fill_filler_list(&config, Some(manager.filler_list.clone()))
+            .await;
+    }
+
+    let mut report = SimulationReport {
+        date: date.to_string(),
+        length: playlist_length(&config, date),
+        ..Default::default()
+    };
+
+    freeze_time(Some(begin));
+
+    let mut program = CurrentProgram::new(manager.clone()).await.dry_run();
+
+    while report.played < report.length - TRIM_TOLERANCE && report.clips.len() < MAX_CLIPS {
+        let Some(node) = program.next().await else {
+            break;
+        };
+
+        let planned = match node.index {
+            Some(i) => manager.current_list.lock().await.get(i).cloned(),
+            None => None,
+        };
+        let clip = simulated_clip(&node, planned.as_ref(), start_sec + report.played);
+
+        debug!(target: Target::file_mail(), channel = config.general.channel_id; "Simulate <b><magenta>{}</></b> at <yellow>{:.3}</>", clip.source, clip.start);
+
+        report.push(clip);
+
+        freeze_time(Some(
+            begin + TimeDelta::milliseconds((report.played * 1000.0) as i64),
+        ));
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(not(feature = "mysql"))]
+    use crate::player::controller::test_manager;
+
+    fn media(source: &str, seek: f64, out: f64) -> Media {
+        Media {
+            index: Some(0),
+            begin: Some(0.0),
+            source: source.to_string(),
+            seek,
+            out,
+            duration: out,
+            cmd: Some(vec![]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn simulation_report() {
+        let mut report = SimulationReport::default();
+        let planned = media("clip.mp4", 0.0, 600.0);
+
+        report.push(simulated_clip(&planned, Some(&planned), 0.0));
+        report.push(simulated_clip(
+            &media("filler.mp4", 0.0, 600.0),
+            Some(&planned),
+            600.0,
+        ));
+        report.push(simulated_clip(
+            &media("clip.mp4", 0.0, 420.0),
+            Some(&planned),
+            1200.0,
+        ));
+
+        let mut skipped = media("clip.mp4", 0.0, 600.0);
+        skipped.skip = true;
+        report.push(simulated_clip(&skipped, Some(&planned), 1620.0));
+
+        assert_eq!(report.played, 1620.0);
+        assert_eq!((report.fillers, report.filler_time), (1, 600.0));
+        assert_eq!(report.trims, 1);
+        assert_eq!(report.clips[2].trimmed, 180.0);
+        assert_eq!(report.skips, 1);
+    }
+
+    #[cfg(not(feature = "mysql"))]
+    #[tokio::test]
+    async fn simulation_restores_state() {
+        let (_, manager) = test_manager("simulate", "").await;
+        manager.channel.lock().await.time_shift = 3600.0;
+        manager.channel.lock().await.last_date = Some("2024-03-31".to_string());
+
+        {
+            let _guard = SimulationGuard::new(&manager).await;
+
+            assert!(manager.is_alive.load(Ordering::SeqCst));
+            assert!(manager.config.lock().await.general.skip_validation);
+            assert_eq!(manager.channel.lock().await.time_shift, 0.0);
+        }
+
+        let channel = manager.channel.lock().await;
+
+        assert!(!manager.is_alive.load(Ordering::SeqCst));
+        assert!(!manager.config.lock().await.general.skip_validation);
+        assert_eq!(channel.time_shift, 3600.0);
+        assert_eq!(channel.last_date.as_deref(), Some("2024-03-31"));
+    }
+}
@@ -0,0 +1,221 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::atomic::Ordering,
+    time::Duration,
+};
+
+use log::*;
+use regex::Regex;
+use serde_json::Value;
+use tokio::{fs, time::sleep};
+
+use crate::player::controller::ChannelManager;
+use crate::utils::{config::PlayoutConfig, logging::Target};
+
+/// Separator between two entries of the crawl.
+const SEPARATOR: &str = "   +++   ";
+
+/// Shortest time between two feed requests.
+const MIN_INTERVAL: i64 = 10;
+
+/// Text file of the ticker, drawtext reloads it on every frame.
+pub fn text_file(config: &PlayoutConfig) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "ffplayout_ticker_{}.txt",
+        config.general.channel_id
+    ))
+}
+
+/// Drawtext fails without text file, create an empty line before the encoder starts.
+pub fn init(config: &PlayoutConfig) {
+    let path = text_file(config);
+
+    if !path.is_file() {
+        if let Err(e) = std::fs::write(&path, " ") {
+            error!(target: Target::file_mail(), channel = config.general.channel_id; "Create ticker file <b><magenta>{path:?}</></b> failed: {e}");
+        }
+    }
+}
+
+/// Fields of one entry, with the local names of RSS/Atom elements or the keys of JSON objects.
+type Entry = HashMap<String, String>;
+
+fn xml_entries(body: &str) -> Result<Vec<Entry>, String> {
+    let doc = roxmltree::Document::parse(body).map_err(|e| e.to_string())?;
+
+    Ok(doc
+        .descendants()
+        .filter(|n| n.is_element() && ["item", "entry"].contains(&n.tag_name().name()))
+        .map(|item| {
+            item.children()
+                .filter(roxmltree::Node::is_element)
+                .filter_map(|c| {
+                    let text: String = c
+                        .descendants()
+                        .filter(roxmltree::Node::is_text)
+                        .filter_map(|t| t.text())
+                        .collect();
+
+                    (!text.trim().is_empty()).then(|| (c.tag_name().name().to_lowercase(), text))
+                })
+                .collect()
+        })
+        .collect())
+}
+
+fn json_entries(body: &str) -> Result<Vec<Entry>, String> {
+    let value: Value = serde_json::from_str(body).map_err(|e| e.to_string())?;
+
+    // entries are the top level array or the first array of an object, like `items`
+    let items = match value {
+        Value::Array(items) => items,
+        Value::Object(map) => map
+            .into_iter()
+            .find_map(|(_, v)| match v {
+                Value::Array(items) => Some(items),
+                _ => None,
+            })
+            .unwrap_or_default(),
+        _ => vec![],
+    };
+
+    Ok(items
+        .into_iter()
+        .map(|item| match item {
+            Value::Object(map) => map
+                .into_iter()
+                .filter_map(|(k, v)| match v {
+                    Value::String(s) => Some((k, s)),
+                    Value::Number(n) => Some((k, n.to_string())),
+                    _ => None,
+                })
+                .collect(),
+            Value::String(s) => Entry::from([("title".to_string(), s)]),
+            _ => Entry::new(),
+        })
+        .collect())
+}
+
+/// Fill the template with the fields of the entry, unknown placeholders are removed.
+/// Markup and line breaks are stripped, the crawl is one line.
+fn format_entry(template: &str, entry: &Entry) -> String {
+    let placeholder = Regex::new(r"\{(\w+)\}").unwrap();
+    let markup = Regex::new(r"<[^>]*>").unwrap();
+
+    let text = placeholder.replace_all(template, |caps: &regex::Captures| {
+        entry
+            .get(&caps[1].to_lowercase())
+            .map(|v| markup.replace_all(v, " ").to_string())
+            .unwrap_or_default()
+    });
+
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Crawl text of a RSS, Atom or JSON feed.
+pub fn ticker_text(template: &str, body: &str) -> Result<String, String> {
+    let entries = if body.trim_start().starts_with('<') {
+        xml_entries(body)?
+    } else {
+        json_entries(body)?
+    };
+
+    let text = entries
+        .iter()
+        .map(|e| format_entry(template, e))
+        .filter(|t| !t.is_empty())
+        .collect::<Vec<_>>()
+        .join(SEPARATOR);
+
+    Ok(text)
+}
+
+async fn fetch(config: &PlayoutConfig) -> Result<String, String> {
+    let body = reqwest::get(config.text.ticker_url.trim())
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| e.to_string())?
+        .text()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    ticker_text(&config.text.ticker_template, &body)
+}
+
+/// Replace the text file in one step, so drawtext never reads a half written file.
+async fn write_text(path: &Path, text: &str) -> std::io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    // the crawl continues behind the last entry, an empty file breaks drawtext
+    let text = if text.is_empty() {
+        " ".to_string()
+    } else {
+        format!("{text}{SEPARATOR}")
+    };
+
+    fs::write(&tmp, text).await?;
+    fs::rename(&tmp, path).await
+}
+
+/// Poll the ticker feed and update the text file, when the entries change.
+pub async fn run(manager: ChannelManager, config: PlayoutConfig) {
+    let id = config.general.channel_id;
+    let path = text_file(&config);
+    let interval = Duration::from_secs(config.text.ticker_interval.max(MIN_INTERVAL) as u64);
+    let mut last_text = None;
+
+    while manager.is_alive.load(Ordering::SeqCst) {
+        match fetch(&config).await {
+            Ok(text) => {
+                if last_text.as_ref() != Some(&text) {
+                    debug!(target: Target::file_mail(), channel = id; "Update ticker with {} characters", text.chars().count());
+
+                    match write_text(&path, &text).await {
+                        Ok(()) => last_text = Some(text),
+                        Err(e) => {
+                            error!(target: Target::file_mail(), channel = id; "Write ticker file failed: {e}");
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                warn!(target: Target::file_mail(), channel = id; "Ticker feed is not readable: {e}");
+            }
+        }
+
+        sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticker_feeds() {
+        let rss = r#"<?xml version="1.0"?>
+            <rss version="2.0"><channel><title>News</title>
+                <item><title>First &amp; best</title><description><![CDATA[<p>Long
+                    text</p>]]></description></item>
+                <item><title>Second</title></item>
+            </channel></rss>"#;
+
+        assert_eq!(
+            ticker_text("{title}: {description}", rss).unwrap(),
+            "First & best: Long text   +++   Second:"
+        );
+
+        let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+            <entry><title>Atom entry</title></entry></feed>"#;
+
+        assert_eq!(ticker_text("{title}", atom).unwrap(), "Atom entry");
+
+        let json = r#"{"status": "ok", "items": [{"title": "Rain", "temp": 12}, "Sun"]}"#;
+
+        assert_eq!(
+            ticker_text("{title} {temp}", json).unwrap(),
+            "Rain 12   +++   Sun"
+        );
+        assert!(ticker_text("{title}", "no feed").is_err());
+    }
+}
@@ -17,7 +17,10 @@ use tokio::{
 };
 use tokio_stream::wrappers::ReceiverStream;
 
-use crate::player::{controller::ChannelManager, utils::get_data_map};
+use crate::player::{
+    controller::ChannelManager,
+    utils::{get_data_map, get_status_map},
+};
 use crate::sse::Endpoint;
 use crate::utils::system;
 
@@ -108,6 +111,12 @@ impl Broadcaster {
 
                     sender_result = client.sender.send(sse::Data::new(message).into()).await;
                 }
+                Endpoint::Status => {
+                    let status_map = get_status_map(&client.manager).await;
+                    let message = serde_json::to_string(&status_map).unwrap_or_default();
+
+                    sender_result = client.sender.send(sse::Data::new(message).into()).await;
+                }
                 Endpoint::System => {
                     let config = client.manager.config.lock().await.clone();
 
@@ -18,6 +18,7 @@ pub mod routes;
 #[serde(rename_all = "snake_case")]
 pub enum Endpoint {
     Playout,
+    Status,
     #[default]
     System,
 }
@@ -28,6 +29,7 @@ impl FromStr for Endpoint {
     fn from_str(input: &str) -> Result<Self, Self::Err> {
         match input {
             "playout" => Ok(Self::Playout),
+            "status" => Ok(Self::Status),
             "system" => Ok(Self::System),
             _ => Err("Missing endpoint".to_string()),
         }
@@ -38,32 +40,30 @@ impl fmt::Display for Endpoint {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             Self::Playout => write!(f, "playout"),
+            Self::Status => write!(f, "status"),
             Self::System => write!(f, "system"),
         }
     }
 }
 
-#[derive(Debug, Eq, Hash, PartialEq, Clone, Copy)]
+#[derive(Debug, Eq, Hash, PartialEq, Clone)]
 pub struct UuidData {
     pub uuid: Uuid,
     pub expiration: SystemTime,
+    /// Channels of the user, `None` for a global admin.
+    pub channels: Option<Vec<i32>>,
 }
 
 impl UuidData {
-    pub fn new() -> Self {
+    pub fn new(channels: Option<Vec<i32>>) -> Self {
         Self {
             uuid: Uuid::new_v4(),
             expiration: SystemTime::now() + Duration::from_secs(2 * 3600), // 2 hours
+            channels,
         }
     }
 }
 
-impl Default for UuidData {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 pub struct SseAuthState {
     pub uuids: Mutex<HashSet<UuidData>>,
 }
@@ -85,3 +85,47 @@ pub fn check_uuid(uuids: &mut HashSet<UuidData>, uuid: &str) -> Result<&'static
         )),
     }
 }
+
+/// Check the UUID and if its user has access to the channel.
+pub fn check_channel(
+    uuids: &mut HashSet<UuidData>,
+    uuid: &str,
+    channel: i32,
+) -> Result<(), ServiceError> {
+    check_uuid(uuids, uuid)?;
+
+    let client_uuid = Uuid::parse_str(uuid)?;
+
+    if uuids.iter().any(|entry| {
+        entry.uuid == client_uuid && entry.channels.as_ref().is_none_or(|c| c.contains(&channel))
+    }) {
+        Ok(())
+    } else {
+        Err(ServiceError::Forbidden(
+            "No access to this channel".to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uuid_channel_access() {
+        let user = UuidData::new(Some(vec![2]));
+        let admin = UuidData::new(None);
+        let mut uuids = HashSet::from([user.clone(), admin.clone()]);
+
+        assert!(check_channel(&mut uuids, &user.uuid.to_string(), 2).is_ok());
+        assert!(matches!(
+            check_channel(&mut uuids, &user.uuid.to_string(), 1),
+            Err(ServiceError::Forbidden(_))
+        ));
+        assert!(check_channel(&mut uuids, &admin.uuid.to_string(), 1).is_ok());
+        assert!(matches!(
+            check_channel(&mut uuids, &Uuid::new_v4().to_string(), 2),
+            Err(ServiceError::Unauthorized(_))
+        ));
+    }
+}
@@ -1,11 +1,17 @@
-use actix_web::{get, post, web, Responder};
-use actix_web_grants::proc_macro::protect;
+use std::sync::atomic::Ordering;
+
+use actix_web::{delete, get, http::header, post, web, HttpRequest, HttpResponse, Responder};
+use actix_web_grants::{authorities::AuthDetails, proc_macro::protect};
+use futures_util::stream;
 use serde::{Deserialize, Serialize};
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast::error::RecvError, Mutex};
 
-use super::{check_uuid, prune_uuids, SseAuthState, UuidData};
-use crate::db::models::Role;
-use crate::player::controller::ChannelController;
+use super::{check_channel, check_uuid, prune_uuids, SseAuthState, UuidData};
+use crate::db::models::{Role, UserMeta};
+use crate::player::{
+    controller::ChannelController,
+    output::{preview::Preview, whep},
+};
 use crate::sse::{broadcast::Broadcaster, Endpoint};
 use crate::utils::errors::ServiceError;
 
@@ -24,6 +30,8 @@ impl User {
 
 /// **Get generated UUID**
 ///
+/// The UUID gives access to the channels of the user.
+///
 /// ```BASH
 /// curl -X GET 'http://127.0.0.1:8787/api/generate-uuid' -H 'Authorization: Bearer <TOKEN>'
 /// ```
@@ -32,9 +40,14 @@ impl User {
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role"
 )]
-async fn generate_uuid(data: web::Data<SseAuthState>) -> Result<impl Responder, ServiceError> {
+async fn generate_uuid(
+    data: web::Data<SseAuthState>,
+    role: AuthDetails<Role>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
     let mut uuids = data.uuids.lock().await;
-    let new_uuid = UuidData::new();
+    let channels = (!role.has_authority(&Role::GlobalAdmin)).then(|| user.channels.clone());
+    let new_uuid = UuidData::new(channels);
     let user_auth = User::new(Endpoint::default(), new_uuid.uuid.to_string());
 
     prune_uuids(&mut uuids);
@@ -64,6 +77,10 @@ async fn validate_uuid(
 
 /// **Connect to event handler**
 ///
+/// Endpoints are `playout`, `status` and `system`, the data is sent every second.
+/// `status` has also the remaining time, the next item and the health of the encoder,
+/// and is sent when the channel is stopped.
+///
 /// ```BASH
 /// curl -X GET 'http://127.0.0.1:8787/data/event/1?endpoint=system&uuid=f2f8c29b-712a-48c5-8919-b535d3a05a3a'
 /// ```
@@ -75,9 +92,7 @@ async fn event_stream(
     user: web::Query<User>,
     controllers: web::Data<Mutex<ChannelController>>,
 ) -> Result<impl Responder, ServiceError> {
-    let mut uuids = data.uuids.lock().await;
-
-    check_uuid(&mut uuids, user.uuid.as_str())?;
+    check_channel(&mut *data.uuids.lock().await, user.uuid.as_str(), *id)?;
 
     let manager = controllers
         .lock()
@@ -85,8 +100,168 @@ async fn event_stream(
         .get(*id)
         .await
         .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
-    
+
     Ok(broadcaster
         .new_client(manager.clone(), user.endpoint.clone())
         .await)
 }
+
+/// **Low latency preview**
+///
+/// MPEG-TS stream of the program, for players like mpegts.js. For WebRTC, see [`whep_play`].
+///
+/// ```BASH
+/// curl -X GET 'http://127.0.0.1:8787/data/preview/1?uuid=f2f8c29b-712a-48c5-8919-b535d3a05a3a' -o preview.ts
+/// ```
+#[get("/preview/{id}")]
+async fn preview_stream(
+    data: web::Data<SseAuthState>,
+    id: web::Path<i32>,
+    user: web::Query<User>,
+    controllers: web::Data<Mutex<ChannelController>>,
+) -> Result<impl Responder, ServiceError> {
+    check_channel(&mut *data.uuids.lock().await, user.uuid.as_str(), *id)?;
+
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+
+    if !manager.preview.running.load(Ordering::SeqCst) {
+        return Err(ServiceError::ServiceUnavailable(
+            "Preview is not running".to_string(),
+        ));
+    }
+
+    Ok(stream_preview(&manager.preview))
+}
+
+/// **Ingest monitor**
+///
+/// MPEG-TS stream of the incoming live ingest in preview size, also when it is not on air,
+/// like during the delay or a scheduled live event.
+///
+/// ```BASH
+/// curl -X GET 'http://127.0.0.1:8787/data/preview/1/ingest?uuid=f2f8c29b-712a-48c5-8919-b535d3a05a3a' -o ingest.ts
+/// ```
+#[get("/preview/{id}/ingest")]
+async fn ingest_preview_stream(
+    data: web::Data<SseAuthState>,
+    id: web::Path<i32>,
+    user: web::Query<User>,
+    controllers: web::Data<Mutex<ChannelController>>,
+) -> Result<impl Responder, ServiceError> {
+    check_channel(&mut *data.uuids.lock().await, user.uuid.as_str(), *id)?;
+
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+
+    if !manager.ingest_preview.running.load(Ordering::SeqCst) {
+        return Err(ServiceError::ServiceUnavailable(
+            "No ingest stream is arriving".to_string(),
+        ));
+    }
+
+    Ok(stream_preview(&manager.ingest_preview))
+}
+
+/// **WebRTC preview**
+///
+/// WHEP endpoint for the low latency preview, when a WHEP URL of the media server is set.
+/// The offer is passed to the media server, the response has the answer and the location of the session.
+///
+/// ```BASH
+/// curl -X POST 'http://127.0.0.1:8787/data/preview/1/whep?uuid=f2f8c29b-712a-48c5-8919-b535d3a05a3a' \
+/// -H 'Content-Type: application/sdp' --data-binary @offer.sdp
+/// ```
+#[post("/preview/{id}/whep")]
+async fn whep_play(
+    req: HttpRequest,
+    data: web::Data<SseAuthState>,
+    id: web::Path<i32>,
+    user: web::Query<User>,
+    offer: String,
+    controllers: web::Data<Mutex<ChannelController>>,
+) -> Result<impl Responder, ServiceError> {
+    check_channel(&mut *data.uuids.lock().await, user.uuid.as_str(), *id)?;
+
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+
+    if !req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|h| h.starts_with("application/sdp"))
+    {
+        return Err(ServiceError::BadRequest(
+            "Offer must be application/sdp".to_string(),
+        ));
+    }
+
+    let (answer, session) = whep::play(&manager, offer).await?;
+
+    Ok(HttpResponse::Created()
+        .content_type("application/sdp")
+        .insert_header((
+            header::LOCATION,
+            format!("/data/preview/{id}/whep/{session}?uuid={}", user.uuid),
+        ))
+        .body(answer))
+}
+
+/// **End WebRTC preview**
+///
+/// ```BASH
+/// curl -X DELETE 'http://127.0.0.1:8787/data/preview/1/whep/<SESSION>?uuid=f2f8c29b-712a-48c5-8919-b535d3a05a3a'
+/// ```
+#[delete("/preview/{id}/whep/{session}")]
+async fn whep_stop(
+    data: web::Data<SseAuthState>,
+    path: web::Path<(i32, String)>,
+    user: web::Query<User>,
+    controllers: web::Data<Mutex<ChannelController>>,
+) -> Result<impl Responder, ServiceError> {
+    let (id, session) = path.into_inner();
+
+    check_channel(&mut *data.uuids.lock().await, user.uuid.as_str(), id)?;
+
+    let manager = controllers
+        .lock()
+        .await
+        .get(id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+
+    whep::stop(&manager, &session).await?;
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+fn stream_preview(preview: &Preview) -> HttpResponse {
+    let chunks = stream::unfold(preview.subscribe(), |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(chunk) => return Some((Ok::<_, actix_web::Error>(chunk), rx)),
+                // a slow viewer skips chunks, the stream stays packet aligned
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    HttpResponse::Ok()
+        .content_type("video/mp2t")
+        .insert_header(header::CacheControl(vec![header::CacheDirective::NoCache]))
+        .streaming(chunks)
+}
@@ -3,11 +3,10 @@ use std::path::Path;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, NoneAsEmptyString};
 use shlex::split;
-use sqlx::{Pool, Sqlite};
 use tokio::io::AsyncReadExt;
 use ts_rs::TS;
 
-use crate::db::{handles, models::AdvancedConfiguration};
+use crate::db::{handles, models::AdvancedConfiguration, DbPool};
 use crate::utils::ServiceError;
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone, TS)]
@@ -189,7 +188,7 @@ impl AdvancedConfig {
         }
     }
 
-    pub async fn dump(pool: &Pool<Sqlite>, id: i32) -> Result<(), ServiceError> {
+    pub async fn dump(pool: &DbPool, id: i32) -> Result<(), ServiceError> {
         for conf in handles::select_related_advanced_configuration(pool, id).await? {
             let config = Self::new(conf);
             let f_keys = [
@@ -294,7 +293,7 @@ impl AdvancedConfig {
         Ok(())
     }
 
-    pub async fn import(pool: &Pool<Sqlite>, id: i32, path: &Path) -> Result<(), ServiceError> {
+    pub async fn import(pool: &DbPool, id: i32, path: &Path) -> Result<(), ServiceError> {
         if path.is_file() {
             let mut file = tokio::fs::File::open(path).await?;
             let mut contents = String::new();
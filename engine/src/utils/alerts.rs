@@ -0,0 +1,405 @@
+/// Alert rules, which check the state of the channel every minute.
+///
+/// An alert is sent once, when the condition of the rule becomes true, and again only after
+/// the condition was false in between. The alert goes to the mail recipient and the notifiers
+/// of the channel, or to the targets of the rule.
+use std::{collections::HashSet, collections::VecDeque, fmt, str::FromStr, sync::atomic::Ordering};
+
+use chrono::{NaiveTime, TimeDelta};
+use log::*;
+use tokio::time::{interval, Duration, Instant};
+
+use crate::db::{handles, models::AlertRule, DbPool};
+use crate::player::{
+    controller::ChannelManager,
+    utils::{
+        is_remote,
+        json_serializer::{
+            date_playlist_path, db_playlist_date, db_playlist_revision, remote_request,
+        },
+        playlist_clock, Media,
+    },
+};
+use crate::utils::{
+    config::{PlayoutConfig, ProcessMode::Playlist},
+    logging::Target,
+    mail::send_mail,
+    notifier::{send_notification, NotifierKind},
+    time_machine::time_now,
+};
+
+const ALERT_POLL: Duration = Duration::from_secs(60);
+
+/// Played items and encoder restarts are kept one day, longer windows are not possible.
+const HISTORY_LENGTH: Duration = Duration::from_secs(86400);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertCondition {
+    FillerRatio,
+    PlaylistMissing,
+    EncoderRestarts,
+}
+
+impl FromStr for AlertCondition {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.trim() {
+            "filler_ratio" => Ok(Self::FillerRatio),
+            "playlist_missing" => Ok(Self::PlaylistMissing),
+            "encoder_restarts" => Ok(Self::EncoderRestarts),
+            _ => Err(format!("Unknown alert condition: {input}")),
+        }
+    }
+}
+
+impl fmt::Display for AlertCondition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::FillerRatio => write!(f, "filler_ratio"),
+            Self::PlaylistMissing => write!(f, "playlist_missing"),
+            Self::EncoderRestarts => write!(f, "encoder_restarts"),
+        }
+    }
+}
+
+fn severity_level(severity: &str) -> Result<Level, String> {
+    match severity.trim() {
+        "info" => Ok(Level::Info),
+        "warning" => Ok(Level::Warn),
+        "error" => Ok(Level::Error),
+        _ => Err(format!("Unknown alert severity: {severity}")),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AlertTarget {
+    Mail,
+    Notifier(NotifierKind),
+}
+
+/// Targets of the rule, separated by comma. Empty means all.
+fn parse_targets(targets: &str) -> Result<Vec<AlertTarget>, String> {
+    targets
+        .split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(|t| match t {
+            "mail" => Ok(AlertTarget::Mail),
+            _ => NotifierKind::from_str(t).map(AlertTarget::Notifier),
+        })
+        .collect()
+}
+
+fn rule_time(rule: &AlertRule) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(rule.time.trim(), "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(rule.time.trim(), "%H:%M"))
+        .map_err(|_| format!("Invalid alert time: {}", rule.time))
+}
+
+/// Check that the rule has valid values for its condition.
+pub fn validate(rule: &AlertRule) -> Result<AlertCondition, String> {
+    let condition = AlertCondition::from_str(&rule.condition)?;
+
+    severity_level(&rule.severity)?;
+    parse_targets(&rule.targets)?;
+
+    match condition {
+        AlertCondition::PlaylistMissing => {
+            rule_time(rule)?;
+        }
+        _ => {
+            if rule.minutes < 1 || rule.minutes > HISTORY_LENGTH.as_secs() as i64 / 60 {
+                return Err("Alert minutes must be between 1 and 1440".to_string());
+            }
+
+            if rule.threshold <= 0.0 {
+                return Err("Alert threshold must be greater than 0".to_string());
+            }
+        }
+    }
+
+    Ok(condition)
+}
+
+/// Played items and encoder restarts of the channel, for the alert rules.
+#[derive(Debug, Default)]
+pub struct AlertHistory {
+    /// Start, length and filler state of the items.
+    items: VecDeque<(Instant, f64, bool)>,
+    restarts: VecDeque<Instant>,
+}
+
+impl AlertHistory {
+    pub fn item(&mut self, node: &Media) {
+        self.push_item(Instant::now(), node.out - node.seek, node.filler);
+    }
+
+    pub fn restart(&mut self) {
+        self.push_restart(Instant::now());
+    }
+
+    fn push_item(&mut self, time: Instant, length: f64, filler: bool) {
+        self.prune(time);
+        self.items.push_back((time, length, filler));
+    }
+
+    fn push_restart(&mut self, time: Instant) {
+        self.prune(time);
+        self.restarts.push_back(time);
+    }
+
+    fn prune(&mut self, now: Instant) {
+        while self
+            .items
+            .front()
+            .is_some_and(|(t, _, _)| now.duration_since(*t) > HISTORY_LENGTH)
+        {
+            self.items.pop_front();
+        }
+
+        while self
+            .restarts
+            .front()
+            .is_some_and(|t| now.duration_since(*t) > HISTORY_LENGTH)
+        {
+            self.restarts.pop_front();
+        }
+    }
+
+    /// Percent of the filler time, from the items which started in the window.
+    fn filler_ratio(&self, now: Instant, window: Duration) -> Option<f64> {
+        let (total, filler) = self
+            .items
+            .iter()
+            .filter(|(t, _, _)| now.duration_since(*t) <= window)
+            .fold((0.0, 0.0), |(total, filler), (_, length, is_filler)| {
+                (
+                    total + length,
+                    if *is_filler { filler + length } else { filler },
+                )
+            });
+
+        (total > 0.0).then(|| filler / total * 100.0)
+    }
+
+    fn restarts(&self, now: Instant, window: Duration) -> usize {
+        self.restarts
+            .iter()
+            .filter(|t| now.duration_since(**t) <= window)
+            .count()
+    }
+}
+
+/// The playlist of the next day exists, local, remote or in the database.
+async fn next_playlist_exists(conn: &DbPool, config: &PlayoutConfig) -> bool {
+    let start_sec = config.playlist.start_sec.unwrap_or_default();
+    let date = (playlist_clock(&config.channel.timezone, start_sec).0 + TimeDelta::days(1))
+        .format("%Y-%m-%d")
+        .to_string();
+    let path = date_playlist_path(config, &date)
+        .to_string_lossy()
+        .to_string();
+
+    if db_playlist_date(&path).is_some() {
+        return db_playlist_revision(conn, config.general.channel_id, &path)
+            .await
+            .is_some();
+    }
+
+    if is_remote(&path) {
+        return remote_request(config, &path)
+            .send()
+            .await
+            .is_ok_and(|r| r.status().is_success());
+    }
+
+    tokio::fs::metadata(&path).await.is_ok_and(|m| m.is_file())
+}
+
+/// Check the condition of the rule, gives the text of the alert when it is true.
+async fn check(
+    manager: &ChannelManager,
+    config: &PlayoutConfig,
+    rule: &AlertRule,
+    condition: AlertCondition,
+) -> Option<String> {
+    let window = Duration::from_secs(rule.minutes.max(1) as u64 * 60);
+    let now = Instant::now();
+
+    match condition {
+        AlertCondition::FillerRatio => manager
+            .alert_history
+            .lock()
+            .await
+            .filler_ratio(now, window)
+            .filter(|ratio| *ratio > rule.threshold)
+            .map(|ratio| {
+                format!(
+                    "Filler ratio is {ratio:.0}% in the last {} minutes",
+                    rule.minutes
+                )
+            }),
+        AlertCondition::EncoderRestarts => {
+            let restarts = manager.alert_history.lock().await.restarts(now, window);
+
+            (restarts as f64 >= rule.threshold).then(|| {
+                format!(
+                    "Encoder restarted {restarts} times in the last {} minutes",
+                    rule.minutes
+                )
+            })
+        }
+        AlertCondition::PlaylistMissing => {
+            let time = rule_time(rule).ok()?;
+
+            if config.processing.mode != Playlist
+                || time_now(&config.channel.timezone).time() < time
+                || next_playlist_exists(&manager.db_pool, config).await
+            {
+                return None;
+            }
+
+            Some("No playlist for the next day".to_string())
+        }
+    }
+}
+
+/// Send the alert to the targets of the rule.
+async fn deliver(config: &PlayoutConfig, rule: &AlertRule, text: &str) {
+    let id = config.general.channel_id;
+    let targets = parse_targets(&rule.targets).unwrap_or_default();
+    let to = |target: AlertTarget| targets.is_empty() || targets.contains(&target);
+    let subject = format!("{} - {}", config.mail.subject, rule.name);
+    let message = format!("[{}] {text}", rule.severity.to_uppercase());
+
+    for notifier in config.mail.notifiers.iter().filter(|n| n.active) {
+        if !NotifierKind::from_str(&notifier.kind).is_ok_and(|k| to(AlertTarget::Notifier(k))) {
+            continue;
+        }
+
+        if let Err(e) = send_notification(notifier, &subject, &message).await {
+            error!(target: Target::file(), channel = id; "Failed to send {} alert: {e}", notifier.kind);
+        }
+    }
+
+    if to(AlertTarget::Mail) && config.mail.recipient.contains('@') {
+        let mut mail = config.mail.clone();
+        mail.subject = subject;
+
+        if let Err(e) = send_mail(&mail, message).await {
+            error!(target: Target::file(), channel = id; "Failed to send alert mail: {e}");
+        }
+    }
+}
+
+/// Check the alert rules of the channel, as long as the playout runs.
+pub async fn alert_scheduler(manager: ChannelManager) {
+    let id = manager.id;
+    let mut firing = HashSet::new();
+    let mut tick = interval(ALERT_POLL);
+
+    while manager.is_alive.load(Ordering::SeqCst) {
+        tick.tick().await;
+
+        let rules = match handles::select_alert_rules(&manager.db_pool, id).await {
+            Ok(rules) => rules,
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = id; "Read alert rules failed: {e}");
+                continue;
+            }
+        };
+        let config = manager.config.lock().await.clone();
+
+        firing.retain(|rule_id| rules.iter().any(|r| r.id == *rule_id && r.active));
+
+        for rule in rules.iter().filter(|r| r.active) {
+            let (Ok(condition), Ok(level)) = (
+                AlertCondition::from_str(&rule.condition),
+                severity_level(&rule.severity),
+            ) else {
+                continue;
+            };
+
+            match check(&manager, &config, rule, condition).await {
+                Some(text) if firing.insert(rule.id) => {
+                    log!(target: Target::file(), level, channel = id; "Alert <yellow>{}</>: {text}", rule.name);
+
+                    deliver(&config, rule, &text).await;
+                }
+                Some(_) => {}
+                None => {
+                    firing.remove(&rule.id);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alert_history() {
+        let start = Instant::now();
+        let hour = Duration::from_secs(3600);
+        let mut history = AlertHistory::default();
+
+        assert_eq!(history.filler_ratio(start, hour), None);
+
+        history.push_item(start, 600.0, false);
+        history.push_item(start + Duration::from_secs(600), 200.0, true);
+        history.push_item(start + Duration::from_secs(800), 200.0, false);
+
+        let now = start + Duration::from_secs(1000);
+
+        assert_eq!(history.filler_ratio(now, hour), Some(20.0));
+        assert_eq!(
+            history.filler_ratio(now, Duration::from_secs(500)),
+            Some(50.0)
+        );
+
+        history.push_restart(start);
+        history.push_restart(now);
+
+        assert_eq!(history.restarts(now, hour), 2);
+        assert_eq!(history.restarts(now, Duration::from_secs(60)), 1);
+
+        history.push_restart(start + HISTORY_LENGTH + Duration::from_secs(10));
+
+        assert_eq!(history.items.len(), 2);
+        assert_eq!(history.restarts.len(), 2);
+    }
+
+    #[test]
+    fn alert_rule_validation() {
+        let mut rule = AlertRule {
+            name: "Filler".to_string(),
+            condition: "filler_ratio".to_string(),
+            threshold: 20.0,
+            minutes: 60,
+            severity: "warning".to_string(),
+            targets: "mail, telegram".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(validate(&rule), Ok(AlertCondition::FillerRatio));
+
+        rule.targets = "pager".to_string();
+        assert!(validate(&rule).is_err());
+
+        rule.targets.clear();
+        rule.minutes = 0;
+        assert!(validate(&rule).is_err());
+
+        rule.condition = "playlist_missing".to_string();
+        assert!(validate(&rule).is_err());
+
+        rule.time = "18:00".to_string();
+        assert_eq!(validate(&rule), Ok(AlertCondition::PlaylistMissing));
+
+        rule.severity = "critical".to_string();
+        assert!(validate(&rule).is_err());
+    }
+}
@@ -1,10 +1,9 @@
 use std::sync::Arc;
 
 use log::*;
-use sqlx::{Pool, Sqlite};
 use tokio::sync::Mutex;
 
-use crate::db::{handles, models::Channel};
+use crate::db::{handles, models::Channel, DbPool};
 use crate::player::controller::{ChannelController, ChannelManager};
 use crate::utils::{
     advanced_config::{AdvancedConfig, DecoderConfig, FilterConfig, IngestConfig},
@@ -13,7 +12,7 @@ use crate::utils::{
     mail::MailQueue,
 };
 
-async fn map_global_admins(conn: &Pool<Sqlite>) -> Result<(), ServiceError> {
+async fn map_global_admins(conn: &DbPool) -> Result<(), ServiceError> {
     let channels = handles::select_related_channels(conn, None).await?;
     let admins = handles::select_global_admins(conn).await?;
 
@@ -51,7 +50,7 @@ const QSV_FILTER_OVERLAY: &str = "overlay_qsv={}:shortest=1";
 const OUTPUT_PARM: &str = "-c:v libx264 -crf 23 -x264-params keyint=50:min-keyint=25:scenecut=-1 -maxrate 1300k -bufsize 2600k -preset faster -tune zerolatency -profile:v Main -level 3.1 -c:a aac -ar 44100 -b:a 128k -flags +cgop -f hls -hls_time 6 -hls_list_size 600 -hls_flags append_list+delete_segments+omit_endlist -hls_segment_filename live/stream-%d.ts live/stream.m3u8";
 
 pub async fn create_channel(
-    conn: &Pool<Sqlite>,
+    conn: &DbPool,
     controllers: Arc<Mutex<ChannelController>>,
     queue: Arc<Mutex<Vec<Arc<Mutex<MailQueue>>>>>,
     target_channel: Channel,
@@ -143,7 +142,7 @@ pub async fn create_channel(
 }
 
 pub async fn delete_channel(
-    conn: &Pool<Sqlite>,
+    conn: &DbPool,
     id: i32,
     controllers: Arc<Mutex<ChannelController>>,
     queue: Arc<Mutex<Vec<Arc<Mutex<MailQueue>>>>>,
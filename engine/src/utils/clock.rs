@@ -0,0 +1,267 @@
+/// Clock Hours
+///
+/// A clock describes one hour of programming, like a program block from 00:00,
+/// a promo at 00:25 and fillers from 00:28. The generator repeats it over the full day,
+/// with category quotas per hour and separation rules for clips and categories.
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+
+use chrono::Timelike;
+use rand::Rng;
+
+use crate::file::{utils::filler::FillerPool, StorageBackend};
+use crate::player::utils::{sum_durations, Media};
+use crate::utils::{
+    config::{ClockSlot, PlayoutConfig, Separation, Template},
+    generator::{fill_gap, template_media},
+};
+
+#[derive(Clone, Debug)]
+struct SlotPool {
+    /// Seconds from the begin of the hour.
+    offset: f64,
+    length: f64,
+    shuffle: bool,
+    media: Vec<Media>,
+    cursor: usize,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ClockPlanner {
+    slots: Vec<SlotPool>,
+    quotas: BTreeMap<String, usize>,
+    separation: Separation,
+    /// Last planned begin per clip and per category, over all generated days.
+    last_source: HashMap<String, f64>,
+    last_category: HashMap<String, f64>,
+    /// Time of all days, which are planned before.
+    elapsed: f64,
+}
+
+fn slot_seconds(time: chrono::NaiveTime) -> f64 {
+    f64::from(time.hour() * 3600 + time.minute() * 60 + time.second())
+}
+
+/// Category of a clip: from the slot, or the name of the parent folder.
+fn clip_category(slot: &ClockSlot, source: &str) -> String {
+    if !slot.category.is_empty() {
+        return slot.category.clone();
+    }
+
+    Path::new(source)
+        .parent()
+        .and_then(|p| p.file_name())
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+impl ClockPlanner {
+    pub async fn new(
+        config: &PlayoutConfig,
+        storage: &StorageBackend,
+        template: &Template,
+    ) -> Self {
+        let mut slots = vec![];
+
+        for slot in &template.clock {
+            let mut media = template_media(config, storage, &slot.paths, slot.shuffle).await;
+
+            media.retain(|m| m.duration > 0.0);
+
+            for item in &mut media {
+                item.category = clip_category(slot, &item.source);
+            }
+
+            slots.push(SlotPool {
+                offset: slot_seconds(slot.start) % 3600.0,
+                length: slot_seconds(slot.duration),
+                shuffle: slot.shuffle,
+                media,
+                cursor: 0,
+            });
+        }
+
+        Self::from_slots(slots, template)
+    }
+
+    fn from_slots(mut slots: Vec<SlotPool>, template: &Template) -> Self {
+        slots.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+
+        Self {
+            slots,
+            quotas: template.quotas.clone(),
+            separation: template.separation.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Find the next clip from the slot, which fits in the remaining time
+    /// and respects quotas and separation.
+    fn pick(
+        &mut self,
+        slot: usize,
+        time: f64,
+        remaining: f64,
+        counts: &HashMap<String, usize>,
+    ) -> Option<Media> {
+        let pool = &self.slots[slot];
+        let len = pool.media.len();
+
+        if len == 0 {
+            return None;
+        }
+
+        let begin = if pool.shuffle {
+            rand::rng().random_range(0..len)
+        } else {
+            pool.cursor
+        };
+
+        for n in 0..len {
+            let index = (begin + n) % len;
+            let item = &pool.media[index];
+
+            if item.duration > remaining
+                || self
+                    .quotas
+                    .get(&item.category)
+                    .is_some_and(|max| counts.get(&item.category).unwrap_or(&0) >= max)
+                || self
+                    .last_source
+                    .get(&item.source)
+                    .is_some_and(|last| time - last < self.separation.source)
+                || (!item.category.is_empty()
+                    && self
+                        .last_category
+                        .get(&item.category)
+                        .is_some_and(|last| time - last < self.separation.category))
+            {
+                continue;
+            }
+
+            let item = item.clone();
+            self.slots[slot].cursor = index + 1;
+            self.last_source.insert(item.source.clone(), time);
+            self.last_category.insert(item.category.clone(), time);
+
+            return Some(item);
+        }
+
+        None
+    }
+
+    /// Plan one day with the given length. Slots, which can not be filled with clips,
+    /// get filler clips for the rest of their time.
+    pub fn plan(&mut self, total_length: f64, fillers: &mut FillerPool) -> Vec<Media> {
+        let mut program = vec![];
+        let mut time = 0.0;
+        let hours = (total_length / 3600.0).ceil() as usize;
+
+        let mut fill = |program: &mut Vec<Media>, time: &mut f64, until: f64| {
+            if until > *time {
+                let mut gap = fill_gap(fillers, until - *time);
+                *time += sum_durations(&gap);
+                program.append(&mut gap);
+            }
+        };
+
+        for hour in 0..hours {
+            let hour_begin = hour as f64 * 3600.0;
+            let mut counts = HashMap::new();
+
+            for slot in 0..self.slots.len() {
+                let slot_begin = hour_begin + self.slots[slot].offset;
+                let slot_end = (slot_begin + self.slots[slot].length).min(total_length);
+
+                if slot_begin >= total_length {
+                    break;
+                }
+
+                fill(&mut program, &mut time, slot_begin);
+
+                while let Some(item) =
+                    self.pick(slot, self.elapsed + time, slot_end - time, &counts)
+                {
+                    *counts.entry(item.category.clone()).or_insert(0) += 1;
+                    time += item.duration;
+                    program.push(item);
+                }
+
+                fill(&mut program, &mut time, slot_end);
+            }
+        }
+
+        fill(&mut program, &mut time, total_length);
+
+        self.elapsed += total_length;
+
+        program
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip(source: &str, duration: f64) -> Media {
+        Media {
+            source: source.to_string(),
+            category: Path::new(source)
+                .parent()
+                .unwrap()
+                .to_string_lossy()
+                .to_string(),
+            out: duration,
+            duration,
+            ..Default::default()
+        }
+    }
+
+    fn slot(offset: f64, length: f64, media: Vec<Media>) -> SlotPool {
+        SlotPool {
+            offset,
+            length,
+            shuffle: false,
+            media,
+            cursor: 0,
+        }
+    }
+
+    #[test]
+    fn plan_clock_hours() {
+        let template = Template {
+            quotas: BTreeMap::from([("news".to_string(), 1)]),
+            separation: Separation {
+                source: 3600.0,
+                category: 0.0,
+            },
+            ..Default::default()
+        };
+        let mut planner = ClockPlanner::from_slots(
+            vec![
+                slot(1500.0, 180.0, vec![clip("promo/a.mp4", 60.0)]),
+                slot(
+                    0.0,
+                    1500.0,
+                    vec![
+                        clip("news/a.mp4", 600.0),
+                        clip("news/b.mp4", 600.0),
+                        clip("show/a.mp4", 900.0),
+                    ],
+                ),
+            ],
+            &template,
+        );
+
+        let program = planner.plan(7200.0, &mut FillerPool::new());
+        let sources: Vec<&str> = program.iter().map(|m| m.source.as_str()).collect();
+
+        // one news per hour, the promo is not repeated in the second hour
+        assert_eq!(
+            sources,
+            vec!["news/a.mp4", "show/a.mp4", "promo/a.mp4", "news/b.mp4"]
+        );
+    }
+}
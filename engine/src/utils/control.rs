@@ -3,30 +3,27 @@ use std::{error::Error, fmt, str::FromStr, sync::atomic::Ordering};
 use log::*;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Map, Value};
-use sqlx::{Pool, Sqlite};
 use zeromq::{Socket, SocketRecv, SocketSend, ZmqMessage};
 
-use crate::db::handles;
+use crate::db::{handles, DbPool};
 use crate::player::{
     controller::{ChannelManager, ProcessUnit::*},
-    utils::{get_delta, get_media_map},
+    utils::{get_delta, get_media_map, seek_and_length, Media},
+};
+use crate::utils::{
+    config::{OutputMode::*, ProcessMode, IMAGE_FORMAT},
+    errors::ServiceError,
+    logging::Target,
+    time_machine::time_now,
+    TextFilter,
 };
-use crate::utils::{config::OutputMode::*, errors::ServiceError, logging::Target, TextFilter};
-
-#[derive(Debug, Deserialize, Serialize, Clone)]
-struct TextParams {
-    control: String,
-    message: TextFilter,
-}
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ControlParams {
     pub control: PlayerCtl,
-}
-
-#[derive(Debug, Deserialize, Serialize, Clone)]
-struct MediaParams {
-    media: String,
+    /// Position in the running clip for the seek command, in seconds like the `in` and `out` points.
+    #[serde(default)]
+    pub position: Option<f64>,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone, Eq, PartialEq)]
@@ -37,6 +34,8 @@ pub enum ProcessCtl {
     Start,
     Stop,
     Restart,
+    Standby,
+    Resume,
 }
 
 impl FromStr for ProcessCtl {
@@ -48,6 +47,8 @@ impl FromStr for ProcessCtl {
             "start" => Ok(Self::Start),
             "stop" => Ok(Self::Stop),
             "restart" => Ok(Self::Restart),
+            "standby" => Ok(Self::Standby),
+            "resume" => Ok(Self::Resume),
             _ => Err(format!("Command '{input}' not found!")),
         }
     }
@@ -60,6 +61,8 @@ impl fmt::Display for ProcessCtl {
             Self::Start => write!(f, "start"),
             Self::Stop => write!(f, "stop"),
             Self::Restart => write!(f, "restart"),
+            Self::Standby => write!(f, "standby"),
+            Self::Resume => write!(f, "resume"),
         }
     }
 }
@@ -68,9 +71,12 @@ impl fmt::Display for ProcessCtl {
 #[serde(rename_all = "snake_case")]
 pub enum PlayerCtl {
     Back,
+    Dump,
     Next,
     #[default]
     Reset,
+    Seek,
+    Skip,
 }
 
 impl FromStr for PlayerCtl {
@@ -79,8 +85,11 @@ impl FromStr for PlayerCtl {
     fn from_str(input: &str) -> Result<Self, Self::Err> {
         match input.to_lowercase().as_str() {
             "back" => Ok(Self::Back),
+            "dump" => Ok(Self::Dump),
             "next" => Ok(Self::Next),
             "reset" => Ok(Self::Reset),
+            "seek" => Ok(Self::Seek),
+            "skip" => Ok(Self::Skip),
             _ => Err(format!("Command '{input}' not found!")),
         }
     }
@@ -90,8 +99,11 @@ impl fmt::Display for PlayerCtl {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             Self::Back => write!(f, "back"),
+            Self::Dump => write!(f, "dump"),
             Self::Next => write!(f, "next"),
             Self::Reset => write!(f, "reset"),
+            Self::Seek => write!(f, "seek"),
+            Self::Skip => write!(f, "skip"),
         }
     }
 }
@@ -101,6 +113,69 @@ pub struct Process {
     pub command: ProcessCtl,
 }
 
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EmergencyCtl {
+    #[default]
+    Status,
+    Start,
+    Release,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Emergency {
+    pub command: EmergencyCtl,
+    /// Reason for the channel log, like the kind of the alert.
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Preempt the program, also a live ingest or event, with the emergency slate, or release it.
+///
+/// Returns `true`, when the override is active afterwards.
+pub async fn emergency_control(
+    manager: &ChannelManager,
+    control: &Emergency,
+) -> Result<bool, ServiceError> {
+    let reason = control.reason.as_deref();
+
+    match control.command {
+        EmergencyCtl::Status => {}
+        EmergencyCtl::Start => {
+            if manager.config.lock().await.storage.emergency.is_empty() {
+                return Err(ServiceError::BadRequest(
+                    "No emergency slate is configured".to_string(),
+                ));
+            }
+
+            if !manager.is_alive.load(Ordering::SeqCst) {
+                return Err(ServiceError::Conflict("Channel is not running".to_string()));
+            }
+
+            if manager.set_emergency(true, reason).await {
+                if manager.event_is_alive.load(Ordering::SeqCst) {
+                    manager.stop(Event).await;
+                }
+
+                if manager.ingest_is_alive.load(Ordering::SeqCst) {
+                    manager.stop(Ingest).await;
+                }
+
+                manager.stop(Decoder).await;
+            }
+        }
+        EmergencyCtl::Release => {
+            if manager.set_emergency(false, reason).await {
+                // the program continues at the current time
+                manager.list_init.store(true, Ordering::SeqCst);
+                manager.stop(Decoder).await;
+            }
+        }
+    }
+
+    Ok(manager.emergency.load(Ordering::SeqCst))
+}
+
 async fn zmq_send(msg: &str, socket_addr: &str) -> Result<String, Box<dyn Error>> {
     let mut socket = zeromq::ReqSocket::new();
     socket.connect(&format!("tcp://{socket_addr}")).await?;
@@ -113,11 +188,21 @@ async fn zmq_send(msg: &str, socket_addr: &str) -> Result<String, Box<dyn Error>
 
 pub async fn send_message(
     manager: ChannelManager,
-    message: TextFilter,
+    mut message: TextFilter,
 ) -> Result<Map<String, Value>, ServiceError> {
+    let config = manager.config.lock().await.clone();
+    let time = time_now(&config.channel.timezone)
+        .format("%H:%M")
+        .to_string();
+
+    message.resolve(
+        manager.current_media.lock().await.as_ref(),
+        &config.channel.name,
+        &time,
+    );
+
     let filter = message.to_string();
     let mut data_map = Map::new();
-    let config = manager.config.lock().await.clone();
 
     if config.text.zmq_stream_socket.is_some() {
         if let Some(clips_filter) = manager.filter_chain.clone() {
@@ -162,20 +247,58 @@ pub async fn send_message(
     ))
 }
 
+/// Clip which is playing now, skip and seek are not possible while a live source is running.
+async fn running_clip(manager: &ChannelManager) -> Result<Media, ServiceError> {
+    if !manager.is_alive.load(Ordering::SeqCst) {
+        return Err(ServiceError::Conflict("Playout is not running".to_string()));
+    }
+
+    if manager.ingest_is_alive.load(Ordering::SeqCst)
+        || manager.event_is_alive.load(Ordering::SeqCst)
+    {
+        return Err(ServiceError::Conflict(
+            "Live source is running, no clip to control".to_string(),
+        ));
+    }
+
+    manager
+        .current_media
+        .lock()
+        .await
+        .clone()
+        .ok_or(ServiceError::Conflict("No clip is playing".to_string()))
+}
+
+/// Playlist item of the running clip, with the original begin and seek values.
+/// The index can already point behind a prerolled clip, so it must match the source.
+fn list_item<'a>(list: &'a [Media], media: &'a Media) -> &'a Media {
+    media
+        .index
+        .and_then(|i| list.get(i))
+        .filter(|item| item.source == media.source)
+        .unwrap_or(media)
+}
+
 pub async fn control_state(
-    conn: &Pool<Sqlite>,
+    conn: &DbPool,
     manager: &ChannelManager,
-    command: &PlayerCtl,
+    params: &ControlParams,
 ) -> Result<Map<String, Value>, ServiceError> {
     let config = manager.config.lock().await.clone();
     let id = config.general.channel_id;
     let current_date = manager.current_date.lock().await.clone();
     let current_list = manager.current_list.lock().await.clone();
+
+    if matches!(params.control, PlayerCtl::Back | PlayerCtl::Next) {
+        // the index must point to the clip after the running one, not behind the prerolled clip
+        manager.discard_preroll().await;
+    }
+
     let index = manager.current_index.load(Ordering::SeqCst);
     let mut data_map = Map::new();
     let mut shift = 0.0;
 
-    match command {
+    match params.control {
         PlayerCtl::Back => {
             if index > 1 && current_list.len() > 1 {
                 let mut media = current_list[index - 2].clone();
@@ -195,6 +318,23 @@ pub async fn control_state(
             }
         }
 
+        PlayerCtl::Dump => {
+            if config.ingest.delay <= 0 || !manager.ingest_is_alive.load(Ordering::SeqCst) {
+                return Err(ServiceError::Conflict(
+                    "No delayed live ingest is running".to_string(),
+                ));
+            }
+
+            info!(target: Target::file_mail(), channel = id; "Dump delayed live ingest");
+
+            manager.ingest_dump.store(true, Ordering::SeqCst);
+
+            // the live stream continues, only the held part is dropped
+            data_map.insert("operation".to_string(), json!("dump_live"));
+
+            return Ok(data_map);
+        }
+
         PlayerCtl::Next => {
             if index < current_list.len() {
                 let mut media = current_list[index].clone();
@@ -219,11 +359,202 @@ pub async fn control_state(
 
             data_map.insert("operation".to_string(), json!("reset_playout_state"));
         }
+
+        PlayerCtl::Seek => {
+            let media = running_clip(manager).await?;
+            let position = params.position.ok_or(ServiceError::BadRequest(
+                "Seek position is missing".to_string(),
+            ))?;
+            let end = media.out.min(media.duration);
+
+            if media.duration <= 0.0
+                || media
+                    .source
+                    .rsplit_once('.')
+                    .is_some_and(|(_, e)| IMAGE_FORMAT.contains(&e.to_lowercase().as_str()))
+            {
+                return Err(ServiceError::BadRequest(
+                    "Running clip is not seekable".to_string(),
+                ));
+            }
+
+            if !(0.0..end - 1.0).contains(&position) {
+                return Err(ServiceError::BadRequest(format!(
+                    "Seek position must be between 0 and {:.3}",
+                    end - 1.0
+                )));
+            }
+
+            shift = manager.channel.lock().await.time_shift;
+
+            if config.processing.mode == ProcessMode::Playlist {
+                let item = list_item(&current_list, &media);
+
+                if let Some(begin) = item.begin {
+                    (shift, _) = get_delta(&config, &(begin + position - item.seek));
+                }
+            }
+
+            let mut node = media.clone();
+            node.seek = position;
+            node.transition_in = false;
+            node.cmd = Some(seek_and_length(&config, &mut node));
+            node.add_filter(&config, &manager.filter_chain).await;
+
+            info!(target: Target::file_mail(), channel = id; "Seek to <yellow>{position:.3}</> in: <b><magenta>{}</></b>", media.source);
+
+            *manager.seek_media.lock().await = Some(node);
+
+            data_map.insert("operation".to_string(), json!("seek_clip"));
+            data_map.insert("position".to_string(), json!(position));
+            data_map.insert("shifted_seconds".to_string(), json!(shift));
+            data_map.insert("media".to_string(), get_media_map(media));
+        }
+
+        PlayerCtl::Skip => {
+            let media = running_clip(manager).await?;
+
+            shift = manager.channel.lock().await.time_shift;
+
+            if config.processing.mode == ProcessMode::Playlist {
+                let item = list_item(&current_list, &media);
+
+                if let Some(begin) = item.begin {
+                    (shift, _) = get_delta(&config, &(begin + item.out - item.seek));
+                }
+            }
+
+            info!(target: Target::file_mail(), channel = id; "Skip clip: <b><magenta>{}</></b>", media.source);
+
+            data_map.insert("operation".to_string(), json!("skip_clip"));
+            data_map.insert("shifted_seconds".to_string(), json!(shift));
+            data_map.insert("media".to_string(), get_media_map(media));
+        }
     }
 
     manager.channel.lock().await.time_shift = shift;
     handles::update_stat(conn, id, &Some(current_date), shift).await?;
+    manager.discard_preroll().await;
     manager.stop(Decoder).await;
 
     Ok(data_map)
 }
+
+#[cfg(all(test, not(feature = "mysql")))]
+mod tests {
+    use super::*;
+    use crate::player::{
+        controller::test_manager, output::emergency_source, utils::probe::MediaProbe,
+    };
+    use crate::vec_strings;
+
+    fn clip(index: usize, source: &str) -> Media {
+        Media {
+            index: Some(index),
+            begin: Some(index as f64 * 60.0),
+            source: source.to_string(),
+            out: 60.0,
+            duration: 60.0,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn next_during_preroll() {
+        let (pool, manager) = test_manager("control", "").await;
+        let list = vec![clip(0, "a.mp4"), clip(1, "b.mp4"), clip(2, "c.mp4")];
+
+        *manager.current_list.lock().await = list.clone();
+        *manager.current_media.lock().await = Some(list[0].clone());
+
+        // b.mp4 is prerolled, so the index points already behind it
+        *manager.preroll.lock().await = Some(1);
+        manager.current_index.store(2, Ordering::SeqCst);
+
+        let params = ControlParams {
+            control: PlayerCtl::Next,
+            position: None,
+        };
+        let data = control_state(&pool, &manager, &params).await.unwrap();
+
+        assert_eq!(data["media"]["source"], "b.mp4");
+        assert_eq!(manager.current_index.load(Ordering::SeqCst), 1);
+        assert!(manager.preroll_discarded.load(Ordering::SeqCst));
+        assert!(manager.preroll.lock().await.is_none());
+
+        // without a waiting preroll the index stays
+        manager.preroll_discarded.store(false, Ordering::SeqCst);
+        manager.current_index.store(2, Ordering::SeqCst);
+
+        let data = control_state(&pool, &manager, &params).await.unwrap();
+
+        assert_eq!(data["media"]["source"], "c.mp4");
+        assert_eq!(manager.current_index.load(Ordering::SeqCst), 2);
+        assert!(!manager.preroll_discarded.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn emergency_switches_decoder() {
+        let (_, manager) = test_manager("control", "").await;
+        let slate =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/assets/logo.png");
+        let start = Emergency {
+            command: EmergencyCtl::Start,
+            reason: Some("Technical difficulties".to_string()),
+        };
+
+        // without slate there is nothing to switch to
+        assert!(matches!(
+            emergency_control(&manager, &start).await,
+            Err(ServiceError::BadRequest(_))
+        ));
+
+        {
+            let mut config = manager.config.lock().await;
+            config.storage.emergency = "logo.png".to_string();
+            config.storage.emergency_path = slate.clone();
+        }
+
+        assert!(matches!(
+            emergency_control(&manager, &start).await,
+            Err(ServiceError::Conflict(_))
+        ));
+
+        manager.is_alive.store(true, Ordering::SeqCst);
+        *manager.decoder.lock().await = Some(
+            tokio::process::Command::new("sleep")
+                .arg("60")
+                .spawn()
+                .unwrap(),
+        );
+
+        assert!(emergency_control(&manager, &start).await.unwrap());
+
+        // the program decoder is gone, the output loop continues with the slate
+        assert!(manager.decoder.lock().await.is_none());
+
+        let config = manager.config.lock().await.clone();
+        let source = slate.to_string_lossy().to_string();
+        let mut node = Media::new(0, &source, false).await;
+        node.probe = Some(MediaProbe::default());
+        let node = emergency_source(&config, node);
+
+        assert_eq!(node.source, source);
+        assert_eq!(node.out, 3600.0);
+        assert_eq!(
+            node.cmd.unwrap()[..4],
+            vec_strings!["-loop", "1", "-i", source]
+        );
+
+        manager.list_init.store(false, Ordering::SeqCst);
+
+        let release = Emergency {
+            command: EmergencyCtl::Release,
+            reason: None,
+        };
+
+        assert!(!emergency_control(&manager, &release).await.unwrap());
+        assert!(!manager.emergency.load(Ordering::SeqCst));
+        assert!(manager.list_init.load(Ordering::SeqCst));
+    }
+}
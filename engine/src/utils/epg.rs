@@ -0,0 +1,331 @@
+/// EPG
+///
+/// Build a programme guide from the upcoming playlists, as XMLTV or JSON.
+/// The times are calculated like in playout, from the day start and the in/out points of the clips.
+use std::{path::Path, sync::atomic::Ordering};
+
+use chrono::{DateTime, FixedOffset, TimeDelta};
+use serde::Serialize;
+
+use crate::db::DbPool;
+use crate::player::{
+    controller::ChannelManager,
+    utils::{day_start_time, json_serializer::expand_includes, playlist_clock, Media},
+};
+use crate::utils::{
+    config::PlayoutConfig,
+    playlist::read_playlist,
+    time_machine::{local_timezone, time_now},
+};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Programme {
+    pub start: DateTime<FixedOffset>,
+    pub stop: DateTime<FixedOffset>,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub category: String,
+}
+
+/// Title of a clip: from the playlist, or the file name without extension.
+/// Paths are never exposed.
+pub fn programme_title(title: Option<String>, source: &str) -> String {
+    title.filter(|t| !t.trim().is_empty()).unwrap_or_else(|| {
+        Path::new(source.split(['?', '#']).next().unwrap_or_default())
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default()
+    })
+}
+
+/// Collect the programmes from the running playlist and the following `days - 1` playlists.
+/// Programmes which are already over are left out, missing playlists are skipped.
+pub async fn programmes(conn: &DbPool, config: &PlayoutConfig, days: u32) -> Vec<Programme> {
+    let tz = local_timezone(&config.channel.timezone);
+    let start_sec = config.playlist.start_sec.unwrap_or_default();
+    let now = time_now(&config.channel.timezone).fixed_offset();
+    let (today, _) = playlist_clock(&config.channel.timezone, start_sec);
+    let mut list = vec![];
+
+    for day in 0..i64::from(days) {
+        let date = today + TimeDelta::days(day);
+
+        let Ok(playlist) = read_playlist(conn, config, date.format("%Y-%m-%d").to_string()).await
+        else {
+            continue;
+        };
+        let program = expand_includes(config, playlist.program).await;
+
+        let mut begin = day_start_time(&tz, date, start_sec).fixed_offset();
+        let day_end = day_start_time(&tz, date + TimeDelta::days(1), start_sec).fixed_offset();
+
+        for item in program {
+            let length = item.out - item.seek;
+
+            if length <= 0.0 {
+                continue;
+            }
+
+            if begin >= day_end {
+                break;
+            }
+
+            let stop = (begin + TimeDelta::milliseconds((length * 1000.0) as i64)).min(day_end);
+
+            if stop > now {
+                list.push(Programme {
+                    start: begin,
+                    stop,
+                    title: programme_title(item.title, &item.source),
+                    description: item.description.filter(|d| !d.trim().is_empty()),
+                    category: item.category,
+                });
+            }
+
+            begin = stop;
+        }
+    }
+
+    list
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OnAir {
+    pub title: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub category: String,
+    pub start: DateTime<FixedOffset>,
+    pub duration: f64,
+    pub elapsed: f64,
+    pub remaining: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UpNext {
+    pub title: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub category: String,
+    pub start: DateTime<FixedOffset>,
+    pub duration: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NowPlaying {
+    pub channel: String,
+    pub live: bool,
+    pub current: Option<OnAir>,
+    pub next: Vec<UpNext>,
+}
+
+/// The following `count` clips, starting at `start`. Clips without length are skipped.
+pub fn up_next(list: &[Media], start: DateTime<FixedOffset>, count: usize) -> Vec<UpNext> {
+    let mut begin = start;
+
+    list.iter()
+        .filter(|item| item.out - item.seek > 0.0)
+        .take(count)
+        .map(|item| {
+            let duration = item.out - item.seek;
+            let next = UpNext {
+                title: programme_title(item.title.clone(), &item.source),
+                category: item.category.clone(),
+                start: begin,
+                duration,
+            };
+
+            begin += TimeDelta::milliseconds((duration * 1000.0) as i64);
+
+            next
+        })
+        .collect()
+}
+
+/// Running clip and the next `count` clips of the channel, for the public now playing endpoint.
+/// Only titles and times are included, no sources.
+pub async fn now_playing(manager: &ChannelManager, count: usize) -> NowPlaying {
+    let channel = manager.channel.lock().await.clone();
+    let start_sec = manager
+        .config
+        .lock()
+        .await
+        .playlist
+        .start_sec
+        .unwrap_or_default();
+    let live = manager.ingest_is_alive.load(Ordering::SeqCst);
+    let mut now_playing = NowPlaying {
+        channel: channel.name,
+        live,
+        current: None,
+        next: vec![],
+    };
+
+    if !manager.is_alive.load(Ordering::SeqCst) {
+        return now_playing;
+    }
+
+    let Some(media) = manager.current_media.lock().await.clone() else {
+        return now_playing;
+    };
+
+    let now = time_now(&channel.timezone).fixed_offset();
+    let (_, current_time) = playlist_clock(&channel.timezone, start_sec);
+    let begin = media.begin.unwrap_or(0.0) - channel.time_shift;
+    let duration = media.out - media.seek;
+    let elapsed = (current_time - begin).clamp(0.0, duration.max(0.0));
+    let remaining = duration - elapsed;
+    let source = if media.key.is_empty() {
+        &media.source
+    } else {
+        &media.key
+    };
+
+    now_playing.current = Some(OnAir {
+        title: programme_title(media.title.clone(), source),
+        category: media.category.clone(),
+        start: now - TimeDelta::milliseconds((elapsed * 1000.0) as i64),
+        duration,
+        elapsed,
+        remaining,
+    });
+
+    if let Some(index) = media.index {
+        let list = manager.current_list.lock().await;
+        let start = now + TimeDelta::milliseconds((remaining * 1000.0) as i64);
+
+        now_playing.next = up_next(list.get(index + 1..).unwrap_or_default(), start, count);
+    }
+
+    now_playing
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Render the programmes as XMLTV document, the channel id is `ffplayout.<id>`.
+pub fn xmltv(channel_id: i32, channel_name: &str, programmes: &[Programme]) -> String {
+    const TIME_FORMAT: &str = "%Y%m%d%H%M%S %z";
+
+    let channel = format!("ffplayout.{channel_id}");
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE tv SYSTEM \"xmltv.dtd\">\n<tv generator-info-name=\"ffplayout\">\n  <channel id=\"{channel}\">\n    <display-name>{}</display-name>\n  </channel>\n",
+        escape_xml(channel_name)
+    );
+
+    for programme in programmes {
+        xml.push_str(&format!(
+            "  <programme start=\"{}\" stop=\"{}\" channel=\"{channel}\">\n    <title>{}</title>\n",
+            programme.start.format(TIME_FORMAT),
+            programme.stop.format(TIME_FORMAT),
+            escape_xml(&programme.title)
+        ));
+
+        if let Some(description) = &programme.description {
+            xml.push_str(&format!("    <desc>{}</desc>\n", escape_xml(description)));
+        }
+
+        if !programme.category.is_empty() {
+            xml.push_str(&format!(
+                "    <category>{}</category>\n",
+                escape_xml(&programme.category)
+            ));
+        }
+
+        xml.push_str("  </programme>\n");
+    }
+
+    xml.push_str("</tv>\n");
+
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_xmltv() {
+        let start = DateTime::parse_from_rfc3339("2024-03-31T01:30:00+01:00").unwrap();
+        let programmes = vec![Programme {
+            start,
+            stop: DateTime::parse_from_rfc3339("2024-03-31T03:15:00+02:00").unwrap(),
+            title: programme_title(None, "/tv/media/Night & Day.mp4"),
+            description: Some("<b>Live</b>".to_string()),
+            category: String::new(),
+        }];
+
+        let xml = xmltv(1, "Channel \"1\"", &programmes);
+
+        assert!(xml.contains("<display-name>Channel &quot;1&quot;</display-name>"));
+        assert!(xml.contains(
+            "<programme start=\"20240331013000 +0100\" stop=\"20240331031500 +0200\" channel=\"ffplayout.1\">"
+        ));
+        assert!(xml.contains("<title>Night &amp; Day</title>"));
+        assert!(xml.contains("<desc>&lt;b&gt;Live&lt;/b&gt;</desc>"));
+        assert!(!xml.contains("<category>"));
+
+        let options = roxmltree::ParsingOptions {
+            allow_dtd: true,
+            ..Default::default()
+        };
+
+        assert!(roxmltree::Document::parse_with_options(&xml, options).is_ok());
+    }
+
+    #[test]
+    fn upcoming_clips() {
+        let start = DateTime::parse_from_rfc3339("2024-03-31T12:00:00+02:00").unwrap();
+        let list = vec![
+            Media {
+                source: "/tv/media/clip one.mp4".to_string(),
+                seek: 10.0,
+                out: 70.0,
+                ..Default::default()
+            },
+            Media {
+                source: "/tv/media/empty.mp4".to_string(),
+                out: 0.0,
+                ..Default::default()
+            },
+            Media {
+                title: Some("Clip Two".to_string()),
+                source: "/tv/media/clip_two.mp4".to_string(),
+                out: 30.0,
+                ..Default::default()
+            },
+            Media {
+                source: "/tv/media/clip_three.mp4".to_string(),
+                out: 30.0,
+                ..Default::default()
+            },
+        ];
+
+        let next = up_next(&list, start, 2);
+
+        assert_eq!(next.len(), 2);
+        assert_eq!(next[0].title, "clip one");
+        assert_eq!(next[0].duration, 60.0);
+        assert_eq!(next[0].start, start);
+        assert_eq!(next[1].title, "Clip Two");
+        assert_eq!(
+            next[1].start,
+            DateTime::parse_from_rfc3339("2024-03-31T12:01:00+02:00").unwrap()
+        );
+        assert!(!serde_json::to_string(&next).unwrap().contains("/tv/media"));
+    }
+}
@@ -22,6 +22,9 @@ pub enum ServiceError {
     #[display("Unauthorized: {_0}")]
     Unauthorized(String),
 
+    #[display("NotFound: {_0}")]
+    NotFound(String),
+
     #[display("NoContent: {_0}")]
     NoContent(String),
 
@@ -40,6 +43,7 @@ impl ResponseError for ServiceError {
             Self::Conflict(ref message) => HttpResponse::Conflict().json(message),
             Self::Forbidden(ref message) => HttpResponse::Forbidden().json(message),
             Self::Unauthorized(ref message) => HttpResponse::Unauthorized().json(message),
+            Self::NotFound(ref message) => HttpResponse::NotFound().json(message),
             Self::NoContent(ref message) => HttpResponse::NoContent().json(message),
             Self::ServiceUnavailable(ref message) => {
                 HttpResponse::ServiceUnavailable().json(message)
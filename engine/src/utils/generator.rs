@@ -3,27 +3,35 @@
 /// You can call ffplayout[.exe] -g YYYY-mm-dd - YYYY-mm-dd to generate JSON playlists.
 ///
 /// The generator takes the files from storage, which are set in config.
-/// It also respect the shuffle/sort mode.
-use std::io::Error;
+/// It also respect the shuffle/sort mode, or plans the day from a template with clock hours.
+use std::{collections::HashMap, io::Error, path::PathBuf};
 
-use chrono::Timelike;
+use chrono::{NaiveDate, Timelike};
 use lexical_sort::{natural_lexical_cmp, StringSort};
 use log::*;
 use rand::{rng, seq::SliceRandom, Rng};
 use tokio::fs;
 
 // use crate::file::utils::
+use crate::db::handles;
+use crate::file::{utils::filler::FillerPool, StorageBackend, WalkOptions};
 use crate::player::{
     controller::ChannelManager,
     input::folder::FolderSource,
     utils::{
-        get_date_range, include_file_extension, json_serializer::JsonPlaylist, sum_durations, Media,
+        get_date_range, include_file_extension,
+        json_serializer::{JsonPlaylist, DB_PLAYLIST},
+        playlist_length, sum_durations, Media,
     },
 };
 use crate::utils::{
+    clock::ClockPlanner,
     config::{PlayoutConfig, Template},
     logging::Target,
+    playlist::store_playlist,
+    rules::RuleSet,
     time_to_sec,
+    validity::{self, Validity},
 };
 
 pub fn random_list(clip_list: Vec<Media>, total_length: f64) -> Vec<Media> {
@@ -95,39 +103,89 @@ pub async fn filler_list(
     manager: &ChannelManager,
     total_length: f64,
 ) -> Vec<Media> {
-    let filler_list = manager
+    let mut fillers = manager
         .storage
         .lock()
         .await
         .fill_filler_list(config, None)
         .await;
-    let mut index = 0;
+
+    fill_gap(&mut fillers, total_length)
+}
+
+/// Take filler clips until the gap is full, the last one gets cut.
+pub fn fill_gap(fillers: &mut FillerPool, total_length: f64) -> Vec<Media> {
     let mut filler_clip_list: Vec<Media> = vec![];
     let mut target_duration = 0.0;
-    let clip_list_length = filler_list.len();
 
-    if clip_list_length > 0 {
-        while target_duration < total_length {
-            if index == clip_list_length {
-                index = 0;
-            }
-
-            let selected_clip = filler_list[index].clone();
+    while target_duration < total_length {
+        let Some(selected_clip) = fillers
+            .next_fitting(target_duration, total_length - target_duration)
+            .or_else(|| fillers.next(target_duration))
+        else {
+            break;
+        };
 
-            target_duration += selected_clip.duration;
-            filler_clip_list.push(selected_clip);
-            index += 1;
+        if selected_clip.duration <= 0.0 {
+            break;
         }
 
+        target_duration += selected_clip.duration;
+        filler_clip_list.push(selected_clip);
+    }
+
+    if let Some(last) = filler_clip_list.last_mut() {
         let over_length = target_duration - total_length;
-        let last_index = filler_clip_list.len() - 1;
 
-        filler_clip_list[last_index].out = filler_clip_list[last_index].duration - over_length;
+        last.out = last.duration - over_length;
     }
 
     filler_clip_list
 }
 
+/// Collect and probe all clips from the template paths, sorted when they are not shuffled.
+pub async fn template_media(
+    config: &PlayoutConfig,
+    storage: &StorageBackend,
+    paths: &[PathBuf],
+    shuffle: bool,
+) -> Vec<Media> {
+    let walk_options = WalkOptions::from_config(config);
+    let mut source_list = vec![];
+
+    for path in paths {
+        debug!("Search files in <b><magenta>{path:?}</></b>");
+        let mut file_list = vec![];
+
+        match storage.walk_dir(path, &walk_options).await {
+            Ok(storage_paths) => {
+                for single_path in storage_paths {
+                    if storage.is_file(&single_path).await
+                        && include_file_extension(config, &single_path)
+                    {
+                        let file = single_path.to_string_lossy().to_string();
+                        file_list.push(file);
+                    }
+                }
+            }
+            Err(e) => {
+                error!("{e:?}");
+            }
+        }
+
+        if !shuffle {
+            file_list.string_sort_unstable(natural_lexical_cmp);
+        }
+
+        for entry in file_list {
+            let media = Media::new(0, &entry, true).await;
+            source_list.push(media);
+        }
+    }
+
+    source_list
+}
+
 pub async fn generate_from_template(
     config: &PlayoutConfig,
     manager: &ChannelManager,
@@ -140,50 +198,13 @@ pub async fn generate_from_template(
     let storage = manager.storage.lock().await.clone();
 
     for source in template.sources {
-        let mut source_list = vec![];
         let duration = (source.duration.hour() as f64 * 3600.0)
             + (source.duration.minute() as f64 * 60.0)
             + source.duration.second() as f64;
 
         debug!(target: Target::all(), channel = id; "Generating playlist block with <yellow>{duration:.2}</> seconds length");
 
-        for path in source.paths {
-            debug!("Search files in <b><magenta>{path:?}</></b>");
-            let mut file_list = vec![];
-
-            match storage.walk_dir(path).await {
-                Ok(storage_paths) => {
-                    for single_path in storage_paths {
-                        if storage.is_file(&single_path).await
-                            && include_file_extension(config, &single_path)
-                        {
-                            let file = single_path.to_string_lossy().to_string();
-                            file_list.push(file);
-                        }
-                    }
-                }
-                Err(e) => {
-                    error!("{e:?}");
-                }
-            }
-
-            // while let Some(Ok(entry)) = entries.next().await {
-            //     if entry.path().is_file() && include_file_extension(config, &entry.path()) {
-            //         let file = entry.path().to_string_lossy().to_string();
-
-            //         file_list.push(file);
-            //     }
-            // }
-
-            if !source.shuffle {
-                file_list.string_sort_unstable(natural_lexical_cmp);
-            }
-
-            for entry in file_list {
-                let media = Media::new(0, &entry, true).await;
-                source_list.push(media);
-            }
-        }
+        let mut source_list = template_media(config, &storage, &source.paths, source.shuffle).await;
 
         let mut timed_list = if source.shuffle {
             source_list.shuffle(&mut rng);
@@ -213,6 +234,43 @@ pub async fn generate_from_template(
     FolderSource::from_list(manager, media_list).await
 }
 
+/// Replace items, which are not valid on the day, with filler clips of the same length.
+pub async fn check_validity(
+    config: &PlayoutConfig,
+    manager: &ChannelManager,
+    library: &HashMap<String, (Option<String>, Option<String>)>,
+    program: Vec<Media>,
+    date: &str,
+) -> Vec<Media> {
+    let id = config.general.channel_id;
+    let Ok(day) = NaiveDate::parse_from_str(date, "%Y-%m-%d") else {
+        return program;
+    };
+    let mut checked = vec![];
+
+    for mut item in program {
+        validity::apply_library(&mut item, library);
+
+        match validity::check(&item, day) {
+            Validity::NotYet(from) => {
+                warn!(target: Target::all(), channel = id; "Skip <b><magenta>{}</></b>, valid from <yellow>{from}</>", item.source);
+                checked.append(&mut filler_list(config, manager, item.out - item.seek).await);
+            }
+            Validity::Expired(to) => {
+                warn!(target: Target::all(), channel = id; "Skip <b><magenta>{}</></b>, expired on <yellow>{to}</>", item.source);
+                checked.append(&mut filler_list(config, manager, item.out - item.seek).await);
+            }
+            Validity::Expiring(to) => {
+                warn!(target: Target::all(), channel = id; "<b><magenta>{}</></b> expires on <yellow>{to}</>", item.source);
+                checked.push(item);
+            }
+            Validity::Valid => checked.push(item),
+        }
+    }
+
+    checked
+}
+
 /// Generate playlists
 pub async fn playlist_generator(manager: &ChannelManager) -> Result<Vec<JsonPlaylist>, Error> {
     let config = manager.config.lock().await.clone();
@@ -234,7 +292,7 @@ pub async fn playlist_generator(manager: &ChannelManager) -> Result<Vec<JsonPlay
     let mut date_range = vec![];
     let mut from_template = false;
 
-    if !playlist_root.is_dir() {
+    if !config.playlist.database && !playlist_root.is_dir() {
         error!(
             target: Target::all(), channel = id;
             "Playlist folder <b><magenta>{:?}</></b> not exists!",
@@ -260,6 +318,24 @@ pub async fn playlist_generator(manager: &ChannelManager) -> Result<Vec<JsonPlay
     };
 
     let list_length = manager.current_list.lock().await.len();
+    let storage = manager.storage.lock().await.clone();
+    let mut rules = RuleSet::new(&config, &storage).await;
+    let library = validity::library_validity(&manager.db_pool, &config).await;
+    let start_sec = config.playlist.start_sec.unwrap_or_default();
+
+    // clock hours are planned for every day again, separation continues over the days
+    let mut clock = match &config.general.template {
+        Some(template) if !template.clock.is_empty() => Some((
+            ClockPlanner::new(&config, &storage, template).await,
+            manager
+                .storage
+                .lock()
+                .await
+                .fill_filler_list(&config, None)
+                .await,
+        )),
+        _ => None,
+    };
 
     for date in date_range {
         let d: Vec<&str> = date.split('-').collect();
@@ -267,16 +343,33 @@ pub async fn playlist_generator(manager: &ChannelManager) -> Result<Vec<JsonPlay
         let month = d[1];
         let playlist_path = playlist_root.join(year).join(month);
         let playlist_file = &playlist_path.join(format!("{date}.json"));
+        let target = if config.playlist.database {
+            format!("{DB_PLAYLIST}{date}")
+        } else {
+            playlist_file.display().to_string()
+        };
+        // a full day can have 23 or 25 hours, when DST changes
+        let total_length = match config.playlist.length_sec {
+            Some(_) => playlist_length(&config, &date),
+            None => total_length,
+        };
         let mut length = 0.0;
         let mut round = 0;
 
-        fs::create_dir_all(playlist_path).await?;
+        let exists = if config.playlist.database {
+            handles::select_playlist(&manager.db_pool, id, &date)
+                .await
+                .is_ok()
+        } else {
+            fs::create_dir_all(playlist_path).await?;
+
+            playlist_file.is_file()
+        };
 
-        if playlist_file.is_file() {
+        if exists {
             warn!(
                 target: Target::all(), channel = id;
-                "Playlist exists, skip: <b><magenta>{}</></b>",
-                playlist_file.display()
+                "Playlist exists, skip: <b><magenta>{target}</></b>"
             );
 
             continue;
@@ -284,8 +377,7 @@ pub async fn playlist_generator(manager: &ChannelManager) -> Result<Vec<JsonPlay
 
         info!(
             target: Target::all(), channel = id;
-            "Generate playlist: <b><magenta>{}</></b>",
-            playlist_file.display()
+            "Generate playlist: <b><magenta>{target}</></b>"
         );
 
         let mut playlist = JsonPlaylist {
@@ -298,9 +390,12 @@ pub async fn playlist_generator(manager: &ChannelManager) -> Result<Vec<JsonPlay
             program: vec![],
         };
 
-        if from_template {
+        if let Some((planner, fillers)) = clock.as_mut() {
+            let program = planner.plan(total_length, fillers);
+            playlist.program = rules.apply(program, start_sec, total_length);
+        } else if from_template {
             let media_list = manager.current_list.lock().await;
-            playlist.program = media_list.to_vec();
+            playlist.program = rules.apply(media_list.to_vec(), start_sec, total_length);
         } else {
             while let Some(item) = folder_iter.next().await {
                 let duration = item.duration;
@@ -316,18 +411,29 @@ pub async fn playlist_generator(manager: &ChannelManager) -> Result<Vec<JsonPlay
                 }
             }
 
+            playlist.program = rules.apply(playlist.program, start_sec, total_length);
+
             let list_duration = sum_durations(&playlist.program);
 
-            if config.playlist.length_sec.unwrap() > list_duration {
-                let time_left = config.playlist.length_sec.unwrap() - list_duration;
+            if total_length > list_duration {
+                let time_left = total_length - list_duration;
                 let mut fillers = filler_list(&config, manager, time_left).await;
 
                 playlist.program.append(&mut fillers);
             }
         }
 
-        let json: String = serde_json::to_string_pretty(&playlist)?;
-        fs::write(playlist_file, json).await?;
+        playlist.program =
+            check_validity(&config, manager, &library, playlist.program, &playlist.date).await;
+
+        if config.playlist.database {
+            store_playlist(&manager.db_pool, id, 0, &playlist)
+                .await
+                .map_err(|e| Error::other(e.to_string()))?;
+        } else {
+            let json: String = serde_json::to_string_pretty(&playlist)?;
+            fs::write(playlist_file, json).await?;
+        }
 
         playlists.push(playlist);
     }
@@ -0,0 +1,261 @@
+use std::{
+    io,
+    net::ToSocketAddrs,
+    path::{Path, PathBuf},
+    process,
+    sync::{atomic::Ordering, Arc},
+    time::{Duration, SystemTime},
+};
+
+use actix_web::dev::ServerHandle;
+use log::*;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{unix::OwnedWriteHalf, TcpSocket, UnixListener, UnixStream},
+    sync::Mutex,
+    time::{sleep, Instant},
+};
+
+use crate::player::controller::{find_m3u8_files, ChannelController, ChannelManager};
+use crate::utils::{config::OutputMode::HLS, logging::Target};
+
+/// Max time to wait for the next HLS segment, before a channel is released anyway.
+const SEGMENT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Messages between the running engine and its successor, send as JSON lines.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HandoffMessage {
+    /// Successor is ready and asks for the channels.
+    Request { pid: u32, version: String },
+    /// Channel is stopped and can be started from the successor.
+    Released { channel_id: i32 },
+    /// All channels are released, the old engine shuts down.
+    Done,
+}
+
+impl HandoffMessage {
+    fn to_line(&self) -> String {
+        let mut line = serde_json::to_string(self).unwrap_or_default();
+        line.push('\n');
+
+        line
+    }
+}
+
+/// Bind the web server socket with `SO_REUSEPORT`, so old and new engine can listen
+/// at the same time and no request gets refused during the upgrade.
+pub fn bind_shared(addr: &str, port: u16) -> io::Result<std::net::TcpListener> {
+    let socket_addr = (addr, port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "No valid listen address"))?;
+
+    let socket = if socket_addr.is_ipv4() {
+        TcpSocket::new_v4()?
+    } else {
+        TcpSocket::new_v6()?
+    };
+
+    socket.set_reuseaddr(true)?;
+    socket.set_reuseport(true)?;
+    socket.bind(socket_addr)?;
+
+    socket.listen(1024)?.into_std()
+}
+
+/// Connect to a running engine. Returns `None` when nobody is listening on the handoff socket.
+pub async fn connect(path: &Path) -> Option<UnixStream> {
+    match UnixStream::connect(path).await {
+        Ok(stream) => {
+            info!("Found running engine on <b><magenta>{path:?}</></b>, take over its channels");
+            Some(stream)
+        }
+        Err(_) => None,
+    }
+}
+
+/// Newest modification time of the HLS playlists in the given path.
+async fn playlist_modified(path: &Path) -> Option<SystemTime> {
+    let mut newest = None;
+
+    for file in find_m3u8_files(path).await.ok()? {
+        if let Ok(modified) = tokio::fs::metadata(file).await.and_then(|m| m.modified()) {
+            newest = newest.max(Some(modified));
+        }
+    }
+
+    newest
+}
+
+/// Wait until the encoder has written a new segment to the HLS playlist.
+async fn wait_segment_boundary(path: &Path) {
+    let start = playlist_modified(path).await;
+    let timer = Instant::now();
+
+    while timer.elapsed() < SEGMENT_TIMEOUT {
+        sleep(Duration::from_millis(50)).await;
+
+        if playlist_modified(path).await > start {
+            return;
+        }
+    }
+}
+
+/// Stop all running channels, for HLS outputs on a segment boundary,
+/// and tell the successor which channel it can start.
+///
+/// The encoder and its connections are not handed over, so other outputs drop until the successor runs.
+async fn release_channels(
+    controllers: &Arc<Mutex<ChannelController>>,
+    writer: &mut OwnedWriteHalf,
+) -> io::Result<()> {
+    let managers = controllers.lock().await.managers.clone();
+
+    for manager in managers {
+        if !manager.is_alive.load(Ordering::SeqCst) {
+            continue;
+        }
+
+        let config = manager.config.lock().await.clone();
+        let channel_id = config.general.channel_id;
+
+        if config.output.mode == HLS {
+            wait_segment_boundary(&config.channel.public).await;
+        } else {
+            warn!(target: Target::all(), channel = channel_id; "Output <yellow>{}</> is interrupted, until the successor has started the channel", config.output.mode);
+        }
+
+        // only in memory, the player state in db stays active for the successor
+        manager.channel.lock().await.active = false;
+        manager.stop_all(false).await;
+
+        debug!(target: Target::all(), channel = channel_id; "Channel <yellow>{channel_id}</> released to successor");
+
+        writer
+            .write_all(HandoffMessage::Released { channel_id }.to_line().as_bytes())
+            .await?;
+    }
+
+    writer
+        .write_all(HandoffMessage::Done.to_line().as_bytes())
+        .await
+}
+
+/// Start the channels from the old engine, as soon as they are released.
+/// Started channels are removed from the `startup_list`.
+async fn take_over(stream: UnixStream, startup_list: &mut Vec<ChannelManager>) -> io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    let request = HandoffMessage::Request {
+        pid: process::id(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+    };
+
+    writer.write_all(request.to_line().as_bytes()).await?;
+
+    while let Some(line) = lines.next_line().await? {
+        match serde_json::from_str::<HandoffMessage>(&line) {
+            Ok(HandoffMessage::Released { channel_id }) => {
+                let Some(index) = startup_list.iter().position(|m| m.id == channel_id) else {
+                    continue;
+                };
+
+                let manager = startup_list.remove(index);
+                manager.taken_over.store(true, Ordering::SeqCst);
+
+                if let Err(e) = manager.start().await {
+                    error!(target: Target::all(), channel = channel_id; "Take over of channel <yellow>{channel_id}</> failed: {e}");
+                }
+            }
+            Ok(HandoffMessage::Done) => break,
+            Ok(msg) => warn!("Unexpected handoff message: {msg:?}"),
+            Err(e) => error!("Invalid handoff message: {e}"),
+        }
+    }
+
+    Ok(())
+}
+
+/// Wait for a successor, release all channels to it and stop the web server afterwards.
+async fn serve(
+    path: &Path,
+    controllers: &Arc<Mutex<ChannelController>>,
+    server: &ServerHandle,
+) -> io::Result<()> {
+    if path.exists() {
+        tokio::fs::remove_file(path).await?;
+    }
+
+    let listener = UnixListener::bind(path)?;
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        let Ok(Some(line)) = lines.next_line().await else {
+            continue;
+        };
+
+        match serde_json::from_str::<HandoffMessage>(&line) {
+            Ok(HandoffMessage::Request { pid, version }) => {
+                info!("Hand over channels to ffplayout v{version} (pid {pid})");
+
+                // successor binds its own handoff socket, when it has all channels
+                drop(listener);
+                tokio::fs::remove_file(path).await?;
+
+                release_channels(controllers, &mut writer).await?;
+                server.stop(true).await;
+
+                return Ok(());
+            }
+            Ok(msg) => warn!("Unexpected handoff message: {msg:?}"),
+            Err(e) => error!("Invalid handoff message: {e}"),
+        }
+    }
+}
+
+/// Handle the engine upgrade over the handoff socket.
+///
+/// When an old engine is running (`predecessor`), its channels are taken over.
+/// All other channels are started over the normal startup queue.
+/// Then the engine waits itself for a successor.
+pub async fn handoff_task(
+    path: PathBuf,
+    predecessor: Option<UnixStream>,
+    controllers: Arc<Mutex<ChannelController>>,
+    mut startup_list: Vec<ChannelManager>,
+    server: ServerHandle,
+) {
+    if let Some(stream) = predecessor {
+        if let Err(e) = take_over(stream, &mut startup_list).await {
+            error!("Engine handoff failed: {e}");
+        }
+    }
+
+    let startup = controllers.lock().await.startup.clone();
+    startup.staged_start(startup_list).await;
+
+    if let Err(e) = serve(&path, &controllers, &server).await {
+        error!("Handoff socket <b><magenta>{path:?}</></b>: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handoff_message_format() {
+        let line = HandoffMessage::Released { channel_id: 2 }.to_line();
+
+        assert_eq!(line, "{\"type\":\"released\",\"channel_id\":2}\n");
+        assert_eq!(
+            serde_json::from_str::<HandoffMessage>(line.trim()).unwrap(),
+            HandoffMessage::Released { channel_id: 2 }
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use std::{
+    fmt,
+    process::{Command, Stdio},
+};
+
+use serde::{Deserialize, Serialize};
+use shlex::split;
+use ts_rs::TS;
+
+use crate::vec_strings;
+
+/// Render node of the GPU, for VAAPI and QSV.
+pub const RENDER_NODE: &str = "/dev/dri/renderD128";
+
+/// Options of libx264 with a value, which the hardware encoders don't know.
+const X264_OPTIONS: [&str; 8] = [
+    "-x264-params",
+    "-x264opts",
+    "-preset",
+    "-tune",
+    "-profile:v",
+    "-level",
+    "-level:v",
+    "-crf",
+];
+
+/// Hardware for decoding, scaling and encoding the video.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+#[serde(rename_all = "lowercase")]
+pub enum HwAccel {
+    #[default]
+    None,
+    /// First hardware, which is found at startup.
+    Auto,
+    Nvenc,
+    Qsv,
+    Vaapi,
+}
+
+impl HwAccel {
+    pub fn new(s: &str) -> Self {
+        match s {
+            "auto" => Self::Auto,
+            "nvenc" => Self::Nvenc,
+            "qsv" => Self::Qsv,
+            "vaapi" => Self::Vaapi,
+            _ => Self::None,
+        }
+    }
+
+    pub fn is_hardware(self) -> bool {
+        matches!(self, Self::Nvenc | Self::Qsv | Self::Vaapi)
+    }
+
+    /// H.264 encoder.
+    pub fn encoder(self) -> &'static str {
+        match self {
+            Self::Nvenc => "h264_nvenc",
+            Self::Qsv => "h264_qsv",
+            Self::Vaapi => "h264_vaapi",
+            Self::None | Self::Auto => "libx264",
+        }
+    }
+
+    /// Encoder options for keyframes, preset and profile, the bitrate options are set by the caller.
+    pub fn encoder_options(self, gop: i64, cbr: bool) -> String {
+        match self {
+            Self::Nvenc => format!(
+                "-g {gop} -keyint_min {gop} -no-scenecut 1 -forced-idr 1 -preset p4 -tune ll -profile:v main{}",
+                if cbr { " -rc cbr" } else { "" }
+            ),
+            Self::Qsv => format!("-g {gop} -preset faster -look_ahead 0 -profile:v main"),
+            Self::Vaapi => format!(
+                "-g {gop} -profile:v main{}",
+                if cbr { " -rc_mode CBR" } else { "" }
+            ),
+            Self::None | Self::Auto if cbr => format!(
+                "-x264-params keyint={gop}:min-keyint={gop}:scenecut=-1:nal-hrd=cbr -preset faster -profile:v Main"
+            ),
+            Self::None | Self::Auto => format!(
+                "-x264-params keyint={gop}:min-keyint={gop}:scenecut=-1 -preset faster -tune zerolatency -profile:v Main"
+            ),
+        }
+    }
+
+    /// Option for the constant quality, which replaces the CRF of libx264.
+    fn quality_option(self) -> &'static str {
+        match self {
+            Self::Nvenc => "-cq",
+            Self::Qsv => "-global_quality",
+            Self::Vaapi => "-qp",
+            Self::None | Self::Auto => "-crf",
+        }
+    }
+
+    /// Options before the input, to decode the clips on the hardware.
+    ///
+    /// The frames go back to the system memory, so the filters work like before.
+    /// QSV decodes over VAAPI, which needs no extra setup on Linux.
+    pub fn decoder_args(self) -> Vec<String> {
+        match self {
+            Self::Nvenc => vec_strings!["-hwaccel", "cuda"],
+            Self::Qsv | Self::Vaapi => {
+                vec_strings!["-hwaccel", "vaapi", "-hwaccel_device", RENDER_NODE]
+            }
+            Self::None | Self::Auto => vec![],
+        }
+    }
+
+    /// Global options, which create the device for the upload filter.
+    pub fn device_args(self) -> String {
+        match self {
+            Self::Qsv => "-init_hw_device qsv=hw -filter_hw_device hw".to_string(),
+            Self::Vaapi => format!("-init_hw_device vaapi=hw:{RENDER_NODE} -filter_hw_device hw"),
+            Self::Nvenc | Self::None | Self::Auto => String::new(),
+        }
+    }
+
+    /// Filter, which moves the frames to the hardware.
+    pub fn upload_filter(self) -> Option<&'static str> {
+        match self {
+            Self::Nvenc => Some("hwupload_cuda"),
+            Self::Qsv => Some("format=nv12,hwupload=extra_hw_frames=64"),
+            Self::Vaapi => Some("format=nv12,hwupload"),
+            Self::None | Self::Auto => None,
+        }
+    }
+
+    /// Scale filter, on the hardware after the upload.
+    pub fn scale_filter(self, width: i64, height: i64) -> String {
+        match self {
+            Self::Nvenc => format!("scale_cuda=w={width}:h={height}"),
+            Self::Qsv => format!("scale_qsv=w={width}:h={height}"),
+            Self::Vaapi => format!("scale_vaapi=w={width}:h={height}"),
+            Self::None | Self::Auto => format!("scale=w={width}:h={height}"),
+        }
+    }
+
+    /// Use the hardware encoder in output parameters, which are written for libx264.
+    ///
+    /// The x264 options are replaced by the options of the hardware encoder,
+    /// a CRF becomes the quality value of the encoder.
+    pub fn translate_x264(self, cmd: Vec<String>, fps: f64) -> Vec<String> {
+        if !self.is_hardware() || !cmd.iter().any(|c| c == "libx264") {
+            return cmd;
+        }
+
+        let value = |name: &str| {
+            cmd.iter()
+                .position(|c| c == name)
+                .and_then(|i| cmd.get(i + 1))
+                .cloned()
+        };
+        let gop = value("-x264-params")
+            .or_else(|| value("-x264opts"))
+            .and_then(|p| {
+                p.split(':')
+                    .find_map(|o| o.strip_prefix("keyint=").and_then(|k| k.parse().ok()))
+            })
+            .unwrap_or((fps * 2.0).round() as i64);
+        let quality = value("-crf");
+        let mut params = vec![];
+        let mut args = cmd.into_iter();
+
+        while let Some(arg) = args.next() {
+            if X264_OPTIONS.contains(&arg.as_str()) {
+                args.next();
+            } else if arg == "libx264" {
+                params.push(self.encoder().to_string());
+                params.append(&mut split(&self.encoder_options(gop, false)).unwrap_or_default());
+
+                if let Some(q) = &quality {
+                    params.append(&mut vec_strings![self.quality_option(), q]);
+                }
+            } else {
+                params.push(arg);
+            }
+        }
+
+        params
+    }
+
+    /// Encode some frames, the encoder works only when the driver and the device are there.
+    fn test_encode(self) -> bool {
+        let mut args = vec_strings!["-hide_banner", "-v", "error"];
+        args.append(&mut split(&self.device_args()).unwrap_or_default());
+        args.append(&mut vec_strings![
+            "-f",
+            "lavfi",
+            "-i",
+            "color=s=256x144:d=0.2",
+            "-vf",
+            self.upload_filter().unwrap_or("null"),
+            "-c:v",
+            self.encoder(),
+            "-f",
+            "null",
+            "-"
+        ]);
+
+        Command::new("ffmpeg")
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success())
+    }
+}
+
+impl fmt::Display for HwAccel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HwAccel::None => write!(f, "none"),
+            HwAccel::Auto => write!(f, "auto"),
+            HwAccel::Nvenc => write!(f, "nvenc"),
+            HwAccel::Qsv => write!(f, "qsv"),
+            HwAccel::Vaapi => write!(f, "vaapi"),
+        }
+    }
+}
+
+/// Hardware encoders, which ffmpeg can use on this machine.
+pub fn detect() -> Vec<HwAccel> {
+    let Ok(output) = Command::new("ffmpeg")
+        .args(["-hide_banner", "-encoders"])
+        .stderr(Stdio::null())
+        .output()
+    else {
+        return vec![];
+    };
+    let encoders = String::from_utf8_lossy(&output.stdout);
+
+    [HwAccel::Nvenc, HwAccel::Qsv, HwAccel::Vaapi]
+        .into_iter()
+        .filter(|hw| encoders.contains(hw.encoder()) && hw.test_encode())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hwaccel_x264_params() {
+        let cmd = split(
+            "-c:v libx264 -crf 23 -x264-params keyint=50:min-keyint=25:scenecut=-1 -maxrate 1300k \
+            -preset faster -tune zerolatency -profile:v Main -level 3.1 -c:a aac -f hls live/stream.m3u8",
+        )
+        .unwrap();
+
+        assert_eq!(HwAccel::None.translate_x264(cmd.clone(), 25.0), cmd);
+        assert_eq!(
+            HwAccel::Nvenc.translate_x264(cmd, 25.0).join(" "),
+            "-c:v h264_nvenc -g 50 -keyint_min 50 -no-scenecut 1 -forced-idr 1 -preset p4 -tune ll -profile:v main \
+            -cq 23 -maxrate 1300k -c:a aac -f hls live/stream.m3u8"
+        );
+
+        let cmd = split("-c:v libx264 -b:v 2000k -f flv rtmp://127.0.0.1/live").unwrap();
+
+        assert_eq!(
+            HwAccel::Vaapi.translate_x264(cmd, 30.0).join(" "),
+            "-c:v h264_vaapi -g 60 -profile:v main -b:v 2000k -f flv rtmp://127.0.0.1/live"
+        );
+    }
+}
@@ -0,0 +1,374 @@
+/// Log sinks beside the log files: syslog, journald and a remote HTTP endpoint.
+///
+/// They get the same lines as the log files of the channels, without the color tags.
+/// A remote URL which ends with `/loki/api/v1/push` gets the Loki push format,
+/// other URLs get a JSON array of the lines.
+use std::{collections::BTreeMap, io};
+
+#[cfg(target_family = "unix")]
+use std::os::unix::net::UnixDatagram;
+
+use chrono::{DateTime, Local};
+use flexi_logger::{writers::LogWriter, DeferredNow};
+use log::*;
+use serde_json::{json, Value};
+use tokio::{
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    time::{interval, Duration},
+};
+
+use super::ARGS;
+use crate::utils::logging::{record_channel, strip_tags};
+
+const IDENTIFIER: &str = "ffplayout";
+
+#[cfg(target_family = "unix")]
+const SYSLOG_SOCKET: &str = "/dev/log";
+
+#[cfg(target_family = "unix")]
+const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Lines are pushed in batches, at least every few seconds.
+const REMOTE_INTERVAL: Duration = Duration::from_secs(2);
+const REMOTE_BATCH: usize = 500;
+
+/// Lines which are kept, while the remote endpoint is not reachable.
+const REMOTE_BUFFER: usize = 10_000;
+
+/// Syslog severity of the log level.
+fn severity(level: Level) -> u8 {
+    match level {
+        Level::Error => 3,
+        Level::Warn => 4,
+        Level::Info => 6,
+        Level::Debug | Level::Trace => 7,
+    }
+}
+
+/// Message in the syslog format of the local socket, with the facility `user`.
+fn syslog_line(level: Level, channel: i32, msg: &str) -> String {
+    format!(
+        "<{}>{IDENTIFIER}[{}]: [{channel}] {msg}",
+        8 + severity(level),
+        std::process::id()
+    )
+}
+
+/// Fields of the native journal protocol, multiline values are sent with their length.
+fn journal_entry(level: Level, channel: i32, msg: &str) -> Vec<u8> {
+    let mut entry = vec![];
+
+    for (key, value) in [
+        ("MESSAGE", msg.to_string()),
+        ("PRIORITY", severity(level).to_string()),
+        ("SYSLOG_IDENTIFIER", IDENTIFIER.to_string()),
+        ("FFPLAYOUT_CHANNEL", channel.to_string()),
+    ] {
+        entry.extend_from_slice(key.as_bytes());
+
+        if value.contains('\n') {
+            entry.push(b'\n');
+            entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+            entry.extend_from_slice(value.as_bytes());
+        } else {
+            entry.push(b'=');
+            entry.extend_from_slice(value.as_bytes());
+        }
+
+        entry.push(b'\n');
+    }
+
+    entry
+}
+
+#[cfg(target_family = "unix")]
+pub struct SyslogWriter {
+    socket: UnixDatagram,
+}
+
+#[cfg(target_family = "unix")]
+impl SyslogWriter {
+    fn new() -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(SYSLOG_SOCKET)?;
+
+        Ok(Self { socket })
+    }
+}
+
+#[cfg(target_family = "unix")]
+impl LogWriter for SyslogWriter {
+    fn write(&self, _now: &mut DeferredNow, record: &Record) -> io::Result<()> {
+        let line = syslog_line(
+            record.level(),
+            record_channel(record),
+            &strip_tags(&record.args().to_string()),
+        );
+
+        self.socket.send(line.as_bytes()).map(|_| ())
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(target_family = "unix")]
+pub struct JournaldWriter {
+    socket: UnixDatagram,
+}
+
+#[cfg(target_family = "unix")]
+impl JournaldWriter {
+    fn new() -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(JOURNALD_SOCKET)?;
+
+        Ok(Self { socket })
+    }
+}
+
+#[cfg(target_family = "unix")]
+impl LogWriter for JournaldWriter {
+    fn write(&self, _now: &mut DeferredNow, record: &Record) -> io::Result<()> {
+        let entry = journal_entry(
+            record.level(),
+            record_channel(record),
+            &strip_tags(&record.args().to_string()),
+        );
+
+        self.socket.send(&entry).map(|_| ())
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct RemoteLine {
+    time: DateTime<Local>,
+    level: Level,
+    channel: i32,
+    message: String,
+}
+
+/// Hands the lines to the push task, so logging never waits for the network.
+pub struct RemoteWriter {
+    sender: UnboundedSender<RemoteLine>,
+}
+
+impl LogWriter for RemoteWriter {
+    fn write(&self, now: &mut DeferredNow, record: &Record) -> io::Result<()> {
+        let line = RemoteLine {
+            time: *now.now(),
+            level: record.level(),
+            channel: record_channel(record),
+            message: strip_tags(&record.args().to_string()),
+        };
+
+        self.sender.send(line).map_err(io::Error::other)
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn is_loki(url: &str) -> bool {
+    url.trim_end_matches('/').ends_with("/loki/api/v1/push")
+}
+
+/// Body of the push request, Loki streams are grouped by channel and level.
+fn remote_body(url: &str, lines: &[RemoteLine]) -> Value {
+    if !is_loki(url) {
+        return Value::Array(
+            lines
+                .iter()
+                .map(|l| {
+                    json!({
+                        "time": l.time.to_rfc3339(),
+                        "level": l.level.as_str().to_lowercase(),
+                        "channel": l.channel,
+                        "message": l.message,
+                    })
+                })
+                .collect(),
+        );
+    }
+
+    let mut streams: BTreeMap<(i32, Level), Vec<Value>> = BTreeMap::new();
+
+    for l in lines {
+        let nanos = l.time.timestamp_nanos_opt().unwrap_or_default();
+
+        streams
+            .entry((l.channel, l.level))
+            .or_default()
+            .push(json!([nanos.to_string(), l.message]));
+    }
+
+    json!({
+        "streams": streams
+            .into_iter()
+            .map(|((channel, level), values)| {
+                json!({
+                    "stream": {
+                        "job": IDENTIFIER,
+                        "channel": channel.to_string(),
+                        "level": level.as_str().to_lowercase(),
+                    },
+                    "values": values,
+                })
+            })
+            .collect::<Vec<_>>()
+    })
+}
+
+/// Push the collected lines to the remote endpoint. Failed batches are sent again with the next one.
+async fn remote_push(url: String, mut receiver: UnboundedReceiver<RemoteLine>) {
+    let client = reqwest::Client::new();
+    let mut tick = interval(REMOTE_INTERVAL);
+    let mut pending: Vec<RemoteLine> = vec![];
+    let mut failed = false;
+
+    loop {
+        tokio::select! {
+            line = receiver.recv() => match line {
+                Some(line) => {
+                    pending.push(line);
+
+                    // after a failure, the next attempt waits for the interval
+                    if pending.len() < REMOTE_BATCH || failed {
+                        continue;
+                    }
+                }
+                None => break,
+            },
+            _ = tick.tick() => {}
+        }
+
+        if pending.is_empty() {
+            continue;
+        }
+
+        let body = remote_body(&url, &pending);
+
+        match client
+            .post(&url)
+            .timeout(REMOTE_INTERVAL * 5)
+            .json(&body)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+        {
+            Ok(_) => {
+                pending.clear();
+                failed = false;
+            }
+            Err(e) => {
+                // goes only to the console, not back to the remote sink
+                if !failed {
+                    error!("Push log lines to <b><magenta>{url}</></b> failed: {e}");
+                }
+
+                failed = true;
+
+                if pending.len() > REMOTE_BUFFER {
+                    pending.drain(..pending.len() - REMOTE_BUFFER);
+                }
+            }
+        }
+    }
+}
+
+/// Log sinks, which are enabled by the arguments of this instance.
+///
+/// The remote push runs as task, so this needs to be called inside the runtime.
+pub fn log_sinks() -> Vec<Box<dyn LogWriter>> {
+    let mut sinks: Vec<Box<dyn LogWriter>> = vec![];
+
+    #[cfg(target_family = "unix")]
+    {
+        if ARGS.log_syslog {
+            match SyslogWriter::new() {
+                Ok(writer) => sinks.push(Box::new(writer)),
+                Err(e) => eprintln!("Syslog socket {SYSLOG_SOCKET} not available: {e}"),
+            }
+        }
+
+        if ARGS.log_journald {
+            match JournaldWriter::new() {
+                Ok(writer) => sinks.push(Box::new(writer)),
+                Err(e) => eprintln!("Journald socket {JOURNALD_SOCKET} not available: {e}"),
+            }
+        }
+    }
+
+    #[cfg(not(target_family = "unix"))]
+    if ARGS.log_syslog || ARGS.log_journald {
+        eprintln!("Syslog and journald are only available on unix systems");
+    }
+
+    if let Some(url) = ARGS.log_remote.clone() {
+        let (sender, receiver) = unbounded_channel();
+
+        tokio::spawn(remote_push(url, receiver));
+        sinks.push(Box::new(RemoteWriter { sender }));
+    }
+
+    sinks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_sink_formats() {
+        assert!(syslog_line(Level::Error, 1, "Source not found").starts_with("<11>ffplayout["));
+        assert!(syslog_line(Level::Info, 2, "Play").ends_with("]: [2] Play"));
+
+        let entry = journal_entry(Level::Warn, 1, "line 1\nline 2");
+        let mut expected = b"MESSAGE\n".to_vec();
+        expected.extend_from_slice(&13u64.to_le_bytes());
+        expected.extend_from_slice(b"line 1\nline 2\nPRIORITY=4\n");
+
+        assert!(entry.starts_with(&expected));
+        assert!(entry.ends_with(b"SYSLOG_IDENTIFIER=ffplayout\nFFPLAYOUT_CHANNEL=1\n"));
+
+        let time = Local::now();
+        let lines = [
+            RemoteLine {
+                time,
+                level: Level::Error,
+                channel: 1,
+                message: "Crash".to_string(),
+            },
+            RemoteLine {
+                time,
+                level: Level::Error,
+                channel: 1,
+                message: "Restart".to_string(),
+            },
+            RemoteLine {
+                time,
+                level: Level::Info,
+                channel: 2,
+                message: "Play".to_string(),
+            },
+        ];
+
+        let loki = remote_body("http://127.0.0.1:3100/loki/api/v1/push", &lines);
+
+        assert_eq!(loki["streams"].as_array().unwrap().len(), 2);
+        assert_eq!(loki["streams"][0]["stream"]["level"], "error");
+        assert_eq!(loki["streams"][0]["values"][1][1], "Restart");
+
+        let plain = remote_body("https://logs.example.org/ingest", &lines);
+
+        assert_eq!(plain.as_array().unwrap().len(), 3);
+        assert_eq!(plain[2]["channel"], 2);
+        assert_eq!(plain[2]["level"], "info");
+    }
+}
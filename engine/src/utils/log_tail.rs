@@ -0,0 +1,253 @@
+/// Read the log files of the channels, for the log view and the log tail of the API.
+///
+/// Rotated files can be compressed with gzip. Channels with a shared log file have their
+/// lines in the log file of the instance, with the channel as prefix.
+use std::{
+    io::{Read, SeekFrom},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::LazyLock,
+};
+
+use flate2::read::GzDecoder;
+use log::Level;
+use path_clean::PathClean;
+use regex::Regex;
+use serde::Serialize;
+use tokio::{
+    fs,
+    io::{AsyncReadExt, AsyncSeekExt},
+};
+
+use crate::utils::{
+    errors::ServiceError,
+    logging::{log_file_path, log_rotation},
+};
+
+/// Bytes from the end of the log file, which are read for the tail.
+const TAIL_BYTES: u64 = 20 * 1024 * 1024;
+
+const MAX_LIMIT: usize = 1000;
+
+static LINE_START: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\[([^\]]+)\] \[\s*([A-Z]+)\] ").unwrap());
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LogLine {
+    pub time: String,
+    pub level: String,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LogPage {
+    pub total: usize,
+    pub page: usize,
+    pub lines: Vec<LogLine>,
+}
+
+/// Log file with the lines of the channel, and whether it is the shared file of the instance.
+pub async fn channel_log_path(channel_id: i32, date: &str) -> (PathBuf, bool) {
+    let shared = channel_id > 0 && log_rotation(channel_id).shared;
+    let id = if shared { 0 } else { channel_id };
+    let date_str = if date.is_empty() {
+        String::new()
+    } else {
+        format!("_{date}")
+    };
+    let path = log_file_path()
+        .join(format!("ffplayout_{id}{date_str}.log"))
+        .clean();
+
+    if date.is_empty() || fs::metadata(&path).await.is_ok() {
+        return (path, shared);
+    }
+
+    let mut gz_path = path.clone().into_os_string();
+    gz_path.push(".gz");
+
+    if fs::metadata(&gz_path).await.is_ok() {
+        return (gz_path.into(), shared);
+    }
+
+    (path, shared)
+}
+
+/// Cut the content to the last bytes of the limit, starting with a full line.
+fn cut_tail(bytes: &[u8], limit: u64) -> &[u8] {
+    if bytes.len() as u64 <= limit {
+        return bytes;
+    }
+
+    let tail = &bytes[bytes.len() - limit as usize..];
+
+    match tail.iter().position(|b| *b == b'\n') {
+        Some(pos) => &tail[pos + 1..],
+        None => tail,
+    }
+}
+
+/// Content of the log file, only the end of it when a limit is given.
+pub async fn read_log(path: &Path, limit: Option<u64>) -> Result<String, ServiceError> {
+    let limit = limit.unwrap_or(u64::MAX);
+
+    if path.extension().is_some_and(|e| e == "gz") {
+        let data = fs::read(path).await?;
+        let mut content = vec![];
+        GzDecoder::new(&data[..]).read_to_end(&mut content)?;
+
+        return Ok(String::from_utf8_lossy(cut_tail(&content, limit)).to_string());
+    }
+
+    let mut file = fs::File::open(path).await?;
+    let size = file.metadata().await?.len();
+    let mut content = vec![];
+
+    if size > limit {
+        // one byte more, to know if the first line is complete
+        file.seek(SeekFrom::Start(size - limit - 1)).await?;
+    }
+
+    file.read_to_end(&mut content).await?;
+
+    Ok(String::from_utf8_lossy(cut_tail(&content, limit)).to_string())
+}
+
+/// Lines of the channel from the shared log file, without the channel prefix.
+pub fn channel_lines(content: &str, channel_id: i32) -> String {
+    let prefix = format!("[Channel {channel_id}] ");
+    let mut keep = false;
+    let mut lines = vec![];
+
+    for line in content.lines() {
+        if let Some(start) = LINE_START.find(line) {
+            let message = &line[start.end()..];
+            keep = message.starts_with(&prefix);
+
+            if keep {
+                lines.push(format!("{}{}", start.as_str(), &message[prefix.len()..]));
+            }
+        } else if keep {
+            lines.push(line.to_string());
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// Split the content in log lines, lines without time and level belong to the line before.
+fn parse_lines(content: &str) -> Vec<LogLine> {
+    let mut lines: Vec<LogLine> = vec![];
+
+    for line in content.lines() {
+        if let Some(cap) = LINE_START.captures(line) {
+            lines.push(LogLine {
+                time: cap[1].to_string(),
+                level: cap[2].to_lowercase(),
+                message: line[cap[0].len()..].to_string(),
+            });
+        } else if let Some(last) = lines.last_mut() {
+            last.message.push('\n');
+            last.message.push_str(line);
+        }
+    }
+
+    lines
+}
+
+/// Lowest level of the lines, empty gives all.
+fn min_level(level: &str) -> Result<Level, String> {
+    match level.trim().to_lowercase().as_str() {
+        "" => Ok(Level::Trace),
+        "warning" => Ok(Level::Warn),
+        l => Level::from_str(l).map_err(|_| format!("Unknown log level: {level}")),
+    }
+}
+
+/// Newest lines first, with the given level or higher.
+fn paginate(lines: Vec<LogLine>, min: Level, page: usize, limit: usize) -> LogPage {
+    let page = page.max(1);
+    let limit = limit.clamp(1, MAX_LIMIT);
+    let lines = lines
+        .into_iter()
+        .rev()
+        .filter(|l| Level::from_str(&l.level).is_ok_and(|l| l <= min))
+        .collect::<Vec<_>>();
+
+    LogPage {
+        total: lines.len(),
+        page,
+        lines: lines
+            .into_iter()
+            .skip((page - 1) * limit)
+            .take(limit)
+            .collect(),
+    }
+}
+
+/// Recent lines of the channel log, filtered by level and split in pages.
+pub async fn tail_log(
+    channel_id: i32,
+    date: &str,
+    level: &str,
+    page: usize,
+    limit: usize,
+) -> Result<LogPage, ServiceError> {
+    let min = min_level(level).map_err(ServiceError::BadRequest)?;
+    let (path, shared) = channel_log_path(channel_id, date).await;
+    let mut content = read_log(&path, Some(TAIL_BYTES)).await?;
+
+    if shared {
+        content = channel_lines(&content, channel_id);
+    }
+
+    Ok(paginate(parse_lines(&content), min, page, limit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_tail_lines() {
+        let content = "[2026-10-17 10:00:00.000000+02:00] [ INFO] Start\n\
+            [2026-10-17 10:00:01.000000+02:00] [ERROR] [Channel 2] Source not found\n\
+            ffmpeg: no such file\n\
+            [2026-10-17 10:00:02.000000+02:00] [ WARN] [Channel 1] Generate filler\n\
+            [2026-10-17 10:00:03.000000+02:00] [ INFO] [Channel 2] Play <b>clip.mp4</b>";
+
+        let channel = channel_lines(content, 2);
+
+        assert_eq!(
+            channel,
+            "[2026-10-17 10:00:01.000000+02:00] [ERROR] Source not found\n\
+            ffmpeg: no such file\n\
+            [2026-10-17 10:00:03.000000+02:00] [ INFO] Play <b>clip.mp4</b>"
+        );
+
+        let lines = parse_lines(&channel);
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].level, "error");
+        assert_eq!(lines[0].message, "Source not found\nffmpeg: no such file");
+
+        let all = paginate(parse_lines(content), min_level("").unwrap(), 1, 2);
+
+        assert_eq!(all.total, 4);
+        assert_eq!(all.lines[0].message, "[Channel 2] Play <b>clip.mp4</b>");
+        assert_eq!(all.lines[1].level, "warn");
+
+        let second = paginate(parse_lines(content), Level::Trace, 2, 2);
+
+        assert_eq!(second.lines.len(), 2);
+        assert_eq!(second.lines[1].message, "Start");
+
+        let warnings = paginate(parse_lines(content), min_level("warning").unwrap(), 1, 10);
+
+        assert_eq!(warnings.total, 2);
+        assert!(min_level("critical").is_err());
+
+        assert_eq!(cut_tail(b"line 1\nline 2\nline 3", 9), b"line 3");
+        assert_eq!(cut_tail(b"line 1", 10), b"line 1");
+    }
+}
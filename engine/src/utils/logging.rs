@@ -1,9 +1,10 @@
 use std::{
-    collections::{hash_map, HashMap},
+    collections::{HashMap, VecDeque},
     env,
-    io::{self, ErrorKind, Write},
+    io::{self, Write},
     path::PathBuf,
-    sync::{Arc, RwLock},
+    sync::{Arc, LazyLock, RwLock},
+    time::{Duration, Instant},
 };
 
 use flexi_logger::{
@@ -20,12 +21,86 @@ use tokio::sync::Mutex;
 use super::ARGS;
 
 use crate::db::GLOBAL_SETTINGS;
+use crate::player::controller::ProcessUnit;
 use crate::utils::{
+    config::Logging,
+    log_sinks::log_sinks,
     mail::{mail_queue, MailQueue},
     time_machine::time_now,
 };
 
 const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.6f%:z";
+const CRASH_DUMP_LINES: usize = 200;
+
+/// Rotation of the channel log files, set from the channel configuration.
+static LOG_ROTATION: LazyLock<RwLock<HashMap<i32, LogRotation>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogRotation {
+    /// Size in bytes, 0 rotates only at the end of the day.
+    max_size: u64,
+    max_files: usize,
+    compress: bool,
+    pub shared: bool,
+}
+
+impl LogRotation {
+    pub fn new(logging: &Logging) -> Self {
+        Self {
+            max_size: logging.max_size.max(0) as u64 * 1024 * 1024,
+            max_files: ARGS
+                .log_backup_count
+                .unwrap_or(logging.max_files.max(1) as usize),
+            compress: logging.compress,
+            shared: logging.shared,
+        }
+    }
+
+    fn criterion(&self) -> Criterion {
+        if self.max_size > 0 {
+            Criterion::AgeOrSize(Age::Day, self.max_size)
+        } else {
+            Criterion::Age(Age::Day)
+        }
+    }
+
+    fn cleanup(&self) -> Cleanup {
+        if self.compress {
+            Cleanup::KeepCompressedFiles(self.max_files)
+        } else {
+            Cleanup::KeepLogFiles(self.max_files)
+        }
+    }
+}
+
+impl Default for LogRotation {
+    fn default() -> Self {
+        Self {
+            max_size: 0,
+            max_files: ARGS.log_backup_count.unwrap_or(14),
+            compress: false,
+            shared: false,
+        }
+    }
+}
+
+/// Set the rotation of the channel log file, the writer is rebuilt with the next line.
+pub fn set_log_rotation(channel: i32, logging: &Logging) {
+    LOG_ROTATION
+        .write()
+        .unwrap()
+        .insert(channel, LogRotation::new(logging));
+}
+
+pub fn log_rotation(channel: i32) -> LogRotation {
+    LOG_ROTATION
+        .read()
+        .unwrap()
+        .get(&channel)
+        .copied()
+        .unwrap_or_default()
+}
 
 #[derive(Debug)]
 pub struct Target;
@@ -72,7 +147,7 @@ impl LogWriter for LogConsole {
 
 pub struct MultiFileLogger {
     log_path: PathBuf,
-    writers: RwLock<HashMap<i32, Arc<FileLogWriter>>>,
+    writers: RwLock<HashMap<i32, (LogRotation, Arc<FileLogWriter>)>>,
 }
 
 impl MultiFileLogger {
@@ -84,58 +159,69 @@ impl MultiFileLogger {
     }
 
     fn get_writer(&self, channel: i32) -> io::Result<Arc<FileLogWriter>> {
+        let rotation = log_rotation(channel);
+
         // Lock the writers HashMap
         let mut writers = self.writers.write().unwrap();
 
-        // Check if the writer already exists
-        if let hash_map::Entry::Vacant(entry) = writers.entry(channel) {
-            let writer = FileLogWriter::builder(
-                FileSpec::default()
-                    .suppress_timestamp()
-                    .directory(&self.log_path)
-                    .basename("ffplayout")
-                    .discriminant(channel.to_string()),
-            )
-            .format(file_formatter)
-            .append()
-            .rotate(
-                Criterion::Age(Age::Day),
-                Naming::TimestampsCustomFormat {
-                    current_infix: Some(""),
-                    format: "%Y-%m-%d",
-                },
-                Cleanup::KeepLogFiles(ARGS.log_backup_count.unwrap_or(14)),
-            )
-            .try_build()
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+        // Reuse the writer, as long as the rotation is not changed
+        if let Some((current, writer)) = writers.get(&channel) {
+            if *current == rotation {
+                return Ok(writer.clone());
+            }
 
-            let arc_writer = Arc::new(writer);
-            entry.insert(arc_writer.clone());
-            Ok(arc_writer)
-        } else {
-            Ok(writers.get(&channel).unwrap().clone())
+            writer.shutdown();
         }
+
+        let writer = FileLogWriter::builder(
+            FileSpec::default()
+                .suppress_timestamp()
+                .directory(&self.log_path)
+                .basename("ffplayout")
+                .discriminant(channel.to_string()),
+        )
+        .format(file_formatter)
+        .append()
+        .rotate(
+            rotation.criterion(),
+            Naming::TimestampsCustomFormat {
+                current_infix: Some(""),
+                format: "%Y-%m-%d",
+            },
+            rotation.cleanup(),
+        )
+        .try_build()
+        .map_err(|e| io::Error::other(e.to_string()))?;
+
+        let arc_writer = Arc::new(writer);
+        writers.insert(channel, (rotation, arc_writer.clone()));
+
+        Ok(arc_writer)
     }
 }
 
 impl LogWriter for MultiFileLogger {
     fn write(&self, now: &mut DeferredNow, record: &Record) -> io::Result<()> {
-        let channel = i32::try_from(
-            record
-                .key_values()
-                .get("channel".into())
-                .and_then(|v| Value::to_i64(&v))
-                .unwrap_or(0),
-        )
-        .unwrap_or(0);
+        let channel = record_channel(record);
+
+        // Shared channels write to the log file of the instance, with the channel as prefix
+        if channel > 0 && log_rotation(channel).shared {
+            return self.get_writer(0)?.write(
+                now,
+                &Record::builder()
+                    .args(format_args!("[Channel {channel}] {}", record.args()))
+                    .level(record.level())
+                    .target(record.target())
+                    .build(),
+            );
+        }
 
-        let writer = self.get_writer(channel)?;
-        writer.write(now, record)
+        self.get_writer(channel)?.write(now, record)
     }
 
     fn flush(&self) -> io::Result<()> {
         let writers = self.writers.read().unwrap();
-        for writer in writers.values() {
+        for (_, writer) in writers.values() {
             writer.flush()?;
         }
         Ok(())
@@ -154,14 +240,7 @@ impl LogMailer {
 
 impl LogWriter for LogMailer {
     fn write(&self, now: &mut DeferredNow, record: &Record<'_>) -> std::io::Result<()> {
-        let id = i32::try_from(
-            record
-                .key_values()
-                .get("channel".into())
-                .and_then(|v| Value::to_i64(&v))
-                .unwrap_or(0),
-        )
-        .unwrap_or(0);
+        let id = record_channel(record);
 
         let message = record.args().to_string();
         let level = record.level();
@@ -198,7 +277,19 @@ impl LogWriter for LogMailer {
     }
 }
 
-fn strip_tags(input: &str) -> String {
+/// Channel id from the key values of the log record, `0` for global messages.
+pub fn record_channel(record: &Record) -> i32 {
+    i32::try_from(
+        record
+            .key_values()
+            .get("channel".into())
+            .and_then(|v| Value::to_i64(&v))
+            .unwrap_or(0),
+    )
+    .unwrap_or(0)
+}
+
+pub fn strip_tags(input: &str) -> String {
     let re = Regex::new(r"<[^>]*>").unwrap();
     re.replace_all(input, "").to_string()
 }
@@ -264,18 +355,50 @@ pub fn log_file_path() -> PathBuf {
     log_path
 }
 
+/// Writes the lines to the log files, or the console, and to the additional sinks.
+pub struct LogSinks {
+    writers: Vec<Box<dyn LogWriter>>,
+}
+
+impl LogWriter for LogSinks {
+    fn write(&self, now: &mut DeferredNow, record: &Record) -> io::Result<()> {
+        for writer in &self.writers {
+            writer.write(now, record)?;
+        }
+
+        Ok(())
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        for writer in &self.writers {
+            writer.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
 fn file_logger() -> Box<dyn LogWriter> {
-    if ARGS.log_to_console {
+    let writer: Box<dyn LogWriter> = if ARGS.log_to_console {
         Box::new(LogConsole)
     } else {
         Box::new(MultiFileLogger::new(log_file_path()))
+    };
+    let mut sinks = log_sinks();
+
+    if sinks.is_empty() {
+        return writer;
     }
+
+    sinks.insert(0, writer);
+
+    Box::new(LogSinks { writers: sinks })
 }
 
 /// Initialize our logging, to have:
 ///
 /// - console logger
-/// - file logger
+/// - file logger, with syslog, journald and remote sinks
 /// - mail logger
 pub fn init_logging(
     mail_queues: Arc<Mutex<Vec<Arc<Mutex<MailQueue>>>>>,
@@ -323,11 +446,106 @@ pub fn init_logging(
         .add_writer("file", file_logger())
         .add_writer("mail", Box::new(LogMailer::new(mail_queues)))
         .start()
-        .map_err(|e| io::Error::new(ErrorKind::Other, e.to_string()))?;
+        .map_err(|e| io::Error::other(e.to_string()))?;
 
     Ok(logger)
 }
 
+/// Bookkeeping for ffmpeg stderr output of one process.
+///
+/// It limits how many lines per second get logged and keeps the last lines,
+/// to write them to a crash dump file when the process failed.
+#[derive(Debug)]
+pub struct StderrLog {
+    id: i32,
+    unit: ProcessUnit,
+    limit: u32,
+    window: Instant,
+    count: u32,
+    suppressed: u32,
+    history: VecDeque<String>,
+    pub failed: bool,
+}
+
+impl StderrLog {
+    pub fn new(logging: &Logging, unit: ProcessUnit, id: i32) -> Self {
+        Self {
+            id,
+            unit,
+            limit: logging.rate(unit),
+            window: Instant::now(),
+            count: 0,
+            suppressed: 0,
+            history: VecDeque::with_capacity(CRASH_DUMP_LINES),
+            failed: false,
+        }
+    }
+
+    /// Remember the line and check if it can be logged without exceeding the rate limit.
+    pub fn push(&mut self, line: &str) -> bool {
+        if self.history.len() >= CRASH_DUMP_LINES {
+            self.history.pop_front();
+        }
+
+        self.history.push_back(line.to_string());
+
+        if line.contains("[error]") || line.contains("[fatal]") {
+            self.failed = true;
+        }
+
+        if self.limit == 0 {
+            return true;
+        }
+
+        if self.window.elapsed() >= Duration::from_secs(1) {
+            if self.suppressed > 0 {
+                warn!(target: Target::file_mail(), channel = self.id; "<bright black>[{}]</> {} log line(s) suppressed", self.unit, self.suppressed);
+            }
+
+            self.window = Instant::now();
+            self.count = 0;
+            self.suppressed = 0;
+        }
+
+        self.count += 1;
+
+        if self.count > self.limit {
+            self.suppressed += 1;
+            return false;
+        }
+
+        true
+    }
+
+    /// Write the last stderr lines to `ffmpeg_crash_<channel>_<unit>.log` in the log folder.
+    pub async fn dump(&self, logging: &Logging) {
+        if !logging.crash_dump {
+            return;
+        }
+
+        let path = logging.path.join(format!(
+            "ffmpeg_crash_{}_{}.log",
+            self.id,
+            self.unit.to_string().to_lowercase()
+        ));
+        let mut content = format!("{}\n", time_now(&None).format(TIME_FORMAT));
+
+        for line in &self.history {
+            content.push_str(line);
+            content.push('\n');
+        }
+
+        match tokio::fs::write(&path, content).await {
+            Ok(_) => {
+                info!(target: Target::file_mail(), channel = self.id; "Write crash dump to <b><magenta>{path:?}</></b>");
+            }
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = self.id; "Write crash dump failed: {e}");
+            }
+        }
+    }
+}
+
 /// Format ingest and HLS logging output
 pub fn log_line(id: i32, line: &str, level: &str) {
     if line.contains("[info]") && level.to_lowercase() == "info" {
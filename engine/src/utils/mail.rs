@@ -10,7 +10,9 @@ use tokio::{
     time::{interval, Duration},
 };
 
-use crate::utils::{config::Mail, errors::ProcessError, round_to_nearest_ten};
+use crate::utils::{
+    config::Mail, errors::ProcessError, notifier::send_notification, round_to_nearest_ten,
+};
 
 #[derive(Clone, Debug)]
 pub struct MailQueue {
@@ -100,7 +102,8 @@ pub async fn send_mail(config: &Mail, msg: String) -> Result<(), ProcessError> {
 
 /// Basic Mail Queue
 ///
-/// Check every give seconds for messages and send them.
+/// Check every give seconds for messages and send them, to the mail recipient
+/// and to the active chat notifiers of the channel.
 pub fn mail_queue(mail_queues: Arc<Mutex<Vec<Arc<Mutex<MailQueue>>>>>) {
     tokio::spawn(async move {
         let sec = 10;
@@ -129,7 +132,9 @@ pub fn mail_queue(mail_queues: Arc<Mutex<Vec<Arc<Mutex<MailQueue>>>>>) {
                     let expire = round_to_nearest_ten(q_lock.config.interval.max(30));
 
                     if interval % expire == 0 && !q_lock.is_empty() {
-                        if q_lock.config.recipient.contains('@') {
+                        if q_lock.config.recipient.contains('@')
+                            || q_lock.config.notifiers.iter().any(|n| n.active)
+                        {
                             tasks.push((q_lock.config.clone(), q_lock.text().clone(), q_lock.id));
                         }
 
@@ -140,8 +145,16 @@ pub fn mail_queue(mail_queues: Arc<Mutex<Vec<Arc<Mutex<MailQueue>>>>>) {
             }
 
             for (config, text, id) in tasks {
-                if let Err(e) = send_mail(&config, text).await {
-                    error!(target: "{file}", channel = id; "Failed to send mail: {e}");
+                for notifier in config.notifiers.iter().filter(|n| n.active) {
+                    if let Err(e) = send_notification(notifier, &config.subject, &text).await {
+                        error!(target: "{file}", channel = id; "Failed to send {} notification: {e}", notifier.kind);
+                    }
+                }
+
+                if config.recipient.contains('@') {
+                    if let Err(e) = send_mail(&config, text).await {
+                        error!(target: "{file}", channel = id; "Failed to send mail: {e}");
+                    }
                 }
             }
         }
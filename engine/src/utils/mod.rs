@@ -16,22 +16,38 @@ use serde::{
 };
 
 pub mod advanced_config;
+pub mod alerts;
 pub mod args_parse;
 pub mod channels;
+pub mod clock;
 pub mod config;
 pub mod control;
+pub mod epg;
 pub mod errors;
 pub mod generator;
+#[cfg(target_family = "unix")]
+pub mod handoff;
+pub mod hwaccel;
+pub mod log_sinks;
+pub mod log_tail;
 pub mod logging;
 pub mod mail;
+pub mod notifier;
 pub mod playlist;
+pub mod rules;
 pub mod system;
 pub mod task_runner;
 pub mod time_machine;
-
-use crate::db::GLOBAL_SETTINGS;
-use crate::player::utils::time_to_sec;
-use crate::utils::{errors::ServiceError, logging::log_file_path};
+pub mod validity;
+pub mod webhook;
+
+use crate::db::{models::TextPreset, GLOBAL_SETTINGS};
+use crate::player::utils::{time_to_sec, Media};
+use crate::utils::{
+    epg::programme_title,
+    errors::ServiceError,
+    log_tail::{channel_lines, channel_log_path, read_log},
+};
 use crate::ARGS;
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
@@ -91,6 +107,44 @@ where
     deserializer.deserialize_any(StringOrNumberVisitor)
 }
 
+impl From<&TextPreset> for TextFilter {
+    fn from(preset: &TextPreset) -> Self {
+        Self {
+            text: Some(preset.text.clone()),
+            x: Some(preset.x.clone()),
+            y: Some(preset.y.clone()),
+            fontsize: Some(preset.fontsize.clone()),
+            line_spacing: Some(preset.line_spacing.clone()),
+            fontcolor: Some(preset.fontcolor.clone()),
+            alpha: Some(preset.alpha.clone()),
+            r#box: Some(preset.r#box.clone()),
+            boxcolor: Some(preset.boxcolor.clone()),
+            boxborderw: Some(preset.boxborderw.clone()),
+        }
+    }
+}
+
+impl TextFilter {
+    /// Resolve the placeholders `{title}`, `{next_title}`, `{time}` and `{channel}` of the text,
+    /// from the clip which is playing when the text is sent.
+    pub fn resolve(&mut self, media: Option<&Media>, channel: &str, time: &str) {
+        let Some(text) = self.text.as_mut().filter(|t| t.contains('{')) else {
+            return;
+        };
+
+        let title = media
+            .map(|m| programme_title(m.title.clone(), &m.source))
+            .unwrap_or_default();
+        let next_title = media.and_then(|m| m.next_title.clone()).unwrap_or_default();
+
+        *text = text
+            .replace("{title}", &title)
+            .replace("{next_title}", &next_title)
+            .replace("{time}", time)
+            .replace("{channel}", channel);
+    }
+}
+
 impl fmt::Display for TextFilter {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let escaped_text = self
@@ -180,22 +234,16 @@ pub fn public_path() -> PathBuf {
 }
 
 pub async fn read_log_file(channel_id: &i32, date: &str) -> Result<String, ServiceError> {
-    let date_str = if date.is_empty() {
-        String::new()
-    } else {
-        format!("_{date}")
-    };
-
-    let log_path = log_file_path()
-        .join(format!("ffplayout_{channel_id}{date_str}.log"))
-        .clean();
+    let (log_path, shared) = channel_log_path(*channel_id, date).await;
     let file_size = fs::metadata(&log_path).await?.len() as f64;
 
     let log_content = if file_size > 5000000.0 {
         error!("Log file to big: {}", sizeof_fmt(file_size));
         format!("The log file is larger ({}) than the hard limit of 5MB, the probability is very high that something is wrong with the playout.\nCheck this on the server with `less {log_path:?}`.", sizeof_fmt(file_size))
+    } else if shared {
+        channel_lines(&read_log(&log_path, None).await?, *channel_id)
     } else {
-        fs::read_to_string(log_path).await?
+        read_log(&log_path, None).await?
     };
 
     Ok(log_content)
@@ -0,0 +1,188 @@
+/// Chat notifiers, which get the same log messages as the mail recipient.
+///
+/// The messages are collected by the mail queue, with the level and interval of the mail settings,
+/// and then sent to every active notifier of the channel.
+use std::{fmt, str::FromStr, time::Duration};
+
+use reqwest::Url;
+use serde_json::json;
+
+use crate::db::models::Notifier;
+use crate::utils::errors::ProcessError;
+
+const NOTIFIER_TIMEOUT: Duration = Duration::from_secs(10);
+
+const TELEGRAM_API: &str = "https://api.telegram.org";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotifierKind {
+    Telegram,
+    Slack,
+    Discord,
+    Matrix,
+}
+
+impl NotifierKind {
+    /// Maximum characters of a message.
+    fn limit(&self) -> usize {
+        match self {
+            Self::Telegram => 4096,
+            Self::Slack => 40000,
+            Self::Discord => 2000,
+            Self::Matrix => 30000,
+        }
+    }
+}
+
+impl FromStr for NotifierKind {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.trim().to_lowercase().as_str() {
+            "telegram" => Ok(Self::Telegram),
+            "slack" => Ok(Self::Slack),
+            "discord" => Ok(Self::Discord),
+            "matrix" => Ok(Self::Matrix),
+            _ => Err(format!("Unknown notifier: {input}")),
+        }
+    }
+}
+
+impl fmt::Display for NotifierKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::Telegram => write!(f, "telegram"),
+            Self::Slack => write!(f, "slack"),
+            Self::Discord => write!(f, "discord"),
+            Self::Matrix => write!(f, "matrix"),
+        }
+    }
+}
+
+/// Check that the notifier has all values, which its kind needs.
+pub fn validate(notifier: &Notifier) -> Result<NotifierKind, String> {
+    let kind = NotifierKind::from_str(&notifier.kind)?;
+    let url_ok = notifier.url.starts_with("http://") || notifier.url.starts_with("https://");
+
+    let missing = match kind {
+        NotifierKind::Telegram => notifier.token.is_empty() || notifier.target.is_empty(),
+        NotifierKind::Slack | NotifierKind::Discord => !url_ok,
+        NotifierKind::Matrix => !url_ok || notifier.token.is_empty() || notifier.target.is_empty(),
+    };
+
+    if missing {
+        return Err(format!("Notifier {kind} is incomplete"));
+    }
+
+    Ok(kind)
+}
+
+/// Messages which are too long for the service are cut, the newest lines get lost.
+fn truncate(text: &str, limit: usize) -> String {
+    if text.chars().count() <= limit {
+        return text.to_string();
+    }
+
+    let mut short = text.chars().take(limit - 4).collect::<String>();
+    short.push_str("\n...");
+
+    short
+}
+
+/// Send the log messages to the chat of the notifier.
+pub async fn send_notification(
+    notifier: &Notifier,
+    subject: &str,
+    msg: &str,
+) -> Result<(), ProcessError> {
+    let kind = validate(notifier).map_err(ProcessError::Custom)?;
+    let text = truncate(&format!("{subject}\n\n{msg}"), kind.limit());
+    let client = reqwest::Client::new();
+
+    let request = match kind {
+        NotifierKind::Telegram => {
+            let api = if notifier.url.is_empty() {
+                TELEGRAM_API
+            } else {
+                notifier.url.trim_end_matches('/')
+            };
+
+            client
+                .post(format!("{api}/bot{}/sendMessage", notifier.token))
+                .json(&json!({"chat_id": notifier.target, "text": text}))
+        }
+        NotifierKind::Slack => client.post(&notifier.url).json(&json!({"text": text})),
+        NotifierKind::Discord => client.post(&notifier.url).json(&json!({"content": text})),
+        NotifierKind::Matrix => {
+            let mut url =
+                Url::parse(&notifier.url).map_err(|e| ProcessError::Custom(e.to_string()))?;
+            let txn = uuid::Uuid::new_v4().to_string();
+
+            url.path_segments_mut()
+                .map_err(|_| "Matrix homeserver is no valid URL")?
+                .pop_if_empty()
+                .extend([
+                    "_matrix",
+                    "client",
+                    "v3",
+                    "rooms",
+                    &notifier.target,
+                    "send",
+                    "m.room.message",
+                    &txn,
+                ]);
+
+            client
+                .put(url)
+                .bearer_auth(&notifier.token)
+                .json(&json!({"msgtype": "m.text", "body": text}))
+        }
+    };
+
+    let resp = request
+        .timeout(NOTIFIER_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| ProcessError::Custom(e.to_string()))?;
+
+    if !resp.status().is_success() {
+        return Err(ProcessError::Custom(format!(
+            "{kind} returns {}",
+            resp.status()
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notifier_validation() {
+        let mut notifier = Notifier {
+            kind: "Telegram".to_string(),
+            token: "123:abc".to_string(),
+            active: true,
+            ..Default::default()
+        };
+
+        assert!(validate(&notifier).is_err());
+
+        notifier.target = "-100200".to_string();
+        assert_eq!(validate(&notifier), Ok(NotifierKind::Telegram));
+
+        notifier.kind = "discord".to_string();
+        assert!(validate(&notifier).is_err());
+
+        notifier.url = "https://discord.com/api/webhooks/1/x".to_string();
+        assert_eq!(validate(&notifier), Ok(NotifierKind::Discord));
+
+        notifier.kind = "irc".to_string();
+        assert!(validate(&notifier).is_err());
+
+        assert_eq!(truncate("abc", 10), "abc");
+        assert_eq!(truncate("abcdefghijkl", 10), "abcdef\n...");
+    }
+}
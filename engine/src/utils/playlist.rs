@@ -1,17 +1,176 @@
-use std::path::PathBuf;
+use std::{ffi::OsStr, path::PathBuf};
 
+use async_walkdir::WalkDir;
+use chrono::{NaiveDate, TimeDelta};
 use log::*;
+use serde::{Deserialize, Serialize};
 use tokio::fs;
+use tokio_stream::StreamExt;
 
-use crate::file::norm_abs_path;
+use crate::db::{
+    handles,
+    models::{PlaylistChange, PlaylistItem},
+    DbPool,
+};
+use crate::file::{norm_abs_path, StorageBackend};
 use crate::player::controller::ChannelManager;
-use crate::player::utils::{json_reader, json_writer, JsonPlaylist};
-use crate::utils::{config::PlayoutConfig, errors::ServiceError, generator::playlist_generator};
+use crate::player::utils::{
+    get_date, is_close, is_remote,
+    json_legacy::{normalize_playlist, LegacyReport},
+    json_reader,
+    json_serializer::remote_request,
+    json_writer,
+    probe::MediaProbe,
+    JsonPlaylist, Media,
+};
+use crate::utils::{
+    config::PlayoutConfig, errors::ServiceError, generator::playlist_generator, logging::Target,
+};
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct TrimObj {
+    pub date: String,
+    pub index: usize,
+    /// Seconds in the media file, `null` removes the trim point.
+    #[serde(default)]
+    pub trim_in: Option<f64>,
+    #[serde(default)]
+    pub trim_out: Option<f64>,
+}
+
+/// Round a time to the frame grid of the channel.
+fn frame_time(time: f64, fps: f64) -> f64 {
+    if fps > 0.0 {
+        (time * fps).round() / fps
+    } else {
+        time
+    }
+}
+
+/// Playlist of the date from the external feed.
+async fn read_feed(config: &PlayoutConfig, url: &str, date: &str) -> Result<JsonPlaylist, String> {
+    let resp = remote_request(config, url)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| e.to_string())?;
+    let body = resp.text().await.map_err(|e| e.to_string())?;
+
+    normalize_playlist(&body, Some(date)).map(|(playlist, _)| playlist)
+}
+
+/// Changes between the stored and the new items, which are compared as JSON.
+///
+/// Equal items at the begin and the end are skipped and the items between them are compared by
+/// position, so an inserted or removed item is one change and not a change of all following items.
+pub fn item_changes(old: &[String], new: &[String]) -> Vec<PlaylistChange> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old = &old[prefix..old.len() - suffix];
+    let new = &new[prefix..new.len() - suffix];
+    let mut changes = vec![];
+
+    for i in 0..old.len().max(new.len()) {
+        let action = match (old.get(i), new.get(i)) {
+            (Some(a), Some(b)) if a == b => continue,
+            (Some(_), Some(_)) => "change",
+            (Some(_), None) => "remove",
+            _ => "add",
+        };
+
+        changes.push(PlaylistChange {
+            position: (prefix + i) as i32,
+            action: action.to_string(),
+            previous: old.get(i).cloned(),
+            item: new.get(i).cloned(),
+            ..Default::default()
+        });
+    }
+
+    changes
+}
+
+/// Playlist of the date from the database.
+pub async fn stored_playlist(
+    conn: &DbPool,
+    channel_id: i32,
+    date: &str,
+) -> Result<JsonPlaylist, ServiceError> {
+    let playlist = handles::select_playlist(conn, channel_id, date)
+        .await
+        .map_err(|_| ServiceError::NoContent(format!("No playlist from {date}")))?;
+    let mut program = vec![];
+
+    for item in handles::select_playlist_items(conn, playlist.id).await? {
+        program.push(serde_json::from_str::<Media>(&item.data)?);
+    }
+
+    Ok(JsonPlaylist {
+        channel: playlist.channel,
+        date: playlist.date,
+        start_sec: None,
+        length: None,
+        path: None,
+        modified: Some(playlist.revision.to_string()),
+        program,
+    })
+}
+
+/// Save the playlist in the database, gives the new revision,
+/// or `None` when the stored playlist is the same.
+pub async fn store_playlist(
+    conn: &DbPool,
+    channel_id: i32,
+    user_id: i32,
+    playlist: &JsonPlaylist,
+) -> Result<Option<i32>, ServiceError> {
+    let mut items = vec![];
+
+    for (position, item) in playlist.program.iter().enumerate() {
+        items.push(PlaylistItem {
+            position: position as i32,
+            source: item.source.clone(),
+            title: item.title.clone(),
+            seek: item.seek,
+            out: item.out,
+            duration: item.duration,
+            data: serde_json::to_string(item)?,
+        });
+    }
+
+    let revision = handles::save_playlist(
+        conn,
+        channel_id,
+        user_id,
+        &playlist.date,
+        &playlist.channel,
+        &items,
+    )
+    .await?;
+
+    Ok(revision)
+}
 
 pub async fn read_playlist(
+    conn: &DbPool,
     config: &PlayoutConfig,
     date: String,
 ) -> Result<JsonPlaylist, ServiceError> {
+    if let Some(url) = config.playlist.feed(&date) {
+        return read_feed(config, &url, &date)
+            .await
+            .map_err(ServiceError::NoContent);
+    }
+
+    if config.playlist.database {
+        return stored_playlist(conn, config.general.channel_id, &date).await;
+    }
+
     let d: Vec<&str> = date.split('-').collect();
     let mut playlist_path = config.channel.playlists.clone();
 
@@ -28,10 +187,32 @@ pub async fn read_playlist(
 }
 
 pub async fn write_playlist(
+    conn: &DbPool,
     config: &PlayoutConfig,
     json_data: JsonPlaylist,
+    user_id: i32,
 ) -> Result<String, ServiceError> {
+    if !config.playlist.feed_url.trim().is_empty() {
+        return Err(ServiceError::Conflict(
+            "Playlists of this channel come from the playlist feed".to_string(),
+        ));
+    }
+
     let date = json_data.date.clone();
+
+    if config.playlist.database {
+        let id = config.general.channel_id;
+        let exists = handles::select_playlist(conn, id, &date).await.is_ok();
+
+        return match store_playlist(conn, id, user_id, &json_data).await? {
+            Some(_) if exists => Ok(format!("Update playlist from {date} success!")),
+            Some(_) => Ok(format!("Write playlist from {date} success!")),
+            None => Err(ServiceError::Conflict(format!(
+                "Playlist from {date}, already exists!"
+            ))),
+        };
+    }
+
     let d: Vec<&str> = date.split('-').collect();
     let mut playlist_path = config.channel.playlists.clone();
 
@@ -81,6 +262,259 @@ pub async fn write_playlist(
     Err(ServiceError::InternalServerError)
 }
 
+/// Set the trim points of one playlist item.
+///
+/// The times are snapped to the frame rate of the channel. The item `duration` becomes
+/// the trimmed length and an item, which was played to the end, plays to the new out point.
+/// The automatic `in`/`out` values stay relative to the trimmed media.
+pub async fn trim_item(
+    conn: &DbPool,
+    config: &PlayoutConfig,
+    storage: &StorageBackend,
+    obj: &TrimObj,
+    user_id: i32,
+) -> Result<Media, ServiceError> {
+    let mut playlist = read_playlist(conn, config, obj.date.clone()).await?;
+    let item = playlist
+        .program
+        .get_mut(obj.index)
+        .ok_or_else(|| ServiceError::BadRequest("Playlist item not found".to_string()))?;
+    let fps = config.processing.fps;
+    let trim_in = obj.trim_in.map(|t| frame_time(t, fps));
+    let trim_out = obj.trim_out.map(|t| frame_time(t, fps));
+
+    if trim_in.is_some_and(|t| t < 0.0)
+        || trim_out.is_some_and(|t| t <= trim_in.unwrap_or_default())
+    {
+        return Err(ServiceError::BadRequest(
+            "Trim out must be after trim in".to_string(),
+        ));
+    }
+
+    let source = if is_remote(&item.source) {
+        item.source.clone()
+    } else {
+        storage
+            .fetch_file_path(&item.source)
+            .await
+            .unwrap_or_else(|_| item.source.clone())
+    };
+    let full = MediaProbe::new(&source)
+        .await
+        .ok()
+        .and_then(|p| p.format.duration)
+        .ok_or_else(|| ServiceError::BadRequest("Media duration not readable".to_string()))?;
+
+    if trim_in.is_some_and(|t| t >= full) {
+        return Err(ServiceError::BadRequest(
+            "Trim in is behind the end of the media".to_string(),
+        ));
+    }
+
+    let to_end = is_close(item.out, item.duration, 0.1);
+
+    item.trim_in = trim_in;
+    item.trim_out = trim_out;
+    item.duration = item.trimmed_length(full);
+
+    if to_end || item.out > item.duration {
+        item.out = item.duration;
+    }
+
+    if item.seek >= item.out {
+        item.seek = 0.0;
+    }
+
+    let item = item.clone();
+
+    match write_playlist(conn, config, playlist, user_id).await {
+        Ok(_) | Err(ServiceError::Conflict(_)) => Ok(item),
+        Err(e) => Err(e),
+    }
+}
+
+/// Find the playlists from today and the next `storage.protect_days` days,
+/// which reference the given file or a file inside the given folder.
+pub async fn playlist_references(
+    conn: &DbPool,
+    config: &PlayoutConfig,
+    storage: &StorageBackend,
+    source: &str,
+) -> Result<Vec<String>, ServiceError> {
+    let mut dates = vec![];
+
+    if config.storage.protect_days <= 0 {
+        return Ok(dates);
+    }
+
+    let (target, _, _) = norm_abs_path(&config.channel.storage, source)?;
+    let start_sec = config.playlist.start_sec.unwrap_or_default();
+    let today = get_date(true, start_sec, false, &config.channel.timezone);
+    let today = NaiveDate::parse_from_str(&today, "%Y-%m-%d")?;
+
+    for day in 0..config.storage.protect_days {
+        let date = (today + TimeDelta::days(day))
+            .format("%Y-%m-%d")
+            .to_string();
+
+        let playlist = if config.playlist.database {
+            stored_playlist(conn, config.general.channel_id, &date).await
+        } else {
+            let d: Vec<&str> = date.split('-').collect();
+            let playlist_path = config
+                .channel
+                .playlists
+                .join(d[0])
+                .join(d[1])
+                .join(&date)
+                .with_extension("json");
+
+            json_reader(&playlist_path)
+                .await
+                .map_err(ServiceError::from)
+        };
+
+        let Ok(playlist) = playlist else {
+            continue;
+        };
+
+        for item in &playlist.program {
+            let item_source = storage.interpreted_file_path(&item.source);
+            let (path, _, _) = norm_abs_path(&config.channel.storage, &item_source)?;
+
+            if path.starts_with(&target) {
+                dates.push(date);
+                break;
+            }
+        }
+    }
+
+    Ok(dates)
+}
+
+/// Convert all playlists of a channel, which are still in an older format, to the current schema.
+///
+/// The original file is kept with a `.bak` extension. With `dry_run` nothing will be written.
+pub async fn convert_playlists(
+    config: &PlayoutConfig,
+    dry_run: bool,
+) -> Result<Vec<LegacyReport>, ServiceError> {
+    let root = &config.channel.playlists;
+    let mut reports = vec![];
+    let mut entries = WalkDir::new(root);
+
+    while let Some(Ok(entry)) = entries.next().await {
+        let path = entry.path();
+
+        if path.extension().and_then(OsStr::to_str) != Some("json") {
+            continue;
+        }
+
+        let file = path.strip_prefix(root).unwrap_or(&path).to_string_lossy();
+        let contents = fs::read_to_string(&path).await?;
+        let date = path.file_stem().and_then(OsStr::to_str);
+
+        let report = match normalize_playlist(&contents, date) {
+            Ok((playlist, mut report)) if report.legacy => {
+                if !dry_run {
+                    fs::copy(&path, path.with_extension("json.bak")).await?;
+                    json_writer(&path, playlist).await?;
+
+                    info!(target: Target::file_mail(), channel = config.general.channel_id; "Converted legacy playlist <b><magenta>{file}</></b>");
+                }
+
+                report.file = Some(file.to_string());
+                report
+            }
+            Ok(_) => continue,
+            Err(e) => LegacyReport {
+                file: Some(file.to_string()),
+                error: Some(e),
+                ..Default::default()
+            },
+        };
+
+        reports.push(report);
+    }
+
+    Ok(reports)
+}
+
+/// Copy the JSON playlists from the playlist folder into the database, files without a date
+/// as name, like included playlist blocks, are left out.
+///
+/// Gives the dates of the playlists, which are new or have changed.
+pub async fn import_json_playlists(
+    conn: &DbPool,
+    config: &PlayoutConfig,
+    user_id: i32,
+) -> Result<Vec<String>, ServiceError> {
+    let id = config.general.channel_id;
+    let mut dates = vec![];
+    let mut entries = WalkDir::new(&config.channel.playlists);
+
+    while let Some(Ok(entry)) = entries.next().await {
+        let path = entry.path();
+
+        if path.extension().and_then(OsStr::to_str) != Some("json")
+            || path
+                .file_stem()
+                .and_then(OsStr::to_str)
+                .is_none_or(|stem| NaiveDate::parse_from_str(stem, "%Y-%m-%d").is_err())
+        {
+            continue;
+        }
+
+        let playlist = match json_reader(&path).await {
+            Ok(playlist) => playlist,
+            Err(e) => {
+                warn!(target: Target::file_mail(), channel = id; "Skip playlist <b><magenta>{}</></b>: {e}", path.display());
+                continue;
+            }
+        };
+
+        if store_playlist(conn, id, user_id, &playlist)
+            .await?
+            .is_some()
+        {
+            dates.push(playlist.date);
+        }
+    }
+
+    dates.sort();
+
+    Ok(dates)
+}
+
+/// Write the playlists from the database as JSON files into the playlist folder.
+pub async fn export_json_playlists(
+    conn: &DbPool,
+    config: &PlayoutConfig,
+) -> Result<Vec<String>, ServiceError> {
+    let id = config.general.channel_id;
+    let dates = handles::select_playlist_dates(conn, id).await?;
+
+    for date in &dates {
+        let playlist = stored_playlist(conn, id, date).await?;
+        let d: Vec<&str> = date.split('-').collect();
+        let playlist_path = config
+            .channel
+            .playlists
+            .join(d[0])
+            .join(d[1])
+            .join(date)
+            .with_extension("json");
+
+        if let Some(p) = playlist_path.parent() {
+            fs::create_dir_all(p).await?;
+        }
+
+        json_writer(&playlist_path, playlist).await?;
+    }
+
+    Ok(dates)
+}
+
 pub async fn generate_playlist(manager: ChannelManager) -> Result<JsonPlaylist, ServiceError> {
     let mut config = manager.config.lock().await;
 
@@ -97,6 +531,18 @@ pub async fn generate_playlist(manager: ChannelManager) -> Result<JsonPlaylist,
             source.paths = paths;
         }
 
+        for slot in &mut template.clock {
+            let mut paths = vec![];
+
+            for path in &slot.paths {
+                let (safe_path, _, _) =
+                    norm_abs_path(&config.channel.storage, &path.to_string_lossy())?;
+                paths.push(safe_path);
+            }
+
+            slot.paths = paths;
+        }
+
         config.general.template = Some(template);
     }
 
@@ -119,7 +565,20 @@ pub async fn generate_playlist(manager: ChannelManager) -> Result<JsonPlaylist,
     }
 }
 
-pub async fn delete_playlist(config: &PlayoutConfig, date: &str) -> Result<String, ServiceError> {
+pub async fn delete_playlist(
+    conn: &DbPool,
+    config: &PlayoutConfig,
+    date: &str,
+    user_id: i32,
+) -> Result<String, ServiceError> {
+    if config.playlist.database {
+        return if handles::delete_playlist(conn, config.general.channel_id, user_id, date).await? {
+            Ok(format!("Delete playlist from {date} success!"))
+        } else {
+            Ok(format!("No playlist to delete on: {date}"))
+        };
+    }
+
     let d: Vec<&str> = date.split('-').collect();
     let mut playlist_path = PathBuf::from(&config.channel.playlists);
 
@@ -141,3 +600,52 @@ pub async fn delete_playlist(config: &PlayoutConfig, date: &str) -> Result<Strin
         Ok(format!("No playlist to delete on: {date}"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(list: &[&str]) -> Vec<String> {
+        list.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn playlist_item_changes() {
+        let old = items(&["a", "b", "c", "d"]);
+
+        assert!(item_changes(&old, &old).is_empty());
+
+        let added = item_changes(&old, &items(&["a", "b", "x", "c", "d"]));
+
+        assert_eq!(added.len(), 1);
+        assert_eq!(added[0].position, 2);
+        assert_eq!(added[0].action, "add");
+        assert_eq!(added[0].previous, None);
+        assert_eq!(added[0].item.as_deref(), Some("x"));
+
+        let removed = item_changes(&old, &items(&["a", "c", "d"]));
+
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].position, 1);
+        assert_eq!(removed[0].action, "remove");
+        assert_eq!(removed[0].previous.as_deref(), Some("b"));
+
+        let changed = item_changes(&old, &items(&["a", "x", "c", "y"]));
+
+        assert_eq!(changed.len(), 2);
+        assert_eq!(changed[0].position, 1);
+        assert_eq!(changed[0].action, "change");
+        assert_eq!(changed[1].position, 3);
+        assert_eq!(changed[1].item.as_deref(), Some("y"));
+
+        let created = item_changes(&[], &items(&["a", "b"]));
+
+        assert_eq!(created.len(), 2);
+        assert!(created.iter().all(|c| c.action == "add"));
+
+        let deleted = item_changes(&old, &[]);
+
+        assert_eq!(deleted.len(), 4);
+        assert_eq!(deleted[3].previous.as_deref(), Some("d"));
+    }
+}
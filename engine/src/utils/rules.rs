@@ -0,0 +1,430 @@
+/// Insert Rules
+///
+/// Rules insert jingles, station IDs or ad breaks in a fixed rhythm,
+/// like every 30 minutes one item from a jingle folder, or a station ID at the top of the hour.
+/// Folder playout checks them against the wall clock, the playlist generator against the planned begin.
+///
+/// Bumper rules wrap every playlist item of a category with an intro and an outro clip,
+/// they are added when the playlist gets loaded.
+use std::path::{Path, PathBuf};
+
+use log::*;
+use rand::Rng;
+
+use crate::db::models::InsertRule;
+use crate::file::{StorageBackend, WalkOptions};
+use crate::player::utils::{include_file_extension, Media};
+use crate::utils::{config::PlayoutConfig, logging::Target};
+
+/// Due items are inserted at the first clip change after their slot.
+#[derive(Clone, Debug)]
+struct RuleState {
+    rule: InsertRule,
+    media: Vec<Media>,
+    cursor: usize,
+}
+
+impl RuleState {
+    /// Check if a slot of the rule lies in `(from, to]`.
+    fn slot_between(&self, from: f64, to: f64) -> bool {
+        let interval = self.rule.interval as f64 * 60.0;
+        let shift = self.rule.minute as f64 * 60.0;
+
+        ((to - shift) / interval).floor() > ((from - shift) / interval).floor()
+    }
+
+    fn pick(&mut self) -> Vec<Media> {
+        let mut items = vec![];
+
+        if self.media.is_empty() {
+            return items;
+        }
+
+        for _ in 0..self.rule.count.max(1) {
+            let index = if self.rule.shuffle {
+                rand::rng().random_range(0..self.media.len())
+            } else {
+                let index = self.cursor % self.media.len();
+                self.cursor += 1;
+
+                index
+            };
+
+            items.push(self.media[index].clone());
+        }
+
+        items
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RuleSet {
+    rules: Vec<RuleState>,
+    source: Vec<InsertRule>,
+    last: Option<f64>,
+}
+
+/// Rule path, relative paths are inside the channel storage.
+fn rule_path(config: &PlayoutConfig, path: &str) -> PathBuf {
+    let path = Path::new(path);
+
+    if path.starts_with(&config.channel.storage) {
+        path.to_path_buf()
+    } else {
+        config
+            .channel
+            .storage
+            .join(path.strip_prefix("/").unwrap_or(path))
+    }
+}
+
+async fn rule_media(config: &PlayoutConfig, storage: &StorageBackend, path: &Path) -> Vec<Media> {
+    let mut paths = vec![];
+    let mut media = vec![];
+
+    if storage.is_dir(path).await {
+        match storage
+            .walk_dir(path, &WalkOptions::from_config(config))
+            .await
+        {
+            Ok(entries) => paths = entries,
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = config.general.channel_id; "Insert rule path <b><magenta>{path:?}</></b>: {e}");
+            }
+        }
+
+        paths.sort();
+    } else if storage.is_file(path).await {
+        paths.push(path.to_path_buf());
+    }
+
+    for p in paths {
+        if !storage.is_file(&p).await || !include_file_extension(config, &p) {
+            continue;
+        }
+
+        let source = storage
+            .fetch_file_path(&p.to_string_lossy())
+            .await
+            .unwrap_or(p.to_string_lossy().to_string());
+        let item = Media::new(0, &source, true).await;
+
+        if item.duration > 0.0 {
+            media.push(item);
+        }
+    }
+
+    media
+}
+
+impl RuleSet {
+    pub async fn new(config: &PlayoutConfig, storage: &StorageBackend) -> Self {
+        let id = config.general.channel_id;
+        let mut rules = vec![];
+
+        for rule in config.rules.iter().filter(|r| r.active && r.interval > 0) {
+            let path = rule_path(config, &rule.path);
+            let media = rule_media(config, storage, &path).await;
+
+            if media.is_empty() {
+                error!(target: Target::file_mail(), channel = id; "Insert rule <yellow>{}</> has no playable files in <b><magenta>{path:?}</></b>", rule.name);
+                continue;
+            }
+
+            debug!(target: Target::file_mail(), channel = id; "Insert rule <yellow>{}</>: every <yellow>{}</> minutes, <yellow>{}</> file(s)", rule.name, rule.interval, media.len());
+
+            rules.push(RuleState {
+                rule: rule.clone(),
+                media,
+                cursor: 0,
+            });
+        }
+
+        Self {
+            rules,
+            source: config.rules.clone(),
+            last: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Rules in config are different from the loaded ones.
+    pub fn changed(&self, rules: &[InsertRule]) -> bool {
+        self.source != rules
+    }
+
+    fn range(&self, time: f64) -> (f64, f64) {
+        match self.last {
+            // day change
+            Some(last) if time < last => (last - 86400.0, time),
+            Some(last) => (last, time),
+            None => (time - 0.5, time),
+        }
+    }
+
+    /// Check if a rule gets due until `time`, without consuming it.
+    pub fn is_due(&self, time: f64) -> bool {
+        let (from, to) = self.range(time);
+
+        self.rules.iter().any(|r| r.slot_between(from, to))
+    }
+
+    /// Items of all rules, which slot is passed since the last call.
+    pub fn due(&mut self, time: f64) -> Vec<Media> {
+        let (from, to) = self.range(time);
+        let mut items = vec![];
+
+        self.last = Some(time);
+
+        for state in &mut self.rules {
+            if state.slot_between(from, to) {
+                items.append(&mut state.pick());
+            }
+        }
+
+        items
+    }
+
+    /// Insert the rule items in a list which starts at `start`,
+    /// clips which not fit anymore in `total_length` are dropped.
+    pub fn apply(&mut self, list: Vec<Media>, start: f64, total_length: f64) -> Vec<Media> {
+        if self.rules.is_empty() {
+            return list;
+        }
+
+        let mut program = vec![];
+        let mut length = 0.0;
+
+        self.last = None;
+
+        for item in list {
+            for insert in self.due(start + length) {
+                let insert_length = insert.out - insert.seek;
+
+                if length + insert_length <= total_length {
+                    length += insert_length;
+                    program.push(insert);
+                }
+            }
+
+            let item_length = item.out - item.seek;
+
+            if length + item_length > total_length {
+                break;
+            }
+
+            length += item_length;
+            program.push(item);
+        }
+
+        for (index, item) in program.iter_mut().enumerate() {
+            item.index = Some(index);
+        }
+
+        program
+    }
+}
+
+/// Loaded clips of a bumper rule.
+#[derive(Clone, Debug, Default)]
+struct Bumper {
+    category: String,
+    intro: Option<Media>,
+    outro: Option<Media>,
+}
+
+async fn bumper_media(
+    config: &PlayoutConfig,
+    storage: &StorageBackend,
+    path: &str,
+) -> Option<Media> {
+    if path.trim().is_empty() {
+        return None;
+    }
+
+    let path = rule_path(config, path);
+    let media = rule_media(config, storage, &path).await.into_iter().next();
+
+    if media.is_none() {
+        error!(target: Target::file_mail(), channel = config.general.channel_id; "Bumper <b><magenta>{path:?}</></b> is not playable");
+    }
+
+    media
+}
+
+/// Put the bumpers around the items of their category. Bumpers which are already
+/// in the playlist, directly before or after the item, are not added again.
+fn wrap_bumpers(program: Vec<Media>, bumpers: &[Bumper]) -> Vec<Media> {
+    let mut list: Vec<Media> = vec![];
+    let mut items = program.into_iter().peekable();
+
+    while let Some(item) = items.next() {
+        let bumper = bumpers
+            .iter()
+            .find(|b| b.category.eq_ignore_ascii_case(item.category.trim()));
+
+        if let Some(intro) = bumper.and_then(|b| b.intro.as_ref()) {
+            if list.last().is_none_or(|last| last.source != intro.source) {
+                list.push(intro.clone());
+            }
+        }
+
+        list.push(item);
+
+        if let Some(outro) = bumper.and_then(|b| b.outro.as_ref()) {
+            if items.peek().is_none_or(|next| next.source != outro.source) {
+                list.push(outro.clone());
+            }
+        }
+    }
+
+    list
+}
+
+/// Add the intro and outro clips of the active bumper rules to the program.
+pub async fn add_bumpers(
+    config: &PlayoutConfig,
+    storage: &StorageBackend,
+    program: Vec<Media>,
+) -> Vec<Media> {
+    let mut bumpers = vec![];
+
+    for rule in config.bumpers.iter().filter(|b| b.active) {
+        let bumper = Bumper {
+            category: rule.category.trim().to_string(),
+            intro: bumper_media(config, storage, &rule.intro).await,
+            outro: bumper_media(config, storage, &rule.outro).await,
+        };
+
+        if bumper.intro.is_some() || bumper.outro.is_some() {
+            bumpers.push(bumper);
+        }
+    }
+
+    if bumpers.is_empty() {
+        return program;
+    }
+
+    wrap_bumpers(program, &bumpers)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use super::*;
+    use crate::player::utils::{playlist_clock_at, seconds_of_day};
+
+    fn clip(source: &str, duration: f64) -> Media {
+        Media {
+            source: source.to_string(),
+            out: duration,
+            duration,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn insert_every_half_hour() {
+        let mut rules = RuleSet {
+            rules: vec![RuleState {
+                rule: InsertRule {
+                    interval: 30,
+                    count: 1,
+                    ..Default::default()
+                },
+                media: vec![clip("jingle.mp4", 10.0)],
+                cursor: 0,
+            }],
+            ..Default::default()
+        };
+
+        let list = vec![clip("a.mp4", 1200.0); 5];
+        let program = rules.apply(list, 21600.0, 86400.0);
+        let sources: Vec<&str> = program.iter().map(|m| m.source.as_str()).collect();
+
+        assert_eq!(
+            sources,
+            vec![
+                "jingle.mp4",
+                "a.mp4",
+                "a.mp4",
+                "jingle.mp4",
+                "a.mp4",
+                "jingle.mp4",
+                "a.mp4",
+                "a.mp4"
+            ]
+        );
+    }
+
+    #[test]
+    fn bumpers_by_category() {
+        let bumpers = vec![Bumper {
+            category: "movie".to_string(),
+            intro: Some(clip("movie_intro.mp4", 5.0)),
+            outro: Some(clip("movie_outro.mp4", 5.0)),
+        }];
+        let mut movie = clip("movie.mp4", 5400.0);
+        movie.category = "Movie".to_string();
+
+        let list = vec![clip("news.mp4", 900.0), movie.clone()];
+        let sources: Vec<String> = wrap_bumpers(list, &bumpers)
+            .into_iter()
+            .map(|m| m.source)
+            .collect();
+
+        assert_eq!(
+            sources,
+            [
+                "news.mp4",
+                "movie_intro.mp4",
+                "movie.mp4",
+                "movie_outro.mp4"
+            ]
+        );
+
+        // bumpers from the editor stay single
+        let list = vec![
+            clip("movie_intro.mp4", 5.0),
+            movie,
+            clip("movie_outro.mp4", 5.0),
+        ];
+
+        assert_eq!(wrap_bumpers(list, &bumpers).len(), 3);
+    }
+
+    #[test]
+    fn insert_on_dst_day() {
+        let at = |time: &str| {
+            DateTime::parse_from_rfc3339(time)
+                .unwrap()
+                .with_timezone(&chrono_tz::Europe::Berlin)
+        };
+        let mut rules = RuleSet {
+            rules: vec![RuleState {
+                rule: InsertRule {
+                    interval: 1440,
+                    minute: 720,
+                    count: 1,
+                    ..Default::default()
+                },
+                media: vec![clip("noon.mp4", 10.0)],
+                cursor: 0,
+            }],
+            ..Default::default()
+        };
+
+        // clocks went forward at 02:00, so one hour less passed since midnight
+        let before = at("2024-03-31T11:59:50+02:00");
+        assert_eq!(seconds_of_day(&before), 43190.0);
+        assert_eq!(playlist_clock_at(before, 0.0).1, 39590.0);
+        assert!(rules.due(seconds_of_day(&before)).is_empty());
+
+        let after = at("2024-03-31T12:00:10+02:00");
+        assert_eq!(rules.due(seconds_of_day(&after)).len(), 1);
+    }
+}
@@ -4,8 +4,8 @@ use local_ip_address::list_afinet_netifas;
 use serde::Serialize;
 use sysinfo::System;
 
-use crate::utils::config::PlayoutConfig;
-use crate::{DISKS, NETWORKS, SYS};
+use crate::utils::{config::PlayoutConfig, hwaccel::HwAccel};
+use crate::{DISKS, HW_ACCEL, NETWORKS, SYS};
 
 const IGNORE_INTERFACES: [&str; 7] = ["docker", "lxdbr", "tab", "tun", "virbr", "veth", "vnet"];
 
@@ -51,6 +51,8 @@ pub struct MySystem {
     pub kernel: Option<String>,
     pub version: Option<String>,
     pub ffp_version: Option<String>,
+    /// Hardware encoders, which are found at startup.
+    pub hwaccel: Vec<HwAccel>,
 }
 
 #[derive(Debug, Serialize)]
@@ -162,6 +164,7 @@ pub fn stat(config: &PlayoutConfig) -> SystemStat {
         kernel: System::kernel_version(),
         version: System::os_version(),
         ffp_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        hwaccel: HW_ACCEL.clone(),
     };
 
     SystemStat {
@@ -2,6 +2,7 @@
 /// It allows, with a hidden command line argument, to override the time in this program.
 /// It is like a time machine where you can fake the time and make the hole program think it is running in the future or past.
 use std::{
+    future::Future,
     io,
     str::FromStr,
     sync::{Arc, LazyLock, RwLock},
@@ -14,6 +15,14 @@ use chrono_tz::Tz;
 static DATE_TIME_DIFF: LazyLock<Arc<RwLock<Option<TimeDelta>>>> =
     LazyLock::new(|| Arc::new(RwLock::new(None)));
 
+// Fixed time for the schedule simulation, it moves only when it is set again
+static FROZEN_TIME: LazyLock<RwLock<Option<DateTime<Utc>>>> = LazyLock::new(|| RwLock::new(None));
+
+tokio::task_local! {
+    // Seconds the clock runs ahead in the current task, while the next clip is read before it starts
+    static LOOKAHEAD: f64;
+}
+
 // Set the mock time offset if `--fake-time` argument is provided
 pub fn set_mock_time(fake_time: &Option<String>) -> Result<(), io::Error> {
     if let Some(time) = fake_time {
@@ -36,11 +45,21 @@ pub fn set_mock_time(fake_time: &Option<String>) -> Result<(), io::Error> {
     Ok(())
 }
 
-// Function to get the current time, using either real or mock time based on `--fake-time`
-pub fn time_now(timezone: &Option<Tz>) -> DateTime<Tz> {
-    let utc_now: DateTime<Utc> = Utc::now();
+// Stop the clock at the given time, `None` lets it run again
+pub fn freeze_time(time: Option<DateTime<Utc>>) {
+    if let Ok(mut frozen) = FROZEN_TIME.write() {
+        *frozen = time;
+    }
+}
+
+// Run the future with the clock `seconds` ahead, like at the begin of a clip which starts later
+pub async fn lookahead<F: Future>(seconds: f64, future: F) -> F::Output {
+    LOOKAHEAD.scope(seconds, future).await
+}
 
-    let tz = match timezone {
+// Channel timezone, or the timezone from the system when no one is set
+pub fn local_timezone(timezone: &Option<Tz>) -> Tz {
+    match timezone {
         Some(tz) => *tz,
         None => match iana_time_zone::get_timezone()
             .ok()
@@ -49,10 +68,25 @@ pub fn time_now(timezone: &Option<Tz>) -> DateTime<Tz> {
             Some(tz) => tz,
             None => Tz::UTC,
         },
+    }
+}
+
+// Function to get the current time, using either real or mock time based on `--fake-time`
+pub fn time_now(timezone: &Option<Tz>) -> DateTime<Tz> {
+    let utc_now: DateTime<Utc> = Utc::now();
+    let tz = local_timezone(timezone);
+
+    let now = if let Some(frozen) = FROZEN_TIME.read().ok().and_then(|t| *t) {
+        frozen.with_timezone(&tz)
+    } else {
+        match DATE_TIME_DIFF.read().ok().and_then(|d| *d) {
+            Some(d) => utc_now.with_timezone(&tz) - d,
+            None => utc_now.with_timezone(&tz),
+        }
     };
 
-    match DATE_TIME_DIFF.read().ok().and_then(|d| *d) {
-        Some(d) => utc_now.with_timezone(&tz) - d,
-        None => utc_now.with_timezone(&tz),
+    match LOOKAHEAD.try_with(|seconds| *seconds) {
+        Ok(seconds) => now + TimeDelta::milliseconds((seconds * 1000.0) as i64),
+        Err(_) => now,
     }
 }
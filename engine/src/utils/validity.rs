@@ -0,0 +1,143 @@
+/// Validity Dates
+///
+/// Playlist items and library entries can have a `valid_from` and `valid_to` date,
+/// like spots of a seasonal campaign. Dates of the item win over the dates of the library entry.
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+
+use crate::db::{handles, DbPool};
+use crate::player::utils::Media;
+use crate::utils::config::PlayoutConfig;
+
+/// Items which expire in less days get a warning.
+pub const EXPIRE_WARNING_DAYS: i64 = 7;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Validity {
+    Valid,
+    /// Valid from the date on.
+    NotYet(NaiveDate),
+    /// Expired after the date.
+    Expired(NaiveDate),
+    /// Valid until the date, which is in less than `EXPIRE_WARNING_DAYS`.
+    Expiring(NaiveDate),
+}
+
+fn parse_date(date: Option<&String>) -> Option<NaiveDate> {
+    date.and_then(|d| NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d").ok())
+}
+
+/// Validity dates of the library entries, with the full path as key.
+pub async fn library_validity(
+    conn: &DbPool,
+    config: &PlayoutConfig,
+) -> HashMap<String, (Option<String>, Option<String>)> {
+    handles::select_media_hashes(conn, config.general.channel_id)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|m| m.valid_from.is_some() || m.valid_to.is_some())
+        .map(|m| {
+            (
+                config
+                    .channel
+                    .storage
+                    .join(&m.path)
+                    .to_string_lossy()
+                    .to_string(),
+                (m.valid_from, m.valid_to),
+            )
+        })
+        .collect()
+}
+
+/// Take the dates of the library entry, when the item has no own dates.
+pub fn apply_library(
+    item: &mut Media,
+    library: &HashMap<String, (Option<String>, Option<String>)>,
+) {
+    if let Some((from, to)) = library.get(&item.source) {
+        if item.valid_from.is_none() {
+            item.valid_from.clone_from(from);
+        }
+
+        if item.valid_to.is_none() {
+            item.valid_to.clone_from(to);
+        }
+    }
+}
+
+/// Check the item against the day it is scheduled on.
+pub fn check(item: &Media, date: NaiveDate) -> Validity {
+    if let Some(from) = parse_date(item.valid_from.as_ref()) {
+        if date < from {
+            return Validity::NotYet(from);
+        }
+    }
+
+    if let Some(to) = parse_date(item.valid_to.as_ref()) {
+        if date > to {
+            return Validity::Expired(to);
+        }
+
+        if (to - date).num_days() < EXPIRE_WARNING_DAYS {
+            return Validity::Expiring(to);
+        }
+    }
+
+    Validity::Valid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spot(from: Option<&str>, to: Option<&str>) -> Media {
+        Media {
+            source: "spot.mp4".to_string(),
+            valid_from: from.map(str::to_string),
+            valid_to: to.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn validity_dates() {
+        let day = NaiveDate::from_ymd_opt(2025, 12, 1).unwrap();
+
+        assert_eq!(check(&spot(None, None), day), Validity::Valid);
+        assert_eq!(
+            check(&spot(Some("2025-12-24"), None), day),
+            Validity::NotYet(NaiveDate::from_ymd_opt(2025, 12, 24).unwrap())
+        );
+        assert_eq!(
+            check(&spot(None, Some("2025-11-30")), day),
+            Validity::Expired(NaiveDate::from_ymd_opt(2025, 11, 30).unwrap())
+        );
+        assert_eq!(
+            check(&spot(Some("2025-11-01"), Some("2025-12-05")), day),
+            Validity::Expiring(NaiveDate::from_ymd_opt(2025, 12, 5).unwrap())
+        );
+        assert_eq!(check(&spot(None, Some("2026-01-31")), day), Validity::Valid);
+        // unreadable dates are ignored
+        assert_eq!(check(&spot(Some("christmas"), None), day), Validity::Valid);
+    }
+
+    #[test]
+    fn library_dates() {
+        let library = HashMap::from([(
+            "spot.mp4".to_string(),
+            (
+                Some("2025-11-01".to_string()),
+                Some("2025-12-31".to_string()),
+            ),
+        )]);
+        let mut item = spot(None, Some("2025-12-24"));
+
+        apply_library(&mut item, &library);
+
+        assert_eq!(item.valid_from.as_deref(), Some("2025-11-01"));
+        assert_eq!(item.valid_to.as_deref(), Some("2025-12-24"));
+    }
+}
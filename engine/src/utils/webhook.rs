@@ -0,0 +1,229 @@
+/// Webhooks, which get the events of the channel as JSON.
+///
+/// Every webhook has its own list of events, an empty list takes all. The request is sent again,
+/// when the server is not reachable or returns an error. With a secret, the body is signed with
+/// HMAC-SHA256 in the `X-Signature-256` header, like `sha256=<hex>`.
+use std::{fmt, str::FromStr, time::Duration};
+
+use hmac::{Hmac, Mac};
+use log::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use tokio::time::sleep;
+
+use crate::db::models::Webhook;
+use crate::player::utils::Media;
+use crate::utils::{config::PlayoutConfig, logging::Target, time_machine::time_now};
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Waiting time before the next attempt, after a failed request.
+const RETRIES: [Duration; 3] = [
+    Duration::from_secs(2),
+    Duration::from_secs(10),
+    Duration::from_secs(30),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    ClipStart,
+    ClipEnd,
+    PlaylistMissing,
+    FfmpegCrash,
+    IngestConnect,
+    IngestDisconnect,
+    StorageError,
+    Black,
+    Silence,
+    OutputError,
+}
+
+impl WebhookEvent {
+    pub const ALL: [Self; 10] = [
+        Self::ClipStart,
+        Self::ClipEnd,
+        Self::PlaylistMissing,
+        Self::FfmpegCrash,
+        Self::IngestConnect,
+        Self::IngestDisconnect,
+        Self::StorageError,
+        Self::Black,
+        Self::Silence,
+        Self::OutputError,
+    ];
+}
+
+impl FromStr for WebhookEvent {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|e| e.to_string() == input.trim())
+            .ok_or(format!("Unknown webhook event: {input}"))
+    }
+}
+
+impl fmt::Display for WebhookEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::ClipStart => write!(f, "clip_start"),
+            Self::ClipEnd => write!(f, "clip_end"),
+            Self::PlaylistMissing => write!(f, "playlist_missing"),
+            Self::FfmpegCrash => write!(f, "ffmpeg_crash"),
+            Self::IngestConnect => write!(f, "ingest_connect"),
+            Self::IngestDisconnect => write!(f, "ingest_disconnect"),
+            Self::StorageError => write!(f, "storage_error"),
+            Self::Black => write!(f, "black"),
+            Self::Silence => write!(f, "silence"),
+            Self::OutputError => write!(f, "output_error"),
+        }
+    }
+}
+
+/// Events of the webhook, separated by comma.
+pub fn parse_events(events: &str) -> Result<Vec<WebhookEvent>, String> {
+    events
+        .split(',')
+        .filter(|e| !e.trim().is_empty())
+        .map(WebhookEvent::from_str)
+        .collect()
+}
+
+fn subscribed(hook: &Webhook, event: WebhookEvent) -> bool {
+    hook.active
+        && parse_events(&hook.events)
+            .is_ok_and(|events| events.is_empty() || events.contains(&event))
+}
+
+pub fn signature(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+    mac.update(body);
+
+    let hex: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+
+    format!("sha256={hex}")
+}
+
+/// Data of the clip events.
+pub fn media_data(node: &Media) -> Value {
+    json!({
+        "title": node.title,
+        "source": node.source,
+        "category": node.category,
+        "in": node.seek,
+        "out": node.out,
+        "duration": node.duration,
+        "index": node.index,
+    })
+}
+
+/// Send the event to all webhooks of the channel, which take it.
+///
+/// The requests run in the background, so the playout doesn't wait for them.
+pub fn emit(config: &PlayoutConfig, event: WebhookEvent, data: Value) {
+    let hooks = config
+        .webhooks
+        .iter()
+        .filter(|h| subscribed(h, event))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    if hooks.is_empty() {
+        return;
+    }
+
+    let id = config.general.channel_id;
+    let body = json!({
+        "event": event,
+        "channel": id,
+        "time": time_now(&config.channel.timezone).to_rfc3339(),
+        "data": data,
+    })
+    .to_string();
+
+    for hook in hooks {
+        let body = body.clone();
+
+        tokio::spawn(async move { deliver(id, &hook, event, body).await });
+    }
+}
+
+async fn deliver(id: i32, hook: &Webhook, event: WebhookEvent, body: String) {
+    let client = reqwest::Client::new();
+    let mut last_error = String::new();
+
+    for wait in std::iter::once(None).chain(RETRIES.map(Some)) {
+        if let Some(wait) = wait {
+            sleep(wait).await;
+        }
+
+        let mut request = client
+            .post(&hook.url)
+            .timeout(WEBHOOK_TIMEOUT)
+            .header("Content-Type", "application/json")
+            .header("X-Webhook-Event", event.to_string());
+
+        if !hook.secret.is_empty() {
+            request = request.header("X-Signature-256", signature(&hook.secret, body.as_bytes()));
+        }
+
+        match request.body(body.clone()).send().await {
+            Ok(resp) if resp.status().is_success() => return,
+            // the request itself is wrong, another attempt gets the same answer
+            Ok(resp) if resp.status().is_client_error() => {
+                last_error = format!("returns {}", resp.status());
+                break;
+            }
+            Ok(resp) => last_error = format!("returns {}", resp.status()),
+            Err(e) => last_error = format!("failed: {e}"),
+        }
+    }
+
+    error!(target: Target::file(), channel = id;
+        "Webhook <b><magenta>{}</></b> for <yellow>{event}</> {last_error}", hook.url
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn webhook_events() {
+        assert_eq!(
+            parse_events("clip_start, black,").unwrap(),
+            vec![WebhookEvent::ClipStart, WebhookEvent::Black]
+        );
+        assert!(parse_events("clip_begin").is_err());
+
+        let mut hook = Webhook {
+            events: "ingest_connect,ingest_disconnect".to_string(),
+            active: true,
+            ..Default::default()
+        };
+
+        assert!(subscribed(&hook, WebhookEvent::IngestConnect));
+        assert!(!subscribed(&hook, WebhookEvent::ClipStart));
+
+        hook.events.clear();
+        assert!(subscribed(&hook, WebhookEvent::ClipStart));
+
+        hook.active = false;
+        assert!(!subscribed(&hook, WebhookEvent::ClipStart));
+
+        // reference value from: echo -n '{"a":1}' | openssl dgst -sha256 -hmac secret
+        assert_eq!(
+            signature("secret", br#"{"a":1}"#),
+            "sha256=aa9e2e3575f5d7098b6caccd790888c36d5fdb63342a73bada2d6a51747a8494"
+        );
+    }
+}
@@ -227,6 +227,42 @@ export const playlistOperations = () => {
                     delete item.audio
                 }
 
+                if (!item.include) {
+                    delete item.include
+                }
+
+                if (!item.random) {
+                    delete item.random
+                }
+
+                if (!item.backup) {
+                    delete item.backup
+                }
+
+                if (!item.valid_from) {
+                    delete item.valid_from
+                }
+
+                if (!item.valid_to) {
+                    delete item.valid_to
+                }
+
+                if (!item.audio_map?.length) {
+                    delete item.audio_map
+                }
+
+                if (!item.subtitle) {
+                    delete item.subtitle
+                }
+
+                if (!item.aspect_mode) {
+                    delete item.aspect_mode
+                }
+
+                if (!item.audio_layout) {
+                    delete item.audio_layout
+                }
+
                 if (!item.category) {
                     delete item.category
                 }
@@ -235,10 +271,30 @@ export const playlistOperations = () => {
                     delete item.custom_filter
                 }
 
+                if (!item.filter_template) {
+                    delete item.filter_template
+                }
+
+                if (item.trim_in == null) {
+                    delete item.trim_in
+                }
+
+                if (item.trim_out == null) {
+                    delete item.trim_out
+                }
+
+                if (!item.events?.length) {
+                    delete item.events
+                }
+
                 if (!item.title) {
                     delete item.title
                 }
 
+                if (!item.description) {
+                    delete item.description
+                }
+
                 if (
                     begin + (item.out - item.in) >
                     configStore.playout.playlist.startInSec + configStore.playout.playlist.lengthInSec
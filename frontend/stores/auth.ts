@@ -9,6 +9,7 @@ export const useAuth = defineStore('auth', {
         authHeader: {},
         role: '',
         uuid: null as null | string,
+        refreshTimer: null as null | ReturnType<typeof setTimeout>,
     }),
 
     getters: {},
@@ -31,9 +32,44 @@ export const useAuth = defineStore('auth', {
             this.jwtToken = token
             this.jwtRefresh = refresh
             this.authHeader = { Authorization: `Bearer ${token}` }
+
+            this.scheduleRefresh(token)
+        },
+
+        scheduleRefresh(token: string) {
+            // access tokens are short-lived, renew them shortly before they expire
+            if (import.meta.server) {
+                return
+            }
+
+            if (this.refreshTimer) {
+                clearTimeout(this.refreshTimer)
+            }
+
+            const expire = jwtDecode<JwtPayloadExt>(token).exp || 0
+            const wait = Math.max((expire - Date.now() / 1000 - 30) * 1000, 0)
+
+            this.refreshTimer = setTimeout(() => this.refreshToken(), wait)
+        },
+
+        async logout() {
+            if (this.jwtRefresh) {
+                await $fetch('/auth/logout/', {
+                    method: 'POST',
+                    headers: new Headers([['content-type', 'application/json;charset=UTF-8']]),
+                    body: JSON.stringify({ refresh: this.jwtRefresh }),
+                }).catch(() => {})
+            }
+
+            this.removeToken()
         },
 
         removeToken() {
+            if (this.refreshTimer) {
+                clearTimeout(this.refreshTimer)
+                this.refreshTimer = null
+            }
+
             const token = useCookie('token')
             const refresh = useCookie('refresh')
             token.value = null
@@ -122,6 +158,10 @@ export const useAuth = defineStore('auth', {
 
                 if (expireToken && this.jwtToken && expireToken - timestamp > 15) {
                     this.isLogin = true
+
+                    if (!this.refreshTimer) {
+                        this.scheduleRefresh(token)
+                    }
                 } else if (expireRefresh && expireRefresh - timestamp > 0) {
                     await this.refreshToken()
                 } else {
@@ -79,5 +79,28 @@ export const useMedia = defineStore('media', {
 
             this.isLoading = false
         },
+
+        async signedUrl(path: string): Promise<string> {
+            const authStore = useAuth()
+            const configStore = useConfig()
+            const indexStore = useIndex()
+            const channel = configStore.channels[configStore.i].id
+
+            return await fetch(`/api/file/${channel}/sign/`, {
+                method: 'POST',
+                headers: { ...configStore.contentType, ...authStore.authHeader },
+                body: JSON.stringify({ path }),
+            })
+                .then(async (response) => {
+                    if (response.status === 200) {
+                        return (await response.json()).url
+                    }
+
+                    indexStore.msgAlert('error', await response.text(), 3)
+
+                    return ''
+                })
+                .catch(() => '')
+        },
     },
 })
@@ -49,6 +49,7 @@ declare global {
         confirm?: string
         admin?: boolean
         channel_ids?: number[]
+        permissions?: Record<number, string>
         role_id?: number
     }
 
@@ -74,14 +75,37 @@ declare global {
         uid: string
         begin: number
         title?: string | null
+        description?: string | null
         source: string
         duration: number
         in: number
         out: number
         audio?: string
+        audio_map?: number[]
+        backup?: string
+        valid_from?: string
+        valid_to?: string
+        include?: boolean
+        random?: boolean
+        subtitle?: string
+        aspect_mode?: 'pad' | 'crop' | 'stretch'
+        audio_layout?: string
         category?: string
         custom_filter?: string
+        filter_template?: string
         overtime?: boolean
+        trim_in?: number | null
+        trim_out?: number | null
+        events?: SecondaryEvent[]
+    }
+
+    interface SecondaryEvent {
+        type: 'logo' | 'text' | 'webhook'
+        offset?: number
+        duration?: number
+        path?: string
+        preset?: string
+        url?: string
     }
 
     interface FileObject {
@@ -120,8 +144,19 @@ declare global {
         paths: string[]
     }
 
+    interface ClockSlot {
+        start: string
+        duration: string
+        shuffle: boolean
+        paths: string[]
+        category?: string
+    }
+
     interface Template {
         sources: TemplateItem[]
+        clock?: ClockSlot[]
+        quotas?: Record<string, number>
+        separation?: { source: number; category: number }
     }
 
     interface BodyObject {
@@ -136,7 +171,7 @@ declare global {
         network?: { name: string; current_in: number; current_out: number; total_in: number; total_out: number }
         storage?: { path: string; total: number; used: number }
         swap: { total: number; used: number; free: number }
-        system: { name?: string; kernel?: string; version?: string; ffp_version?: string }
+        system: { name?: string; kernel?: string; version?: string; ffp_version?: string; hwaccel?: string[] }
     }
 
     interface PlayoutStatus {
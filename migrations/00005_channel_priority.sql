@@ -0,0 +1 @@
+ALTER TABLE channels ADD priority INTEGER NOT NULL DEFAULT 0;
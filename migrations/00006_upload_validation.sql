@@ -0,0 +1 @@
+ALTER TABLE configurations ADD storage_quarantine INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,14 @@
+CREATE TABLE
+    media (
+        id INTEGER PRIMARY KEY,
+        channel_id INTEGER NOT NULL,
+        path TEXT NOT NULL,
+        hash TEXT NOT NULL,
+        size INTEGER NOT NULL DEFAULT 0,
+        modified INTEGER NOT NULL DEFAULT 0,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_media_channel_path ON media (channel_id, path);
+
+CREATE INDEX IF NOT EXISTS idx_media_hash ON media (channel_id, hash);
@@ -0,0 +1,11 @@
+ALTER TABLE configurations ADD logging_decoder_level TEXT NOT NULL DEFAULT '';
+
+ALTER TABLE configurations ADD logging_encoder_level TEXT NOT NULL DEFAULT '';
+
+ALTER TABLE configurations ADD logging_decoder_rate INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE configurations ADD logging_encoder_rate INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE configurations ADD logging_ingest_rate INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE configurations ADD logging_crash_dump INTEGER NOT NULL DEFAULT 0;
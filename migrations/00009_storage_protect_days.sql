@@ -0,0 +1 @@
+ALTER TABLE configurations ADD storage_protect_days INTEGER NOT NULL DEFAULT 2;
@@ -0,0 +1,5 @@
+ALTER TABLE media ADD duration REAL NOT NULL DEFAULT 0;
+
+ALTER TABLE media ADD error TEXT;
+
+ALTER TABLE media ADD checked INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,13 @@
+CREATE TABLE
+    storage_migrations (
+        id INTEGER PRIMARY KEY,
+        channel_id INTEGER NOT NULL,
+        target TEXT NOT NULL,
+        path TEXT NOT NULL,
+        size INTEGER NOT NULL DEFAULT 0,
+        done INTEGER NOT NULL DEFAULT 0,
+        error TEXT,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_storage_migrations_path ON storage_migrations (channel_id, target, path);
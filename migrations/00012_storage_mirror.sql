@@ -0,0 +1 @@
+ALTER TABLE configurations ADD storage_mirror TEXT NOT NULL DEFAULT '';
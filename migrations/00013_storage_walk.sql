@@ -0,0 +1,2 @@
+ALTER TABLE configurations ADD storage_exclude TEXT NOT NULL DEFAULT '';
+ALTER TABLE configurations ADD storage_depth INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,15 @@
+CREATE TABLE
+    storage_audit (
+        id INTEGER PRIMARY KEY,
+        channel_id INTEGER NOT NULL,
+        user_id INTEGER NOT NULL,
+        username TEXT NOT NULL,
+        action TEXT NOT NULL,
+        path TEXT NOT NULL,
+        target TEXT,
+        timestamp INTEGER NOT NULL,
+        error TEXT,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+CREATE INDEX IF NOT EXISTS idx_storage_audit_channel ON storage_audit (channel_id, timestamp);
@@ -0,0 +1,5 @@
+ALTER TABLE roles ADD upload_max_size INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE roles ADD upload_extensions TEXT NOT NULL DEFAULT '';
+
+ALTER TABLE roles ADD upload_folders TEXT NOT NULL DEFAULT '';
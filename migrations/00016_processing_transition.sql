@@ -0,0 +1,2 @@
+ALTER TABLE configurations ADD processing_transition TEXT NOT NULL DEFAULT 'cut';
+ALTER TABLE configurations ADD processing_transition_duration INTEGER NOT NULL DEFAULT 500;
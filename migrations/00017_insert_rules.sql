@@ -0,0 +1,13 @@
+CREATE TABLE
+    insert_rules (
+        id INTEGER PRIMARY KEY,
+        channel_id INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        interval INTEGER NOT NULL DEFAULT 60,
+        minute INTEGER NOT NULL DEFAULT 0,
+        path TEXT NOT NULL,
+        count INTEGER NOT NULL DEFAULT 1,
+        shuffle INTEGER NOT NULL DEFAULT 0,
+        active INTEGER NOT NULL DEFAULT 1,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
@@ -0,0 +1 @@
+ALTER TABLE configurations ADD output_scte35 INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,10 @@
+CREATE TABLE
+    filler_sources (
+        id INTEGER PRIMARY KEY,
+        channel_id INTEGER NOT NULL,
+        path TEXT NOT NULL,
+        priority INTEGER NOT NULL DEFAULT 0,
+        weight INTEGER NOT NULL DEFAULT 1,
+        no_repeat INTEGER NOT NULL DEFAULT 0,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
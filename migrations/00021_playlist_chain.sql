@@ -0,0 +1 @@
+ALTER TABLE configurations ADD playlist_chain INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1 @@
+ALTER TABLE configurations ADD playlist_epg INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+ALTER TABLE channels ADD standby INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD storage_slate TEXT NOT NULL DEFAULT '';
@@ -0,0 +1,2 @@
+ALTER TABLE configurations ADD storage_no_repeat_items INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD storage_no_repeat_time REAL NOT NULL DEFAULT 0;
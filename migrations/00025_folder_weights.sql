@@ -0,0 +1 @@
+ALTER TABLE configurations ADD storage_weights TEXT NOT NULL DEFAULT '';
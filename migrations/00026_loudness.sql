@@ -0,0 +1,15 @@
+ALTER TABLE media ADD loudness_i REAL;
+
+ALTER TABLE media ADD loudness_tp REAL;
+
+ALTER TABLE media ADD loudness_lra REAL;
+
+ALTER TABLE media ADD loudness_thresh REAL;
+
+ALTER TABLE configurations ADD processing_loudnorm INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE configurations ADD processing_loudnorm_i REAL NOT NULL DEFAULT -23;
+
+ALTER TABLE configurations ADD processing_loudnorm_tp REAL NOT NULL DEFAULT -1;
+
+ALTER TABLE configurations ADD processing_loudnorm_lra REAL NOT NULL DEFAULT 7;
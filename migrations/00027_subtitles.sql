@@ -0,0 +1,3 @@
+ALTER TABLE configurations ADD processing_subtitles INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE configurations ADD processing_subtitle_style TEXT NOT NULL DEFAULT '';
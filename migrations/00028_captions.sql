@@ -0,0 +1,3 @@
+ALTER TABLE configurations ADD processing_captions INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE configurations ADD processing_captions_dummy TEXT NOT NULL DEFAULT '';
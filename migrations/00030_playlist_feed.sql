@@ -0,0 +1,5 @@
+ALTER TABLE configurations ADD playlist_feed_url TEXT NOT NULL DEFAULT '';
+
+ALTER TABLE configurations ADD playlist_feed_auth TEXT NOT NULL DEFAULT '';
+
+ALTER TABLE configurations ADD playlist_feed_interval INTEGER NOT NULL DEFAULT 300;
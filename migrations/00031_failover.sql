@@ -0,0 +1 @@
+ALTER TABLE configurations ADD processing_failover_timeout REAL NOT NULL DEFAULT 5.0;
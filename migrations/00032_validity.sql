@@ -0,0 +1,3 @@
+ALTER TABLE media ADD valid_from TEXT;
+
+ALTER TABLE media ADD valid_to TEXT;
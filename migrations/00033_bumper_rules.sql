@@ -0,0 +1,10 @@
+CREATE TABLE
+    bumper_rules (
+        id INTEGER PRIMARY KEY,
+        channel_id INTEGER NOT NULL,
+        category TEXT NOT NULL,
+        intro TEXT NOT NULL DEFAULT '',
+        outro TEXT NOT NULL DEFAULT '',
+        active INTEGER NOT NULL DEFAULT 1,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
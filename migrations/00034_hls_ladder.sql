@@ -0,0 +1,5 @@
+ALTER TABLE configurations ADD output_hls_ladder TEXT NOT NULL DEFAULT '';
+
+ALTER TABLE configurations ADD output_hls_time INTEGER NOT NULL DEFAULT 6;
+
+ALTER TABLE configurations ADD output_hls_list_size INTEGER NOT NULL DEFAULT 10;
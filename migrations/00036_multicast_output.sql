@@ -0,0 +1,15 @@
+ALTER TABLE configurations ADD output_multicast_address TEXT NOT NULL DEFAULT '';
+
+ALTER TABLE configurations ADD output_multicast_rtp INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE configurations ADD output_multicast_ttl INTEGER NOT NULL DEFAULT 16;
+
+ALTER TABLE configurations ADD output_multicast_muxrate INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE configurations ADD output_multicast_service TEXT NOT NULL DEFAULT 'ffplayout';
+
+ALTER TABLE configurations ADD output_multicast_pmt_pid INTEGER NOT NULL DEFAULT 4096;
+
+ALTER TABLE configurations ADD output_multicast_video_pid INTEGER NOT NULL DEFAULT 256;
+
+ALTER TABLE configurations ADD output_multicast_audio_pid INTEGER NOT NULL DEFAULT 257;
@@ -0,0 +1,7 @@
+ALTER TABLE configurations ADD output_icecast_url TEXT NOT NULL DEFAULT '';
+
+ALTER TABLE configurations ADD output_icecast_format TEXT NOT NULL DEFAULT 'mp3';
+
+ALTER TABLE configurations ADD output_icecast_bitrate INTEGER NOT NULL DEFAULT 128;
+
+ALTER TABLE configurations ADD output_icecast_name TEXT NOT NULL DEFAULT '';
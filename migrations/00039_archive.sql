@@ -0,0 +1,7 @@
+ALTER TABLE configurations ADD archive_enable INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE configurations ADD archive_path TEXT NOT NULL DEFAULT 'archive';
+
+ALTER TABLE configurations ADD archive_retention INTEGER NOT NULL DEFAULT 30;
+
+ALTER TABLE configurations ADD archive_param TEXT NOT NULL DEFAULT '-c:v libx264 -crf 28 -preset veryfast -c:a aac -b:a 96k';
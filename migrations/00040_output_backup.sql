@@ -0,0 +1 @@
+ALTER TABLE configurations ADD output_backup TEXT NOT NULL DEFAULT '';
@@ -0,0 +1 @@
+ALTER TABLE configurations ADD processing_hwaccel TEXT NOT NULL DEFAULT 'none';
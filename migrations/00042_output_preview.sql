@@ -0,0 +1 @@
+ALTER TABLE configurations ADD output_preview INTEGER NOT NULL DEFAULT 0;
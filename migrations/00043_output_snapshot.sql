@@ -0,0 +1 @@
+ALTER TABLE configurations ADD output_snapshot INTEGER NOT NULL DEFAULT 0;
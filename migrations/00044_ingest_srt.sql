@@ -0,0 +1,4 @@
+ALTER TABLE configurations ADD ingest_srt_enable INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD ingest_srt_address TEXT NOT NULL DEFAULT '0.0.0.0:8890';
+ALTER TABLE configurations ADD ingest_srt_passphrase TEXT NOT NULL DEFAULT '';
+ALTER TABLE configurations ADD ingest_srt_streamid TEXT NOT NULL DEFAULT '';
@@ -0,0 +1,11 @@
+CREATE TABLE
+    ingest_keys (
+        id INTEGER PRIMARY KEY,
+        channel_id INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        stream_key TEXT NOT NULL,
+        active INTEGER NOT NULL DEFAULT 1,
+        last_used INTEGER,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE,
+        UNIQUE (channel_id, stream_key)
+    );
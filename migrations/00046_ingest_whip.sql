@@ -0,0 +1,3 @@
+ALTER TABLE configurations ADD ingest_whip_enable INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD ingest_whip_url TEXT NOT NULL DEFAULT '';
+ALTER TABLE configurations ADD ingest_whip_source TEXT NOT NULL DEFAULT '';
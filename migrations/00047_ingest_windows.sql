@@ -0,0 +1,2 @@
+ALTER TABLE configurations ADD ingest_windows TEXT NOT NULL DEFAULT '';
+ALTER TABLE configurations ADD ingest_countdown INTEGER NOT NULL DEFAULT 0;
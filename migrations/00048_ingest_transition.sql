@@ -0,0 +1,2 @@
+ALTER TABLE configurations ADD ingest_transition TEXT NOT NULL DEFAULT 'cut';
+ALTER TABLE configurations ADD ingest_transition_duration INTEGER NOT NULL DEFAULT 1000;
@@ -0,0 +1 @@
+ALTER TABLE configurations ADD ingest_delay INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1 @@
+ALTER TABLE configurations ADD ingest_monitor INTEGER NOT NULL DEFAULT 0;
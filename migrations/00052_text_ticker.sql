@@ -0,0 +1,6 @@
+ALTER TABLE configurations ADD text_ticker INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD text_ticker_url TEXT NOT NULL DEFAULT '';
+ALTER TABLE configurations ADD text_ticker_template TEXT NOT NULL DEFAULT '{title}';
+ALTER TABLE configurations ADD text_ticker_interval INTEGER NOT NULL DEFAULT 300;
+ALTER TABLE configurations ADD text_ticker_speed INTEGER NOT NULL DEFAULT 100;
+ALTER TABLE configurations ADD text_ticker_style TEXT NOT NULL DEFAULT 'y=h-line_h-20:fontsize=24:fontcolor=#ffffff:box=1:boxcolor=#000000@0.7:boxborderw=8';
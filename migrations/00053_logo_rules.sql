@@ -0,0 +1 @@
+ALTER TABLE configurations ADD processing_logo_rules TEXT NOT NULL DEFAULT '';
@@ -0,0 +1,5 @@
+ALTER TABLE configurations ADD text_now_next INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD text_now_next_duration INTEGER NOT NULL DEFAULT 10;
+ALTER TABLE configurations ADD text_now_next_now TEXT NOT NULL DEFAULT 'Now: {title}';
+ALTER TABLE configurations ADD text_now_next_next TEXT NOT NULL DEFAULT 'Next: {title}';
+ALTER TABLE configurations ADD text_now_next_style TEXT NOT NULL DEFAULT 'x=40:y=h-th-60:fontsize=28:fontcolor=#ffffff:box=1:boxcolor=#000000@0.7:boxborderw=10';
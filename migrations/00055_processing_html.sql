@@ -0,0 +1,3 @@
+ALTER TABLE configurations ADD processing_html INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD processing_html_url TEXT NOT NULL DEFAULT '';
+ALTER TABLE configurations ADD processing_html_browser TEXT NOT NULL DEFAULT 'chromium';
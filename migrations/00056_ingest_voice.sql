@@ -0,0 +1,5 @@
+ALTER TABLE configurations ADD ingest_voice INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD ingest_voice_param TEXT NOT NULL DEFAULT '-f flv -listen 1 -i rtmp://127.0.0.1:1937/voice';
+ALTER TABLE configurations ADD ingest_voice_duck REAL NOT NULL DEFAULT 12;
+ALTER TABLE configurations ADD ingest_voice_attack REAL NOT NULL DEFAULT 20;
+ALTER TABLE configurations ADD ingest_voice_release REAL NOT NULL DEFAULT 500;
@@ -0,0 +1,2 @@
+ALTER TABLE configurations ADD storage_emergency TEXT NOT NULL DEFAULT '';
+ALTER TABLE configurations ADD storage_emergency_loop INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1 @@
+ALTER TABLE configurations ADD processing_aspect_mode TEXT NOT NULL DEFAULT 'pad';
@@ -0,0 +1 @@
+ALTER TABLE configurations ADD processing_audio_layout TEXT NOT NULL DEFAULT '';
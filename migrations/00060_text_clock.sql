@@ -0,0 +1,3 @@
+ALTER TABLE configurations ADD text_clock INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD text_clock_format TEXT NOT NULL DEFAULT '%H:%M:%S';
+ALTER TABLE configurations ADD text_clock_style TEXT NOT NULL DEFAULT 'x=w-tw-40:y=40:fontsize=28:fontcolor=#ffffff:box=1:boxcolor=#000000@0.5:boxborderw=8';
@@ -0,0 +1,12 @@
+CREATE TABLE
+    filter_templates (
+        id INTEGER PRIMARY KEY,
+        channel_id INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        video_filter TEXT NOT NULL DEFAULT '',
+        audio_filter TEXT NOT NULL DEFAULT '',
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE,
+        UNIQUE (channel_id, name)
+    );
+
+ALTER TABLE configurations ADD processing_filter_template TEXT NOT NULL DEFAULT '';
@@ -0,0 +1,2 @@
+ALTER TABLE presets ADD schedule TEXT NOT NULL DEFAULT '';
+ALTER TABLE presets ADD duration REAL NOT NULL DEFAULT 0;
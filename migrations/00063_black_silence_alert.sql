@@ -0,0 +1,2 @@
+ALTER TABLE configurations ADD processing_black_alert REAL NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD processing_silence_alert REAL NOT NULL DEFAULT 0;
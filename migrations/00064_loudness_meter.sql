@@ -0,0 +1 @@
+ALTER TABLE configurations ADD processing_loudness_meter INTEGER NOT NULL DEFAULT 0;
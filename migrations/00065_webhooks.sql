@@ -0,0 +1,10 @@
+CREATE TABLE
+    webhooks (
+        id INTEGER PRIMARY KEY,
+        channel_id INTEGER NOT NULL,
+        url TEXT NOT NULL,
+        secret TEXT NOT NULL DEFAULT '',
+        events TEXT NOT NULL DEFAULT '',
+        active INTEGER NOT NULL DEFAULT 1,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
@@ -0,0 +1,11 @@
+CREATE TABLE
+    notifiers (
+        id INTEGER PRIMARY KEY,
+        channel_id INTEGER NOT NULL,
+        kind TEXT NOT NULL,
+        url TEXT NOT NULL DEFAULT '',
+        token TEXT NOT NULL DEFAULT '',
+        target TEXT NOT NULL DEFAULT '',
+        active INTEGER NOT NULL DEFAULT 1,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
@@ -0,0 +1,14 @@
+CREATE TABLE
+    alert_rules (
+        id INTEGER PRIMARY KEY,
+        channel_id INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        condition TEXT NOT NULL,
+        threshold REAL NOT NULL DEFAULT 0,
+        minutes INTEGER NOT NULL DEFAULT 60,
+        time TEXT NOT NULL DEFAULT '',
+        severity TEXT NOT NULL DEFAULT 'warning',
+        targets TEXT NOT NULL DEFAULT '',
+        active INTEGER NOT NULL DEFAULT 1,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
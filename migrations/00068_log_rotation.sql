@@ -0,0 +1,4 @@
+ALTER TABLE configurations ADD logging_max_size INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD logging_max_files INTEGER NOT NULL DEFAULT 14;
+ALTER TABLE configurations ADD logging_compress INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD logging_shared INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,7 @@
+ALTER TABLE configurations ADD archive_screenshots INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE configurations ADD archive_screenshot_path TEXT NOT NULL DEFAULT 'screenshots';
+
+ALTER TABLE configurations ADD archive_screenshot_interval INTEGER NOT NULL DEFAULT 10;
+
+ALTER TABLE configurations ADD archive_screenshot_retention INTEGER NOT NULL DEFAULT 90;
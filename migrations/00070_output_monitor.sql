@@ -0,0 +1,3 @@
+ALTER TABLE configurations ADD output_monitor_url TEXT NOT NULL DEFAULT '';
+
+ALTER TABLE configurations ADD output_monitor_interval INTEGER NOT NULL DEFAULT 300;
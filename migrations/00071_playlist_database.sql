@@ -0,0 +1,49 @@
+ALTER TABLE configurations ADD playlist_database INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE
+    playlists (
+        id INTEGER PRIMARY KEY,
+        channel_id INTEGER NOT NULL,
+        date TEXT NOT NULL,
+        channel TEXT NOT NULL,
+        revision INTEGER NOT NULL DEFAULT 1,
+        modified INTEGER NOT NULL,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_playlists_date ON playlists (channel_id, date);
+
+CREATE TABLE
+    playlist_items (
+        id INTEGER PRIMARY KEY,
+        playlist_id INTEGER NOT NULL,
+        position INTEGER NOT NULL,
+        source TEXT NOT NULL,
+        title TEXT,
+        seek REAL NOT NULL DEFAULT 0,
+        `out` REAL NOT NULL DEFAULT 0,
+        duration REAL NOT NULL DEFAULT 0,
+        data TEXT NOT NULL,
+        FOREIGN KEY (playlist_id) REFERENCES playlists (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_playlist_items_position ON playlist_items (playlist_id, position);
+
+CREATE INDEX IF NOT EXISTS idx_playlist_items_source ON playlist_items (source);
+
+CREATE TABLE
+    playlist_history (
+        id INTEGER PRIMARY KEY,
+        channel_id INTEGER NOT NULL,
+        date TEXT NOT NULL,
+        revision INTEGER NOT NULL,
+        position INTEGER NOT NULL,
+        action TEXT NOT NULL,
+        user_id INTEGER NOT NULL DEFAULT 0,
+        previous TEXT,
+        item TEXT,
+        timestamp INTEGER NOT NULL,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+CREATE INDEX IF NOT EXISTS idx_playlist_history_date ON playlist_history (channel_id, date, revision);
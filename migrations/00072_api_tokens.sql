@@ -0,0 +1,15 @@
+CREATE TABLE
+    api_tokens (
+        id INTEGER PRIMARY KEY,
+        user_id INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        token_hash TEXT NOT NULL,
+        prefix TEXT NOT NULL,
+        scope TEXT NOT NULL DEFAULT 'read_only',
+        channel_ids TEXT NOT NULL DEFAULT '',
+        created INTEGER NOT NULL,
+        expires INTEGER,
+        last_used INTEGER,
+        FOREIGN KEY (user_id) REFERENCES user (id) ON UPDATE CASCADE ON DELETE CASCADE,
+        UNIQUE (token_hash)
+    );
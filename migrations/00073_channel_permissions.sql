@@ -0,0 +1 @@
+ALTER TABLE user_channels ADD permission TEXT NOT NULL DEFAULT 'editor';
@@ -0,0 +1,10 @@
+CREATE TABLE
+    auth_audit (
+        id INTEGER PRIMARY KEY,
+        username TEXT NOT NULL,
+        ip TEXT NOT NULL DEFAULT '',
+        event TEXT NOT NULL,
+        timestamp INTEGER NOT NULL
+    );
+
+CREATE INDEX IF NOT EXISTS idx_auth_audit_timestamp ON auth_audit (timestamp);
@@ -0,0 +1 @@
+ALTER TABLE configurations ADD playlist_now_playing INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,625 @@
+-- Schema for MySQL/MariaDB, at the state of the SQLite migrations up to 00070.
+-- Later changes of the schema need a migration here, with the same number as the SQLite one.
+
+CREATE TABLE
+    global (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        secret VARCHAR(255) NOT NULL,
+        logs TEXT NOT NULL DEFAULT ('/var/log/ffplayout'),
+        playlists TEXT NOT NULL DEFAULT ('/var/lib/ffplayout/playlists'),
+        public TEXT NOT NULL DEFAULT ('/usr/share/ffplayout/public'),
+        storage TEXT NOT NULL DEFAULT ('/var/lib/ffplayout/tv-media'),
+        shared BOOLEAN NOT NULL DEFAULT 0,
+        smtp_server TEXT NOT NULL DEFAULT ('mail.example.org'),
+        smtp_user TEXT NOT NULL DEFAULT ('ffplayout@example.org'),
+        smtp_password TEXT NOT NULL DEFAULT (''),
+        smtp_starttls BOOLEAN NOT NULL DEFAULT 0,
+        smtp_port SMALLINT UNSIGNED NOT NULL DEFAULT 465,
+        UNIQUE (secret)
+    );
+
+CREATE TABLE
+    roles (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        name VARCHAR(64) NOT NULL,
+        upload_max_size BIGINT NOT NULL DEFAULT 0,
+        upload_extensions TEXT NOT NULL DEFAULT (''),
+        upload_folders TEXT NOT NULL DEFAULT (''),
+        UNIQUE (name)
+    );
+
+CREATE TABLE
+    channels (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        name TEXT NOT NULL,
+        preview_url TEXT NOT NULL,
+        extra_extensions TEXT NOT NULL DEFAULT ('jpg,jpeg,png'),
+        active BOOLEAN NOT NULL DEFAULT 0,
+        public TEXT NOT NULL DEFAULT ('/usr/share/ffplayout/public'),
+        playlists TEXT NOT NULL DEFAULT ('/var/lib/ffplayout/playlists'),
+        storage TEXT NOT NULL DEFAULT ('/var/lib/ffplayout/tv-media'),
+        last_date TEXT NULL,
+        time_shift DOUBLE NOT NULL DEFAULT 0,
+        timezone TEXT NULL,
+        advanced_id INT NULL,
+        priority INT NOT NULL DEFAULT 0,
+        standby BOOLEAN NOT NULL DEFAULT 0
+    );
+
+CREATE TABLE
+    presets (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        name TEXT NOT NULL,
+        text TEXT NOT NULL,
+        x TEXT NOT NULL,
+        y TEXT NOT NULL,
+        fontsize TEXT NOT NULL,
+        line_spacing TEXT NOT NULL,
+        fontcolor TEXT NOT NULL,
+        box TEXT NOT NULL,
+        boxcolor TEXT NOT NULL,
+        boxborderw TEXT NOT NULL,
+        alpha TEXT NOT NULL,
+        channel_id INT NOT NULL DEFAULT 1,
+        schedule TEXT NOT NULL DEFAULT (''),
+        duration DOUBLE NOT NULL DEFAULT 0,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+CREATE TABLE
+    user (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        mail TEXT NOT NULL,
+        username VARCHAR(255) NOT NULL,
+        password TEXT NOT NULL,
+        role_id INT NOT NULL DEFAULT 3,
+        FOREIGN KEY (role_id) REFERENCES roles (id) ON UPDATE CASCADE ON DELETE RESTRICT,
+        UNIQUE (username)
+    );
+
+CREATE TABLE
+    user_channels (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        channel_id INT NOT NULL,
+        user_id INT NOT NULL,
+        FOREIGN KEY (user_id) REFERENCES user (id) ON UPDATE CASCADE ON DELETE CASCADE,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+CREATE UNIQUE INDEX idx_user_channels_unique ON user_channels (channel_id, user_id);
+
+CREATE TABLE
+    configurations (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        channel_id INT NOT NULL DEFAULT 1,
+        general_stop_threshold DOUBLE NOT NULL DEFAULT 11.0,
+        mail_subject TEXT NOT NULL DEFAULT ('Playout Error'),
+        mail_recipient TEXT NOT NULL DEFAULT (''),
+        mail_level TEXT NOT NULL DEFAULT ('ERROR'),
+        mail_interval BIGINT NOT NULL DEFAULT 120,
+        logging_ffmpeg_level TEXT NOT NULL DEFAULT ('ERROR'),
+        logging_ingest_level TEXT NOT NULL DEFAULT ('ERROR'),
+        logging_detect_silence BOOLEAN NOT NULL DEFAULT 0,
+        logging_ignore TEXT NOT NULL DEFAULT ('P sub_mb_type 4 out of range at;error while decoding MB;negative number of zero coeffs at;out of range intra chroma pred mode;non-existing SPS 0 referenced in buffering period'),
+        processing_mode TEXT NOT NULL DEFAULT ('playlist'),
+        processing_audio_only BOOLEAN NOT NULL DEFAULT 0,
+        processing_copy_audio BOOLEAN NOT NULL DEFAULT 0,
+        processing_copy_video BOOLEAN NOT NULL DEFAULT 0,
+        processing_width BIGINT NOT NULL DEFAULT 1280,
+        processing_height BIGINT NOT NULL DEFAULT 720,
+        processing_aspect DOUBLE NOT NULL DEFAULT 1.778,
+        processing_fps DOUBLE NOT NULL DEFAULT 25.0,
+        processing_add_logo BOOLEAN NOT NULL DEFAULT 1,
+        processing_logo TEXT NOT NULL DEFAULT ('00-assets/logo.png'),
+        processing_logo_scale TEXT NOT NULL DEFAULT (''),
+        processing_logo_opacity DOUBLE NOT NULL DEFAULT 0.7,
+        processing_logo_position TEXT NOT NULL DEFAULT ('W-w-12:12'),
+        processing_audio_tracks INT NOT NULL DEFAULT 1,
+        processing_audio_track_index INT NOT NULL DEFAULT -1,
+        processing_audio_channels TINYINT UNSIGNED NOT NULL DEFAULT 2,
+        processing_volume DOUBLE NOT NULL DEFAULT 1.0,
+        processing_filter TEXT NOT NULL DEFAULT (''),
+        processing_vtt_enable BOOLEAN NOT NULL DEFAULT 0,
+        processing_vtt_dummy TEXT NULL DEFAULT ('00-assets/dummy.vtt'),
+        ingest_enable BOOLEAN NOT NULL DEFAULT 0,
+        ingest_param TEXT NOT NULL DEFAULT ('-f live_flv -listen 1 -i rtmp://127.0.0.1:1936/live/stream'),
+        ingest_filter TEXT NOT NULL DEFAULT (''),
+        playlist_day_start TEXT NOT NULL DEFAULT ('05:59:25'),
+        playlist_length TEXT NOT NULL DEFAULT ('24:00:00'),
+        playlist_infinit BOOLEAN NOT NULL DEFAULT 0,
+        storage_filler TEXT NOT NULL DEFAULT ('filler/filler.mp4'),
+        storage_extensions TEXT NOT NULL DEFAULT ('mp4;mkv;webm'),
+        storage_shuffle BOOLEAN NOT NULL DEFAULT 1,
+        text_add BOOLEAN NOT NULL DEFAULT 1,
+        text_from_filename BOOLEAN NOT NULL DEFAULT 0,
+        text_font TEXT NOT NULL DEFAULT ('00-assets/DejaVuSans.ttf'),
+        text_style TEXT NOT NULL DEFAULT ('x=(w-tw)/2:y=(h-line_h)*0.9:fontsize=24:fontcolor=#ffffff:box=1:boxcolor=#000000:boxborderw=4'),
+        text_regex TEXT NOT NULL DEFAULT ('^.+[/\\\\](.*)(.mp4|.mkv|.webm)$'),
+        task_enable BOOLEAN NOT NULL DEFAULT 0,
+        task_path TEXT NOT NULL DEFAULT (''),
+        output_mode TEXT NOT NULL DEFAULT ('hls'),
+        output_param TEXT NOT NULL DEFAULT ('-c:v libx264 -crf 23 -x264-params keyint=50:min-keyint=25:scenecut=-1 -maxrate 1300k -bufsize 2600k -preset faster -tune zerolatency -profile:v Main -level 3.1 -c:a aac -ar 44100 -b:a 128k -flags +cgop -muxpreload 0 -muxdelay 0 -f hls -hls_time 6 -hls_list_size 600 -hls_flags append_list+delete_segments+omit_endlist -hls_segment_filename live/stream-%d.ts live/stream.m3u8'),
+        processing_override_filter BOOLEAN NOT NULL DEFAULT 0,
+        storage_quarantine BOOLEAN NOT NULL DEFAULT 0,
+        logging_decoder_level TEXT NOT NULL DEFAULT (''),
+        logging_encoder_level TEXT NOT NULL DEFAULT (''),
+        logging_decoder_rate INT UNSIGNED NOT NULL DEFAULT 0,
+        logging_encoder_rate INT UNSIGNED NOT NULL DEFAULT 0,
+        logging_ingest_rate INT UNSIGNED NOT NULL DEFAULT 0,
+        logging_crash_dump BOOLEAN NOT NULL DEFAULT 0,
+        storage_protect_days BIGINT NOT NULL DEFAULT 2,
+        storage_mirror TEXT NOT NULL DEFAULT (''),
+        storage_exclude TEXT NOT NULL DEFAULT (''),
+        storage_depth BIGINT NOT NULL DEFAULT 0,
+        processing_transition TEXT NOT NULL DEFAULT ('cut'),
+        processing_transition_duration BIGINT NOT NULL DEFAULT 500,
+        output_scte35 BOOLEAN NOT NULL DEFAULT 0,
+        playlist_chain BOOLEAN NOT NULL DEFAULT 0,
+        playlist_epg BOOLEAN NOT NULL DEFAULT 0,
+        storage_slate TEXT NOT NULL DEFAULT (''),
+        storage_no_repeat_items BIGINT NOT NULL DEFAULT 0,
+        storage_no_repeat_time DOUBLE NOT NULL DEFAULT 0,
+        storage_weights TEXT NOT NULL DEFAULT (''),
+        processing_loudnorm BOOLEAN NOT NULL DEFAULT 0,
+        processing_loudnorm_i DOUBLE NOT NULL DEFAULT -23,
+        processing_loudnorm_tp DOUBLE NOT NULL DEFAULT -1,
+        processing_loudnorm_lra DOUBLE NOT NULL DEFAULT 7,
+        processing_subtitles BOOLEAN NOT NULL DEFAULT 0,
+        processing_subtitle_style TEXT NOT NULL DEFAULT (''),
+        processing_captions BOOLEAN NOT NULL DEFAULT 0,
+        processing_captions_dummy TEXT NOT NULL DEFAULT (''),
+        processing_audio_languages TEXT NOT NULL DEFAULT (''),
+        playlist_feed_url TEXT NOT NULL DEFAULT (''),
+        playlist_feed_auth TEXT NOT NULL DEFAULT (''),
+        playlist_feed_interval BIGINT NOT NULL DEFAULT 300,
+        processing_failover_timeout DOUBLE NOT NULL DEFAULT 5.0,
+        output_hls_ladder TEXT NOT NULL DEFAULT (''),
+        output_hls_time BIGINT NOT NULL DEFAULT 6,
+        output_hls_list_size BIGINT NOT NULL DEFAULT 10,
+        output_srt_address TEXT NOT NULL DEFAULT (''),
+        output_srt_mode TEXT NOT NULL DEFAULT ('caller'),
+        output_srt_latency BIGINT NOT NULL DEFAULT 200,
+        output_srt_passphrase TEXT NOT NULL DEFAULT (''),
+        output_srt_streamid TEXT NOT NULL DEFAULT (''),
+        output_multicast_address TEXT NOT NULL DEFAULT (''),
+        output_multicast_rtp BOOLEAN NOT NULL DEFAULT 0,
+        output_multicast_ttl BIGINT NOT NULL DEFAULT 16,
+        output_multicast_muxrate BIGINT NOT NULL DEFAULT 0,
+        output_multicast_service TEXT NOT NULL DEFAULT ('ffplayout'),
+        output_multicast_pmt_pid BIGINT NOT NULL DEFAULT 4096,
+        output_multicast_video_pid BIGINT NOT NULL DEFAULT 256,
+        output_multicast_audio_pid BIGINT NOT NULL DEFAULT 257,
+        output_icecast_url TEXT NOT NULL DEFAULT (''),
+        output_icecast_format TEXT NOT NULL DEFAULT ('mp3'),
+        output_icecast_bitrate BIGINT NOT NULL DEFAULT 128,
+        output_icecast_name TEXT NOT NULL DEFAULT (''),
+        archive_enable BOOLEAN NOT NULL DEFAULT 0,
+        archive_path TEXT NOT NULL DEFAULT ('archive'),
+        archive_retention BIGINT NOT NULL DEFAULT 30,
+        archive_param TEXT NOT NULL DEFAULT ('-c:v libx264 -crf 28 -preset veryfast -c:a aac -b:a 96k'),
+        output_backup TEXT NOT NULL DEFAULT (''),
+        processing_hwaccel TEXT NOT NULL DEFAULT ('none'),
+        output_preview BOOLEAN NOT NULL DEFAULT 0,
+        output_snapshot BOOLEAN NOT NULL DEFAULT 0,
+        ingest_srt_enable BOOLEAN NOT NULL DEFAULT 0,
+        ingest_srt_address TEXT NOT NULL DEFAULT ('0.0.0.0:8890'),
+        ingest_srt_passphrase TEXT NOT NULL DEFAULT (''),
+        ingest_srt_streamid TEXT NOT NULL DEFAULT (''),
+        ingest_whip_enable BOOLEAN NOT NULL DEFAULT 0,
+        ingest_whip_url TEXT NOT NULL DEFAULT (''),
+        ingest_whip_source TEXT NOT NULL DEFAULT (''),
+        ingest_windows TEXT NOT NULL DEFAULT (''),
+        ingest_countdown BIGINT NOT NULL DEFAULT 0,
+        ingest_transition TEXT NOT NULL DEFAULT ('cut'),
+        ingest_transition_duration BIGINT NOT NULL DEFAULT 1000,
+        ingest_record BOOLEAN NOT NULL DEFAULT 0,
+        ingest_record_path TEXT NOT NULL DEFAULT ('live'),
+        ingest_delay BIGINT NOT NULL DEFAULT 0,
+        ingest_monitor BOOLEAN NOT NULL DEFAULT 0,
+        text_ticker BOOLEAN NOT NULL DEFAULT 0,
+        text_ticker_url TEXT NOT NULL DEFAULT (''),
+        text_ticker_template TEXT NOT NULL DEFAULT ('{title}'),
+        text_ticker_interval BIGINT NOT NULL DEFAULT 300,
+        text_ticker_speed BIGINT NOT NULL DEFAULT 100,
+        text_ticker_style TEXT NOT NULL DEFAULT ('y=h-line_h-20:fontsize=24:fontcolor=#ffffff:box=1:boxcolor=#000000@0.7:boxborderw=8'),
+        processing_logo_rules TEXT NOT NULL DEFAULT (''),
+        text_now_next BOOLEAN NOT NULL DEFAULT 0,
+        text_now_next_duration BIGINT NOT NULL DEFAULT 10,
+        text_now_next_now TEXT NOT NULL DEFAULT ('Now: {title}'),
+        text_now_next_next TEXT NOT NULL DEFAULT ('Next: {title}'),
+        text_now_next_style TEXT NOT NULL DEFAULT ('x=40:y=h-th-60:fontsize=28:fontcolor=#ffffff:box=1:boxcolor=#000000@0.7:boxborderw=10'),
+        processing_html BOOLEAN NOT NULL DEFAULT 0,
+        processing_html_url TEXT NOT NULL DEFAULT (''),
+        processing_html_browser TEXT NOT NULL DEFAULT ('chromium'),
+        ingest_voice BOOLEAN NOT NULL DEFAULT 0,
+        ingest_voice_param TEXT NOT NULL DEFAULT ('-f flv -listen 1 -i rtmp://127.0.0.1:1937/voice'),
+        ingest_voice_duck DOUBLE NOT NULL DEFAULT 12,
+        ingest_voice_attack DOUBLE NOT NULL DEFAULT 20,
+        ingest_voice_release DOUBLE NOT NULL DEFAULT 500,
+        storage_emergency TEXT NOT NULL DEFAULT (''),
+        storage_emergency_loop BOOLEAN NOT NULL DEFAULT 1,
+        processing_aspect_mode TEXT NOT NULL DEFAULT ('pad'),
+        processing_audio_layout TEXT NOT NULL DEFAULT (''),
+        text_clock BOOLEAN NOT NULL DEFAULT 0,
+        text_clock_format TEXT NOT NULL DEFAULT ('%H:%M:%S'),
+        text_clock_style TEXT NOT NULL DEFAULT ('x=w-tw-40:y=40:fontsize=28:fontcolor=#ffffff:box=1:boxcolor=#000000@0.5:boxborderw=8'),
+        processing_filter_template TEXT NOT NULL DEFAULT (''),
+        processing_black_alert DOUBLE NOT NULL DEFAULT 0,
+        processing_silence_alert DOUBLE NOT NULL DEFAULT 0,
+        processing_loudness_meter BOOLEAN NOT NULL DEFAULT 0,
+        logging_max_size BIGINT NOT NULL DEFAULT 0,
+        logging_max_files BIGINT NOT NULL DEFAULT 14,
+        logging_compress BOOLEAN NOT NULL DEFAULT 0,
+        logging_shared BOOLEAN NOT NULL DEFAULT 0,
+        archive_screenshots BOOLEAN NOT NULL DEFAULT 0,
+        archive_screenshot_path TEXT NOT NULL DEFAULT ('screenshots'),
+        archive_screenshot_interval BIGINT NOT NULL DEFAULT 10,
+        archive_screenshot_retention BIGINT NOT NULL DEFAULT 90,
+        output_monitor_url TEXT NOT NULL DEFAULT (''),
+        output_monitor_interval BIGINT NOT NULL DEFAULT 300,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+CREATE TABLE
+    advanced_configurations (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        channel_id INT NOT NULL DEFAULT 1,
+        decoder_input_param TEXT NULL,
+        decoder_output_param TEXT NULL,
+        encoder_input_param TEXT NULL,
+        ingest_input_param TEXT NULL,
+        filter_deinterlace TEXT NULL,
+        filter_pad_video TEXT NULL,
+        filter_fps TEXT NULL,
+        filter_scale TEXT NULL,
+        filter_set_dar TEXT NULL,
+        filter_fade_in TEXT NULL,
+        filter_fade_out TEXT NULL,
+        filter_overlay_logo_scale TEXT NULL,
+        filter_overlay_logo_fade_in TEXT NULL,
+        filter_overlay_logo_fade_out TEXT NULL,
+        filter_overlay_logo TEXT NULL,
+        filter_tpad TEXT NULL,
+        filter_drawtext_from_file TEXT NULL,
+        filter_drawtext_from_zmq TEXT NULL,
+        filter_aevalsrc TEXT NULL,
+        filter_afade_in TEXT NULL,
+        filter_afade_out TEXT NULL,
+        filter_apad TEXT NULL,
+        filter_volume TEXT NULL,
+        filter_split TEXT NULL,
+        filter_logo TEXT NULL,
+        name TEXT NULL,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+CREATE TABLE
+    media (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        channel_id INT NOT NULL,
+        path VARCHAR(512) NOT NULL,
+        hash VARCHAR(128) NOT NULL,
+        size BIGINT NOT NULL DEFAULT 0,
+        modified BIGINT NOT NULL DEFAULT 0,
+        duration DOUBLE NOT NULL DEFAULT 0,
+        error TEXT NULL,
+        checked BIGINT NOT NULL DEFAULT 0,
+        loudness_i DOUBLE NULL,
+        loudness_tp DOUBLE NULL,
+        loudness_lra DOUBLE NULL,
+        loudness_thresh DOUBLE NULL,
+        valid_from TEXT NULL,
+        valid_to TEXT NULL,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+CREATE INDEX idx_media_hash ON media (channel_id, hash);
+
+CREATE UNIQUE INDEX idx_media_channel_path ON media (channel_id, path);
+
+CREATE TABLE
+    storage_migrations (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        channel_id INT NOT NULL,
+        target VARCHAR(190) NOT NULL,
+        path VARCHAR(512) NOT NULL,
+        size BIGINT NOT NULL DEFAULT 0,
+        done BOOLEAN NOT NULL DEFAULT 0,
+        error TEXT NULL,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+CREATE UNIQUE INDEX idx_storage_migrations_path ON storage_migrations (channel_id, target, path);
+
+CREATE TABLE
+    storage_audit (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        channel_id INT NOT NULL,
+        user_id INT NOT NULL,
+        username TEXT NOT NULL,
+        action TEXT NOT NULL,
+        path TEXT NOT NULL,
+        target TEXT NULL,
+        timestamp BIGINT NOT NULL,
+        error TEXT NULL,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+CREATE INDEX idx_storage_audit_channel ON storage_audit (channel_id, timestamp);
+
+CREATE TABLE
+    insert_rules (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        channel_id INT NOT NULL,
+        name TEXT NOT NULL,
+        `interval` BIGINT NOT NULL DEFAULT 60,
+        minute BIGINT NOT NULL DEFAULT 0,
+        path TEXT NOT NULL,
+        count BIGINT NOT NULL DEFAULT 1,
+        shuffle BOOLEAN NOT NULL DEFAULT 0,
+        active BOOLEAN NOT NULL DEFAULT 1,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+CREATE TABLE
+    live_events (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        channel_id INT NOT NULL,
+        name TEXT NOT NULL,
+        source TEXT NOT NULL,
+        start_time BIGINT NOT NULL,
+        end_time BIGINT NOT NULL,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+CREATE INDEX idx_live_events_channel ON live_events (channel_id, end_time);
+
+CREATE TABLE
+    filler_sources (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        channel_id INT NOT NULL,
+        path TEXT NOT NULL,
+        priority BIGINT NOT NULL DEFAULT 0,
+        weight BIGINT NOT NULL DEFAULT 1,
+        no_repeat BIGINT NOT NULL DEFAULT 0,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+CREATE TABLE
+    bumper_rules (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        channel_id INT NOT NULL,
+        category TEXT NOT NULL,
+        intro TEXT NOT NULL DEFAULT (''),
+        outro TEXT NOT NULL DEFAULT (''),
+        active BOOLEAN NOT NULL DEFAULT 1,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+CREATE TABLE
+    outputs (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        channel_id INT NOT NULL,
+        name TEXT NOT NULL,
+        param TEXT NOT NULL,
+        active BOOLEAN NOT NULL DEFAULT 1,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+CREATE TABLE
+    ingest_keys (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        channel_id INT NOT NULL,
+        name TEXT NOT NULL,
+        stream_key VARCHAR(255) NOT NULL,
+        active BOOLEAN NOT NULL DEFAULT 1,
+        last_used BIGINT NULL,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE,
+        UNIQUE (channel_id, stream_key)
+    );
+
+CREATE TABLE
+    filter_templates (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        channel_id INT NOT NULL,
+        name VARCHAR(255) NOT NULL,
+        video_filter TEXT NOT NULL DEFAULT (''),
+        audio_filter TEXT NOT NULL DEFAULT (''),
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE,
+        UNIQUE (channel_id, name)
+    );
+
+CREATE TABLE
+    webhooks (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        channel_id INT NOT NULL,
+        url TEXT NOT NULL,
+        secret TEXT NOT NULL DEFAULT (''),
+        events TEXT NOT NULL DEFAULT (''),
+        active BOOLEAN NOT NULL DEFAULT 1,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+CREATE TABLE
+    notifiers (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        channel_id INT NOT NULL,
+        kind TEXT NOT NULL,
+        url TEXT NOT NULL DEFAULT (''),
+        token TEXT NOT NULL DEFAULT (''),
+        target TEXT NOT NULL DEFAULT (''),
+        active BOOLEAN NOT NULL DEFAULT 1,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+CREATE TABLE
+    alert_rules (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        channel_id INT NOT NULL,
+        name TEXT NOT NULL,
+        `condition` TEXT NOT NULL,
+        threshold DOUBLE NOT NULL DEFAULT 0,
+        minutes BIGINT NOT NULL DEFAULT 60,
+        time TEXT NOT NULL DEFAULT (''),
+        severity TEXT NOT NULL DEFAULT ('warning'),
+        targets TEXT NOT NULL DEFAULT (''),
+        active BOOLEAN NOT NULL DEFAULT 1,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+ALTER TABLE channels ADD FOREIGN KEY (advanced_id) REFERENCES advanced_configurations (id) ON UPDATE CASCADE ON DELETE SET NULL;
+
+-------------------------------------------------------------------------------
+-- set defaults
+INSERT INTO
+    roles (name)
+VALUES
+    ('global_admin'),
+    ('channel_admin'),
+    ('user'),
+    ('guest');
+
+INSERT INTO
+    channels (name, preview_url, extra_extensions, active)
+VALUES
+    (
+        'Channel 1',
+        'http://127.0.0.1:8787/1/live/stream.m3u8',
+        'jpg,jpeg,png',
+        0
+    );
+
+INSERT INTO
+    presets (
+        name,
+        text,
+        x,
+        y,
+        fontsize,
+        line_spacing,
+        fontcolor,
+        box,
+        boxcolor,
+        boxborderw,
+        alpha,
+        channel_id
+    )
+VALUES
+    (
+        'Default',
+        'Welcome to ffplayout messenger!',
+        '(w-text_w)/2',
+        '(h-text_h)/2',
+        '24',
+        '4',
+        '#ffffff@0xff',
+        '0',
+        '#000000@0x80',
+        '4',
+        '1.0',
+        '1'
+    ),
+    (
+        'Empty Text',
+        '',
+        '0',
+        '0',
+        '24',
+        '4',
+        '#000000',
+        '0',
+        '#000000',
+        '0',
+        '0',
+        '1'
+    ),
+    (
+        'Bottom Text fade in',
+        'The upcoming event will be delayed by a few minutes.',
+        '(w-text_w)/2',
+        '(h-line_h)*0.9',
+        '24',
+        '4',
+        '#ffffff',
+        '1',
+        '#000000@0x80',
+        '4',
+        'ifnot(ld(1),st(1,t));if(lt(t,ld(1)+1),0,if(lt(t,ld(1)+2),(t-(ld(1)+1))/1,if(lt(t,ld(1)+8),1,if(lt(t,ld(1)+9),(1-(t-(ld(1)+8)))/1,0))))',
+        '1'
+    ),
+    (
+        'Scrolling Text',
+        'We have a very important announcement to make.',
+        'ifnot(ld(1),st(1,t));if(lt(t,ld(1)+1),w+4,w-w/12*mod(t-ld(1),12*(w+tw)/w))',
+        '(h-line_h)*0.9',
+        '24',
+        '4',
+        '#ffffff',
+        '1',
+        '#000000@0x80',
+        '4',
+        '1.0',
+        '1'
+    );
+
+INSERT INTO
+    configurations ()
+VALUES
+    ();
+
+INSERT INTO
+    advanced_configurations (name)
+VALUES
+    ('Default');
+
+INSERT INTO
+    advanced_configurations (
+        channel_id,
+        decoder_input_param,
+        decoder_output_param,
+        ingest_input_param,
+        filter_deinterlace,
+        filter_scale,
+        filter_overlay_logo_scale,
+        filter_overlay_logo,
+        name
+    )
+VALUES
+    (
+        1,
+        '-thread_queue_size 1024 -hwaccel_device 0 -hwaccel cuvid -hwaccel_output_format cuda',
+        '-c:v h264_nvenc -preset p2 -tune ll -b:v 50000k -minrate 50000k -maxrate 50000k -bufsize 25000k -c:a s302m -strict -2 -sample_fmt s16 -ar 48000 -ac 2',
+        '-thread_queue_size 1024 -hwaccel_device 0 -hwaccel cuvid -hwaccel_output_format cuda',
+        'yadif_cuda=0:-1:0',
+        'scale_cuda={}:{}:format=yuv420p',
+        'null',
+        'overlay_cuda={}:shortest=1',
+        'Nvidia'
+    );
+
+INSERT INTO
+    advanced_configurations (
+        channel_id,
+        decoder_input_param,
+        decoder_output_param,
+        ingest_input_param,
+        filter_deinterlace,
+        filter_fps,
+        filter_scale,
+        filter_overlay_logo_scale,
+        filter_overlay_logo,
+        name
+    )
+VALUES
+    (
+        1,
+        '-hwaccel qsv -init_hw_device qsv=hw -filter_hw_device hw -hwaccel_output_format qsv',
+        '-c:v mpeg2_qsv -g 1 -b:v 50000k -minrate 50000k -maxrate 50000k -bufsize 25000k -c:a s302m -strict -2 -sample_fmt s16 -ar 48000 -ac 2',
+        '-hwaccel qsv -init_hw_device qsv=hw -filter_hw_device hw -hwaccel_output_format qsv',
+        'deinterlace_qsv',
+        'vpp_qsv=framerate=25',
+        'scale_qsv={}:{}',
+        'scale_qsv={}',
+        'overlay_qsv={}:shortest=1,vpp_qsv=format=nv12',
+        'QSV'
+    );
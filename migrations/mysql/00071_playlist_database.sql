@@ -0,0 +1,49 @@
+ALTER TABLE configurations ADD playlist_database BOOLEAN NOT NULL DEFAULT 0;
+
+CREATE TABLE
+    playlists (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        channel_id INT NOT NULL,
+        date VARCHAR(10) NOT NULL,
+        channel TEXT NOT NULL,
+        revision INT NOT NULL DEFAULT 1,
+        modified BIGINT NOT NULL,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+CREATE UNIQUE INDEX idx_playlists_date ON playlists (channel_id, date);
+
+CREATE TABLE
+    playlist_items (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        playlist_id INT NOT NULL,
+        position INT NOT NULL,
+        source TEXT NOT NULL,
+        title TEXT NULL,
+        seek DOUBLE NOT NULL DEFAULT 0,
+        `out` DOUBLE NOT NULL DEFAULT 0,
+        duration DOUBLE NOT NULL DEFAULT 0,
+        data MEDIUMTEXT NOT NULL,
+        FOREIGN KEY (playlist_id) REFERENCES playlists (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+CREATE UNIQUE INDEX idx_playlist_items_position ON playlist_items (playlist_id, position);
+
+CREATE INDEX idx_playlist_items_source ON playlist_items (source(255));
+
+CREATE TABLE
+    playlist_history (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        channel_id INT NOT NULL,
+        date VARCHAR(10) NOT NULL,
+        revision INT NOT NULL,
+        position INT NOT NULL,
+        action VARCHAR(16) NOT NULL,
+        user_id INT NOT NULL DEFAULT 0,
+        previous MEDIUMTEXT NULL,
+        item MEDIUMTEXT NULL,
+        timestamp BIGINT NOT NULL,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+CREATE INDEX idx_playlist_history_date ON playlist_history (channel_id, date, revision);
@@ -0,0 +1,15 @@
+CREATE TABLE
+    api_tokens (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        user_id INT NOT NULL,
+        name VARCHAR(255) NOT NULL,
+        token_hash CHAR(64) NOT NULL,
+        prefix VARCHAR(16) NOT NULL,
+        scope VARCHAR(32) NOT NULL DEFAULT 'read_only',
+        channel_ids TEXT NOT NULL,
+        created BIGINT NOT NULL,
+        expires BIGINT NULL,
+        last_used BIGINT NULL,
+        FOREIGN KEY (user_id) REFERENCES user (id) ON UPDATE CASCADE ON DELETE CASCADE,
+        UNIQUE (token_hash)
+    );
@@ -0,0 +1 @@
+ALTER TABLE user_channels ADD permission VARCHAR(32) NOT NULL DEFAULT 'editor';
@@ -0,0 +1,13 @@
+CREATE TABLE
+    sessions (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        user_id INT NOT NULL,
+        token_hash CHAR(64) NOT NULL,
+        ip VARCHAR(64) NOT NULL DEFAULT '',
+        user_agent TEXT NOT NULL,
+        created BIGINT NOT NULL,
+        last_used BIGINT NOT NULL,
+        expires BIGINT NOT NULL,
+        FOREIGN KEY (user_id) REFERENCES user (id) ON UPDATE CASCADE ON DELETE CASCADE,
+        UNIQUE (token_hash)
+    );
@@ -0,0 +1,10 @@
+CREATE TABLE
+    auth_audit (
+        id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+        username VARCHAR(255) NOT NULL,
+        ip VARCHAR(64) NOT NULL DEFAULT '',
+        event VARCHAR(16) NOT NULL,
+        timestamp BIGINT NOT NULL
+    );
+
+CREATE INDEX idx_auth_audit_timestamp ON auth_audit (timestamp);
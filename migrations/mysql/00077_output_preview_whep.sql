@@ -0,0 +1,2 @@
+ALTER TABLE configurations ADD output_preview_whep_url TEXT NOT NULL DEFAULT ('');
+ALTER TABLE configurations ADD output_preview_publish TEXT NOT NULL DEFAULT ('');
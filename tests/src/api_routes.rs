@@ -1,13 +1,15 @@
 use actix_web::{get, web, App, Error, HttpResponse, Responder};
+use actix_web_httpauth::middleware::HttpAuthentication;
 
 use serde_json::json;
 use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite};
+use tokio::sync::Mutex;
 
-use ffplayout::api::routes::login;
+use ffplayout::api::routes::{control_emergency, get_epg, login, logout, refresh};
 use ffplayout::db::{handles, init_globales, models::User};
-use ffplayout::player::controller::ChannelManager;
+use ffplayout::player::controller::{ChannelController, ChannelManager};
 use ffplayout::utils::config::PlayoutConfig;
-// use ffplayout::validator;
+use ffplayout::validator;
 
 async fn prepare_config() -> (PlayoutConfig, ChannelManager, Pool<Sqlite>) {
     let pool = SqlitePoolOptions::new()
@@ -33,6 +35,7 @@ async fn prepare_config() -> (PlayoutConfig, ChannelManager, Pool<Sqlite>) {
         password: "admin".to_string(),
         role_id: Some(1),
         channel_ids: Some(vec![1]),
+        permissions: None,
         token: None,
     };
 
@@ -64,21 +67,68 @@ async fn test_get() {
 async fn test_login() {
     let (_, _, pool) = prepare_config().await;
 
-    init_globales(&pool).await.unwrap();
+    // the globals are shared by the tests of this binary, the first one sets them
+    init_globales(&pool).await.ok();
 
     let srv = actix_test::start(move || {
         let db_pool = web::Data::new(pool.clone());
         App::new()
             .app_data(db_pool)
-            .service(web::scope("/auth").service(login))
+            .service(
+                web::scope("/auth")
+                    .service(login)
+                    .service(refresh)
+                    .service(logout),
+            )
+            .service(
+                web::scope("/api")
+                    .wrap(HttpAuthentication::bearer(validator))
+                    .service(get_handler),
+            )
     });
 
     let payload = json!({"username": "admin", "password": "admin"});
 
-    let res = srv.post("/auth/login/").send_json(&payload).await.unwrap();
+    let mut res = srv.post("/auth/login/").send_json(&payload).await.unwrap();
+
+    assert!(res.status().is_success());
+
+    // the refresh token works until the session ends
+    let tokens: serde_json::Value = res.json().await.unwrap();
+    let session = json!({"refresh": tokens["refresh"]});
+
+    // only the access token is valid for the API
+    for (token, status) in [("access", 200), ("refresh", 401)] {
+        let res = srv
+            .get("/api/")
+            .bearer_auth(tokens[token].as_str().unwrap())
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(res.status().as_u16(), status);
+    }
+
+    let res = srv
+        .post("/auth/refresh/")
+        .send_json(&session)
+        .await
+        .unwrap();
+
+    assert!(res.status().is_success());
+
+    let res = srv.post("/auth/logout/").send_json(&session).await.unwrap();
 
     assert!(res.status().is_success());
 
+    let res = srv
+        .post("/auth/refresh/")
+        .send_json(&session)
+        .await
+        .unwrap();
+
+    assert_eq!(res.status().as_u16(), 401);
+
     let payload = json!({"username": "admin", "password": "1234"});
 
     let res = srv.post("/auth/login/").send_json(&payload).await.unwrap();
@@ -91,3 +141,111 @@ async fn test_login() {
 
     assert_eq!(res.status().as_u16(), 400);
 }
+
+#[actix_web::test]
+async fn test_emergency() {
+    let (_, manager, pool) = prepare_config().await;
+
+    // the globals are shared by the tests of this binary, the first one sets them
+    init_globales(&pool).await.ok();
+
+    // role user without a channel permission is only a viewer
+    let viewer = User {
+        id: 0,
+        mail: Some("viewer@mail.com".to_string()),
+        username: "viewer".to_string(),
+        password: "viewer".to_string(),
+        role_id: Some(3),
+        channel_ids: Some(vec![1]),
+        permissions: None,
+        token: None,
+    };
+
+    handles::insert_user(&pool, viewer).await.unwrap();
+
+    let mut controller = ChannelController::new();
+    controller.add(manager);
+    let controllers = web::Data::new(Mutex::new(controller));
+
+    let srv = actix_test::start(move || {
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(controllers.clone())
+            .service(web::scope("/auth").service(login))
+            .service(
+                web::scope("/api")
+                    .wrap(HttpAuthentication::bearer(validator))
+                    .service(control_emergency),
+            )
+    });
+
+    let mut tokens = vec![];
+
+    for user in ["admin", "viewer"] {
+        let payload = json!({"username": user, "password": user});
+        let mut res = srv.post("/auth/login/").send_json(&payload).await.unwrap();
+        let body: serde_json::Value = res.json().await.unwrap();
+
+        tokens.push(body["access"].as_str().unwrap().to_string());
+    }
+
+    let status = json!({"command": "status"});
+    let start = json!({"command": "start", "reason": "Technical difficulties"});
+
+    let res = srv
+        .post("/api/control/1/emergency/")
+        .send_json(&status)
+        .await
+        .unwrap();
+
+    assert_eq!(res.status().as_u16(), 401);
+
+    let res = srv
+        .post("/api/control/1/emergency/")
+        .bearer_auth(&tokens[1])
+        .send_json(&start)
+        .await
+        .unwrap();
+
+    assert_eq!(res.status().as_u16(), 403);
+
+    let mut res = srv
+        .post("/api/control/1/emergency/")
+        .bearer_auth(&tokens[0])
+        .send_json(&status)
+        .await
+        .unwrap();
+
+    assert!(res.status().is_success());
+    assert_eq!(res.json::<String>().await.unwrap(), "inactive");
+
+    // without slate there is nothing to switch to
+    let res = srv
+        .post("/api/control/1/emergency/")
+        .bearer_auth(&tokens[0])
+        .send_json(&start)
+        .await
+        .unwrap();
+
+    assert_eq!(res.status().as_u16(), 400);
+}
+
+#[actix_web::test]
+async fn test_epg() {
+    let (_, manager, _) = prepare_config().await;
+
+    let mut controller = ChannelController::new();
+    controller.add(manager);
+    let controllers = web::Data::new(Mutex::new(controller));
+
+    let srv = actix_test::start(move || App::new().app_data(controllers.clone()).service(get_epg));
+
+    // the EPG is not published by default
+    let res = srv.get("/epg/1.json").send().await.unwrap();
+
+    assert_eq!(res.status().as_u16(), 403);
+
+    let res = srv.get("/epg/2.xml").send().await.unwrap();
+
+    assert_eq!(res.status().as_u16(), 404);
+}
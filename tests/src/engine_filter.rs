@@ -2,9 +2,17 @@ use std::fs;
 
 use sqlx::sqlite::SqlitePoolOptions;
 
-use ffplayout::db::handles;
-use ffplayout::player::{controller::ChannelManager, utils::Media};
-use ffplayout::utils::config::{OutputMode::*, PlayoutConfig};
+use ffplayout::db::{handles, models::FilterTemplate};
+use ffplayout::player::{
+    controller::{ChannelManager, ProcessUnit::*},
+    filter::{crossfade, filter_chains},
+    utils::{
+        probe::{AudioStream, MediaFormat, MediaProbe, VideoStream},
+        Media,
+    },
+};
+use ffplayout::utils::config::{AspectMode, OutputMode::*, PlayoutConfig, Transition};
+use ffplayout::vec_strings;
 
 async fn get_config() -> (PlayoutConfig, ChannelManager) {
     let pool = SqlitePoolOptions::new()
@@ -31,6 +39,23 @@ async fn get_config() -> (PlayoutConfig, ChannelManager) {
     (config, manager)
 }
 
+fn probe(width: i64, height: i64, aspect: &str) -> MediaProbe {
+    MediaProbe {
+        format: MediaFormat {
+            duration: Some(30.0),
+            ..Default::default()
+        },
+        audio: vec![AudioStream::default()],
+        video: vec![VideoStream {
+            width: Some(width),
+            height: Some(height),
+            aspect_ratio: Some(aspect.to_string()),
+            frame_rate: "25/1".to_string(),
+            ..Default::default()
+        }],
+    }
+}
+
 #[tokio::test]
 async fn simple_filtering() {
     let (mut config, _) = get_config().await;
@@ -47,3 +72,460 @@ async fn simple_filtering() {
 
     // println!("{f:?}");
 }
+
+#[tokio::test]
+async fn crossfade_filtering() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+    config.processing.transition = Transition::Crossfade;
+    config.processing.transition_duration = 1000;
+
+    let probe = probe(1024, 576, "16:9");
+
+    let mut media = Media::new(0, "./assets/media_mix/with_audio.mp4", false).await;
+    media.duration = 30.0;
+    media.out = 30.0;
+    media.probe = Some(probe.clone());
+    media.add_filter(&config, &None).await;
+
+    let mut next = Media::new(1, "./assets/media_mix/av_sync.mp4", false).await;
+    next.duration = 30.0;
+    next.out = 30.0;
+    next.probe = Some(probe);
+
+    assert_eq!(crossfade(&config, &mut media, &next), 1.0);
+    assert_eq!(
+        media.cmd,
+        Some(vec_strings![
+            "-i",
+            "./assets/media_mix/with_audio.mp4",
+            "-t",
+            "1",
+            "-i",
+            "./assets/media_mix/av_sync.mp4"
+        ])
+    );
+
+    let mut filter = media.filter.unwrap();
+    let filter_cmd = filter.cmd();
+
+    assert!(filter_cmd[1].contains("[vxa][vxb]xfade=transition=fade:duration=1:offset=29[vxf]"));
+    assert!(filter_cmd[1].contains("[aout0][1:a:0]acrossfade=d=1[axf0]"));
+    assert_eq!(
+        filter.map(),
+        vec_strings!["-map", "[vxf]", "-map", "[axf0]"]
+    );
+}
+
+#[tokio::test]
+async fn aspect_filtering() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+    config.processing.aspect = 1.778;
+
+    let probe = probe(640, 480, "4:3");
+
+    let mut media: Media = serde_json::from_str(
+        r#"{"in": 0, "out": 30, "duration": 30, "source": "./assets/media_mix/with_audio.mp4"}"#,
+    )
+    .unwrap();
+    media.probe = Some(probe.clone());
+    media.add_filter(&config, &None).await;
+
+    // channel default
+    assert!(media.filter.unwrap().cmd()[1].starts_with(
+        "[0:v:0]pad='ih*16/9:ih:(ow-iw)/2:(oh-ih)/2',scale=1024:576,setdar=dar=1.778"
+    ));
+
+    config.processing.aspect_mode = AspectMode::Stretch;
+
+    let mut media: Media = serde_json::from_str(
+        r#"{"in": 0, "out": 30, "duration": 30, "source": "./assets/media_mix/with_audio.mp4", "aspect_mode": "crop"}"#,
+    )
+    .unwrap();
+    media.probe = Some(probe.clone());
+    media.add_filter(&config, &None).await;
+
+    assert_eq!(media.aspect_mode, Some(AspectMode::Crop));
+    assert!(media.filter.unwrap().cmd()[1].starts_with(
+        "[0:v:0]crop='min(iw,ih*16/9)':'min(ih,iw*9/16)',scale=1024:576,setdar=dar=1.778"
+    ));
+
+    let mut media = Media::new(0, "./assets/media_mix/with_audio.mp4", false).await;
+    media.duration = 30.0;
+    media.out = 30.0;
+    media.probe = Some(probe);
+    media.add_filter(&config, &None).await;
+
+    assert!(media.filter.unwrap().cmd()[1].starts_with("[0:v:0]scale=1024:576,setdar=dar=1.778"));
+}
+
+#[tokio::test]
+async fn audio_layout_filtering() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+    config.processing.audio_layout = "6 = center_boost\n1 = merge".to_string();
+
+    let stream = |channels| AudioStream {
+        channels,
+        ..Default::default()
+    };
+    let probe = MediaProbe {
+        audio: vec![stream(6)],
+        ..probe(1024, 576, "16:9")
+    };
+
+    let mut media = Media::new(0, "./assets/media_mix/with_audio.mp4", false).await;
+    media.duration = 30.0;
+    media.out = 30.0;
+    media.probe = Some(probe.clone());
+    media.add_filter(&config, &None).await;
+
+    assert!(media.filter.unwrap().cmd()[1].contains(
+        "[0:a:0]pan=stereo|FL<c0+1.414*c2+0.5*c4|FR<c1+1.414*c2+0.5*c5,anull,volume=0.05[aout0]"
+    ));
+
+    // two mono streams become one stereo track
+    let mut media = Media::new(0, "./assets/media_mix/with_audio.mp4", false).await;
+    media.duration = 30.0;
+    media.out = 30.0;
+    media.probe = Some(MediaProbe {
+        audio: vec![stream(1), stream(1)],
+        ..probe.clone()
+    });
+    media.add_filter(&config, &None).await;
+
+    assert!(media.filter.unwrap().cmd()[1]
+        .contains("[0:a:0][0:a:1]amerge=inputs=2,pan=stereo|c0=c0|c1=c1,anull"));
+
+    // the playlist item overwrites the channel rules
+    let mut media: Media = serde_json::from_str(
+        r#"{"in": 0, "out": 30, "duration": 30, "source": "./assets/media_mix/with_audio.mp4", "audio_layout": "left"}"#,
+    )
+    .unwrap();
+    media.probe = Some(MediaProbe {
+        audio: vec![stream(2)],
+        ..probe
+    });
+    media.add_filter(&config, &None).await;
+
+    assert!(media.filter.unwrap().cmd()[1].contains("[0:a:0]pan=stereo|c0=c0|c1=c0,anull"));
+}
+
+#[tokio::test]
+async fn live_transition_filtering() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+    config.ingest.transition = Transition::FadeBlack;
+    config.ingest.transition_duration = 1000;
+
+    let probe = probe(1024, 576, "16:9");
+
+    let mut tail = Media::new(0, "./assets/media_mix/with_audio.mp4", false).await;
+    tail.duration = 30.0;
+    tail.seek = 10.0;
+    tail.out = 10.5;
+    tail.live_out = true;
+    tail.probe = Some(probe.clone());
+    tail.add_filter(&config, &None).await;
+
+    let filter_cmd = tail.filter.unwrap().cmd();
+
+    assert!(filter_cmd[1].contains("fade=out:st=0:d=0.5"));
+    assert!(filter_cmd[1].contains("afade=out:st=0:d=0.5"));
+    assert!(!filter_cmd[1].contains("fade=in"));
+
+    let mut rest = Media::new(0, "./assets/media_mix/with_audio.mp4", false).await;
+    rest.duration = 30.0;
+    rest.seek = 10.5;
+    rest.out = 30.0;
+    rest.live_in = true;
+    rest.probe = Some(probe);
+    rest.add_filter(&config, &None).await;
+
+    let filter_cmd = rest.filter.unwrap().cmd();
+
+    assert!(filter_cmd[1].contains("fade=in:st=0:d=0.5"));
+    assert!(filter_cmd[1].contains("afade=in:st=0:d=0.5"));
+}
+
+#[tokio::test]
+async fn ticker_filtering() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+    config.text.ticker = true;
+    config.text.ticker_speed = 120;
+
+    let mut encoder = Media::new(0, "", false).await;
+    encoder.unit = Encoder;
+
+    let filter_cmd = filter_chains(&config, &mut encoder, &None).await.cmd();
+
+    assert!(filter_cmd[1].contains("drawtext@ticker=textfile="));
+    assert!(filter_cmd[1].contains(":reload=1:expansion=none:x='w-mod(t*120,w+tw)'"));
+
+    // the clips don't get the ticker, it crawls on the encoder
+    let mut media = Media::new(0, "./assets/media_mix/with_audio.mp4", true).await;
+    media.add_filter(&config, &None).await;
+
+    let filter_cmd = media.filter.unwrap().cmd();
+
+    assert!(!filter_cmd[1].contains("drawtext@ticker"));
+}
+
+#[tokio::test]
+async fn template_filtering() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+    config.processing.filter_template = "news".to_string();
+    config.templates = vec![
+        FilterTemplate {
+            name: "news".to_string(),
+            video_filter: "eq=contrast=1.1".to_string(),
+            audio_filter: "highpass=f=80".to_string(),
+            ..Default::default()
+        },
+        FilterTemplate {
+            name: "cinema".to_string(),
+            video_filter: "vignette".to_string(),
+            ..Default::default()
+        },
+    ];
+
+    let mut media = Media::new(0, "./assets/media_mix/with_audio.mp4", true).await;
+    media.add_filter(&config, &None).await;
+
+    let filter_cmd = media.filter.unwrap().cmd();
+
+    assert!(filter_cmd[1].contains(",eq=contrast=1.1[vout0]"));
+    assert!(filter_cmd[1].contains(",highpass=f=80[aout0]"));
+
+    // the playlist item uses another template
+    let mut media: Media = serde_json::from_str(
+        r#"{"in": 0, "out": 30, "duration": 30, "source": "./assets/media_mix/with_audio.mp4", "filter_template": "cinema"}"#,
+    )
+    .unwrap();
+    media.add_filter(&config, &None).await;
+
+    let filter_cmd = media.filter.unwrap().cmd();
+
+    assert!(filter_cmd[1].contains(",vignette[vout0]"));
+    assert!(!filter_cmd[1].contains("highpass"));
+}
+
+#[tokio::test]
+async fn clock_filtering() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+    config.channel.name = "Tom's TV".to_string();
+    config.text.clock = true;
+    config.text.clock_format = "{channel} %H:%M".to_string();
+    config.text.clock_style = "x=20:y=20".to_string();
+
+    let mut encoder = Media::new(0, "", false).await;
+    encoder.unit = Encoder;
+
+    let filter_cmd = filter_chains(&config, &mut encoder, &None).await.cmd();
+
+    // the timestamps count from the channel time, the format is escaped for the expansion
+    assert!(filter_cmd[1].contains(r"drawtext@clock=text=%{pts\\\:gmtime\\\:"));
+    assert!(filter_cmd[1].contains(r"\\\:Tom\\\\\\\'s TV %H\\\\\\\:%M}:x=20:y=20"));
+
+    // the clips don't get the clock, it runs on the encoder
+    let mut media = Media::new(0, "./assets/media_mix/with_audio.mp4", true).await;
+    media.add_filter(&config, &None).await;
+
+    assert!(!media.filter.unwrap().cmd()[1].contains("drawtext@clock"));
+}
+
+#[tokio::test]
+async fn detect_filtering() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+    config.processing.black_alert = 5.0;
+    config.processing.silence_alert = 10.0;
+    config.processing.loudness_meter = true;
+
+    let mut encoder = Media::new(0, "", false).await;
+    encoder.unit = Encoder;
+
+    let filter_cmd = filter_chains(&config, &mut encoder, &None).await.cmd();
+
+    // the metadata is written to stderr of the encoder
+    assert!(filter_cmd[1].contains(
+        r"[0:v:0]blackdetect=d=5:pix_th=0.10,metadata=mode=print:key=lavfi.black_start:file=pipe\\\:2"
+    ));
+    assert!(filter_cmd[1].contains("silencedetect=n=-60dB:d=1,ametadata=mode=print"));
+    assert!(filter_cmd[1]
+        .contains("asetnsamples=n=4800,ebur128=metadata=1,ametadata=mode=print:key=lavfi.r128.M"));
+
+    // clips are not checked
+    let mut media = Media::new(0, "./assets/media_mix/with_audio.mp4", true).await;
+    media.add_filter(&config, &None).await;
+
+    assert!(!media.filter.unwrap().cmd()[1].contains("blackdetect"));
+}
+
+#[tokio::test]
+async fn html_filtering() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+    config.processing.html_socket = Some("127.0.0.1:45999".to_string());
+
+    let mut encoder = Media::new(0, "", false).await;
+    encoder.unit = Encoder;
+
+    let filter_cmd = filter_chains(&config, &mut encoder, &None).await.cmd();
+
+    assert!(filter_cmd[1].starts_with(
+        r"[0:v:0]null[v];movie=tcp\\://127.0.0.1\\:45999?listen=1:f=png_pipe,format=rgba,scale=1024:576[l];[v][l]overlay=0:0:eof_action=pass"
+    ));
+}
+
+#[tokio::test]
+async fn voice_filtering() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+    config.processing.audio_tracks = 2;
+    config.processing.audio_channels = 2;
+    config.text.add_text = false;
+    config.ingest.voice_socket = Some("127.0.0.1:46999".to_string());
+    config.ingest.voice_duck = 12.0;
+    config.ingest.voice_attack = 20.0;
+    config.ingest.voice_release = 500.0;
+
+    let mut encoder = Media::new(0, "", false).await;
+    encoder.unit = Encoder;
+
+    let mut filters = filter_chains(&config, &mut encoder, &None).await;
+    let filter_cmd = filters.cmd();
+
+    assert_eq!(
+        filter_cmd[1],
+        r"[0:a:0]anull[pg0];amovie=tcp\\://127.0.0.1\\:46999?listen=1:f=s16le,aresample=48000,aformat=sample_fmts=fltp:channel_layouts=stereo,asplit=4[vo0d][vo0m][vo1d][vo1m];[pg0][vo0d]sidechaincompress=threshold=0.01:ratio=20:attack=20:release=500:mix=0.749[dk0];[dk0][vo0m]amix=inputs=2:duration=first:normalize=0[aout0];[0:a:1]anull[pg1];[pg1][vo1d]sidechaincompress=threshold=0.01:ratio=20:attack=20:release=500:mix=0.749[dk1];[dk1][vo1m]amix=inputs=2:duration=first:normalize=0[aout1]"
+    );
+    assert_eq!(
+        filters.map(),
+        vec!["-map", "[aout0]", "-map", "[aout1]", "-map", "0:v"]
+    );
+}
+
+#[tokio::test]
+async fn now_next_filtering() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+    config.text.now_next = true;
+    config.text.now_next_duration = 10;
+
+    let mut media = Media::new(0, "./assets/media_mix/with_audio.mp4", true).await;
+    media.title = Some("Opener".to_string());
+    media.next_title = Some("News, Weather".to_string());
+    media.seek = 0.0;
+    media.out = 30.0;
+    media.add_filter(&config, &None).await;
+
+    let filter_cmd = media.filter.unwrap().cmd();
+
+    assert!(filter_cmd[1].contains(
+        r"drawtext@now=text=Now\\\: Opener:expansion=none:enable='between(t,0.000,10.000)'"
+    ));
+    assert!(filter_cmd[1].contains(
+        r"drawtext@next=text=Next\\\: News\\\, Weather:expansion=none:enable='between(t,20.000,30.000)'"
+    ));
+
+    // short clips share the time, advertisements get no overlay
+    let mut media = Media::new(0, "./assets/media_mix/with_audio.mp4", true).await;
+    media.seek = 0.0;
+    media.out = 8.0;
+    media.add_filter(&config, &None).await;
+
+    let filter_cmd = media.filter.unwrap().cmd();
+
+    assert!(filter_cmd[1].contains(
+        r"drawtext@now=text=Now\\\: with_audio:expansion=none:enable='between(t,0.000,4.000)'"
+    ));
+    assert!(!filter_cmd[1].contains("drawtext@next"));
+
+    let mut media = Media::new(0, "./assets/media_mix/with_audio.mp4", true).await;
+    media.category = "advertisement".to_string();
+    media.add_filter(&config, &None).await;
+
+    assert!(!media.filter.unwrap().cmd()[1].contains("drawtext@now"));
+}
+
+#[tokio::test]
+async fn subtitle_filtering() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+    config.processing.subtitles = true;
+    config.processing.subtitle_style = "FontSize=22,Outline=1".to_string();
+
+    let mut media = Media::new(0, "./assets/media_mix/with_audio.mp4", true).await;
+    media.seek = 5.0;
+    media.subtitle_file = Some("./assets/media_mix/with_audio: en.srt".to_string());
+    media.add_filter(&config, &None).await;
+
+    let filter_cmd = media.filter.unwrap().cmd();
+
+    assert!(filter_cmd[1].contains(
+        r"setpts=PTS+5/TB,subtitles=filename=./assets/media_mix/with_audio\\\: en.srt:force_style=FontSize=22\\\,Outline=1,setpts=PTS-STARTPTS"
+    ));
+}
+
+#[tokio::test]
+async fn audio_track_mapping() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+    config.processing.audio_tracks = 3;
+    config.processing.audio_languages = vec_strings!["eng", "deu", "eng"];
+
+    let language = |l: &str| AudioStream {
+        language: Some(l.to_string()),
+        ..Default::default()
+    };
+    let probe = MediaProbe {
+        audio: vec![language("deu"), language("eng"), language("eng")],
+        ..probe(1024, 576, "16:9")
+    };
+
+    let mut media = Media::new(0, "./assets/media_mix/with_audio.mp4", false).await;
+    media.duration = 30.0;
+    media.out = 30.0;
+    media.probe = Some(probe.clone());
+    media.add_filter(&config, &None).await;
+
+    let filter_cmd = media.filter.unwrap().cmd();
+
+    // tracks by language, the second english stream is the audio description
+    assert!(filter_cmd[1].contains("[0:a:1]anull"));
+    assert!(filter_cmd[1].contains("[0:a:0]anull"));
+    assert!(filter_cmd[1].contains("[0:a:2]anull"));
+
+    let mut media = Media::new(0, "./assets/media_mix/with_audio.mp4", false).await;
+    media.duration = 30.0;
+    media.out = 30.0;
+    media.probe = Some(probe);
+    media.audio_map = vec![2, 0, -1];
+    media.add_filter(&config, &None).await;
+
+    let filter_cmd = media.filter.unwrap().cmd();
+
+    assert!(filter_cmd[1].contains("[0:a:2]anull"));
+    assert!(filter_cmd[1].contains("[0:a:0]anull"));
+    assert!(filter_cmd[1]
+        .contains("aevalsrc=0:channel_layout=stereo:duration=30:sample_rate=48000,anull"));
+    assert!(!filter_cmd[1].contains("[0:a:1]"));
+}
@@ -140,6 +140,7 @@ async fn test_generate_playlist_from_template() {
                 paths: vec![PathBuf::from("assets/")],
             },
         ],
+        ..Default::default()
     });
     config.processing.mode = Playlist;
     config.storage.filler = "assets/".into();
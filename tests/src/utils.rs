@@ -8,6 +8,7 @@ use ffplayout::player::{controller::ChannelManager, utils::*};
 use ffplayout::utils::{
     config::{PlayoutConfig, ProcessMode::Playlist},
     time_machine::{set_mock_time, time_now},
+    TextFilter,
 };
 
 async fn prepare_config() -> (PlayoutConfig, ChannelManager) {
@@ -73,6 +74,42 @@ fn get_date_tomorrow() {
     assert_eq!("2022-05-21".to_string(), date);
 }
 
+#[test]
+fn dst_day_length() {
+    let berlin = chrono_tz::Europe::Berlin;
+    let tz = Some(berlin);
+    let date = |d| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+
+    assert_eq!(day_length(&tz, date("2025-05-20"), 0.0), 86400.0);
+    assert_eq!(day_length(&tz, date("2025-03-30"), 0.0), 82800.0);
+    assert_eq!(day_length(&tz, date("2025-03-29"), 21600.0), 82800.0);
+    assert_eq!(day_length(&tz, date("2025-03-30"), 21600.0), 86400.0);
+    assert_eq!(day_length(&tz, date("2025-10-26"), 0.0), 90000.0);
+    assert_eq!(day_length(&tz, date("2025-10-25"), 21600.0), 90000.0);
+
+    // start in DST gap moves forward
+    assert_eq!(
+        day_start_time(&berlin, date("2025-03-30"), 9000.0).to_rfc3339(),
+        "2025-03-30T03:30:00+02:00"
+    );
+}
+
+#[test]
+#[serial]
+#[ignore]
+fn playlist_clock_dst() {
+    let tz = Some(chrono_tz::Europe::Berlin);
+
+    // second 02:30, after clocks went back
+    set_mock_time(&Some("2025-10-26T02:30:00+01:00".to_string())).unwrap();
+
+    let (date, time) = playlist_clock(&tz, 21600.0);
+
+    assert_eq!(date.to_string(), "2025-10-25");
+    assert!((time - 99000.0).abs() < 1.0);
+    assert_eq!(get_date(true, 21600.0, false, &tz), "2025-10-25");
+}
+
 #[actix_web::test]
 #[serial]
 #[ignore]
@@ -89,3 +126,108 @@ async fn test_delta() {
 
     assert!(delta < 2.0);
 }
+
+#[actix_web::test]
+async fn trimmed_seek_and_length() {
+    let (config, _) = prepare_config().await;
+    let mut node = Media {
+        source: "assets/media_mix/with_audio.mp4".to_string(),
+        seek: 2.0,
+        out: 8.0,
+        duration: 10.0,
+        trim_in: Some(5.0),
+        trim_out: Some(15.0),
+        ..Default::default()
+    };
+
+    assert_eq!(node.trimmed_length(30.0), 10.0);
+
+    let cmd = seek_and_length(&config, &mut node);
+
+    assert_eq!(
+        cmd,
+        vec![
+            "-ss",
+            "7",
+            "-i",
+            "assets/media_mix/with_audio.mp4",
+            "-t",
+            "6"
+        ]
+    );
+}
+
+#[actix_web::test]
+async fn backup_source_timeout() {
+    let (mut config, _) = prepare_config().await;
+    config.processing.failover_timeout = 2.5;
+
+    let mut node = Media {
+        source: "srt://127.0.0.1:9000".to_string(),
+        backup: "https://example.org/live.m3u8".to_string(),
+        out: 60.0,
+        duration: 60.0,
+        ..Default::default()
+    };
+
+    let cmd = seek_and_length(&config, &mut node);
+
+    assert_eq!(
+        cmd,
+        vec![
+            "-rw_timeout",
+            "2500000",
+            "-i",
+            "srt://127.0.0.1:9000",
+            "-t",
+            "60"
+        ]
+    );
+
+    node.swap_source();
+
+    assert!(node.on_backup);
+    assert_eq!(node.source, "https://example.org/live.m3u8");
+    assert_eq!(node.backup, "srt://127.0.0.1:9000");
+}
+
+#[test]
+fn unexpected_stream_name() {
+    let line = "[rtmp @ 0x5600] Unexpected stream a1b2c3d4e5, expecting stream";
+
+    assert_eq!(unexpected_stream(line), Some("a1b2c3d4e5".to_string()));
+    assert!(!valid_stream(line));
+    assert_eq!(
+        unexpected_stream("[rtmp @ 0x5600] App field don't match up: foo <-> live"),
+        None
+    );
+}
+
+#[test]
+fn text_placeholders() {
+    let media = Media {
+        source: "/tv-media/shows/late_night_01.mp4".to_string(),
+        next_title: Some("News".to_string()),
+        ..Default::default()
+    };
+
+    let mut message = TextFilter {
+        text: Some("{channel} {time}: {title}, next {next_title}".to_string()),
+        ..Default::default()
+    };
+    message.resolve(Some(&media), "Channel 1", "20:15");
+
+    assert_eq!(
+        message.text.as_deref(),
+        Some("Channel 1 20:15: late_night_01, next News")
+    );
+
+    // without playing clip the titles are empty
+    let mut message = TextFilter {
+        text: Some("{title}{next_title}".to_string()),
+        ..Default::default()
+    };
+    message.resolve(None, "Channel 1", "20:15");
+
+    assert_eq!(message.text.as_deref(), Some(""));
+}